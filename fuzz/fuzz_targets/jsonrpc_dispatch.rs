@@ -0,0 +1,76 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+use std::sync::OnceLock;
+use template_mcp_server::raw_messages::RawMessageHandler;
+use template_mcp_server::{TemplateBackend, TemplateMcpServer};
+
+// Small enough that generated inputs reach the oversized message path
+const MAX_MESSAGE_SIZE: usize = 1024;
+
+const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"fuzz","version":"0"}}}"#;
+
+struct Harness {
+    runtime: tokio::runtime::Runtime,
+    handler: RawMessageHandler,
+}
+
+fn harness() -> &'static Harness {
+    static HARNESS: OnceLock<Harness> = OnceLock::new();
+    HARNESS.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime starts");
+        // Not `TemplateBackend::default()`, which reads the environment
+        let backend = TemplateBackend::new(TemplateMcpServer::default());
+        let handler = runtime
+            .block_on(RawMessageHandler::new(backend))
+            .expect("handler starts")
+            .with_max_message_size(MAX_MESSAGE_SIZE);
+        // Initialized up front, so inputs reach the methods past the handshake
+        runtime.block_on(handler.handle(INITIALIZE.as_bytes()));
+        Harness { runtime, handler }
+    })
+}
+
+fn assert_response(response: &Value) {
+    assert_eq!(
+        response["jsonrpc"], "2.0",
+        "not a JSON-RPC 2.0 response: {response}"
+    );
+    assert!(
+        response.get("result").is_some() != response.get("error").is_some(),
+        "response needs exactly one of result and error: {response}"
+    );
+    if let Some(error) = response.get("error") {
+        assert!(error["code"].is_i64(), "error without a code: {response}");
+        assert!(
+            error["message"].is_string(),
+            "error without a message: {response}"
+        );
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let harness = harness();
+    let response = harness.runtime.block_on(harness.handler.handle(data));
+
+    let is_json = serde_json::from_slice::<Value>(data).is_ok();
+    let Some(response) = response else {
+        assert!(is_json, "invalid input got no response");
+        return;
+    };
+    let response: Value = serde_json::from_str(&response).expect("responses are JSON");
+    match &response {
+        Value::Array(responses) => responses.iter().for_each(assert_response),
+        response => assert_response(response),
+    }
+    if !is_json {
+        assert!(
+            response.get("error").is_some(),
+            "invalid JSON didn't get an error"
+        );
+    }
+});
@@ -0,0 +1,88 @@
+#!/usr/bin/env node
+
+// Compare the last `cargo bench` run with the committed baseline
+// Usage: node scripts/check-bench.js
+//
+// Fails if a benchmark's mean is more than BENCH_TOLERANCE (0.5 by default,
+// i.e. 50%) slower than its baseline. Whole calls vary by up to about 40%
+// from run to run on a shared or single-core machine, so the default only
+// catches a large regression; use a lower tolerance on a quiet machine.
+// UPDATE_BENCH_BASELINE=1 writes the last run as the new baseline instead.
+
+const fs = require("fs");
+const path = require("path");
+
+const root = path.join(__dirname, "..");
+const baselinePath = path.join(root, "template-mcp-server", "benches", "baseline.json");
+const targetDir = process.env.CARGO_TARGET_DIR || path.join(root, "target");
+const criterionDir = path.join(targetDir, "criterion");
+const tolerance = Number(process.env.BENCH_TOLERANCE || "0.5");
+
+// Mean time of each benchmark of the last run, in nanoseconds, by id
+function lastRun(dir, results = {}) {
+  if (!fs.existsSync(dir)) {
+    return results;
+  }
+  for (const entry of fs.readdirSync(dir, { withFileTypes: true })) {
+    if (!entry.isDirectory()) {
+      continue;
+    }
+    const full = path.join(dir, entry.name);
+    const benchmark = path.join(full, "new", "benchmark.json");
+    if (entry.name !== "report" && fs.existsSync(benchmark)) {
+      const { full_id: id } = JSON.parse(fs.readFileSync(benchmark, "utf8"));
+      const estimates = JSON.parse(
+        fs.readFileSync(path.join(full, "new", "estimates.json"), "utf8"),
+      );
+      results[id] = Math.round(estimates.mean.point_estimate);
+    } else {
+      lastRun(full, results);
+    }
+  }
+  return results;
+}
+
+const format = (ns) => (ns >= 1000 ? `${(ns / 1000).toFixed(2)} µs` : `${ns} ns`);
+
+const current = lastRun(criterionDir);
+if (Object.keys(current).length === 0) {
+  console.error(`No benchmark results in ${criterionDir}; run \`cargo bench\` first`);
+  process.exit(1);
+}
+
+if (process.env.UPDATE_BENCH_BASELINE === "1") {
+  const sorted = Object.fromEntries(Object.entries(current).sort(([a], [b]) => a.localeCompare(b)));
+  fs.writeFileSync(baselinePath, JSON.stringify({ unit: "ns", means: sorted }, null, 2) + "\n");
+  console.log(`✅ Wrote ${Object.keys(sorted).length} baseline means to ${baselinePath}`);
+  process.exit(0);
+}
+
+const baseline = JSON.parse(fs.readFileSync(baselinePath, "utf8")).means;
+let regressions = 0;
+for (const [id, base] of Object.entries(baseline)) {
+  if (current[id] === undefined) {
+    console.log(`? ${id}: not in the last run`);
+    continue;
+  }
+  const change = (current[id] - base) / base;
+  const regressed = change > tolerance;
+  regressions += regressed ? 1 : 0;
+  const sign = change >= 0 ? "+" : "";
+  console.log(
+    `${regressed ? "✗" : "✓"} ${id}: ${format(current[id])} ` +
+      `(baseline ${format(base)}, ${sign}${(change * 100).toFixed(1)}%)`,
+  );
+}
+
+// What the framework adds around the tool itself
+if (current["stage/invoke"] !== undefined && current["stage/tool"] !== undefined) {
+  console.log(`\nDispatch overhead: ${format(current["stage/invoke"] - current["stage/tool"])}`);
+}
+
+if (regressions > 0) {
+  console.error(
+    `\n❌ ${regressions} benchmark(s) over ${tolerance * 100}% slower than the baseline`,
+  );
+  process.exit(1);
+}
+console.log("\n✅ No benchmark regressed");
@@ -9,7 +9,9 @@ const binaryName =
   process.platform === "win32"
     ? "template-mcp-server.exe"
     : "template-mcp-server";
-const binaryPath = path.join(__dirname, "..", "dist", binaryName);
+// MCP_SERVER_BINARY points at a binary built elsewhere, as in CI
+const binaryPath =
+  process.env.MCP_SERVER_BINARY || path.join(__dirname, "..", "dist", binaryName);
 
 // Test 1: Initialize request
 console.log("\n1. Testing initialize request...");
@@ -29,6 +31,8 @@ init.stdin.write(
     },
   }) + "\n",
 );
+// The server exits at the end of its input, which is when this test ends
+init.stdin.end();
 
 let initResponse = "";
 init.stdout.on("data", (data) => {
@@ -145,9 +149,3487 @@ function testToolCall() {
     call.kill();
     if (callResponse.includes("Test: Hello, MCP!")) {
       console.log("✓ Tool call successful");
+      testRequestIds();
+    } else {
+      console.error("Tool call test failed");
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testRequestIds() {
+  console.log("\n4. Testing request ids and notifications...");
+  const ids = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  ids.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // A string id, a numeric id that looks like it, and a notification
+  setTimeout(() => {
+    ids.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", id: "req-2", method: "ping" }) + "\n",
+    );
+    ids.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", id: 2, method: "ping" }) + "\n",
+    );
+    ids.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        method: "tools/call",
+        params: { name: "get_status", arguments: {} },
+      }) + "\n",
+    );
+  }, 100);
+
+  let idsResponse = "";
+  ids.stdout.on("data", (data) => {
+    idsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    ids.kill();
+    const responses = idsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .filter((response) => response.id !== 1);
+    const stringId = responses.find((response) => response.id === "req-2");
+    const numberId = responses.find((response) => response.id === 2);
+    if (stringId && numberId && responses.length === 2) {
+      console.log("✓ Request ids preserved and notification not answered");
+      testDynamicJson();
+    } else {
+      console.error("Request id test failed:", idsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testDynamicJson() {
+  console.log("\n5. Testing dynamic JSON as structured content...");
+  const dynamic = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  dynamic.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // Store an entry, then count its tags
+  setTimeout(() => {
+    dynamic.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "tools/call",
+        params: {
+          name: "create_data",
+          arguments: { name: "a", value: 1, tags: ["x", "y"] },
+        },
+      }) + "\n",
+    );
+    dynamic.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 3,
+        method: "tools/call",
+        params: { name: "tag_counts", arguments: {} },
+      }) + "\n",
+    );
+  }, 100);
+
+  let dynamicResponse = "";
+  dynamic.stdout.on("data", (data) => {
+    dynamicResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    dynamic.kill();
+    const counts = dynamicResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 3);
+    const structured = counts && counts.result.structuredContent;
+    if (
+      structured &&
+      structured.entries === 1 &&
+      structured.tags.x === 1 &&
+      structured.tags.y === 1
+    ) {
+      console.log("✓ Dynamic JSON returned as structured content");
+      testUnitConversion();
+    } else {
+      console.error("Dynamic JSON test failed:", dynamicResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testUnitConversion() {
+  console.log("\n6. Testing unit conversion...");
+  const units = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  units.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // 5 ft in one second is 1.524 m/s once converted to metres
+  setTimeout(() => {
+    units.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "tools/call",
+        params: {
+          name: "average_speed",
+          arguments: { distance: { value: 5, unit: "ft" }, duration: 1 },
+        },
+      }) + "\n",
+    );
+  }, 100);
+
+  let unitsResponse = "";
+  units.stdout.on("data", (data) => {
+    unitsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    units.kill();
+    const speed = unitsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 2);
+    const text = speed && speed.result && speed.result.content[0].text;
+    if (Math.abs(Number(text) - 1.524) < 1e-9) {
+      console.log("✓ Feet converted to metres before the tool ran");
+      testPromptEmbedsResource();
+    } else {
+      console.error("Unit conversion test failed:", unitsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testPromptEmbedsResource() {
+  console.log("\n7. Testing prompt with embedded resource...");
+  const prompt = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  prompt.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // Get the prompt, then read the resource it embeds
+  setTimeout(() => {
+    prompt.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "prompts/get",
+        params: { name: "diagnose_server" },
+      }) + "\n",
+    );
+    prompt.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 3,
+        method: "resources/read",
+        params: { uri: "template://server-status" },
+      }) + "\n",
+    );
+  }, 100);
+
+  let promptResponse = "";
+  prompt.stdout.on("data", (data) => {
+    promptResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    prompt.kill();
+    const responses = promptResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const got = responses.find((response) => response.id === 2);
+    const read = responses.find((response) => response.id === 3);
+    // Uptime may tick between the two requests
+    const status = (text) => ({ ...JSON.parse(text), uptime_seconds: 0 });
+    const embedded = got && status(got.result.messages[1].content.text);
+    const direct = read && status(read.result.contents[0].text);
+    if (embedded && JSON.stringify(embedded) === JSON.stringify(direct)) {
+      console.log("✓ Prompt embeds the current server status");
+      testConditionalRead();
+    } else {
+      console.error("Prompt test failed:", promptResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testConditionalRead() {
+  console.log("\n8. Testing conditional resource read...");
+  const conditional = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  conditional.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  const read = (id, ifNoneMatch) =>
+    conditional.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id,
+        method: "resources/read_conditional",
+        params: { uri: "template://logo.png", if_none_match: ifNoneMatch },
+      }) + "\n",
+    );
+
+  // Read once for the ETag, then again with it
+  setTimeout(() => read(2), 100);
+
+  let conditionalResponse = "";
+  let sentConditional = false;
+  const responses = () =>
+    conditionalResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+  conditional.stdout.on("data", (data) => {
+    conditionalResponse += data.toString();
+    const first = responses().find((response) => response.id === 2);
+    if (first && first.result && !sentConditional) {
+      sentConditional = true;
+      read(3, first.result.etag);
+    }
+  });
+
+  setTimeout(() => {
+    conditional.kill();
+    const first = responses().find((response) => response.id === 2);
+    const second = responses().find((response) => response.id === 3);
+    if (
+      first &&
+      first.result.etag &&
+      !first.result.not_modified &&
+      first.result.contents.length === 1 &&
+      second &&
+      second.result.not_modified &&
+      second.result.etag === first.result.etag &&
+      second.result.contents.length === 0
+    ) {
+      console.log("✓ Unchanged resource reported as not modified");
+      testToolStats();
+    } else {
+      console.error("Conditional read test failed:", conditionalResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testToolStats() {
+  console.log("\n9. Testing tool call statistics...");
+  const stats = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  stats.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // Call add_numbers three times, then read the statistics
+  setTimeout(() => {
+    for (const id of [2, 3, 4]) {
+      stats.stdin.write(
+        JSON.stringify({
+          jsonrpc: "2.0",
+          id,
+          method: "tools/call",
+          params: { name: "add_numbers", arguments: { a: id, b: 1 } },
+        }) + "\n",
+      );
+    }
+    stats.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 5,
+        method: "resources/read",
+        params: { uri: "template://stats/tools" },
+      }) + "\n",
+    );
+  }, 100);
+
+  let statsResponse = "";
+  stats.stdout.on("data", (data) => {
+    statsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    stats.kill();
+    const read = statsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 5);
+    const entries = read && JSON.parse(read.result.contents[0].text);
+    const add = entries && entries.find((entry) => entry.tool === "add_numbers");
+    if (
+      add &&
+      add.calls === 3 &&
+      add.errors === 0 &&
+      typeof add.p50_ms === "number" &&
+      add.p95_ms >= add.p50_ms &&
+      add.last_called
+    ) {
+      console.log("✓ Calls and latency of add_numbers in the statistics");
+      testParameterGroups();
+    } else {
+      console.error("Tool statistics test failed:", statsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testParameterGroups() {
+  console.log("\n10. Testing mutually exclusive parameters...");
+  const groups = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  groups.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // Arguments are JSON text, since ids don't fit in a JavaScript number
+  const find = (id, args) =>
+    groups.stdin.write(
+      `{"jsonrpc":"2.0","id":${id},"method":"tools/call",` +
+        `"params":{"name":"find_data","arguments":${args}}}\n`,
+    );
+
+  // Create an entry, then look it up by id, by name, by both and by neither
+  setTimeout(() => {
+    groups.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "tools/call",
+        params: { name: "create_data", arguments: { name: "alpha", value: 1 } },
+      }) + "\n",
+    );
+  }, 100);
+
+  let groupsResponse = "";
+  let sentLookups = false;
+  const responses = () =>
+    groupsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+  groups.stdout.on("data", (data) => {
+    groupsResponse += data.toString();
+    const created = responses().find((response) => response.id === 2);
+    if (created && created.result && !sentLookups) {
+      sentLookups = true;
+      const id = groupsResponse.match(/"structuredContent":\{"id":(\d+)/)[1];
+      find(3, `{"id":${id}}`);
+      find(4, `{"name":"alpha"}`);
+      find(5, `{"id":${id},"name":"alpha"}`);
+      find(6, "{}");
+    }
+  });
+
+  setTimeout(() => {
+    groups.kill();
+    const byId = (id) => responses().find((response) => response.id === id);
+    const found = (response) =>
+      response && response.result && response.result.structuredContent.name;
+    const rejected = (response) =>
+      response &&
+      response.error &&
+      response.error.code === -32602 &&
+      response.error.message.includes("exactly one of the parameters id, name");
+    if (
+      found(byId(3)) === "alpha" &&
+      found(byId(4)) === "alpha" &&
+      rejected(byId(5)) &&
+      rejected(byId(6))
+    ) {
+      console.log("✓ Exactly one of id and name accepted");
+      testCountdownProgress();
+    } else {
+      console.error("Parameter group test failed:", groupsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testCountdownProgress() {
+  console.log("\n11. Testing countdown progress and cancellation...");
+  const countdown = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  countdown.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  const call = (id, name, args) =>
+    countdown.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id,
+        method: "tools/call",
+        params: { name, arguments: args },
+      }) + "\n",
+    );
+
+  // One countdown runs to the end; the other is cancelled after two steps
+  setTimeout(() => {
+    call(2, "countdown", { from: 3, interval_ms: 100 });
+    call(3, "countdown", { from: 10, interval_ms: 100 });
+  }, 100);
+
+  let countdownResponse = "";
+  let scheduled = false;
+  const responses = () =>
+    countdownResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+  const byId = (id) => responses().find((response) => response.id === id);
+  const operation = (id) => JSON.parse(byId(id).result.content[0].text);
+  countdown.stdout.on("data", (data) => {
+    countdownResponse += data.toString();
+    if (!scheduled && byId(2) && byId(3)) {
+      scheduled = true;
+      const finished = operation(2).operation_id;
+      const cancelled = operation(3).operation_id;
+      setTimeout(() => call(4, "cancel_operation", { operation_id: cancelled }), 250);
+      setTimeout(() => {
+        call(5, "get_operation", { operation_id: finished });
+        call(6, "get_operation", { operation_id: cancelled });
+      }, 800);
+    }
+  });
+
+  setTimeout(() => {
+    countdown.kill();
+    const finished = byId(5) && byId(5).result.structuredContent;
+    const cancelled = byId(6) && byId(6).result.structuredContent;
+    if (
+      finished &&
+      finished.state === "completed" &&
+      finished.result === "done" &&
+      finished.progress.reports === 3 &&
+      finished.progress.progress === 3 &&
+      finished.progress.total === 3 &&
+      cancelled &&
+      cancelled.state === "cancelled" &&
+      cancelled.progress.reports >= 1 &&
+      cancelled.progress.reports < 10
+    ) {
+      console.log("✓ Countdown reported every step and stopped when cancelled");
+      testConfigFromEnv();
+    } else {
+      console.error("Countdown test failed:", countdownResponse);
+      process.exit(1);
+    }
+  }, 1500);
+}
+
+function testConfigFromEnv() {
+  console.log("\n12. Testing configuration from the environment...");
+
+  // An out-of-range value stops the server with a message naming it
+  const invalid = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error", MCP_TIMEOUT: "0" },
+  });
+  let invalidError = "";
+  invalid.stderr.on("data", (data) => {
+    invalidError += data.toString();
+  });
+  invalid.on("close", (code) => {
+    if (code === 0 || !invalidError.includes("timeout_seconds (from MCP_TIMEOUT)")) {
+      console.error("Invalid configuration test failed:", code, invalidError);
+      process.exit(1);
+    }
+    console.log("✓ Out-of-range MCP_TIMEOUT rejected at startup");
+    testConfigOverride();
+  });
+  invalid.stdin.end();
+}
+
+function testConfigOverride() {
+  // Valid overrides show up in the configuration, read as an admin client
+  const config = spawn(binaryPath, [], {
+    env: {
+      ...process.env,
+      RUST_LOG: "error",
+      MCP_TIMEOUT: "5",
+      MCP_ADMIN_CLIENTS: "test-admin",
+      MCP_CLIENT_ID: "test-admin",
+    },
+  });
+
+  // Initialize
+  config.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  setTimeout(() => {
+    config.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "resources/read",
+        params: { uri: "template://server-config" },
+      }) + "\n",
+    );
+  }, 100);
+
+  let configResponse = "";
+  config.stdout.on("data", (data) => {
+    configResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    config.kill();
+    const read = configResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 2);
+    const loaded = read && read.result && JSON.parse(read.result.contents[0].text);
+    if (loaded && loaded.timeout_seconds === 5 && loaded.transport.max_connections === 100) {
+      console.log("✓ MCP_TIMEOUT overrides the default timeout");
+      testRangeRead();
+    } else {
+      console.error("Configuration override test failed:", configResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testRangeRead() {
+  console.log("\n13. Testing byte-range resource reads...");
+  const range = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  range.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // Read the whole image, then bytes 8 to 23 of it
+  setTimeout(() => {
+    range.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "resources/read_range",
+        params: { uri: "template://logo.png" },
+      }) + "\n",
+    );
+    range.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 3,
+        method: "resources/read_range",
+        params: { uri: "template://logo.png", range: { offset: 8, length: 16 } },
+      }) + "\n",
+    );
+  }, 100);
+
+  let rangeResponse = "";
+  range.stdout.on("data", (data) => {
+    rangeResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    range.kill();
+    const responses = rangeResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const full = responses.find((response) => response.id === 2);
+    const part = responses.find((response) => response.id === 3);
+    const bytes = (response) => Buffer.from(response.result.blob, "base64");
+    if (
+      full &&
+      part &&
+      bytes(full).length === full.result.total_size &&
+      part.result.total_size === full.result.total_size &&
+      part.result.length === 16 &&
+      bytes(part).equals(bytes(full).subarray(8, 24))
+    ) {
+      console.log("✓ Range matches the same bytes of the full read");
+      testEffectPolicy();
+    } else {
+      console.error("Range read test failed:", rangeResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testEffectPolicy() {
+  console.log("\n14. Testing the tool effect policy...");
+  // This deployment forbids filesystem tools
+  const policy = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error", MCP_ALLOWED_EFFECTS: "network,database" },
+  });
+
+  // Initialize
+  policy.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  const call = (id, name) =>
+    policy.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id,
+        method: "tools/call",
+        params: { name, arguments: name === "add_numbers" ? { a: 1, b: 2 } : {} },
+      }) + "\n",
+    );
+
+  // List the tools, call the filesystem tool, then a database tool and one
+  // without effects
+  setTimeout(() => {
+    policy.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", id: 2, method: "tools/list", params: {} }) + "\n",
+    );
+    call(3, "list_roots");
+    call(4, "tag_counts");
+    call(5, "add_numbers");
+  }, 100);
+
+  let policyResponse = "";
+  policy.stdout.on("data", (data) => {
+    policyResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    policy.kill();
+    const responses = policyResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const tools = byId(2) && byId(2).result.tools;
+    const tagCounts = tools && tools.find((tool) => tool.name === "tag_counts");
+    if (
+      tools &&
+      !tools.some((tool) => tool.name === "list_roots") &&
+      tagCounts.inputSchema["x-effects"].includes("database") &&
+      byId(3).error &&
+      byId(3).error.message.includes("filesystem") &&
+      byId(4).result &&
+      byId(5).result
+    ) {
+      console.log("✓ Filesystem tool hidden and refused, other tools work");
+      testRequestDeadline();
+    } else {
+      console.error("Effect policy test failed:", policyResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testRequestDeadline() {
+  console.log("\n15. Testing tool call deadlines...");
+  // Calls time out after a second
+  const deadline = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "info", MCP_TIMEOUT: "1" },
+  });
+
+  // Initialize
+  deadline.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  // Call a tool whose upstream takes longer than the deadline
+  setTimeout(() => {
+    deadline.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 2,
+        method: "tools/call",
+        params: { name: "fetch_upstream", arguments: { path: "/slow", delay_ms: 3000 } },
+      }) + "\n",
+    );
+  }, 100);
+
+  // Once it timed out, check the upstream request was cancelled with it
+  setTimeout(() => {
+    deadline.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id: 3,
+        method: "resources/read",
+        params: { uri: "template://logs" },
+      }) + "\n",
+    );
+  }, 1500);
+
+  let deadlineResponse = "";
+  deadline.stdout.on("data", (data) => {
+    deadlineResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    deadline.kill();
+    const responses = deadlineResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const call = responses.find((response) => response.id === 2);
+    const logs = responses.find((response) => response.id === 3);
+    const messages = logs ? JSON.parse(logs.result.contents[0].text).map((e) => e.message) : [];
+    if (
+      call &&
+      call.error &&
+      call.error.message.includes("timed out after 1s") &&
+      messages.includes("Upstream request cancelled") &&
+      !messages.includes("Upstream request completed")
+    ) {
+      console.log("✓ Call timed out and its upstream request was cancelled");
+      testProtocolVersions();
+    } else {
+      console.error("Request deadline test failed:", deadlineResponse);
+      process.exit(1);
+    }
+  }, 2000);
+}
+
+function testProtocolVersions() {
+  console.log("\n16. Testing protocol version negotiation...");
+  // One client on the legacy revision, one on the latest
+  const session = (protocolVersion) => {
+    const client = spawn(binaryPath, [], {
+      env: { ...process.env, RUST_LOG: "error" },
+    });
+    const send = (id, method, params) =>
+      client.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+    const state = { client, output: "" };
+    client.stdout.on("data", (data) => {
+      state.output += data.toString();
+    });
+
+    // Initialize, list the tools and call a tool with structured output
+    send(1, "initialize", {
+      protocolVersion,
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    setTimeout(() => {
+      send(2, "tools/list", {});
+      send(3, "tools/call", { name: "tag_counts", arguments: {} });
+    }, 100);
+    return state;
+  };
+  const legacy = session("2024-11-05");
+  const latest = session("2025-06-18");
+
+  setTimeout(() => {
+    const responses = (state) => {
+      state.client.kill();
+      const byId = {};
+      state.output
+        .split("\n")
+        .filter((line) => line.trim().startsWith("{"))
+        .map((line) => JSON.parse(line))
+        .forEach((response) => (byId[response.id] = response));
+      return byId;
+    };
+    const old = responses(legacy);
+    const current = responses(latest);
+    const hasOutputSchema = (byId) =>
+      byId[2].result.tools.some((tool) => "outputSchema" in tool);
+    const hasJsonText = (result) =>
+      result.content.some((item) => {
+        try {
+          return typeof JSON.parse(item.text) === "object";
+        } catch {
+          return false;
+        }
+      });
+    if (
+      old[1] &&
+      old[1].result.protocolVersion === "2024-11-05" &&
+      !hasOutputSchema(old) &&
+      old[3].result.content.length > 0 &&
+      !("structuredContent" in old[3].result) &&
+      hasJsonText(old[3].result) &&
+      current[1] &&
+      current[1].result.protocolVersion === "2025-06-18" &&
+      hasOutputSchema(current) &&
+      "structuredContent" in current[3].result
+    ) {
+      console.log("✓ Legacy client gets 2024-11-05 results, latest client gets 2025-06-18");
+      testCatalogResources();
+    } else {
+      console.error("Protocol version test failed:", legacy.output, latest.output);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testCatalogResources() {
+  console.log("\n17. Testing catalog resources...");
+  const catalog = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // Initialize
+  catalog.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  const send = (id, method, params) =>
+    catalog.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+  // List the resources and the catalog, then read every report type
+  const reportTypes = ["daily", "weekly", "monthly"];
+  setTimeout(() => {
+    send(2, "resources/list", {});
+    send(3, "tools/call", { name: "list_catalog", arguments: {} });
+    reportTypes.forEach((name, i) =>
+      send(4 + i, "resources/read", { uri: `template://catalog/${name}` }),
+    );
+  }, 100);
+
+  let catalogResponse = "";
+  catalog.stdout.on("data", (data) => {
+    catalogResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    catalog.kill();
+    const responses = catalogResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const listed = byId(2)
+      ? byId(2)
+          .result.resources.map((resource) => resource.uri)
+          .filter((uri) => uri.startsWith("template://catalog/"))
+      : [];
+    const entries = byId(3) ? JSON.parse(byId(3).result.content[0].text) : [];
+    const reads = reportTypes.map((name, i) => {
+      const read = byId(4 + i);
+      return read && read.result && JSON.parse(read.result.contents[0].text).report_type === name;
+    });
+    if (
+      listed.length === 3 &&
+      reportTypes.every((name) => listed.includes(`template://catalog/${name}`)) &&
+      entries.length === 3 &&
+      reads.every(Boolean)
+    ) {
+      console.log("✓ Three report types listed and each readable by name");
+      testResponseSizeLimit();
+    } else {
+      console.error("Catalog test failed:", catalogResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testResponseSizeLimit() {
+  console.log("\n18. Testing the response size limit...");
+  // Results over 400 bytes are rejected, except list_data's, which truncates
+  const limited = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error", MCP_MAX_RESPONSE_BYTES: "400" },
+  });
+
+  // Initialize
+  limited.stdin.write(
+    JSON.stringify({
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    }) + "\n",
+  );
+
+  const call = (id, name, args) =>
+    limited.stdin.write(
+      JSON.stringify({
+        jsonrpc: "2.0",
+        id,
+        method: "tools/call",
+        params: { name, arguments: args },
+      }) + "\n",
+    );
+
+  // Store enough entries for the listing to go over the limit, list them and
+  // echo a long message
+  setTimeout(() => {
+    ["first", "second", "third"].forEach((name, i) =>
+      call(2 + i, "create_data", { name: name.repeat(10), value: i, tags: ["x"] }),
+    );
+    call(5, "list_data", {});
+    call(6, "echo", { message: "x".repeat(500) });
+  }, 100);
+
+  let limitedResponse = "";
+  limited.stdout.on("data", (data) => {
+    limitedResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    limited.kill();
+    const responses = limitedResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const listing = byId(5) && byId(5).result;
+    const note = listing && JSON.parse(listing.content[listing.content.length - 1].text);
+    const echo = byId(6);
+    if (
+      listing &&
+      JSON.stringify(listing).length <= 400 &&
+      note.truncated === true &&
+      note.original_bytes > 400 &&
+      echo &&
+      echo.error &&
+      echo.error.message.includes("over the 400 byte response limit")
+    ) {
+      console.log("✓ Listing truncated to the limit, long echo rejected");
+      testRepl();
+    } else {
+      console.error("Response size test failed:", limitedResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testRepl() {
+  console.log("\n19. Testing the REPL...");
+  const repl = spawn(binaryPath, ["--repl"], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  // One command per line, each answered after the prompt
+  repl.stdin.write(
+    [
+      "help",
+      "list tools",
+      'call add_numbers {"a": 1, "b": 2}',
+      "read template://server-status",
+      "schema add_numbers",
+      "frobnicate",
+      "quit",
+    ].join("\n") + "\n",
+  );
+  repl.stdin.end();
+
+  let replOutput = "";
+  repl.stdout.on("data", (data) => {
+    replOutput += data.toString();
+  });
+
+  repl.on("close", () => {
+    const outputs = replOutput.split("mcp> ").slice(1);
+    const [help, tools, sum, status, schema, unknown] = outputs;
+    if (
+      outputs.length === 7 &&
+      help.includes("call <tool> [json]") &&
+      tools.includes("add_numbers") &&
+      sum.trim() === "3.0" &&
+      JSON.parse(status).name === "Template MCP Server" &&
+      JSON.parse(schema).required.includes("b") &&
+      unknown.startsWith("error: Unknown command 'frobnicate'")
+    ) {
+      console.log("✓ Scripted REPL commands listed, called, read and explained");
+      testDefaultEmptyLists();
+    } else {
+      console.error("REPL test failed:", replOutput);
+      process.exit(1);
+    }
+  });
+}
+
+function testDefaultEmptyLists() {
+  console.log("\n20. Testing list parameters that default to empty...");
+  const lists = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    lists.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+  // Initialize
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+
+  // List the tools, then create entries with the tags omitted, empty and given
+  setTimeout(() => {
+    send(2, "tools/list", {});
+    send(3, "tools/call", { name: "create_data", arguments: { name: "omitted", value: 1 } });
+    send(4, "tools/call", {
+      name: "create_data",
+      arguments: { name: "empty", value: 2, tags: [] },
+    });
+    send(5, "tools/call", {
+      name: "create_data",
+      arguments: { name: "tagged", value: 3, tags: ["a", "b"] },
+    });
+  }, 100);
+
+  let listsResponse = "";
+  lists.stdout.on("data", (data) => {
+    listsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    lists.kill();
+    const responses = listsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const tagsOf = (id) => byId(id) && byId(id).result && byId(id).result.structuredContent.tags;
+    const createData = byId(2) && byId(2).result.tools.find((tool) => tool.name === "create_data");
+    const schema = createData && createData.inputSchema;
+    if (
+      schema &&
+      !schema.required.includes("tags") &&
+      JSON.stringify(schema.properties.tags.default) === "[]" &&
+      JSON.stringify(tagsOf(3)) === "[]" &&
+      JSON.stringify(tagsOf(4)) === "[]" &&
+      JSON.stringify(tagsOf(5)) === '["a","b"]'
+    ) {
+      console.log("✓ Omitted tags defaulted to empty, given tags kept");
+      testLifecycle();
+    } else {
+      console.error("Default empty list test failed:", listsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testLifecycle() {
+  console.log("\n21. Testing the initialize lifecycle...");
+  const lifecycle = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    lifecycle.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  const initialize = {
+    protocolVersion: "2025-06-18",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  };
+
+  // Call a tool before initializing, then initialize twice and call it again
+  send(1, "tools/call", { name: "echo", arguments: { message: "too early" } });
+  send(2, "ping", {});
+  send(3, "initialize", initialize);
+  send(4, "initialize", initialize);
+  send(5, "tools/call", { name: "echo", arguments: { message: "on time" } });
+
+  let lifecycleResponse = "";
+  lifecycle.stdout.on("data", (data) => {
+    lifecycleResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    lifecycle.kill();
+    const responses = lifecycleResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const early = byId(1);
+    const duplicate = byId(4);
+    if (
+      early &&
+      early.error &&
+      early.error.code === -32002 &&
+      early.error.message.includes("not initialized") &&
+      byId(2) &&
+      byId(2).result &&
+      byId(3) &&
+      byId(3).result &&
+      duplicate &&
+      duplicate.error &&
+      duplicate.error.message.includes("already initialized") &&
+      byId(5) &&
+      byId(5).result.content[0].text.includes("on time")
+    ) {
+      console.log("✓ Pre-initialize call and duplicate initialize rejected");
+      testPingUnderLoad();
+    } else {
+      console.error("Lifecycle test failed:", lifecycleResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testPingUnderLoad() {
+  console.log("\n22. Testing pings under load and keepalive...");
+  const busy = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    busy.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+  // Two slow calls, handled one after the other, then a ping
+  send(1, "initialize", {
+    protocolVersion: "2025-06-18",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  send(2, "tools/call", { name: "fetch_upstream", arguments: { path: "/a", delay_ms: 1000 } });
+  send(3, "tools/call", { name: "fetch_upstream", arguments: { path: "/b", delay_ms: 1000 } });
+  setTimeout(() => send(4, "ping", {}), 100);
+
+  // Ids in the order their responses arrive
+  const order = [];
+  let busyBuffer = "";
+  busy.stdout.on("data", (data) => {
+    busyBuffer += data.toString();
+    const lines = busyBuffer.split("\n");
+    busyBuffer = lines.pop();
+    lines
+      .filter((line) => line.trim().startsWith("{"))
+      .forEach((line) => order.push(JSON.parse(line).id));
+  });
+
+  setTimeout(() => {
+    busy.kill();
+    if (JSON.stringify(order) === "[1,4,2,3]") {
+      console.log("✓ Ping answered while the slow calls were running");
+      testKeepalive();
+    } else {
+      console.error("Ping under load test failed:", order);
+      process.exit(1);
+    }
+  }, 2600);
+}
+
+function testKeepalive() {
+  // A client answering the server's pings gets its round trip in the status
+  const env = { ...process.env, RUST_LOG: "error", MCP_PING_INTERVAL: "1", MCP_PING_TIMEOUT: "1" };
+  const live = spawn(binaryPath, [], { env });
+  const send = (client, message) => client.stdin.write(JSON.stringify(message) + "\n");
+  const initialize = (client) =>
+    send(client, {
+      jsonrpc: "2.0",
+      id: 1,
+      method: "initialize",
+      params: {
+        protocolVersion: "2025-06-18",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      },
+    });
+
+  initialize(live);
+  let liveBuffer = "";
+  let keepalive = null;
+  live.stdout.on("data", (data) => {
+    liveBuffer += data.toString();
+    const lines = liveBuffer.split("\n");
+    liveBuffer = lines.pop();
+    lines
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .forEach((message) => {
+        if (message.method === "ping") {
+          send(live, { jsonrpc: "2.0", id: message.id, result: {} });
+          send(live, {
+            jsonrpc: "2.0",
+            id: 2,
+            method: "resources/read",
+            params: { uri: "template://server-status" },
+          });
+        } else if (message.id === 2) {
+          keepalive = JSON.parse(message.result.contents[0].text).keepalive;
+        }
+      });
+  });
+
+  // A client that never answers is disconnected
+  const dead = spawn(binaryPath, [], { env });
+  initialize(dead);
+  let deadPings = 0;
+  dead.stdout.on("data", (data) => {
+    deadPings += (data.toString().match(/"method":"ping"/g) || []).length;
+  });
+  const deadTimer = setTimeout(() => dead.kill(), 5000);
+
+  dead.on("close", (code) => {
+    clearTimeout(deadTimer);
+    live.kill();
+    if (
+      code === 0 &&
+      deadPings >= 1 &&
+      keepalive &&
+      keepalive.pongs_received >= 1 &&
+      keepalive.last_round_trip_ms !== null
+    ) {
+      console.log("✓ Answered pings timed, silent client disconnected");
+      testDataValidation();
+    } else {
+      console.error("Keepalive test failed:", code, deadPings, keepalive);
+      process.exit(1);
+    }
+  });
+}
+
+function testDataValidation() {
+  console.log("\n23. Testing example data validation...");
+  const validation = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    validation.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  // A valid entry, then a blank name
+  send(2, "tools/call", { name: "create_data", arguments: { name: "valid", value: 1.5 } });
+  send(3, "tools/call", { name: "create_data", arguments: { name: "   ", value: 1 } });
+
+  let validationResponse = "";
+  validation.stdout.on("data", (data) => {
+    validationResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    validation.kill();
+    const responses = validationResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const created = byId(2) && byId(2).result && byId(2).result.structuredContent;
+    const blank = byId(3) && byId(3).error;
+    if (
+      created &&
+      created.name === "valid" &&
+      created.value === 1.5 &&
+      blank &&
+      blank.code === -32602 &&
+      blank.data.code === 1002 &&
+      blank.data.details.path === "name"
+    ) {
+      console.log("✓ Valid entry built, blank name rejected with details");
+      testToolVersions();
+    } else {
+      console.error("Data validation test failed:", validationResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testToolVersions() {
+  console.log("\n24. Testing tool versions...");
+  const versions = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    versions.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  // Both versions, then the unversioned alias
+  send(2, "tools/list", {});
+  send(3, "tools/call", { name: "echo@1", arguments: { message: "hi" } });
+  send(4, "tools/call", { name: "echo@2", arguments: { message: "hi" } });
+  send(5, "tools/call", { name: "echo", arguments: { message: "hi" } });
+
+  let versionsResponse = "";
+  versions.stdout.on("data", (data) => {
+    versionsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    versions.kill();
+    const responses = versionsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const text = (id) => byId(id) && byId(id).result && byId(id).result.content[0].text;
+    const tools = byId(2) ? byId(2).result.tools : [];
+    const version = (name) => {
+      const tool = tools.find((tool) => tool.name === name);
+      return tool && tool.inputSchema["x-version"];
+    };
+    if (
+      version("echo@1") === "1" &&
+      version("echo@2") === "2" &&
+      version("echo") === "2" &&
+      !tools.some((tool) => tool.name === "echo_v2") &&
+      text(3) === '"Echo: hi"' &&
+      text(4) === '"hi"' &&
+      text(5) === '"hi"'
+    ) {
+      console.log("✓ Each version behaves as its own, echo aliases the latest");
+      testHtmlContent();
+    } else {
+      console.error("Tool versions test failed:", versionsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testHtmlContent() {
+  console.log("\n25. Testing HTML content...");
+  const html = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    html.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+  const body = '<h1 class="title">Docs</h1><p>See <a href="#usage">usage</a>.</p>';
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  send(2, "tools/call", { name: "preview_html", arguments: { html: body } });
+
+  let htmlResponse = "";
+  html.stdout.on("data", (data) => {
+    htmlResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    html.kill();
+    const preview = htmlResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 2);
+    const content = preview && preview.result && preview.result.content[0];
+    if (
+      content &&
+      content.type === "text" &&
+      content.mimeType === "text/html" &&
+      content.text === body
+    ) {
+      console.log("✓ HTML body passed through as text/html content");
+      testHttpProxy();
+    } else {
+      console.error("HTML content test failed:", htmlResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testHttpProxy() {
+  console.log("\n26. Testing HTTP proxy resources...");
+  const http = require("http");
+  const requests = [];
+  const upstream = http.createServer((req, res) => {
+    requests.push(req.url);
+    if (req.url === "/v1/users/7") {
+      res.writeHead(200, { "Content-Type": "application/json" });
+      res.end(JSON.stringify({ id: 7, name: "Ada" }));
+    } else {
+      res.writeHead(404, { "Content-Type": "text/plain" });
+      res.end("no such user");
+    }
+  });
+
+  upstream.listen(0, "127.0.0.1", () => {
+    const { port } = upstream.address();
+    const proxy = spawn(binaryPath, [], {
+      env: {
+        ...process.env,
+        RUST_LOG: "error",
+        MCP_HTTP_PROXY_URL: `http://127.0.0.1:${port}/v1`,
+      },
+    });
+
+    const send = (id, method, params) =>
+      proxy.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+
+    send(1, "initialize", {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    send(2, "resources/read", { uri: "template://api/users/7" });
+    send(3, "resources/read", { uri: "template://api/users/8" });
+
+    let proxyResponse = "";
+    proxy.stdout.on("data", (data) => {
+      proxyResponse += data.toString();
+    });
+
+    setTimeout(() => {
+      proxy.kill();
+      upstream.close();
+      const responses = proxyResponse
+        .split("\n")
+        .filter((line) => line.trim().startsWith("{"))
+        .map((line) => JSON.parse(line));
+      const found = responses.find((response) => response.id === 2);
+      const missing = responses.find((response) => response.id === 3);
+      const contents = found && found.result && found.result.contents[0];
+      if (
+        contents &&
+        contents.mime_type === "application/json" &&
+        JSON.parse(contents.text).name === "Ada" &&
+        requests[0] === "/v1/users/7" &&
+        missing &&
+        missing.error &&
+        missing.error.code === -32602 &&
+        missing.error.message.includes("Resource not found: users/8")
+      ) {
+        console.log("✓ Proxied read returned the upstream body and content type");
+        console.log("✓ Upstream 404 returned as a resource error");
+        testChunkedUpload();
+      } else {
+        console.error("HTTP proxy test failed:", proxyResponse, requests);
+        process.exit(1);
+      }
+    }, 1000);
+  });
+}
+
+function testChunkedUpload() {
+  console.log("\n27. Testing chunked argument uploads...");
+  const upload = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    upload.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  const call = (id, name, args) => send(id, "tools/call", { name, arguments: args });
+
+  const chunks = ["a".repeat(40000), "b".repeat(40000), "c".repeat(40000)];
+  const responses = {};
+  let buffered = "";
+  upload.stdout.on("data", (data) => {
+    buffered += data.toString();
+    const lines = buffered.split("\n");
+    buffered = lines.pop();
+    for (const line of lines.filter((line) => line.trim().startsWith("{"))) {
+      const response = JSON.parse(line);
+      responses[response.id] = response;
+      if (response.id === 2) {
+        const uploadId = response.result.structuredContent.upload_id;
+        chunks.forEach((data, index) =>
+          call(3 + index, "append_chunk", { upload_id: uploadId, data, index }),
+        );
+        call(6, "finalize", { upload_id: uploadId });
+        call(7, "finalize", { upload_id: uploadId });
+      }
+    }
+  });
+
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  call(2, "begin_upload", { tool: "echo", param: "message" });
+
+  setTimeout(() => {
+    upload.kill();
+    const last = responses[5] && responses[5].result.structuredContent;
+    const finalized = responses[6] && responses[6].result;
+    const again = responses[7];
+    if (
+      last &&
+      last.chunks === 3 &&
+      last.received_bytes === 120000 &&
+      finalized &&
+      finalized.content[0].text === JSON.stringify(chunks.join("")) &&
+      again &&
+      again.error &&
+      again.error.message.includes("Unknown or expired upload")
+    ) {
+      console.log("✓ Three chunks reassembled and passed to echo");
+      console.log("✓ Finalized upload can't be finalized again");
+      testPlayground();
+    } else {
+      console.error("Chunked upload test failed:", JSON.stringify(responses).slice(-1500));
+      process.exit(1);
+    }
+  }, 1500);
+}
+
+function testPlayground() {
+  console.log("\n28. Testing the playground resource...");
+  const playground = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  let entry;
+  let playgroundResponse = "";
+  playground.stdout.on("data", (data) => {
+    playgroundResponse += data.toString();
+    const read = playgroundResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 2);
+    if (read && !entry) {
+      const document = JSON.parse(read.result.contents[0].text);
+      entry = document.tools.find((tool) => tool.tool === "create_data");
+      // The example request is sent as it is
+      if (entry) playground.stdin.write(JSON.stringify({ ...entry.request, id: 3 }) + "\n");
+    }
+  });
+
+  const send = (id, method, params) =>
+    playground.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  send(2, "resources/read", { uri: "template://playground" });
+
+  setTimeout(() => {
+    playground.kill();
+    const created = playgroundResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((response) => response.id === 3);
+    const args = entry && entry.request.params.arguments;
+    if (
+      args &&
+      entry.request.method === "tools/call" &&
+      args.name === "widget" &&
+      args.value === 42 &&
+      created &&
+      created.result &&
+      created.result.isError === false
+    ) {
+      console.log("✓ Playground entry for create_data has its required fields");
+      console.log("✓ The example request runs as sent");
+      testStatusAndVersion();
+    } else {
+      console.error("Playground test failed:", playgroundResponse.slice(-2000));
+      process.exit(1);
+    }
+  }, 1000);
+}
+
+function testStatusAndVersion() {
+  console.log("\n29. Testing the status and version tools' messages...");
+  const status = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    status.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  send(2, "tools/call", { name: "get_status", arguments: {} });
+  send(3, "tools/call", { name: "get_status", arguments: {} });
+  send(4, "tools/call", { name: "get_version", arguments: {} });
+
+  let statusResponse = "";
+  status.stdout.on("data", (data) => {
+    statusResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    status.kill();
+    const responses = statusResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const text = (id) => {
+      const response = responses.find((response) => response.id === id);
+      return response && response.result && response.result.content[0].text;
+    };
+    const expected = /^"Template MCP Server is running and ready to serve requests\. Uptime: \d+s"$/;
+    if (
+      [text(2), text(3)].every((text) => expected.test(text)) &&
+      /^"\d+\.\d+\.\d+"$/.test(text(4))
+    ) {
+      console.log("✓ get_status returns its message with the uptime on every call");
+      console.log("✓ get_version returns the version");
+      testBuildInfo();
+    } else {
+      console.error("Status and version test failed:", statusResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testBuildInfo() {
+  console.log("\n30. Testing the build info resource...");
+  const manifest = require("fs").readFileSync(
+    path.join(__dirname, "..", "template-mcp-server", "Cargo.toml"),
+    "utf8",
+  );
+  const crateVersion = manifest.match(/^version = "(.+)"$/m)[1];
+  const build = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    build.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  send(2, "resources/read", { uri: "template://build-info" });
+  send(3, "resources/read", { uri: "template://server-status" });
+
+  let buildResponse = "";
+  build.stdout.on("data", (data) => {
+    buildResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    build.kill();
+    const responses = buildResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const text = (id) => {
+      const response = responses.find((response) => response.id === id);
+      return response && response.result && JSON.parse(response.result.contents[0].text);
+    };
+    const initialized = responses.find((response) => response.id === 1);
+    const info = text(2);
+    const status = text(3);
+    if (
+      info &&
+      /^[0-9a-f]{7,40}$/.test(info.git_sha) &&
+      info.version === crateVersion &&
+      info.build_timestamp &&
+      info.rustc_version.startsWith("rustc ") &&
+      status.version === crateVersion &&
+      initialized.result.serverInfo.version === crateVersion
+    ) {
+      console.log("✓ Build info has the git sha and the crate version");
+      console.log("✓ Server status and server info report the same version");
+      testBatchPolicy();
+    } else {
+      console.error("Build info test failed:", buildResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testBatchPolicy() {
+  console.log("\n31. Testing batch failure policies...");
+  const batchServer = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const call = (id, name, args, meta) => ({
+    jsonrpc: "2.0",
+    id,
+    method: "tools/call",
+    params: meta ? { name, arguments: args, _meta: meta } : { name, arguments: args },
+  });
+  const send = (message) => batchServer.stdin.write(JSON.stringify(message) + "\n");
+  send({
+    jsonrpc: "2.0",
+    id: 1,
+    method: "initialize",
+    params: {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    },
+  });
+  send({ jsonrpc: "2.0", method: "notifications/initialized" });
+  // The default policy runs every request of the batch
+  send([
+    call(10, "echo", { message: "first" }),
+    call(11, "missing_tool", {}),
+    call(12, "create_data", { name: "after-continue", value: 1 }),
+  ]);
+  send([
+    call(20, "echo", { message: "first" }, { batchPolicy: "fail_fast" }),
+    call(21, "missing_tool", {}),
+    call(22, "create_data", { name: "after-fail-fast", value: 1 }),
+  ]);
+  send(call(30, "list_data", {}));
+
+  let batchResponse = "";
+  batchServer.stdout.on("data", (data) => {
+    batchResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    batchServer.kill();
+    const lines = batchResponse
+      .split("\n")
+      .filter((line) => /^\s*[[{]/.test(line))
+      .map((line) => JSON.parse(line));
+    const batches = lines.filter(Array.isArray);
+    const byId = (batch, id) => batch && batch.find((response) => response.id === id);
+    const [continued, failFast] = [10, 20].map((id) =>
+      batches.find((batch) => byId(batch, id)),
+    );
+    const listed = lines.find((line) => line.id === 30);
+    const listText = listed && listed.result ? listed.result.content[0].text : "";
+    const skipped = byId(failFast, 22);
+    if (
+      continued &&
+      continued.length === 3 &&
+      byId(continued, 10).result &&
+      byId(continued, 11).error &&
+      byId(continued, 12).result &&
+      failFast &&
+      failFast.length === 3 &&
+      byId(failFast, 20).result &&
+      byId(failFast, 21).error &&
+      skipped.error &&
+      skipped.error.data.skipped === true &&
+      skipped.error.data.failed_id === 21 &&
+      listText.includes("after-continue") &&
+      !listText.includes("after-fail-fast")
+    ) {
+      console.log("✓ continue_on_error runs the call after a failure");
+      console.log("✓ fail_fast skips the call after a failure");
+      testRequiredCapabilities();
+    } else {
+      console.error("Batch policy test failed:", batchResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testRequiredCapabilities() {
+  console.log("\n32. Testing tools that need a client capability...");
+  // Run a session, advertising `capabilities`, that lists the tools and calls
+  // list_roots
+  const session = (capabilities, done) => {
+    const server = spawn(binaryPath, [], {
+      env: { ...process.env, RUST_LOG: "error" },
+    });
+    const send = (id, method, params) =>
+      server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+    send(1, "initialize", {
+      protocolVersion: "0.1.0",
+      capabilities,
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    server.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+    );
+    send(2, "tools/list", {});
+    send(3, "tools/call", { name: "list_roots", arguments: {} });
+
+    let output = "";
+    server.stdout.on("data", (data) => {
+      output += data.toString();
+    });
+    setTimeout(() => {
+      server.kill();
+      const responses = output
+        .split("\n")
+        .filter((line) => line.trim().startsWith("{"))
+        .map((line) => JSON.parse(line));
+      const byId = (id) => responses.find((response) => response.id === id);
+      done(byId(2), byId(3), output);
+    }, 500);
+  };
+  const listsRoots = (list) =>
+    list && list.result && list.result.tools.some((tool) => tool.name === "list_roots");
+
+  session({ roots: { listChanged: true } }, (withList, withCall, withOutput) => {
+    if (!listsRoots(withList) || !withCall || !withCall.result) {
+      console.error("Capability test failed with roots advertised:", withOutput);
+      process.exit(1);
+    }
+    console.log("✓ list_roots is listed and runs for a client with roots");
+    session({}, (withoutList, withoutCall, withoutOutput) => {
+      const error = withoutCall && withoutCall.error;
+      if (
+        withoutList &&
+        withoutList.result &&
+        !listsRoots(withoutList) &&
+        error &&
+        error.data.error === "precondition_failed" &&
+        error.data.capability === "roots"
+      ) {
+        console.log("✓ list_roots is hidden and refused for a client without roots");
+        testToolWarnings();
+      } else {
+        console.error("Capability test failed without roots:", withoutOutput);
+        process.exit(1);
+      }
+    });
+  });
+}
+
+function testToolWarnings() {
+  console.log("\n33. Testing warnings sent from within a tool...");
+  const warnings = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (message) => warnings.stdin.write(JSON.stringify(message) + "\n");
+  send({
+    jsonrpc: "2.0",
+    id: 1,
+    method: "initialize",
+    params: {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    },
+  });
+  send({ jsonrpc: "2.0", method: "notifications/initialized" });
+  send({
+    jsonrpc: "2.0",
+    id: 2,
+    method: "tools/call",
+    params: { name: "process_list", arguments: { items: [], operation: "count" } },
+  });
+
+  let warningsResponse = "";
+  warnings.stdout.on("data", (data) => {
+    warningsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    warnings.kill();
+    const messages = warningsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const warningIndex = messages.findIndex(
+      (message) => message.method === "notifications/message",
+    );
+    const warning = messages[warningIndex];
+    const resultIndex = messages.findIndex((message) => message.id === 2);
+    const result = messages[resultIndex];
+    if (
+      warning &&
+      warning.params.level === "warning" &&
+      warning.params.logger === "process_list" &&
+      warning.params.data.includes("empty list") &&
+      warningIndex < resultIndex &&
+      result.result &&
+      !result.result.isError &&
+      result.result.content[0].text.includes("List contains 0 items")
+    ) {
+      console.log("✓ The warning is sent as a log message before the result");
+      console.log("✓ The tool still returns its result");
+      testComputedTimeouts();
+    } else {
+      console.error("Tool warning test failed:", warningsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testComputedTimeouts() {
+  console.log("\n34. Testing timeouts computed from the arguments...");
+  const timeouts = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error", MCP_TIMEOUT: "10" },
+  });
+
+  const send = (message) => timeouts.stdin.write(JSON.stringify(message) + "\n");
+  const validate = (id, name, args) =>
+    send({ jsonrpc: "2.0", id, method: "tools/validate", params: { name, arguments: args } });
+  send({
+    jsonrpc: "2.0",
+    id: 1,
+    method: "initialize",
+    params: {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    },
+  });
+  send({ jsonrpc: "2.0", method: "notifications/initialized" });
+  validate(2, "process_list", { items: ["one"], operation: "count" });
+  validate(3, "process_list", {
+    items: Array.from({ length: 5000 }, (_, index) => `item ${index}`),
+    operation: "count",
+  });
+  validate(4, "echo", { message: "hello" });
+
+  let timeoutsResponse = "";
+  timeouts.stdout.on("data", (data) => {
+    timeoutsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    timeouts.kill();
+    const responses = timeoutsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const timeoutOf = (id) => {
+      const response = responses.find((response) => response.id === id);
+      return response && response.result && response.result.timeout_ms;
+    };
+    const [small, large, fixed] = [2, 3, 4].map(timeoutOf);
+    if (small === 10001 && large === 15000 && fixed === 10000) {
+      console.log("✓ A larger list gets a longer timeout than a small one");
+      console.log("✓ Tools without a timeout function keep the configured timeout");
+      testToolFilter();
+    } else {
+      console.error("Computed timeout test failed:", timeoutsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testToolFilter() {
+  console.log("\n35. Testing the per-deployment tool filter...");
+  const filtered = spawn(binaryPath, [], {
+    env: {
+      ...process.env,
+      RUST_LOG: "error",
+      MCP_ENABLED_TOOLS: "get_status,echo",
+      MCP_DISABLED_TOOLS: "echo@1",
+    },
+  });
+
+  const send = (id, method, params) =>
+    filtered.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  filtered.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+  send(3, "tools/call", { name: "echo", arguments: { message: "hello" } });
+  send(4, "tools/call", { name: "add_numbers", arguments: { a: 1, b: 2 } });
+  send(5, "tools/call", { name: "echo@1", arguments: { message: "hello" } });
+
+  let filteredResponse = "";
+  filtered.stdout.on("data", (data) => {
+    filteredResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    filtered.kill();
+    const responses = filteredResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const names = byId(2) && byId(2).result.tools.map((tool) => tool.name).sort();
+    const notFound = (id) => byId(id) && byId(id).error && byId(id).error.code === -32601;
+    if (
+      names &&
+      JSON.stringify(names) === JSON.stringify(["echo", "echo@2", "get_status"]) &&
+      byId(3).result &&
+      notFound(4) &&
+      notFound(5)
+    ) {
+      console.log("✓ Only the enabled tools are listed");
+      console.log("✓ Filtered tools are refused as not found");
+      testFallibleStartup();
+    } else {
+      console.error("Tool filter test failed:", filteredResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testFallibleStartup() {
+  console.log("\n36. Testing setup that can fail at startup...");
+  const fs = require("fs");
+  const os = require("os");
+  const missing = path.join(os.tmpdir(), `missing-seed-${process.pid}.json`);
+
+  // A seed snapshot that can't be read stops the server with the reason
+  const failed = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error", MCP_SEED_SNAPSHOT: missing },
+  });
+  let failedError = "";
+  failed.stderr.on("data", (data) => {
+    failedError += data.toString();
+  });
+  failed.on("close", (code) => {
+    const expected = `Failed to start: Failed to read seed snapshot ${missing}`;
+    if (code === 0 || !failedError.includes(expected)) {
+      console.error("Fallible startup test failed:", code, failedError);
+      process.exit(1);
+    }
+    console.log("✓ Server refused to start with the setup error");
+
+    // A readable one is loaded into the data store
+    const seed = path.join(os.tmpdir(), `seed-${process.pid}.json`);
+    fs.writeFileSync(
+      seed,
+      JSON.stringify({
+        version: 1,
+        exported_at: "2024-01-01T00:00:00Z",
+        entries: [{ id: 42, name: "seeded", value: 1.5, tags: [] }],
+      }),
+    );
+    const seeded = spawn(binaryPath, [], {
+      env: { ...process.env, RUST_LOG: "error", MCP_SEED_SNAPSHOT: seed },
+    });
+    const send = (id, method, params) =>
+      seeded.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+    send(1, "initialize", {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    seeded.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+    );
+    send(2, "tools/call", { name: "find_data", arguments: { id: 42 } });
+
+    let seededResponse = "";
+    seeded.stdout.on("data", (data) => {
+      seededResponse += data.toString();
+    });
+
+    setTimeout(() => {
+      seeded.kill();
+      fs.unlinkSync(seed);
+      const found = seededResponse
+        .split("\n")
+        .filter((line) => line.trim().startsWith("{"))
+        .map((line) => JSON.parse(line))
+        .find((response) => response.id === 2);
+      if (found && found.result && JSON.stringify(found.result).includes("seeded")) {
+        console.log("✓ Seed snapshot loaded before serving");
+        testResourceListChanged();
+      } else {
+        console.error("Seed snapshot test failed:", seededResponse);
+        process.exit(1);
+      }
+    }, 500);
+  });
+  failed.stdin.end();
+}
+
+function testResourceListChanged() {
+  console.log("\n37. Testing resource list change notifications...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  // A static resource read changes nothing; each report adds a resource
+  send(2, "resources/read", { uri: "template://build-info" });
+  send(3, "tools/call", { name: "data_report", arguments: {} });
+  send(4, "tools/call", { name: "data_report", arguments: {} });
+
+  let listResponse = "";
+  server.stdout.on("data", (data) => {
+    listResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const messages = listResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const indexOf = (id) => messages.findIndex((message) => message.id === id);
+    const changes = messages
+      .map((message, index) => ({ message, index }))
+      .filter(({ message }) => message.method === "notifications/resources/list_changed");
+    const initialize = messages[indexOf(1)];
+    if (
+      initialize &&
+      initialize.result.capabilities.resources.list_changed === true &&
+      messages[indexOf(2)].result &&
+      changes.length === 1 &&
+      changes[0].index > indexOf(2) &&
+      changes[0].index < indexOf(3)
+    ) {
+      console.log("✓ Adding a result resource notified the client");
+      console.log("✓ Changes within the interval were throttled");
+      testToolSunset();
+    } else {
+      console.error("Resource list change test failed:", listResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testToolSunset() {
+  console.log("\n38. Testing tool sunsets...");
+
+  // Runs a server whose clock is fixed at `time`, calling echo@1
+  const runAt = (time, done) => {
+    const server = spawn(binaryPath, [], {
+      env: { ...process.env, RUST_LOG: "error", MCP_FIXED_TIME: time },
+    });
+    const send = (id, method, params) =>
+      server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+    send(1, "initialize", {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    server.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+    );
+    send(2, "tools/list", {});
+    send(3, "tools/call", { name: "echo@1", arguments: { message: "hello" } });
+
+    let output = "";
+    server.stdout.on("data", (data) => {
+      output += data.toString();
+    });
+    setTimeout(() => {
+      server.kill();
+      const messages = output
+        .split("\n")
+        .filter((line) => line.trim().startsWith("{"))
+        .map((line) => JSON.parse(line));
+      const byId = (id) => messages.find((message) => message.id === id);
+      const listed = byId(2) && byId(2).result.tools.find((tool) => tool.name === "echo@1");
+      const warning = messages.find(
+        (message) =>
+          message.method === "notifications/message" && message.params.logger === "echo@1",
+      );
+      done({ output, listed, call: byId(3), warning });
+    }, 500);
+  };
+
+  runAt("2027-05-20T12:00:00Z", (before) => {
+    if (
+      !before.listed ||
+      before.listed.inputSchema["x-sunset"] !== "2027-06-01" ||
+      !before.call ||
+      !before.call.result ||
+      !before.warning ||
+      !before.warning.params.data.includes("will be removed on 2027-06-01, in 12 days")
+    ) {
+      console.error("Before-sunset test failed:", before.output);
+      process.exit(1);
+    }
+    console.log("✓ Deprecated tool works with a warning before its sunset");
+
+    runAt("2027-06-01T00:00:00Z", (after) => {
+      const error = after.call && after.call.error;
+      if (
+        after.listed ||
+        !error ||
+        error.code !== -32601 ||
+        error.data.error !== "gone" ||
+        error.data.replacement !== "echo@2"
+      ) {
+        console.error("After-sunset test failed:", after.output);
+        process.exit(1);
+      }
+      console.log("✓ Tool is gone from its sunset date on");
+      testToolCosts();
+    });
+  });
+}
+
+function testToolCosts() {
+  console.log("\n39. Testing tool cost estimates...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "error" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+  send(3, "tools/call", { name: "data_report", arguments: {} });
+
+  let costResponse = "";
+  server.stdout.on("data", (data) => {
+    costResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = costResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const costOf = (name) => {
+      const tool = byId(2) && byId(2).result.tools.find((tool) => tool.name === name);
+      return tool && tool.inputSchema["x-cost"];
+    };
+    const result = byId(3) && byId(3).result;
+    const report = result && result._meta && result._meta.cost;
+    if (
+      costOf("echo") === "low" &&
+      costOf("data_report") === "medium" &&
+      costOf("fetch_upstream") === "high" &&
+      report &&
+      report.declared === "medium" &&
+      typeof report.durationMs === "number" &&
+      report.resultBytes > 0
+    ) {
+      console.log("✓ Declared costs are listed");
+      console.log("✓ Results report their actual cost");
+      testPanicRecovery();
+    } else {
+      console.error("Tool cost test failed:", costResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testPanicRecovery() {
+  console.log("\n40. Testing recovery from a panicking tool...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "warn" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  // Panics while the data store is locked for writing
+  send(2, "tools/call", { name: "example_with_panic", arguments: {} });
+  send(3, "tools/call", { name: "create_data", arguments: { name: "after-panic", value: 1 } });
+  send(4, "tools/call", { name: "find_data", arguments: { name: "after-panic" } });
+
+  let panicResponse = "";
+  let panicLog = "";
+  server.stdout.on("data", (data) => {
+    panicResponse += data.toString();
+  });
+  server.stderr.on("data", (data) => {
+    panicLog += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = panicResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const panicked = byId(2) && byId(2).error;
+    if (
+      panicked &&
+      panicked.message.includes("Tool 'example_with_panic' panicked") &&
+      byId(3) &&
+      byId(3).result &&
+      byId(4) &&
+      byId(4).result &&
+      JSON.stringify(byId(4).result).includes("after-panic") &&
+      panicLog.includes("poisoned the data store lock")
+    ) {
+      console.log("✓ The panic failed only its own call");
+      console.log("✓ The data store stayed usable after the panic");
+      testTextAndStructuredOutput();
+    } else {
+      console.error("Panic recovery test failed:", panicResponse, panicLog);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testTextAndStructuredOutput() {
+  console.log("\n41. Testing text and structured renderings of results...");
+  const server = spawn(binaryPath, []);
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/call", {
+    name: "create_data",
+    arguments: { name: "rendered", value: 2.5, tags: ["red", "blue"] },
+  });
+  send(3, "tools/call", { name: "list_catalog", arguments: {} });
+
+  let renderResponse = "";
+  server.stdout.on("data", (data) => {
+    renderResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = renderResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const created = byId(2) && byId(2).result;
+    const text = created && created.content[0].text;
+    const structured = created && created.structuredContent;
+    const catalog = byId(3) && byId(3).result;
+    if (
+      typeof text === "string" &&
+      text.startsWith("rendered (id ") &&
+      text.includes("2.5, tagged red, blue") &&
+      structured &&
+      structured.name === "rendered" &&
+      structured.value === 2.5 &&
+      catalog &&
+      Array.isArray(JSON.parse(catalog.content[0].text))
+    ) {
+      console.log("✓ create_data answers with readable text");
+      console.log("✓ create_data carries its value as structuredContent");
+      console.log("✓ Tools without an output schema keep JSON text");
+      testBulkCreate();
+    } else {
+      console.error("Text and structured output test failed:", renderResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testBulkCreate() {
+  console.log("\n42. Testing bulk creation with partial success...");
+  const server = spawn(binaryPath, []);
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  // The item at index 1 has a blank name, so only it fails
+  send(2, "tools/call", {
+    name: "create_data_bulk",
+    arguments: {
+      items: [
+        { name: "first", value: 1 },
+        { name: "  ", value: 2 },
+        { name: "third", value: 3, tags: ["own"] },
+      ],
+      tags: ["bulk"],
+    },
+  });
+  send(3, "tools/call", { name: "find_data", arguments: { name: "third" } });
+
+  let bulkResponse = "";
+  server.stdout.on("data", (data) => {
+    bulkResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = bulkResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const bulk = byId(2) && byId(2).result;
+    const outcome = bulk && bulk.structuredContent;
+    const found = byId(3) && byId(3).result && byId(3).result.structuredContent;
+    if (
+      outcome &&
+      !bulk.isError &&
+      outcome.succeeded.map((data) => data.name).join() === "first,third" &&
+      outcome.failed.length === 1 &&
+      outcome.failed[0].index === 1 &&
+      outcome.failed[0].error.includes("name must not be blank") &&
+      bulk.content[0].text.startsWith("2 succeeded, 1 failed") &&
+      found &&
+      found.tags.join() === "own,bulk"
+    ) {
+      console.log("✓ Valid items were created, each with the common tags");
+      console.log("✓ The invalid item was reported by its index");
+      testRequestCorrelation();
+    } else {
+      console.error("Bulk creation test failed:", bulkResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testRequestCorrelation() {
+  console.log("\n43. Testing request correlation ids...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, RUST_LOG: "info", MCP_ECHO_REQUEST_ID: "true" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/call", { name: "get_status", arguments: {} });
+  send(3, "tools/call", { name: "get_status", arguments: {} });
+
+  let correlationResponse = "";
+  let correlationLog = "";
+  server.stdout.on("data", (data) => {
+    correlationResponse += data.toString();
+  });
+  server.stderr.on("data", (data) => {
+    correlationLog += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = correlationResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const requestId = (id) => {
+      const result = byId(id) && byId(id).result;
+      return result && result._meta && result._meta.request_id;
+    };
+    const ids = [requestId(2), requestId(3)];
+    const uuid = /^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[0-9a-f]{4}-[0-9a-f]{12}$/;
+    // Without the colors of the log output
+    const plainLog = correlationLog.replace(/\x1b\[[0-9;]*m/g, "");
+    if (
+      ids.every((id) => typeof id === "string" && uuid.test(id)) &&
+      ids[0] !== ids[1] &&
+      ids.every((id) => plainLog.includes(`correlation_id=${id}`))
+    ) {
+      console.log("✓ Each request got its own correlation id in _meta.request_id");
+      console.log("✓ The ids appear in the request spans of the logs");
+      testSharedArguments();
+    } else {
+      console.error("Request correlation test failed:", correlationResponse, correlationLog);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testSharedArguments() {
+  console.log("\n44. Testing arguments shared by a tool and a prompt...");
+  const server = spawn(binaryPath, []);
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+  send(3, "prompts/list", {});
+  send(4, "tools/call", {
+    name: "create_data",
+    arguments: { name: "kept-entry", value: 5, tags: ["review"] },
+  });
+  send(5, "tools/call", { name: "create_data", arguments: { name: "other-entry", value: 1 } });
+  send(6, "prompts/get", {
+    name: "review_data",
+    arguments: { tag: "review", min_value: "2" },
+  });
+  send(7, "prompts/get", { name: "review_data", arguments: { min_value: "lots" } });
+
+  let sharedResponse = "";
+  server.stdout.on("data", (data) => {
+    sharedResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = sharedResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const byId = (id) => responses.find((response) => response.id === id);
+    const tool = byId(2) && byId(2).result.tools.find((t) => t.name === "filter_data");
+    const prompt = byId(3) && byId(3).result.prompts.find((p) => p.name === "review_data");
+    const fromSchema = tool
+      ? Object.entries(tool.inputSchema.properties)
+          .map(([name, property]) => ({
+            name,
+            description: property.description,
+            required: (tool.inputSchema.required || []).includes(name),
+          }))
+          .sort((a, b) => a.name.localeCompare(b.name))
+      : [];
+    const fromPrompt = prompt
+      ? prompt.arguments
+          .map(({ name, description, required }) => ({ name, description, required }))
+          .sort((a, b) => a.name.localeCompare(b.name))
+      : [];
+    const review = byId(6) && byId(6).result;
+    const reviewText = review ? review.messages.map((m) => m.content.text).join("\n") : "";
+    if (
+      fromSchema.length === 3 &&
+      JSON.stringify(fromSchema) === JSON.stringify(fromPrompt) &&
+      reviewText.includes("kept-entry") &&
+      !reviewText.includes("other-entry") &&
+      byId(7) &&
+      byId(7).error &&
+      byId(7).error.message.includes("min_value")
+    ) {
+      console.log("✓ The tool schema and the prompt arguments match");
+      console.log("✓ The prompt embeds the tool's result for its arguments");
+      console.log("✓ Prompt arguments of the wrong type are rejected");
+      testNdjsonResults();
+    } else {
+      console.error("Shared arguments test failed:", sharedResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testNdjsonResults() {
+  console.log("\n45. Testing NDJSON results...");
+  const server = spawn(binaryPath, []);
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  ["one", "two", "three"].forEach((name, index) =>
+    send(2 + index, "tools/call", { name: "create_data", arguments: { name, value: index } }),
+  );
+  send(5, "tools/call", { name: "export_ndjson", arguments: {} });
+
+  let ndjsonResponse = "";
+  server.stdout.on("data", (data) => {
+    ndjsonResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = ndjsonResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const exported = responses.find((response) => response.id === 5);
+    const content = exported && exported.result && exported.result.content[0];
+    // Every line ends in a newline, the last one included
+    const body = content && content.text.endsWith("\n") ? content.text.slice(0, -1) : "";
+    const lines = body ? body.split("\n") : [];
+    const records = lines.map((line) => JSON.parse(line));
+    if (
+      content &&
+      content.mimeType === "application/x-ndjson" &&
+      lines.length === 3 &&
+      records.map((record) => record.name).sort().join() === "one,three,two"
+    ) {
+      console.log("✓ Three entries were exported as three NDJSON lines");
+      console.log("✓ The content is tagged application/x-ndjson");
+      testWhoAmI();
+    } else {
+      console.error("NDJSON result test failed:", ndjsonResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testWhoAmI() {
+  console.log("\n46. Testing the whoami tool...");
+  // One client identified as an admin, one without an identity
+  const session = (env) => {
+    const inherited = { ...process.env };
+    delete inherited.MCP_CLIENT_ID;
+    delete inherited.MCP_ADMIN_CLIENTS;
+    const client = spawn(binaryPath, [], {
+      env: { ...inherited, MCP_WHOAMI_TOOL: "true", ...env },
+    });
+    const send = (id, method, params) =>
+      client.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+    const state = { client, output: "" };
+    client.stdout.on("data", (data) => {
+      state.output += data.toString();
+    });
+    send(1, "initialize", {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    client.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+    );
+    send(2, "tools/call", { name: "whoami", arguments: {} });
+    return state;
+  };
+  const identified = session({ MCP_CLIENT_ID: "ops-console", MCP_ADMIN_CLIENTS: "ops-console" });
+  const anonymous = session({});
+
+  setTimeout(() => {
+    const whoami = (state) => {
+      state.client.kill();
+      const response = state.output
+        .split("\n")
+        .filter((line) => line.trim().startsWith("{"))
+        .map((line) => JSON.parse(line))
+        .find((message) => message.id === 2);
+      return response && response.result && response.result.structuredContent;
+    };
+    const ops = whoami(identified);
+    const nobody = whoami(anonymous);
+    if (
+      ops &&
+      ops.identity === "ops-console" &&
+      ops.authenticated === true &&
+      ops.method === "client_id" &&
+      ops.scopes.join() === "admin" &&
+      nobody &&
+      nobody.identity === "anonymous" &&
+      nobody.authenticated === false &&
+      nobody.method === "none" &&
+      nobody.scopes.length === 0
+    ) {
+      console.log("✓ An identified client sees its identity and scopes");
+      console.log("✓ A client without an identity is anonymous");
+      testResourceQueries();
+    } else {
+      console.error("whoami test failed:", identified.output, anonymous.output);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testResourceQueries() {
+  console.log("\n47. Testing resource query parameters...");
+  // A lenient server and a strict one, each reading one entry several ways
+  const session = (env, queries) => {
+    const client = spawn(binaryPath, [], { env: { ...process.env, ...env } });
+    const send = (id, method, params) =>
+      client.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+    const state = { client, output: "", responses: {} };
+    client.stdout.on("data", (data) => {
+      state.output += data.toString();
+      const lines = state.output.split("\n").filter((line) => line.trim().startsWith("{"));
+      for (const response of lines.map((line) => JSON.parse(line))) {
+        if (response.id === 2 && !state.responses[2]) {
+          // Read the entry once it exists, as the query of each read asks
+          // JSON numbers can't hold every u64, so the id comes from the text
+          const id = response.result.content[0].text.match(/\(id (\d+)\)/)[1];
+          queries.forEach((query, index) =>
+            send(3 + index, "resources/read", { uri: `template://example-data/${id}${query}` }),
+          );
+        }
+        state.responses[response.id] = response;
+      }
+    });
+    send(1, "initialize", {
+      protocolVersion: "0.1.0",
+      capabilities: {},
+      clientInfo: { name: "test-client", version: "0.1.0" },
+    });
+    client.stdin.write(
+      JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+    );
+    send(2, "tools/call", {
+      name: "create_data",
+      arguments: { name: "widget", value: 1.5, tags: ["a"] },
+    });
+    return state;
+  };
+  const lenient = session({}, ["", "?format=summary", "?format=summary&verbose=1", "?format=x"]);
+  const strict = session({ MCP_STRICT_QUERY_PARAMS: "true" }, ["?format=summary&verbose=1"]);
+
+  setTimeout(() => {
+    lenient.client.kill();
+    strict.client.kill();
+    const contents = (state, id) => {
+      const response = state.responses[id];
+      const text = response && response.result && response.result.contents[0].text;
+      return text ? JSON.parse(text) : undefined;
+    };
+    const error = (state, id) => state.responses[id] && state.responses[id].error;
+    const full = contents(lenient, 3);
+    const summary = contents(lenient, 4);
+    const ignored = contents(lenient, 5);
+    const summaryUri = lenient.responses[4] && lenient.responses[4].result.contents[0].uri;
+    if (
+      full &&
+      full.name === "widget" &&
+      full.value === 1.5 &&
+      summary &&
+      Object.keys(summary).sort().join() === "id,name" &&
+      summaryUri &&
+      summaryUri.endsWith("?format=summary") &&
+      ignored &&
+      Object.keys(ignored).sort().join() === "id,name" &&
+      error(lenient, 6) &&
+      error(lenient, 6).message.includes("one of full, summary") &&
+      error(strict, 3) &&
+      error(strict, 3).message.includes("Unknown query parameter 'verbose'")
+    ) {
+      console.log("✓ Without a query the entry is read in full");
+      console.log("✓ ?format=summary reads its id and name only");
+      console.log("✓ Unknown parameters are ignored, or rejected when strict");
+      console.log("✓ A value of the wrong type fails the read");
+      testSchemaCatalog();
+    } else {
+      console.error("Resource query test failed:", lenient.output, strict.output);
+      process.exit(1);
+    }
+  }, 1000);
+}
+
+function testSchemaCatalog() {
+  console.log("\n48. Testing the cached schema catalog...");
+  const server = spawn(binaryPath, [], { env: { ...process.env, MCP_SCHEMA_RESOURCES: "1" } });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+  send(3, "tools/list", {});
+  send(4, "resources/read", { uri: "template://schema" });
+
+  let catalogResponse = "";
+  server.stdout.on("data", (data) => {
+    catalogResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = catalogResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const result = (id) => {
+      const response = responses.find((message) => message.id === id);
+      return response && response.result;
+    };
+    const first = result(2) && JSON.stringify(result(2).tools);
+    const second = result(3) && JSON.stringify(result(3).tools);
+    const schema = result(4) && JSON.stringify(JSON.parse(result(4).contents[0].text).tools);
+    const createData = result(2) && result(2).tools.find((tool) => tool.name === "create_data");
+    if (
+      first &&
+      first === second &&
+      first === schema &&
+      createData &&
+      createData.outputSchema &&
+      createData.inputSchema["x-effects"]
+    ) {
+      console.log("✓ Two tools/list calls return the identical catalog");
+      console.log("✓ The schema resource serves the same catalog");
+      console.log("✓ Listed schemas carry their decorations");
+      testSharedState();
+    } else {
+      console.error("Schema catalog test failed:", catalogResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testSharedState() {
+  console.log("\n49. Testing shared state...");
+  const server = spawn(binaryPath, []);
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "resources/read", { uri: "template://server-status" });
+  ["one", "two", "three"].forEach((name, index) =>
+    send(3 + index, "tools/call", { name: "create_data", arguments: { name, value: index } }),
+  );
+  send(6, "resources/read", { uri: "template://server-status" });
+  send(7, "tools/list", {});
+
+  let stateResponse = "";
+  server.stdout.on("data", (data) => {
+    stateResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = stateResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const result = (id) => {
+      const response = responses.find((message) => message.id === id);
+      return response && response.result;
+    };
+    const upstream = (id) => result(id) && JSON.parse(result(id).contents[0].text).upstream;
+    const before = upstream(2);
+    const after = upstream(6);
+    const createData = result(7) && result(7).tools.find((tool) => tool.name === "create_data");
+    const params = createData ? Object.keys(createData.inputSchema.properties).sort() : [];
+    if (
+      before &&
+      after &&
+      before.instance_id === after.instance_id &&
+      after.requests - before.requests === 3 &&
+      params.join() === "name,tags,value"
+    ) {
+      console.log("✓ Three create_data calls went through one shared client");
+      console.log("✓ The shared client isn't a tool argument");
+      testSharedSchemaDefs();
+    } else {
+      console.error("Shared state test failed:", stateResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testSharedSchemaDefs() {
+  console.log("\n50. Testing shared type definitions in schemas...");
+  const server = spawn(binaryPath, []);
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+
+  let defsResponse = "";
+  server.stdout.on("data", (data) => {
+    defsResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const listed = defsResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line))
+      .find((message) => message.id === 2);
+    const tools = (listed && listed.result && listed.result.tools) || [];
+    const schemaOf = (name, key) => {
+      const tool = tools.find((candidate) => candidate.name === name);
+      return tool && tool[key];
+    };
+    // Every $ref of a schema points into its own $defs
+    const refs = (value) =>
+      value && typeof value === "object"
+        ? Object.entries(value).flatMap(([key, inner]) =>
+            key === "$ref" ? [inner] : refs(inner),
+          )
+        : [];
+    const resolves = (schema) =>
+      refs(schema).every((ref) => {
+        const name = ref.replace("#/$defs/", "");
+        return schema.$defs && schema.$defs[name];
+      });
+    const users = [
+      ["create_data", "outputSchema"],
+      ["find_data", "outputSchema"],
+      ["create_data_bulk", "outputSchema"],
+      ["export_snapshot", "outputSchema"],
+      ["import_snapshot", "inputSchema"],
+    ];
+    const description = "Example data structure that your tools might work with";
+    const ok = users.every(([name, key]) => {
+      const schema = schemaOf(name, key);
+      const text = JSON.stringify(schema || {});
+      return (
+        schema &&
+        schema.$defs &&
+        schema.$defs.ExampleData &&
+        text.split(description).length === 2 &&
+        refs(schema).includes("#/$defs/ExampleData") &&
+        resolves(schema)
+      );
+    });
+    const createData = schemaOf("create_data", "outputSchema");
+    if (ok && createData.type === "object" && !createData.properties) {
+      console.log("✓ ExampleData is defined once in the $defs of each schema using it");
+      console.log("✓ Those schemas refer to it with $ref, and every $ref resolves");
+      testSafeMode();
+    } else {
+      console.error("Shared schema definitions test failed:", defsResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testSafeMode() {
+  console.log("\n51. Testing safe mode...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, MCP_SAFE_MODE: "1", MCP_SAFE_MODE_EFFECTS: "network" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+  send(3, "tools/call", { name: "clear_data", arguments: {} });
+  send(4, "tools/call", { name: "list_data", arguments: {} });
+  send(5, "tools/call", { name: "fetch_upstream", arguments: { path: "status", delay_ms: 0 } });
+  send(6, "resources/read", { uri: "template://server-status" });
+
+  let safeResponse = "";
+  server.stdout.on("data", (data) => {
+    safeResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = safeResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const response = (id) => responses.find((message) => message.id === id) || {};
+    const listed = response(2).result ? response(2).result.tools.map((tool) => tool.name) : [];
+    const destructive = ["clear_data", "delete_data", "import_snapshot", "reset_tool_stats"];
+    const refused = (id) => response(id).error && response(id).error.message;
+    if (
+      listed.includes("list_data") &&
+      destructive.every((name) => !listed.includes(name)) &&
+      !listed.includes("fetch_upstream") &&
+      refused(3) &&
+      refused(3).includes("Tool 'clear_data' is disabled in safe mode") &&
+      response(4).result &&
+      !response(4).result.isError &&
+      refused(5) &&
+      refused(5).includes("disabled in safe mode") &&
+      response(6).result
+    ) {
+      console.log("✓ Destructive tools are hidden and refused in safe mode");
+      console.log("✓ Tools with an effect safe mode covers are turned off too");
+      console.log("✓ Read-only tools and resources stay available");
+      testResourceEventFeed();
+    } else {
+      console.error("Safe mode test failed:", safeResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testResourceEventFeed() {
+  console.log("\n52. Testing the resource event feed...");
+  const http = require("http");
+  const net = require("net");
+  const uri = "template://stats/tools";
+
+  // Events of a feed as { event, id, data }, read as they arrive
+  const follow = (port, lastEventId) => {
+    const feed = { events: [], request: undefined };
+    const headers = lastEventId ? { "Last-Event-ID": lastEventId } : {};
+    const path = `/events?uri=${encodeURIComponent(uri)}`;
+    feed.request = http.get({ host: "127.0.0.1", port, path, headers }, (response) => {
+      feed.contentType = response.headers["content-type"];
+      let buffer = "";
+      response.on("data", (chunk) => {
+        buffer += chunk.toString();
+        const blocks = buffer.split("\n\n");
+        buffer = blocks.pop();
+        for (const block of blocks) {
+          const event = {};
+          for (const line of block.split("\n")) {
+            const [field, ...rest] = line.split(":");
+            if (["event", "id", "data"].includes(field)) event[field] = rest.join(":").trim();
+          }
+          if (event.event) feed.events.push(event);
+        }
+      });
+    });
+    feed.request.on("error", () => {});
+    return feed;
+  };
+
+  // Find a free port for the feed's listener
+  const probe = net.createServer().listen(0, "127.0.0.1", () => {
+    const { port } = probe.address();
+    probe.close(() => {
+      const server = spawn(binaryPath, [], {
+        env: { ...process.env, MCP_EVENTS_ADDR: `127.0.0.1:${port}` },
+      });
+      server.stdout.on("data", () => {});
+      const send = (id, method, params) =>
+        server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+      send(1, "initialize", {
+        protocolVersion: "0.1.0",
+        capabilities: {},
+        clientInfo: { name: "test-client", version: "0.1.0" },
+      });
+      server.stdin.write(
+        JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+      );
+
+      let first;
+      let resumed;
+      setTimeout(() => {
+        first = follow(port);
+      }, 500);
+      // A tool call in the background changes the call stats
+      setTimeout(() => {
+        send(2, "tools/call", { name: "echo", arguments: { message: "hi" } });
+      }, 1000);
+      setTimeout(() => {
+        first.request.destroy();
+        const last = first.events[first.events.length - 1];
+        resumed = follow(port, last && last.id);
+      }, 1800);
+      setTimeout(() => send(3, "tools/call", { name: "get_status", arguments: {} }), 2600);
+
+      setTimeout(() => {
+        resumed.request.destroy();
+        server.kill();
+        const [initial, update] = first.events;
+        const stats = (event) => {
+          const text = JSON.parse(event.data).contents[0].text;
+          return JSON.parse(text);
+        };
+        const named = (event, tool) => JSON.stringify(stats(event)).includes(`"${tool}"`);
+        if (
+          (first.contentType || "").startsWith("text/event-stream") &&
+          first.events.length === 2 &&
+          initial.event === "changed" &&
+          update.event === "changed" &&
+          initial.id !== update.id &&
+          !named(initial, "echo") &&
+          named(update, "echo") &&
+          resumed.events.length === 1 &&
+          resumed.events[0].id !== update.id &&
+          named(resumed.events[0], "get_status")
+        ) {
+          console.log("✓ The feed starts with the resource's current content");
+          console.log("✓ A background change is pushed as a changed event");
+          console.log("✓ Reconnecting with Last-Event-ID only sends later changes");
+          testContentInputs();
+        } else {
+          console.error("Resource event feed test failed:", JSON.stringify([first.events, resumed.events]));
+          process.exit(1);
+        }
+      }, 3600);
+    });
+  });
+}
+
+function testContentInputs() {
+  console.log("\n53. Testing tool inputs made of content items...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, MCP_MAX_CONTENT_INPUT_BYTES: "64" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  const blob = Buffer.from("%PDF-1.7 hello").toString("base64");
+  send(2, "tools/call", {
+    name: "process_document",
+    arguments: {
+      parts: [
+        { type: "text", text: "Summarize the attachment" },
+        { type: "blob", mimeType: "application/pdf", data: blob },
+      ],
+    },
+  });
+  send(3, "tools/call", {
+    name: "process_document",
+    arguments: { parts: [{ type: "blob", mimeType: "image/png", data: "not base64!" }] },
+  });
+  send(4, "tools/call", {
+    name: "process_document",
+    arguments: { parts: [{ type: "text", text: "x".repeat(65) }] },
+  });
+  send(5, "tools/list", {});
+
+  let contentResponse = "";
+  server.stdout.on("data", (data) => {
+    contentResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = contentResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const response = (id) => responses.find((message) => message.id === id) || {};
+    const failed = (id) =>
+      response(id).error || (response(id).result && response(id).result.isError);
+    const processed = response(2).result && response(2).result.structuredContent;
+    const attachment = processed && processed.attachments && processed.attachments[0];
+    const tools = response(5).result ? response(5).result.tools : [];
+    const tool = tools.find((t) => t.name === "process_document");
+    const parts = tool && tool.inputSchema.properties.parts;
+    const kinds = parts && parts.items && parts.items.oneOf
+      ? parts.items.oneOf.map((item) => item.properties.type.const)
+      : [];
+    if (
+      processed &&
+      processed.instructions.length === 1 &&
+      processed.instructions[0] === "Summarize the attachment" &&
+      processed.attachments.length === 1 &&
+      attachment.mime_type === "application/pdf" &&
+      attachment.bytes === 14 &&
+      attachment.preview === "%PDF-1.7 hello" &&
+      failed(3) &&
+      failed(4) &&
+      JSON.stringify(failed(4)).includes("more than the limit of 64") &&
+      kinds.includes("text") &&
+      kinds.includes("blob")
+    ) {
+      console.log("✓ A text part and a base64 blob reach the tool decoded");
+      console.log("✓ A blob that isn't base64 fails the call");
+      console.log("✓ Parts over the size limit are refused");
+      console.log("✓ The schema lists text and blob items");
+      testCallOrder();
+    } else {
+      console.error("Content input test failed:", contentResponse);
+      process.exit(1);
+    }
+  }, 500);
+}
+
+function testCallOrder() {
+  console.log("\n54. Testing tools that need another tool called first...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, MCP_CLIENT_ID: "test-admin", MCP_ADMIN_CLIENTS: "test-admin" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  const snapshot = { version: 1, exported_at: "2026-01-01T00:00:00Z", entries: [] };
+  const importCall = (id) =>
+    send(id, "tools/call", { name: "import_snapshot", arguments: { snapshot, mode: "merge" } });
+  importCall(2);
+  send(3, "tools/list", {});
+
+  let orderResponse = "";
+  let exported = false;
+  let importedAfter = false;
+  server.stdout.on("data", (data) => {
+    orderResponse += data.toString();
+    // Send each call once the one before it is answered, so they run in order
+    if (!exported && orderResponse.includes('"id":3')) {
+      exported = true;
+      send(4, "tools/call", { name: "export_snapshot", arguments: {} });
+    } else if (exported && !importedAfter && orderResponse.includes('"id":4')) {
+      importedAfter = true;
+      importCall(5);
+    }
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = orderResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const response = (id) => responses.find((message) => message.id === id) || {};
+    const rejected = response(2).error;
+    const tools = response(3).result ? response(3).result.tools : [];
+    const tool = tools.find((t) => t.name === "import_snapshot");
+    const requires = tool && tool.inputSchema["x-requires"];
+    const succeeded = (id) => response(id).result && !response(id).result.isError;
+    if (
+      rejected &&
+      rejected.data &&
+      rejected.data.error === "precondition_failed" &&
+      rejected.data.requires === "export_snapshot" &&
+      rejected.message.includes("needs a successful call of 'export_snapshot'") &&
+      requires &&
+      requires.includes("export_snapshot") &&
+      succeeded(4) &&
+      succeeded(5)
+    ) {
+      console.log("✓ Calling a tool before the one it needs is rejected");
+      console.log("✓ The schema names the tools to call first");
+      console.log("✓ Calling the tools in order works");
+      testRouterTool();
+    } else {
+      console.error("Call order test failed:", orderResponse);
+      process.exit(1);
+    }
+  }, 1000);
+}
+
+function testRouterTool() {
+  console.log("\n55. Testing every tool behind one router tool...");
+  const server = spawn(binaryPath, [], {
+    env: { ...process.env, MCP_ROUTER_TOOL: "1" },
+  });
+
+  const send = (id, method, params) =>
+    server.stdin.write(JSON.stringify({ jsonrpc: "2.0", id, method, params }) + "\n");
+  send(1, "initialize", {
+    protocolVersion: "0.1.0",
+    capabilities: {},
+    clientInfo: { name: "test-client", version: "0.1.0" },
+  });
+  server.stdin.write(
+    JSON.stringify({ jsonrpc: "2.0", method: "notifications/initialized" }) + "\n",
+  );
+  send(2, "tools/list", {});
+  send(3, "tools/call", {
+    name: "invoke",
+    arguments: { tool: "add_numbers", arguments: { a: 2, b: 3 } },
+  });
+  send(4, "tools/call", { name: "invoke", arguments: { tool: "no_such_tool" } });
+  send(5, "tools/call", { name: "invoke", arguments: { tool: "invoke" } });
+
+  let routerResponse = "";
+  server.stdout.on("data", (data) => {
+    routerResponse += data.toString();
+  });
+
+  setTimeout(() => {
+    server.kill();
+    const responses = routerResponse
+      .split("\n")
+      .filter((line) => line.trim().startsWith("{"))
+      .map((line) => JSON.parse(line));
+    const response = (id) => responses.find((message) => message.id === id) || {};
+    const tools = response(2).result ? response(2).result.tools : [];
+    const router = tools[0];
+    const names = router && router.inputSchema.properties.tool.enum;
+    const sum = response(3).result;
+    const text = sum && sum.content && sum.content[0] ? sum.content[0].text : "";
+    const failed = (id) =>
+      response(id).error || (response(id).result && response(id).result.isError);
+    if (
+      tools.length === 1 &&
+      router.name === "invoke" &&
+      names &&
+      names.includes("add_numbers") &&
+      !names.includes("invoke") &&
+      router.description.includes("add_numbers(a, b)") &&
+      sum &&
+      !sum.isError &&
+      text.includes("5") &&
+      failed(4) &&
+      failed(5)
+    ) {
+      console.log("✓ Only the router tool is listed, enumerating every tool");
+      console.log("✓ add_numbers called through the router returns its result");
+      console.log("✓ Unknown tools and calls of the router itself fail");
       console.log("\n✅ All integration tests passed!");
     } else {
-      console.error("Tool call test failed");
+      console.error("Router tool test failed:", routerResponse);
       process.exit(1);
     }
   }, 500);
@@ -0,0 +1,63 @@
+//! Heap allocations of the tool methods returning text
+//!
+//! Counts the allocations of `CALLS` calls of `get_version`, which returns a
+//! `&'static str`, and of `get_status`, which formats a `String`, with a
+//! counting global allocator. Only the tool methods are called, not the
+//! framework around them, which renders every result into a new `String`.
+//! Fails if `get_version` allocates at all.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use futures_util::FutureExt;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use template_mcp_server::TemplateMcpServer;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: defers to the system allocator, only counting the calls
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const CALLS: usize = 10_000;
+
+/// Allocations of `CALLS` calls of `tool`, whose futures finish at once
+fn allocations<T, Fut>(tool: impl Fn() -> Fut) -> usize
+where
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..CALLS {
+        let output = tool()
+            .now_or_never()
+            .expect("the tool doesn't wait")
+            .expect("the tool succeeds");
+        std::hint::black_box(output);
+    }
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let server = TemplateMcpServer::default();
+    let version = allocations(|| server.get_version());
+    let status = allocations(|| server.get_status());
+    println!("allocations per {CALLS} calls: get_version {version}, get_status {status}");
+    if version != 0 {
+        eprintln!("get_version allocated {version} times");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,178 @@
+//! Framework overhead of a tool call
+//!
+//! `add_numbers` does almost nothing, so the time of a call to it is the time
+//! the framework takes around a tool. The `stage` group splits one call into
+//! the steps of its path, each on its own:
+//!
+//! - `parse`: the request line into a JSON-RPC message and its call params
+//! - `validate`: the arguments against the tool's input schema
+//! - `invoke`: `TemplateBackend::call_tool`, with every check and result
+//!   handling the backend does around the tool
+//! - `tool`: the tool method alone, the part that isn't framework cost
+//! - `serialize`: the response into the line sent back
+//!
+//! The `call` group runs whole requests through the framework's request
+//! handler and serializes the response, with and without validating the
+//! arguments first, as `tools/validate` does, and with and without the
+//! authentication middleware. 0.11's middleware accepts every request once
+//! enabled, so `auth` measures what the stack adds around a call; time your
+//! own check by adding it there. `raw_message` is the whole STDIO path of
+//! [`RawMessageHandler`], bytes in and line out.
+//!
+//! Run with `cargo bench`, and compare with the committed numbers with
+//! `node scripts/check-bench.js`; see `baseline.json`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pulseengine_mcp_protocol::{CallToolRequestParam, Request, Response};
+use pulseengine_mcp_server::auth::{AuthConfig, AuthenticationManager};
+use pulseengine_mcp_server::{GenericServerHandler, McpBackend, MiddlewareStack};
+use pulseengine_mcp_transport::batch::JsonRpcMessage;
+use serde_json::{json, Value};
+use std::hint::black_box;
+use std::sync::Arc;
+use template_mcp_server::raw_messages::RawMessageHandler;
+use template_mcp_server::validation;
+use template_mcp_server::{TemplateBackend, TemplateMcpServer};
+use tokio::runtime::Runtime;
+
+const CALL: &str = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"add_numbers","arguments":{"a":2,"b":3}}}"#;
+
+const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"bench","version":"0"}}}"#;
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime starts")
+}
+
+// Not `TemplateBackend::default()`, which reads the environment
+fn backend() -> TemplateBackend {
+    TemplateBackend::new(TemplateMcpServer::default())
+}
+
+fn call_request() -> Request {
+    serde_json::from_str(CALL).expect("the call is a request")
+}
+
+fn call_params() -> CallToolRequestParam {
+    serde_json::from_value(call_request().params).expect("the call has tool call params")
+}
+
+fn input_schema(backend: &TemplateBackend) -> Value {
+    backend
+        .schema_catalog()
+        .get("add_numbers")
+        .expect("add_numbers is a tool")
+        .input_schema
+        .clone()
+}
+
+async fn handler(backend: TemplateBackend, auth: bool) -> GenericServerHandler<TemplateBackend> {
+    let mut config = AuthConfig::memory();
+    config.enabled = auth;
+    let manager = Arc::new(
+        AuthenticationManager::new(config)
+            .await
+            .expect("auth starts"),
+    );
+    let middleware = if auth {
+        MiddlewareStack::new().with_auth(manager.clone())
+    } else {
+        MiddlewareStack::new()
+    };
+    GenericServerHandler::new(Arc::new(backend), manager, middleware)
+}
+
+fn stages(c: &mut Criterion) {
+    let runtime = runtime();
+    let backend = backend();
+    let schema = input_schema(&backend);
+    let arguments = call_params().arguments.unwrap_or(Value::Null);
+    let result = runtime
+        .block_on(backend.call_tool(call_params()))
+        .expect("add_numbers succeeds");
+    let response = Response {
+        jsonrpc: "2.0".to_string(),
+        id: json!(1),
+        result: Some(serde_json::to_value(&result).expect("results are JSON")),
+        error: None,
+    };
+
+    let mut group = c.benchmark_group("stage");
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            let JsonRpcMessage::Single(message) =
+                JsonRpcMessage::parse(black_box(CALL)).expect("the call parses")
+            else {
+                unreachable!("the call isn't a batch")
+            };
+            let request: Request = serde_json::from_value(message).expect("the call is a request");
+            serde_json::from_value::<CallToolRequestParam>(request.params)
+                .expect("the call has tool call params")
+        })
+    });
+    group.bench_function("validate", |b| {
+        b.iter(|| validation::validate_arguments(black_box(&schema), black_box(&arguments)))
+    });
+    group.bench_function("invoke", |b| {
+        b.to_async(&runtime)
+            .iter(|| backend.call_tool(black_box(call_params())))
+    });
+    group.bench_function("tool", |b| {
+        b.to_async(&runtime)
+            .iter(|| backend.server().add_numbers(black_box(2.0), black_box(3.0)))
+    });
+    group.bench_function("serialize", |b| {
+        b.iter(|| serde_json::to_string(black_box(&response)))
+    });
+    group.finish();
+}
+
+fn calls(c: &mut Criterion) {
+    let runtime = runtime();
+    let backend = backend();
+    let schema = input_schema(&backend);
+
+    let mut group = c.benchmark_group("call");
+    for auth in [false, true] {
+        let handler = runtime.block_on(handler(backend.clone(), auth));
+        for validated in [false, true] {
+            let id = format!(
+                "{}/{}",
+                if validated {
+                    "validated"
+                } else {
+                    "unvalidated"
+                },
+                if auth { "auth" } else { "no_auth" }
+            );
+            group.bench_function(BenchmarkId::from_parameter(id), |b| {
+                b.to_async(&runtime).iter(|| async {
+                    let request = call_request();
+                    if validated {
+                        let errors =
+                            validation::validate_arguments(&schema, &request.params["arguments"]);
+                        assert!(errors.is_empty(), "the arguments are valid");
+                    }
+                    let response = handler.handle_request(request).await.expect("handled");
+                    serde_json::to_string(&response).expect("responses are JSON")
+                })
+            });
+        }
+    }
+    group.finish();
+
+    let raw = runtime
+        .block_on(RawMessageHandler::new(backend))
+        .expect("handler starts");
+    // Initialized up front, so the call reaches the backend
+    runtime.block_on(raw.handle(INITIALIZE.as_bytes()));
+    c.bench_function("raw_message", |b| {
+        b.to_async(&runtime)
+            .iter(|| raw.handle(black_box(CALL.as_bytes())))
+    });
+}
+
+criterion_group!(benches, stages, calls);
+criterion_main!(benches);
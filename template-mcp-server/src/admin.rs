@@ -0,0 +1,220 @@
+//! Tools and resources restricted to admin clients
+//!
+//! Tools with the `admin` setting (see [`crate::tool_settings`]) act on the
+//! whole server, such as exporting or replacing all stored data, so only
+//! clients named in `AccessConfig::admin_clients` may call them. Calls from other clients fail
+//! before the tool runs. Clients are identified as for quotas; see
+//! [`crate::quota`].
+//!
+//! Likewise, only admin clients may read the resources in
+//! [`ADMIN_RESOURCES`], which reveal internal settings. Reads by anonymous
+//! clients fail as unauthorized and reads by other clients as forbidden. With
+//! `AccessConfig::hide_admin_resources`, `resources/list` also leaves these
+//! resources out for clients that can't read them.
+//!
+//! A client reads its own `template://quota/{client}` resource, that of the
+//! identity its calls count against (see [`crate::quota`]); only admin
+//! clients read the quotas of others.
+
+use crate::quota::ANONYMOUS_CLIENT;
+use crate::{tool_settings, ServerConfig};
+
+pub fn is_admin_tool(tool: &str) -> bool {
+    tool_settings::settings_of(tool).admin
+}
+
+/// Resources only admin clients may read
+pub const ADMIN_RESOURCES: &[&str] = &["template://server-config"];
+
+/// Whether `uri` is an admin resource, with or without a query
+pub fn is_admin_resource(uri: &str) -> bool {
+    let path = uri.split_once('?').map_or(uri, |(path, _)| path);
+    ADMIN_RESOURCES.contains(&path)
+}
+
+/// Why a client may not read an admin resource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessDenied {
+    /// The client didn't identify itself
+    Unauthenticated {
+        uri: String,
+    },
+    NotAdmin {
+        uri: String,
+        client: String,
+    },
+}
+
+impl std::fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unauthenticated { uri } => write!(
+                f,
+                "Resource '{uri}' requires admin access; identify the client to read it"
+            ),
+            Self::NotAdmin { uri, client } => write!(
+                f,
+                "Resource '{uri}' requires admin access, which client '{client}' doesn't have"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AccessDenied {}
+
+impl From<AccessDenied> for pulseengine_mcp_protocol::Error {
+    fn from(denied: AccessDenied) -> Self {
+        match denied {
+            AccessDenied::Unauthenticated { .. } => Self::unauthorized(denied.to_string()),
+            AccessDenied::NotAdmin { .. } => Self::forbidden(denied.to_string()),
+        }
+    }
+}
+
+/// Path of the quota resources, ending in the client
+const QUOTA_RESOURCE_PREFIX: &str = "template://quota/";
+
+fn is_admin(client: &str, config: &ServerConfig) -> bool {
+    config
+        .access
+        .admin_clients
+        .iter()
+        .any(|admin| admin == client)
+}
+
+/// Reject a read of another client's quota by a client that isn't an admin
+///
+/// `caller` is the client whose quota the reader's calls count against, and
+/// `client` the one checked for admin access, as for admin resources.
+pub fn check_quota_access(
+    uri: &str,
+    caller: &str,
+    client: &str,
+    config: &ServerConfig,
+) -> Result<(), AccessDenied> {
+    let path = uri.split_once('?').map_or(uri, |(path, _)| path);
+    match path.strip_prefix(QUOTA_RESOURCE_PREFIX) {
+        Some(owner) if owner != caller && !is_admin(client, config) => {
+            Err(AccessDenied::NotAdmin {
+                uri: uri.to_string(),
+                client: caller.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reject a read of an admin resource by a client that isn't an admin
+pub fn check_resource_access(
+    uri: &str,
+    client: &str,
+    config: &ServerConfig,
+) -> Result<(), AccessDenied> {
+    if !is_admin_resource(uri) {
+        return Ok(());
+    }
+    if client == ANONYMOUS_CLIENT {
+        return Err(AccessDenied::Unauthenticated {
+            uri: uri.to_string(),
+        });
+    }
+    if is_admin(client, config) {
+        return Ok(());
+    }
+    Err(AccessDenied::NotAdmin {
+        uri: uri.to_string(),
+        client: client.to_string(),
+    })
+}
+
+/// Reject a call to an admin tool from a client that isn't an admin
+pub fn check_access(tool: &str, client: &str, config: &ServerConfig) -> Result<(), String> {
+    if !is_admin_tool(tool) || is_admin(client, config) {
+        return Ok(());
+    }
+    Err(format!(
+        "Tool '{tool}' requires admin access, which client '{client}' doesn't have"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulseengine_mcp_protocol::Error;
+
+    fn with_admin(client: &str) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.access.admin_clients = vec![client.to_string()];
+        config
+    }
+
+    #[test]
+    fn only_admins_call_admin_tools() {
+        let config = with_admin("ops");
+        assert!(is_admin_tool("export_snapshot"));
+        assert_eq!(check_access("export_snapshot", "ops", &config), Ok(()));
+        assert_eq!(
+            check_access("export_snapshot", "agent", &config),
+            Err(
+                "Tool 'export_snapshot' requires admin access, which client 'agent' doesn't have"
+                    .to_string()
+            )
+        );
+        assert!(!is_admin_tool("echo"));
+        assert_eq!(check_access("echo", "agent", &config), Ok(()));
+    }
+
+    #[test]
+    fn admin_resources_match_with_a_query() {
+        assert!(is_admin_resource("template://server-config"));
+        assert!(is_admin_resource("template://server-config?format=json"));
+        assert!(!is_admin_resource("template://server-status"));
+    }
+
+    #[test]
+    fn denies_resource_reads_by_client() {
+        let config = with_admin("ops");
+        let uri = "template://server-config";
+        assert_eq!(check_resource_access(uri, "ops", &config), Ok(()));
+        assert_eq!(
+            check_resource_access("template://server-status", ANONYMOUS_CLIENT, &config),
+            Ok(())
+        );
+
+        let anonymous = check_resource_access(uri, ANONYMOUS_CLIENT, &config).unwrap_err();
+        assert_eq!(
+            anonymous,
+            AccessDenied::Unauthenticated {
+                uri: uri.to_string()
+            }
+        );
+        let error = Error::from(anonymous);
+        assert_eq!(error.code, Error::unauthorized("").code);
+
+        let other = check_resource_access(uri, "agent", &config).unwrap_err();
+        assert_eq!(
+            other.to_string(),
+            "Resource 'template://server-config' requires admin access, which client 'agent' doesn't have"
+        );
+        assert_eq!(Error::from(other).code, Error::forbidden("").code);
+    }
+
+    #[test]
+    fn clients_read_only_their_own_quota() {
+        let config = with_admin("ops");
+        let alice = "template://quota/alice";
+        assert_eq!(check_quota_access(alice, "alice", "alice", &config), Ok(()));
+        assert_eq!(check_quota_access(alice, "bob", "ops", &config), Ok(()));
+        assert_eq!(
+            check_quota_access("template://server-status", "bob", "bob", &config),
+            Ok(())
+        );
+
+        let denied = check_quota_access(alice, "bob", "bob", &config).unwrap_err();
+        assert_eq!(
+            denied.to_string(),
+            "Resource 'template://quota/alice' requires admin access, which client 'bob' doesn't have"
+        );
+        assert!(check_quota_access("template://quota/alice?x=1", "bob", "bob", &config).is_err());
+    }
+}
@@ -0,0 +1,1699 @@
+//! Backend that serves the template's tools with framework extensions
+//!
+//! `#[mcp_server]` generates a complete `McpBackend` for `TemplateMcpServer`.
+//! `TemplateBackend` wraps that generated backend and delegates to it, adding
+//! the behavior the macros don't cover yet, such as filling tool parameters
+//! from configuration and recording sessions. `main.rs` serves this wrapper.
+//!
+//! Every tool call counts against the client's daily quota; see [`crate::quota`].
+//! Destructive tools run only once the client confirms the call; see
+//! [`crate::confirmation`]. Admin tools and resources are limited to admin
+//! clients; see [`crate::admin`]. Tools needing a client capability are only
+//! served to clients that advertised it; see [`crate::client_capabilities`].
+//! Tools needing another tool called first fail until it was; see
+//! [`crate::call_order`].
+//! Deployments can serve a subset of the tools; see [`crate::tool_filter`].
+//! Safe mode turns off destructive tools; see [`crate::safe_mode`].
+//! Clients that load few tools can get all of them behind one; see
+//! [`crate::router_tool`].
+//! Deprecated tools are removed on their sunset date; see [`crate::sunset`].
+//! Clients are told when result resources come and go; see
+//! [`crate::resource_list`].
+//!
+//! Tools are served from a [`ToolRegistry`] seeded with the macro-generated
+//! tools and the operation tools, so tools from other crates can be registered
+//! alongside them. Their decorated schemas are listed from a cached catalog;
+//! see [`crate::schema_catalog`].
+
+use crate::banner::{self, StartupSummary};
+use crate::byte_ranges::{self, RangeReadRequest, RangeReadResult};
+use crate::call_order::{self, CallHistory, MissingPriorCall};
+use crate::client_capabilities::{ClientCapabilities, MissingCapability};
+use crate::client_notifications::{ClientNotifier, LogLevel};
+use crate::costs::{self, CostReport};
+use crate::etag::{self, ConditionalReadRequest, ConditionalReadResult};
+use crate::file_resources::{self, FileResourceProvider, FILE_RESOURCES_DIR_ENV_VAR};
+use crate::health::HealthStatus;
+use crate::html::{self, BasicHtmlSanitizer, HtmlSanitizer};
+use crate::http_proxy::{self, HttpProxyResource, HTTP_PROXY_URL_ENV_VAR};
+use crate::playground;
+use crate::post_processing::{self, ResponsePostProcessor};
+use crate::quota::{self, ANONYMOUS_CLIENT, CLIENT_ID_ENV_VAR};
+use crate::raw_messages::RawMessageHandler;
+use crate::recording::{RecordedExchange, SessionRecorder};
+use crate::registry::{provider_tools, Tool, ToolRegistry};
+use crate::request_context::{RequestContext, Stopped};
+use crate::resource_list::ResourceListChanges;
+use crate::resource_queries;
+use crate::resource_wait::{self, WaitResourceRequest, WaitResourceResult};
+use crate::response_size;
+use crate::result_resources::{self, ResourceLink};
+use crate::roots::{self, RootsSource};
+use crate::router_tool;
+use crate::safe_mode;
+use crate::schema_catalog::{SchemaCatalog, SchemaCatalogCache};
+use crate::schema_defs;
+use crate::schema_resources;
+use crate::sunset::{self, GoneTool};
+use crate::tool_filter::{self, FilteredTool};
+use crate::uploads;
+use crate::validation::{self, ValidationError, ValidationResult};
+use crate::{
+    admin, binary_resources, blocking, catalog, config_defaults, confirmation, decimal,
+    default_empty, effects, finite, logs, ndjson, operations, pagination, panics, param_examples,
+    param_groups, prompts, strict_arguments, structured, table, timeouts, tool_errors,
+    tool_settings, tool_versions, unit_result, units, warnings, whoami, ServerConfig,
+    TemplateMcpServer,
+};
+use async_trait::async_trait;
+use pulseengine_mcp_protocol::{
+    CallToolRequestParam, CallToolResult, GetPromptRequestParam, GetPromptResult,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+    ReadResourceRequestParam, ReadResourceResult, Resource, ServerInfo, Tool as ToolDefinition,
+};
+use pulseengine_mcp_server::auth::AuthContext;
+use pulseengine_mcp_server::{
+    BackendError, CommonMcpError, McpBackend, McpServer, McpServerBuilder, TransportConfig,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Environment variable with an address to also serve HTTP on, e.g.
+/// `127.0.0.1:8080`
+pub const HTTP_ADDR_ENV_VAR: &str = "MCP_HTTP_ADDR";
+
+/// How [`TemplateBackend::serve`] authenticates clients
+const AUTH_MODE: &str = "disabled";
+
+/// Error of a [`TemplateBackend`] request
+///
+/// Tool errors with a payload keep it; see [`crate::tool_errors`].
+#[derive(Debug)]
+pub enum TemplateError {
+    Mcp(CommonMcpError),
+    Tool(tool_errors::ToolErrorData),
+    /// A client read an admin resource it may not read
+    Denied(admin::AccessDenied),
+    /// A client called a tool needing a capability it didn't advertise
+    Precondition(MissingCapability),
+    /// A client called a tool before a tool it needs
+    OutOfOrder(MissingPriorCall),
+    /// A client called a tool this deployment doesn't serve
+    Filtered(FilteredTool),
+    /// A client called a tool after its sunset
+    Gone(GoneTool),
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mcp(e) => e.fmt(f),
+            Self::Tool(data) => write!(f, "Invalid parameters: {}", data.message),
+            Self::Denied(denied) => denied.fmt(f),
+            Self::Precondition(missing) => missing.fmt(f),
+            Self::OutOfOrder(missing) => missing.fmt(f),
+            Self::Filtered(filtered) => filtered.fmt(f),
+            Self::Gone(gone) => gone.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<CommonMcpError> for TemplateError {
+    fn from(error: CommonMcpError) -> Self {
+        Self::Mcp(error)
+    }
+}
+
+impl From<BackendError> for TemplateError {
+    fn from(error: BackendError) -> Self {
+        Self::Mcp(error.into())
+    }
+}
+
+impl From<TemplateError> for pulseengine_mcp_protocol::Error {
+    fn from(error: TemplateError) -> Self {
+        match error {
+            TemplateError::Mcp(e) => e.into(),
+            TemplateError::Tool(data) => Self::with_data(
+                pulseengine_mcp_protocol::ErrorCode::InvalidParams,
+                data.message.clone(),
+                data.data(),
+            ),
+            TemplateError::Denied(denied) => denied.into(),
+            TemplateError::Precondition(missing) => missing.into(),
+            TemplateError::OutOfOrder(missing) => missing.into(),
+            TemplateError::Filtered(filtered) => filtered.into(),
+            TemplateError::Gone(gone) => gone.into(),
+        }
+    }
+}
+
+/// MCP backend wrapping the macro-generated `TemplateMcpServer` backend
+#[derive(Clone)]
+pub struct TemplateBackend {
+    server: TemplateMcpServer,
+    tools: Arc<ToolRegistry>,
+    schema_catalog: Arc<SchemaCatalogCache>,
+    recorder: Option<Arc<SessionRecorder>>,
+    client_id: String,
+    /// The client the quota is counted against, set with authentication
+    quota_client: Option<String>,
+    client_capabilities: ClientCapabilities,
+    call_history: CallHistory,
+    notifier: ClientNotifier,
+    resource_list: ResourceListChanges,
+    file_resources: Vec<FileResourceProvider>,
+    http_proxies: Vec<HttpProxyResource>,
+    schema_resources: bool,
+    post_processors: Vec<Arc<dyn ResponsePostProcessor>>,
+    html_sanitizer: Arc<dyn HtmlSanitizer>,
+    banner: bool,
+    max_wait_ms: u64,
+    #[cfg(feature = "otel")]
+    tracer: Option<crate::otel::Tracer>,
+}
+
+impl Default for TemplateBackend {
+    /// Default server, recording the session if `MCP_RECORD` is set,
+    /// identifying the client by `MCP_CLIENT_ID`, loading plugins from
+    /// `MCP_PLUGIN_DIR`, serving the files in `MCP_FILE_RESOURCES_DIR`, proxying
+    /// to `MCP_HTTP_PROXY_URL`, serving the schema resources if `MCP_SCHEMA_RESOURCES` is set and, with the
+    /// `otel` feature, exporting spans to `OTEL_EXPORTER_OTLP_ENDPOINT`
+    fn default() -> Self {
+        Self::for_server(TemplateMcpServer::default())
+    }
+}
+
+impl TemplateBackend {
+    /// Backend for `server`, set up from the environment like the default one
+    pub fn for_server(server: TemplateMcpServer) -> Self {
+        let mut backend = Self::new(server);
+        if let Some(dir) = std::env::var_os(crate::plugins::PLUGIN_DIR_ENV_VAR) {
+            // SAFETY: the plugin directory is set by the operator, who vouches
+            // for its libraries like for the server binary
+            backend.register_plugins(unsafe { crate::plugins::load_dir(dir.as_ref()) });
+        }
+        if std::env::var_os(schema_resources::SCHEMA_RESOURCES_ENV_VAR).is_some() {
+            backend = backend.with_schema_resources();
+        }
+        if let Some(root) = std::env::var_os(FILE_RESOURCES_DIR_ENV_VAR) {
+            match FileResourceProvider::new(root, file_resources::DEFAULT_URI_TEMPLATE) {
+                Ok(provider) => backend = backend.with_file_resources(provider),
+                Err(e) => tracing::warn!("Not serving file resources: {e}"),
+            }
+        }
+        if let Ok(base_url) = std::env::var(HTTP_PROXY_URL_ENV_VAR) {
+            match HttpProxyResource::new(&base_url, http_proxy::DEFAULT_URI_TEMPLATE) {
+                Ok(proxy) => backend = backend.with_http_proxy(proxy),
+                Err(e) => tracing::warn!("Not proxying resources: {e}"),
+            }
+        }
+        #[cfg(feature = "otel")]
+        match crate::otel::Tracer::from_env() {
+            Some(Ok(tracer)) => backend = backend.with_tracer(tracer),
+            Some(Err(e)) => tracing::warn!("Not exporting spans: {e}"),
+            None => {}
+        }
+        if let Ok(client_id) = std::env::var(CLIENT_ID_ENV_VAR) {
+            backend = backend.with_client_id(client_id);
+        }
+        match SessionRecorder::from_env() {
+            Some(recorder) => backend.with_recorder(recorder),
+            None => backend,
+        }
+    }
+
+    pub fn new(server: TemplateMcpServer) -> Self {
+        let tools = ToolRegistry::new();
+        tools
+            .register_all(tool_versions::apply(provider_tools(server.clone())))
+            .and_then(|()| tools.register_all(operations::tools(server.operations().clone())))
+            .and_then(|()| tools.register_all(uploads::tools(server.clone())))
+            .and_then(|()| {
+                if server.config().access.whoami_tool {
+                    tools.register(whoami::tool(server.clone()))
+                } else {
+                    Ok(())
+                }
+            })
+            .expect("built-in tool names are unique");
+
+        let backend = Self {
+            server,
+            tools: Arc::new(tools),
+            schema_catalog: Arc::new(SchemaCatalogCache::default()),
+            recorder: None,
+            client_id: ANONYMOUS_CLIENT.to_string(),
+            quota_client: None,
+            client_capabilities: ClientCapabilities::new(),
+            call_history: CallHistory::new(),
+            notifier: ClientNotifier::new(),
+            resource_list: ResourceListChanges::default(),
+            file_resources: Vec::new(),
+            http_proxies: Vec::new(),
+            schema_resources: false,
+            post_processors: Vec::new(),
+            html_sanitizer: Arc::new(BasicHtmlSanitizer),
+            banner: true,
+            max_wait_ms: resource_wait::MAX_TIMEOUT_MS,
+            #[cfg(feature = "otel")]
+            tracer: None,
+        };
+        backend.schema_catalog();
+        backend
+    }
+
+    /// Serve the files of `provider` as resources
+    pub fn with_file_resources(mut self, provider: FileResourceProvider) -> Self {
+        self.file_resources.push(provider);
+        self
+    }
+
+    /// Serve the upstream of `proxy` as resources
+    pub fn with_http_proxy(mut self, proxy: HttpProxyResource) -> Self {
+        self.http_proxies.push(proxy);
+        self
+    }
+
+    /// Serve the tool catalog and OpenAPI document as resources; see
+    /// [`schema_resources`]
+    pub fn with_schema_resources(mut self) -> Self {
+        self.schema_resources = true;
+        self
+    }
+
+    /// Rewrite every tool result and resource read with `processor`, after
+    /// the processors registered before it; see [`post_processing`]
+    pub fn with_post_processor(mut self, processor: impl ResponsePostProcessor + 'static) -> Self {
+        self.post_processors.push(Arc::new(processor));
+        self
+    }
+
+    /// Sanitize HTML tool results with `sanitizer` instead of the
+    /// [`BasicHtmlSanitizer`] when `ToolsConfig::sanitize_html` is set; see
+    /// [`crate::html`]
+    pub fn with_html_sanitizer(mut self, sanitizer: impl HtmlSanitizer + 'static) -> Self {
+        self.html_sanitizer = Arc::new(sanitizer);
+        self
+    }
+
+    /// Let `resources/wait` block for at most `max_wait_ms`; see
+    /// [`resource_wait`]
+    pub fn with_max_wait_ms(mut self, max_wait_ms: u64) -> Self {
+        self.max_wait_ms = max_wait_ms;
+        self
+    }
+
+    /// Don't log the startup banner; see [`crate::banner`]
+    pub fn without_banner(mut self) -> Self {
+        self.banner = false;
+        self
+    }
+
+    /// Create a span for every tool call; see [`crate::otel`]
+    #[cfg(feature = "otel")]
+    pub fn with_tracer(mut self, tracer: crate::otel::Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Identify the connected client, e.g. for its call quota
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Count calls against the identity `context` authenticated, whatever
+    /// the client id; see [`crate::quota`]
+    pub fn with_auth_context(mut self, context: &AuthContext) -> Self {
+        self.quota_client = Some(quota::authenticated_client(context).to_string());
+        self
+    }
+
+    /// The client whose quota calls count against
+    pub fn quota_client(&self) -> &str {
+        self.quota_client.as_deref().unwrap_or(&self.client_id)
+    }
+
+    /// Hold back tools needing capabilities the client behind `capabilities`
+    /// lacks; see [`crate::client_capabilities`]
+    pub fn with_client_capabilities(mut self, capabilities: ClientCapabilities) -> Self {
+        self.client_capabilities = capabilities;
+        self
+    }
+
+    /// Check the order of tool calls against `history`; see
+    /// [`crate::call_order`]
+    pub fn with_call_history(mut self, history: CallHistory) -> Self {
+        self.call_history = history;
+        self
+    }
+
+    /// Send the notifications of tools, such as their warnings, and of
+    /// resource list changes through `notifier`; see
+    /// [`crate::client_notifications`]
+    pub fn with_notifier(mut self, notifier: ClientNotifier) -> Self {
+        self.resource_list = ResourceListChanges::new(notifier.clone());
+        self.notifier = notifier;
+        self
+    }
+
+    /// Fetch the client's roots from `source`; see [`crate::roots`]
+    pub fn with_roots_source(self, source: Arc<dyn RootsSource>) -> Self {
+        self.server.roots().set_source(source);
+        self
+    }
+
+    /// Register an additional tool, e.g. one defined in another crate
+    pub fn register_tool(&self, tool: Box<dyn Tool>) -> anyhow::Result<()> {
+        self.tools.register(tool)
+    }
+
+    /// Register the tools of loaded plugins, skipping names already taken
+    fn register_plugins(&self, plugins: anyhow::Result<Vec<crate::plugins::LoadedPlugin>>) {
+        let plugins = match plugins {
+            Ok(plugins) => plugins,
+            Err(e) => {
+                tracing::warn!("Failed to load plugins: {e}");
+                return;
+            }
+        };
+        for plugin in plugins {
+            for tool in plugin.tools {
+                if let Err(e) = self.register_tool(tool) {
+                    tracing::warn!("Skipping tool from plugin {}: {e}", plugin.path.display());
+                }
+            }
+        }
+    }
+
+    /// All tools served by this backend
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    /// Check [`tool_settings::TOOL_SETTINGS`] against the tools this backend
+    /// serves, so a setting for a misspelled tool fails instead of being
+    /// ignored
+    pub fn check_tool_settings(&self) -> Result<(), tool_settings::SettingsError> {
+        tool_settings::check(&self.tools.definitions())
+    }
+
+    /// Record every handled request and its outcome
+    pub fn with_recorder(mut self, recorder: SessionRecorder) -> Self {
+        self.recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    /// The wrapped server, for direct access to its state and tools
+    pub fn server(&self) -> &TemplateMcpServer {
+        &self.server
+    }
+
+    /// Log to stderr like `configure_stdio_logging`, and also keep recent
+    /// entries for the logs resource; see [`logs`]
+    pub fn configure_logging() {
+        use tracing_subscriber::layer::SubscriberExt;
+        use tracing_subscriber::util::SubscriberInitExt;
+
+        let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+        let stderr = tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_target(false);
+        // Fails if logging was already set up, which then stays as it was
+        let _ = tracing_subscriber::registry()
+            .with(filter)
+            .with(stderr)
+            .with(logs::LogCapture)
+            .try_init();
+    }
+
+    /// Create an MCP server for this backend using the STDIO transport
+    ///
+    /// Mirrors the `serve_stdio` generated by `#[mcp_server]`; keep the auth
+    /// setup in sync with the `auth` attribute on `TemplateMcpServer`.
+    pub async fn serve_stdio(self) -> Result<McpServer<Self>, CommonMcpError> {
+        self.serve(TransportConfig::Stdio).await
+    }
+
+    /// Create an MCP server for this backend using `transport`
+    ///
+    /// Over a transport other than STDIO, whose clients the backend can't
+    /// tell apart, tools that need an earlier call are refused; see
+    /// [`call_order`].
+    pub async fn serve(
+        self,
+        transport: TransportConfig,
+    ) -> Result<McpServer<Self>, CommonMcpError> {
+        let backend = match transport {
+            TransportConfig::Stdio => self,
+            _ => self.with_call_history(CallHistory::shared()),
+        };
+        let mut config = pulseengine_mcp_server::ServerConfig {
+            server_info: backend.get_server_info(),
+            transport_config: transport,
+            ..Default::default()
+        };
+
+        // Keep AUTH_MODE and RawMessageHandler::new in sync with this
+        let mut auth_config = pulseengine_mcp_server::auth::AuthConfig::memory();
+        auth_config.enabled = false;
+        config.auth_config = auth_config;
+
+        McpServer::new(backend, config)
+            .await
+            .map_err(|e| CommonMcpError::Internal(format!("Failed to create server: {e}")))
+    }
+
+    /// What serving over `transports` serves, for the startup banner
+    pub async fn startup_summary(
+        &self,
+        transports: &[TransportConfig],
+    ) -> Result<StartupSummary, CommonMcpError> {
+        let info = self.get_server_info();
+        let prompts = self
+            .server
+            .list_prompts(PaginatedRequestParam { cursor: None })
+            .await?;
+        Ok(StartupSummary {
+            name: info.server_info.name,
+            version: info.server_info.version,
+            transports: transports.iter().map(banner::describe_transport).collect(),
+            auth: AUTH_MODE,
+            safe_mode: self.server.config().access.safe_mode,
+            tools: self.tool_definitions().len(),
+            resources: self.resources().await?.len(),
+            prompts: prompts.prompts.len(),
+        })
+    }
+
+    /// STDIO, plus HTTP on `MCP_HTTP_ADDR` if it is set
+    pub fn transports_from_env() -> anyhow::Result<Vec<TransportConfig>> {
+        let mut transports = vec![TransportConfig::Stdio];
+        if let Ok(addr) = std::env::var(HTTP_ADDR_ENV_VAR) {
+            let addr: std::net::SocketAddr = addr
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid {HTTP_ADDR_ENV_VAR} '{addr}': {e}"))?;
+            transports.push(TransportConfig::Http {
+                port: addr.port(),
+                host: Some(addr.ip().to_string()),
+            });
+        }
+        Ok(transports)
+    }
+
+    /// Serve this backend over every transport in `transports` at once
+    ///
+    /// Each transport gets its own MCP server around a clone of this backend,
+    /// and clones share all server state, so data created over one transport
+    /// is visible over the others. STDIO is served by a [`RawMessageHandler`]
+    /// instead, which negotiates the protocol version with older clients, and
+    /// stops once stdin is closed. Runs until Ctrl+C stops all of them, or
+    /// until one fails, which stops the rest.
+    ///
+    /// The STDIO client has a session of its own: its call history and client
+    /// capabilities. The 0.11 HTTP transport doesn't tell the backend which
+    /// client sent a request, so every HTTP client shares one connection's
+    /// state, that of this backend, and nothing recorded per client over HTTP
+    /// tells one client apart from another.
+    pub async fn serve_all(self, transports: Vec<TransportConfig>) -> Result<(), CommonMcpError> {
+        self.serve_all_with_stdio(transports, tokio::io::stdin(), tokio::io::stdout())
+            .await
+    }
+
+    /// [`serve_all`](Self::serve_all), reading the STDIO client's lines from
+    /// `input` and answering on `output`
+    async fn serve_all_with_stdio<R, W>(
+        self,
+        transports: Vec<TransportConfig>,
+        input: R,
+        output: W,
+    ) -> Result<(), CommonMcpError>
+    where
+        R: tokio::io::AsyncRead + Unpin + Send + 'static,
+        W: tokio::io::AsyncWrite + Unpin + Send + 'static,
+    {
+        if self.banner {
+            self.startup_summary(&transports).await?.log();
+        }
+        let mut servers = Vec::with_capacity(transports.len());
+        let mut stdio = None;
+        for transport in transports {
+            match transport {
+                TransportConfig::Stdio => {
+                    let handler = RawMessageHandler::new(self.clone()).await.map_err(|e| {
+                        CommonMcpError::Internal(format!("Failed to create server: {e}"))
+                    })?;
+                    stdio = Some(handler);
+                }
+                transport => servers.push(self.clone().serve(transport).await?),
+            }
+        }
+
+        let mut running = tokio::task::JoinSet::new();
+        for mut server in servers {
+            running.spawn(async move { server.run().await.map_err(|e| e.to_string()) });
+        }
+        if let Some(handler) = stdio {
+            running.spawn(async move {
+                handler
+                    .serve(input, output)
+                    .await
+                    .map_err(|e| e.to_string())
+            });
+        }
+        let first_failure = async {
+            while let Some(finished) = running.join_next().await {
+                match finished {
+                    Ok(Ok(())) => continue,
+                    Ok(Err(e)) => return Err(format!("Server failed: {e}")),
+                    Err(e) => return Err(format!("Server task failed: {e}")),
+                }
+            }
+            Ok(())
+        };
+        // The STDIO transport only notices Ctrl+C once stdin has a line to
+        // read, so the servers are stopped here instead
+        let outcome = tokio::select! {
+            outcome = first_failure => outcome,
+            _ = tokio::signal::ctrl_c() => Ok(()),
+        };
+        running.abort_all();
+        outcome.map_err(CommonMcpError::Internal)
+    }
+
+    /// Dispatch an MCP method by name with JSON params
+    ///
+    /// Used to drive the backend in-process, e.g. when replaying a recording.
+    pub async fn dispatch(&self, method: &str, params: Value) -> Result<Value, TemplateError> {
+        let params = match params {
+            Value::Null => Value::Object(Default::default()),
+            params => params,
+        };
+
+        match method {
+            "tools/list" => to_value(self.list_tools(parse(params)?).await?),
+            "tools/call" => to_value(self.call_tool(parse(params)?).await?),
+            "resources/list" => to_value(self.list_resources(parse(params)?).await?),
+            "resources/read" => to_value(self.read_resource(parse(params)?).await?),
+            "prompts/list" => to_value(self.list_prompts(parse(params)?).await?),
+            "prompts/get" => to_value(self.get_prompt(parse(params)?).await?),
+            _ => self.handle_custom_method(method, params).await,
+        }
+    }
+
+    /// Read a resource, optionally waiting for it to change
+    ///
+    /// Served as the `resources/wait` method; see [`resource_wait`].
+    pub async fn wait_resource(
+        &self,
+        request: WaitResourceRequest,
+    ) -> Result<WaitResourceResult, TemplateError> {
+        let result = resource_wait::wait_for_change(
+            &request,
+            self.server.resource_changes(),
+            self.max_wait_ms,
+            || self.read_for_client(&request.uri),
+        )
+        .await;
+        self.record(resource_wait::WAIT_METHOD, &request, &result);
+        result
+    }
+
+    /// Read a resource unless it still matches the client's ETags
+    ///
+    /// Served as the `resources/read_conditional` method; see [`etag`].
+    pub async fn read_resource_conditional(
+        &self,
+        request: ConditionalReadRequest,
+    ) -> Result<ConditionalReadResult, TemplateError> {
+        let result = self
+            .read_for_client(&request.uri)
+            .await
+            .map(|result| etag::respond(&request, result));
+        self.record(etag::CONDITIONAL_READ_METHOD, &request, &result);
+        result
+    }
+
+    /// Read the bytes of a resource in a range
+    ///
+    /// Served as the `resources/read_range` method; see [`byte_ranges`].
+    pub async fn read_resource_range(
+        &self,
+        request: RangeReadRequest,
+    ) -> Result<RangeReadResult, TemplateError> {
+        let result = self.read_for_client(&request.uri).await.and_then(|result| {
+            byte_ranges::slice(result, request.range)
+                .map_err(|e| CommonMcpError::InvalidParams(e).into())
+        });
+        self.record(byte_ranges::RANGE_READ_METHOD, &request, &result);
+        result
+    }
+
+    /// Check a tool call's arguments without running the tool
+    ///
+    /// Served as the `tools/validate` method; see [`validation`]. Doesn't
+    /// count against the client's quota.
+    pub async fn validate_tool_call(
+        &self,
+        mut request: CallToolRequestParam,
+    ) -> Result<ValidationResult, CommonMcpError> {
+        let config = self.server.config();
+        if request.name == router_tool::ROUTER_TOOL && config.tools.router_tool {
+            request = router_tool::routed_call(request.arguments.as_ref())
+                .map_err(|e| CommonMcpError::InvalidParams(e.message))?;
+        }
+        let access = self
+            .check_access(&request.name, &config)
+            .map_err(|refused| refused.to_string());
+        let tool = self
+            .tools
+            .get(&request.name)
+            .filter(|_| tool_filter::is_enabled(&request.name, &config))
+            .filter(|_| !sunset::is_gone(&request.name, self.server.clock().now()));
+        let result = match (tool, access) {
+            (Some(_), Err(message)) => Err(CommonMcpError::InvalidParams(message)),
+            (Some(tool), Ok(())) => {
+                let mut definition = tool.definition();
+                config_defaults::apply_to_schema(&mut definition, &config);
+                confirmation::apply_to_schema(&mut definition);
+                default_empty::apply_to_schema(&mut definition);
+                strict_arguments::apply_to_schema(&mut definition, &config);
+                config_defaults::apply_to_arguments(&request.name, &mut request.arguments, &config);
+                default_empty::apply_to_arguments(&request.name, &mut request.arguments);
+                let converted = units::apply_to_arguments(&request.name, &mut request.arguments);
+
+                let arguments = request.arguments.clone().unwrap_or(Value::Null);
+                // The unit, non-finite and decimal checks explain bad numbers
+                // better than the type check, and the group check runs with
+                // them since the validator doesn't read `oneOf`
+                let errors = match converted
+                    .and_then(|()| finite::check_arguments(&definition, Some(&arguments)))
+                    .and_then(|()| decimal::check_arguments(&definition, Some(&arguments)))
+                    .and_then(|()| param_groups::check_arguments(&request.name, Some(&arguments)))
+                {
+                    Ok(()) => validation::validate_arguments(&definition.input_schema, &arguments),
+                    Err(message) => vec![ValidationError {
+                        path: "arguments".to_string(),
+                        message,
+                    }],
+                };
+                let timeout = timeouts::timeout_for(
+                    &request.name,
+                    Some(&arguments),
+                    Duration::from_secs(config.timeout_seconds),
+                );
+                Ok(ValidationResult::new(arguments, errors, timeout))
+            }
+            (None, _) => Err(CommonMcpError::InvalidParams(format!(
+                "Unknown tool: {}",
+                request.name
+            ))),
+        };
+        self.record(validation::VALIDATE_METHOD, &request, &result);
+        result
+    }
+
+    /// Whether this client may call `tool` now: admin access, effects, safe
+    /// mode, client capabilities and call order
+    ///
+    /// Both `tools/call` and `tools/validate` check this, so a call that
+    /// validates isn't refused for access when it runs.
+    fn check_access(&self, tool: &str, config: &ServerConfig) -> Result<(), TemplateError> {
+        admin::check_access(tool, &self.client_id, config)
+            .and_then(|()| effects::check_access(tool, config))
+            .and_then(|()| safe_mode::check_access(tool, config))
+            .map_err(|message| CommonMcpError::InvalidParams(message).into())
+            .and_then(|()| {
+                self.client_capabilities
+                    .check(tool)
+                    .map_err(TemplateError::Precondition)
+            })
+            .and_then(|()| {
+                self.call_history
+                    .check(tool)
+                    .map_err(TemplateError::OutOfOrder)
+            })
+    }
+
+    /// Handle `tools/call`: access, confirmation, argument handling, quota,
+    /// the call itself, result handling, post-processors and call statistics
+    async fn run_tool_call(
+        &self,
+        mut request: CallToolRequestParam,
+    ) -> Result<CallToolResult, TemplateError> {
+        if request.name == router_tool::ROUTER_TOOL && self.server.config().tools.router_tool {
+            match router_tool::routed_call(request.arguments.as_ref()) {
+                Ok(call) => request = call,
+                Err(e) => {
+                    let result = Err(CommonMcpError::InvalidParams(e.message).into());
+                    self.record("tools/call", &request, &result);
+                    return result;
+                }
+            }
+        }
+        if request.name == uploads::FINALIZE_TOOL {
+            match uploads::finalize_call(self.server.uploads(), request.arguments.as_ref()) {
+                Ok(call) => request = call,
+                Err(e) => {
+                    let result = Err(CommonMcpError::InvalidParams(e.message).into());
+                    self.record("tools/call", &request, &result);
+                    return result;
+                }
+            }
+        }
+
+        let config = self.server.config();
+        if let Err(filtered) = tool_filter::check(&request.name, &config) {
+            let result = Err(TemplateError::Filtered(filtered));
+            self.record("tools/call", &request, &result);
+            return result;
+        }
+        match sunset::check(&request.name, self.server.clock().now()) {
+            Ok(None) => {}
+            Ok(Some(deprecation)) => {
+                if deprecation.is_imminent() {
+                    tracing::warn!("{deprecation}");
+                }
+                self.notifier.log_message(
+                    LogLevel::Warning,
+                    Some(&request.name),
+                    Value::from(deprecation.to_string()),
+                );
+            }
+            Err(gone) => {
+                let result = Err(TemplateError::Gone(gone));
+                self.record("tools/call", &request, &result);
+                return result;
+            }
+        }
+        if let Err(refused) = self.check_access(&request.name, &config) {
+            let result = Err(refused);
+            self.record("tools/call", &request, &result);
+            return result;
+        }
+
+        if let Some(requirement) = confirmation::requirement(&request.name) {
+            let token = confirmation::take_token(&mut request.arguments);
+            let arguments = request
+                .arguments
+                .clone()
+                .unwrap_or_else(|| Value::Object(Default::default()));
+            let confirmations = self.server.confirmations();
+            let refused = match token {
+                None => Some(Ok(confirmations
+                    .challenge(&requirement, &arguments)
+                    .into_result())),
+                Some(token) => confirmations
+                    .confirm(&token, &request.name, &arguments)
+                    .err()
+                    .map(|e| Err(CommonMcpError::InvalidParams(e.to_string()).into())),
+            };
+            if let Some(result) = refused {
+                self.record("tools/call", &request, &result);
+                return result;
+            }
+        }
+
+        if let Err(e) = self.server.roots().ensure_fetched().await {
+            tracing::warn!("Failed to fetch client roots: {e}");
+        }
+
+        let started = std::time::Instant::now();
+        config_defaults::apply_to_arguments(&request.name, &mut request.arguments, &config);
+        default_empty::apply_to_arguments(&request.name, &mut request.arguments);
+        let arguments_checked = units::apply_to_arguments(&request.name, &mut request.arguments)
+            .and_then(|()| match self.tools.get(&request.name) {
+                Some(tool) => {
+                    let definition = tool.definition();
+                    let arguments = request.arguments.as_ref();
+                    finite::check_arguments(&definition, arguments)
+                        .and_then(|()| decimal::check_arguments(&definition, arguments))
+                        .and_then(|()| param_groups::check_arguments(&request.name, arguments))
+                        .and_then(|()| {
+                            strict_arguments::check_arguments(&definition, arguments, &config)
+                        })
+                }
+                None => Ok(()),
+            })
+            // Charged last, so refused calls and unknown tools don't use up
+            // the quota
+            .and_then(|()| match self.tools.get(&request.name) {
+                Some(_) => self
+                    .server
+                    .quota()
+                    .try_acquire(self.quota_client(), config.quota_policy())
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                None => Ok(()),
+            });
+        let result = match arguments_checked {
+            Ok(()) => {
+                let timeout = timeouts::timeout_for(
+                    &request.name,
+                    request.arguments.as_ref(),
+                    Duration::from_secs(config.timeout_seconds),
+                );
+                let context = RequestContext::new(timeout)
+                    .with_notifier(self.notifier.clone(), &request.name)
+                    .with_client(&self.client_id);
+                let call = async {
+                    let panicked =
+                        |panic: String| format!("Tool '{}' panicked: {panic}", request.name);
+                    let outcome = if blocking::is_blocking_tool(&request.name) {
+                        let tools = self.tools.clone();
+                        let request = request.clone();
+                        // Task-locals don't follow the call onto the blocking pool
+                        let context = RequestContext::current().unwrap_or_default();
+                        blocking::run_blocking(
+                            context.enter(async move { tools.call(request).await }),
+                        )
+                        .await
+                        .map_err(|e| match e.try_into_panic() {
+                            Ok(payload) => panicked(panics::panic_message(&*payload)),
+                            Err(e) => format!("Tool failed: {e}"),
+                        })
+                    } else {
+                        let call = panics::catch_panic(self.tools.call(request.clone()));
+                        match config.limits.blocking_warn_ms {
+                            Some(ms) => {
+                                blocking::warn_blocking(
+                                    &request.name,
+                                    Duration::from_millis(ms),
+                                    call,
+                                )
+                                .await
+                            }
+                            None => call.await,
+                        }
+                        .map_err(panicked)
+                    };
+                    outcome.unwrap_or_else(|message| {
+                        Err(pulseengine_mcp_protocol::Error::internal_error(message))
+                    })
+                };
+                let scoped = async {
+                    context.scope(call).await.unwrap_or_else(|stopped| {
+                        Err(pulseengine_mcp_protocol::Error::internal_error(
+                            match stopped {
+                                Stopped::DeadlineExceeded => format!(
+                                    "Tool '{}' timed out after {}",
+                                    request.name,
+                                    timeouts::describe(timeout)
+                                ),
+                                Stopped::Cancelled => {
+                                    format!("Tool '{}' was cancelled", request.name)
+                                }
+                            },
+                        ))
+                    })
+                };
+                // Only failures of the tool itself count for its breaker, not
+                // structured errors such as invalid input
+                let called = match self.server.circuit_breaker(&request.name) {
+                    Some(breaker) => breaker
+                        .call_counting(scoped, |called| {
+                            matches!(called, Err(e) if e.code
+                                == pulseengine_mcp_protocol::ErrorCode::InternalError
+                                && tool_errors::unpack(e).is_none())
+                        })
+                        .await
+                        .unwrap_or_else(|open| {
+                            Err(pulseengine_mcp_protocol::Error::internal_error(
+                                open.to_string(),
+                            ))
+                        }),
+                    None => scoped.await,
+                };
+                called
+                    .map(|mut result| {
+                        unit_result::apply_to_result(&mut result, config.tools.unit_result);
+                        if let Some(link) =
+                            result_resources::unpack(&mut result, self.server.result_resources())
+                        {
+                            self.announce_result_resource(&link);
+                        }
+                        warnings::unpack(&mut result);
+                        ndjson::unpack(&mut result);
+                        structured::apply_to_result(&request.name, &mut result);
+                        table::detect(&mut result);
+                        result
+                    })
+                    .map_err(|e| match tool_errors::unpack(&e) {
+                        Some(data) => TemplateError::Tool(data),
+                        // Failures of the tool itself, such as a non-finite
+                        // result, stay internal errors; those of the
+                        // arguments stay invalid params
+                        None if e.code == pulseengine_mcp_protocol::ErrorCode::InternalError => {
+                            CommonMcpError::Internal(e.message).into()
+                        }
+                        None => CommonMcpError::InvalidParams(e.message).into(),
+                    })
+                    .and_then(|result| {
+                        let sanitizer = config.tools.sanitize_html.then_some(&*self.html_sanitizer);
+                        html::apply(
+                            &request.name,
+                            result,
+                            config.limits.max_html_bytes,
+                            sanitizer,
+                        )
+                        .map_err(|e| CommonMcpError::Internal(e).into())
+                    })
+                    .and_then(|result| {
+                        post_processing::apply(&self.post_processors, &request.name, result)
+                            .map_err(|e| CommonMcpError::Internal(e).into())
+                    })
+                    .and_then(|result| match config.limits.max_response_bytes {
+                        Some(max_bytes) => {
+                            let policy = response_size::policy_for(
+                                &request.name,
+                                config.limits.oversize_responses,
+                            );
+                            response_size::limit(&request.name, result, max_bytes, policy)
+                                .map_err(|e| CommonMcpError::Internal(e).into())
+                        }
+                        None => Ok(result),
+                    })
+            }
+            Err(message) => Err(CommonMcpError::InvalidParams(message).into()),
+        };
+        if let Ok(result) = &result {
+            costs::record(CostReport::new(&request.name, started.elapsed(), result));
+            if result.is_error != Some(true) {
+                self.call_history.record(&request.name);
+            }
+        }
+        if self.tools.get(&request.name).is_some() {
+            let failed = result
+                .as_ref()
+                .map_or(true, |result| result.is_error == Some(true));
+            self.server
+                .tool_stats()
+                .record(&request.name, started.elapsed(), failed);
+        }
+        self.record("tools/call", &request, &result);
+        result
+    }
+
+    /// Tool definitions as listed by `tools/list`; only the router tool in
+    /// router mode
+    pub fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        let config = self.server.config();
+        let now = self.server.clock().now();
+        let mut tools = self.schema_catalog().tools().to_vec();
+        tools.retain(|tool| {
+            tool_filter::is_enabled(&tool.name, &config)
+                && !sunset::is_gone(&tool.name, now)
+                && effects::is_allowed(&tool.name, &config)
+                && !safe_mode::is_disabled(&tool.name, &config)
+                && self.client_capabilities.is_available(&tool.name)
+        });
+        if config.tools.router_tool {
+            return vec![router_tool::definition(&tools)];
+        }
+        tools
+    }
+
+    /// Decorated definitions of every registered tool, built once and again
+    /// only after a tool is registered or the configuration is reloaded
+    pub fn schema_catalog(&self) -> Arc<SchemaCatalog> {
+        let version = (self.tools.generation(), self.server.config_generation());
+        self.schema_catalog.get_or_build(version, || {
+            let config = self.server.config();
+            let mut tools = self.tools.definitions();
+            for tool in &mut tools {
+                config_defaults::apply_to_schema(tool, &config);
+                confirmation::apply_to_schema(tool);
+                call_order::apply_to_schema(tool);
+                default_empty::apply_to_schema(tool);
+                effects::apply_to_schema(tool);
+                param_examples::apply_to_schema(tool);
+                param_groups::apply_to_schema(tool);
+                costs::apply_to_schema(tool);
+                strict_arguments::apply_to_schema(tool, &config);
+                structured::apply_to_schema(tool);
+                sunset::apply_to_schema(tool);
+                units::apply_to_schema(tool);
+                // Last, so every decoration is in the types it hoists
+                schema_defs::apply_to_schema(tool);
+            }
+            tools
+        })
+    }
+
+    /// Tell the client the resource list changed, now that the resource
+    /// behind `link` was added and again once it expires
+    fn announce_result_resource(&self, link: &ResourceLink) {
+        self.resource_list.changed();
+        let lifetime = self.server.result_resources().time_left(link);
+        let server = self.server.clone();
+        let resource_list = self.resource_list.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(lifetime).await;
+            if server.result_resources().remove_expired() > 0 {
+                resource_list.changed();
+            }
+        });
+    }
+
+    /// Every resource, unpaged: the generated ones and those of each source
+    async fn resources(&self) -> Result<Vec<Resource>, CommonMcpError> {
+        // The generated backend lists everything; paging covers all sources
+        let all = PaginatedRequestParam { cursor: None };
+        let mut resources = self.server.list_resources(all).await?.resources;
+        resources.extend(binary_resources::list());
+        resources.push(logs::resource());
+        resources.push(playground::resource());
+        if self.schema_resources {
+            resources.extend(schema_resources::list());
+        }
+        resources.extend(self.server.result_resources().list());
+        resources.extend(self.server.catalog().resources());
+        resources.extend(
+            self.file_resources
+                .iter()
+                .map(FileResourceProvider::resource),
+        );
+        resources.extend(self.http_proxies.iter().map(HttpProxyResource::resource));
+        Ok(resources)
+    }
+
+    /// Read `uri` for the client: after the admin checks, and post-processed
+    async fn read_for_client(&self, uri: &str) -> Result<ReadResourceResult, TemplateError> {
+        let config = self.server.config();
+        admin::check_resource_access(uri, &self.client_id, &config)
+            .and_then(|()| {
+                admin::check_quota_access(uri, self.quota_client(), &self.client_id, &config)
+            })
+            .map_err(TemplateError::Denied)?;
+        let result = self.read_contents(uri).await?;
+        post_processing::apply(&self.post_processors, uri, result)
+            .map_err(|e| CommonMcpError::Internal(e).into())
+    }
+
+    /// Read `uri` from whichever source serves it
+    async fn read_contents(&self, uri: &str) -> Result<ReadResourceResult, CommonMcpError> {
+        if let Some(proxy) = self.http_proxies.iter().find(|proxy| proxy.matches(uri)) {
+            return proxy.read(uri).await.map_err(CommonMcpError::from);
+        }
+        let file_provider = self
+            .file_resources
+            .iter()
+            .find(|provider| provider.matches(uri));
+        match (binary_resources::find(uri), file_provider) {
+            (Some(resource), _) => binary_resources::read(resource, &self.server)
+                .map_err(|e| CommonMcpError::InvalidParams(format!("Resource error: {e}"))),
+            (None, Some(provider)) => provider
+                .read(uri)
+                .await
+                .map_err(|e| CommonMcpError::InvalidParams(format!("Resource error: {e}"))),
+            (None, None) if self.schema_resources && schema_resources::matches(uri) => self
+                .read_schema_resource(uri)
+                .map_err(|e| CommonMcpError::InvalidParams(format!("Resource error: {e}"))),
+            (None, None) if logs::matches(uri) => logs::read(uri)
+                .map_err(|e| CommonMcpError::InvalidParams(format!("Resource error: {e}"))),
+            (None, None) if playground::matches(uri) => playground::read(&self.tool_definitions())
+                .map_err(|e| CommonMcpError::Internal(format!("Resource error: {e}"))),
+            (None, None) if catalog::matches(uri) => self
+                .server
+                .catalog()
+                .read(uri)
+                .map_err(|e| CommonMcpError::InvalidParams(format!("Resource error: {e}"))),
+            (None, None) if result_resources::matches(uri) => {
+                self.server.result_resources().read(uri).ok_or_else(|| {
+                    CommonMcpError::InvalidParams(format!(
+                        "Resource error: Resource not found or expired: {}",
+                        uri
+                    ))
+                })
+            }
+            (None, None) => {
+                let (path, query) = resource_queries::parse(uri, &self.server.config())
+                    .map_err(|e| CommonMcpError::InvalidParams(format!("Resource error: {e}")))?;
+                let request = ReadResourceRequestParam {
+                    uri: path.to_string(),
+                };
+                let mut result =
+                    resource_queries::with_query(query, self.server.read_resource(request)).await?;
+                for contents in &mut result.contents {
+                    contents.uri = uri.to_string();
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    fn read_schema_resource(&self, uri: &str) -> anyhow::Result<ReadResourceResult> {
+        let tools = self.tool_definitions();
+        let document = if uri == schema_resources::OPENAPI_URI {
+            schema_resources::openapi(&self.get_server_info(), &tools)
+        } else {
+            serde_json::to_value(ListToolsResult {
+                tools,
+                next_cursor: None,
+            })?
+        };
+        schema_resources::contents(uri, &document)
+    }
+
+    fn record<P: Serialize, R: Serialize, E: std::fmt::Display>(
+        &self,
+        method: &str,
+        params: &P,
+        result: &Result<R, E>,
+    ) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+        let params = serde_json::to_value(params).unwrap_or(Value::Null);
+        let outcome = match result {
+            Ok(value) => serde_json::to_value(value).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        recorder.record(&RecordedExchange::new(method, params, outcome));
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, TemplateError> {
+    serde_json::from_value(params)
+        .map_err(|e| CommonMcpError::InvalidParams(format!("Invalid params: {e}")).into())
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, TemplateError> {
+    serde_json::to_value(value)
+        .map_err(|e| CommonMcpError::Internal(format!("Failed to serialize result: {e}")).into())
+}
+
+#[async_trait]
+impl McpBackend for TemplateBackend {
+    type Error = TemplateError;
+    type Config = ();
+
+    async fn initialize(_config: Self::Config) -> Result<Self, Self::Error> {
+        Ok(Self::default())
+    }
+
+    fn get_server_info(&self) -> ServerInfo {
+        let mut info = self.server.get_server_info();
+        if let Some(resources) = &mut info.capabilities.resources {
+            resources.list_changed = Some(self.notifier.is_connected());
+        }
+        info
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.server.health_check().await?;
+        let report = self.server.health().report().await;
+        if report.status == HealthStatus::Unhealthy {
+            let unhealthy: Vec<_> = report
+                .components
+                .iter()
+                .filter(|component| component.status == HealthStatus::Unhealthy)
+                .map(|component| component.name.as_str())
+                .collect();
+            return Err(CommonMcpError::Internal(format!(
+                "Unhealthy components: {}",
+                unhealthy.join(", ")
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn list_tools(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> Result<ListToolsResult, Self::Error> {
+        let result = Ok(ListToolsResult {
+            tools: self.tool_definitions(),
+            next_cursor: None,
+        });
+        self.record("tools/list", &request, &result);
+        result
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, Self::Error> {
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = &self.tracer {
+            let span = tracer.start_tool_call(&request.name, &self.client_id);
+            let result = self.run_tool_call(request).await;
+            span.end_with(&result);
+            return result;
+        }
+        self.run_tool_call(request).await
+    }
+
+    async fn list_resources(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> Result<ListResourcesResult, Self::Error> {
+        let config = self.server.config();
+        let result = self.resources().await.and_then(|mut resources| {
+            if config.access.hide_admin_resources {
+                resources.retain(|resource| {
+                    admin::check_resource_access(&resource.uri, &self.client_id, &config).is_ok()
+                });
+            }
+            let (resources, next_cursor) = pagination::page(
+                resources,
+                |resource| &resource.uri,
+                request.cursor.as_deref(),
+                config.resources.page_size,
+            )
+            .map_err(CommonMcpError::InvalidParams)?;
+            Ok(ListResourcesResult {
+                resources,
+                next_cursor,
+            })
+        });
+        self.record("resources/list", &request, &result);
+        result.map_err(Into::into)
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+    ) -> Result<ReadResourceResult, Self::Error> {
+        let result = self.read_for_client(&request.uri).await;
+        self.record("resources/read", &request, &result);
+        result
+    }
+
+    async fn list_prompts(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> Result<ListPromptsResult, Self::Error> {
+        let result = self
+            .server
+            .list_prompts(request.clone())
+            .await
+            .map(|mut result| {
+                result.prompts.extend(prompts::list());
+                result
+            });
+        self.record("prompts/list", &request, &result);
+        result.map_err(Into::into)
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+    ) -> Result<GetPromptResult, Self::Error> {
+        let result = match prompts::find(&request.name) {
+            Some(prompt) => {
+                let call = |name: &str, arguments: Value| {
+                    self.call_tool(CallToolRequestParam {
+                        name: name.to_string(),
+                        arguments: Some(arguments),
+                    })
+                };
+                prompts::resolve(
+                    prompt,
+                    request.arguments.as_ref(),
+                    |uri| self.read_for_client(uri),
+                    call,
+                )
+                .await
+            }
+            None => self
+                .server
+                .get_prompt(request.clone())
+                .await
+                .map_err(Into::into),
+        };
+        self.record("prompts/get", &request, &result);
+        result
+    }
+
+    async fn handle_custom_method(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, Self::Error> {
+        match method {
+            resource_wait::WAIT_METHOD => to_value(self.wait_resource(parse(params)?).await?),
+            validation::VALIDATE_METHOD => to_value(self.validate_tool_call(parse(params)?).await?),
+            byte_ranges::RANGE_READ_METHOD => {
+                to_value(self.read_resource_range(parse(params)?).await?)
+            }
+            etag::CONDITIONAL_READ_METHOD => {
+                to_value(self.read_resource_conditional(parse(params)?).await?)
+            }
+            roots::LIST_CHANGED_NOTIFICATION => {
+                if let Err(e) = self.server.roots().refresh().await {
+                    tracing::warn!("Failed to refresh client roots: {e}");
+                }
+                Ok(Value::Null)
+            }
+            _ => Err(CommonMcpError::InvalidParams(format!("Unknown method: {method}")).into()),
+        }
+    }
+}
+
+impl McpServerBuilder for TemplateBackend {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend(config: ServerConfig) -> TemplateBackend {
+        TemplateBackend::new(TemplateMcpServer::with_config(config))
+    }
+
+    /// The default config, changed by `change`
+    fn configured(change: impl FnOnce(&mut ServerConfig)) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        change(&mut config);
+        config
+    }
+
+    fn call(tool: &str) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: tool.to_string(),
+            arguments: None,
+        }
+    }
+
+    /// The refusals of validating and of calling `tool`, which must agree
+    async fn refusals(backend: &TemplateBackend, tool: &str) -> (Option<String>, Option<String>) {
+        let validated = backend.validate_tool_call(call(tool)).await.err();
+        let called = backend.call_tool(call(tool)).await.err();
+        (
+            validated.map(|e| e.to_string()),
+            called.map(|e| e.to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn validation_refuses_tools_safe_mode_turns_off() {
+        let backend = backend(configured(|config| config.access.safe_mode = true));
+        let (validated, called) = refusals(&backend, "clear_data").await;
+        let called = called.expect("safe mode refuses the call");
+        assert!(called.contains("disabled in safe mode"), "{called}");
+        assert!(validated.expect("and its validation").contains(&called));
+    }
+
+    #[tokio::test]
+    async fn validation_refuses_admin_tools_to_other_clients() {
+        let backend = backend(ServerConfig::default());
+        let (validated, called) = refusals(&backend, "reset_tool_stats").await;
+        let called = called.expect("non-admins can't call admin tools");
+        assert!(called.contains("requires admin access"), "{called}");
+        assert!(validated.expect("nor validate them").contains(&called));
+
+        let admin = TemplateBackend::new(TemplateMcpServer::with_config(configured(|config| {
+            config.access.admin_clients = vec!["ops".to_string()]
+        })))
+        .with_client_id("ops");
+        assert_eq!(refusals(&admin, "reset_tool_stats").await, (None, None));
+    }
+
+    #[tokio::test]
+    async fn refused_calls_dont_use_up_the_quota() {
+        let backend = backend(configured(|config| {
+            config.limits.daily_call_quota = Some(1)
+        }));
+        for tool in ["reset_tool_stats", "no_such_tool"] {
+            assert!(backend.call_tool(call(tool)).await.is_err());
+        }
+        let add = CallToolRequestParam {
+            name: "add_numbers".to_string(),
+            arguments: Some(serde_json::json!({ "a": 1, "b": 2 })),
+        };
+        // Challenged for confirmation instead of run
+        let delete = CallToolRequestParam {
+            name: "delete_data".to_string(),
+            arguments: Some(serde_json::json!({ "id": 1 })),
+        };
+        backend.call_tool(delete).await.expect("a challenge");
+
+        assert!(backend.call_tool(add.clone()).await.is_ok());
+        let error = backend.call_tool(add).await.unwrap_err().to_string();
+        assert!(error.contains("quota_exceeded"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn authenticated_calls_count_against_their_identity() {
+        let server = TemplateMcpServer::with_config(configured(|config| {
+            config.limits.daily_call_quota = Some(1)
+        }));
+        let context = AuthContext {
+            user_id: Some("alice".to_string()),
+            roles: Vec::new(),
+            api_key_id: Some("key-1".to_string()),
+            permissions: Vec::new(),
+        };
+        let add = CallToolRequestParam {
+            name: "add_numbers".to_string(),
+            arguments: Some(serde_json::json!({ "a": 1, "b": 2 })),
+        };
+
+        let first = TemplateBackend::new(server.clone())
+            .with_client_id("first")
+            .with_auth_context(&context);
+        assert_eq!(first.quota_client(), "alice");
+        assert!(first.call_tool(add.clone()).await.is_ok());
+
+        // Another client id doesn't get a fresh quota
+        let second = TemplateBackend::new(server)
+            .with_auth_context(&context)
+            .with_client_id("second");
+        let error = second.call_tool(add).await.unwrap_err().to_string();
+        assert!(error.contains("client 'alice'"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn authenticated_clients_read_only_their_own_quota() {
+        let server = TemplateMcpServer::with_config(configured(|config| {
+            config.access.admin_clients = vec!["ops".to_string()]
+        }));
+        let connection = |user: &str| {
+            TemplateBackend::new(server.clone()).with_auth_context(&AuthContext {
+                user_id: Some(user.to_string()),
+                roles: Vec::new(),
+                api_key_id: None,
+                permissions: Vec::new(),
+            })
+        };
+        let read = |uri: &str| ReadResourceRequestParam {
+            uri: uri.to_string(),
+        };
+
+        let alice = connection("alice");
+        let own = alice
+            .read_resource(read("template://quota/alice"))
+            .await
+            .unwrap();
+        assert_eq!(own.contents.len(), 1);
+
+        let error = alice
+            .read_resource(read("template://quota/bob"))
+            .await
+            .unwrap_err();
+        let error = pulseengine_mcp_protocol::Error::from(error);
+        assert_eq!(
+            error.message,
+            "Resource 'template://quota/bob' requires admin access, which client 'alice' doesn't have"
+        );
+        assert_eq!(
+            error.code,
+            pulseengine_mcp_protocol::Error::forbidden("").code
+        );
+
+        let admin = connection("alice").with_client_id("ops");
+        assert!(admin
+            .read_resource(read("template://quota/bob"))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn non_finite_results_are_internal_errors_and_arguments_invalid_params() {
+        let backend = backend(ServerConfig::default());
+        let add = |a: Value| CallToolRequestParam {
+            name: "add_numbers".to_string(),
+            arguments: Some(serde_json::json!({ "a": a, "b": 1e308 })),
+        };
+
+        let overflow = backend.call_tool(add(1e308.into())).await.unwrap_err();
+        let overflow = pulseengine_mcp_protocol::Error::from(overflow);
+        assert_eq!(
+            overflow.code,
+            pulseengine_mcp_protocol::ErrorCode::InternalError
+        );
+        assert!(
+            overflow.message.contains("Infinity"),
+            "{}",
+            overflow.message
+        );
+
+        let nan = backend.call_tool(add("NaN".into())).await.unwrap_err();
+        let nan = pulseengine_mcp_protocol::Error::from(nan);
+        assert_eq!(nan.code, pulseengine_mcp_protocol::ErrorCode::InvalidParams);
+        assert!(nan.message.contains("must be a finite number"));
+    }
+
+    #[tokio::test]
+    async fn validation_refuses_calls_out_of_order() {
+        let admin = backend(configured(|config| {
+            config.access.admin_clients = vec!["ops".to_string()]
+        }))
+        .with_client_id("ops");
+        let validated = admin.validate_tool_call(call("import_snapshot")).await;
+        let message = validated
+            .expect_err("import needs an export first")
+            .to_string();
+        assert!(message.contains("export_snapshot"), "{message}");
+    }
+
+    /// Ids that always clash, so every `create_data` after the first fails
+    struct SameId;
+
+    impl crate::ids::IdGenerator for SameId {
+        fn next_id(&self) -> u64 {
+            7
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_calls_of_a_guarded_tool_open_its_breaker() {
+        let server =
+            TemplateMcpServer::with_sources(Arc::new(crate::clock::SystemClock), Arc::new(SameId));
+        let backend = TemplateBackend::new(server.clone());
+        let create = |name: &str| CallToolRequestParam {
+            name: "create_data".to_string(),
+            arguments: Some(serde_json::json!({ "name": name, "value": 1 })),
+        };
+
+        backend.call_tool(create("entry")).await.unwrap();
+        // Invalid input doesn't count for the breaker
+        for _ in 0..5 {
+            backend.call_tool(create(" ")).await.unwrap_err();
+        }
+        let breaker = server.circuit_breaker("create_data").unwrap();
+        assert_eq!(breaker.status().consecutive_failures, 0);
+
+        for _ in 0..5 {
+            let conflict = backend.call_tool(create("entry")).await.unwrap_err();
+            assert!(conflict.to_string().contains("7"), "{conflict}");
+        }
+        let open = backend.call_tool(create("entry")).await.unwrap_err();
+        assert!(
+            open.to_string().contains("Circuit open for 'create_data'"),
+            "{open}"
+        );
+        assert!(server.circuit_breaker("create_data_bulk").is_none());
+    }
+
+    /// POST `message` to the HTTP transport on `port`, returning the response
+    async fn post_message(port: u16, message: Value) -> Value {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let mut stream = loop {
+            match tokio::net::TcpStream::connect(("127.0.0.1", port)).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        };
+        let body = message.to_string();
+        let request = format!(
+            "POST /messages HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Type: application/json\r\n\
+             Accept: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_stdio_and_http_from_one_backend() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let (mut stdin, input) = tokio::io::duplex(1 << 16);
+        let (output, stdout) = tokio::io::duplex(1 << 16);
+        let transports = vec![
+            TransportConfig::Stdio,
+            TransportConfig::Http {
+                port,
+                host: Some("127.0.0.1".to_string()),
+            },
+        ];
+        let serving = tokio::spawn(
+            TemplateBackend::new(TemplateMcpServer::default())
+                .serve_all_with_stdio(transports, input, output),
+        );
+
+        let created = post_message(
+            port,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": {
+                    "name": "create_data",
+                    "arguments": { "name": "over-http", "value": 2 }
+                }
+            }),
+        )
+        .await;
+        assert!(created["result"].is_object(), "{created}");
+
+        for message in [
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": { "name": "serve-all", "version": "1.0" }
+                }
+            }),
+            serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": { "name": "find_data", "arguments": { "name": "over-http" } }
+            }),
+        ] {
+            stdin
+                .write_all(format!("{message}\n").as_bytes())
+                .await
+                .unwrap();
+        }
+        let mut lines = tokio::io::BufReader::new(stdout).lines();
+        let mut found = None;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let response: Value = serde_json::from_str(&line).unwrap();
+            if response["id"] == 2 {
+                found = Some(response);
+                break;
+            }
+        }
+        let found = found.expect("find_data is answered over STDIO");
+        let text = found["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("over-http"), "{found}");
+
+        serving.abort();
+    }
+}
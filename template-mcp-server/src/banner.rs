@@ -0,0 +1,83 @@
+//! Startup banner
+//!
+//! Before starting its servers, `TemplateBackend::serve_all` logs a
+//! [`StartupSummary`] at info level with the server's name and version, its
+//! transports and their addresses, the auth mode, whether it runs in safe
+//! mode and how many tools, resources and prompts it serves. Operators can check it to confirm the
+//! right build is running. The counts come from the live backend, so they
+//! include plugin tools and runtime resources.
+//!
+//! The banner follows the log level like any other message. The `--quiet`
+//! flag, or `TemplateBackend::without_banner`, turns it off.
+
+use pulseengine_mcp_server::TransportConfig;
+
+/// Command-line flag turning the startup banner off
+pub const QUIET_FLAG: &str = "--quiet";
+
+/// What a starting server serves
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartupSummary {
+    pub name: String,
+    pub version: String,
+    /// Each transport, with its bind address if it has one
+    pub transports: Vec<String>,
+    pub auth: &'static str,
+    /// Whether destructive tools are turned off; see [`crate::safe_mode`]
+    pub safe_mode: bool,
+    pub tools: usize,
+    pub resources: usize,
+    pub prompts: usize,
+}
+
+impl StartupSummary {
+    /// Log the summary at info level
+    pub fn log(&self) {
+        tracing::info!(
+            name = %self.name,
+            version = %self.version,
+            transports = %self.transports.join(", "),
+            auth = %self.auth,
+            safe_mode = self.safe_mode,
+            tools = self.tools,
+            resources = self.resources,
+            prompts = self.prompts,
+            "Server starting"
+        );
+    }
+}
+
+/// A transport and where it listens, e.g. `http://127.0.0.1:8080`
+pub fn describe_transport(transport: &TransportConfig) -> String {
+    let address = |scheme: &str, host: &Option<String>, port: &u16| {
+        let host = host.as_deref().unwrap_or("127.0.0.1");
+        format!("{scheme}://{host}:{port}")
+    };
+    match transport {
+        TransportConfig::Stdio => "stdio".to_string(),
+        TransportConfig::Http { port, host } | TransportConfig::StreamableHttp { port, host } => {
+            address("http", host, port)
+        }
+        TransportConfig::WebSocket { port, host } => address("ws", host, port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_each_transport_with_its_address() {
+        assert_eq!(describe_transport(&TransportConfig::Stdio), "stdio");
+        let http = TransportConfig::Http {
+            port: 8080,
+            host: Some("0.0.0.0".to_string()),
+        };
+        assert_eq!(describe_transport(&http), "http://0.0.0.0:8080");
+        let ws = TransportConfig::WebSocket {
+            port: 9000,
+            host: None,
+        };
+        assert_eq!(describe_transport(&ws), "ws://127.0.0.1:9000");
+    }
+}
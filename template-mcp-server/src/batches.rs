@@ -0,0 +1,220 @@
+//! Partial failure in JSON-RPC batches
+//!
+//! The requests of a batch run one after the other, in order. Under
+//! [`BatchPolicy::ContinueOnError`] all of them run and the response holds
+//! each one's result or error. Under [`BatchPolicy::FailFast`] the requests
+//! after the first failing one don't run, and each gets an error saying which
+//! request failed, since JSON-RPC answers every request of a batch:
+//!
+//! ```json
+//! {
+//!   "code": -32603,
+//!   "message": "Not run: request 2 of the batch failed",
+//!   "data": { "skipped": true, "failed_id": 2 }
+//! }
+//! ```
+//!
+//! A request fails when its response is an error, which includes every failed
+//! tool call. Notifications in a batch are handled first, whatever the policy.
+//!
+//! Batches follow `TransportConfig::batch_policy` unless one of their requests
+//! sets [`POLICY_META_KEY`] in the `_meta` of its params, e.g.
+//! `"_meta": { "batchPolicy": "fail_fast" }`; the first request that sets it
+//! decides for the whole batch. Batches are run this way by
+//! [`crate::raw_messages::RawMessageHandler`], so over STDIO; the framework's
+//! HTTP transport runs every request of a batch.
+
+use pulseengine_mcp_protocol::{Error, ErrorCode, Request, Response};
+use pulseengine_mcp_transport::batch::{self, JsonRpcMessage};
+use pulseengine_mcp_transport::{RequestHandler, TransportError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Key in a request's `params._meta` choosing the policy of its batch
+pub const POLICY_META_KEY: &str = "batchPolicy";
+
+/// What happens to the rest of a batch when one of its requests fails
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchPolicy {
+    /// Run every request and answer each with its result or error
+    #[default]
+    ContinueOnError,
+    /// Stop at the first error and answer the requests after it as skipped
+    FailFast,
+}
+
+/// The policy one of `requests` asks for, if any
+pub fn requested_policy(requests: &[Request]) -> Result<Option<BatchPolicy>, Error> {
+    let Some(requested) = requests.iter().find_map(|request| {
+        request
+            .params
+            .get("_meta")
+            .and_then(|meta| meta.get(POLICY_META_KEY))
+    }) else {
+        return Ok(None);
+    };
+    serde_json::from_value(requested.clone())
+        .map(Some)
+        .map_err(|_| {
+            Error::invalid_request(format!(
+                "Unknown batch policy {requested}; expected \"continue_on_error\" or \"fail_fast\""
+            ))
+        })
+}
+
+/// Dispatch `message` through `handler`, running a batch per its policy, or
+/// `default` if it asks for none
+pub async fn process(
+    message: JsonRpcMessage,
+    handler: &RequestHandler,
+    default: BatchPolicy,
+) -> Result<Option<JsonRpcMessage>, TransportError> {
+    if !matches!(message, JsonRpcMessage::Batch(_)) {
+        return batch::process_batch(message, handler).await;
+    }
+    message.validate()?;
+    for notification in message.extract_notifications()? {
+        handler(notification).await;
+    }
+    let requests = message.extract_requests()?;
+    if requests.is_empty() {
+        return Ok(None);
+    }
+
+    let responses = match requested_policy(&requests) {
+        Ok(policy) => run(requests, handler, policy.unwrap_or(default)).await,
+        Err(error) => requests
+            .into_iter()
+            .map(|request| batch::create_error_response(error.clone(), request.id))
+            .collect(),
+    };
+    let responses = responses
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|e| {
+            TransportError::Protocol(format!("Failed to serialize batch response: {e}"))
+        })?;
+    Ok(Some(JsonRpcMessage::Batch(responses)))
+}
+
+async fn run(
+    requests: Vec<Request>,
+    handler: &RequestHandler,
+    policy: BatchPolicy,
+) -> Vec<Response> {
+    let mut responses = Vec::with_capacity(requests.len());
+    let mut failed: Option<Value> = None;
+    for request in requests {
+        if let Some(failed_id) = &failed {
+            responses.push(batch::create_error_response(skipped(failed_id), request.id));
+            continue;
+        }
+        let response = handler(request).await;
+        if policy == BatchPolicy::FailFast && response.error.is_some() {
+            failed = Some(response.id.clone());
+        }
+        responses.push(response);
+    }
+    responses
+}
+
+fn skipped(failed_id: &Value) -> Error {
+    Error::with_data(
+        ErrorCode::InternalError,
+        format!("Not run: request {failed_id} of the batch failed"),
+        json!({ "skipped": true, "failed_id": failed_id }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A handler failing requests for `fail` and answering others with their
+    /// method
+    fn handler() -> RequestHandler {
+        Box::new(|request: Request| {
+            Box::pin(async move {
+                let (result, error) = if request.method == "fail" {
+                    (None, Some(Error::internal_error("failed")))
+                } else {
+                    (Some(json!(request.method)), None)
+                };
+                Response {
+                    jsonrpc: "2.0".to_string(),
+                    result,
+                    error,
+                    id: request.id,
+                }
+            })
+        })
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    async fn responses(batch: Vec<Value>, default: BatchPolicy) -> Vec<Value> {
+        match process(JsonRpcMessage::Batch(batch), &handler(), default).await {
+            Ok(Some(JsonRpcMessage::Batch(responses))) => responses,
+            other => panic!("not a batch response: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn continues_after_errors_by_default() {
+        let batch = vec![
+            request(1, "ping", json!({})),
+            request(2, "fail", json!({})),
+            request(3, "ping", json!({})),
+        ];
+        let responses = responses(batch, BatchPolicy::ContinueOnError).await;
+        assert_eq!(responses[0]["result"], "ping");
+        assert_eq!(responses[1]["error"]["message"], "failed");
+        assert_eq!(responses[2]["result"], "ping");
+    }
+
+    #[tokio::test]
+    async fn skips_the_rest_when_failing_fast() {
+        let batch = vec![
+            request(
+                1,
+                "ping",
+                json!({ "_meta": { "batchPolicy": "fail_fast" } }),
+            ),
+            request(2, "fail", json!({})),
+            request(3, "ping", json!({})),
+        ];
+        let responses = responses(batch, BatchPolicy::ContinueOnError).await;
+        assert_eq!(responses[0]["result"], "ping");
+        assert_eq!(responses[1]["error"]["message"], "failed");
+        assert_eq!(
+            responses[2]["error"]["message"],
+            "Not run: request 2 of the batch failed"
+        );
+        assert_eq!(
+            responses[2]["error"]["data"],
+            json!({ "skipped": true, "failed_id": 2 })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_policies() {
+        let batch = vec![
+            request(1, "ping", json!({})),
+            request(
+                2,
+                "ping",
+                json!({ "_meta": { "batchPolicy": "sometimes" } }),
+            ),
+        ];
+        for response in responses(batch, BatchPolicy::FailFast).await {
+            assert_eq!(
+                response["error"]["message"],
+                "Unknown batch policy \"sometimes\"; expected \"continue_on_error\" or \"fail_fast\""
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Emit TypeScript declarations for the server's tools
+//!
+//! Usage:
+//! - `cargo run --bin emit-ts-types > template-mcp-server.d.ts` writes the
+//!   declarations for the current `tools/list` result
+//! - `cargo run --bin emit-ts-types -- --check template-mcp-server.d.ts` exits
+//!   non-zero if a previously generated file is out of date
+//!
+//! See `template_mcp_server::ts_types` for how schemas map to TypeScript.
+
+use pulseengine_mcp_protocol::ListToolsResult;
+use serde_json::Value;
+use template_mcp_server::{ts_types, TemplateBackend, TemplateMcpServer};
+
+const USAGE: &str = "Usage: emit-ts-types [--check <file.d.ts>]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let tools: ListToolsResult = serde_json::from_value(
+        TemplateBackend::new(TemplateMcpServer::default())
+            .dispatch("tools/list", Value::Null)
+            .await?,
+    )?;
+    let declarations = ts_types::emit(&tools.tools);
+
+    match args.as_slice() {
+        [] => print!("{declarations}"),
+        [flag, path] if flag == "--check" => {
+            if std::fs::read_to_string(path)? != declarations {
+                println!("{path} is out of date; regenerate it with emit-ts-types");
+                std::process::exit(1);
+            }
+            println!("{path} is up to date");
+        }
+        _ => anyhow::bail!(USAGE),
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Replay a recorded MCP session against a fresh server
+//!
+//! Usage: `cargo run --bin replay -- session.jsonl`
+//!
+//! Each recorded request is dispatched through `TemplateBackend` and its
+//! outcome compared with the recording after normalizing timestamps. Exits
+//! non-zero if any response differs.
+
+use template_mcp_server::recording::{diff, load_session, normalize};
+use template_mcp_server::{TemplateBackend, TemplateMcpServer};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!("Usage: replay <session.jsonl>"))?;
+    let exchanges = load_session(&path)?;
+
+    // Built without a recorder so replaying never appends to a recording
+    let backend = TemplateBackend::new(TemplateMcpServer::default());
+    let mut mismatches = 0;
+
+    for (index, exchange) in exchanges.iter().enumerate() {
+        let outcome = match backend
+            .dispatch(&exchange.method, exchange.params.clone())
+            .await
+        {
+            Ok(value) => serde_json::json!({ "result": value }),
+            Err(e) => serde_json::json!({ "error": e.to_string() }),
+        };
+
+        let mut expected = exchange.outcome();
+        let mut actual = outcome;
+        normalize(&mut expected);
+        normalize(&mut actual);
+
+        let differences = diff(&expected, &actual);
+        if differences.is_empty() {
+            println!("[{}] {} ... ok", index + 1, exchange.method);
+        } else {
+            mismatches += 1;
+            println!("[{}] {} ... MISMATCH", index + 1, exchange.method);
+            for difference in differences {
+                println!("    {difference}");
+            }
+        }
+    }
+
+    println!(
+        "\n{} exchanges replayed, {} matched, {} differed",
+        exchanges.len(),
+        exchanges.len() - mismatches,
+        mismatches
+    );
+
+    if mismatches > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Check the server's tool schemas for breaking changes
+//!
+//! Usage:
+//! - `cargo run --bin schema-check -- --dump > schemas.json` writes the current
+//!   `tools/list` result
+//! - `cargo run --bin schema-check -- schemas.json` compares a previous dump with
+//!   the current tools and exits non-zero if any change is breaking
+
+use pulseengine_mcp_protocol::Tool;
+use serde_json::Value;
+use template_mcp_server::schema_compat::{tools_diff, ChangeKind};
+use template_mcp_server::{TemplateBackend, TemplateMcpServer};
+
+const USAGE: &str = "Usage: schema-check --dump | schema-check <old-schemas.json>";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let arg = std::env::args()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!(USAGE))?;
+
+    let current = TemplateBackend::new(TemplateMcpServer::default())
+        .dispatch("tools/list", Value::Null)
+        .await?;
+
+    if arg == "--dump" {
+        println!("{}", serde_json::to_string_pretty(&current)?);
+        return Ok(());
+    }
+
+    let old = serde_json::from_str(&std::fs::read_to_string(&arg)?)?;
+    let report = tools_diff(&parse_tools(old)?, &parse_tools(current)?);
+
+    for change in &report.changes {
+        let kind = match change.kind {
+            ChangeKind::Cosmetic => "cosmetic",
+            ChangeKind::Additive => "additive",
+            ChangeKind::Breaking => "BREAKING",
+        };
+        println!("{kind:>9}  {}: {}", change.path, change.description);
+    }
+
+    if report.changes.is_empty() {
+        println!("No schema changes");
+    }
+    if report.is_breaking() {
+        println!("\nBreaking schema changes found");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Tools from a `tools/list` result or a bare array of tools
+fn parse_tools(value: Value) -> anyhow::Result<Vec<Tool>> {
+    let tools = match value {
+        Value::Object(mut fields) => fields.remove("tools").unwrap_or(Value::Null),
+        other => other,
+    };
+    serde_json::from_value(tools).map_err(|e| anyhow::anyhow!("Invalid schema dump: {e}"))
+}
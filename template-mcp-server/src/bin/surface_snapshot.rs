@@ -0,0 +1,36 @@
+//! Snapshot the server's MCP surface for review
+//!
+//! Usage:
+//! - `cargo run --bin surface-snapshot > surface.txt` writes the tools,
+//!   resources and prompts the server exposes
+//! - `cargo run --bin surface-snapshot -- --check surface.txt` exits non-zero
+//!   with a diff if the committed snapshot is out of date; with
+//!   `UPDATE_SURFACE_SNAPSHOT=1` it rewrites the file instead
+//!
+//! See `template_mcp_server::surface` for the format.
+
+use std::path::Path;
+use template_mcp_server::{surface, TemplateBackend, TemplateMcpServer};
+
+const USAGE: &str = "Usage: surface-snapshot [--check <surface.txt>]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let backend = TemplateBackend::new(TemplateMcpServer::default());
+    let snapshot = surface::surface_snapshot(&backend).await?;
+
+    match args.as_slice() {
+        [] => print!("{snapshot}"),
+        [flag, path] if flag == "--check" => {
+            if let Err(diff) = surface::check_snapshot(Path::new(path), &snapshot) {
+                println!("{diff}");
+                std::process::exit(1);
+            }
+            println!("{path} is up to date");
+        }
+        _ => anyhow::bail!(USAGE),
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! Resources that serve raw bytes
+//!
+//! `#[mcp_resource]` serializes every resource to JSON text. Resources listed
+//! in [`BINARY_RESOURCES`] are served by the backend instead: the bytes their
+//! `read` function returns are delivered base64-encoded as the `blob` of the
+//! `resources/read` response, with the declared `mime_type` and no JSON
+//! serialization.
+
+use crate::TemplateMcpServer;
+use base64::Engine;
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, ResourceContents};
+
+/// A resource whose content is raw bytes
+#[derive(Debug, Clone, Copy)]
+pub struct BinaryResource {
+    pub uri: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub mime_type: &'static str,
+    pub read: fn(&TemplateMcpServer) -> anyhow::Result<Vec<u8>>,
+}
+
+/// Binary resources served next to the `#[mcp_resource]` ones
+pub const BINARY_RESOURCES: &[BinaryResource] = &[BinaryResource {
+    uri: "template://logo.png",
+    name: "logo",
+    description: "Server logo as a PNG image",
+    mime_type: "image/png",
+    read: |_| Ok(LOGO_PNG.to_vec()),
+}];
+
+/// A 1x1 PNG, standing in for real image data
+const LOGO_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x90, 0xcf, 0xdf, 0xf2,
+    0x1f, 0x00, 0x04, 0x35, 0x02, 0x42, 0x7d, 0x4c, 0x97, 0xdb, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45,
+    0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// The binary resource at `uri`, if any
+pub fn find(uri: &str) -> Option<&'static BinaryResource> {
+    BINARY_RESOURCES.iter().find(|resource| resource.uri == uri)
+}
+
+/// Resource listings for all binary resources
+pub fn list() -> impl Iterator<Item = Resource> {
+    BINARY_RESOURCES.iter().map(|resource| Resource {
+        uri: resource.uri.to_string(),
+        name: resource.name.to_string(),
+        description: Some(resource.description.to_string()),
+        mime_type: Some(resource.mime_type.to_string()),
+        annotations: None,
+        raw: None,
+    })
+}
+
+/// Read a binary resource into a base64 blob
+pub fn read(
+    resource: &BinaryResource,
+    server: &TemplateMcpServer,
+) -> anyhow::Result<ReadResourceResult> {
+    let bytes = (resource.read)(server)?;
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents {
+            uri: resource.uri.to_string(),
+            mime_type: Some(resource.mime_type.to_string()),
+            text: None,
+            blob: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_the_logo_as_a_base64_blob() {
+        let logo = find("template://logo.png").expect("the logo is listed");
+        let result = read(logo, &TemplateMcpServer::default()).unwrap();
+        let [contents] = result.contents.as_slice() else {
+            panic!("one content item");
+        };
+        assert_eq!(contents.mime_type.as_deref(), Some("image/png"));
+        assert_eq!(contents.text, None);
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(contents.blob.as_deref().unwrap())
+            .unwrap();
+        assert_eq!(bytes, LOGO_PNG);
+        assert!(bytes.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn lists_every_binary_resource() {
+        let uris: Vec<String> = list().map(|resource| resource.uri).collect();
+        assert_eq!(uris, ["template://logo.png"]);
+        assert!(find("template://logo.gif").is_none());
+    }
+}
@@ -0,0 +1,110 @@
+//! Detecting tools that block the async executor
+//!
+//! A tool that calls `std::fs::read`, `std::thread::sleep` or another blocking
+//! function from its async body holds a tokio worker for the whole call, which
+//! stalls every other request scheduled on it. The macros can't see what a
+//! tool body calls, so this is checked at runtime instead: with
+//! `LimitsConfig::blocking_warn_ms` set, the backend times every poll of a
+//! tool call and logs a warning when a single poll runs longer than that.
+//! Awaiting never counts, only synchronous work between awaits.
+//!
+//! The fix is to move the blocking work off the executor, with
+//! `tokio::task::spawn_blocking` or the async equivalent (`tokio::fs`,
+//! `tokio::time::sleep`).
+//!
+//! A tool wrapping a library without an async API can instead be a plain
+//! `fn` returning `anyhow::Result<T>`, with the `blocking` setting (see
+//! [`crate::tool_settings`]). The macros call a tool that isn't `async`
+//! inline, on the executor, so the backend runs calls of such tools on tokio's
+//! blocking thread pool, and other requests go on while they run.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Whether calls of `tool` run on the blocking thread pool
+pub fn is_blocking_tool(tool: &str) -> bool {
+    crate::tool_settings::settings_of(tool).blocking
+}
+
+/// Run `future` to completion on the blocking thread pool
+///
+/// Fails if `future` panics.
+pub async fn run_blocking<F>(future: F) -> Result<F::Output, tokio::task::JoinError>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || runtime.block_on(future)).await
+}
+
+/// A future that warns when one of its polls takes longer than a threshold
+pub struct WarnBlocking<F> {
+    inner: Pin<Box<F>>,
+    tool: String,
+    threshold: Duration,
+}
+
+/// Warn about polls of `future`, a call of `tool`, longer than `threshold`
+pub fn warn_blocking<F: Future>(
+    tool: impl Into<String>,
+    threshold: Duration,
+    future: F,
+) -> WarnBlocking<F> {
+    WarnBlocking {
+        inner: Box::pin(future),
+        tool: tool.into(),
+        threshold,
+    }
+}
+
+impl<F: Future> Future for WarnBlocking<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let started = Instant::now();
+        let poll = self.inner.as_mut().poll(cx);
+        let blocked = started.elapsed();
+        if blocked > self.threshold {
+            tracing::warn!(
+                tool = %self.tool,
+                blocked_ms = blocked.as_millis() as u64,
+                "Tool blocked the async executor; run blocking work with spawn_blocking"
+            );
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_tools_with_the_setting_run_off_the_executor() {
+        assert!(is_blocking_tool("hash_rounds"));
+        assert!(!is_blocking_tool("echo"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_futures_on_the_blocking_pool() {
+        let worker = std::thread::current().id();
+        let thread = run_blocking(async { std::thread::current().id() })
+            .await
+            .unwrap();
+        assert_ne!(thread, worker);
+        assert!(run_blocking(async { panic!("broken") }).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn passes_on_the_output_of_watched_futures() {
+        let output = warn_blocking("echo", Duration::from_millis(1), async {
+            std::thread::sleep(Duration::from_millis(5));
+            7
+        })
+        .await;
+        assert_eq!(output, 7);
+    }
+}
@@ -0,0 +1,75 @@
+//! What build of the server is running
+//!
+//! `build.rs` bakes the git commit, build time and compiler version into the
+//! binary, and `template://build-info` serves them with the crate version, so
+//! a deployed server can be matched to its source:
+//!
+//! ```json
+//! {
+//!   "version": "0.2.0",
+//!   "git_sha": "0f3c9d2…",
+//!   "build_timestamp": "2024-05-01T12:00:00Z",
+//!   "rustc_version": "rustc 1.80.0 (051478957 2024-07-21)"
+//! }
+//! ```
+//!
+//! [`VERSION`] is also the version in `ServerStatus` and the server info, so
+//! the crate version is the one place to change it. Builds outside a git
+//! checkout report the sha as `unknown`; CI builds take it from `GITHUB_SHA`.
+
+use serde::{Deserialize, Serialize};
+
+/// Crate version
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the server was built from
+pub const GIT_SHA: &str = env!("BUILD_GIT_SHA");
+
+/// When the server was built, in RFC 3339
+pub const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
+/// `rustc --version` of the compiler that built the server
+pub const RUSTC_VERSION: &str = env!("BUILD_RUSTC_VERSION");
+
+/// Build metadata, as served by `template://build-info`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_sha: String,
+    pub build_timestamp: String,
+    pub rustc_version: String,
+}
+
+impl BuildInfo {
+    /// Metadata of this build
+    pub fn current() -> Self {
+        Self {
+            version: VERSION.to_string(),
+            git_sha: GIT_SHA.to_string(),
+            build_timestamp: BUILD_TIMESTAMP.to_string(),
+            rustc_version: RUSTC_VERSION.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_this_build() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_sha.is_empty());
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(&info.build_timestamp).is_ok(),
+            "{}",
+            info.build_timestamp
+        );
+        assert!(
+            info.rustc_version.starts_with("rustc "),
+            "{}",
+            info.rustc_version
+        );
+    }
+}
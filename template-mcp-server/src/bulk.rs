@@ -0,0 +1,150 @@
+//! Bulk operations that report the outcome of each item
+//!
+//! A tool taking many items in one call shouldn't fail the whole call on its
+//! first bad item, leaving the client to guess which of the others went
+//! through. A bulk tool runs its operation on every item with
+//! [`BulkResult::process`] and returns the [`BulkResult`]: what each item that
+//! succeeded produced, and for each item that failed its index in the call's
+//! list and the error:
+//!
+//! ```json
+//! {
+//!   "succeeded": [{ "id": 7, "name": "widget", "value": 1.5, "tags": [] }],
+//!   "failed": [{ "index": 1, "error": "Invalid example data: name must not be blank" }]
+//! }
+//! ```
+//!
+//! The call itself succeeds even if every item failed; only arguments that
+//! don't match the tool's schema fail it as a whole. Items are processed one
+//! at a time, in order, so later items see the effects of earlier ones.
+//! `create_data_bulk` is the example.
+
+use crate::structured::ToText;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Outcome of a bulk operation, item by item
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct BulkResult<T> {
+    /// What each item that succeeded produced, in the order of the items
+    pub succeeded: Vec<T>,
+    pub failed: Vec<BulkFailure>,
+}
+
+/// An item of a bulk operation that failed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct BulkFailure {
+    /// Position of the item in the call's list, from 0
+    pub index: usize,
+    pub error: String,
+}
+
+impl<T> Default for BulkResult<T> {
+    fn default() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+}
+
+impl<T> BulkResult<T> {
+    /// Run `operation` on each of `items` in turn, collecting the outcomes
+    pub async fn process<I, F, Fut>(items: impl IntoIterator<Item = I>, mut operation: F) -> Self
+    where
+        F: FnMut(I) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut result = Self::default();
+        for (index, item) in items.into_iter().enumerate() {
+            result.push(index, operation(item).await);
+        }
+        result
+    }
+
+    /// Record the outcome of the item at `index`
+    pub fn push(&mut self, index: usize, outcome: anyhow::Result<T>) {
+        match outcome {
+            Ok(value) => self.succeeded.push(value),
+            Err(error) => self.failed.push(BulkFailure {
+                index,
+                error: error.to_string(),
+            }),
+        }
+    }
+}
+
+impl<T: ToText> ToText for BulkResult<T> {
+    fn to_text(&self) -> String {
+        let mut lines = vec![format!(
+            "{} succeeded, {} failed",
+            self.succeeded.len(),
+            self.failed.len()
+        )];
+        lines.extend(
+            self.succeeded
+                .iter()
+                .map(|value| format!("- {}", value.to_text())),
+        );
+        lines.extend(
+            self.failed
+                .iter()
+                .map(|failure| format!("- item {} failed: {}", failure.index, failure.error)),
+        );
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn double(n: u32) -> anyhow::Result<u32> {
+        anyhow::ensure!(n < 5, "{n} is too large");
+        Ok(n * 2)
+    }
+
+    #[tokio::test]
+    async fn reports_each_item_and_goes_on_after_failures() {
+        let result = BulkResult::process([2, 7, 4, 9], double).await;
+        assert_eq!(result.succeeded, [4, 8]);
+        assert_eq!(
+            result.failed,
+            [
+                BulkFailure {
+                    index: 1,
+                    error: "7 is too large".to_string()
+                },
+                BulkFailure {
+                    index: 3,
+                    error: "9 is too large".to_string()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn processes_items_in_order() {
+        let mut seen = Vec::new();
+        let result = BulkResult::process(["a", "b"], |item| {
+            seen.push(item);
+            async move { Ok(json!(item)) }
+        })
+        .await;
+        assert_eq!(seen, ["a", "b"]);
+        assert_eq!(result.succeeded, [json!("a"), json!("b")]);
+    }
+
+    #[test]
+    fn summarizes_the_outcome_as_text() {
+        let mut result = BulkResult::default();
+        result.push(0, Ok(json!("widget")));
+        result.push(1, Err(anyhow::anyhow!("name must not be blank")));
+        assert_eq!(
+            result.to_text(),
+            "1 succeeded, 1 failed\n- widget\n- item 1 failed: name must not be blank"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Byte-range reads of resources
+//!
+//! `resources/read_range` reads a resource like `resources/read` and returns
+//! only the bytes in the requested `range`, with the resource's total size, so
+//! a client that lost its connection partway through a large resource can
+//! resume from the last offset it received. The slice is always a base64
+//! `blob`, since a range of a text resource needn't be valid UTF-8. These are
+//! the values an HTTP server would send as `Content-Range`.
+//!
+//! Any resource works, whatever source serves it: the whole resource is read
+//! and then sliced, so ranges save bandwidth between server and client, not
+//! work on the server. Resources with several contents can't be read by range.
+
+use base64::Engine;
+use pulseengine_mcp_protocol::{ReadResourceResult, ResourceContents};
+use serde::{Deserialize, Serialize};
+
+/// Custom JSON-RPC method for byte-range reads
+pub const RANGE_READ_METHOD: &str = "resources/read_range";
+
+/// Bytes to read, starting at `offset`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub offset: u64,
+    /// Bytes to read; up to the end if unset or past it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<u64>,
+}
+
+/// Parameters of `resources/read_range`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RangeReadRequest {
+    pub uri: String,
+    /// The whole resource if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<ByteRange>,
+}
+
+/// Result of `resources/read_range`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RangeReadResult {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub offset: u64,
+    /// Bytes returned, less than requested at the end of the resource
+    pub length: u64,
+    /// Size of the whole resource in bytes
+    pub total_size: u64,
+    /// The bytes, base64-encoded
+    pub blob: String,
+}
+
+/// The bytes of `content`: its text as UTF-8, or its decoded blob
+pub fn bytes_of(content: &ResourceContents) -> Result<Vec<u8>, String> {
+    match (&content.text, &content.blob) {
+        (Some(text), _) => Ok(text.as_bytes().to_vec()),
+        (None, Some(blob)) => base64::engine::general_purpose::STANDARD
+            .decode(blob)
+            .map_err(|e| format!("Resource {} has an invalid blob: {e}", content.uri)),
+        (None, None) => Ok(Vec::new()),
+    }
+}
+
+/// The bytes of `result` in `range`
+pub fn slice(
+    result: ReadResourceResult,
+    range: Option<ByteRange>,
+) -> Result<RangeReadResult, String> {
+    let content = match <[ResourceContents; 1]>::try_from(result.contents) {
+        Ok([content]) => content,
+        Err(contents) => {
+            return Err(format!(
+                "Only resources with one content can be read by range, not {}",
+                contents.len()
+            ))
+        }
+    };
+    let bytes = bytes_of(&content)?;
+    let total_size = bytes.len() as u64;
+    let range = range.unwrap_or(ByteRange {
+        offset: 0,
+        length: None,
+    });
+    if range.offset > total_size {
+        return Err(format!(
+            "Range starts at byte {}, past the end of {} ({total_size} bytes)",
+            range.offset, content.uri
+        ));
+    }
+    let end = range
+        .length
+        .map_or(total_size, |length| range.offset.saturating_add(length))
+        .min(total_size);
+    let slice = &bytes[range.offset as usize..end as usize];
+    Ok(RangeReadResult {
+        uri: content.uri,
+        mime_type: content.mime_type,
+        offset: range.offset,
+        length: slice.len() as u64,
+        total_size,
+        blob: base64::engine::general_purpose::STANDARD.encode(slice),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> ReadResourceResult {
+        ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: "template://logs".to_string(),
+                mime_type: Some("text/plain".to_string()),
+                text: Some(text.to_string()),
+                blob: None,
+            }],
+        }
+    }
+
+    fn decoded(result: &RangeReadResult) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD
+            .decode(&result.blob)
+            .unwrap()
+    }
+
+    #[test]
+    fn returns_the_bytes_in_range_with_the_total_size() {
+        let range = ByteRange {
+            offset: 2,
+            length: Some(3),
+        };
+        let result = slice(text("abcdefg"), Some(range)).unwrap();
+        assert_eq!(decoded(&result), b"cde");
+        assert_eq!((result.offset, result.length, result.total_size), (2, 3, 7));
+    }
+
+    #[test]
+    fn stops_at_the_end_of_the_resource() {
+        let tail = ByteRange {
+            offset: 5,
+            length: Some(100),
+        };
+        assert_eq!(decoded(&slice(text("abcdefg"), Some(tail)).unwrap()), b"fg");
+        assert_eq!(decoded(&slice(text("abc"), None).unwrap()), b"abc");
+
+        let past = ByteRange {
+            offset: 8,
+            length: None,
+        };
+        assert_eq!(
+            slice(text("abcdefg"), Some(past)).unwrap_err(),
+            "Range starts at byte 8, past the end of template://logs (7 bytes)"
+        );
+    }
+
+    #[test]
+    fn refuses_resources_with_several_contents() {
+        let mut result = text("a");
+        result.contents.push(result.contents[0].clone());
+        assert_eq!(
+            slice(result, None).unwrap_err(),
+            "Only resources with one content can be read by range, not 2"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Tools that only run after another tool in the same session
+//!
+//! Some tools only make sense once another has run, such as a commit after
+//! its prepare. The `requires` setting of such a tool (see
+//! [`crate::tool_settings`]) lists the tools it needs a successful call of
+//! earlier in the session. A call before then fails
+//! without running, with a `precondition_failed` error naming the tool to
+//! call first:
+//!
+//! ```json
+//! {
+//!   "code": -32600,
+//!   "message": "Tool 'import_snapshot' needs a successful call of 'export_snapshot' earlier in the session",
+//!   "data": { "error": "precondition_failed", "requires": "export_snapshot" }
+//! }
+//! ```
+//!
+//! `import_snapshot` is the example: it may replace every entry, so an agent
+//! has to export a snapshot to restore first. `tools/list` still shows such
+//! tools, with the tools they need as `x-requires` in their input schema, so
+//! clients can call them in order.
+//!
+//! A tool's requirement only holds within a session: calls are checked
+//! against the [`CallHistory`] of the backend, which
+//! [`crate::raw_messages::RawMessageHandler`] gives each STDIO client of its
+//! own. The 0.11 HTTP transport shows the backend no sessions, so one
+//! client's calls can't be told from another's: its connection has a
+//! [`CallHistory::shared`], which refuses every tool with a requirement
+//! instead of letting one client's call satisfy another's.
+
+use crate::tool_settings;
+use pulseengine_mcp_protocol::Tool;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+/// Tools `tool` needs called before it
+pub fn required_before(tool: &str) -> &'static [&'static str] {
+    tool_settings::settings_of(tool).requires
+}
+
+/// Show the tools a tool needs called first as `x-requires` in its schema
+pub fn apply_to_schema(tool: &mut Tool) {
+    let required = required_before(&tool.name);
+    if required.is_empty() {
+        return;
+    }
+    if let Some(schema) = tool.input_schema.as_object_mut() {
+        schema.insert("x-requires".to_string(), json!(required));
+    }
+}
+
+/// A call to a tool before a tool it needs was called
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPriorCall {
+    pub tool: String,
+    pub requires: &'static str,
+    /// The call came over a connection shared by clients, which has no
+    /// sessions to keep the call in
+    pub shared: bool,
+}
+
+impl std::fmt::Display for MissingPriorCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tool '{}' needs a successful call of '{}' earlier in the session",
+            self.tool, self.requires
+        )?;
+        if self.shared {
+            write!(f, ", which this transport doesn't keep; call it over STDIO")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingPriorCall {}
+
+impl From<MissingPriorCall> for pulseengine_mcp_protocol::Error {
+    fn from(missing: MissingPriorCall) -> Self {
+        Self::with_data(
+            pulseengine_mcp_protocol::ErrorCode::InvalidRequest,
+            missing.to_string(),
+            json!({ "error": "precondition_failed", "requires": missing.requires }),
+        )
+    }
+}
+
+/// The tools called successfully in a session, shared by the clones of a
+/// backend
+#[derive(Debug, Clone, Default)]
+pub struct CallHistory {
+    succeeded: Arc<RwLock<HashSet<String>>>,
+    shared: bool,
+}
+
+impl CallHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// History of a connection whose clients can't be told apart, which
+    /// refuses every tool with a requirement
+    pub fn shared() -> Self {
+        Self {
+            shared: true,
+            ..Self::default()
+        }
+    }
+
+    /// Note a successful call of `tool`
+    pub fn record(&self, tool: &str) {
+        self.succeeded
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(tool.to_string());
+    }
+
+    pub fn has_called(&self, tool: &str) -> bool {
+        self.succeeded
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains(tool)
+    }
+
+    /// Reject a call to `tool` if a tool it needs wasn't called yet
+    pub fn check(&self, tool: &str) -> Result<(), MissingPriorCall> {
+        match required_before(tool)
+            .iter()
+            .find(|required| self.shared || !self.has_called(required))
+        {
+            Some(required) => Err(MissingPriorCall {
+                tool: tool.to_string(),
+                requires: required,
+                shared: self.shared,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_the_required_tool_first() {
+        let history = CallHistory::new();
+        let missing = history.check("import_snapshot").unwrap_err();
+        assert_eq!(
+            missing,
+            MissingPriorCall {
+                tool: "import_snapshot".to_string(),
+                requires: "export_snapshot",
+                shared: false,
+            }
+        );
+        let error = pulseengine_mcp_protocol::Error::from(missing);
+        assert_eq!(
+            error.message,
+            "Tool 'import_snapshot' needs a successful call of 'export_snapshot' earlier in the session"
+        );
+        assert_eq!(
+            error.data,
+            Some(json!({ "error": "precondition_failed", "requires": "export_snapshot" }))
+        );
+
+        history.record("export_snapshot");
+        assert_eq!(history.check("import_snapshot"), Ok(()));
+        assert_eq!(CallHistory::new().check("echo"), Ok(()));
+    }
+
+    #[test]
+    fn shared_connections_refuse_ordered_tools() {
+        let history = CallHistory::shared();
+        history.record("export_snapshot");
+        let missing = history.check("import_snapshot").unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "Tool 'import_snapshot' needs a successful call of 'export_snapshot' earlier in the \
+             session, which this transport doesn't keep; call it over STDIO"
+        );
+        assert_eq!(history.check("echo"), Ok(()));
+    }
+
+    #[test]
+    fn clones_share_the_history() {
+        let history = CallHistory::new();
+        history.clone().record("export_snapshot");
+        assert!(history.has_called("export_snapshot"));
+        assert!(!history.has_called("echo"));
+    }
+
+    #[test]
+    fn shows_requirements_in_the_schema() {
+        let tool = |name: &str| Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        };
+        let mut import = tool("import_snapshot");
+        apply_to_schema(&mut import);
+        assert_eq!(
+            import.input_schema["x-requires"],
+            json!(["export_snapshot"])
+        );
+        let mut echo = tool("echo");
+        apply_to_schema(&mut echo);
+        assert_eq!(echo.input_schema, json!({ "type": "object" }));
+    }
+}
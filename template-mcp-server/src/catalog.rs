@@ -0,0 +1,226 @@
+//! Fixed catalogs served as resources
+//!
+//! A catalog is a unit enum implementing [`CatalogItem`], such as
+//! [`ReportType`](crate::ReportType). [`Catalog::of`] finds its variants in
+//! the enum's JSON schema, so every variant, including any added later, is
+//! served as the resource `template://catalog/{variant}`, holding the variant's
+//! [`details`](CatalogItem::details), and listed by the `list_catalog` tool.
+//! Variant names are their serde names and descriptions their doc comments.
+//!
+//! The resources are listed in `resources/list` like the others, sorted by
+//! name, since the schema doesn't keep the declaration order.
+
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, ResourceContents};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// URI of a catalog item is this followed by its name
+pub const CATALOG_URI_PREFIX: &str = "template://catalog/";
+
+/// A variant of a unit enum served as a catalog item
+pub trait CatalogItem: JsonSchema + DeserializeOwned {
+    /// Content of the item's resource
+    fn details(&self) -> Value;
+}
+
+/// An item as `list_catalog` lists it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    entry: CatalogEntry,
+    details: Value,
+}
+
+/// Every item of a catalog, with its details
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    items: Vec<Item>,
+}
+
+impl Catalog {
+    /// The catalog of `T`'s variants
+    ///
+    /// Fails unless `T` is an enum of unit variants.
+    pub fn of<T: CatalogItem>() -> anyhow::Result<Self> {
+        let schema = serde_json::to_value(schemars::schema_for!(T))?;
+        let mut items = variants(&schema)
+            .into_iter()
+            .map(|(name, description)| {
+                let item: T = serde_json::from_value(Value::String(name.clone()))
+                    .map_err(|e| anyhow::anyhow!("Catalog item '{name}' can't be read: {e}"))?;
+                Ok(Item {
+                    entry: CatalogEntry {
+                        uri: format!("{CATALOG_URI_PREFIX}{name}"),
+                        name,
+                        description,
+                    },
+                    details: item.details(),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if items.is_empty() {
+            anyhow::bail!("{} has no unit variants to catalog", T::schema_name());
+        }
+        items.sort_by(|a, b| a.entry.name.cmp(&b.entry.name));
+        Ok(Self { items })
+    }
+
+    pub fn entries(&self) -> Vec<CatalogEntry> {
+        self.items.iter().map(|item| item.entry.clone()).collect()
+    }
+
+    /// Resource listings for every item
+    pub fn resources(&self) -> Vec<Resource> {
+        self.items
+            .iter()
+            .map(|item| Resource {
+                uri: item.entry.uri.clone(),
+                name: item.entry.name.clone(),
+                description: item.entry.description.clone(),
+                mime_type: Some("application/json".to_string()),
+                annotations: None,
+                raw: None,
+            })
+            .collect()
+    }
+
+    /// Read the item at `uri` as JSON text
+    pub fn read(&self, uri: &str) -> anyhow::Result<ReadResourceResult> {
+        let name = uri.strip_prefix(CATALOG_URI_PREFIX).unwrap_or(uri);
+        let item = self
+            .items
+            .iter()
+            .find(|item| item.entry.name == name)
+            .ok_or_else(|| anyhow::anyhow!("No catalog item named '{name}'"))?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: item.entry.uri.clone(),
+                mime_type: Some("application/json".to_string()),
+                text: Some(serde_json::to_string_pretty(&item.details)?),
+                blob: None,
+            }],
+        })
+    }
+}
+
+/// Whether `uri` names a catalog item
+pub fn matches(uri: &str) -> bool {
+    uri.starts_with(CATALOG_URI_PREFIX)
+}
+
+/// Names and descriptions of the variants of a unit enum's `schema`
+///
+/// Variants without a doc comment are an `enum` of names; each documented one
+/// is a `oneOf` alternative with a `const` name.
+fn variants(schema: &Value) -> Vec<(String, Option<String>)> {
+    let alternatives = match schema.get("oneOf").and_then(Value::as_array) {
+        Some(alternatives) => alternatives.iter().collect(),
+        None => vec![schema],
+    };
+    let mut variants = Vec::new();
+    for alternative in alternatives {
+        if let Some(name) = alternative.get("const").and_then(Value::as_str) {
+            let description = alternative
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            variants.push((name.to_string(), description));
+        }
+        let names = alternative.get("enum").and_then(Value::as_array);
+        for name in names.into_iter().flatten().filter_map(Value::as_str) {
+            variants.push((name.to_string(), None));
+        }
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Size {
+        /// The smallest size
+        Small,
+        Large,
+    }
+
+    impl CatalogItem for Size {
+        fn details(&self) -> Value {
+            match self {
+                Self::Small => json!({ "litres": 1 }),
+                Self::Large => json!({ "litres": 5 }),
+            }
+        }
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct NotAnEnum {
+        _size: u32,
+    }
+
+    impl CatalogItem for NotAnEnum {
+        fn details(&self) -> Value {
+            Value::Null
+        }
+    }
+
+    #[test]
+    fn lists_every_variant_by_name() {
+        let catalog = Catalog::of::<Size>().unwrap();
+        assert_eq!(
+            catalog.entries(),
+            [
+                CatalogEntry {
+                    name: "large".to_string(),
+                    uri: "template://catalog/large".to_string(),
+                    description: None,
+                },
+                CatalogEntry {
+                    name: "small".to_string(),
+                    uri: "template://catalog/small".to_string(),
+                    description: Some("The smallest size".to_string()),
+                },
+            ]
+        );
+        let uris: Vec<String> = catalog.resources().into_iter().map(|r| r.uri).collect();
+        assert!(uris.iter().all(|uri| matches(uri)));
+    }
+
+    #[test]
+    fn reads_an_items_details() {
+        let catalog = Catalog::of::<Size>().unwrap();
+        let read = catalog.read("template://catalog/small").unwrap();
+        let text = read.contents[0].text.as_deref().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(text).unwrap(),
+            json!({ "litres": 1 })
+        );
+        assert_eq!(
+            catalog
+                .read("template://catalog/medium")
+                .unwrap_err()
+                .to_string(),
+            "No catalog item named 'medium'"
+        );
+    }
+
+    #[test]
+    fn refuses_types_without_unit_variants() {
+        assert_eq!(
+            Catalog::of::<NotAnEnum>().unwrap_err().to_string(),
+            "NotAnEnum has no unit variants to catalog"
+        );
+    }
+}
@@ -0,0 +1,496 @@
+//! Circuit breaker for tools that call flaky dependencies
+//!
+//! After `threshold` consecutive failures the breaker opens and every call is
+//! rejected immediately with a "circuit open" error until `cooldown` has
+//! elapsed. The next call is then let through as a half-open trial: success
+//! closes the circuit again, failure re-opens it for another cooldown.
+//!
+//! Each tool with the `circuit_breaker` setting (see [`crate::tool_settings`])
+//! gets a breaker named after it, in [`CircuitBreakers`], and the backend runs
+//! every call of the tool through it. A call fails for the breaker when the
+//! tool itself fails with an internal error or times out; refused calls and
+//! the tool's structured errors, such as invalid input, don't count. Its
+//! [`BreakerSettings`] are `ToolsConfig::circuit_breaker`, e.g.
+//! `MCP_CIRCUIT_BREAKER='{"threshold":3,"cooldown_seconds":10}'`, or the
+//! tool's own entry in `ToolsConfig::tool_circuit_breakers`, read when the
+//! server starts. Their state, trips and rejected calls are in `ServerStatus`
+//! and, in the Prometheus text format of [`render_metrics`], on
+//! `GET /metrics` next to `/healthz`; see [`crate::health`].
+
+use crate::tool_settings::{ToolSettings, TOOL_SETTINGS};
+use crate::ServerConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Names of the breakers the server runs, those of the guarded tools
+pub fn breakers() -> impl Iterator<Item = &'static str> {
+    guarded(TOOL_SETTINGS)
+}
+
+fn guarded(entries: &'static [ToolSettings]) -> impl Iterator<Item = &'static str> {
+    entries
+        .iter()
+        .filter(|settings| settings.circuit_breaker)
+        .map(|settings| settings.tool)
+}
+
+/// The breaker of every tool with the `circuit_breaker` setting
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    by_tool: BTreeMap<&'static str, Arc<CircuitBreaker>>,
+}
+
+impl CircuitBreakers {
+    /// Breakers for the tools of [`TOOL_SETTINGS`], with the settings of
+    /// `config`
+    pub fn new(config: &ServerConfig) -> Self {
+        Self::for_entries(TOOL_SETTINGS, config)
+    }
+
+    fn for_entries(entries: &'static [ToolSettings], config: &ServerConfig) -> Self {
+        let by_tool = guarded(entries)
+            .map(|tool| {
+                let breaker = CircuitBreaker::with_settings(tool, config.breaker_settings(tool));
+                (tool, Arc::new(breaker))
+            })
+            .collect();
+        Self { by_tool }
+    }
+
+    /// The breaker guarding `tool`, if it has one
+    pub fn get(&self, tool: &str) -> Option<&Arc<CircuitBreaker>> {
+        self.by_tool.get(tool)
+    }
+
+    /// Every breaker, ordered by tool name
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<CircuitBreaker>> {
+        self.by_tool.values()
+    }
+
+    /// The status of every breaker, ordered by tool name
+    pub fn statuses(&self) -> Vec<CircuitBreakerStatus> {
+        self.iter().map(|breaker| breaker.status()).collect()
+    }
+}
+
+/// When a breaker opens and how long it stays open
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerSettings {
+    /// Consecutive failures that open the circuit
+    pub threshold: u32,
+    /// Seconds the circuit stays open before a trial call
+    pub cooldown_seconds: u64,
+}
+
+impl Default for BreakerSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 5,
+            cooldown_seconds: 30,
+        }
+    }
+}
+
+/// Current state of a circuit breaker
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls pass through normally
+    Closed,
+    /// Calls are rejected until the cooldown elapses
+    Open,
+    /// A single trial call is allowed to probe recovery
+    HalfOpen,
+}
+
+/// Point-in-time view of a breaker (exposed through `ServerStatus`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CircuitBreakerStatus {
+    pub name: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Times the circuit opened
+    pub times_opened: u64,
+    /// Calls rejected while the circuit was open
+    pub rejected_calls: u64,
+    pub threshold: u32,
+    pub cooldown_seconds: u64,
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+    times_opened: u64,
+    rejected_calls: u64,
+}
+
+/// Guards calls to a dependency and short-circuits them while it is failing
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker that opens after `threshold` consecutive failures
+    pub fn new(name: impl Into<String>, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name: name.into(),
+            threshold: threshold.max(1),
+            cooldown,
+            inner: Mutex::new(BreakerInner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_in_flight: false,
+                times_opened: 0,
+                rejected_calls: 0,
+            }),
+        }
+    }
+
+    /// Create a closed breaker with `settings`
+    pub fn with_settings(name: impl Into<String>, settings: BreakerSettings) -> Self {
+        Self::new(
+            name,
+            settings.threshold,
+            Duration::from_secs(settings.cooldown_seconds),
+        )
+    }
+
+    /// Run `operation` through the breaker
+    ///
+    /// Returns a "circuit open" error without running the operation while the
+    /// circuit is open (or while a half-open trial is already in flight).
+    pub async fn call<F, Fut, T>(&self, operation: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.call_counting(operation(), Result::is_err).await?
+    }
+
+    /// Run `operation` through the breaker, counting the outputs `failed`
+    /// picks out as failures
+    ///
+    /// Returns the "circuit open" error as [`call`](Self::call) does, and
+    /// the output otherwise.
+    pub async fn call_counting<Fut, T>(
+        &self,
+        operation: Fut,
+        failed: impl FnOnce(&T) -> bool,
+    ) -> anyhow::Result<T>
+    where
+        Fut: Future<Output = T>,
+    {
+        self.acquire()?;
+        let outcome = PendingOutcome { breaker: self };
+        let output = operation.await;
+        std::mem::forget(outcome);
+        self.record(!failed(&output));
+        Ok(output)
+    }
+
+    /// The name of the breaker, that of the tool it guards
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Current state, accounting for an elapsed cooldown
+    pub fn state(&self) -> CircuitState {
+        let inner = self.lock();
+        self.effective_state(&inner)
+    }
+
+    /// Snapshot of the breaker for status reporting
+    pub fn status(&self) -> CircuitBreakerStatus {
+        let inner = self.lock();
+        CircuitBreakerStatus {
+            name: self.name.clone(),
+            state: self.effective_state(&inner),
+            consecutive_failures: inner.consecutive_failures,
+            times_opened: inner.times_opened,
+            rejected_calls: inner.rejected_calls,
+            threshold: self.threshold,
+            cooldown_seconds: self.cooldown.as_secs(),
+        }
+    }
+
+    fn acquire(&self) -> anyhow::Result<()> {
+        let mut inner = self.lock();
+        match self.effective_state(&inner) {
+            CircuitState::Closed => Ok(()),
+            CircuitState::HalfOpen if !inner.trial_in_flight => {
+                inner.state = CircuitState::HalfOpen;
+                inner.trial_in_flight = true;
+                tracing::info!(breaker = %self.name, "Circuit half-open, probing dependency");
+                Ok(())
+            }
+            _ => {
+                inner.rejected_calls += 1;
+                let remaining = inner
+                    .opened_at
+                    .map(|at| self.cooldown.saturating_sub(at.elapsed()))
+                    .unwrap_or_default();
+                Err(anyhow::anyhow!(
+                    "Circuit open for '{}': dependency is failing, retry in {}s",
+                    self.name,
+                    remaining.as_secs()
+                ))
+            }
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut inner = self.lock();
+        inner.trial_in_flight = false;
+
+        if success {
+            if inner.state != CircuitState::Closed {
+                tracing::info!(breaker = %self.name, "Circuit closed, dependency recovered");
+            }
+            inner.state = CircuitState::Closed;
+            inner.consecutive_failures = 0;
+            inner.opened_at = None;
+            return;
+        }
+
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        if inner.state == CircuitState::HalfOpen || inner.consecutive_failures >= self.threshold {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.times_opened += 1;
+            tracing::warn!(
+                breaker = %self.name,
+                failures = inner.consecutive_failures,
+                cooldown_secs = self.cooldown.as_secs(),
+                "Circuit opened"
+            );
+        }
+    }
+
+    fn effective_state(&self, inner: &BreakerInner) -> CircuitState {
+        match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(at)) if at.elapsed() >= self.cooldown => {
+                CircuitState::HalfOpen
+            }
+            (state, _) => state,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Counts a call as failed if its future is dropped before completing, so a
+/// cancelled half-open trial can't leave the breaker stuck
+struct PendingOutcome<'a> {
+    breaker: &'a CircuitBreaker,
+}
+
+impl Drop for PendingOutcome<'_> {
+    fn drop(&mut self) {
+        self.breaker.record(false);
+    }
+}
+
+/// `statuses` in the Prometheus text exposition format
+pub fn render_metrics(statuses: &[CircuitBreakerStatus]) -> String {
+    let mut out = String::new();
+    let mut metric =
+        |name: &str, kind: &str, help: &str, value: fn(&CircuitBreakerStatus) -> u64| {
+            out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for status in statuses {
+                out.push_str(&format!(
+                    "{name}{{breaker=\"{}\"}} {}\n",
+                    status.name,
+                    value(status)
+                ));
+            }
+        };
+    metric(
+        "mcp_circuit_breaker_state",
+        "gauge",
+        "State of the circuit: 0 closed, 1 half-open, 2 open",
+        |status| match status.state {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        },
+    );
+    metric(
+        "mcp_circuit_breaker_trips_total",
+        "counter",
+        "Times the circuit opened",
+        |status| status.times_opened,
+    );
+    metric(
+        "mcp_circuit_breaker_rejections_total",
+        "counter",
+        "Calls rejected while the circuit was open",
+        |status| status.rejected_calls,
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_millis(50);
+
+    async fn fail(breaker: &CircuitBreaker) -> anyhow::Result<()> {
+        breaker
+            .call(|| async { Err::<(), _>(anyhow::anyhow!("down")) })
+            .await
+    }
+
+    async fn succeed(breaker: &CircuitBreaker) -> anyhow::Result<()> {
+        breaker.call(|| async { Ok(()) }).await
+    }
+
+    #[tokio::test]
+    async fn opens_then_closes_after_the_cooldown() {
+        let breaker = CircuitBreaker::new("dep", 2, COOLDOWN);
+        assert!(fail(&breaker).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(fail(&breaker).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let rejected = succeed(&breaker).await.unwrap_err().to_string();
+        assert!(rejected.starts_with("Circuit open for 'dep'"), "{rejected}");
+
+        std::thread::sleep(COOLDOWN);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        succeed(&breaker).await.unwrap();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let status = breaker.status();
+        assert_eq!(
+            (
+                status.times_opened,
+                status.rejected_calls,
+                status.consecutive_failures
+            ),
+            (1, 1, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_trial_reopens() {
+        let breaker = CircuitBreaker::new("dep", 1, COOLDOWN);
+        assert!(fail(&breaker).await.is_err());
+        std::thread::sleep(COOLDOWN);
+        assert!(fail(&breaker).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.status().times_opened, 2);
+    }
+
+    #[tokio::test]
+    async fn cancelled_trial_counts_as_failed() {
+        let breaker = CircuitBreaker::new("dep", 1, COOLDOWN);
+        assert!(fail(&breaker).await.is_err());
+        std::thread::sleep(COOLDOWN);
+        let trial = breaker.call(std::future::pending::<anyhow::Result<()>>);
+        assert!(tokio::time::timeout(Duration::from_millis(10), trial)
+            .await
+            .is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn uses_its_settings() {
+        let breaker = CircuitBreaker::with_settings(
+            "dep",
+            BreakerSettings {
+                threshold: 3,
+                cooldown_seconds: 7,
+            },
+        );
+        let status = breaker.status();
+        assert_eq!((status.threshold, status.cooldown_seconds), (3, 7));
+    }
+
+    #[test]
+    fn guards_the_flagged_tools() {
+        static ENTRIES: &[ToolSettings] = &[
+            ToolSettings {
+                circuit_breaker: true,
+                ..ToolSettings::new("echo")
+            },
+            ToolSettings::new("create_data"),
+        ];
+        let mut config = ServerConfig::default();
+        config.tools.tool_circuit_breakers.insert(
+            "echo".to_string(),
+            BreakerSettings {
+                threshold: 2,
+                cooldown_seconds: 9,
+            },
+        );
+        let breakers = CircuitBreakers::for_entries(ENTRIES, &config);
+
+        let status = breakers.get("echo").expect("echo is flagged").status();
+        assert_eq!(
+            (
+                status.name.as_str(),
+                status.threshold,
+                status.cooldown_seconds
+            ),
+            ("echo", 2, 9)
+        );
+        assert!(breakers.get("create_data").is_none());
+        assert_eq!(breakers.statuses().len(), 1);
+    }
+
+    #[test]
+    fn guards_the_tools_of_the_server() {
+        let breakers = CircuitBreakers::new(&ServerConfig::default());
+        let names: Vec<&str> = breakers.iter().map(|breaker| breaker.name()).collect();
+        assert_eq!(names, super::breakers().collect::<Vec<_>>());
+        assert!(breakers.get("create_data").is_some());
+        assert!(breakers.get("create_data_bulk").is_none());
+    }
+
+    #[tokio::test]
+    async fn counts_only_the_picked_failures() {
+        let breaker = CircuitBreaker::new("dep", 1, Duration::from_secs(60));
+        let output = breaker
+            .call_counting(async { Err::<(), _>("invalid input") }, |_| false)
+            .await
+            .unwrap();
+        assert_eq!(output, Err("invalid input"));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let output = breaker
+            .call_counting(async { Err::<(), _>("down") }, Result::is_err)
+            .await
+            .unwrap();
+        assert_eq!(output, Err("down"));
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn renders_prometheus_metrics() {
+        let breaker = CircuitBreaker::new("dep", 1, Duration::from_secs(60));
+        assert!(fail(&breaker).await.is_err());
+        assert!(succeed(&breaker).await.is_err());
+
+        let metrics = render_metrics(&[breaker.status()]);
+        assert!(metrics.contains("# TYPE mcp_circuit_breaker_state gauge\n"));
+        assert!(metrics.contains("mcp_circuit_breaker_state{breaker=\"dep\"} 2\n"));
+        assert!(metrics.contains("mcp_circuit_breaker_trips_total{breaker=\"dep\"} 1\n"));
+        assert!(metrics.contains("mcp_circuit_breaker_rejections_total{breaker=\"dep\"} 1\n"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Tools that need a capability of the client
+//!
+//! Some tools only work with a client that supports sampling or roots. The
+//! `capabilities` setting of such a tool (see [`crate::tool_settings`]) lists
+//! the capabilities it needs. A tool
+//! needing one the client didn't advertise in `initialize` is left out of
+//! `tools/list`, and calls to it fail before it runs with a
+//! `precondition_failed` error naming the capability:
+//!
+//! ```json
+//! {
+//!   "code": -32600,
+//!   "message": "Tool 'list_roots' needs the client capability 'roots', which the client didn't advertise",
+//!   "data": { "error": "precondition_failed", "capability": "roots" }
+//! }
+//! ```
+//!
+//! The 0.11 transports don't show the backend the client's `initialize`
+//! params, so the capabilities are recorded by
+//! [`crate::raw_messages::RawMessageHandler`], which serves STDIO and gives
+//! each session its own [`ClientCapabilities`]: one client's capabilities
+//! never change the tools another is served. Until a client's capabilities
+//! are recorded, as over HTTP, no tool is held back.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+
+/// A capability a client advertises in `initialize`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientCapability {
+    Roots,
+    Sampling,
+    Elicitation,
+}
+
+impl ClientCapability {
+    /// Key of the capability in the client's `capabilities`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Roots => "roots",
+            Self::Sampling => "sampling",
+            Self::Elicitation => "elicitation",
+        }
+    }
+}
+
+/// Client capabilities `tool` needs
+pub fn required_by(tool: &str) -> &'static [ClientCapability] {
+    crate::tool_settings::settings_of(tool).capabilities
+}
+
+/// A call to a tool needing a capability the client didn't advertise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingCapability {
+    pub tool: String,
+    pub capability: ClientCapability,
+}
+
+impl std::fmt::Display for MissingCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tool '{}' needs the client capability '{}', which the client didn't advertise",
+            self.tool,
+            self.capability.name()
+        )
+    }
+}
+
+impl std::error::Error for MissingCapability {}
+
+impl From<MissingCapability> for pulseengine_mcp_protocol::Error {
+    fn from(missing: MissingCapability) -> Self {
+        Self::with_data(
+            pulseengine_mcp_protocol::ErrorCode::InvalidRequest,
+            missing.to_string(),
+            json!({ "error": "precondition_failed", "capability": missing.capability }),
+        )
+    }
+}
+
+/// The capabilities the connected client advertised, shared by the clones of
+/// a backend
+#[derive(Debug, Clone, Default)]
+pub struct ClientCapabilities {
+    advertised: Arc<RwLock<Option<Value>>>,
+}
+
+impl ClientCapabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the `capabilities` of the client's `initialize` params
+    pub fn record(&self, initialize_params: &Value) {
+        let capabilities = initialize_params
+            .get("capabilities")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        *self
+            .advertised
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(capabilities);
+    }
+
+    /// Whether the client advertised `capability`; true until its
+    /// capabilities are recorded
+    pub fn has(&self, capability: ClientCapability) -> bool {
+        match &*self
+            .advertised
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+        {
+            Some(advertised) => advertised
+                .get(capability.name())
+                .is_some_and(|advertised| !advertised.is_null()),
+            None => true,
+        }
+    }
+
+    /// Reject a call to `tool` if it needs a capability the client lacks
+    pub fn check(&self, tool: &str) -> Result<(), MissingCapability> {
+        match required_by(tool)
+            .iter()
+            .find(|capability| !self.has(**capability))
+        {
+            Some(capability) => Err(MissingCapability {
+                tool: tool.to_string(),
+                capability: *capability,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_available(&self, tool: &str) -> bool {
+        self.check(tool).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_nothing_back_until_recorded() {
+        let capabilities = ClientCapabilities::new();
+        assert!(capabilities.has(ClientCapability::Roots));
+        assert!(capabilities.is_available("list_roots"));
+    }
+
+    #[test]
+    fn checks_the_advertised_capabilities() {
+        let capabilities = ClientCapabilities::new();
+        capabilities.record(&json!({ "capabilities": { "sampling": {}, "roots": null } }));
+        assert!(capabilities.has(ClientCapability::Sampling));
+        assert!(!capabilities.has(ClientCapability::Roots));
+        assert!(capabilities.is_available("echo"));
+
+        let missing = capabilities.clone().check("list_roots").unwrap_err();
+        let error = pulseengine_mcp_protocol::Error::from(missing);
+        assert_eq!(
+            error.message,
+            "Tool 'list_roots' needs the client capability 'roots', which the client didn't advertise"
+        );
+        assert_eq!(
+            error.data,
+            Some(json!({ "error": "precondition_failed", "capability": "roots" }))
+        );
+    }
+
+    #[test]
+    fn clients_without_capabilities_have_none() {
+        let capabilities = ClientCapabilities::new();
+        capabilities.record(&json!({}));
+        assert!(!capabilities.has(ClientCapability::Elicitation));
+        assert!(!capabilities.is_available("list_roots"));
+    }
+}
@@ -0,0 +1,197 @@
+//! Notifications to the client from within tools
+//!
+//! A tool that runs into something the user should know about, without it
+//! failing the call, warns through its context:
+//!
+//! ```ignore
+//! let ctx = RequestContext::current().unwrap_or_default();
+//! ctx.warn("Some input was ignored");
+//! ```
+//!
+//! The warning reaches the client at once as an MCP log message, apart from
+//! whatever the tool returns, with the tool's name as the logger:
+//!
+//! ```json
+//! {
+//!   "jsonrpc": "2.0",
+//!   "method": "notifications/message",
+//!   "params": { "level": "warning", "logger": "process_list", "data": "Some input was ignored" }
+//! }
+//! ```
+//!
+//! Notifications travel through the [`ClientNotifier`] of the connection,
+//! which [`crate::raw_messages::RawMessageHandler::serve`] connects to its
+//! output; the ones a call sends are written before the call's response. The
+//! 0.11 transports can't send notifications, so over HTTP, or with no
+//! connection at all, they only go to the server log.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{mpsc, oneshot};
+
+/// Method of the notifications carrying log messages
+pub const LOG_MESSAGE_NOTIFICATION: &str = "notifications/message";
+
+/// Severity of a log message, as named by MCP
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+}
+
+/// What the connection writes to the client, in order
+#[derive(Debug)]
+pub(crate) enum Outgoing {
+    Line(String),
+    /// Answered once every line before it is written
+    Flush(oneshot::Sender<()>),
+}
+
+/// Sends notifications to the connected client, shared by the clones of a
+/// backend
+#[derive(Debug, Clone, Default)]
+pub struct ClientNotifier {
+    outgoing: Arc<RwLock<Option<mpsc::UnboundedSender<Outgoing>>>>,
+}
+
+impl ClientNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether notifications reach a client
+    pub fn is_connected(&self) -> bool {
+        self.sender().is_some()
+    }
+
+    /// Send a notification; false if no client is connected
+    pub fn notify(&self, method: &str, params: Value) -> bool {
+        let notification = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.sender().is_some_and(|sender| {
+            sender
+                .send(Outgoing::Line(notification.to_string()))
+                .is_ok()
+        })
+    }
+
+    /// Send `data` as a log message at `level` from `logger`, logging it on
+    /// the server instead if no client is connected
+    pub fn log_message(&self, level: LogLevel, logger: Option<&str>, data: Value) {
+        let mut params = json!({ "level": level, "data": data });
+        if let Some(logger) = logger {
+            params["logger"] = json!(logger);
+        }
+        if !self.notify(LOG_MESSAGE_NOTIFICATION, params) {
+            let logger = logger.unwrap_or_default();
+            match level {
+                LogLevel::Debug => tracing::debug!("{logger}: {data}"),
+                LogLevel::Info | LogLevel::Notice => tracing::info!("{logger}: {data}"),
+                LogLevel::Warning => tracing::warn!("{logger}: {data}"),
+                LogLevel::Error => tracing::error!("{logger}: {data}"),
+            }
+        }
+    }
+
+    /// Route notifications to the returned receiver from now on
+    pub(crate) fn connect(&self) -> mpsc::UnboundedReceiver<Outgoing> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        *self
+            .outgoing
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(sender);
+        receiver
+    }
+
+    /// Stop routing notifications, which closes the receiver
+    pub(crate) fn disconnect(&self) {
+        self.outgoing
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+    }
+
+    /// Wait until the notifications sent so far are written
+    pub(crate) async fn flush(&self) {
+        let Some(sender) = self.sender() else {
+            return;
+        };
+        let (done, flushed) = oneshot::channel();
+        if sender.send(Outgoing::Flush(done)).is_ok() {
+            let _ = flushed.await;
+        }
+    }
+
+    fn sender(&self) -> Option<mpsc::UnboundedSender<Outgoing>> {
+        self.outgoing
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(outgoing: Option<Outgoing>) -> Value {
+        match outgoing {
+            Some(Outgoing::Line(line)) => serde_json::from_str(&line).unwrap(),
+            other => panic!("expected a line, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn sends_notifications_only_while_connected() {
+        let notifier = ClientNotifier::new();
+        assert!(!notifier.is_connected());
+        assert!(!notifier.notify("notifications/test", json!({})));
+
+        let mut outgoing = notifier.connect();
+        assert!(notifier.notify("notifications/test", json!({ "n": 1 })));
+        assert_eq!(
+            line(outgoing.try_recv().ok()),
+            json!({ "jsonrpc": "2.0", "method": "notifications/test", "params": { "n": 1 } })
+        );
+
+        notifier.disconnect();
+        assert!(!notifier.notify("notifications/test", json!({})));
+    }
+
+    #[test]
+    fn log_messages_name_their_level_and_logger() {
+        let notifier = ClientNotifier::new();
+        let mut outgoing = notifier.connect();
+        notifier.log_message(LogLevel::Warning, Some("process_list"), json!("ignored"));
+        assert_eq!(
+            line(outgoing.try_recv().ok())["params"],
+            json!({ "level": "warning", "logger": "process_list", "data": "ignored" })
+        );
+    }
+
+    #[tokio::test]
+    async fn flush_waits_for_earlier_lines() {
+        let notifier = ClientNotifier::new();
+        let mut outgoing = notifier.connect();
+        notifier.notify("notifications/test", json!({}));
+        let writer = tokio::spawn(async move {
+            let mut lines = 0;
+            while let Some(item) = outgoing.recv().await {
+                match item {
+                    Outgoing::Line(_) => lines += 1,
+                    Outgoing::Flush(done) => {
+                        let _ = done.send(());
+                        return lines;
+                    }
+                }
+            }
+            lines
+        });
+        notifier.flush().await;
+        assert_eq!(writer.await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+//! Tool parameter defaults sourced from server configuration
+//!
+//! A tool's `config_defaults` setting (see [`crate::tool_settings`]) pairs
+//! parameters with `ServerConfig` fields, for defaults shared by several tools,
+//! such as a region or tenant. When a client omits such a parameter, the
+//! backend fills it from its field before the tool runs. The lookup happens on
+//! every call, so a config reload changes the default without a restart, and
+//! `tools/list` advertises the currently resolved value as the schema
+//! `default`.
+
+use crate::{config_loading, ServerConfig};
+use pulseengine_mcp_protocol::Tool;
+use serde_json::Value;
+
+/// Parameters of `tool` and the config fields they default to
+fn defaults_for(tool: &str) -> &'static [(&'static str, &'static str)] {
+    crate::tool_settings::settings_of(tool).config_defaults
+}
+
+fn config_value(config: &ServerConfig, field: &str) -> Option<Value> {
+    let config = serde_json::to_value(config).ok()?;
+    config_loading::field_value(&config, field).cloned()
+}
+
+/// Fill omitted (or null) arguments of `tool` from `config`
+pub fn apply_to_arguments(tool: &str, arguments: &mut Option<Value>, config: &ServerConfig) {
+    let args = arguments.get_or_insert_with(|| Value::Object(Default::default()));
+    let Some(args) = args.as_object_mut() else {
+        return;
+    };
+
+    for &(param, config_field) in defaults_for(tool) {
+        let missing = args.get(param).is_none_or(Value::is_null);
+        if missing {
+            if let Some(value) = config_value(config, config_field) {
+                args.insert(param.to_string(), value);
+            }
+        }
+    }
+}
+
+/// Advertise the resolved defaults in a tool's input schema
+///
+/// Parameters with a config default are no longer required from the client.
+pub fn apply_to_schema(tool: &mut Tool, config: &ServerConfig) {
+    for &(param, config_field) in defaults_for(&tool.name) {
+        let Some(value) = config_value(config, config_field) else {
+            continue;
+        };
+
+        if let Some(property) = tool
+            .input_schema
+            .get_mut("properties")
+            .and_then(|p| p.get_mut(param))
+            .and_then(Value::as_object_mut)
+        {
+            property.insert("default".to_string(), value);
+        }
+
+        if let Some(required) = tool
+            .input_schema
+            .get_mut("required")
+            .and_then(Value::as_array_mut)
+        {
+            required.retain(|name| name != param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(region: &str) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.tools.default_region = region.to_string();
+        config
+    }
+
+    #[test]
+    fn fills_omitted_parameters_from_the_config() {
+        let mut omitted = None;
+        apply_to_arguments("service_endpoint", &mut omitted, &config("eu-west-1"));
+        assert_eq!(omitted, Some(json!({ "region": "eu-west-1" })));
+
+        let mut given = Some(json!({ "region": "ap-south-1" }));
+        apply_to_arguments("service_endpoint", &mut given, &config("eu-west-1"));
+        assert_eq!(given, Some(json!({ "region": "ap-south-1" })));
+    }
+
+    #[test]
+    fn advertises_the_current_default() {
+        let mut tool = Tool {
+            name: "service_endpoint".to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "region": { "type": "string" } },
+                "required": ["region"]
+            }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool, &config("eu-west-1"));
+        assert_eq!(
+            tool.input_schema["properties"]["region"]["default"],
+            json!("eu-west-1")
+        );
+        assert_eq!(tool.input_schema["required"], json!([]));
+    }
+}
@@ -0,0 +1,430 @@
+//! Loading and validating the server configuration
+//!
+//! Each [`ConfigField`] in [`CONFIG_FIELDS`] names a `ServerConfig` field by
+//! its path, such as `limits.max_upload_bytes` for a field of one of the
+//! groups, the environment variable overriding it, if any, and the values it
+//! accepts.
+//! [`ServerConfig::from_env`](crate::ServerConfig::from_env) starts from the
+//! defaults, applies the variables that are set and checks every field,
+//! failing with one [`ConfigProblem`] per bad value, so a misconfigured server
+//! stops at startup instead of misbehaving later.
+//!
+//! Variables hold the value as JSON (`30`, `true`, `"reject"`), except that
+//! string fields take the raw text, list fields also take a
+//! comma-separated list, e.g. `MCP_ADMIN_CLIENTS=alice,bob`, and boolean
+//! fields also take `1` and `0`.
+//!
+//! Add an entry when adding a field to `ServerConfig`: a field without one
+//! can't be set from the environment, and its value goes unchecked.
+
+use crate::{circuit_breaker, ServerConfig};
+use serde_json::Value;
+
+/// Where a `ServerConfig` field can come from and which values it accepts
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigField {
+    /// Path of the field, its group and name joined by `.` for grouped ones
+    pub name: &'static str,
+    /// Environment variable overriding the field
+    pub env: Option<&'static str>,
+    /// Smallest accepted number
+    pub min: Option<f64>,
+    /// Largest accepted number
+    pub max: Option<f64>,
+    /// Reject empty strings and lists
+    pub non_empty: bool,
+}
+
+impl ConfigField {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            env: None,
+            min: None,
+            max: None,
+            non_empty: false,
+        }
+    }
+
+    const fn env(mut self, env: &'static str) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    const fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    const fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    const fn non_empty(mut self) -> Self {
+        self.non_empty = true;
+        self
+    }
+}
+
+/// Sources and constraints of the `ServerConfig` fields
+pub const CONFIG_FIELDS: &[ConfigField] = &[
+    ConfigField::new("max_concurrent_requests")
+        .env("MCP_MAX_CONCURRENT_REQUESTS")
+        .min(1.0),
+    ConfigField::new("timeout_seconds")
+        .env("MCP_TIMEOUT")
+        .min(1.0),
+    ConfigField::new("debug_mode").env("MCP_DEBUG"),
+    ConfigField::new("supported_formats")
+        .env("MCP_SUPPORTED_FORMATS")
+        .non_empty(),
+    ConfigField::new("seed_snapshot").env("MCP_SEED_SNAPSHOT"),
+    ConfigField::new("fixed_time").env("MCP_FIXED_TIME"),
+    ConfigField::new("tools.default_region")
+        .env("MCP_DEFAULT_REGION")
+        .non_empty(),
+    ConfigField::new("tools.non_finite_floats").env("MCP_NON_FINITE_FLOATS"),
+    ConfigField::new("tools.unit_result").env("MCP_UNIT_RESULT"),
+    ConfigField::new("tools.strict_arguments").env("MCP_STRICT_ARGUMENTS"),
+    ConfigField::new("tools.sanitize_html").env("MCP_SANITIZE_HTML"),
+    ConfigField::new("tools.router_tool").env("MCP_ROUTER_TOOL"),
+    ConfigField::new("tools.circuit_breaker").env("MCP_CIRCUIT_BREAKER"),
+    ConfigField::new("tools.tool_circuit_breakers").env("MCP_TOOL_CIRCUIT_BREAKERS"),
+    ConfigField::new("limits.daily_call_quota")
+        .env("MCP_DAILY_CALL_QUOTA")
+        .min(1.0),
+    ConfigField::new("limits.quota_reset_hour_utc")
+        .env("MCP_QUOTA_RESET_HOUR_UTC")
+        .max(23.0),
+    ConfigField::new("limits.max_response_bytes")
+        .env("MCP_MAX_RESPONSE_BYTES")
+        .min(1.0),
+    ConfigField::new("limits.oversize_responses").env("MCP_OVERSIZE_RESPONSES"),
+    ConfigField::new("limits.max_html_bytes")
+        .env("MCP_MAX_HTML_BYTES")
+        .min(1.0),
+    ConfigField::new("limits.max_upload_bytes")
+        .env("MCP_MAX_UPLOAD_BYTES")
+        .min(1.0),
+    ConfigField::new("limits.upload_ttl_seconds")
+        .env("MCP_UPLOAD_TTL")
+        .min(1.0),
+    ConfigField::new("limits.max_content_input_bytes")
+        .env("MCP_MAX_CONTENT_INPUT_BYTES")
+        .min(1.0),
+    ConfigField::new("limits.blocking_warn_ms")
+        .env("MCP_BLOCKING_WARN_MS")
+        .min(1.0),
+    ConfigField::new("access.admin_clients").env("MCP_ADMIN_CLIENTS"),
+    ConfigField::new("access.hide_admin_resources").env("MCP_HIDE_ADMIN_RESOURCES"),
+    ConfigField::new("access.allowed_effects").env("MCP_ALLOWED_EFFECTS"),
+    ConfigField::new("access.enabled_tools").env("MCP_ENABLED_TOOLS"),
+    ConfigField::new("access.disabled_tools").env("MCP_DISABLED_TOOLS"),
+    ConfigField::new("access.safe_mode").env("MCP_SAFE_MODE"),
+    ConfigField::new("access.safe_mode_effects").env("MCP_SAFE_MODE_EFFECTS"),
+    ConfigField::new("access.whoami_tool").env("MCP_WHOAMI_TOOL"),
+    ConfigField::new("transport.max_connections")
+        .env("MCP_MAX_CONNECTIONS")
+        .min(1.0),
+    ConfigField::new("transport.connection_idle_timeout_seconds")
+        .env("MCP_CONNECTION_IDLE_TIMEOUT")
+        .min(1.0),
+    ConfigField::new("transport.ping_interval_seconds")
+        .env("MCP_PING_INTERVAL")
+        .min(1.0),
+    ConfigField::new("transport.ping_timeout_seconds")
+        .env("MCP_PING_TIMEOUT")
+        .min(1.0),
+    ConfigField::new("transport.batch_policy").env("MCP_BATCH_POLICY"),
+    ConfigField::new("transport.echo_request_id").env("MCP_ECHO_REQUEST_ID"),
+    ConfigField::new("resources.page_size")
+        .env("MCP_RESOURCE_PAGE_SIZE")
+        .min(1.0),
+    ConfigField::new("resources.strict_query_params").env("MCP_STRICT_QUERY_PARAMS"),
+];
+
+/// One invalid configuration value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    pub field: String,
+    /// Environment variable the value came from, if any
+    pub env: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.env {
+            Some(env) => write!(f, "{} (from {env}): {}", self.field, self.message),
+            None => write!(f, "{}: {}", self.field, self.message),
+        }
+    }
+}
+
+/// Every problem found in a configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid server configuration")?;
+        for problem in &self.problems {
+            write!(f, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// `base` with the fields whose variables `env` returns overridden
+pub fn load(
+    base: &ServerConfig,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<ServerConfig, ConfigError> {
+    let mut fields = serde_json::to_value(base).unwrap_or(Value::Null);
+    let mut problems = Vec::new();
+    let mut sources = Vec::new();
+
+    for field in CONFIG_FIELDS {
+        let Some((var, raw)) = field.env.and_then(|var| Some((var, env(var)?))) else {
+            continue;
+        };
+        let value = parse_env_value(field_value(&fields, field.name), &raw);
+        // Deserialized on its own so a bad value is reported with its field
+        let mut candidate = fields.clone();
+        set_field(&mut candidate, field.name, value);
+        match serde_json::from_value::<ServerConfig>(candidate.clone()) {
+            Ok(_) => {
+                fields = candidate;
+                sources.push((field.name, var));
+            }
+            Err(e) => problems.push(ConfigProblem {
+                field: field.name.to_string(),
+                env: Some(var.to_string()),
+                message: format!("invalid value '{raw}': {e}"),
+            }),
+        }
+    }
+
+    let config = serde_json::from_value::<ServerConfig>(fields).map_err(|e| ConfigError {
+        problems: vec![ConfigProblem {
+            field: "config".to_string(),
+            env: None,
+            message: e.to_string(),
+        }],
+    })?;
+    if let Err(error) = validate(&config) {
+        problems.extend(error.problems.into_iter().map(|mut problem| {
+            problem.env = sources
+                .iter()
+                .find(|(name, _)| *name == problem.field)
+                .map(|(_, var)| var.to_string());
+            problem
+        }));
+    }
+    if problems.is_empty() {
+        Ok(config)
+    } else {
+        Err(ConfigError { problems })
+    }
+}
+
+/// Check every field of `config` against [`CONFIG_FIELDS`]
+pub fn validate(config: &ServerConfig) -> Result<(), ConfigError> {
+    let fields = serde_json::to_value(config).unwrap_or(Value::Null);
+    let mut problems: Vec<_> = CONFIG_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let message = check(field, field_value(&fields, field.name)?)?;
+            Some(ConfigProblem {
+                field: field.name.to_string(),
+                env: None,
+                message,
+            })
+        })
+        .collect();
+    problems.extend(
+        check_breakers(config)
+            .into_iter()
+            .map(|(field, message)| ConfigProblem {
+                field: field.to_string(),
+                env: None,
+                message,
+            }),
+    );
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError { problems })
+    }
+}
+
+/// The value of the field at `path`, such as `limits.max_upload_bytes`, in
+/// the JSON of a `ServerConfig`
+pub fn field_value<'a>(config: &'a Value, path: &str) -> Option<&'a Value> {
+    config.pointer(&format!("/{}", path.replace('.', "/")))
+}
+
+fn set_field(config: &mut Value, path: &str, value: Value) {
+    if let Some(field) = config.pointer_mut(&format!("/{}", path.replace('.', "/"))) {
+        *field = value;
+    }
+}
+
+/// The breaker settings that can't be used, as field and reason
+fn check_breakers(config: &ServerConfig) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+    if config.tools.circuit_breaker.threshold == 0 {
+        problems.push((
+            "tools.circuit_breaker",
+            "threshold must be at least 1".to_string(),
+        ));
+    }
+    for (tool, settings) in &config.tools.tool_circuit_breakers {
+        if !circuit_breaker::breakers().any(|breaker| breaker == tool) {
+            problems.push((
+                "tools.tool_circuit_breakers",
+                format!(
+                    "no circuit breaker guards '{tool}'; breakers: {}",
+                    circuit_breaker::breakers().collect::<Vec<_>>().join(", ")
+                ),
+            ));
+        } else if settings.threshold == 0 {
+            problems.push((
+                "tools.tool_circuit_breakers",
+                format!("threshold of '{tool}' must be at least 1"),
+            ));
+        }
+    }
+    problems
+}
+
+/// Why `value` isn't accepted for `field`, if it isn't
+fn check(field: &ConfigField, value: &Value) -> Option<String> {
+    if let Some(number) = value.as_f64() {
+        if let Some(min) = field.min.filter(|min| number < *min) {
+            return Some(format!("must be at least {min}, got {number}"));
+        }
+        if let Some(max) = field.max.filter(|max| number > *max) {
+            return Some(format!("must be at most {max}, got {number}"));
+        }
+    }
+    let empty = match value {
+        Value::String(text) => text.trim().is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    };
+    (field.non_empty && empty).then(|| "must not be empty".to_string())
+}
+
+/// The JSON value of variable text `raw` for a field currently holding
+/// `current`
+fn parse_env_value(current: Option<&Value>, raw: &str) -> Value {
+    match current {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        Some(Value::Bool(_)) if raw == "1" || raw == "0" => Value::Bool(raw == "1"),
+        Some(Value::Array(_)) if !raw.trim_start().starts_with('[') => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        // Unquoted enum variants, such as `reject`, are taken as strings
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_breaker::BreakerSettings;
+
+    fn load_with(vars: &[(&str, &str)]) -> Result<ServerConfig, ConfigError> {
+        load(&ServerConfig::default(), |var| {
+            vars.iter()
+                .find(|(name, _)| *name == var)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn reads_breaker_settings_for_every_tool_and_one() {
+        let config = load_with(&[
+            (
+                "MCP_CIRCUIT_BREAKER",
+                r#"{"threshold":3,"cooldown_seconds":10}"#,
+            ),
+            (
+                "MCP_TOOL_CIRCUIT_BREAKERS",
+                r#"{"create_data":{"threshold":8,"cooldown_seconds":60}}"#,
+            ),
+        ])
+        .unwrap();
+        assert_eq!(
+            config.breaker_settings("create_data"),
+            BreakerSettings {
+                threshold: 8,
+                cooldown_seconds: 60
+            }
+        );
+        assert_eq!(config.breaker_settings("other").threshold, 3);
+    }
+
+    #[test]
+    fn rejects_breakers_of_unknown_tools_and_zero_thresholds() {
+        let error = load_with(&[
+            (
+                "MCP_CIRCUIT_BREAKER",
+                r#"{"threshold":0,"cooldown_seconds":10}"#,
+            ),
+            (
+                "MCP_TOOL_CIRCUIT_BREAKERS",
+                r#"{"no_such_tool":{"threshold":1,"cooldown_seconds":1}}"#,
+            ),
+        ])
+        .unwrap_err();
+        let problems: Vec<_> = error.problems.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            problems,
+            [
+                "tools.circuit_breaker (from MCP_CIRCUIT_BREAKER): threshold must be at least 1",
+                "tools.tool_circuit_breakers (from MCP_TOOL_CIRCUIT_BREAKERS): no circuit breaker \
+                 guards 'no_such_tool'; breakers: create_data",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_entry_names_a_field() {
+        let config = serde_json::to_value(ServerConfig::default()).unwrap();
+        for field in CONFIG_FIELDS {
+            assert!(field_value(&config, field.name).is_some(), "{}", field.name);
+        }
+    }
+
+    #[test]
+    fn overrides_grouped_fields() {
+        let config =
+            load_with(&[("MCP_MAX_UPLOAD_BYTES", "1024"), ("MCP_SAFE_MODE", "1")]).unwrap();
+        assert_eq!(config.limits.max_upload_bytes, 1024);
+        assert!(config.access.safe_mode);
+        let error = load_with(&[("MCP_RESOURCE_PAGE_SIZE", "0")]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid server configuration\n  resources.page_size (from MCP_RESOURCE_PAGE_SIZE): \
+             must be at least 1, got 0"
+        );
+    }
+
+    #[test]
+    fn reports_each_bad_variable() {
+        let error = load_with(&[("MCP_TIMEOUT", "0"), ("MCP_DEBUG", "maybe")]).unwrap_err();
+        let fields: Vec<_> = error.problems.iter().map(|p| p.field.as_str()).collect();
+        assert_eq!(fields, ["debug_mode", "timeout_seconds"]);
+    }
+}
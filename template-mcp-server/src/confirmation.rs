@@ -0,0 +1,289 @@
+//! Confirmation before running destructive tools
+//!
+//! Tools with a `confirmation` setting (see [`crate::tool_settings`]) don't
+//! run on the first call. Instead the call returns a `confirmation_required`
+//! result with a token and a summary of what the call would do, for the
+//! client to show its user. Calling the tool again with the same arguments
+//! plus the token in [`CONFIRM_TOKEN_PARAM`] runs it. Tokens are single-use,
+//! bound to the tool and arguments they were issued for, and expire after
+//! [`DEFAULT_TOKEN_TTL`].
+//!
+//! This is enforced by the server, unlike the `destructiveHint` annotation,
+//! which clients are free to ignore.
+
+use crate::clock::Clock;
+use chrono::{DateTime, Utc};
+use pulseengine_mcp_protocol::{CallToolResult, Content, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Argument carrying the confirmation token
+pub const CONFIRM_TOKEN_PARAM: &str = "_confirm_token";
+
+/// How long a confirmation token stays valid
+pub const DEFAULT_TOKEN_TTL: chrono::Duration = chrono::Duration::seconds(60);
+
+/// A tool that needs confirmation, with a summary of what a call would do
+#[derive(Debug, Clone, Copy)]
+pub struct RequiresConfirmation {
+    pub tool: &'static str,
+    pub summary: fn(&Value) -> String,
+}
+
+/// What a call of `delete_data` with `arguments` would delete
+pub fn delete_data_summary(arguments: &Value) -> String {
+    match (arguments.get("id"), arguments.get("tag")) {
+        (Some(id), _) if !id.is_null() => format!("Delete the example data with ID {id}"),
+        (_, Some(Value::String(tag))) => format!("Delete all example data tagged '{tag}'"),
+        _ => "Delete example data".to_string(),
+    }
+}
+
+/// The result of a call that still needs confirmation
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename = "confirmation_required")]
+pub struct ConfirmationRequired {
+    pub tool: String,
+    pub summary: String,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ConfirmationRequired {
+    /// As a successful tool result, with the challenge as structured content
+    pub fn into_result(self) -> CallToolResult {
+        let text = format!(
+            "Confirmation required: {}. Call {} again with {CONFIRM_TOKEN_PARAM} = \"{}\" to proceed.",
+            self.summary, self.tool, self.token
+        );
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            structured_content: serde_json::to_value(&self).ok(),
+        }
+    }
+}
+
+/// Why a confirmation token was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmationError {
+    /// Unknown or already used
+    InvalidToken,
+    Expired,
+    /// Issued for another tool or other arguments
+    Mismatch,
+}
+
+impl std::fmt::Display for ConfirmationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            Self::InvalidToken => "the token is unknown or was already used",
+            Self::Expired => "the token has expired",
+            Self::Mismatch => "the token was issued for a different call",
+        };
+        write!(
+            f,
+            "confirmation_failed: {reason}; call the tool without {CONFIRM_TOKEN_PARAM} to get a new one"
+        )
+    }
+}
+
+impl std::error::Error for ConfirmationError {}
+
+/// Issues and checks confirmation tokens
+pub struct ConfirmationGate {
+    clock: Arc<dyn Clock>,
+    ttl: chrono::Duration,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+struct Pending {
+    tool: String,
+    arguments: Value,
+    expires_at: DateTime<Utc>,
+}
+
+impl ConfirmationGate {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            ttl: DEFAULT_TOKEN_TTL,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Let tokens expire after `ttl` instead of the default minute
+    pub fn with_token_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Issue a token for calling `tool` with `arguments`
+    pub fn challenge(
+        &self,
+        tool: &RequiresConfirmation,
+        arguments: &Value,
+    ) -> ConfirmationRequired {
+        let now = self.clock.now();
+        let expires_at = now + self.ttl;
+        let token = format!("{:032x}", rand::random::<u128>());
+
+        let mut pending = self.lock();
+        pending.retain(|_, entry| entry.expires_at > now);
+        pending.insert(
+            token.clone(),
+            Pending {
+                tool: tool.tool.to_string(),
+                arguments: arguments.clone(),
+                expires_at,
+            },
+        );
+
+        ConfirmationRequired {
+            tool: tool.tool.to_string(),
+            summary: (tool.summary)(arguments),
+            token,
+            expires_at,
+        }
+    }
+
+    /// Use up `token` for calling `tool` with `arguments`
+    pub fn confirm(
+        &self,
+        token: &str,
+        tool: &str,
+        arguments: &Value,
+    ) -> Result<(), ConfirmationError> {
+        let entry = self
+            .lock()
+            .remove(token)
+            .ok_or(ConfirmationError::InvalidToken)?;
+        if entry.expires_at <= self.clock.now() {
+            return Err(ConfirmationError::Expired);
+        }
+        if entry.tool != tool || entry.arguments != *arguments {
+            return Err(ConfirmationError::Mismatch);
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Pending>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The confirmation requirement of `tool`, if any
+pub fn requirement(tool: &str) -> Option<RequiresConfirmation> {
+    let settings = crate::tool_settings::settings_of(tool);
+    settings.confirmation.map(|summary| RequiresConfirmation {
+        tool: settings.tool,
+        summary,
+    })
+}
+
+/// Remove and return the confirmation token from call arguments
+pub fn take_token(arguments: &mut Option<Value>) -> Option<String> {
+    match arguments
+        .as_mut()?
+        .as_object_mut()?
+        .remove(CONFIRM_TOKEN_PARAM)?
+    {
+        Value::String(token) => Some(token),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Advertise the confirmation token parameter in a tool's input schema
+pub fn apply_to_schema(tool: &mut Tool) {
+    if requirement(&tool.name).is_none() {
+        return;
+    }
+    if let Some(properties) = tool
+        .input_schema
+        .get_mut("properties")
+        .and_then(Value::as_object_mut)
+    {
+        properties.insert(
+            CONFIRM_TOKEN_PARAM.to_string(),
+            serde_json::json!({
+                "type": "string",
+                "description": "Token from a confirmation_required result; omit it on the first call",
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use serde_json::json;
+
+    fn gate() -> (Arc<ManualClock>, ConfirmationGate) {
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        (clock.clone(), ConfirmationGate::new(clock))
+    }
+
+    fn clear_data() -> RequiresConfirmation {
+        requirement("clear_data").expect("clear_data needs confirmation")
+    }
+
+    #[test]
+    fn tokens_confirm_the_call_they_were_issued_for_once() {
+        let (_, gate) = gate();
+        let arguments = json!({});
+        let challenge = gate.challenge(&clear_data(), &arguments);
+        assert_eq!(challenge.summary, "Delete all example data");
+
+        assert_eq!(
+            gate.confirm(&challenge.token, "clear_data", &arguments),
+            Ok(())
+        );
+        assert_eq!(
+            gate.confirm(&challenge.token, "clear_data", &arguments),
+            Err(ConfirmationError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn tokens_are_bound_to_tool_and_arguments() {
+        let (_, gate) = gate();
+        let delete = requirement("delete_data").unwrap();
+        let token = gate.challenge(&delete, &json!({ "id": 1 })).token;
+        assert_eq!(
+            gate.confirm(&token, "delete_data", &json!({ "id": 2 })),
+            Err(ConfirmationError::Mismatch)
+        );
+        let token = gate.challenge(&delete, &json!({ "id": 1 })).token;
+        assert_eq!(
+            gate.confirm(&token, "clear_data", &json!({ "id": 1 })),
+            Err(ConfirmationError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn tokens_expire() {
+        let (clock, gate) = gate();
+        let token = gate.challenge(&clear_data(), &json!({})).token;
+        clock.advance(DEFAULT_TOKEN_TTL);
+        assert_eq!(
+            gate.confirm(&token, "clear_data", &json!({})),
+            Err(ConfirmationError::Expired)
+        );
+    }
+
+    #[test]
+    fn destructive_tools_are_listed_once() {
+        for settings in crate::tool_settings::TOOL_SETTINGS {
+            assert!(
+                settings.confirmation.is_none() || !settings.destructive,
+                "{} needs confirmation, which already makes it destructive",
+                settings.tool
+            );
+        }
+    }
+}
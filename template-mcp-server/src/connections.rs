@@ -0,0 +1,266 @@
+//! Connection limits and idle-connection reaping
+//!
+//! A transport that accepts connections asks the [`ConnectionLimiter`] for a
+//! [`ConnectionGuard`] before serving each one. Connections beyond
+//! `max_connections` are refused, and connections with no activity for the
+//! idle timeout are reaped: their guard's [`ConnectionGuard::closed`] resolves
+//! and the transport drops the connection. A connection that never completes
+//! its handshake never calls [`ConnectionGuard::touch`], so it is reaped too.
+//!
+//! The HTTP and WebSocket transports of this framework version accept
+//! connections internally and don't expose a hook for this, so the limiter
+//! applies to transports that call it; its counts appear in `ServerStatus`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Connection counts, as reported in `ServerStatus`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    pub active: usize,
+    pub max_connections: usize,
+    /// Connections refused because the limit was reached
+    pub refused: u64,
+    /// Connections closed for being idle
+    pub reaped: u64,
+}
+
+/// A connection refused because `max_connections` are already open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionRefused {
+    pub max_connections: usize,
+}
+
+impl std::fmt::Display for ConnectionRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Connection limit of {} reached", self.max_connections)
+    }
+}
+
+impl std::error::Error for ConnectionRefused {}
+
+/// Caps open connections and closes idle ones
+#[derive(Debug)]
+pub struct ConnectionLimiter {
+    max_connections: usize,
+    idle_timeout: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: u64,
+    open: HashMap<u64, OpenConnection>,
+    refused: u64,
+    reaped: u64,
+}
+
+#[derive(Debug)]
+struct OpenConnection {
+    last_activity: Instant,
+    close: Arc<Notify>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(max_connections: usize, idle_timeout: Duration) -> Self {
+        Self {
+            max_connections,
+            idle_timeout,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Admit a new connection, or refuse it if the limit is reached
+    pub fn try_acquire(self: &Arc<Self>) -> Result<ConnectionGuard, ConnectionRefused> {
+        let mut state = self.lock();
+        if state.open.len() >= self.max_connections {
+            state.refused += 1;
+            tracing::warn!(
+                max_connections = self.max_connections,
+                "Refusing connection: limit reached"
+            );
+            return Err(ConnectionRefused {
+                max_connections: self.max_connections,
+            });
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        let close = Arc::new(Notify::new());
+        state.open.insert(
+            id,
+            OpenConnection {
+                last_activity: Instant::now(),
+                close: close.clone(),
+            },
+        );
+        Ok(ConnectionGuard {
+            id,
+            limiter: Arc::downgrade(self),
+            close,
+        })
+    }
+
+    /// Close every connection idle for longer than the timeout
+    ///
+    /// Returns the number of connections reaped.
+    pub fn reap_idle(&self) -> usize {
+        let mut state = self.lock();
+        let now = Instant::now();
+        let idle: Vec<u64> = state
+            .open
+            .iter()
+            .filter(|(_, conn)| now.duration_since(conn.last_activity) >= self.idle_timeout)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &idle {
+            if let Some(conn) = state.open.remove(id) {
+                conn.close.notify_one();
+            }
+        }
+        state.reaped += idle.len() as u64;
+        if !idle.is_empty() {
+            tracing::info!(count = idle.len(), "Closed idle connections");
+        }
+        idle.len()
+    }
+
+    /// Reap idle connections in the background until the limiter is dropped
+    pub fn spawn_reaper(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let limiter = Arc::downgrade(self);
+        // Check a few times per timeout so connections close close to on time
+        let period = (self.idle_timeout / 4).max(Duration::from_millis(10));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match limiter.upgrade() {
+                    Some(limiter) => {
+                        limiter.reap_idle();
+                    }
+                    None => break,
+                }
+            }
+        })
+    }
+
+    pub fn stats(&self) -> ConnectionStats {
+        let state = self.lock();
+        ConnectionStats {
+            active: state.open.len(),
+            max_connections: self.max_connections,
+            refused: state.refused,
+            reaped: state.reaped,
+        }
+    }
+
+    fn touch(&self, id: u64) {
+        if let Some(conn) = self.lock().open.get_mut(&id) {
+            conn.last_activity = Instant::now();
+        }
+    }
+
+    fn release(&self, id: u64) {
+        self.lock().open.remove(&id);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An admitted connection; releases its slot when dropped
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    id: u64,
+    limiter: Weak<ConnectionLimiter>,
+    close: Arc<Notify>,
+}
+
+impl ConnectionGuard {
+    /// Record activity, postponing the idle timeout
+    pub fn touch(&self) {
+        if let Some(limiter) = self.limiter.upgrade() {
+            limiter.touch(self.id);
+        }
+    }
+
+    /// Resolves once the connection has been reaped for being idle
+    pub async fn closed(&self) {
+        self.close.notified().await;
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.limiter.upgrade() {
+            limiter.release(self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDLE: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn refuses_connections_over_the_limit() {
+        let limiter = Arc::new(ConnectionLimiter::new(1, IDLE));
+        let first = limiter.try_acquire().unwrap();
+        let refused = limiter.try_acquire().unwrap_err();
+        assert_eq!(refused.to_string(), "Connection limit of 1 reached");
+
+        drop(first);
+        let _second = limiter.try_acquire().unwrap();
+        assert_eq!(
+            limiter.stats(),
+            ConnectionStats {
+                active: 1,
+                max_connections: 1,
+                refused: 1,
+                reaped: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reaps_idle_connections() {
+        let limiter = Arc::new(ConnectionLimiter::new(2, IDLE));
+        let idle = limiter.try_acquire().unwrap();
+        let busy = limiter.try_acquire().unwrap();
+        tokio::time::sleep(IDLE).await;
+        busy.touch();
+
+        assert_eq!(limiter.reap_idle(), 1);
+        tokio::time::timeout(IDLE, idle.closed())
+            .await
+            .expect("the idle connection is closed");
+        let stats = limiter.stats();
+        assert_eq!((stats.active, stats.reaped), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn the_reaper_closes_connections_in_the_background() {
+        let limiter = Arc::new(ConnectionLimiter::new(1, IDLE));
+        let reaper = limiter.spawn_reaper();
+        let guard = limiter.try_acquire().unwrap();
+        tokio::time::timeout(IDLE * 10, guard.closed())
+            .await
+            .expect("the reaper closes the connection");
+
+        drop(limiter);
+        tokio::time::timeout(IDLE * 10, reaper)
+            .await
+            .expect("the reaper stops with the limiter")
+            .unwrap();
+    }
+}
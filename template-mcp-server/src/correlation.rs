@@ -0,0 +1,96 @@
+//! Correlation ids for requests
+//!
+//! The JSON-RPC id of a request is the client's: often a small counter that
+//! restarts with every session, so it can't tell two requests apart in the
+//! server's logs. [`RawMessageHandler`](crate::raw_messages::RawMessageHandler)
+//! mints a UUID for every request and handles it in a `request` span
+//! carrying the id as `correlation_id`, so every event logged while the server
+//! works on the request, in the backend or in a tool, can be traced back to
+//! it:
+//!
+//! ```text
+//! INFO request{correlation_id=5f0c9c1e-8a4b-4c41-9d6f-0b8f2f9e3a17 method="tools/call"}:handle_request{..}: Request completed successfully
+//! ```
+//!
+//! With `TransportConfig::echo_request_id`, results also carry the id as
+//! `request_id` in their `_meta`, so a client can quote it when reporting a
+//! problem:
+//!
+//! ```json
+//! "_meta": { "request_id": "5f0c9c1e-8a4b-4c41-9d6f-0b8f2f9e3a17" }
+//! ```
+//!
+//! Tools read the id of the request they run in with [`current`]. STDIO
+//! requests get an id; HTTP requests don't go through the handler, so they
+//! don't.
+
+use serde_json::{json, Value};
+use std::future::Future;
+use tracing::Instrument;
+
+/// Key of the correlation id in the `_meta` of results
+pub const REQUEST_ID_META_KEY: &str = "request_id";
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+/// A new correlation id
+pub fn mint() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Correlation id of the request being handled on this task, if any
+pub fn current() -> Option<String> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// Handle a `method` request with `future` under the correlation id `id`:
+/// as the [`current`] id and in a `request` span carrying it
+pub async fn correlated<F: Future>(id: String, method: &str, future: F) -> F::Output {
+    let span = tracing::info_span!("request", correlation_id = %id, method);
+    CURRENT.scope(id, future).instrument(span).await
+}
+
+/// Add `id` to the `_meta` of the JSON of a result
+pub fn attach(result: &mut Value, id: &str) {
+    let Some(object) = result.as_object_mut() else {
+        return;
+    };
+    let meta = object.entry("_meta").or_insert_with(|| json!({}));
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(REQUEST_ID_META_KEY.to_string(), json!(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn requests_see_their_own_id() {
+        assert_eq!(current(), None);
+        let id = mint();
+        let seen = correlated(id.clone(), "tools/call", async { current() }).await;
+        assert_eq!(seen, Some(id));
+        assert_eq!(current(), None);
+    }
+
+    #[test]
+    fn mints_distinct_uuids() {
+        let (a, b) = (mint(), mint());
+        assert_ne!(a, b);
+        assert!(uuid::Uuid::parse_str(&a).is_ok(), "{a}");
+    }
+
+    #[test]
+    fn attaches_the_id_to_the_result_meta() {
+        let mut result = json!({ "content": [], "_meta": { "cost": 1 } });
+        attach(&mut result, "abc");
+        assert_eq!(result["_meta"], json!({ "cost": 1, "request_id": "abc" }));
+
+        let mut bare = json!({ "content": [] });
+        attach(&mut bare, "abc");
+        assert_eq!(bare["_meta"], json!({ "request_id": "abc" }));
+    }
+}
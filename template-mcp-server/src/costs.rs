@@ -0,0 +1,185 @@
+//! Estimated and actual costs of tool calls
+//!
+//! Agents working to a budget pick cheaper tools when they suffice. Every
+//! tool declares one of three [`ToolCost`] classes as its `cost` setting (see
+//! [`crate::tool_settings`]), shown as `x-cost` in its input schema in
+//! `tools/list`; tools declaring none are `low`:
+//!
+//! - `low`: answers from memory in milliseconds, such as `echo`
+//! - `medium`: reads or writes a store or the client, or does work growing
+//!   with its input, within about a second, such as `data_report`
+//! - `high`: calls an upstream service or computes for seconds, and may be
+//!   rate limited or billed, such as `fetch_upstream`
+//!
+//! The result of each call also reports what it actually cost, as `cost` in
+//! its `_meta`: the declared class, how long the call took and the size of
+//! the result in bytes of JSON, which is roughly what it adds to a model's
+//! context. Results the report would push over
+//! `LimitsConfig::max_response_bytes` go without it:
+//!
+//! ```json
+//! "_meta": { "cost": { "declared": "medium", "durationMs": 3, "resultBytes": 412 } }
+//! ```
+//!
+//! The class is a promise to agents, so declare the class of the slowest
+//! calls a tool usually handles, not of its fastest. The 0.11
+//! `CallToolResult` has no `_meta`, so the report is added to the JSON of the
+//! response by [`RawMessageHandler`](crate::raw_messages::RawMessageHandler),
+//! which serves STDIO. HTTP clients only get the declared cost.
+
+use pulseengine_mcp_protocol::{CallToolResult, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::future::Future;
+use std::time::Duration;
+
+/// How expensive a call to a tool is expected to be
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCost {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+pub fn cost_of(tool: &str) -> ToolCost {
+    crate::tool_settings::settings_of(tool).cost
+}
+
+/// Show the tool's declared cost as `x-cost` in its schema
+pub fn apply_to_schema(tool: &mut Tool) {
+    let cost = cost_of(&tool.name);
+    if let Some(schema) = tool.input_schema.as_object_mut() {
+        schema.insert("x-cost".to_string(), json!(cost));
+    }
+}
+
+/// What a call actually cost
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CostReport {
+    pub declared: ToolCost,
+    pub duration_ms: u64,
+    pub result_bytes: usize,
+}
+
+impl CostReport {
+    pub fn new(tool: &str, duration: Duration, result: &CallToolResult) -> Self {
+        Self {
+            declared: cost_of(tool),
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            result_bytes: serde_json::to_vec(result).map_or(0, |json| json.len()),
+        }
+    }
+}
+
+tokio::task_local! {
+    // Cost of the tool call made by the current request
+    static COST: Cell<Option<CostReport>>;
+}
+
+/// Keep `report` for the [`reporting`] the call runs in, if any
+pub fn record(report: CostReport) {
+    // Outside `reporting`, e.g. over HTTP, the report is dropped
+    let _ = COST.try_with(|cost| cost.set(Some(report)));
+}
+
+/// Run `future`, returning the cost of the tool call it made, if any
+pub async fn reporting<F: Future>(future: F) -> (F::Output, Option<CostReport>) {
+    COST.scope(Cell::new(None), async {
+        let output = future.await;
+        (output, COST.with(Cell::take))
+    })
+    .await
+}
+
+/// Add `report` to the `_meta` of the JSON of a tool result, unless that
+/// would make the result longer than `max_bytes`
+pub fn attach(result: &mut Value, report: &CostReport, max_bytes: Option<usize>) {
+    let mut reported = result.clone();
+    let Some(object) = reported.as_object_mut() else {
+        return;
+    };
+    let meta = object.entry("_meta").or_insert_with(|| json!({}));
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert("cost".to_string(), json!(report));
+    }
+    let fits = max_bytes.is_none_or(|max_bytes| reported.to_string().len() <= max_bytes);
+    if fits {
+        *result = reported;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> CostReport {
+        CostReport {
+            declared: ToolCost::Medium,
+            duration_ms: 3,
+            result_bytes: 412,
+        }
+    }
+
+    #[test]
+    fn declares_cost_classes() {
+        assert_eq!(cost_of("echo"), ToolCost::Low);
+        assert_eq!(cost_of("data_report"), ToolCost::Medium);
+        assert_eq!(cost_of("fetch_upstream"), ToolCost::High);
+
+        let mut tool = Tool {
+            name: "fetch_upstream".to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool);
+        assert_eq!(tool.input_schema["x-cost"], "high");
+    }
+
+    #[test]
+    fn measures_the_result() {
+        let result = CallToolResult::text("hello");
+        let report = CostReport::new("echo", Duration::from_millis(7), &result);
+        assert_eq!(report.declared, ToolCost::Low);
+        assert_eq!(report.duration_ms, 7);
+        assert_eq!(
+            report.result_bytes,
+            serde_json::to_vec(&result).unwrap().len()
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_the_report_of_the_call() {
+        let (output, cost) = reporting(async {
+            record(report());
+            42
+        })
+        .await;
+        assert_eq!(output, 42);
+        assert_eq!(cost, Some(report()));
+
+        let ((), cost) = reporting(async {}).await;
+        assert_eq!(cost, None);
+        // Outside `reporting` the report is dropped rather than panicking
+        record(report());
+    }
+
+    #[test]
+    fn attaches_reports_that_fit() {
+        let mut result = json!({ "content": [] });
+        attach(&mut result, &report(), None);
+        assert_eq!(
+            result["_meta"]["cost"],
+            json!({ "declared": "medium", "durationMs": 3, "resultBytes": 412 })
+        );
+
+        let mut result = json!({ "content": [] });
+        let len = result.to_string().len();
+        attach(&mut result, &report(), Some(len + 10));
+        assert_eq!(result, json!({ "content": [] }));
+    }
+}
@@ -0,0 +1,213 @@
+//! Shared store of example data
+//!
+//! Tools run concurrently, so every [`DataStore`] operation takes the lock
+//! once and completes under it. In particular [`DataStore::create`] generates
+//! the id, checks it and inserts the entry in one critical section, and an id
+//! that is already taken is reported as a [`DataConflict`] instead of
+//! overwriting the existing entry.
+//!
+//! A tool that panics while holding the lock, say in the `build` of a
+//! `create`, poisons it. Every operation leaves the entries whole even when
+//! interrupted, at worst with only part of a bulk change applied, so the store
+//! logs a warning, clears the poison and goes on with them rather than
+//! failing every later call; see [`crate::panics`].
+
+use crate::ids::IdGenerator;
+use crate::ExampleData;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// An entry with the same id already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataConflict {
+    pub id: u64,
+}
+
+impl std::fmt::Display for DataConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "conflict: example data with ID {} already exists",
+            self.id
+        )
+    }
+}
+
+impl std::error::Error for DataConflict {}
+
+/// Example data by id
+#[derive(Debug, Default)]
+pub struct DataStore {
+    entries: RwLock<HashMap<u64, ExampleData>>,
+}
+
+impl DataStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store an entry built by `build` under a new id from `ids`
+    ///
+    /// `build` runs while the store is locked, so keep it cheap.
+    pub fn create(
+        &self,
+        ids: &dyn IdGenerator,
+        build: impl FnOnce(u64) -> ExampleData,
+    ) -> Result<ExampleData, DataConflict> {
+        let mut entries = self.write();
+        let id = ids.next_id();
+        if entries.contains_key(&id) {
+            return Err(DataConflict { id });
+        }
+        let data = build(id);
+        entries.insert(id, data.clone());
+        Ok(data)
+    }
+
+    /// Store `data` under its own id, unless that id is taken
+    pub fn insert(&self, data: ExampleData) -> Result<(), DataConflict> {
+        let mut entries = self.write();
+        if entries.contains_key(&data.id) {
+            return Err(DataConflict { id: data.id });
+        }
+        entries.insert(data.id, data);
+        Ok(())
+    }
+
+    /// Store `entries`, overwriting entries with the same ids, and return how
+    /// many were overwritten
+    pub fn upsert_all(&self, entries: Vec<ExampleData>) -> usize {
+        let mut stored = self.write();
+        let mut updated = 0;
+        for data in entries {
+            if stored.insert(data.id, data).is_some() {
+                updated += 1;
+            }
+        }
+        updated
+    }
+
+    /// Replace every entry with `entries`, returning how many were removed
+    pub fn replace_all(&self, entries: Vec<ExampleData>) -> usize {
+        let mut stored = self.write();
+        let removed = stored.len();
+        *stored = entries.into_iter().map(|data| (data.id, data)).collect();
+        removed
+    }
+
+    pub fn get(&self, id: u64) -> Option<ExampleData> {
+        self.read().get(&id).cloned()
+    }
+
+    /// Remove and return the entry with `id`
+    pub fn remove(&self, id: u64) -> Option<ExampleData> {
+        self.write().remove(&id)
+    }
+
+    /// Remove every entry matching `predicate`, returning how many were removed
+    pub fn remove_matching(&self, predicate: impl Fn(&ExampleData) -> bool) -> usize {
+        let mut entries = self.write();
+        let before = entries.len();
+        entries.retain(|_, data| !predicate(data));
+        before - entries.len()
+    }
+
+    /// All entries, ordered by id
+    pub fn entries(&self) -> Vec<ExampleData> {
+        let mut entries: Vec<ExampleData> = self.read().values().cloned().collect();
+        entries.sort_by_key(|data| data.id);
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<u64, ExampleData>> {
+        self.entries.read().unwrap_or_else(|poisoned| {
+            self.recover();
+            poisoned.into_inner()
+        })
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<u64, ExampleData>> {
+        self.entries.write().unwrap_or_else(|poisoned| {
+            self.recover();
+            poisoned.into_inner()
+        })
+    }
+
+    fn recover(&self) {
+        tracing::warn!("A panic poisoned the data store lock; going on with its entries");
+        self.entries.clear_poison();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ids::SequentialIds;
+
+    fn data(id: u64, name: &str) -> ExampleData {
+        ExampleData::builder().id(id).name(name).build().unwrap()
+    }
+
+    #[test]
+    fn creates_entries_under_new_ids() {
+        let store = DataStore::new();
+        let ids = SequentialIds::starting_at(7);
+        let created = store.create(&ids, |id| data(id, "first")).unwrap();
+        assert_eq!(created.id, 7);
+        assert_eq!(
+            store.get(7).map(|data| data.name),
+            Some("first".to_string())
+        );
+        assert_eq!(store.create(&ids, |id| data(id, "second")).unwrap().id, 8);
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn reports_taken_ids_without_overwriting() {
+        let store = DataStore::new();
+        store.insert(data(1, "original")).unwrap();
+        assert_eq!(store.insert(data(1, "other")), Err(DataConflict { id: 1 }));
+        let error = store
+            .create(&SequentialIds::starting_at(1), |id| data(id, "other"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "conflict: example data with ID 1 already exists"
+        );
+        assert_eq!(store.get(1).unwrap().name, "original");
+    }
+
+    #[test]
+    fn bulk_changes_report_what_they_replaced() {
+        let store = DataStore::new();
+        assert!(store.is_empty());
+        assert_eq!(store.upsert_all(vec![data(1, "a"), data(2, "b")]), 0);
+        assert_eq!(store.upsert_all(vec![data(2, "c"), data(3, "d")]), 1);
+        assert_eq!(store.remove_matching(|data| data.name == "a"), 1);
+        assert_eq!(store.remove(3).map(|data| data.name), Some("d".to_string()));
+        assert_eq!(store.replace_all(vec![data(9, "z"), data(4, "y")]), 1);
+        let ids: Vec<u64> = store.entries().iter().map(|data| data.id).collect();
+        assert_eq!(ids, [4, 9]);
+    }
+
+    #[test]
+    fn recovers_from_a_panic_under_the_lock() {
+        let store = std::sync::Arc::new(DataStore::new());
+        let panicking = store.clone();
+        let panicked = std::thread::spawn(move || {
+            let _ = panicking.create(&SequentialIds::starting_at(1), |_| panic!("in build"));
+        })
+        .join();
+        assert!(panicked.is_err());
+        store.insert(data(1, "after")).unwrap();
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -0,0 +1,404 @@
+//! Exact decimal numbers for money and measurements
+//!
+//! `f64` can't represent most decimal fractions, so `0.1 + 0.2` comes out as
+//! `0.30000000000000004`. Tools that handle amounts take and return
+//! [`Decimal`] instead, which stores an integer and a decimal scale and does
+//! exact arithmetic. On the wire a decimal is a JSON string such as `"12.50"`,
+//! declared as `{"type": "string", "format": "decimal"}` in tool schemas,
+//! because JSON numbers are parsed as floats by most clients, and JSON numbers
+//! are rejected on input. The backend checks decimal arguments before a call with
+//! [`check_arguments`], so a malformed amount gets an error saying why.
+//!
+//! The type covers what the example tools need (parsing, formatting,
+//! addition, subtraction and multiplication) with up to [`MAX_SCALE`]
+//! fractional digits. A server doing more, such as rounding or division,
+//! should use a full decimal library like `rust_decimal` the same way.
+
+use pulseengine_mcp_protocol::Tool as ToolDefinition;
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// Most fractional digits a [`Decimal`] holds
+pub const MAX_SCALE: u32 = 28;
+
+const NUMBER_NOT_EXACT: &str = "JSON numbers aren't exact; send decimals as strings";
+
+/// Why a decimal couldn't be parsed or computed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecimalError {
+    /// Not a plain decimal such as `-12.50`
+    Invalid(String),
+    /// More than [`MAX_SCALE`] fractional digits
+    TooPrecise(String),
+    Overflow,
+}
+
+impl std::fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(text) => write!(f, "'{text}' is not a decimal number"),
+            Self::TooPrecise(text) => write!(
+                f,
+                "'{text}' has more than {MAX_SCALE} digits after the decimal point"
+            ),
+            Self::Overflow => write!(f, "decimal result is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for DecimalError {}
+
+/// An exact decimal number, `mantissa / 10^scale`
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub const ZERO: Self = Self {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    /// `mantissa / 10^scale`, e.g. `Decimal::new(1250, 2)` is `12.50`
+    pub fn new(mantissa: i128, scale: u32) -> Result<Self, DecimalError> {
+        if scale > MAX_SCALE {
+            return Err(DecimalError::TooPrecise(format!("{mantissa}e-{scale}")));
+        }
+        Ok(Self { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self, DecimalError> {
+        let (a, b, scale) = align(self, other)?;
+        let mantissa = a.checked_add(b).ok_or(DecimalError::Overflow)?;
+        Ok(Self { mantissa, scale })
+    }
+
+    pub fn checked_sub(self, other: Self) -> Result<Self, DecimalError> {
+        let (a, b, scale) = align(self, other)?;
+        let mantissa = a.checked_sub(b).ok_or(DecimalError::Overflow)?;
+        Ok(Self { mantissa, scale })
+    }
+
+    pub fn checked_mul(self, other: Self) -> Result<Self, DecimalError> {
+        let mantissa = self
+            .mantissa
+            .checked_mul(other.mantissa)
+            .ok_or(DecimalError::Overflow)?;
+        let product = Self {
+            mantissa,
+            scale: self.scale + other.scale,
+        }
+        .normalized();
+        if product.scale > MAX_SCALE {
+            return Err(DecimalError::TooPrecise(product.to_string()));
+        }
+        Ok(product)
+    }
+
+    /// The same value without trailing fractional zeros, e.g. `1.5` for `1.50`
+    pub fn normalized(self) -> Self {
+        let mut normalized = self;
+        while normalized.scale > 0 && normalized.mantissa % 10 == 0 {
+            normalized.mantissa /= 10;
+            normalized.scale -= 1;
+        }
+        normalized
+    }
+}
+
+/// Both mantissas at the larger scale
+fn align(a: Decimal, b: Decimal) -> Result<(i128, i128, u32), DecimalError> {
+    let scale = a.scale.max(b.scale);
+    Ok((rescale(a, scale)?, rescale(b, scale)?, scale))
+}
+
+fn rescale(value: Decimal, scale: u32) -> Result<i128, DecimalError> {
+    10i128
+        .checked_pow(scale - value.scale)
+        .and_then(|factor| value.mantissa.checked_mul(factor))
+        .ok_or(DecimalError::Overflow)
+}
+
+impl std::str::FromStr for Decimal {
+    type Err = DecimalError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || DecimalError::Invalid(text.to_string());
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let digits_only = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if integer.is_empty() || !digits_only(integer) || !digits_only(fraction) {
+            return Err(invalid());
+        }
+        if unsigned.ends_with('.') {
+            return Err(invalid());
+        }
+        let scale = fraction.len() as u32;
+        if scale > MAX_SCALE {
+            return Err(DecimalError::TooPrecise(text.to_string()));
+        }
+
+        let magnitude: i128 = format!("{integer}{fraction}")
+            .parse()
+            .map_err(|_| DecimalError::Overflow)?;
+        let mantissa = if negative { -magnitude } else { magnitude };
+        Ok(Self { mantissa, scale })
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
+
+impl std::fmt::Debug for Decimal {
+    /// Tool results are rendered with `Debug`, so this writes the JSON string
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{self}\"")
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    /// Compares values, so `1.5` equals `1.50`
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Integer and fractional parts separately, which can't overflow
+        let split = |value: &Self| {
+            let unit = 10i128.pow(value.scale);
+            (value.mantissa / unit, value.mantissa % unit)
+        };
+        let ((a_integer, a_fraction), (b_integer, b_fraction)) = (split(self), split(other));
+        let scale = self.scale.max(other.scale);
+        a_integer.cmp(&b_integer).then_with(|| {
+            let a = a_fraction * 10i128.pow(scale - self.scale);
+            let b = b_fraction * 10i128.pow(scale - other.scale);
+            a.cmp(&b)
+        })
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Decimal;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a decimal number as a string, such as \"12.50\"")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, text: &str) -> Result<Decimal, E> {
+                text.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
+impl JsonSchema for Decimal {
+    fn schema_name() -> Cow<'static, str> {
+        "Decimal".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "format": "decimal",
+            "pattern": r"^[+-]?[0-9]+(\.[0-9]+)?$",
+        })
+    }
+}
+
+/// Reject malformed values sent for decimal parameters of `tool`
+pub fn check_arguments(tool: &ToolDefinition, arguments: Option<&Value>) -> Result<(), String> {
+    let (Some(properties), Some(arguments)) = (
+        tool.input_schema
+            .get("properties")
+            .and_then(Value::as_object),
+        arguments.and_then(Value::as_object),
+    ) else {
+        return Ok(());
+    };
+
+    for (name, schema) in properties {
+        if schema.get("format").and_then(Value::as_str) != Some("decimal") {
+            continue;
+        }
+        let problem = match arguments.get(name) {
+            None | Some(Value::Null) => None,
+            Some(Value::String(text)) => text.parse::<Decimal>().err().map(|e| e.to_string()),
+            Some(Value::Number(_)) => Some(NUMBER_NOT_EXACT.to_string()),
+            Some(other) => Some(format!("expected a decimal string, got {other}")),
+        };
+        if let Some(problem) = problem {
+            return Err(format!(
+                "Parameter '{}' for tool '{}' must be a decimal: {}",
+                name, tool.name, problem
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn decimal(text: &str) -> Decimal {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_formats() {
+        for (text, formatted) in [
+            ("12.50", "12.50"),
+            ("+0.05", "0.05"),
+            ("-3", "-3"),
+            ("-0.001", "-0.001"),
+        ] {
+            assert_eq!(decimal(text).to_string(), formatted);
+        }
+        for text in ["", "1.", ".5", "1e3", "--1", "1,5"] {
+            assert_eq!(
+                text.parse::<Decimal>(),
+                Err(DecimalError::Invalid(text.to_string()))
+            );
+        }
+        let precise = format!("0.{}", "1".repeat(29));
+        assert_eq!(
+            precise.parse::<Decimal>(),
+            Err(DecimalError::TooPrecise(precise.clone()))
+        );
+        assert_eq!(Decimal::new(1250, 2).unwrap().to_string(), "12.50");
+    }
+
+    #[test]
+    fn computes_exactly() {
+        assert_eq!(
+            decimal("0.1")
+                .checked_add(decimal("0.2"))
+                .unwrap()
+                .to_string(),
+            "0.3"
+        );
+        assert_eq!(
+            decimal("10")
+                .checked_sub(decimal("0.25"))
+                .unwrap()
+                .to_string(),
+            "9.75"
+        );
+        assert_eq!(
+            decimal("1.50")
+                .checked_mul(decimal("4"))
+                .unwrap()
+                .to_string(),
+            "6"
+        );
+        assert_eq!(
+            Decimal::new(i128::MAX, 0)
+                .unwrap()
+                .checked_add(decimal("1")),
+            Err(DecimalError::Overflow)
+        );
+    }
+
+    #[test]
+    fn compares_values_regardless_of_scale() {
+        assert_eq!(decimal("1.5"), decimal("1.50"));
+        assert!(decimal("-1.5") < decimal("-1.25"));
+        assert!(decimal("2") > decimal("1.999"));
+        assert_eq!(decimal("1.500").normalized().scale(), 1);
+    }
+
+    #[test]
+    fn travels_as_json_strings() {
+        assert_eq!(
+            serde_json::to_value(decimal("12.50")).unwrap(),
+            json!("12.50")
+        );
+        assert_eq!(format!("{:?}", decimal("12.50")), "\"12.50\"");
+        assert_eq!(
+            serde_json::from_value::<Decimal>(json!("0.3")).unwrap(),
+            decimal("0.3")
+        );
+        assert!(serde_json::from_value::<Decimal>(json!(0.3)).is_err());
+    }
+
+    #[test]
+    fn checks_decimal_arguments() {
+        let tool = ToolDefinition {
+            name: "convert".to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "amount": { "type": "string", "format": "decimal" },
+                    "note": { "type": "string" }
+                }
+            }),
+            output_schema: None,
+        };
+        assert!(check_arguments(&tool, Some(&json!({ "amount": "1.25", "note": "x" }))).is_ok());
+        assert!(check_arguments(&tool, Some(&json!({}))).is_ok());
+        assert_eq!(
+            check_arguments(&tool, Some(&json!({ "amount": 1.25 }))),
+            Err(format!(
+                "Parameter 'amount' for tool 'convert' must be a decimal: {NUMBER_NOT_EXACT}"
+            ))
+        );
+        assert_eq!(
+            check_arguments(&tool, Some(&json!({ "amount": "1.2.5" }))),
+            Err("Parameter 'amount' for tool 'convert' must be a decimal: \
+                 '1.2.5' is not a decimal number"
+                .to_string())
+        );
+    }
+}
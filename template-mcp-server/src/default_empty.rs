@@ -0,0 +1,103 @@
+//! List parameters that default to empty
+//!
+//! A tool taking `Vec<T>` makes clients send the list even when they have
+//! nothing to put in it, and `Option<Vec<T>>` makes the tool tell a missing
+//! list from an empty one. Parameters in a tool's `default_empty` setting
+//! (see [`crate::tool_settings`]) are declared `Vec<T>` and may still be
+//! omitted: the backend fills in `[]` for a missing or `null` argument before
+//! the tool runs, and `tools/list` shows them as optional with a `default` of
+//! `[]`. Other list parameters stay required.
+//!
+//! Only use it where an empty list means the same as no list. A filter whose
+//! absence means "everything" needs `Option<Vec<T>>`.
+
+use pulseengine_mcp_protocol::Tool;
+use serde_json::{json, Value};
+
+fn params_of(tool: &str) -> impl Iterator<Item = &'static str> {
+    crate::tool_settings::settings_of(tool)
+        .default_empty
+        .iter()
+        .copied()
+}
+
+/// Fill omitted (or null) list arguments of `tool` with `[]`
+pub fn apply_to_arguments(tool: &str, arguments: &mut Option<Value>) {
+    let args = arguments.get_or_insert_with(|| Value::Object(Default::default()));
+    let Some(args) = args.as_object_mut() else {
+        return;
+    };
+    for param in params_of(tool) {
+        if args.get(param).is_none_or(Value::is_null) {
+            args.insert(param.to_string(), json!([]));
+        }
+    }
+}
+
+/// Show the listed parameters of a tool as optional, defaulting to `[]`
+pub fn apply_to_schema(tool: &mut Tool) {
+    for param in params_of(&tool.name) {
+        if let Some(property) = tool
+            .input_schema
+            .get_mut("properties")
+            .and_then(|p| p.get_mut(param))
+            .and_then(Value::as_object_mut)
+        {
+            property.insert("default".to_string(), json!([]));
+        }
+        if let Some(required) = tool
+            .input_schema
+            .get_mut("required")
+            .and_then(Value::as_array_mut)
+        {
+            required.retain(|name| name != param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_omitted_and_null_lists() {
+        let mut omitted = None;
+        apply_to_arguments("create_data", &mut omitted);
+        assert_eq!(omitted, Some(json!({ "tags": [] })));
+
+        let mut null = Some(json!({ "name": "a", "tags": null }));
+        apply_to_arguments("create_data", &mut null);
+        assert_eq!(null, Some(json!({ "name": "a", "tags": [] })));
+
+        let mut given = Some(json!({ "tags": ["x"] }));
+        apply_to_arguments("create_data", &mut given);
+        assert_eq!(given, Some(json!({ "tags": ["x"] })));
+    }
+
+    #[test]
+    fn leaves_other_tools_alone() {
+        let mut arguments = Some(json!({ "message": "hi" }));
+        apply_to_arguments("echo", &mut arguments);
+        assert_eq!(arguments, Some(json!({ "message": "hi" })));
+    }
+
+    #[test]
+    fn shows_the_lists_as_optional() {
+        let mut tool = Tool {
+            name: "process_list".to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "items": { "type": "array" }, "operation": { "type": "string" } },
+                "required": ["items", "operation"]
+            }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool);
+        assert_eq!(
+            tool.input_schema["properties"]["items"]["default"],
+            json!([])
+        );
+        assert_eq!(tool.input_schema["required"], json!(["operation"]));
+    }
+}
@@ -0,0 +1,131 @@
+//! Tool side effects and the policy allowing them
+//!
+//! A tool's `effects` setting (see [`crate::tool_settings`]) tags it with the
+//! kinds of side effect it has, such as touching the database or the
+//! filesystem. `AccessConfig::allowed_effects`
+//! lists the effects a deployment permits, all of them by default. A tool with
+//! an effect that isn't allowed is left out of `tools/list`, and calls to it
+//! fail before it runs, so a deployment can, say, forbid every filesystem tool
+//! by leaving `filesystem` out.
+//!
+//! Tools declaring no effects have none, so an effect left out lets the tool
+//! through any policy. The 0.11 tool definitions have no annotations, so
+//! `tools/list` shows a tool's effects as the `x-effects` keyword of its input
+//! schema.
+
+use crate::ServerConfig;
+use pulseengine_mcp_protocol::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// A kind of side effect a tool can have
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolEffect {
+    Network,
+    Filesystem,
+    Database,
+}
+
+impl ToolEffect {
+    pub const ALL: &'static [ToolEffect] = &[Self::Network, Self::Filesystem, Self::Database];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Network => "network",
+            Self::Filesystem => "filesystem",
+            Self::Database => "database",
+        }
+    }
+}
+
+/// Side effects of `tool`
+pub fn effects_of(tool: &str) -> &'static [ToolEffect] {
+    crate::tool_settings::settings_of(tool).effects
+}
+
+/// Effects of `tool` that `config` doesn't allow
+pub fn forbidden_effects(tool: &str, config: &ServerConfig) -> Vec<ToolEffect> {
+    effects_of(tool)
+        .iter()
+        .copied()
+        .filter(|effect| !config.access.allowed_effects.contains(effect))
+        .collect()
+}
+
+pub fn is_allowed(tool: &str, config: &ServerConfig) -> bool {
+    forbidden_effects(tool, config).is_empty()
+}
+
+/// Reject a call to a tool with an effect `config` doesn't allow
+pub fn check_access(tool: &str, config: &ServerConfig) -> Result<(), String> {
+    match forbidden_effects(tool, config).as_slice() {
+        [] => Ok(()),
+        forbidden => Err(format!(
+            "Tool '{tool}' is not available: it has effects this server doesn't allow: {}",
+            forbidden
+                .iter()
+                .map(|effect| effect.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Show a tool's effects in its input schema
+pub fn apply_to_schema(tool: &mut Tool) {
+    let effects = effects_of(&tool.name);
+    if effects.is_empty() {
+        return;
+    }
+    if let Some(schema) = tool.input_schema.as_object_mut() {
+        schema.insert("x-effects".to_string(), json!(effects));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowing(effects: &[ToolEffect]) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.access.allowed_effects = effects.to_vec();
+        config
+    }
+
+    #[test]
+    fn allows_every_effect_by_default() {
+        let config = ServerConfig::default();
+        for tool in ["fetch_upstream", "list_roots", "list_data", "echo"] {
+            assert_eq!(check_access(tool, &config), Ok(()), "{tool}");
+        }
+    }
+
+    #[test]
+    fn refuses_tools_with_effects_left_out() {
+        let config = allowing(&[ToolEffect::Network, ToolEffect::Database]);
+        assert_eq!(
+            check_access("list_roots", &config),
+            Err(
+                "Tool 'list_roots' is not available: it has effects this server doesn't \
+                 allow: filesystem"
+                    .to_string()
+            )
+        );
+        assert!(is_allowed("fetch_upstream", &config));
+        // Tools without effects pass any policy
+        assert!(is_allowed("echo", &allowing(&[])));
+    }
+
+    #[test]
+    fn shows_the_effects_in_the_schema() {
+        let mut tool = Tool {
+            name: "fetch_upstream".to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool);
+        assert_eq!(tool.input_schema["x-effects"], json!(["network"]));
+    }
+}
@@ -0,0 +1,130 @@
+//! Conditional resource reads with ETags
+//!
+//! `resources/read_conditional` reads a resource like `resources/read` and
+//! also returns an ETag, the quoted [`content_token`] of the body. A client
+//! that sends the ETag back as `if_none_match` gets `not_modified: true` and
+//! no contents while the resource is unchanged, so stable resources such as
+//! the server config aren't sent again. As with HTTP's `If-None-Match`,
+//! `if_none_match` may list several ETags separated by commas, or be `*` to
+//! match any content.
+//!
+//! The ETag is computed from the body after post-processing, so it is the
+//! same token `resources/wait` hands out, quoted.
+
+use crate::resource_wait::content_token;
+use pulseengine_mcp_protocol::{ReadResourceResult, ResourceContents};
+use serde::{Deserialize, Serialize};
+
+/// Custom JSON-RPC method for conditional reads
+pub const CONDITIONAL_READ_METHOD: &str = "resources/read_conditional";
+
+/// Parameters of `resources/read_conditional`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConditionalReadRequest {
+    pub uri: String,
+    /// ETags from previous reads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_none_match: Option<String>,
+}
+
+/// Result of `resources/read_conditional`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConditionalReadResult {
+    /// Empty when `not_modified` is true
+    pub contents: Vec<ResourceContents>,
+    pub etag: String,
+    /// True if the content matches `if_none_match`
+    pub not_modified: bool,
+}
+
+/// ETag of a resource read
+pub fn etag(result: &ReadResourceResult) -> String {
+    format!("\"{}\"", content_token(result))
+}
+
+/// Whether `if_none_match` matches `etag`
+///
+/// Weak ETags (`W/"..."`) and unquoted tokens compare equal to the strong
+/// ETag with the same token.
+pub fn matches(if_none_match: &str, etag: &str) -> bool {
+    let token = |tag: &str| -> String {
+        let tag = tag.trim();
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        tag.trim_matches('"').to_string()
+    };
+    let etag = token(etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || token(candidate) == etag)
+}
+
+/// Answer `request` with the result of reading its resource
+pub fn respond(
+    request: &ConditionalReadRequest,
+    result: ReadResourceResult,
+) -> ConditionalReadResult {
+    let etag = etag(&result);
+    let not_modified = request
+        .if_none_match
+        .as_deref()
+        .is_some_and(|if_none_match| matches(if_none_match, &etag));
+    ConditionalReadResult {
+        contents: if not_modified {
+            Vec::new()
+        } else {
+            result.contents
+        },
+        etag,
+        not_modified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> ReadResourceResult {
+        ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: "template://server-config".to_string(),
+                mime_type: Some("application/json".to_string()),
+                text: Some(text.to_string()),
+                blob: None,
+            }],
+        }
+    }
+
+    fn request(if_none_match: Option<&str>) -> ConditionalReadRequest {
+        ConditionalReadRequest {
+            uri: "template://server-config".to_string(),
+            if_none_match: if_none_match.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn skips_the_body_while_unchanged() {
+        let first = respond(&request(None), read("{}"));
+        assert!(!first.not_modified);
+        assert_eq!(first.contents.len(), 1);
+
+        let again = respond(&request(Some(&first.etag)), read("{}"));
+        assert!(again.not_modified);
+        assert!(again.contents.is_empty());
+        assert_eq!(again.etag, first.etag);
+
+        let changed = respond(&request(Some(&first.etag)), read("{\"a\":1}"));
+        assert!(!changed.not_modified);
+        assert_ne!(changed.etag, first.etag);
+    }
+
+    #[test]
+    fn matches_lists_weak_tags_and_wildcards() {
+        let etag = "\"abc\"";
+        assert!(matches("\"abc\"", etag));
+        assert!(matches("W/\"abc\"", etag));
+        assert!(matches("abc", etag));
+        assert!(matches("\"x\", \"abc\"", etag));
+        assert!(matches("*", etag));
+        assert!(!matches("\"abd\"", etag));
+    }
+}
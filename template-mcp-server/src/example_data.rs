@@ -0,0 +1,279 @@
+//! Building validated example data
+//!
+//! [`ExampleData::builder`] is the way to make an [`ExampleData`] that holds
+//! up: [`ExampleDataBuilder::build`] checks that the name isn't blank and the
+//! value is finite, failing with a [`ValidationError`] naming the field.
+//! `create_data` builds its entry this way before storing it, and returns
+//! a failure as a tool error with code [`INVALID_DATA_CODE`], the problem in
+//! its details:
+//!
+//! ```json
+//! {
+//!   "code": -32602,
+//!   "message": "Invalid example data",
+//!   "data": { "code": 1002, "details": { "path": "name", "message": "must not be blank" } }
+//! }
+//! ```
+//!
+//! `create_data_bulk` builds each of its [`CreateDataInput`] items the same
+//! way, reporting a failure next to the item's index instead (see
+//! [`crate::bulk`]).
+//!
+//! Validate the same way in your own types: check in the one place values are
+//! constructed, so no caller can skip it. JSON can't carry a non-finite
+//! number, so only other callers can hit the `value` check.
+
+use crate::tool_errors::{ErrorCode, ToolError};
+use crate::validation::ValidationError;
+use crate::ExampleData;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Code of the tool error for invalid example data
+pub const INVALID_DATA_CODE: i32 = 1002;
+
+impl ErrorCode for ValidationError {
+    fn code(&self) -> i32 {
+        INVALID_DATA_CODE
+    }
+}
+
+impl ExampleData {
+    pub fn builder() -> ExampleDataBuilder {
+        ExampleDataBuilder::default()
+    }
+}
+
+/// An [`ExampleData`] under construction
+///
+/// The id is 0 unless set; stores assign their own.
+#[derive(Debug, Clone, Default)]
+pub struct ExampleDataBuilder {
+    id: u64,
+    name: String,
+    value: f64,
+    tags: Vec<String>,
+}
+
+impl ExampleDataBuilder {
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn value(mut self, value: f64) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// The data, if every field is valid
+    pub fn build(self) -> Result<ExampleData, ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(invalid("name", "must not be blank".to_string()));
+        }
+        if !self.value.is_finite() {
+            return Err(invalid(
+                "value",
+                format!("must be finite, got {}", self.value),
+            ));
+        }
+        Ok(ExampleData {
+            id: self.id,
+            name: self.name,
+            value: self.value,
+            tags: self.tags,
+        })
+    }
+}
+
+/// One entry to create with `create_data_bulk`, with the arguments of
+/// `create_data`
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(inline)]
+pub struct CreateDataInput {
+    pub name: String,
+    pub value: f64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// The items of a `create_data_bulk` call
+///
+/// The 0.11 macros can't build the schema of a `Vec` of structs, but take the
+/// schema of any other named type from its `JsonSchema` impl, so the
+/// parameter is declared with this alias.
+pub type CreateDataInputs = Vec<CreateDataInput>;
+
+impl CreateDataInput {
+    pub fn builder(self) -> ExampleDataBuilder {
+        ExampleData::builder()
+            .name(self.name)
+            .value(self.value)
+            .tags(self.tags)
+    }
+}
+
+/// Which stored example data entries to include
+///
+/// Shared by the `filter_data` tool and the `review_data` prompt, so both
+/// take the same arguments (see `shared_args.rs`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct DataFilter {
+    /// Only entries with this tag
+    pub tag: Option<String>,
+    /// Only entries whose name contains this text
+    pub name_contains: Option<String>,
+    /// Only entries with at least this value
+    pub min_value: Option<f64>,
+}
+
+impl DataFilter {
+    pub fn matches(&self, data: &ExampleData) -> bool {
+        self.tag.as_ref().is_none_or(|tag| data.tags.contains(tag))
+            && self
+                .name_contains
+                .as_ref()
+                .is_none_or(|text| data.name.contains(text.as_str()))
+            && self.min_value.is_none_or(|min| data.value >= min)
+    }
+}
+
+/// An [`ExampleData`] as the `template://example-data/{id}` resource returns
+/// it: in full, or just its id and name for `?format=summary` (see
+/// `resource_queries.rs`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum ExampleDataView {
+    Full(ExampleData),
+    Summary { id: u64, name: String },
+}
+
+impl ExampleDataView {
+    /// `data` in the resource's `format`, in full unless `summary`
+    pub fn new(data: ExampleData, format: Option<&str>) -> Self {
+        match format {
+            Some("summary") => Self::Summary {
+                id: data.id,
+                name: data.name,
+            },
+            _ => Self::Full(data),
+        }
+    }
+}
+
+fn invalid(path: &str, message: String) -> ValidationError {
+    ValidationError {
+        path: path.to_string(),
+        message,
+    }
+}
+
+/// `error` as the tool error `create_data` returns
+pub fn invalid_data(error: ValidationError) -> ToolError<ValidationError> {
+    ToolError::new("Invalid example data", error)
+}
+
+/// `error` as the failure of a `create_data_bulk` item
+pub fn invalid_item(error: ValidationError) -> anyhow::Error {
+    anyhow::anyhow!("Invalid example data: {} {}", error.path, error.message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, value: f64, tags: &[&str]) -> ExampleData {
+        ExampleData::builder()
+            .name(name)
+            .value(value)
+            .tags(tags.iter().map(ToString::to_string).collect())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn builds_valid_data() {
+        let data = ExampleData::builder()
+            .id(3)
+            .name("sensor")
+            .value(1.5)
+            .tag("a")
+            .tag("b")
+            .build()
+            .unwrap();
+        assert_eq!(
+            (data.id, data.name.as_str(), data.value, data.tags),
+            (3, "sensor", 1.5, vec!["a".to_string(), "b".to_string()])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let blank = ExampleData::builder().name("  ").build().unwrap_err();
+        assert_eq!(blank, invalid("name", "must not be blank".to_string()));
+        let infinite = ExampleData::builder()
+            .name("x")
+            .value(f64::INFINITY)
+            .build()
+            .unwrap_err();
+        assert_eq!(infinite.path, "value");
+        assert_eq!(infinite.message, "must be finite, got inf");
+        assert_eq!(
+            invalid_item(blank).to_string(),
+            "Invalid example data: name must not be blank"
+        );
+    }
+
+    #[test]
+    fn filters_on_every_given_field() {
+        let data = entry("kitchen sensor", 4.0, &["home"]);
+        assert!(DataFilter::default().matches(&data));
+        let filter = DataFilter {
+            tag: Some("home".to_string()),
+            name_contains: Some("sensor".to_string()),
+            min_value: Some(4.0),
+        };
+        assert!(filter.matches(&data));
+        assert!(!DataFilter {
+            min_value: Some(5.0),
+            ..filter.clone()
+        }
+        .matches(&data));
+        assert!(!DataFilter {
+            tag: Some("work".to_string()),
+            ..filter
+        }
+        .matches(&data));
+    }
+
+    #[test]
+    fn summarizes_on_request() {
+        let data = ExampleData {
+            id: 2,
+            ..entry("probe", 1.0, &[])
+        };
+        assert_eq!(
+            serde_json::to_value(ExampleDataView::new(data.clone(), Some("summary"))).unwrap(),
+            serde_json::json!({ "id": 2, "name": "probe" })
+        );
+        assert_eq!(
+            serde_json::to_value(ExampleDataView::new(data, None)).unwrap()["value"],
+            1.0
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Resources served from files on disk
+//!
+//! A [`FileResourceProvider`] maps a URI template ending in `{path}` to the
+//! files under a root directory, so `template://files/{path}` with root
+//! `./docs` serves `./docs/guide/intro.md` as `template://files/guide/intro.md`.
+//! The backend serves the providers attached with
+//! `TemplateBackend::with_file_resources`, and the default backend serves
+//! `MCP_FILE_RESOURCES_DIR` this way.
+//!
+//! Paths can't leave the root: `..`, absolute paths and symlinks pointing
+//! outside it are rejected. The mime type comes from the file extension; text
+//! files are returned as `text` and everything else as a base64 `blob`. The
+//! protocol has no streaming reads, so files larger than [`MAX_FILE_SIZE`] are
+//! refused rather than loaded whole.
+
+use base64::Engine;
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, ResourceContents};
+use std::path::{Component, Path, PathBuf};
+
+/// Environment variable naming the directory the default backend serves
+pub const FILE_RESOURCES_DIR_ENV_VAR: &str = "MCP_FILE_RESOURCES_DIR";
+
+/// URI template the default backend serves files under
+pub const DEFAULT_URI_TEMPLATE: &str = "template://files/{path}";
+
+/// Largest file served, in bytes
+pub const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+const PATH_PARAM: &str = "{path}";
+
+/// Why a file resource couldn't be read
+#[derive(Debug)]
+pub enum FileResourceError {
+    /// The path would leave the root directory
+    OutsideRoot(String),
+    NotFound(String),
+    TooLarge {
+        path: String,
+        size: u64,
+    },
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for FileResourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutsideRoot(path) => write!(f, "Path '{path}' is outside the resource root"),
+            Self::NotFound(path) => write!(f, "Resource not found: {path}"),
+            Self::TooLarge { path, size } => write!(
+                f,
+                "File '{path}' is {size} bytes, more than the {MAX_FILE_SIZE} byte limit"
+            ),
+            Self::Io(e) => write!(f, "Failed to read file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for FileResourceError {}
+
+/// Serves the files under a directory as resources
+#[derive(Debug, Clone)]
+pub struct FileResourceProvider {
+    root: PathBuf,
+    uri_template: String,
+    uri_prefix: String,
+}
+
+impl FileResourceProvider {
+    /// Serve the files under `root` at `uri_template`, which must end in `{path}`
+    pub fn new(root: impl Into<PathBuf>, uri_template: &str) -> anyhow::Result<Self> {
+        let uri_prefix = uri_template.strip_suffix(PATH_PARAM).ok_or_else(|| {
+            anyhow::anyhow!("URI template must end in {PATH_PARAM}: {uri_template}")
+        })?;
+        Ok(Self {
+            root: root.into(),
+            uri_template: uri_template.to_string(),
+            uri_prefix: uri_prefix.to_string(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether `uri` falls under this provider's template
+    pub fn matches(&self, uri: &str) -> bool {
+        uri.starts_with(&self.uri_prefix)
+    }
+
+    /// The resource template, as listed by `resources/list`
+    pub fn resource(&self) -> Resource {
+        Resource {
+            uri: self.uri_template.clone(),
+            name: "files".to_string(),
+            description: Some(format!("Files under {}", self.root.display())),
+            mime_type: None,
+            annotations: None,
+            raw: None,
+        }
+    }
+
+    /// Read the file a matching `uri` points to
+    pub async fn read(&self, uri: &str) -> Result<ReadResourceResult, FileResourceError> {
+        let relative = uri.strip_prefix(&self.uri_prefix).unwrap_or(uri);
+        let relative = percent_decode(relative);
+        let path = self.resolve(&relative).await?;
+
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| not_found_or_io(e, &relative))?;
+        if !metadata.is_file() {
+            return Err(FileResourceError::NotFound(relative));
+        }
+        let size = metadata.len();
+        if size > MAX_FILE_SIZE {
+            return Err(FileResourceError::TooLarge {
+                path: relative,
+                size,
+            });
+        }
+
+        let bytes = tokio::fs::read(&path)
+            .await
+            .map_err(|e| not_found_or_io(e, &relative))?;
+        let mime_type = mime_type(&path);
+        let (text, blob) = if is_text(mime_type) {
+            match String::from_utf8(bytes) {
+                Ok(text) => (Some(text), None),
+                Err(e) => (None, Some(encode(e.as_bytes()))),
+            }
+        } else {
+            (None, Some(encode(&bytes)))
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(mime_type.to_string()),
+                text,
+                blob,
+            }],
+        })
+    }
+
+    /// The canonical path of `relative`, checked to be under the root
+    async fn resolve(&self, relative: &str) -> Result<PathBuf, FileResourceError> {
+        let relative_path = Path::new(relative);
+        let escapes = relative_path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+        if relative.is_empty() || escapes {
+            return Err(FileResourceError::OutsideRoot(relative.to_string()));
+        }
+
+        // Canonical paths resolve symlinks, which could also point outside
+        let root = tokio::fs::canonicalize(&self.root)
+            .await
+            .map_err(FileResourceError::Io)?;
+        let path = tokio::fs::canonicalize(root.join(relative_path))
+            .await
+            .map_err(|e| not_found_or_io(e, relative))?;
+        if !path.starts_with(&root) {
+            return Err(FileResourceError::OutsideRoot(relative.to_string()));
+        }
+        Ok(path)
+    }
+}
+
+fn not_found_or_io(error: std::io::Error, path: &str) -> FileResourceError {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => FileResourceError::NotFound(path.to_string()),
+        _ => FileResourceError::Io(error),
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Mime type for a file extension, `application/octet-stream` if unknown
+pub fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("txt") => "text/plain",
+        Some("md" | "markdown") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("js" | "mjs") => "text/javascript",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("yaml" | "yml") => "application/yaml",
+        Some("toml") => "application/toml",
+        Some("rs") => "text/x-rust",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+pub(crate) fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json"
+                | "application/xml"
+                | "application/yaml"
+                | "application/toml"
+                | "image/svg+xml"
+        )
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are
+pub(crate) fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| input.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory with a text file, a binary file and a subdirectory
+    fn root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("template-mcp-files-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("guide")).unwrap();
+        std::fs::write(root.join("guide/intro.md"), "# Intro").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        root
+    }
+
+    fn provider(root: &Path) -> FileResourceProvider {
+        FileResourceProvider::new(root, DEFAULT_URI_TEMPLATE).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_text_and_binary_files() {
+        let root = root("serve");
+        let files = provider(&root);
+        assert!(files.matches("template://files/guide/intro.md"));
+
+        let text = files.read("template://files/guide/intro.md").await.unwrap();
+        assert_eq!(text.contents[0].mime_type.as_deref(), Some("text/markdown"));
+        assert_eq!(text.contents[0].text.as_deref(), Some("# Intro"));
+
+        let binary = files.read("template://files/logo.png").await.unwrap();
+        assert_eq!(binary.contents[0].text, None);
+        assert_eq!(binary.contents[0].blob.as_deref(), Some("iVBORw=="));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_paths_outside_the_root() {
+        let root = root("outside");
+        let files = provider(&root.join("guide"));
+        for uri in [
+            "template://files/../logo.png",
+            "template://files/%2e%2e/logo.png",
+            "template://files//etc/passwd",
+            "template://files/",
+        ] {
+            let error = files.read(uri).await.unwrap_err();
+            assert!(
+                matches!(error, FileResourceError::OutsideRoot(_)),
+                "{uri}: {error}"
+            );
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("logo.png"), root.join("guide/link.png")).unwrap();
+            let error = files.read("template://files/link.png").await.unwrap_err();
+            assert!(
+                matches!(error, FileResourceError::OutsideRoot(_)),
+                "{error}"
+            );
+        }
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_missing_files() {
+        let root = root("missing");
+        let error = provider(&root)
+            .read("template://files/guide")
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Resource not found: guide");
+        let error = provider(&root)
+            .read("template://files/none.txt")
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Resource not found: none.txt");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn templates_end_in_the_path() {
+        assert!(FileResourceProvider::new("/tmp", "template://files/{name}").is_err());
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+        assert_eq!(mime_type(Path::new("DATA.JSON")), "application/json");
+        assert!(!is_text(mime_type(Path::new("archive.zip"))));
+    }
+}
@@ -0,0 +1,160 @@
+//! Guards for non-finite floats (`NaN`, `Infinity`), which JSON can't represent
+//!
+//! Tool results should pass floats through [`guard_f64`], which either rejects
+//! non-finite values or encodes them as the strings `"NaN"`, `"Infinity"` and
+//! `"-Infinity"`, depending on [`NonFinitePolicy`]. A rejected result fails
+//! the call with an internal error (-32603), since the tool produced it.
+//! Arguments are always checked: a non-finite token sent for a numeric
+//! parameter is rejected as invalid params (-32602) with a clear error
+//! instead of a confusing deserialization failure.
+
+use pulseengine_mcp_protocol::Tool as ToolDefinition;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+/// How tool results containing non-finite floats are handled
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NonFinitePolicy {
+    /// Fail the call with an error naming the value
+    #[default]
+    Reject,
+    /// Encode as `"NaN"`, `"Infinity"` or `"-Infinity"`
+    Stringify,
+}
+
+/// A float that is safe to serialize as JSON
+#[derive(Clone, Copy, PartialEq)]
+pub enum JsonFloat {
+    Finite(f64),
+    /// A non-finite value encoded by the documented string convention
+    NonFinite(&'static str),
+}
+
+impl std::fmt::Debug for JsonFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Finite(value) => write!(f, "{value:?}"),
+            Self::NonFinite(token) => write!(f, "{token:?}"),
+        }
+    }
+}
+
+impl Serialize for JsonFloat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Finite(value) => serializer.serialize_f64(*value),
+            Self::NonFinite(token) => serializer.serialize_str(token),
+        }
+    }
+}
+
+/// String encoding of a non-finite value, or `None` if it is finite
+pub fn non_finite_token(value: f64) -> Option<&'static str> {
+    if value.is_nan() {
+        Some("NaN")
+    } else if value == f64::INFINITY {
+        Some("Infinity")
+    } else if value == f64::NEG_INFINITY {
+        Some("-Infinity")
+    } else {
+        None
+    }
+}
+
+/// Check a float tool result against `policy`
+pub fn guard_f64(value: f64, policy: NonFinitePolicy) -> anyhow::Result<JsonFloat> {
+    match (non_finite_token(value), policy) {
+        (None, _) => Ok(JsonFloat::Finite(value)),
+        (Some(token), NonFinitePolicy::Stringify) => Ok(JsonFloat::NonFinite(token)),
+        (Some(token), NonFinitePolicy::Reject) => Err(anyhow::anyhow!(
+            "Result is not a finite number ({}) and cannot be represented in JSON",
+            token
+        )),
+    }
+}
+
+/// Reject non-finite tokens sent for numeric parameters of `tool`
+pub fn check_arguments(tool: &ToolDefinition, arguments: Option<&Value>) -> Result<(), String> {
+    let (Some(properties), Some(arguments)) = (
+        tool.input_schema
+            .get("properties")
+            .and_then(Value::as_object),
+        arguments.and_then(Value::as_object),
+    ) else {
+        return Ok(());
+    };
+
+    for (name, schema) in properties {
+        let numeric = matches!(
+            schema.get("type").and_then(Value::as_str),
+            Some("number") | Some("integer")
+        );
+        if let (true, Some(Value::String(text))) = (numeric, arguments.get(name)) {
+            if is_non_finite_token(text) {
+                return Err(format!(
+                    "Parameter '{}' for tool '{}' must be a finite number, got \"{}\"",
+                    name, tool.name, text
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_non_finite_token(text: &str) -> bool {
+    matches!(
+        text.trim().to_ascii_lowercase().as_str(),
+        "nan" | "infinity" | "+infinity" | "-infinity" | "inf" | "+inf" | "-inf"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn finite_results_pass_either_policy() {
+        for policy in [NonFinitePolicy::Reject, NonFinitePolicy::Stringify] {
+            let value = guard_f64(1.5, policy).unwrap();
+            assert_eq!(serde_json::to_value(value).unwrap(), json!(1.5));
+        }
+    }
+
+    #[test]
+    fn non_finite_results_are_rejected_or_stringified() {
+        for (value, token) in [
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ] {
+            let error = guard_f64(value, NonFinitePolicy::Reject).unwrap_err();
+            assert!(error.to_string().contains(token), "{error}");
+            let encoded = guard_f64(value, NonFinitePolicy::Stringify).unwrap();
+            assert_eq!(serde_json::to_value(encoded).unwrap(), json!(token));
+        }
+    }
+
+    #[test]
+    fn non_finite_tokens_for_numbers_are_refused() {
+        let tool = ToolDefinition {
+            name: "add_numbers".to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "a": { "type": "number" }, "label": { "type": "string" } }
+            }),
+            output_schema: None,
+        };
+        for token in ["NaN", "Infinity", "-inf", " +Infinity "] {
+            let error = check_arguments(&tool, Some(&json!({ "a": token }))).unwrap_err();
+            assert!(error.contains("must be a finite number"), "{error}");
+        }
+        assert_eq!(check_arguments(&tool, Some(&json!({ "a": 2 }))), Ok(()));
+        assert_eq!(
+            check_arguments(&tool, Some(&json!({ "label": "NaN" }))),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Server health aggregated from its components
+//!
+//! Tools and providers register a [`HealthCheck`] per dependency on
+//! `TemplateMcpServer::health`. A check reports [`HealthStatus::Degraded`]
+//! when its dependency is down but tools still work without it, such as a
+//! cache, and [`HealthStatus::Unhealthy`] when the server can't do its job.
+//! The server's status is the worst of its components, and is served:
+//!
+//! - as `health` in `ServerStatus` (the `template://server-status` resource)
+//! - from `GET /healthz` on [`HEALTH_ADDR_ENV_VAR`], when it is set, with
+//!   status 200 unless the server is unhealthy, which gets 503
+//! - to the framework's own health endpoint, as unhealthy or not
+//!
+//! The same address serves the circuit breaker metrics on `GET /metrics`, in
+//! the Prometheus text format; see [`crate::circuit_breaker`].
+//!
+//! The circuit breaker of every tool with the `circuit_breaker` setting, such
+//! as `create_data`, is registered out of the box: an open circuit degrades
+//! the server.
+
+use crate::circuit_breaker::{self, CircuitBreaker, CircuitState};
+use crate::TemplateMcpServer;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Environment variable with an address to serve `/healthz` on, e.g.
+/// `127.0.0.1:8081`
+pub const HEALTH_ADDR_ENV_VAR: &str = "MCP_HEALTH_ADDR";
+
+/// Health of a component or of the whole server, from best to worst
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    /// Tools are served, but without some non-critical dependency
+    Degraded,
+    Unhealthy,
+}
+
+/// Checks one dependency
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    async fn check(&self) -> HealthStatus;
+}
+
+#[async_trait]
+impl HealthCheck for CircuitBreaker {
+    /// Degraded while the circuit is open
+    async fn check(&self) -> HealthStatus {
+        match self.state() {
+            CircuitState::Open => HealthStatus::Degraded,
+            CircuitState::Closed | CircuitState::HalfOpen => HealthStatus::Ok,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+}
+
+/// The server's health and that of each component
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HealthReport {
+    /// The worst component status, or ok without components
+    pub status: HealthStatus,
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    /// HTTP status code for the report: 503 if unhealthy, 200 otherwise
+    pub fn http_status(&self) -> u16 {
+        match self.status {
+            HealthStatus::Ok | HealthStatus::Degraded => 200,
+            HealthStatus::Unhealthy => 503,
+        }
+    }
+}
+
+/// Registered health checks by name
+#[derive(Default)]
+pub struct HealthChecks {
+    checks: RwLock<Vec<(String, Arc<dyn HealthCheck>)>>,
+}
+
+impl HealthChecks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check component `name` with `check`, replacing an earlier check of
+    /// that name
+    pub fn register(&self, name: &str, check: Arc<dyn HealthCheck>) {
+        let mut checks = self
+            .checks
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        checks.retain(|(existing, _)| existing != name);
+        checks.push((name.to_string(), check));
+    }
+
+    /// Run every check, in registration order
+    pub async fn report(&self) -> HealthReport {
+        let checks = self
+            .checks
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        let mut components = Vec::with_capacity(checks.len());
+        for (name, check) in checks {
+            components.push(ComponentHealth {
+                name,
+                status: check.check().await,
+            });
+        }
+        HealthReport {
+            status: components
+                .iter()
+                .map(|component| component.status)
+                .max()
+                .unwrap_or(HealthStatus::Ok),
+            components,
+        }
+    }
+}
+
+/// Content type of the Prometheus text format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Serve `server`'s health report from `GET /healthz` on `listener`, and its
+/// circuit breaker metrics from `GET /metrics`
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    server: TemplateMcpServer,
+) -> std::io::Result<()> {
+    use axum::http::StatusCode;
+
+    let metrics_server = server.clone();
+    let router = axum::Router::new()
+        .route(
+            "/healthz",
+            axum::routing::get(move || {
+                let server = server.clone();
+                async move {
+                    let report = server.health().report().await;
+                    let status = StatusCode::from_u16(report.http_status())
+                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                    (status, axum::Json(report))
+                }
+            }),
+        )
+        .route(
+            "/metrics",
+            axum::routing::get(move || {
+                let metrics = circuit_breaker::render_metrics(&metrics_server.circuit_breakers());
+                async move {
+                    (
+                        [(axum::http::header::CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+                        metrics,
+                    )
+                }
+            }),
+        );
+    axum::serve(listener, router).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(HealthStatus);
+
+    #[async_trait]
+    impl HealthCheck for Fixed {
+        async fn check(&self) -> HealthStatus {
+            self.0
+        }
+    }
+
+    fn component(name: &str, status: HealthStatus) -> ComponentHealth {
+        ComponentHealth {
+            name: name.to_string(),
+            status,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_the_worst_component() {
+        let checks = HealthChecks::new();
+        assert_eq!(checks.report().await.status, HealthStatus::Ok);
+
+        checks.register("cache", Arc::new(Fixed(HealthStatus::Degraded)));
+        checks.register("database", Arc::new(Fixed(HealthStatus::Ok)));
+        let report = checks.report().await;
+        assert_eq!(
+            report,
+            HealthReport {
+                status: HealthStatus::Degraded,
+                components: vec![
+                    component("cache", HealthStatus::Degraded),
+                    component("database", HealthStatus::Ok),
+                ],
+            }
+        );
+        assert_eq!(report.http_status(), 200);
+    }
+
+    #[tokio::test]
+    async fn replaces_checks_of_the_same_name() {
+        let checks = HealthChecks::new();
+        checks.register("database", Arc::new(Fixed(HealthStatus::Ok)));
+        checks.register("database", Arc::new(Fixed(HealthStatus::Unhealthy)));
+        let report = checks.report().await;
+        assert_eq!(
+            report.components,
+            [component("database", HealthStatus::Unhealthy)]
+        );
+        assert_eq!(report.http_status(), 503);
+    }
+}
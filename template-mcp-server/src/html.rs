@@ -0,0 +1,239 @@
+//! HTML tool results for clients that render them
+//!
+//! A tool returning [`Html`] sends its body as a text content item tagged
+//! `"mimeType": "text/html"`, so clients that can render HTML do, and others
+//! show the source:
+//!
+//! ```json
+//! { "type": "text", "text": "<h1>Report</h1>", "mimeType": "text/html" }
+//! ```
+//!
+//! Bodies over `LimitsConfig::max_html_bytes` fail the call. With
+//! `ToolsConfig::sanitize_html` set, bodies first pass through the backend's
+//! [`HtmlSanitizer`]: [`BasicHtmlSanitizer`] unless another was given to
+//! [`TemplateBackend::with_html_sanitizer`](crate::TemplateBackend::with_html_sanitizer).
+//!
+//! The 0.11 `Content::Text` has no `mimeType`, so the tag is added to the JSON
+//! of the response by [`RawMessageHandler`](crate::raw_messages::RawMessageHandler),
+//! which serves STDIO. HTTP clients get the body as plain text content. Other
+//! text results with a mime type, such as [`crate::ndjson`], are tagged the
+//! same way, with [`tag_later`].
+
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::LazyLock;
+
+pub const HTML_MIME_TYPE: &str = "text/html";
+
+// Rendered HTML always starts with its tag, which keeps detection cheap
+const HTML_PREFIX: &str = r#"{"type":"html""#;
+
+/// A tool return value sent as HTML
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Html(pub String);
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "html")]
+struct Rendered {
+    html: String,
+}
+
+impl std::fmt::Debug for Html {
+    /// Tool results are rendered with `Debug`, so this writes the shape
+    /// [`apply`] recognizes
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rendered = Rendered {
+            html: self.0.clone(),
+        };
+        match serde_json::to_string(&rendered) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+/// Makes untrusted HTML safe to render
+pub trait HtmlSanitizer: Send + Sync {
+    fn sanitize(&self, html: &str) -> String;
+}
+
+/// Removes scripts, styles, embedded objects, event handler attributes and
+/// `javascript:` links
+///
+/// A pattern-based filter for HTML the server builds itself; HTML from
+/// untrusted sources calls for a parsing sanitizer such as `ammonia`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BasicHtmlSanitizer;
+
+static SCRIPTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<script\b[^>]*>.*?</script\s*>|<style\b[^>]*>.*?</style\s*>")
+        .expect("script pattern is valid")
+});
+static EMBEDS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)</?(script|style|iframe|object|embed)\b[^>]*>")
+        .expect("embed pattern is valid")
+});
+static EVENT_HANDLERS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\s+on[a-z]+\s*=\s*("[^"]*"|'[^']*'|[^\s>]+)"#)
+        .expect("event handler pattern is valid")
+});
+static SCRIPT_LINKS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(href|src)\s*=\s*("\s*javascript:[^"]*"|'\s*javascript:[^']*'|javascript:[^\s>]*)"#)
+        .expect("script link pattern is valid")
+});
+
+impl HtmlSanitizer for BasicHtmlSanitizer {
+    fn sanitize(&self, html: &str) -> String {
+        let html = SCRIPTS.replace_all(html, "");
+        let html = EMBEDS.replace_all(&html, "");
+        let html = EVENT_HANDLERS.replace_all(&html, "");
+        SCRIPT_LINKS.replace_all(&html, r##"$1="#""##).into_owned()
+    }
+}
+
+/// `text` with the characters HTML gives a meaning escaped
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+tokio::task_local! {
+    // Positions and mime types of the items to tag in the content of the
+    // current tool result
+    static TAGGED_CONTENTS: RefCell<Vec<(usize, &'static str)>>;
+}
+
+/// Tag the text content item at `index` of the current tool result with
+/// `mime_type` once the response is written
+pub fn tag_later(index: usize, mime_type: &'static str) {
+    // Outside `tagging`, e.g. over HTTP, the item stays plain text
+    let _ = TAGGED_CONTENTS.try_with(|contents| contents.borrow_mut().push((index, mime_type)));
+}
+
+/// Unpack the [`Html`] items of a `tool` result into plain text, checked
+/// against `max_bytes` and sanitized with `sanitizer` if given
+pub fn apply(
+    tool: &str,
+    mut result: CallToolResult,
+    max_bytes: usize,
+    sanitizer: Option<&dyn HtmlSanitizer>,
+) -> Result<CallToolResult, String> {
+    for (index, content) in result.content.iter_mut().enumerate() {
+        let Content::Text { text } = content else {
+            continue;
+        };
+        if !text.starts_with(HTML_PREFIX) {
+            continue;
+        }
+        let Ok(Rendered { html }) = serde_json::from_str(text) else {
+            continue;
+        };
+        if html.len() > max_bytes {
+            return Err(format!(
+                "Tool '{tool}' returned {} bytes of HTML, over the {max_bytes} byte limit",
+                html.len()
+            ));
+        }
+        *text = match sanitizer {
+            Some(sanitizer) => sanitizer.sanitize(&html),
+            None => html,
+        };
+        tag_later(index, HTML_MIME_TYPE);
+    }
+    Ok(result)
+}
+
+/// Run `future`, returning the positions and mime types of the items
+/// [`tag_later`] was called for in the tool result it produced
+pub async fn tagging<F: Future>(future: F) -> (F::Output, Vec<(usize, &'static str)>) {
+    TAGGED_CONTENTS
+        .scope(RefCell::new(Vec::new()), async {
+            let output = future.await;
+            (output, TAGGED_CONTENTS.with(RefCell::take))
+        })
+        .await
+}
+
+/// Tag the content items of a `tools/call` result at the positions of `tags`
+/// with their mime types
+pub fn tag(result: &mut Value, tags: &[(usize, &'static str)]) {
+    let Some(contents) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for (position, mime_type) in tags {
+        if let Some(Value::Object(content)) = contents.get_mut(*position) {
+            if content.get("type").and_then(Value::as_str) == Some("text") {
+                content.insert("mimeType".to_string(), Value::from(*mime_type));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rendered(html: &str) -> CallToolResult {
+        CallToolResult::text(format!("{:?}", Html(html.to_string())))
+    }
+
+    fn text_of(result: &CallToolResult) -> &str {
+        match &result.content[0] {
+            Content::Text { text } => text,
+            other => panic!("not text: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn strips_scripts_handlers_and_script_links() {
+        let sanitized = BasicHtmlSanitizer.sanitize(
+            r#"<h1 onclick="steal()">Hi</h1><script>alert(1)</script><style>h1{}</style><iframe src="x"></iframe><a href="javascript:alert(1)">link</a>"#,
+        );
+        assert_eq!(sanitized, r##"<h1>Hi</h1><a href="#">link</a>"##);
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(
+            escape(r#"<a href="x">&</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[tokio::test]
+    async fn unpacks_and_tags_html_items() {
+        let (result, tags) = tagging(async {
+            apply(
+                "report",
+                rendered("<b>bold</b><script>x</script>"),
+                1000,
+                Some(&BasicHtmlSanitizer),
+            )
+        })
+        .await;
+        let result = result.unwrap();
+        assert_eq!(text_of(&result), "<b>bold</b>");
+        assert_eq!(tags, [(0, HTML_MIME_TYPE)]);
+
+        let mut response = json!({ "content": [{ "type": "text", "text": "<b>bold</b>" }] });
+        tag(&mut response, &tags);
+        assert_eq!(response["content"][0]["mimeType"], HTML_MIME_TYPE);
+    }
+
+    #[test]
+    fn rejects_html_over_the_limit() {
+        assert_eq!(
+            apply("report", rendered("<p>long</p>"), 5, None).unwrap_err(),
+            "Tool 'report' returned 11 bytes of HTML, over the 5 byte limit"
+        );
+        let plain = apply("report", CallToolResult::text("<p>plain</p>"), 5, None).unwrap();
+        assert_eq!(text_of(&plain), "<p>plain</p>");
+    }
+}
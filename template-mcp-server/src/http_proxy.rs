@@ -0,0 +1,545 @@
+//! Resources proxied to an HTTP upstream
+//!
+//! An [`HttpProxyResource`] maps a URI template ending in `{path}` to GET
+//! requests under a base URL, so `template://api/{path}` with base
+//! `http://localhost:8080/v1` serves `template://api/users/7` from
+//! `http://localhost:8080/v1/users/7`. The upstream's body comes back as the
+//! resource contents, with its `Content-Type` as the mime type: text as
+//! `text`, anything else as a base64 `blob`. The backend serves the proxies
+//! attached with `TemplateBackend::with_http_proxy`, and the default backend
+//! proxies to `MCP_HTTP_PROXY_URL` this way.
+//!
+//! Paths are percent-decoded and normalized before they're checked, so
+//! `%2e%2e` and `%2f` can't climb out of an allowed prefix, and are
+//! percent-encoded again for the request line. Paths with whitespace or
+//! control characters, raw or encoded, are refused rather than sent.
+//!
+//! Paths can be limited to prefixes with [`HttpProxyResource::allow_path`],
+//! every request can carry headers such as an API key, and an upstream that
+//! doesn't answer within the timeout fails the read. A 404 and other 4xx
+//! answers are Invalid Params errors, as for a missing file; 5xx answers,
+//! timeouts and connection failures are Internal errors. Redirects aren't
+//! followed.
+//!
+//! Only `http://` upstreams are supported, since the crate has no TLS
+//! client. Requests are sent as HTTP/1.0, so the answer is the raw body up
+//! to the closed connection, never chunked.
+
+use crate::file_resources;
+use base64::Engine;
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, ResourceContents};
+use pulseengine_mcp_server::CommonMcpError;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Environment variable with the base URL the default backend proxies to
+pub const HTTP_PROXY_URL_ENV_VAR: &str = "MCP_HTTP_PROXY_URL";
+
+/// URI template the default backend serves the upstream under
+pub const DEFAULT_URI_TEMPLATE: &str = "template://api/{path}";
+
+/// Time allowed for the upstream to answer, unless set with
+/// [`HttpProxyResource::with_timeout`]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest upstream body served, in bytes
+pub const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+const PATH_PARAM: &str = "{path}";
+
+/// Why a proxied resource couldn't be read
+#[derive(Debug)]
+pub enum HttpProxyError {
+    /// The path is outside the allowed prefixes
+    NotAllowed(String),
+    /// The path has whitespace or control characters
+    InvalidPath(String),
+    NotFound(String),
+    /// The upstream answered with a status other than 2xx or 404
+    Status {
+        path: String,
+        status: u16,
+    },
+    TooLarge(String),
+    Timeout {
+        path: String,
+        timeout: Duration,
+    },
+    InvalidResponse(String),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for HttpProxyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAllowed(path) => write!(f, "Path '{path}' is not allowed by the proxy"),
+            Self::InvalidPath(path) => write!(f, "Path {path:?} has invalid characters"),
+            Self::NotFound(path) => write!(f, "Resource not found: {path}"),
+            Self::Status { path, status } => {
+                write!(f, "Upstream answered {status} for '{path}'")
+            }
+            Self::TooLarge(path) => write!(
+                f,
+                "Upstream body for '{path}' is more than the {MAX_BODY_SIZE} byte limit"
+            ),
+            Self::Timeout { path, timeout } => write!(
+                f,
+                "Upstream didn't answer for '{path}' within {}ms",
+                timeout.as_millis()
+            ),
+            Self::InvalidResponse(reason) => write!(f, "Invalid upstream response: {reason}"),
+            Self::Io(e) => write!(f, "Failed to reach upstream: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for HttpProxyError {}
+
+impl From<HttpProxyError> for CommonMcpError {
+    fn from(error: HttpProxyError) -> Self {
+        match error {
+            HttpProxyError::NotAllowed(_)
+            | HttpProxyError::InvalidPath(_)
+            | HttpProxyError::NotFound(_) => {
+                Self::InvalidParams(format!("Resource error: {error}"))
+            }
+            HttpProxyError::Status { status, .. } if (400..500).contains(&status) => {
+                Self::InvalidParams(format!("Resource error: {error}"))
+            }
+            _ => Self::Internal(format!("Resource error: {error}")),
+        }
+    }
+}
+
+/// Serves the paths under an upstream base URL as resources
+#[derive(Debug, Clone)]
+pub struct HttpProxyResource {
+    host: String,
+    port: u16,
+    base_path: String,
+    uri_template: String,
+    uri_prefix: String,
+    allowed_paths: Vec<String>,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+}
+
+impl HttpProxyResource {
+    /// Proxy `uri_template`, which must end in `{path}`, to `base_url`
+    pub fn new(base_url: &str, uri_template: &str) -> anyhow::Result<Self> {
+        let uri_prefix = uri_template.strip_suffix(PATH_PARAM).ok_or_else(|| {
+            anyhow::anyhow!("URI template must end in {PATH_PARAM}: {uri_template}")
+        })?;
+        let rest = base_url
+            .strip_prefix("http://")
+            .ok_or_else(|| anyhow::anyhow!("Only http:// upstreams are supported: {base_url}"))?;
+        let (authority, base_path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            anyhow::bail!("Upstream URL has no host: {base_url}");
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            base_path: base_path.trim_end_matches('/').to_string(),
+            uri_template: uri_template.to_string(),
+            uri_prefix: uri_prefix.to_string(),
+            allowed_paths: Vec::new(),
+            headers: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Serve only paths under `prefix`, e.g. `users` for `users/7`; with no
+    /// prefixes allowed, every path is served
+    pub fn allow_path(mut self, prefix: impl Into<String>) -> Self {
+        self.allowed_paths
+            .push(prefix.into().trim_matches('/').to_string());
+        self
+    }
+
+    /// Send `name: value` with every upstream request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether `uri` falls under this proxy's template
+    pub fn matches(&self, uri: &str) -> bool {
+        uri.starts_with(&self.uri_prefix)
+    }
+
+    /// The resource template, as listed by `resources/list`
+    pub fn resource(&self) -> Resource {
+        Resource {
+            uri: self.uri_template.clone(),
+            name: "api".to_string(),
+            description: Some(format!(
+                "Proxied from http://{}:{}{}",
+                self.host, self.port, self.base_path
+            )),
+            mime_type: None,
+            annotations: None,
+            raw: None,
+        }
+    }
+
+    /// Fetch the upstream path a matching `uri` points to
+    pub async fn read(&self, uri: &str) -> Result<ReadResourceResult, HttpProxyError> {
+        let relative = uri.strip_prefix(&self.uri_prefix).unwrap_or(uri);
+        let target = self.request_target(relative)?;
+
+        let response = tokio::time::timeout(self.timeout, self.get(&target, relative))
+            .await
+            .map_err(|_| HttpProxyError::Timeout {
+                path: relative.to_string(),
+                timeout: self.timeout,
+            })??;
+        match response.status {
+            200..=299 => {}
+            404 => return Err(HttpProxyError::NotFound(relative.to_string())),
+            status => {
+                return Err(HttpProxyError::Status {
+                    path: relative.to_string(),
+                    status,
+                })
+            }
+        }
+
+        let mime_type = response
+            .content_type
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let (text, blob) = if file_resources::is_text(essence(&mime_type)) {
+            match String::from_utf8(response.body) {
+                Ok(text) => (Some(text), None),
+                Err(e) => (None, Some(encode(e.as_bytes()))),
+            }
+        } else {
+            (None, Some(encode(&response.body)))
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(mime_type),
+                text,
+                blob,
+            }],
+        })
+    }
+
+    /// The path and query to request for `relative`, percent-encoded
+    ///
+    /// The path is decoded and normalized first, then checked: `..` segments,
+    /// which upstreams may resolve to somewhere else, and paths outside the
+    /// allowed prefixes are rejected.
+    fn request_target(&self, relative: &str) -> Result<String, HttpProxyError> {
+        if relative
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control())
+        {
+            return Err(HttpProxyError::InvalidPath(relative.to_string()));
+        }
+        // Fragments are never sent to the upstream
+        let relative_without_fragment = relative.split('#').next().unwrap_or_default();
+        let (path, query) = match relative_without_fragment.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (relative_without_fragment, None),
+        };
+
+        let decoded = file_resources::percent_decode(path);
+        if decoded.chars().any(char::is_control) {
+            return Err(HttpProxyError::InvalidPath(relative.to_string()));
+        }
+        let not_allowed = || HttpProxyError::NotAllowed(relative.to_string());
+        let segments: Vec<&str> = decoded
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect();
+        if segments.contains(&"..") {
+            return Err(not_allowed());
+        }
+        if !self.is_allowed(&segments) {
+            return Err(not_allowed());
+        }
+
+        let mut target = segments
+            .iter()
+            .map(|segment| percent_encode(segment, PATH_SAFE))
+            .collect::<Vec<_>>()
+            .join("/");
+        if decoded.ends_with('/') && !segments.is_empty() {
+            target.push('/');
+        }
+        if let Some(query) = query {
+            target.push('?');
+            // Escapes already in the query are the client's, and stay
+            target.push_str(&percent_encode(query, QUERY_SAFE));
+        }
+        Ok(target)
+    }
+
+    /// Whether the normalized path `segments` is under an allowed prefix
+    fn is_allowed(&self, segments: &[&str]) -> bool {
+        self.allowed_paths.is_empty()
+            || self.allowed_paths.iter().any(|prefix| {
+                let prefix: Vec<&str> = prefix.split('/').filter(|s| !s.is_empty()).collect();
+                segments.starts_with(&prefix)
+            })
+    }
+
+    /// GET `target`, the encoded form of `relative`
+    async fn get(&self, target: &str, relative: &str) -> Result<UpstreamResponse, HttpProxyError> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(HttpProxyError::Io)?;
+        let mut request = format!(
+            "GET {}/{target} HTTP/1.0\r\nHost: {}:{}\r\nAccept: */*\r\n",
+            self.base_path, self.host, self.port
+        );
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(HttpProxyError::Io)?;
+
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 8192];
+        loop {
+            let read = stream.read(&mut chunk).await.map_err(HttpProxyError::Io)?;
+            if read == 0 {
+                break;
+            }
+            response.extend_from_slice(&chunk[..read]);
+            // Room for the head on top of the largest body
+            if response.len() > MAX_BODY_SIZE + 64 * 1024 {
+                return Err(HttpProxyError::TooLarge(relative.to_string()));
+            }
+        }
+        let parsed = UpstreamResponse::parse(response)?;
+        if parsed.body.len() > MAX_BODY_SIZE {
+            return Err(HttpProxyError::TooLarge(relative.to_string()));
+        }
+        Ok(parsed)
+    }
+}
+
+struct UpstreamResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+impl UpstreamResponse {
+    fn parse(mut response: Vec<u8>) -> Result<Self, HttpProxyError> {
+        let head_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(|| HttpProxyError::InvalidResponse("no end of headers".to_string()))?;
+        let head = String::from_utf8_lossy(&response[..head_end]).into_owned();
+        let mut lines = head.split("\r\n");
+
+        // "HTTP/1.1 200 OK"
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| HttpProxyError::InvalidResponse("bad status line".to_string()))?;
+        let content_type = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.trim().to_string());
+
+        let body = response.split_off(head_end + 4);
+        Ok(Self {
+            status,
+            content_type,
+            body,
+        })
+    }
+}
+
+/// The mime type without parameters such as `charset`
+fn essence(mime_type: &str) -> &str {
+    mime_type.split(';').next().unwrap_or_default().trim()
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Characters other than letters and digits left as they are in a path
+/// segment
+const PATH_SAFE: &[u8] = b"-._~!$&'()*+,;=:@";
+
+/// Characters other than letters and digits left as they are in a query
+const QUERY_SAFE: &[u8] = b"-._~!$&'()*+,;=:@/?%";
+
+/// Escape every byte of `input` but ASCII letters, digits and `safe` as `%XX`
+fn percent_encode(input: &str, safe: &[u8]) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for &byte in input.as_bytes() {
+        if byte.is_ascii_alphanumeric() || safe.contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockResponse, MockUpstream};
+
+    fn proxy(base_url: &str) -> HttpProxyResource {
+        HttpProxyResource::new(base_url, DEFAULT_URI_TEMPLATE).expect("valid proxy")
+    }
+
+    fn offline() -> HttpProxyResource {
+        proxy("http://127.0.0.1:9/v1")
+    }
+
+    #[test]
+    fn encodes_segments_of_the_request_target() {
+        let proxy = offline();
+        assert_eq!(proxy.request_target("users/7").unwrap(), "users/7");
+        assert_eq!(proxy.request_target("users/a%20b").unwrap(), "users/a%20b");
+        assert_eq!(proxy.request_target("./users//7/").unwrap(), "users/7/");
+        assert_eq!(
+            proxy.request_target("search?q=a%20b&page=2#top").unwrap(),
+            "search?q=a%20b&page=2"
+        );
+        assert_eq!(proxy.request_target("caf\u{e9}").unwrap(), "caf%C3%A9");
+    }
+
+    #[test]
+    fn rejects_whitespace_and_control_characters() {
+        let proxy = offline();
+        for path in [
+            "users/7\r\nHost: evil",
+            "users/7 HTTP/1.1",
+            "users/7\tx",
+            "users/7%0d%0aX-Injected: 1",
+            "users/7%0d%0a",
+            "users/%00",
+        ] {
+            assert!(
+                matches!(
+                    proxy.request_target(path),
+                    Err(HttpProxyError::InvalidPath(_))
+                ),
+                "{path:?} should be refused"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_parent_segments_raw_or_encoded() {
+        let proxy = offline();
+        for path in [
+            "../secret",
+            "%2e%2e/secret",
+            "%2E%2E%2Fsecret",
+            "users/%2e%2e/%2e%2e/secret",
+            "users%2f..%2fsecret",
+        ] {
+            assert!(
+                matches!(
+                    proxy.request_target(path),
+                    Err(HttpProxyError::NotAllowed(_))
+                ),
+                "{path:?} should be refused"
+            );
+        }
+    }
+
+    #[test]
+    fn checks_allowed_prefixes_after_decoding() {
+        let proxy = offline().allow_path("users");
+        assert_eq!(proxy.request_target("users").unwrap(), "users");
+        assert_eq!(proxy.request_target("%75sers/7").unwrap(), "users/7");
+        for path in ["admin", "usersx/7", "users%2f..%2fadmin", "users/../admin"] {
+            assert!(
+                matches!(
+                    proxy.request_target(path),
+                    Err(HttpProxyError::NotAllowed(_))
+                ),
+                "{path:?} should be refused"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_the_upstream_body() {
+        let upstream = MockUpstream::start().await.unwrap();
+        upstream.enqueue(MockResponse::json(serde_json::json!({ "id": 7 })));
+        let proxy = proxy(&format!("{}/v1", upstream.url())).with_header("X-Api-Key", "secret");
+
+        let result = proxy.read("template://api/users/a%20b").await.unwrap();
+        let contents = &result.contents[0];
+        assert_eq!(contents.mime_type.as_deref(), Some("application/json"));
+        assert_eq!(contents.text.as_deref(), Some(r#"{"id":7}"#));
+
+        let request = &upstream.requests()[0];
+        assert_eq!(request.path, "/v1/users/a%20b");
+        assert_eq!(request.header("x-api-key"), Some("secret"));
+    }
+
+    #[tokio::test]
+    async fn upstream_404_is_not_found() {
+        let upstream = MockUpstream::start().await.unwrap();
+        upstream.enqueue(MockResponse::status(404));
+        let proxy = proxy(&upstream.url());
+
+        let error = proxy.read("template://api/users/404").await.unwrap_err();
+        assert!(matches!(&error, HttpProxyError::NotFound(path) if path == "users/404"));
+        assert!(matches!(
+            CommonMcpError::from(error),
+            CommonMcpError::InvalidParams(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn upstream_5xx_is_internal() {
+        let upstream = MockUpstream::start().await.unwrap();
+        upstream.enqueue(MockResponse::status(503));
+        let proxy = proxy(&upstream.url());
+
+        let error = proxy.read("template://api/users/7").await.unwrap_err();
+        assert!(matches!(error, HttpProxyError::Status { status: 503, .. }));
+        assert!(matches!(
+            CommonMcpError::from(error),
+            CommonMcpError::Internal(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn refused_paths_never_reach_the_upstream() {
+        let upstream = MockUpstream::start().await.unwrap();
+        let proxy = proxy(&upstream.url());
+
+        assert!(proxy.read("template://api/%2e%2e/admin").await.is_err());
+        assert!(proxy
+            .read("template://api/x%0d%0aHost:%20evil")
+            .await
+            .is_err());
+        assert_eq!(upstream.request_count(), 0);
+    }
+}
@@ -0,0 +1,92 @@
+//! Injectable id source
+//!
+//! Code that creates random ids takes an [`IdGenerator`], like it takes a
+//! [`Clock`](crate::clock::Clock) for time, so tests can swap in
+//! [`SeededIds`] or [`SequentialIds`] and get the same ids on every run.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of new ids
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> u64;
+}
+
+/// Random ids from the thread-local RNG
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> u64 {
+        rand::random::<u64>()
+    }
+}
+
+/// Random-looking ids that are the same for the same seed
+///
+/// Uses SplitMix64, so the sequence doesn't change with `rand` upgrades.
+#[derive(Debug)]
+pub struct SeededIds {
+    state: AtomicU64,
+}
+
+impl SeededIds {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+}
+
+impl IdGenerator for SeededIds {
+    fn next_id(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Ids counting up from a starting value
+#[derive(Debug)]
+pub struct SequentialIds {
+    next: AtomicU64,
+}
+
+impl SequentialIds {
+    pub fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_ids_repeat_for_the_same_seed() {
+        let ids = SeededIds::new(0);
+        // The first SplitMix64 output for seed 0
+        assert_eq!(ids.next_id(), 0xe220_a839_7b1d_cdaf);
+        let again = SeededIds::new(0);
+        again.next_id();
+        assert_eq!(again.next_id(), ids.next_id());
+        assert_ne!(SeededIds::new(1).next_id(), SeededIds::new(0).next_id());
+    }
+
+    #[test]
+    fn sequential_ids_count_up() {
+        let ids = SequentialIds::starting_at(41);
+        assert_eq!([ids.next_id(), ids.next_id()], [41, 42]);
+    }
+}
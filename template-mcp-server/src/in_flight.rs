@@ -0,0 +1,105 @@
+//! Rejecting duplicate in-flight request ids
+//!
+//! A client that reuses the id of a request still being handled can't tell
+//! the two responses apart. [`InFlightIds`] tracks the ids being handled, and
+//! a dispatcher rejects a request whose id is taken with an Invalid Request
+//! error instead of handling it. The id is free again once the first request
+//! has its response. Ids are compared as JSON, so `1` and `"1"` differ, and
+//! notifications, which have no id, are never rejected.
+//!
+//! [`RawMessageHandler`](crate::raw_messages::RawMessageHandler) checks every
+//! request. The STDIO transport handles one message at a time, so no id is
+//! ever in flight twice there, and the 0.11 HTTP transport dispatches inside
+//! the framework, where the template can't add the check.
+
+use pulseengine_mcp_protocol::Error;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Ids of the requests being handled
+#[derive(Debug, Clone, Default)]
+pub struct InFlightIds {
+    ids: Arc<Mutex<HashSet<String>>>,
+}
+
+impl InFlightIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.ids
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Mark `id` in flight until the guard is dropped
+    ///
+    /// Fails if it already is. A null id is a notification's and is never
+    /// marked.
+    pub fn begin(&self, id: &Value) -> Result<InFlightGuard, Error> {
+        if id.is_null() {
+            return Ok(InFlightGuard { entry: None });
+        }
+        let key = id.to_string();
+        if !self.lock().insert(key.clone()) {
+            return Err(Error::invalid_request(format!(
+                "Request id {key} is already in flight"
+            )));
+        }
+        Ok(InFlightGuard {
+            entry: Some((self.clone(), key)),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Frees its request id when dropped
+#[derive(Debug)]
+pub struct InFlightGuard {
+    entry: Option<(InFlightIds, String)>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Some((ids, key)) = self.entry.take() {
+            ids.lock().remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rejects_an_id_until_its_request_is_done() {
+        let ids = InFlightIds::new();
+        let first = ids.begin(&json!(1)).unwrap();
+        let error = ids.begin(&json!(1)).unwrap_err();
+        assert_eq!(error.message, "Request id 1 is already in flight");
+
+        drop(first);
+        assert!(ids.is_empty());
+        assert!(ids.begin(&json!(1)).is_ok());
+    }
+
+    #[test]
+    fn compares_ids_as_json_and_ignores_notifications() {
+        let ids = InFlightIds::new();
+        let _number = ids.begin(&json!(1)).unwrap();
+        let _string = ids.begin(&json!("1")).unwrap();
+        let _a = ids.begin(&Value::Null).unwrap();
+        let _b = ids.begin(&Value::Null).unwrap();
+        assert_eq!(ids.len(), 2);
+    }
+}
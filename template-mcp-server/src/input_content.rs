@@ -0,0 +1,241 @@
+//! Tool inputs made of content items, text and binary
+//!
+//! Tool arguments are JSON, so binary data has to travel as text. A parameter
+//! declared as [`InputContents`] takes a list of MCP-style content items
+//! instead, mixing text parts with base64 blobs:
+//!
+//! ```json
+//! [
+//!   { "type": "text", "text": "Summarize the attachment" },
+//!   { "type": "blob", "mimeType": "application/pdf", "data": "JVBERi0xLjcK..." }
+//! ]
+//! ```
+//!
+//! The items are decoded before the tool runs, so it gets [`InputContent`]
+//! values holding the text and the bytes, and a blob that isn't valid base64
+//! fails the call as invalid arguments, like any other argument of the wrong
+//! type. The schema lists both kinds of item, tagged by `type`, with blob data
+//! marked as base64.
+//!
+//! The decoded parts of one call may hold at most
+//! `LimitsConfig::max_content_input_bytes` together; tools check this with
+//! [`check_size`] before using them. `process_document` is the example.
+
+use crate::structured::ToText;
+use base64::Engine;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+
+/// One content item of a tool input
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[schemars(inline)]
+pub enum InputContent {
+    Text {
+        text: String,
+    },
+    Blob {
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        data: Base64Data,
+    },
+}
+
+/// The content items of a tool input
+///
+/// Declared as an alias for the same reason as
+/// [`CreateDataInputs`](crate::example_data::CreateDataInputs).
+pub type InputContents = Vec<InputContent>;
+
+impl InputContent {
+    /// Size of the decoded item, in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Text { text } => text.len(),
+            Self::Blob { data, .. } => data.0.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Bytes sent as a base64 string
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Base64Data(pub Vec<u8>);
+
+impl<'de> Deserialize<'de> for Base64Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map(Self)
+            .map_err(|e| serde::de::Error::custom(format!("invalid base64 data: {e}")))
+    }
+}
+
+impl JsonSchema for Base64Data {
+    fn schema_name() -> Cow<'static, str> {
+        "Base64Data".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "type": "string", "contentEncoding": "base64" })
+    }
+}
+
+/// Fail if `parts` hold more than `limit` bytes together
+pub fn check_size(parts: &[InputContent], limit: usize) -> anyhow::Result<()> {
+    let total: usize = parts.iter().map(InputContent::len).sum();
+    if total > limit {
+        anyhow::bail!("Content parts hold {total} bytes, more than the limit of {limit}");
+    }
+    Ok(())
+}
+
+/// What `process_document` found in its parts
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessedDocument {
+    /// The text parts, in order
+    pub instructions: Vec<String>,
+    /// The blob parts, in order
+    pub attachments: Vec<Attachment>,
+    /// Decoded size of all parts, in bytes
+    pub total_bytes: usize,
+}
+
+/// A blob part of a document
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Attachment {
+    pub mime_type: String,
+    pub bytes: usize,
+    /// The first bytes of the blob as text, invalid UTF-8 replaced
+    pub preview: String,
+}
+
+impl ProcessedDocument {
+    /// Sort `parts` into instructions and attachments, previewing the first
+    /// `preview_bytes` of each attachment
+    pub fn new(parts: InputContents, preview_bytes: usize) -> Self {
+        let total_bytes = parts.iter().map(InputContent::len).sum();
+        let mut instructions = Vec::new();
+        let mut attachments = Vec::new();
+        for part in parts {
+            match part {
+                InputContent::Text { text } => instructions.push(text),
+                InputContent::Blob { mime_type, data } => attachments.push(Attachment {
+                    preview: String::from_utf8_lossy(&data.0[..data.0.len().min(preview_bytes)])
+                        .into_owned(),
+                    bytes: data.0.len(),
+                    mime_type,
+                }),
+            }
+        }
+        Self {
+            instructions,
+            attachments,
+            total_bytes,
+        }
+    }
+}
+
+impl ToText for ProcessedDocument {
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "{} text part(s) and {} attachment(s), {} bytes in all",
+            self.instructions.len(),
+            self.attachments.len(),
+            self.total_bytes
+        );
+        for attachment in &self.attachments {
+            text.push_str(&format!(
+                "\n- {}, {} bytes: {}",
+                attachment.mime_type, attachment.bytes, attachment.preview
+            ));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parts() -> InputContents {
+        serde_json::from_value(json!([
+            { "type": "text", "text": "Summarize" },
+            { "type": "blob", "mimeType": "text/plain", "data": "aGVsbG8gd29ybGQ=" }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn decodes_text_and_blob_items() {
+        assert_eq!(
+            parts(),
+            [
+                InputContent::Text {
+                    text: "Summarize".to_string()
+                },
+                InputContent::Blob {
+                    mime_type: "text/plain".to_string(),
+                    data: Base64Data(b"hello world".to_vec()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        let error = serde_json::from_value::<InputContent>(
+            json!({ "type": "blob", "mimeType": "text/plain", "data": "not base64!" }),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().starts_with("invalid base64 data"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn limits_the_decoded_size() {
+        assert!(check_size(&parts(), 20).is_ok());
+        assert_eq!(
+            check_size(&parts(), 19).unwrap_err().to_string(),
+            "Content parts hold 20 bytes, more than the limit of 19"
+        );
+    }
+
+    #[test]
+    fn sorts_parts_into_instructions_and_attachments() {
+        let document = ProcessedDocument::new(parts(), 5);
+        assert_eq!(document.instructions, ["Summarize"]);
+        assert_eq!(document.total_bytes, 20);
+        assert_eq!(
+            document.to_text(),
+            "1 text part(s) and 1 attachment(s), 20 bytes in all\n- text/plain, 11 bytes: hello"
+        );
+    }
+
+    #[test]
+    fn marks_blob_data_as_base64() {
+        let schema = serde_json::to_value(schemars::schema_for!(InputContent)).unwrap();
+        let blob = schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|variant| variant["properties"]["type"]["const"] == "blob")
+            .unwrap();
+        assert_eq!(
+            blob["properties"]["data"],
+            json!({ "type": "string", "contentEncoding": "base64" })
+        );
+    }
+}
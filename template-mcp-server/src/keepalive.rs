@@ -0,0 +1,131 @@
+//! Keepalive pings and round-trip latency
+//!
+//! MCP lets either side send `ping`, answered with an empty result. Over
+//! STDIO, with `TransportConfig::ping_interval_seconds` set, the server pings the
+//! client at that interval and times the answers; [`KeepaliveStats`], in
+//! `ServerStatus`, has the counts and the latest round trip. A client that
+//! leaves a ping unanswered for `TransportConfig::ping_timeout_seconds` is taken
+//! to be gone, and the connection is closed at the next interval.
+//!
+//! Pings from the client are answered as soon as they are read, ahead of the
+//! requests waiting to be handled, so a busy server still shows it is
+//! alive. The 0.11 HTTP transport answers requests inside the framework
+//! and has no way to send the client a request, so it doesn't ping.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Ids of the server's pings start with this
+pub const PING_ID_PREFIX: &str = "keepalive-";
+
+/// Ping counts and latency, as reported in `ServerStatus`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeepaliveStats {
+    pub pings_sent: u64,
+    pub pongs_received: u64,
+    /// Pings left unanswered past the timeout
+    pub missed: u64,
+    /// Round trip of the latest answered ping
+    pub last_round_trip_ms: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: u64,
+    // Sent time of each unanswered ping, by id
+    pending: HashMap<String, Instant>,
+    stats: KeepaliveStats,
+}
+
+/// Pings sent to the client and those it has answered
+#[derive(Debug, Default)]
+pub struct Keepalive {
+    state: Mutex<State>,
+}
+
+impl Keepalive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A new ping request to send, timed from now
+    pub fn ping(&self) -> Value {
+        let mut state = self.lock();
+        state.next_id += 1;
+        let id = format!("{PING_ID_PREFIX}{}", state.next_id);
+        state.pending.insert(id.clone(), Instant::now());
+        state.stats.pings_sent += 1;
+        json!({ "jsonrpc": "2.0", "id": id, "method": "ping" })
+    }
+
+    /// Record the client's answer to the ping with `id`
+    ///
+    /// Returns whether `id` is a ping awaiting its answer.
+    pub fn pong(&self, id: &Value) -> bool {
+        let mut state = self.lock();
+        let Some(sent) = id.as_str().and_then(|id| state.pending.remove(id)) else {
+            return false;
+        };
+        state.stats.pongs_received += 1;
+        state.stats.last_round_trip_ms = Some(sent.elapsed().as_millis() as u64);
+        true
+    }
+
+    /// Whether a ping has gone unanswered for `timeout`, counting it missed
+    pub fn missed(&self, timeout: Duration) -> bool {
+        let mut state = self.lock();
+        let before = state.pending.len();
+        state.pending.retain(|_, sent| sent.elapsed() < timeout);
+        let missed = (before - state.pending.len()) as u64;
+        state.stats.missed += missed;
+        missed > 0
+    }
+
+    pub fn stats(&self) -> KeepaliveStats {
+        self.lock().stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn times_answered_pings() {
+        let keepalive = Keepalive::new();
+        let ping = keepalive.ping();
+        assert_eq!(ping["method"], "ping");
+        assert_eq!(ping["id"], json!("keepalive-1"));
+
+        assert!(keepalive.pong(&ping["id"]));
+        // Answered already, and ids the server didn't send
+        assert!(!keepalive.pong(&ping["id"]));
+        assert!(!keepalive.pong(&json!(1)));
+
+        let stats = keepalive.stats();
+        assert_eq!((stats.pings_sent, stats.pongs_received), (1, 1));
+        assert!(stats.last_round_trip_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn counts_pings_unanswered_past_the_timeout() {
+        let keepalive = Keepalive::new();
+        keepalive.ping();
+        assert!(!keepalive.missed(Duration::from_secs(60)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(keepalive.missed(Duration::from_millis(10)));
+        assert_eq!(keepalive.stats().missed, 1);
+        // Only counted once
+        assert!(!keepalive.missed(Duration::from_millis(10)));
+    }
+}
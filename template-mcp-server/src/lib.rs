@@ -8,12 +8,159 @@
 //! - Resource implementations for read-only data access
 //! - URI templates for parameterized resources
 //! - Proper error handling and async support
+//!
+//! The other server features it demonstrates, mostly one module each, are
+//! listed under "Features" in the README.
+
+pub mod admin;
+pub mod backend;
+pub mod banner;
+pub mod batches;
+pub mod binary_resources;
+pub mod blocking;
+pub mod build_info;
+pub mod bulk;
+pub mod byte_ranges;
+pub mod call_order;
+pub mod catalog;
+pub mod circuit_breaker;
+pub mod client_capabilities;
+pub mod client_notifications;
+pub mod clock;
+pub mod config_defaults;
+pub mod config_loading;
+pub mod confirmation;
+pub mod connections;
+pub mod correlation;
+pub mod costs;
+pub mod data_store;
+pub mod decimal;
+pub mod default_empty;
+pub mod effects;
+pub mod etag;
+pub mod example_data;
+pub mod file_resources;
+pub mod finite;
+pub mod health;
+pub mod html;
+pub mod http_proxy;
+pub mod ids;
+pub mod in_flight;
+pub mod input_content;
+pub mod keepalive;
+pub mod lifecycle;
+pub mod logs;
+pub mod ndjson;
+pub mod operations;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod pagination;
+pub mod panics;
+pub mod param_examples;
+pub mod param_groups;
+pub mod playground;
+pub mod plugins;
+pub mod post_processing;
+pub mod prompts;
+pub mod protocol_versions;
+pub mod providers;
+pub mod quota;
+pub mod raw_messages;
+pub mod recording;
+pub mod registry;
+pub mod repl;
+pub mod request_context;
+pub mod resource_events;
+pub mod resource_list;
+pub mod resource_queries;
+pub mod resource_wait;
+pub mod response_size;
+pub mod result_resources;
+pub mod roots;
+pub mod router_tool;
+pub mod safe_mode;
+pub mod scheduler;
+pub mod schema_catalog;
+pub mod schema_compat;
+pub mod schema_defs;
+pub mod schema_resources;
+pub mod shared_args;
+pub mod snapshot;
+pub mod strict_arguments;
+pub mod structured;
+pub mod sunset;
+pub mod surface;
+pub mod table;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timeouts;
+pub mod tool_errors;
+pub mod tool_filter;
+pub mod tool_settings;
+pub mod tool_stats;
+pub mod tool_versions;
+pub mod ts_types;
+pub mod unit_result;
+pub mod units;
+pub mod uploads;
+pub mod upstream;
+pub mod validation;
+pub mod warnings;
+pub mod whoami;
+
+pub use backend::TemplateBackend;
 
-use pulseengine_mcp_macros::{mcp_server, mcp_tools};
+use batches::BatchPolicy;
+use build_info::BuildInfo;
+use bulk::BulkResult;
+use catalog::{Catalog, CatalogEntry, CatalogItem};
+use circuit_breaker::{BreakerSettings, CircuitBreaker, CircuitBreakerStatus, CircuitBreakers};
+use clock::{Clock, ManualClock, SystemClock};
+use config_loading::ConfigError;
+use confirmation::ConfirmationGate;
+use connections::{ConnectionLimiter, ConnectionStats};
+use data_store::DataStore;
+use decimal::Decimal;
+use effects::ToolEffect;
+pub use example_data::DataFilter;
+use example_data::{CreateDataInputs, ExampleDataView};
+use finite::{JsonFloat, NonFinitePolicy};
+use health::{HealthChecks, HealthReport};
+use html::Html;
+use ids::{IdGenerator, RandomIds};
+use input_content::{InputContents, ProcessedDocument};
+use keepalive::{Keepalive, KeepaliveStats};
+use ndjson::NdjsonStream;
+use operations::{OperationHandle, OperationManager};
+use providers::Providers;
+use pulseengine_mcp_macros::{mcp_resource, mcp_server, mcp_tools};
+use pulseengine_mcp_protocol::Content;
+use quota::{QuotaPolicy, QuotaTracker, QuotaUsage};
+use request_context::RequestContext;
+use resource_wait::ResourceChanges;
+use response_size::OversizePolicy;
+use result_resources::{ContentWithResource, ResourceDef, ResultResources};
+use roots::{ClientRoots, Root};
+use scheduler::Scheduler;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use snapshot::{ImportMode, ImportSummary, Snapshot};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use structured::{Json, ToText};
+use table::{Column, ColumnType, Table, TableRow};
+use tool_errors::{ErrorCode, ToolError};
+use tool_stats::{ToolStats, ToolStatsEntry};
+use unit_result::UnitResult;
+use uploads::Uploads;
+use upstream::{UpstreamClient, UpstreamStats};
+use warnings::WithWarnings;
 
 /// Example data structure that your tools might work with
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ExampleData {
     pub id: u64,
     pub name: String,
@@ -21,6 +168,79 @@ pub struct ExampleData {
     pub tags: Vec<String>,
 }
 
+impl TableRow for ExampleData {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("id", ColumnType::Integer),
+            Column::new("name", ColumnType::String),
+            Column::new("value", ColumnType::Number),
+            Column::new("tags", ColumnType::Json),
+        ]
+    }
+
+    fn cells(&self) -> Vec<serde_json::Value> {
+        vec![
+            self.id.into(),
+            self.name.clone().into(),
+            // Non-finite values can't be represented in JSON and become null
+            self.value.into(),
+            self.tags.clone().into(),
+        ]
+    }
+}
+
+impl ToText for ExampleData {
+    fn to_text(&self) -> String {
+        let tags = if self.tags.is_empty() {
+            String::new()
+        } else {
+            format!(", tagged {}", self.tags.join(", "))
+        };
+        format!("{} (id {}): {}{tags}", self.name, self.id, self.value)
+    }
+}
+
+/// Kinds of report the server can produce, served as a catalog
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportType {
+    /// Totals of the previous day
+    Daily,
+    /// Totals of the previous seven days, by day
+    Weekly,
+    /// Totals of the previous month, by week
+    Monthly,
+}
+
+impl CatalogItem for ReportType {
+    fn details(&self) -> serde_json::Value {
+        let (period_days, breakdown) = match self {
+            Self::Daily => (1, None),
+            Self::Weekly => (7, Some("day")),
+            Self::Monthly => (30, Some("week")),
+        };
+        serde_json::json!({
+            "report_type": self,
+            "period_days": period_days,
+            "breakdown": breakdown,
+            "format": "csv",
+        })
+    }
+}
+
+/// Why `withdraw` refused, sent to the client as error details
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsufficientFunds {
+    pub balance: Decimal,
+    pub requested: Decimal,
+}
+
+impl ErrorCode for InsufficientFunds {
+    fn code(&self) -> i32 {
+        1001
+    }
+}
+
 /// Server status information (exposed as a resource)
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServerStatus {
@@ -29,15 +249,254 @@ pub struct ServerStatus {
     pub uptime_seconds: u64,
     pub tools_count: usize,
     pub resources_count: usize,
+    pub circuit_breakers: Vec<CircuitBreakerStatus>,
+    pub connections: ConnectionStats,
+    pub keepalive: KeepaliveStats,
+    pub health: HealthReport,
+    /// The shared upstream client, if provided; see [`upstream`]
+    pub upstream: Option<UpstreamStats>,
 }
 
 /// Server configuration (exposed as a resource)
+///
+/// Settings are grouped by what they govern; [`config_loading`] lists the
+/// environment variable of each.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServerConfig {
     pub max_concurrent_requests: usize,
     pub timeout_seconds: u64,
     pub debug_mode: bool,
     pub supported_formats: Vec<String>,
+    /// Snapshot file, as written by `export_snapshot`, the data store starts
+    /// with; read by [`TemplateMcpServer::try_new`]
+    pub seed_snapshot: Option<std::path::PathBuf>,
+    /// Time the server's clock stays at, e.g. for reproducible output or to
+    /// try a server past a tool's sunset; the system clock if unset. Read at
+    /// startup
+    pub fixed_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub tools: ToolsConfig,
+    pub limits: LimitsConfig,
+    pub access: AccessConfig,
+    pub transport: TransportConfig,
+    pub resources: ResourcesConfig,
+}
+
+/// How tools behave when called
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolsConfig {
+    /// Region used by tools when the client doesn't specify one
+    pub default_region: String,
+    /// Whether NaN/Infinity results are rejected or encoded as strings
+    pub non_finite_floats: NonFinitePolicy,
+    /// What clients see when a tool returns `()`
+    pub unit_result: UnitResult,
+    /// Reject undeclared arguments for every tool, not just the strict ones
+    pub strict_arguments: bool,
+    /// Pass HTML tool results through the backend's HTML sanitizer
+    pub sanitize_html: bool,
+    /// List every tool behind one `invoke` tool, for clients that load few
+    /// tools; see [`router_tool`]
+    pub router_tool: bool,
+    /// When circuit breakers open and how long they stay open, unless set
+    /// for the tool in `tool_circuit_breakers`; read at startup, see
+    /// [`circuit_breaker`]
+    pub circuit_breaker: BreakerSettings,
+    /// Breaker settings of single tools, by tool name
+    pub tool_circuit_breakers: BTreeMap<String, BreakerSettings>,
+}
+
+/// How much clients may ask of the server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LimitsConfig {
+    /// Tool calls allowed per client per day; unlimited if unset
+    pub daily_call_quota: Option<u64>,
+    /// UTC hour (0-23) at which daily quotas reset
+    pub quota_reset_hour_utc: u32,
+    /// Largest tool result in bytes of JSON, unlimited if unset
+    pub max_response_bytes: Option<usize>,
+    /// What happens to larger results of tools without their own policy
+    pub oversize_responses: OversizePolicy,
+    /// Largest HTML body a tool may return, in bytes
+    pub max_html_bytes: usize,
+    /// Largest argument uploaded in chunks, in bytes
+    pub max_upload_bytes: usize,
+    /// Seconds an upload is kept after its last chunk
+    pub upload_ttl_seconds: u64,
+    /// Largest total size of the content parts of one tool input, in bytes,
+    /// once decoded; see [`input_content`]
+    pub max_content_input_bytes: usize,
+    /// Warn when a tool blocks the executor for longer than this, in
+    /// milliseconds; not checked if unset
+    pub blocking_warn_ms: Option<u64>,
+}
+
+/// Which clients may use which tools and resources
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessConfig {
+    /// Clients allowed to call admin tools such as `export_snapshot` and to
+    /// read admin resources such as `template://server-config`
+    pub admin_clients: Vec<String>,
+    /// Leave admin resources out of `resources/list` for other clients
+    pub hide_admin_resources: bool,
+    /// Side effects tools may have; tools with others are hidden and refused
+    pub allowed_effects: Vec<ToolEffect>,
+    /// Tools this deployment serves; all of them if empty
+    pub enabled_tools: Vec<String>,
+    /// Tools this deployment never serves, even if enabled
+    pub disabled_tools: Vec<String>,
+    /// Hide and refuse every destructive tool; see [`safe_mode`]
+    pub safe_mode: bool,
+    /// Effects whose tools safe mode also turns off
+    pub safe_mode_effects: Vec<ToolEffect>,
+    /// Serve the `whoami` tool, which shows clients the identity and scopes
+    /// the server sees for them; read at startup
+    pub whoami_tool: bool,
+}
+
+/// Connections, pings and the handling of JSON-RPC messages
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransportConfig {
+    /// Connections served at once; read at startup
+    pub max_connections: usize,
+    /// Seconds without activity before a connection is closed; read at startup
+    pub connection_idle_timeout_seconds: u64,
+    /// Seconds between pings to STDIO clients, which aren't pinged if unset;
+    /// read at startup
+    pub ping_interval_seconds: Option<u64>,
+    /// Seconds a client has to answer a ping before it is disconnected
+    pub ping_timeout_seconds: u64,
+    /// Whether a batch goes on after a failed request, unless it asks for a
+    /// policy of its own
+    pub batch_policy: BatchPolicy,
+    /// Return the correlation id of each request as `_meta.request_id` of
+    /// its result; see [`correlation`]
+    pub echo_request_id: bool,
+}
+
+/// How resources are listed and read
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResourcesConfig {
+    /// Resources per `resources/list` page
+    pub page_size: usize,
+    /// Fail resource reads whose query carries parameters the resource
+    /// doesn't declare, instead of ignoring them; see [`resource_queries`]
+    pub strict_query_params: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 10,
+            timeout_seconds: 30,
+            debug_mode: false,
+            supported_formats: vec!["json".to_string(), "text".to_string()],
+            seed_snapshot: None,
+            fixed_time: None,
+            tools: ToolsConfig::default(),
+            limits: LimitsConfig::default(),
+            access: AccessConfig::default(),
+            transport: TransportConfig::default(),
+            resources: ResourcesConfig::default(),
+        }
+    }
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            default_region: "us-east-1".to_string(),
+            non_finite_floats: NonFinitePolicy::Reject,
+            unit_result: UnitResult::Ok,
+            strict_arguments: false,
+            sanitize_html: false,
+            router_tool: false,
+            circuit_breaker: BreakerSettings::default(),
+            tool_circuit_breakers: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            daily_call_quota: None,
+            quota_reset_hour_utc: 0,
+            max_response_bytes: None,
+            oversize_responses: OversizePolicy::Reject,
+            max_html_bytes: 256 * 1024,
+            max_upload_bytes: 16 * 1024 * 1024,
+            upload_ttl_seconds: 300,
+            max_content_input_bytes: 8 * 1024 * 1024,
+            blocking_warn_ms: None,
+        }
+    }
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            admin_clients: Vec::new(),
+            hide_admin_resources: false,
+            allowed_effects: ToolEffect::ALL.to_vec(),
+            enabled_tools: Vec::new(),
+            disabled_tools: Vec::new(),
+            safe_mode: false,
+            safe_mode_effects: Vec::new(),
+            whoami_tool: false,
+        }
+    }
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 100,
+            connection_idle_timeout_seconds: 300,
+            ping_interval_seconds: None,
+            ping_timeout_seconds: 10,
+            batch_policy: BatchPolicy::ContinueOnError,
+            echo_request_id: false,
+        }
+    }
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            page_size: 100,
+            strict_query_params: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The defaults, overridden by the `MCP_*` variables that are set
+    ///
+    /// Fails with every invalid value; see [`config_loading`].
+    pub fn from_env() -> Result<Self, ConfigError> {
+        config_loading::load(&Self::default(), |var| std::env::var(var).ok())
+    }
+
+    /// Check every field against its constraints
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        config_loading::validate(self)
+    }
+
+    /// The settings of the circuit breaker of `tool`
+    pub fn breaker_settings(&self, tool: &str) -> BreakerSettings {
+        self.tools
+            .tool_circuit_breakers
+            .get(tool)
+            .copied()
+            .unwrap_or(self.tools.circuit_breaker)
+    }
+
+    pub fn quota_policy(&self) -> QuotaPolicy {
+        QuotaPolicy {
+            daily_limit: self.limits.daily_call_quota,
+            reset_hour_utc: self.limits.quota_reset_hour_utc,
+        }
+    }
 }
 
 /// Template MCP Server
@@ -46,22 +505,120 @@ pub struct ServerConfig {
 /// automatically generates the necessary MCP infrastructure.
 #[mcp_server(
     name = "Template MCP Server",
-    version = "0.2.0",
     description = "A template MCP server demonstrating basic functionality",
     auth = "disabled"  // Change to "memory", "file", or remove for production
 )]
 #[derive(Clone)]
 pub struct TemplateMcpServer {
     start_time: std::time::Instant,
+    circuit_breakers: Arc<CircuitBreakers>,
+    config: Arc<RwLock<ServerConfig>>,
+    config_generation: Arc<AtomicU64>,
+    resource_changes: Arc<ResourceChanges>,
+    data_store: Arc<DataStore>,
+    quota: Arc<QuotaTracker>,
+    operations: Arc<OperationManager>,
+    uploads: Arc<Uploads>,
+    connections: Arc<ConnectionLimiter>,
+    keepalive: Arc<Keepalive>,
+    ids: Arc<dyn IdGenerator>,
+    clock: Arc<dyn Clock>,
+    roots: Arc<ClientRoots>,
+    confirmations: Arc<ConfirmationGate>,
+    scheduler: Arc<Scheduler>,
+    result_resources: Arc<ResultResources>,
+    providers: Arc<RwLock<Providers>>,
+    health: Arc<HealthChecks>,
+    tool_stats: Arc<ToolStats>,
+    catalog: Arc<Catalog>,
     // Add your server state here
-    // Example:
-    // data_store: Arc<RwLock<HashMap<u64, ExampleData>>>,
 }
 
 impl Default for TemplateMcpServer {
     fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+}
+
+impl TemplateMcpServer {
+    /// Server whose time-dependent state (such as quotas) follows `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self::with_sources(clock, Arc::new(RandomIds))
+    }
+
+    /// Server taking time from `clock` and new ids (such as `create_data`'s)
+    /// from `ids`, e.g. fakes that make tool output reproducible in tests
+    pub fn with_sources(clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self::from_parts(ServerConfig::default(), clock, ids)
+    }
+
+    /// Server starting with `config`, so the settings read at startup, such
+    /// as `max_connections` and `fixed_time`, apply too
+    pub fn with_config(config: ServerConfig) -> Self {
+        let clock: Arc<dyn Clock> = match config.fixed_time {
+            Some(time) => Arc::new(ManualClock::new(time)),
+            None => Arc::new(SystemClock),
+        };
+        Self::from_parts(config, clock, Arc::new(RandomIds))
+    }
+
+    /// Server starting with `config`, after the setup that can fail
+    ///
+    /// Checks `config` and loads `seed_snapshot` into the data store, failing
+    /// with the reason if either is bad, so a server that can't work stops
+    /// before serving instead of failing its first calls. The macros construct
+    /// servers with `Default`, which can't fail, and don't look for this
+    /// constructor; `main` calls it. Add other fallible setup, such as opening
+    /// a database, here.
+    pub async fn try_new(config: ServerConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+        let seed_snapshot = config.seed_snapshot.clone();
+        let server = Self::with_config(config);
+        if let Some(path) = seed_snapshot {
+            let text = tokio::fs::read_to_string(&path).await.map_err(|e| {
+                anyhow::anyhow!("Failed to read seed snapshot {}: {e}", path.display())
+            })?;
+            let snapshot: Snapshot = serde_json::from_str(&text)
+                .map_err(|e| anyhow::anyhow!("Invalid seed snapshot {}: {e}", path.display()))?;
+            snapshot::import(&server.data_store, snapshot, ImportMode::Replace)?;
+        }
+        Ok(server)
+    }
+
+    fn from_parts(config: ServerConfig, clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        let connections = Arc::new(ConnectionLimiter::new(
+            config.transport.max_connections,
+            Duration::from_secs(config.transport.connection_idle_timeout_seconds),
+        ));
+
+        let circuit_breakers = Arc::new(CircuitBreakers::new(&config));
+        let health = Arc::new(HealthChecks::new());
+        for breaker in circuit_breakers.iter() {
+            health.register(breaker.name(), breaker.clone());
+        }
+
         Self {
             start_time: std::time::Instant::now(),
+            circuit_breakers,
+            config: Arc::new(RwLock::new(config)),
+            config_generation: Arc::new(AtomicU64::new(0)),
+            resource_changes: Arc::new(ResourceChanges::new()),
+            data_store: Arc::new(DataStore::new()),
+            quota: Arc::new(QuotaTracker::new(clock.clone())),
+            tool_stats: Arc::new(ToolStats::new(clock.clone())),
+            catalog: Arc::new(Catalog::of::<ReportType>().expect("ReportType is a unit enum")),
+            confirmations: Arc::new(ConfirmationGate::new(clock.clone())),
+            result_resources: Arc::new(ResultResources::new(clock.clone(), ids.clone())),
+            operations: Arc::new(OperationManager::new(clock.clone()).with_ids(ids.clone())),
+            uploads: Arc::new(Uploads::new(clock.clone(), ids.clone())),
+            connections,
+            keepalive: Arc::new(Keepalive::new()),
+            ids,
+            clock,
+            roots: Arc::new(ClientRoots::new()),
+            scheduler: Arc::new(Scheduler::new()),
+            providers: Arc::new(RwLock::new(Providers::default())),
+            health,
         }
     }
 }
@@ -73,7 +630,8 @@ impl TemplateMcpServer {
     /// Get server status and basic information
     ///
     /// This is a simple tool that requires no parameters and returns
-    /// a status message about the server.
+    /// a status message about the server with its uptime; the other details
+    /// are in `template://server-status`.
     pub async fn get_status(&self) -> anyhow::Result<String> {
         let uptime = self.start_time.elapsed().as_secs();
         Ok(format!(
@@ -82,9 +640,20 @@ impl TemplateMcpServer {
         ))
     }
 
+    /// Get the server version
+    ///
+    /// Demonstrates a tool returning constant text: a `&'static str` (or a
+    /// `Cow<'static, str>`) is returned as is, without building a `String`
+    /// for every call. Only the framework's rendering of the result allocates.
+    pub async fn get_version(&self) -> anyhow::Result<&'static str> {
+        Ok(build_info::VERSION)
+    }
+
     /// Echo back a message with optional prefix
     ///
     /// Demonstrates a tool with both required and optional parameters.
+    /// Served as `echo@1`; plain `echo` is the latest version (see
+    /// `tool_versions.rs`).
     ///
     /// # Parameters
     /// - message: The message to echo back (required)
@@ -96,63 +665,581 @@ impl TemplateMcpServer {
         }
     }
 
+    /// Echo back a message with optional prefix
+    ///
+    /// Version 2 of `echo`, served as `echo@2` and `echo`: a message without a
+    /// prefix comes back unchanged instead of as `Echo: <message>`.
+    ///
+    /// # Parameters
+    /// - message: The message to echo back (required)
+    /// - prefix: Optional prefix to add to the message
+    pub async fn echo_v2(&self, message: String, prefix: Option<String>) -> anyhow::Result<String> {
+        match prefix {
+            Some(p) => Ok(format!("{}: {}", p, message)),
+            None => Ok(message),
+        }
+    }
+
     /// Add two numbers together
     ///
-    /// Demonstrates a tool that works with numeric parameters.
+    /// Demonstrates a tool that works with numeric parameters. A sum that
+    /// overflows to infinity is handled per `ToolsConfig::non_finite_floats`.
     ///
     /// # Parameters
     /// - a: First number
     /// - b: Second number
-    pub async fn add_numbers(&self, a: f64, b: f64) -> anyhow::Result<f64> {
-        Ok(a + b)
+    pub async fn add_numbers(&self, a: f64, b: f64) -> anyhow::Result<JsonFloat> {
+        finite::guard_f64(a + b, self.config().tools.non_finite_floats)
+    }
+
+    /// Compute an average speed in metres per second
+    ///
+    /// Demonstrates parameters in units: each can be a plain number in SI
+    /// units or a value with a unit, such as `{"value": 5, "unit": "ft"}`,
+    /// which is converted before the tool runs.
+    ///
+    /// # Parameters
+    /// - distance: Distance covered, in metres or with a length unit
+    /// - duration: Time taken, in seconds or with a time unit
+    pub async fn average_speed(&self, distance: f64, duration: f64) -> anyhow::Result<JsonFloat> {
+        if duration <= 0.0 {
+            return Err(anyhow::anyhow!("Duration must be positive"));
+        }
+        finite::guard_f64(distance / duration, self.config().tools.non_finite_floats)
+    }
+
+    /// Hash text repeatedly
+    ///
+    /// Demonstrates a blocking tool: CPU-bound work written as a plain `fn`,
+    /// which the backend runs on the blocking thread pool because of its
+    /// `blocking` setting in `tool_settings::TOOL_SETTINGS`.
+    ///
+    /// # Parameters
+    /// - text: Text to hash
+    /// - rounds: How many times to hash it
+    pub fn hash_rounds(&self, text: String, rounds: u32) -> anyhow::Result<String> {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let mut digest = {
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            hasher.finish()
+        };
+        for _ in 1..rounds {
+            let mut hasher = DefaultHasher::new();
+            digest.hash(&mut hasher);
+            digest = hasher.finish();
+        }
+        Ok(format!("{digest:016x}"))
+    }
+
+    /// Add two decimal amounts exactly
+    ///
+    /// Demonstrates exact decimal arithmetic for money and measurements, where
+    /// `add_numbers` would round: `"0.1"` plus `"0.2"` is exactly `"0.3"`.
+    /// Amounts are sent and returned as strings.
+    ///
+    /// # Parameters
+    /// - a: First amount, e.g. "12.50"
+    /// - b: Second amount
+    pub async fn add_decimals(&self, a: Decimal, b: Decimal) -> anyhow::Result<Decimal> {
+        Ok(a.checked_add(b)?)
+    }
+
+    /// Withdraw an amount from a balance
+    ///
+    /// Demonstrates a tool error clients can handle programmatically: a
+    /// withdrawal larger than the balance fails with code 1001 and both
+    /// amounts in the JSON-RPC error's `data`.
+    ///
+    /// # Parameters
+    /// - balance: Current balance, e.g. "20.00"
+    /// - amount: Amount to withdraw
+    pub async fn withdraw(
+        &self,
+        balance: Decimal,
+        amount: Decimal,
+    ) -> Result<Decimal, ToolError<InsufficientFunds>> {
+        match balance.checked_sub(amount) {
+            Ok(remaining) if remaining >= Decimal::ZERO => Ok(remaining),
+            _ => Err(ToolError::new(
+                "Insufficient funds",
+                InsufficientFunds {
+                    balance,
+                    requested: amount,
+                },
+            )),
+        }
     }
 
     /// Create example data
     ///
-    /// Demonstrates a tool that creates and returns structured data.
+    /// Demonstrates a tool that creates and returns structured data. The entry
+    /// is built with `ExampleData::builder()`, so a blank name fails with a
+    /// structured error (see `example_data.rs`). The rest runs behind a
+    /// circuit breaker, as a tool calling an upstream API would, and sends
+    /// its request through the shared `UpstreamClient` when one is provided.
+    /// The entry is kept in the data store for `list_data` and the
+    /// `template://example-data/{id}` resource. The result is returned as
+    /// readable text, rendered by `ToText`, and as structured content matching
+    /// the declared output schema.
     ///
     /// # Parameters
     /// - name: Name for the data entry
     /// - value: Numeric value
-    /// - tags: Optional list of tags
+    /// - tags: Tags of the entry; none if omitted
     pub async fn create_data(
         &self,
         name: String,
         value: f64,
-        tags: Option<Vec<String>>,
-    ) -> anyhow::Result<ExampleData> {
-        Ok(ExampleData {
-            id: rand::random::<u64>(),
-            name,
-            value,
-            tags: tags.unwrap_or_default(),
+        tags: Vec<String>,
+    ) -> anyhow::Result<Json<ExampleData>> {
+        let data = ExampleData::builder()
+            .name(name)
+            .value(value)
+            .tags(tags)
+            .build()
+            .map_err(example_data::invalid_data)?;
+        if let Some(upstream) = self.state::<UpstreamClient>() {
+            upstream.send("create_data").await?;
+        }
+        let data = self
+            .data_store
+            .create(self.ids.as_ref(), |id| ExampleData { id, ..data })?;
+        self.resource_changes.notify();
+        Ok(Json(data))
+    }
+
+    /// Create many example data entries in one call
+    ///
+    /// Demonstrates a bulk operation with partial success: each item is
+    /// created as by `create_data`, and the result lists the entries created
+    /// and, by their index, the items that failed, instead of failing the
+    /// whole batch on its first bad item (see `bulk.rs`).
+    ///
+    /// # Parameters
+    /// - items: Entries to create, each with a name, a value and optional tags
+    /// - tags: Tags added to every entry
+    pub async fn create_data_bulk(
+        &self,
+        items: CreateDataInputs,
+        tags: Vec<String>,
+    ) -> anyhow::Result<Json<BulkResult<ExampleData>>> {
+        // A second parameter also keeps the input schema an object; see
+        // `preview_html`
+        let tags = &tags;
+        let result = BulkResult::process(items, |item| async move {
+            let data = tags
+                .iter()
+                .fold(item.builder(), |builder, tag| builder.tag(tag.clone()))
+                .build()
+                .map_err(example_data::invalid_item)?;
+            Ok(self
+                .data_store
+                .create(self.ids.as_ref(), |id| ExampleData { id, ..data })?)
         })
+        .await;
+        if !result.succeeded.is_empty() {
+            self.resource_changes.notify();
+        }
+        Ok(Json(result))
+    }
+
+    /// Find stored example data by ID or by name
+    ///
+    /// Demonstrates mutually exclusive parameters: calls must give exactly one
+    /// of `id` and `name` (see `param_groups.rs`).
+    ///
+    /// # Parameters
+    /// - id: ID of the entry
+    /// - name: Name of the entry; the first match is returned
+    pub async fn find_data(
+        &self,
+        id: Option<u64>,
+        name: Option<String>,
+    ) -> anyhow::Result<Json<ExampleData>> {
+        let found = match (id, name) {
+            (Some(id), _) => self.data_store.get(id),
+            (None, Some(name)) => self
+                .data_store
+                .entries()
+                .into_iter()
+                .find(|data| data.name == name),
+            (None, None) => None,
+        };
+        found
+            .map(Json)
+            .ok_or_else(|| anyhow::anyhow!("No example data matches the lookup"))
+    }
+
+    /// List stored example data as a table
+    ///
+    /// Demonstrates returning tabular data that clients can render as a table.
+    ///
+    /// # Parameters
+    /// - tag: Only include entries with this tag
+    /// - limit: Maximum number of rows to return
+    pub async fn list_data(
+        &self,
+        tag: Option<String>,
+        limit: Option<usize>,
+    ) -> anyhow::Result<Table> {
+        Ok(self
+            .data_store
+            .entries()
+            .iter()
+            .filter(|data| tag.as_ref().is_none_or(|tag| data.tags.contains(tag)))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// List stored example data matching a filter as a table
+    ///
+    /// Demonstrates arguments shared with a prompt: the filter is a
+    /// `DataFilter`, which the `review_data` prompt takes too, so the tool's
+    /// schema and the prompt's arguments come from one definition (see
+    /// `shared_args.rs`).
+    pub async fn filter_data(&self, filter: DataFilter) -> anyhow::Result<Table> {
+        Ok(self
+            .data_store
+            .entries()
+            .iter()
+            .filter(|data| filter.matches(data))
+            .collect())
+    }
+
+    /// Delete stored example data by ID or by tag
+    ///
+    /// Demonstrates a destructive tool that only runs after the client confirms
+    /// it: the first call returns a confirmation token, and the deletion happens
+    /// when the tool is called again with the token in `_confirm_token`.
+    ///
+    /// # Parameters
+    /// - id: ID of the entry to delete
+    /// - tag: Delete every entry with this tag
+    pub async fn delete_data(
+        &self,
+        id: Option<u64>,
+        tag: Option<String>,
+    ) -> anyhow::Result<String> {
+        let deleted = match (id, tag) {
+            (Some(id), _) => usize::from(self.data_store.remove(id).is_some()),
+            (None, Some(tag)) => self
+                .data_store
+                .remove_matching(|data| data.tags.contains(&tag)),
+            (None, None) => return Err(anyhow::anyhow!("Provide an id or a tag to delete")),
+        };
+        if deleted > 0 {
+            self.resource_changes.notify();
+        }
+        Ok(format!("Deleted {deleted} entries"))
+    }
+
+    /// Remove all stored example data
+    ///
+    /// Demonstrates a command-style tool with nothing to return: clients get
+    /// the result configured by `ToolsConfig::unit_result`.
+    pub async fn clear_data(&self) -> anyhow::Result<()> {
+        if self.data_store.remove_matching(|_| true) > 0 {
+            self.resource_changes.notify();
+        }
+        Ok(())
+    }
+
+    /// Preview HTML in the client
+    ///
+    /// Demonstrates an HTML result: clients that render HTML show the page,
+    /// others its source. With `MCP_SANITIZE_HTML=true`, scripts and event
+    /// handlers are removed first (see `html.rs`).
+    ///
+    /// # Parameters
+    /// - html: The HTML to show
+    /// - title: Optional heading shown above it, as plain text
+    pub async fn preview_html(&self, html: String, title: Option<String>) -> anyhow::Result<Html> {
+        // Tools with a single parameter get that parameter's schema from the
+        // 0.11 macros instead of an object schema, so the title also keeps
+        // the input schema an object
+        match title {
+            Some(title) => Ok(Html(format!("<h1>{}</h1>{html}", html::escape(&title)))),
+            None => Ok(Html(html)),
+        }
+    }
+
+    /// Process a document sent as text and binary parts
+    ///
+    /// Demonstrates content items as input: `parts` takes text parts and
+    /// base64 blobs, decoded before the tool runs, and the result lists the
+    /// instructions and attachments the tool received (see
+    /// `input_content.rs`).
+    ///
+    /// # Parameters
+    /// - parts: Content of the document, text parts and base64 blobs with a MIME type
+    /// - preview_bytes: Bytes of each attachment shown as text; 32 if omitted
+    pub async fn process_document(
+        &self,
+        parts: InputContents,
+        preview_bytes: Option<usize>,
+    ) -> anyhow::Result<Json<ProcessedDocument>> {
+        // A second parameter also keeps the input schema an object; see
+        // `preview_html`
+        input_content::check_size(&parts, self.config().limits.max_content_input_bytes)?;
+        Ok(Json(ProcessedDocument::new(
+            parts,
+            preview_bytes.unwrap_or(32),
+        )))
+    }
+
+    /// Count stored entries per tag
+    ///
+    /// Demonstrates returning dynamic JSON as structured content: the tags
+    /// aren't known up front, so the result is a `serde_json::Value`.
+    pub async fn tag_counts(&self) -> anyhow::Result<Json<serde_json::Value>> {
+        let entries = self.data_store.entries();
+        let mut counts = std::collections::BTreeMap::<&str, usize>::new();
+        for tag in entries.iter().flat_map(|data| &data.tags) {
+            *counts.entry(tag).or_default() += 1;
+        }
+        Ok(Json(serde_json::json!({
+            "entries": entries.len(),
+            "tags": counts,
+        })))
+    }
+
+    /// Export every stored entry as a snapshot
+    ///
+    /// Admin tool for backups and migration; the result can be passed to
+    /// `import_snapshot` as is.
+    pub async fn export_snapshot(&self) -> anyhow::Result<Json<Snapshot>> {
+        Ok(Json(Snapshot::export(&self.data_store, self.clock.now())))
+    }
+
+    /// Export every stored entry as newline-delimited JSON
+    ///
+    /// Demonstrates an NDJSON result for streaming consumers: one entry per
+    /// line, tagged `application/x-ndjson` (see `ndjson.rs`).
+    pub async fn export_ndjson(&self) -> anyhow::Result<NdjsonStream<ExampleData>> {
+        Ok(self.data_store.entries().into_iter().collect())
+    }
+
+    /// Restore stored entries from a snapshot
+    ///
+    /// Admin tool. Runs only after an `export_snapshot` call in the session,
+    /// so there is a snapshot to go back to. Snapshots of older versions are
+    /// migrated first.
+    ///
+    /// # Parameters
+    /// - snapshot: A snapshot written by `export_snapshot`
+    /// - mode: "merge" to upsert the entries, "replace" to remove all existing
+    ///   entries first
+    pub async fn import_snapshot(
+        &self,
+        snapshot: Snapshot,
+        mode: ImportMode,
+    ) -> anyhow::Result<Json<ImportSummary>> {
+        let summary = snapshot::import(&self.data_store, snapshot, mode)?;
+        self.resource_changes.notify();
+        Ok(Json(summary))
+    }
+
+    /// Clear the call statistics of every tool
+    ///
+    /// Admin tool. `template://stats/tools` is empty until tools are called
+    /// again.
+    pub async fn reset_tool_stats(&self) -> anyhow::Result<String> {
+        let cleared = self.tool_stats.reset();
+        self.resource_changes.notify();
+        let plural = if cleared == 1 { "" } else { "s" };
+        Ok(format!("Cleared call statistics of {cleared} tool{plural}"))
+    }
+
+    /// Report on the stored example data
+    ///
+    /// Demonstrates a preview-plus-download result: a summary is returned
+    /// inline and the full report is served as a CSV resource that expires.
+    pub async fn data_report(&self) -> anyhow::Result<ContentWithResource> {
+        let entries = self.data_store.entries();
+        let total: f64 = entries.iter().map(|data| data.value).sum();
+        let summary = format!("{} entries, total value {total}", entries.len());
+
+        let mut csv = String::from("id,name,value,tags\n");
+        for data in &entries {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                data.id,
+                csv_field(&data.name),
+                data.value,
+                csv_field(&data.tags.join(";"))
+            ));
+        }
+
+        Ok(ContentWithResource::new(
+            vec![Content::text(summary)],
+            ResourceDef::text("data-report.csv", "text/csv", csv)
+                .with_description("Every stored example data entry"),
+        ))
     }
 
     /// Process a list of items
     ///
-    /// Demonstrates working with arrays/lists as parameters.
+    /// Demonstrates working with arrays/lists as parameters. Blank items are
+    /// skipped, which is reported as a warning rather than failing the call.
+    /// An empty list is processed too, with a warning sent to the client.
+    /// Its timeout grows with the number of items; see `timeouts.rs`.
     ///
     /// # Parameters
-    /// - items: List of strings to process
+    /// - items: List of strings to process; empty if omitted
     /// - operation: Operation to perform ("count", "join", "reverse")
     pub async fn process_list(
         &self,
         items: Vec<String>,
         operation: String,
-    ) -> anyhow::Result<String> {
-        match operation.as_str() {
-            "count" => Ok(format!("List contains {} items", items.len())),
-            "join" => Ok(items.join(", ")),
+    ) -> anyhow::Result<WithWarnings<String>> {
+        let total = items.len();
+        if total == 0 {
+            RequestContext::current()
+                .unwrap_or_default()
+                .warn("process_list was given an empty list");
+        }
+        let items: Vec<String> = items
+            .into_iter()
+            .filter(|item| !item.trim().is_empty())
+            .collect();
+
+        let processed = match operation.as_str() {
+            "count" => format!("List contains {} items", items.len()),
+            "join" => items.join(", "),
             "reverse" => {
-                let reversed: Vec<String> = items.into_iter().rev().collect();
-                Ok(reversed.join(", "))
+                let reversed: Vec<String> = items.iter().rev().cloned().collect();
+                reversed.join(", ")
             }
-            _ => Err(anyhow::anyhow!(
-                "Unknown operation: {}. Supported: count, join, reverse",
-                operation
-            )),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unknown operation: {}. Supported: count, join, reverse",
+                    operation
+                ))
+            }
+        };
+
+        let mut result = WithWarnings::new(processed);
+        let skipped = total - items.len();
+        if skipped > 0 {
+            result.warn(format!("Skipped {skipped} blank item(s)"));
         }
+        Ok(result)
+    }
+
+    /// Resolve the endpoint URL for a service
+    ///
+    /// Demonstrates a parameter whose default comes from server configuration:
+    /// when the client omits `region`, `ToolsConfig::default_region` is used.
+    ///
+    /// # Parameters
+    /// - service: Name of the service
+    /// - region: Region to use (defaults to the configured region)
+    pub async fn service_endpoint(
+        &self,
+        service: String,
+        region: String,
+    ) -> anyhow::Result<String> {
+        Ok(format!("https://{}.{}.example.com", service, region))
+    }
+
+    /// Start a slow task and return an operation handle immediately
+    ///
+    /// Demonstrates a long-running tool: poll the returned operation with
+    /// `get_operation` and stop it with `cancel_operation`.
+    ///
+    /// # Parameters
+    /// - steps: Number of steps to run
+    /// - step_ms: Duration of each step in milliseconds (default 1000)
+    pub async fn long_task(
+        &self,
+        steps: u32,
+        step_ms: Option<u64>,
+    ) -> anyhow::Result<OperationHandle> {
+        let step = Duration::from_millis(step_ms.unwrap_or(1000));
+        Ok(self.operations.start("long_task", async move {
+            for _ in 0..steps {
+                tokio::time::sleep(step).await;
+            }
+            Ok(serde_json::json!({ "steps_completed": steps }))
+        }))
+    }
+
+    /// Count down from a number, reporting progress each step
+    ///
+    /// Demonstrates a streaming tool: the countdown runs as an operation that
+    /// reports progress at every step, and finishes with "done". Follow it with
+    /// `get_operation` and stop it with `cancel_operation`.
+    ///
+    /// # Parameters
+    /// - from: Number to count down from
+    /// - interval_ms: Time between steps in milliseconds (default 1000)
+    pub async fn countdown(
+        &self,
+        from: u32,
+        interval_ms: Option<u64>,
+    ) -> anyhow::Result<OperationHandle> {
+        let interval = Duration::from_millis(interval_ms.unwrap_or(1000));
+        Ok(self
+            .operations
+            .start_with_progress("countdown", move |progress| async move {
+                for step in 1..=from {
+                    tokio::time::sleep(interval).await;
+                    let remaining = from - step;
+                    progress.report(step.into(), Some(from.into()), remaining.to_string());
+                }
+                Ok(serde_json::json!("done"))
+            }))
+    }
+
+    /// Fetch a path from a simulated upstream service
+    ///
+    /// Demonstrates passing the call's deadline and cancellation on to
+    /// outbound work: the simulated request runs on its own task, like one on
+    /// an HTTP client's connection pool, and stops as soon as the call times
+    /// out or is dropped.
+    ///
+    /// # Parameters
+    /// - path: Path to fetch
+    /// - delay_ms: Time the upstream takes to respond in milliseconds
+    pub async fn fetch_upstream(&self, path: String, delay_ms: u64) -> anyhow::Result<String> {
+        let token = RequestContext::current()
+            .unwrap_or_default()
+            .token()
+            .clone();
+        let target = path.clone();
+        let request = tokio::spawn(async move {
+            tokio::select! {
+                _ = token.cancelled() => {
+                    tracing::info!(path = %target, "Upstream request cancelled");
+                    None
+                }
+                _ = tokio::time::sleep(Duration::from_millis(delay_ms)) => {
+                    tracing::info!(path = %target, "Upstream request completed");
+                    Some(format!("Fetched {target}"))
+                }
+            }
+        });
+        request
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Upstream request for {path} was cancelled"))
+    }
+
+    /// List the catalog of report types
+    ///
+    /// Demonstrates a catalog: each variant of `ReportType` is also served as
+    /// the resource `template://catalog/{variant}`.
+    pub async fn list_catalog(&self) -> anyhow::Result<Json<Vec<CatalogEntry>>> {
+        Ok(Json(self.catalog.entries()))
+    }
+
+    /// List the client's filesystem roots
+    ///
+    /// Demonstrates a path-aware tool: the roots are the directories the client
+    /// allows the server to work in, refreshed when the client reports a change.
+    pub async fn list_roots(&self) -> anyhow::Result<Json<Vec<Root>>> {
+        Ok(Json(self.roots.roots()))
     }
 
     /// Example of a tool that might fail
@@ -169,28 +1256,359 @@ impl TemplateMcpServer {
         }
     }
 
-    // TODO: Resources disabled temporarily due to framework bug in v0.9.0
-    // The mcp_server macro doesn't properly delegate to McpResourcesProvider
-    // Will re-enable once framework is fixed
-    //
-    // Example resources that will be available once the framework is fixed:
-    //
-    // #[mcp_resource(uri_template = "template://server-status")]
-    // pub async fn server_status_resource(&self) -> anyhow::Result<ServerStatus>
-    //
-    // #[mcp_resource(uri_template = "template://server-config")]
-    // pub async fn server_config_resource(&self) -> anyhow::Result<ServerConfig>
-    //
-    // #[mcp_resource(uri_template = "template://example-data/{id}")]
-    // pub async fn example_data_resource(&self, id: String) -> anyhow::Result<ExampleData>
+    /// Example of a tool that panics
+    ///
+    /// Demonstrates that a panic only fails its own call, as an internal
+    /// error, even one raised while the data store is locked for writing; the
+    /// store stays usable (see `panics.rs`).
+    pub async fn example_with_panic(&self) -> anyhow::Result<String> {
+        let data = self
+            .data_store
+            .create(&*self.ids, |_| panic!("example_with_panic panicked"))?;
+        Ok(format!("Created {}", data.id))
+    }
+
+    /// Current server status, including circuit breaker state
+    // Clients can long-poll this with `resources/wait` instead of re-reading it
+    #[mcp_resource(uri_template = "template://server-status")]
+    pub async fn server_status_resource(&self) -> anyhow::Result<ServerStatus> {
+        Ok(self.status().await)
+    }
+
+    /// Version, git commit, build time and compiler of the running build
+    #[mcp_resource(uri_template = "template://build-info")]
+    pub async fn build_info_resource(&self) -> anyhow::Result<BuildInfo> {
+        Ok(BuildInfo::current())
+    }
+
+    /// Current server configuration, for admin clients only
+    #[mcp_resource(uri_template = "template://server-config")]
+    pub async fn server_config_resource(&self) -> anyhow::Result<ServerConfig> {
+        Ok(self.config())
+    }
+
+    /// Call counts, errors and latency of each tool called since the last reset
+    #[mcp_resource(uri_template = "template://stats/tools")]
+    pub async fn tool_stats_resource(&self) -> anyhow::Result<Vec<ToolStatsEntry>> {
+        Ok(self.tool_stats.entries())
+    }
+
+    /// Daily call quota usage of a client
+    #[mcp_resource(uri_template = "template://quota/{client}")]
+    pub async fn quota_resource(&self, client: String) -> anyhow::Result<QuotaUsage> {
+        Ok(self.quota.usage(&client, self.config().quota_policy()))
+    }
+
+    /// Look up stored example data by ID; add ?format=summary for its id and
+    /// name only
+    #[mcp_resource(uri_template = "template://example-data/{id}")]
+    pub async fn example_data_resource(&self, id: String) -> anyhow::Result<ExampleDataView> {
+        let id: u64 = id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid example data ID: {}", id))?;
+        let data = self
+            .data_store
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("No example data with ID {}", id))?;
+        let query = resource_queries::current();
+        Ok(ExampleDataView::new(data, query.text("format")))
+    }
 }
 
 // Add any additional implementation methods here that are NOT tools
 // (private methods, helper functions, etc.)
 impl TemplateMcpServer {
+    /// Current server status, including circuit breaker state
+    pub async fn status(&self) -> ServerStatus {
+        use pulseengine_mcp_server::{McpResourcesProvider, McpToolsProvider};
+
+        ServerStatus {
+            name: "Template MCP Server".to_string(),
+            version: build_info::VERSION.to_string(),
+            uptime_seconds: self.start_time.elapsed().as_secs(),
+            tools_count: self.get_available_tools().len(),
+            resources_count: self.get_available_resources().len(),
+            circuit_breakers: self.circuit_breakers(),
+            connections: self.connections.stats(),
+            keepalive: self.keepalive.stats(),
+            health: self.health.report().await,
+            upstream: self.state::<UpstreamClient>().map(|client| client.stats()),
+        }
+    }
+
+    /// Status of every circuit breaker
+    pub fn circuit_breakers(&self) -> Vec<CircuitBreakerStatus> {
+        self.circuit_breakers.statuses()
+    }
+
+    /// The breaker guarding the calls of `tool`, if it has one
+    pub fn circuit_breaker(&self, tool: &str) -> Option<&Arc<CircuitBreaker>> {
+        self.circuit_breakers.get(tool)
+    }
+
+    /// Snapshot of the current configuration
+    pub fn config(&self) -> ServerConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Replace the configuration, e.g. after reloading it from disk
+    pub fn reload_config(&self, config: ServerConfig) {
+        *self
+            .config
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = config;
+        self.config_generation.fetch_add(1, Ordering::SeqCst);
+        self.resource_changes.notify();
+    }
+
+    /// Number of times the configuration was replaced, which changes with it
+    pub fn config_generation(&self) -> u64 {
+        self.config_generation.load(Ordering::SeqCst)
+    }
+
+    /// Per-client call counters
+    pub fn quota(&self) -> &QuotaTracker {
+        &self.quota
+    }
+
+    /// Connection slots for transports that accept connections
+    pub fn connections(&self) -> &Arc<ConnectionLimiter> {
+        &self.connections
+    }
+
+    /// Pings sent to the client and their round trips
+    pub fn keepalive(&self) -> &Arc<Keepalive> {
+        &self.keepalive
+    }
+
+    /// Pending confirmations of destructive tool calls
+    pub fn confirmations(&self) -> &ConfirmationGate {
+        &self.confirmations
+    }
+
+    /// The time source of the server, e.g. for tool sunsets
+    pub fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    /// Example data created by `create_data`
+    pub fn data_store(&self) -> &DataStore {
+        &self.data_store
+    }
+
+    /// Long-running operations started by tools
+    pub fn operations(&self) -> &Arc<OperationManager> {
+        &self.operations
+    }
+
+    /// Arguments being uploaded in chunks
+    pub fn uploads(&self) -> &Uploads {
+        &self.uploads
+    }
+
+    /// Resources registered by tools returning `ContentWithResource`
+    pub fn result_resources(&self) -> &ResultResources {
+        &self.result_resources
+    }
+
+    /// The client's filesystem roots
+    pub fn roots(&self) -> &ClientRoots {
+        &self.roots
+    }
+
+    /// Signal waiting resource reads that server state has changed
+    pub fn resource_changes(&self) -> &ResourceChanges {
+        &self.resource_changes
+    }
+
+    /// Run `task` with this server every `interval`; see [`scheduler`]
+    ///
+    /// The task gets its own handle to the server, which shares all state with
+    /// this one. Must be called within a tokio runtime.
+    pub fn schedule<F, Fut>(&self, name: &str, interval: Duration, task: F)
+    where
+        F: Fn(TemplateMcpServer) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let server = self.clone();
+        self.scheduler
+            .every(name, interval, move || task(server.clone()));
+    }
+
+    /// Recurring tasks; shut it down to stop them when the server stops
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    /// Call statistics of each tool; see [`tool_stats`]
+    pub fn tool_stats(&self) -> &ToolStats {
+        &self.tool_stats
+    }
+
+    /// Items served as `template://catalog/` resources; see [`catalog`]
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Health checks of the server's dependencies; see [`health`]
+    pub fn health(&self) -> &HealthChecks {
+        &self.health
+    }
+
+    /// Make `providers` available to tools; see [`providers`]
+    pub fn set_providers(&self, providers: Providers) {
+        *self
+            .providers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = providers;
+    }
+
+    /// The provider named `name`, if it exists and is a `T`
+    pub fn provider<T: Send + Sync + 'static>(&self, name: &str) -> Option<Arc<T>> {
+        self.providers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+    }
+
+    /// The shared state of type `T`, if provided; see [`providers`]
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.providers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .state()
+    }
+
     // Example private helper method
     #[allow(dead_code)]
     fn internal_helper(&self) -> String {
         "This method is not exposed as an MCP tool".to_string()
     }
 }
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn status_message_reports_the_uptime() {
+        let message = TemplateMcpServer::default().get_status().await.unwrap();
+        let uptime = message
+            .strip_prefix("Template MCP Server is running and ready to serve requests. Uptime: ")
+            .and_then(|rest| rest.strip_suffix('s'))
+            .expect("message with uptime");
+        assert!(uptime.parse::<u64>().is_ok(), "{message}");
+    }
+
+    /// The default config, seeded from `path`
+    fn seeded(path: &std::path::Path) -> ServerConfig {
+        ServerConfig {
+            seed_snapshot: Some(path.to_path_buf()),
+            ..ServerConfig::default()
+        }
+    }
+
+    async fn try_new_error(config: ServerConfig) -> String {
+        match TemplateMcpServer::try_new(config).await {
+            Ok(_) => panic!("try_new accepted the config"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn try_new_starts_with_the_defaults_and_the_seed() {
+        let server = TemplateMcpServer::try_new(ServerConfig::default())
+            .await
+            .unwrap();
+        assert!(server.data_store().is_empty());
+        assert!(TemplateMcpServer::default().data_store().is_empty());
+
+        let path = std::env::temp_dir().join(format!("seed-snapshot-{}.json", std::process::id()));
+        let entry = ExampleData {
+            id: 3,
+            name: "seeded".to_string(),
+            value: 1.5,
+            tags: Vec::new(),
+        };
+        let snapshot = Snapshot {
+            version: snapshot::SNAPSHOT_VERSION,
+            exported_at: "2026-01-01T00:00:00Z".parse().unwrap(),
+            entries: vec![entry.clone()],
+        };
+        std::fs::write(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+        let server = TemplateMcpServer::try_new(seeded(&path)).await;
+        std::fs::remove_file(&path).unwrap();
+        let seeded = server
+            .unwrap()
+            .data_store()
+            .get(3)
+            .expect("the seeded entry");
+        assert_eq!((seeded.name, seeded.value), (entry.name, entry.value));
+    }
+
+    #[tokio::test]
+    async fn try_new_fails_with_an_invalid_config() {
+        let mut config = ServerConfig::default();
+        config.tools.circuit_breaker.threshold = 0;
+        assert_eq!(
+            try_new_error(config).await,
+            "Invalid server configuration\n  tools.circuit_breaker: threshold must be at least 1"
+        );
+    }
+
+    #[tokio::test]
+    async fn try_new_fails_with_a_missing_seed_snapshot() {
+        let path = std::env::temp_dir().join(format!("no-seed-{}.json", std::process::id()));
+        let error = try_new_error(seeded(&path)).await;
+        let expected = format!("Failed to read seed snapshot {}: ", path.display());
+        assert!(error.starts_with(&expected), "{error}");
+    }
+
+    #[tokio::test]
+    async fn try_new_fails_with_a_corrupt_seed_snapshot() {
+        let path = std::env::temp_dir().join(format!("corrupt-seed-{}.json", std::process::id()));
+        std::fs::write(&path, "{\"version\": 1, \"entries\": [").unwrap();
+        let error = try_new_error(seeded(&path)).await;
+        std::fs::remove_file(&path).unwrap();
+        let expected = format!("Invalid seed snapshot {}: ", path.display());
+        assert!(error.starts_with(&expected), "{error}");
+    }
+
+    #[tokio::test]
+    async fn version_tool_returns_the_version() {
+        let backend = TemplateBackend::new(TemplateMcpServer::default());
+        let result = pulseengine_mcp_server::McpBackend::call_tool(
+            &backend,
+            pulseengine_mcp_protocol::CallToolRequestParam {
+                name: "get_version".to_string(),
+                arguments: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&result.content).unwrap(),
+            serde_json::json!([{ "type": "text", "text": format!("{:?}", build_info::VERSION) }])
+        );
+        assert_eq!(result.is_error, Some(false));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
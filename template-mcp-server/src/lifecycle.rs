@@ -0,0 +1,140 @@
+//! Enforcing the MCP lifecycle
+//!
+//! A session starts with `initialize`. Before it has succeeded, a client
+//! knows neither the server's capabilities nor the protocol version, so
+//! [`Lifecycle`] rejects every other request except `ping` with a "Server not
+//! initialized" error, code `-32002` as in other JSON-RPC protocols (the 0.11
+//! `ErrorCode` names that code `ResourceNotFound`). Once initialized, a
+//! second `initialize` is rejected as an Invalid Request, since the session's
+//! capabilities and version are already settled. A failed `initialize` may be
+//! retried. Notifications are never rejected; they get no response to carry
+//! the error.
+//!
+//! [`RawMessageHandler`](crate::raw_messages::RawMessageHandler) checks every
+//! request of its session. The 0.11 HTTP transport dispatches inside the
+//! framework, where the template can't add the check.
+
+use pulseengine_mcp_protocol::{Error, ErrorCode};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Methods answered before `initialize`
+pub const ALLOWED_BEFORE_INITIALIZE: &[&str] = &["initialize", "ping"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Uninitialized,
+    Initializing,
+    Initialized,
+}
+
+/// Where a session is in its lifecycle
+#[derive(Debug, Clone, Default)]
+pub struct Lifecycle {
+    state: Arc<Mutex<State>>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether `initialize` has succeeded
+    pub fn is_initialized(&self) -> bool {
+        *self.lock() == State::Initialized
+    }
+
+    /// Check that a request for `method` with `id` may be handled now
+    ///
+    /// An `initialize` that passes is in progress until
+    /// [`finish_initialize`](Self::finish_initialize); another one meanwhile
+    /// is rejected too.
+    pub fn check(&self, method: &str, id: &Value) -> Result<(), Error> {
+        if id.is_null() {
+            return Ok(());
+        }
+        let mut state = self.lock();
+        match (*state, method) {
+            (State::Uninitialized, "initialize") => {
+                *state = State::Initializing;
+                Ok(())
+            }
+            (_, "initialize") => Err(Error::invalid_request(
+                "Server already initialized; initialize may only be sent once",
+            )),
+            (State::Initialized, _) => Ok(()),
+            (_, method) if ALLOWED_BEFORE_INITIALIZE.contains(&method) => Ok(()),
+            (_, method) => Err(Error::new(
+                ErrorCode::ResourceNotFound,
+                format!("Server not initialized; send initialize before {method}"),
+            )),
+        }
+    }
+
+    /// Record the outcome of the `initialize` that [`check`](Self::check)
+    /// let through
+    pub fn finish_initialize(&self, succeeded: bool) {
+        let mut state = self.lock();
+        if *state == State::Initializing {
+            *state = if succeeded {
+                State::Initialized
+            } else {
+                State::Uninitialized
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn only_ping_and_initialize_come_before_initialize() {
+        let lifecycle = Lifecycle::new();
+        assert!(lifecycle.check("ping", &json!(1)).is_ok());
+        let error = lifecycle.check("tools/list", &json!(2)).unwrap_err();
+        assert_eq!(error.code, ErrorCode::ResourceNotFound);
+        assert_eq!(
+            error.message,
+            "Server not initialized; send initialize before tools/list"
+        );
+        // Notifications are never rejected
+        assert!(lifecycle
+            .check("notifications/initialized", &Value::Null)
+            .is_ok());
+
+        assert!(lifecycle.check("initialize", &json!(3)).is_ok());
+        lifecycle.finish_initialize(true);
+        assert!(lifecycle.is_initialized());
+        assert!(lifecycle.check("tools/list", &json!(4)).is_ok());
+    }
+
+    #[test]
+    fn initialize_is_accepted_once() {
+        let lifecycle = Lifecycle::new();
+        assert!(lifecycle.check("initialize", &json!(1)).is_ok());
+        // Still in progress
+        assert!(lifecycle.check("initialize", &json!(2)).is_err());
+        lifecycle.finish_initialize(true);
+        let error = lifecycle.check("initialize", &json!(3)).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidRequest);
+    }
+
+    #[test]
+    fn a_failed_initialize_may_be_retried() {
+        let lifecycle = Lifecycle::new();
+        assert!(lifecycle.check("initialize", &json!(1)).is_ok());
+        lifecycle.finish_initialize(false);
+        assert!(!lifecycle.is_initialized());
+        assert!(lifecycle.check("initialize", &json!(2)).is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+//! Recent server logs as a resource
+//!
+//! With logging set up by `TemplateBackend::configure_logging`, a
+//! [`LogCapture`] layer keeps the process's most recent [`LOG_CAPACITY`] log
+//! events, and [`LOGS_URI`] serves them as JSON, oldest first.
+//! `template://logs?level=warn` only returns entries at that level or above.
+//! Fields whose names look sensitive, such as `token` or `password`, are
+//! recorded as `[redacted]`, and response post-processors apply as for any
+//! resource.
+//!
+//! The 0.11 transports can't push notifications to clients, so there are no
+//! subscriptions. Clients tail the logs with `resources/wait` on the URI
+//! instead, which returns once new entries have been logged.
+//!
+//! Logging is process-wide, so every server in the process shares one buffer.
+
+use chrono::{DateTime, Utc};
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, ResourceContents};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::Context;
+
+pub const LOGS_URI: &str = "template://logs";
+
+/// Log entries kept
+pub const LOG_CAPACITY: usize = 1000;
+
+/// Fields whose names contain one of these are redacted
+pub const SENSITIVE_FIELDS: &[&str] = &["password", "secret", "token", "api_key", "authorization"];
+
+const REDACTED: &str = "[redacted]";
+
+static LOGS: LazyLock<LogBuffer> = LazyLock::new(|| LogBuffer::new(LOG_CAPACITY));
+
+/// The process's log buffer
+pub fn buffer() -> &'static LogBuffer {
+    &LOGS
+}
+
+/// Severity of a log entry, from least to most severe
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<&tracing::Level> for LogLevel {
+    fn from(level: &tracing::Level) -> Self {
+        match *level {
+            tracing::Level::TRACE => Self::Trace,
+            tracing::Level::DEBUG => Self::Debug,
+            tracing::Level::INFO => Self::Info,
+            tracing::Level::WARN => Self::Warn,
+            tracing::Level::ERROR => Self::Error,
+        }
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, String> {
+        match level.to_ascii_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(format!(
+                "Unknown log level '{level}'; use trace, debug, info, warn or error"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    /// Module that logged the entry
+    pub target: String,
+    pub message: String,
+    /// Structured fields other than the message
+    pub fields: Map<String, Value>,
+}
+
+/// The most recent log entries
+#[derive(Debug)]
+pub struct LogBuffer {
+    entries: Mutex<VecDeque<LogEntry>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<LogEntry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Add `entry`, dropping the oldest entry if the buffer is full
+    pub fn push(&self, entry: LogEntry) {
+        let mut entries = self.lock();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Entries at `min_level` or above, oldest first
+    pub fn entries(&self, min_level: LogLevel) -> Vec<LogEntry> {
+        self.lock()
+            .iter()
+            .filter(|entry| entry.level >= min_level)
+            .cloned()
+            .collect()
+    }
+}
+
+/// `tracing` layer recording events into the process's [`LogBuffer`]
+#[derive(Debug, Default)]
+pub struct LogCapture;
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for LogCapture {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        buffer().push(LogEntry {
+            timestamp: Utc::now(),
+            level: metadata.level().into(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        let name = field.name();
+        if name == "message" {
+            self.message = match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            return;
+        }
+        let lowercase = name.to_ascii_lowercase();
+        let value = if SENSITIVE_FIELDS.iter().any(|s| lowercase.contains(s)) {
+            Value::from(REDACTED)
+        } else {
+            value
+        };
+        self.fields.insert(name.to_string(), value);
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, Value::from(format!("{value:?}")));
+    }
+}
+
+/// Whether `uri` is the logs resource, with or without a query
+pub fn matches(uri: &str) -> bool {
+    uri.split_once('?').map_or(uri, |(path, _)| path) == LOGS_URI
+}
+
+pub fn resource() -> Resource {
+    Resource {
+        uri: LOGS_URI.to_string(),
+        name: "logs".to_string(),
+        description: Some(
+            "Recent log entries, oldest first; add ?level=warn for warnings and errors only"
+                .to_string(),
+        ),
+        mime_type: Some("application/json".to_string()),
+        annotations: None,
+        raw: None,
+    }
+}
+
+/// Read the logs resource at `uri`, applying its `level` query
+pub fn read(uri: &str) -> Result<ReadResourceResult, String> {
+    let mut min_level = LogLevel::Trace;
+    if let Some((_, query)) = uri.split_once('?') {
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some(("level", level)) => min_level = level.parse()?,
+                _ => return Err(format!("Unknown query parameter '{pair}' for {LOGS_URI}")),
+            }
+        }
+    }
+    let text = serde_json::to_string(&buffer().entries(min_level))
+        .map_err(|e| format!("Failed to serialize logs: {e}"))?;
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text: Some(text),
+            blob: None,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn entry(level: LogLevel, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            level,
+            target: "test".to_string(),
+            message: message.to_string(),
+            fields: Map::new(),
+        }
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.message.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_the_latest_entries() {
+        let logs = LogBuffer::new(2);
+        logs.push(entry(LogLevel::Info, "first"));
+        logs.push(entry(LogLevel::Error, "second"));
+        logs.push(entry(LogLevel::Debug, "third"));
+        assert_eq!(
+            messages(&logs.entries(LogLevel::Trace)),
+            ["second", "third"]
+        );
+        assert_eq!(messages(&logs.entries(LogLevel::Warn)), ["second"]);
+    }
+
+    #[test]
+    fn captures_events_with_redacted_fields() {
+        let subscriber = tracing_subscriber::registry().with(LogCapture);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(
+                user = "ada",
+                api_token = "s3cret",
+                attempts = 3,
+                "captured login"
+            );
+        });
+        let entry = buffer()
+            .entries(LogLevel::Warn)
+            .into_iter()
+            .find(|entry| entry.message == "captured login")
+            .expect("the event is captured");
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(
+            Value::Object(entry.fields),
+            serde_json::json!({ "user": "ada", "api_token": "[redacted]", "attempts": 3 })
+        );
+    }
+
+    #[test]
+    fn reads_with_a_level_query() {
+        assert!(matches("template://logs?level=warn"));
+        let errors = read("template://logs?level=error").unwrap();
+        let entries: Vec<LogEntry> =
+            serde_json::from_str(errors.contents[0].text.as_deref().unwrap()).unwrap();
+        assert!(entries.iter().all(|entry| entry.level == LogLevel::Error));
+
+        assert_eq!(
+            read("template://logs?level=loud").unwrap_err(),
+            "Unknown log level 'loud'; use trace, debug, info, warn or error"
+        );
+        assert_eq!(
+            read("template://logs?since=1").unwrap_err(),
+            "Unknown query parameter 'since=1' for template://logs"
+        );
+    }
+}
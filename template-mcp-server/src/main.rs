@@ -3,17 +3,97 @@
 //! This template demonstrates the basic structure for creating an MCP server
 //! using the PulseEngine MCP framework with automatic tool discovery.
 
-use pulseengine_mcp_server::McpServerBuilder;
-use template_mcp_server::TemplateMcpServer;
+use std::time::Duration;
+use template_mcp_server::providers::Providers;
+use template_mcp_server::upstream::UpstreamClient;
+use template_mcp_server::{banner, health, repl, resource_events};
+use template_mcp_server::{ServerConfig, TemplateBackend, TemplateMcpServer};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Configure logging for STDIO transport
-    TemplateMcpServer::configure_stdio_logging();
+    // Log to stderr, keeping STDOUT for the protocol, and serve recent
+    // entries as template://logs
+    TemplateBackend::configure_logging();
 
-    // Start the server using the macro-generated infrastructure
-    let mut server = TemplateMcpServer::with_defaults().serve_stdio().await?;
-    server.run().await?;
+    // MCP_* variables override the configuration defaults; an invalid value
+    // stops the server here
+    let config = ServerConfig::from_env().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1)
+    });
+    // Setup that can fail, such as loading MCP_SEED_SNAPSHOT, also stops the
+    // server before it serves anything
+    let server = TemplateMcpServer::try_new(config)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to start: {e}");
+            std::process::exit(1)
+        });
+    let mut backend = TemplateBackend::for_server(server);
+    // So does a tool setting naming a tool, or a parameter, the server lacks
+    backend.check_tool_settings().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1)
+    });
+    if std::env::args().any(|arg| arg == banner::QUIET_FLAG) {
+        backend = backend.without_banner();
+    }
+    let scheduler_server = backend.server().clone();
 
-    Ok(())
+    // Shared subsystems (a database pool, a cache) that tools use; register
+    // them with `.provide(name, depends_on, init)`, or by type with
+    // `.provide_state(depends_on, init)`. One that fails to initialize aborts
+    // startup.
+    let providers = Providers::builder()
+        .provide_state(&[], |_| async { Ok(UpstreamClient::new()) })
+        .build()
+        .await?;
+    backend.server().set_providers(providers);
+
+    // Read commands from stdin instead of serving MCP
+    if std::env::args().any(|arg| arg == repl::REPL_FLAG) {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        repl::Repl::new(backend)
+            .run(stdin, tokio::io::stdout())
+            .await?;
+        std::process::exit(0)
+    }
+
+    // Serve the health report on GET /healthz if MCP_HEALTH_ADDR is set
+    if let Ok(addr) = std::env::var(health::HEALTH_ADDR_ENV_VAR) {
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        let server = backend.server().clone();
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(listener, server).await {
+                tracing::warn!(error = %e, "Health endpoint stopped");
+            }
+        });
+    }
+
+    // Serve resource changes as Server-Sent Events if MCP_EVENTS_ADDR is set
+    if let Ok(addr) = std::env::var(resource_events::EVENTS_ADDR_ENV_VAR) {
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        let backend = backend.clone();
+        tokio::spawn(async move {
+            if let Err(e) = resource_events::serve(listener, backend).await {
+                tracing::warn!(error = %e, "Resource event feed stopped");
+            }
+        });
+    }
+
+    // Periodic work runs on the server's scheduler; replace this with your own
+    scheduler_server.schedule("heartbeat", Duration::from_secs(60), |server| async move {
+        tracing::debug!(entries = server.data_store().len(), "Server heartbeat");
+        Ok(())
+    });
+
+    // Serve STDIO, and HTTP if MCP_HTTP_ADDR is set; TemplateBackend
+    // delegates to the macro-generated backend
+    let transports = TemplateBackend::transports_from_env()?;
+    backend.serve_all(transports).await?;
+    scheduler_server.scheduler().shutdown().await;
+
+    // Dropping the runtime would wait for the STDIO transport's pending stdin
+    // read, which only ends when the client closes stdin
+    std::process::exit(0)
 }
@@ -0,0 +1,119 @@
+//! NDJSON tool results for streaming consumers
+//!
+//! A tool returning many records can return them as an [`NdjsonStream`]
+//! instead of one big JSON array: each item is serialized as one line of
+//! newline-delimited JSON, which downstream pipelines can process a line at a
+//! time. The result carries the lines as one text content item tagged
+//! `"mimeType": "application/x-ndjson"`:
+//!
+//! ```json
+//! { "type": "text", "text": "{\"id\":1,...}\n{\"id\":2,...}\n", "mimeType": "application/x-ndjson" }
+//! ```
+//!
+//! The 0.11 transports send one response per request, so the body is
+//! buffered on every transport; a transport that can stream results would
+//! send the items of [`NdjsonStream::lines`] as they are serialized. Like
+//! [`crate::html`], the mime type is added by
+//! [`RawMessageHandler`](crate::raw_messages::RawMessageHandler), which
+//! serves STDIO, and HTTP clients get the lines as plain text content.
+
+use crate::html;
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde::{Deserialize, Serialize};
+
+pub const NDJSON_MIME_TYPE: &str = "application/x-ndjson";
+
+// Rendered streams always start with their tag, which keeps detection cheap
+const NDJSON_PREFIX: &str = r#"{"type":"ndjson""#;
+
+/// A tool return value sent as newline-delimited JSON, one line per item
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct NdjsonStream<T>(pub Vec<T>);
+
+impl<T> FromIterator<T> for NdjsonStream<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        Self(items.into_iter().collect())
+    }
+}
+
+impl<T: Serialize> NdjsonStream<T> {
+    /// The items as NDJSON lines, each ending in a newline
+    pub fn lines(&self) -> impl Iterator<Item = serde_json::Result<String>> + '_ {
+        self.0
+            .iter()
+            .map(|item| serde_json::to_string(item).map(|line| line + "\n"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "ndjson")]
+struct Rendered {
+    ndjson: String,
+}
+
+impl<T: Serialize> std::fmt::Debug for NdjsonStream<T> {
+    /// Tool results are rendered with `Debug`, so this writes the shape
+    /// [`unpack`] recognizes
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ndjson = self
+            .lines()
+            .collect::<serde_json::Result<String>>()
+            .map_err(|_| std::fmt::Error)?;
+        match serde_json::to_string(&Rendered { ndjson }) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+/// Unpack the [`NdjsonStream`] items of a tool result into their lines,
+/// tagging them as NDJSON
+pub fn unpack(result: &mut CallToolResult) {
+    for (index, content) in result.content.iter_mut().enumerate() {
+        let Content::Text { text } = content else {
+            continue;
+        };
+        if !text.starts_with(NDJSON_PREFIX) {
+            continue;
+        }
+        let Ok(Rendered { ndjson }) = serde_json::from_str(text) else {
+            continue;
+        };
+        *text = ndjson;
+        html::tag_later(index, NDJSON_MIME_TYPE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn writes_one_line_per_item() {
+        let stream: NdjsonStream<_> = [json!({ "id": 1 }), json!({ "id": 2 })]
+            .into_iter()
+            .collect();
+        let lines: Vec<String> = stream.lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["{\"id\":1}\n", "{\"id\":2}\n"]);
+    }
+
+    #[test]
+    fn unpacks_rendered_streams_into_their_lines() {
+        let stream = NdjsonStream(vec![1, 2, 3]);
+        let mut result = CallToolResult::success(vec![
+            Content::text(format!("{stream:?}")),
+            Content::text(r#"{"type":"other"}"#),
+        ]);
+        unpack(&mut result);
+        let texts: Vec<&str> = result
+            .content
+            .iter()
+            .filter_map(|content| match content {
+                Content::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["1\n2\n3\n", r#"{"type":"other"}"#]);
+    }
+}
@@ -0,0 +1,470 @@
+//! Long-running operations
+//!
+//! A tool that would run for too long to answer inline starts its work with
+//! [`OperationManager::start`] and returns the [`OperationHandle`] right away.
+//! The client then polls the `get_operation` tool until the operation finishes
+//! and can stop it early with `cancel_operation`; both are registered by the
+//! backend for every server. Finished operations are kept for a TTL (one hour
+//! by default) and then forgotten.
+//!
+//! Work started with [`OperationManager::start_with_progress`] gets a
+//! [`ProgressReporter`], and each report shows up as the `progress` of the
+//! operation's status. The 0.11 transports can't push
+//! `notifications/progress` to clients, so polling `get_operation` is how
+//! clients follow it.
+
+use crate::clock::Clock;
+use crate::ids::{IdGenerator, RandomIds};
+use crate::registry::Tool;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use pulseengine_mcp_protocol::{CallToolResult, Content, Error, Tool as ToolDefinition};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, Weak};
+use tokio::task::AbortHandle;
+
+/// How long finished operations stay available by default
+pub const DEFAULT_RESULT_TTL: chrono::Duration = chrono::Duration::hours(1);
+
+/// Lifecycle state of an operation
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Returned by a tool that started an operation
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationHandle {
+    pub operation_id: String,
+    pub state: OperationState,
+}
+
+impl std::fmt::Debug for OperationHandle {
+    /// Tool results are rendered with `Debug`, so this writes the handle JSON
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match serde_json::to_string(self) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+/// Current status of an operation, as returned by `get_operation`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OperationStatus {
+    pub operation_id: String,
+    pub tool: String,
+    pub state: OperationState,
+    /// Latest progress report, if the operation reports progress
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// How far an operation has got
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Progress {
+    pub progress: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Reports made so far
+    pub reports: u64,
+}
+
+/// Reports the progress of one operation
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    manager: Weak<OperationManager>,
+    operation_id: String,
+}
+
+impl ProgressReporter {
+    /// Record that the operation has got to `progress` of `total`
+    ///
+    /// Ignored once the operation has finished or been cancelled.
+    pub fn report(&self, progress: u64, total: Option<u64>, message: impl Into<String>) {
+        let Some(manager) = self.manager.upgrade() else {
+            return;
+        };
+        let mut operations = manager.lock();
+        let Some(entry) = operations.get_mut(&self.operation_id) else {
+            return;
+        };
+        if entry.status.state != OperationState::Running {
+            return;
+        }
+        let reports = entry.status.progress.as_ref().map_or(0, |p| p.reports) + 1;
+        let message = message.into();
+        tracing::debug!(operation_id = %self.operation_id, progress, message = %message, "Operation progress");
+        entry.status.progress = Some(Progress {
+            progress,
+            total,
+            message: Some(message).filter(|message| !message.is_empty()),
+            reports,
+        });
+    }
+}
+
+/// Runs operations in the background and tracks their status
+pub struct OperationManager {
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    result_ttl: chrono::Duration,
+    operations: Mutex<HashMap<String, Entry>>,
+}
+
+struct Entry {
+    status: OperationStatus,
+    task: Option<AbortHandle>,
+}
+
+impl OperationManager {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            ids: Arc::new(RandomIds),
+            result_ttl: DEFAULT_RESULT_TTL,
+            operations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep finished operations for `ttl` instead of the default hour
+    pub fn with_result_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.result_ttl = ttl;
+        self
+    }
+
+    /// Generate operation ids with `ids` instead of randomly
+    pub fn with_ids(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Run `work` in the background as an operation started by `tool`
+    pub fn start<F>(self: &Arc<Self>, tool: &str, work: F) -> OperationHandle
+    where
+        F: Future<Output = anyhow::Result<Value>> + Send + 'static,
+    {
+        self.start_with_progress(tool, |_| work)
+    }
+
+    /// Like [`start`](Self::start), giving the work a reporter for its
+    /// progress
+    pub fn start_with_progress<W, F>(self: &Arc<Self>, tool: &str, work: W) -> OperationHandle
+    where
+        W: FnOnce(ProgressReporter) -> F,
+        F: Future<Output = anyhow::Result<Value>> + Send + 'static,
+    {
+        let operation_id = format!("op-{:016x}", self.ids.next_id());
+        self.lock().insert(
+            operation_id.clone(),
+            Entry {
+                status: OperationStatus {
+                    operation_id: operation_id.clone(),
+                    tool: tool.to_string(),
+                    state: OperationState::Running,
+                    progress: None,
+                    result: None,
+                    error: None,
+                    started_at: self.clock.now(),
+                    finished_at: None,
+                },
+                task: None,
+            },
+        );
+
+        let work = work(ProgressReporter {
+            manager: Arc::downgrade(self),
+            operation_id: operation_id.clone(),
+        });
+        let manager = Arc::downgrade(self);
+        let id = operation_id.clone();
+        let task = tokio::spawn(async move {
+            let outcome = work.await;
+            if let Some(manager) = manager.upgrade() {
+                manager.finish(&id, outcome);
+            }
+        });
+        if let Some(entry) = self.lock().get_mut(&operation_id) {
+            if entry.status.state == OperationState::Running {
+                entry.task = Some(task.abort_handle());
+            }
+        }
+
+        tracing::info!(operation_id = %operation_id, tool = %tool, "Started operation");
+        OperationHandle {
+            operation_id,
+            state: OperationState::Running,
+        }
+    }
+
+    /// Status of an operation, or `None` if it is unknown or has expired
+    pub fn get(&self, operation_id: &str) -> Option<OperationStatus> {
+        let mut operations = self.lock();
+        self.purge_expired(&mut operations);
+        operations
+            .get(operation_id)
+            .map(|entry| entry.status.clone())
+    }
+
+    /// Cancel a running operation; finished operations are left as they are
+    pub fn cancel(&self, operation_id: &str) -> Option<OperationStatus> {
+        let mut operations = self.lock();
+        self.purge_expired(&mut operations);
+        let entry = operations.get_mut(operation_id)?;
+        if entry.status.state == OperationState::Running {
+            if let Some(task) = entry.task.take() {
+                task.abort();
+            }
+            entry.status.state = OperationState::Cancelled;
+            entry.status.finished_at = Some(self.clock.now());
+            tracing::info!(operation_id = %operation_id, "Cancelled operation");
+        }
+        Some(entry.status.clone())
+    }
+
+    fn finish(&self, operation_id: &str, outcome: anyhow::Result<Value>) {
+        let mut operations = self.lock();
+        let Some(entry) = operations.get_mut(operation_id) else {
+            return;
+        };
+        if entry.status.state != OperationState::Running {
+            return;
+        }
+        match outcome {
+            Ok(result) => {
+                entry.status.state = OperationState::Completed;
+                entry.status.result = Some(result);
+            }
+            Err(e) => {
+                entry.status.state = OperationState::Failed;
+                entry.status.error = Some(e.to_string());
+            }
+        }
+        entry.status.finished_at = Some(self.clock.now());
+        entry.task = None;
+    }
+
+    fn purge_expired(&self, operations: &mut HashMap<String, Entry>) {
+        let now = self.clock.now();
+        operations.retain(|_, entry| {
+            entry
+                .status
+                .finished_at
+                .is_none_or(|finished| finished + self.result_ttl > now)
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.operations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The `get_operation` and `cancel_operation` tools for `manager`
+pub fn tools(manager: Arc<OperationManager>) -> Vec<Box<dyn Tool>> {
+    vec![
+        Box::new(OperationTool {
+            manager: manager.clone(),
+            kind: OperationToolKind::Get,
+        }),
+        Box::new(OperationTool {
+            manager,
+            kind: OperationToolKind::Cancel,
+        }),
+    ]
+}
+
+#[derive(Clone, Copy)]
+enum OperationToolKind {
+    Get,
+    Cancel,
+}
+
+struct OperationTool {
+    manager: Arc<OperationManager>,
+    kind: OperationToolKind,
+}
+
+#[async_trait]
+impl Tool for OperationTool {
+    fn definition(&self) -> ToolDefinition {
+        let (name, description) = match self.kind {
+            OperationToolKind::Get => (
+                "get_operation",
+                "Get the status of a long-running operation, including its result once finished",
+            ),
+            OperationToolKind::Cancel => ("cancel_operation", "Cancel a long-running operation"),
+        };
+        ToolDefinition {
+            name: name.to_string(),
+            description: description.to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "operation_id": { "type": "string" }
+                },
+                "required": ["operation_id"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn invoke(&self, arguments: Option<Value>) -> Result<CallToolResult, Error> {
+        let operation_id = arguments
+            .as_ref()
+            .and_then(|args| args.get("operation_id"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::invalid_params("Missing required parameter: operation_id"))?;
+
+        let status = match self.kind {
+            OperationToolKind::Get => self.manager.get(operation_id),
+            OperationToolKind::Cancel => self.manager.cancel(operation_id),
+        }
+        .ok_or_else(|| Error::invalid_params(format!("Unknown operation: {operation_id}")))?;
+
+        let status = serde_json::to_value(status)
+            .map_err(|e| Error::internal_error(format!("Failed to serialize status: {e}")))?;
+        Ok(CallToolResult {
+            content: vec![Content::text(status.to_string())],
+            is_error: Some(false),
+            structured_content: Some(status),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::ids::SequentialIds;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn manager() -> (Arc<ManualClock>, Arc<OperationManager>) {
+        let clock = Arc::new(ManualClock::new("2026-01-01T00:00:00Z".parse().unwrap()));
+        let manager = OperationManager::new(clock.clone())
+            .with_ids(Arc::new(SequentialIds::starting_at(1)))
+            .with_result_ttl(chrono::Duration::minutes(5));
+        (clock, Arc::new(manager))
+    }
+
+    /// The status of `operation_id` once it has stopped running
+    async fn settled(manager: &OperationManager, operation_id: &str) -> OperationStatus {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            let status = manager.get(operation_id).unwrap();
+            if status.state != OperationState::Running || tokio::time::Instant::now() > deadline {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn records_results_and_failures() {
+        let (_, manager) = manager();
+        let done = manager.start("report", async { Ok(json!({ "rows": 3 })) });
+        let failed = manager.start("report", async { anyhow::bail!("disk full") });
+        assert_eq!(done.operation_id, "op-0000000000000001");
+        assert_eq!(done.state, OperationState::Running);
+
+        let status = settled(&manager, &done.operation_id).await;
+        assert_eq!(status.state, OperationState::Completed);
+        assert_eq!(status.result, Some(json!({ "rows": 3 })));
+        assert_eq!(status.tool, "report");
+        assert!(status.finished_at.is_some());
+
+        let status = settled(&manager, &failed.operation_id).await;
+        assert_eq!(status.state, OperationState::Failed);
+        assert_eq!(status.error.as_deref(), Some("disk full"));
+    }
+
+    #[tokio::test]
+    async fn reports_progress_and_cancels() {
+        let (_, manager) = manager();
+        let handle = manager.start_with_progress("export", |progress| async move {
+            progress.report(1, Some(4), "first batch");
+            std::future::pending::<()>().await;
+            Ok(Value::Null)
+        });
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while manager
+            .get(&handle.operation_id)
+            .unwrap()
+            .progress
+            .is_none()
+            && tokio::time::Instant::now() < deadline
+        {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(
+            manager.get(&handle.operation_id).unwrap().progress,
+            Some(Progress {
+                progress: 1,
+                total: Some(4),
+                message: Some("first batch".to_string()),
+                reports: 1,
+            })
+        );
+
+        let status = manager.cancel(&handle.operation_id).unwrap();
+        assert_eq!(status.state, OperationState::Cancelled);
+        assert!(manager.cancel("op-unknown").is_none());
+    }
+
+    #[tokio::test]
+    async fn forgets_finished_operations_after_the_ttl() {
+        let (clock, manager) = manager();
+        let handle = manager.start("report", async { Ok(Value::Null) });
+        settled(&manager, &handle.operation_id).await;
+
+        clock.advance(chrono::Duration::minutes(4));
+        assert!(manager.get(&handle.operation_id).is_some());
+        clock.advance(chrono::Duration::minutes(1));
+        assert!(manager.get(&handle.operation_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn serves_status_through_the_tools() {
+        let (_, manager) = manager();
+        let handle = manager.start("report", async { Ok(json!("done")) });
+        settled(&manager, &handle.operation_id).await;
+        let [get, cancel] = <[Box<dyn Tool>; 2]>::try_from(tools(manager)).ok().unwrap();
+        assert_eq!(get.definition().name, "get_operation");
+        assert_eq!(cancel.definition().name, "cancel_operation");
+
+        let arguments = json!({ "operation_id": handle.operation_id });
+        let result = get.invoke(Some(arguments.clone())).await.unwrap();
+        let status = result.structured_content.unwrap();
+        assert_eq!(status["state"], "completed");
+        assert_eq!(status["result"], "done");
+        // Cancelling a finished operation leaves it as it is
+        let result = cancel.invoke(Some(arguments)).await.unwrap();
+        assert_eq!(result.structured_content.unwrap()["state"], "completed");
+
+        let error = get
+            .invoke(Some(json!({ "operation_id": "op-nope" })))
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "Unknown operation: op-nope");
+        let error = get.invoke(None).await.unwrap_err();
+        assert_eq!(error.message, "Missing required parameter: operation_id");
+    }
+}
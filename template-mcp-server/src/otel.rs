@@ -0,0 +1,517 @@
+//! OpenTelemetry spans for tool calls, enabled with the `otel` feature
+//!
+//! With a [`Tracer`] attached (`TemplateBackend::with_tracer`), every tool call
+//! produces a server span named `tools/call {tool}` with these attributes:
+//!
+//! - `mcp.tool.name`
+//! - `mcp.client.id`
+//! - `mcp.tool.outcome`: `ok`, `tool_error` (the tool reported `isError`) or
+//!   `error` (the call failed)
+//! - `mcp.tool.duration_ms`
+//!
+//! The default backend exports spans when `OTEL_EXPORTER_OTLP_ENDPOINT` is
+//! set, posting them as OTLP/HTTP JSON to `{endpoint}/v1/traces` in batches
+//! from a background thread, under the service name in `OTEL_SERVICE_NAME`.
+//! Only `http://` endpoints are supported, since the crate has no TLS
+//! client; point it at a local collector.
+//!
+//! Spans join the caller's trace when a W3C `traceparent` is available. The
+//! 0.11 HTTP transport doesn't show request headers to the backend, so the
+//! `traceparent` header can't be read yet; instead the trace context is taken
+//! from the `TRACEPARENT` environment variable, the convention for processes
+//! started by a traced parent, such as a STDIO server.
+
+use pulseengine_mcp_protocol::CallToolResult;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Environment variable with the OTLP collector's base URL
+pub const ENDPOINT_ENV_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Environment variable with the service name reported for spans
+pub const SERVICE_NAME_ENV_VAR: &str = "OTEL_SERVICE_NAME";
+
+/// Environment variable with the W3C trace context of the parent process
+pub const TRACEPARENT_ENV_VAR: &str = "TRACEPARENT";
+
+const MAX_BATCH: usize = 512;
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// W3C trace context identifying a parent span
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+}
+
+impl TraceContext {
+    /// Parse a `traceparent` value such as
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = decode_hex::<16>(parts.next()?)?;
+        let span_id = decode_hex::<8>(parts.next()?)?;
+        let _flags = decode_hex::<1>(parts.next()?)?;
+        if version.len() != 2 || version == "ff" {
+            return None;
+        }
+        // All-zero ids are invalid
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+        Some(Self { trace_id, span_id })
+    }
+}
+
+/// How a tool call ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    /// The tool ran and reported `isError`
+    ToolError,
+    /// The call failed before or while running the tool
+    Error,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::ToolError => "tool_error",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A finished span
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanData {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: String,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(String, Value)>,
+    pub outcome: Outcome,
+    /// Error message for failed calls
+    pub status_message: Option<String>,
+}
+
+impl SpanData {
+    /// Value of an attribute
+    pub fn attribute(&self, key: &str) -> Option<&Value> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Receives finished spans
+pub trait SpanExporter: Send + Sync {
+    /// Take a finished span; must not block the caller
+    fn export(&self, span: SpanData);
+}
+
+/// Keeps spans in memory, for tests
+#[derive(Debug, Default)]
+pub struct InMemoryExporter {
+    spans: Mutex<Vec<SpanData>>,
+}
+
+impl InMemoryExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spans exported so far, in order
+    pub fn spans(&self) -> Vec<SpanData> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<SpanData>> {
+        self.spans
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SpanExporter for InMemoryExporter {
+    fn export(&self, span: SpanData) {
+        self.lock().push(span);
+    }
+}
+
+/// Posts spans to an OTLP/HTTP collector as JSON
+pub struct OtlpHttpExporter {
+    sender: Mutex<mpsc::Sender<SpanData>>,
+}
+
+impl OtlpHttpExporter {
+    /// Export to the collector at `endpoint`, e.g. `http://localhost:4318`
+    pub fn new(endpoint: &str, service_name: impl Into<String>) -> anyhow::Result<Self> {
+        let target = HttpTarget::parse(endpoint)?;
+        let service_name = service_name.into();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("otlp-exporter".to_string())
+            .spawn(move || export_loop(&receiver, &target, &service_name))?;
+        Ok(Self {
+            sender: Mutex::new(sender),
+        })
+    }
+
+    /// Exporter configured from `OTEL_EXPORTER_OTLP_ENDPOINT`, if set
+    pub fn from_env() -> Option<anyhow::Result<Self>> {
+        let endpoint = std::env::var(ENDPOINT_ENV_VAR).ok()?;
+        let service_name = std::env::var(SERVICE_NAME_ENV_VAR)
+            .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+        Some(Self::new(&endpoint, service_name))
+    }
+}
+
+impl SpanExporter for OtlpHttpExporter {
+    fn export(&self, span: SpanData) {
+        // Fails only once the export thread has stopped
+        let _ = self
+            .sender
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .send(span);
+    }
+}
+
+/// Creates tool call spans
+#[derive(Clone)]
+pub struct Tracer {
+    exporter: Arc<dyn SpanExporter>,
+    parent: Option<TraceContext>,
+}
+
+impl Tracer {
+    /// Spans become children of the context in `TRACEPARENT`, if set
+    pub fn new(exporter: Arc<dyn SpanExporter>) -> Self {
+        let parent = std::env::var(TRACEPARENT_ENV_VAR)
+            .ok()
+            .and_then(|traceparent| TraceContext::from_traceparent(&traceparent));
+        Self { exporter, parent }
+    }
+
+    /// Make spans children of `parent` instead
+    pub fn with_parent(mut self, parent: Option<TraceContext>) -> Self {
+        self.parent = parent;
+        self
+    }
+
+    /// Tracer exporting to `OTEL_EXPORTER_OTLP_ENDPOINT`, if set
+    pub fn from_env() -> Option<anyhow::Result<Self>> {
+        OtlpHttpExporter::from_env()
+            .map(|exporter| exporter.map(|exporter| Self::new(Arc::new(exporter))))
+    }
+
+    /// Start the span of a tool call
+    pub fn start_tool_call(&self, tool: &str, client_id: &str) -> ToolCallSpan {
+        ToolCallSpan {
+            exporter: self.exporter.clone(),
+            trace_id: self
+                .parent
+                .map_or_else(rand::random::<[u8; 16]>, |parent| parent.trace_id),
+            span_id: rand::random::<[u8; 8]>(),
+            parent_span_id: self.parent.map(|parent| parent.span_id),
+            tool: tool.to_string(),
+            client_id: client_id.to_string(),
+            start: SystemTime::now(),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// A tool call span in progress
+pub struct ToolCallSpan {
+    exporter: Arc<dyn SpanExporter>,
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    tool: String,
+    client_id: String,
+    start: SystemTime,
+    started: Instant,
+}
+
+impl ToolCallSpan {
+    /// Finish the span with the outcome of `result`
+    pub fn end_with<E: std::fmt::Display>(self, result: &Result<CallToolResult, E>) {
+        match result {
+            Ok(result) if result.is_error == Some(true) => self.end(Outcome::ToolError, None),
+            Ok(_) => self.end(Outcome::Ok, None),
+            Err(e) => self.end(Outcome::Error, Some(e.to_string())),
+        }
+    }
+
+    /// Finish the span and hand it to the exporter
+    pub fn end(self, outcome: Outcome, error: Option<String>) {
+        let elapsed = self.started.elapsed();
+        let span = SpanData {
+            trace_id: self.trace_id,
+            span_id: self.span_id,
+            parent_span_id: self.parent_span_id,
+            name: format!("tools/call {}", self.tool),
+            start: self.start,
+            end: self.start + elapsed,
+            attributes: vec![
+                ("mcp.tool.name".to_string(), json!(self.tool)),
+                ("mcp.client.id".to_string(), json!(self.client_id)),
+                ("mcp.tool.outcome".to_string(), json!(outcome.as_str())),
+                (
+                    "mcp.tool.duration_ms".to_string(),
+                    json!(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)),
+                ),
+            ],
+            outcome,
+            status_message: error,
+        };
+        self.exporter.export(span);
+    }
+}
+
+/// OTLP/HTTP JSON body for `spans`
+pub fn otlp_json(spans: &[SpanData], service_name: &str) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut status = json!({ "code": if span.outcome == Outcome::Ok { 1 } else { 2 } });
+            if let Some(message) = &span.status_message {
+                status["message"] = json!(message);
+            }
+            json!({
+                "traceId": encode_hex(&span.trace_id),
+                "spanId": encode_hex(&span.span_id),
+                "parentSpanId": span.parent_span_id.map(|id| encode_hex(&id)).unwrap_or_default(),
+                "name": span.name,
+                // SPAN_KIND_SERVER
+                "kind": 2,
+                "startTimeUnixNano": unix_nanos(span.start),
+                "endTimeUnixNano": unix_nanos(span.end),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| json!({ "key": key, "value": any_value(value) }))
+                    .collect::<Vec<_>>(),
+                "status": status,
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{ "key": "service.name", "value": { "stringValue": service_name } }]
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }]
+        }]
+    })
+}
+
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Bool(flag) => json!({ "boolValue": flag }),
+        // OTLP JSON encodes 64-bit integers as strings
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+        Value::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Where to post spans: an `http://` URL split into its parts
+struct HttpTarget {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl HttpTarget {
+    fn parse(endpoint: &str) -> anyhow::Result<Self> {
+        let rest = endpoint.strip_prefix("http://").ok_or_else(|| {
+            anyhow::anyhow!("Only http:// OTLP endpoints are supported: {endpoint}")
+        })?;
+        let (authority, base_path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (authority, 80),
+        };
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: format!("{}/v1/traces", base_path.trim_end_matches('/')),
+        })
+    }
+
+    fn post(&self, body: &str) -> std::io::Result<()> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_read_timeout(Some(EXPORT_TIMEOUT))?;
+        stream.set_write_timeout(Some(EXPORT_TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.host,
+            self.port,
+            body.len()
+        )?;
+
+        let mut status_line = [0u8; 12];
+        stream.read_exact(&mut status_line)?;
+        // "HTTP/1.1 200"
+        match status_line.get(9) {
+            Some(b'2') => Ok(()),
+            _ => Err(std::io::Error::other(format!(
+                "collector answered {}",
+                String::from_utf8_lossy(&status_line)
+            ))),
+        }
+    }
+}
+
+fn export_loop(receiver: &mpsc::Receiver<SpanData>, target: &HttpTarget, service_name: &str) {
+    while let Ok(first) = receiver.recv() {
+        let mut batch = vec![first];
+        while batch.len() < MAX_BATCH {
+            match receiver.try_recv() {
+                Ok(span) => batch.push(span),
+                Err(_) => break,
+            }
+        }
+        let body = otlp_json(&batch, service_name).to_string();
+        if let Err(e) = target.post(&body) {
+            tracing::warn!("Failed to export {} spans: {e}", batch.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn parses_traceparents() {
+        let parent = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+        assert_eq!(
+            encode_hex(&parent.trace_id),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(encode_hex(&parent.span_id), "00f067aa0ba902b7");
+        for invalid in [
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473g-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(TraceContext::from_traceparent(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn records_tool_call_spans_under_the_parent() {
+        let exporter = Arc::new(InMemoryExporter::new());
+        let parent = TraceContext::from_traceparent(TRACEPARENT);
+        let tracer = Tracer::new(exporter.clone()).with_parent(parent);
+
+        let ok: Result<CallToolResult, String> = Ok(CallToolResult::success(vec![]));
+        tracer.start_tool_call("echo", "client-1").end_with(&ok);
+        let failed: Result<CallToolResult, String> = Err("boom".to_string());
+        tracer.start_tool_call("echo", "client-1").end_with(&failed);
+
+        let spans = exporter.spans();
+        assert_eq!(spans[0].name, "tools/call echo");
+        assert_eq!(spans[0].trace_id, parent.unwrap().trace_id);
+        assert_eq!(spans[0].parent_span_id, Some(parent.unwrap().span_id));
+        assert_eq!(
+            spans[0].attribute("mcp.client.id"),
+            Some(&json!("client-1"))
+        );
+        assert_eq!(spans[0].attribute("mcp.tool.outcome"), Some(&json!("ok")));
+        assert_eq!(spans[1].outcome, Outcome::Error);
+        assert_eq!(spans[1].status_message.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn encodes_spans_as_otlp_json() {
+        let start = UNIX_EPOCH + Duration::from_secs(1);
+        let span = SpanData {
+            trace_id: [1; 16],
+            span_id: [2; 8],
+            parent_span_id: None,
+            name: "tools/call echo".to_string(),
+            start,
+            end: start + Duration::from_millis(5),
+            attributes: vec![
+                ("mcp.tool.duration_ms".to_string(), json!(5)),
+                ("flag".to_string(), json!(true)),
+            ],
+            outcome: Outcome::ToolError,
+            status_message: None,
+        };
+        let body = otlp_json(&[span], "template");
+        assert_eq!(
+            body["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"],
+            "template"
+        );
+        let span = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "01".repeat(16));
+        assert_eq!(span["parentSpanId"], "");
+        assert_eq!(span["startTimeUnixNano"], "1000000000");
+        assert_eq!(span["endTimeUnixNano"], "1005000000");
+        assert_eq!(span["attributes"][0]["value"], json!({ "intValue": "5" }));
+        assert_eq!(span["attributes"][1]["value"], json!({ "boolValue": true }));
+        assert_eq!(span["status"], json!({ "code": 2 }));
+    }
+
+    #[test]
+    fn parses_http_endpoints() {
+        let target = HttpTarget::parse("http://collector:4318/otel/").unwrap();
+        assert_eq!(
+            (target.host.as_str(), target.port, target.path.as_str()),
+            ("collector", 4318, "/otel/v1/traces")
+        );
+        let target = HttpTarget::parse("http://localhost").unwrap();
+        assert_eq!((target.port, target.path.as_str()), (80, "/v1/traces"));
+        assert!(HttpTarget::parse("https://collector:4318").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Paging through `resources/list`
+//!
+//! The backend gathers every resource, both the macro-generated ones and
+//! those registered at runtime, sorts them by URI and returns at most
+//! `ResourcesConfig::page_size` of them. When more follow, the result
+//! carries a `nextCursor`, which the client passes as `cursor` to get the next
+//! page.
+//!
+//! A cursor names the last URI of its page, encoded so clients treat it as
+//! opaque. The next page starts after that URI, so a cursor keeps working
+//! when resources are added or removed between calls: later pages neither
+//! skip nor repeat the resources that were already there.
+//!
+//! The 0.11 protocol types write the field as `next_cursor` rather than the
+//! spec's `nextCursor`; the cursor itself is the same either way.
+
+use base64::Engine;
+
+const CURSOR_ENGINE: base64::engine::GeneralPurpose =
+    base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// Cursor resuming a listing after `key`
+pub fn encode_cursor(key: &str) -> String {
+    CURSOR_ENGINE.encode(key)
+}
+
+/// The key a cursor resumes after
+pub fn decode_cursor(cursor: &str) -> Result<String, String> {
+    CURSOR_ENGINE
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| format!("Invalid cursor: {cursor}"))
+}
+
+/// The page of `items` after `cursor`, with the cursor of the next page
+///
+/// `items` are sorted by `key` first, which must be unique.
+pub fn page<T>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> &str,
+    cursor: Option<&str>,
+    page_size: usize,
+) -> Result<(Vec<T>, Option<String>), String> {
+    items.sort_by(|a, b| key(a).cmp(key(b)));
+    let start = match cursor {
+        Some(cursor) => {
+            let after = decode_cursor(cursor)?;
+            items.partition_point(|item| key(item) <= after.as_str())
+        }
+        None => 0,
+    };
+
+    // A page of zero would never make progress
+    let end = start.saturating_add(page_size.max(1)).min(items.len());
+    let next_cursor = (end < items.len()).then(|| encode_cursor(key(&items[end - 1])));
+    items.truncate(end);
+    items.drain(..start);
+    Ok((items, next_cursor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uris(items: &[&str]) -> Vec<String> {
+        items.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn pages_through_sorted_items() {
+        let items = uris(&["c", "a", "e", "b", "d"]);
+        let (first, cursor) = page(items.clone(), String::as_str, None, 2).unwrap();
+        assert_eq!(first, ["a", "b"]);
+        let (second, cursor) = page(items.clone(), String::as_str, cursor.as_deref(), 2).unwrap();
+        assert_eq!(second, ["c", "d"]);
+        let (last, cursor) = page(items, String::as_str, cursor.as_deref(), 2).unwrap();
+        assert_eq!(last, ["e"]);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn cursors_survive_changes_between_pages() {
+        let (_, cursor) = page(uris(&["a", "b", "c", "d"]), String::as_str, None, 2).unwrap();
+        // "b" removed and "aa" added before the next page
+        let changed = uris(&["a", "aa", "c", "d"]);
+        let (next, _) = page(changed, String::as_str, cursor.as_deref(), 2).unwrap();
+        assert_eq!(next, ["c", "d"]);
+    }
+
+    #[test]
+    fn rejects_cursors_it_did_not_issue() {
+        assert_eq!(
+            page(uris(&["a"]), String::as_str, Some("not base64!"), 2).unwrap_err(),
+            "Invalid cursor: not base64!"
+        );
+        assert_eq!(
+            decode_cursor(&encode_cursor("template://a")).unwrap(),
+            "template://a"
+        );
+    }
+
+    #[test]
+    fn a_page_size_of_zero_still_makes_progress() {
+        let (page, cursor) = page(uris(&["a", "b"]), String::as_str, None, 0).unwrap();
+        assert_eq!(page, ["a"]);
+        assert!(cursor.is_some());
+    }
+}
@@ -0,0 +1,79 @@
+//! Containing panics in tool calls
+//!
+//! A tool that panics would otherwise unwind through the task answering the
+//! connection's requests, leaving every later request unanswered. The backend
+//! runs each call under [`catch_panic`], so a panic only fails its own call,
+//! with an internal error carrying the panic message. Locks the tool held are
+//! released as the panic unwinds, and the shared state behind them recovers
+//! from the poisoning; see [`crate::data_store`].
+
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A future that fails with the panic message if polling it panics
+pub struct CatchPanic<F> {
+    inner: Pin<Box<F>>,
+}
+
+/// Run `future`, failing with its panic message if it panics
+pub fn catch_panic<F: Future>(future: F) -> CatchPanic<F> {
+    CatchPanic {
+        inner: Box::pin(future),
+    }
+}
+
+impl<F: Future> Future for CatchPanic<F> {
+    type Output = Result<F::Output, String>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The future isn't polled again after a panic, so whatever state it
+        // broke is never seen
+        match panic::catch_unwind(AssertUnwindSafe(|| self.inner.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(panic_message(&*payload))),
+        }
+    }
+}
+
+/// The message a panic was raised with
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn passes_on_the_output() {
+        assert_eq!(catch_panic(async { 2 + 2 }).await, Ok(4));
+    }
+
+    #[tokio::test]
+    async fn fails_with_the_panic_message() {
+        let literal = catch_panic(async { panic!("broken") }).await;
+        assert_eq!(literal, Err::<(), _>("broken".to_string()));
+
+        let formatted = catch_panic(async {
+            let id = 7;
+            panic!("no item {id}")
+        })
+        .await;
+        assert_eq!(formatted, Err::<(), _>("no item 7".to_string()));
+    }
+
+    #[test]
+    fn names_panics_without_a_message() {
+        assert_eq!(panic_message(&42_u32), "unknown panic");
+    }
+}
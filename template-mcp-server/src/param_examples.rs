@@ -0,0 +1,76 @@
+//! Example values for tool parameters
+//!
+//! The `examples` setting of a tool (see [`crate::tool_settings`]) gives
+//! example values of its parameters, added to each parameter's `examples`
+//! array in the tool's input schema, which helps LLM clients produce
+//! well-formed calls. Each builds its values with [`examples`] and names the
+//! parameter's Rust type, so an example of the wrong type fails to compile.
+
+use pulseengine_mcp_protocol::Tool;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Builds the example values of one parameter
+pub type ExamplesFn = fn() -> Vec<Value>;
+
+/// Serialize examples of a parameter of type `T`
+pub fn examples<T: Serialize>(values: impl IntoIterator<Item = T>) -> Vec<Value> {
+    values
+        .into_iter()
+        .filter_map(|value| serde_json::to_value(value).ok())
+        .collect()
+}
+
+/// Add the examples of a tool's parameters to its input schema
+pub fn apply_to_schema(tool: &mut Tool) {
+    for (param, examples) in crate::tool_settings::settings_of(&tool.name).examples {
+        if let Some(property) = tool
+            .input_schema
+            .get_mut("properties")
+            .and_then(|p| p.get_mut(*param))
+            .and_then(Value::as_object_mut)
+        {
+            property.insert("examples".to_string(), Value::Array(examples()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "message": { "type": "string" }, "other": { "type": "string" } }
+            }),
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn adds_the_examples_of_each_parameter() {
+        let mut echo = tool("echo");
+        apply_to_schema(&mut echo);
+        let properties = &echo.input_schema["properties"];
+        assert_eq!(properties["message"]["examples"], json!(["hello world"]));
+        assert!(properties["other"].get("examples").is_none());
+    }
+
+    #[test]
+    fn leaves_tools_without_examples_alone() {
+        let mut other = tool("get_status");
+        apply_to_schema(&mut other);
+        assert_eq!(other.input_schema, tool("get_status").input_schema);
+    }
+
+    #[test]
+    fn serializes_examples_of_any_type() {
+        assert_eq!(examples([1.5, 2.0]), [json!(1.5), json!(2.0)]);
+        assert_eq!(examples(Some("a")), [json!("a")]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Mutually exclusive tool parameters
+//!
+//! Some tools look an entry up by one of several parameters, such as `id` or
+//! `name`, and need exactly one of them. Each group in a tool's `one_of`
+//! setting (see [`crate::tool_settings`]) declares such parameters: calls
+//! giving none or more than one of them fail with an error naming the group
+//! before the tool runs, and the input schema gets a `oneOf` with one
+//! `required` alternative per parameter. As elsewhere, an explicit null counts
+//! as not given.
+//!
+//! The parameters of a group are declared `Option<T>` in the tool, which can
+//! then rely on exactly one of them being `Some`.
+
+use pulseengine_mcp_protocol::Tool;
+use serde_json::{json, Value};
+
+/// Groups of parameters of `tool` of which a call must give exactly one
+fn groups_for(tool: &str) -> impl Iterator<Item = &'static [&'static str]> {
+    crate::tool_settings::settings_of(tool)
+        .one_of
+        .iter()
+        .copied()
+}
+
+/// Require exactly one parameter of each group in `tool`'s input schema
+///
+/// A single group becomes the schema's `oneOf`; with more, each is added to
+/// its `allOf`.
+pub fn apply_to_schema(tool: &mut Tool) {
+    let groups: Vec<Value> = groups_for(&tool.name)
+        .map(|group| {
+            let alternatives: Vec<_> = group
+                .iter()
+                .map(|param| json!({ "required": [param] }))
+                .collect();
+            json!(alternatives)
+        })
+        .collect();
+    let Some(schema) = tool.input_schema.as_object_mut() else {
+        return;
+    };
+    match groups.as_slice() {
+        [] => {}
+        [alternatives] => {
+            schema.insert("oneOf".to_string(), alternatives.clone());
+        }
+        _ => {
+            let all_of = groups
+                .iter()
+                .map(|alternatives| json!({ "oneOf": alternatives }))
+                .collect();
+            schema.insert("allOf".to_string(), Value::Array(all_of));
+        }
+    }
+}
+
+/// Reject a call to `tool` that doesn't give exactly one parameter of a group
+pub fn check_arguments(tool: &str, arguments: Option<&Value>) -> Result<(), String> {
+    let arguments = arguments.and_then(Value::as_object);
+    for group in groups_for(tool) {
+        let given: Vec<_> = group
+            .iter()
+            .copied()
+            .filter(|param| {
+                arguments
+                    .and_then(|arguments| arguments.get(*param))
+                    .is_some_and(|value| !value.is_null())
+            })
+            .collect();
+        if given.len() != 1 {
+            let found = if given.is_empty() {
+                "none".to_string()
+            } else {
+                given.join(", ")
+            };
+            return Err(format!(
+                "Tool '{tool}' needs exactly one of the parameters {}; got {found}",
+                group.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_exactly_one_parameter_of_a_group() {
+        assert_eq!(
+            check_arguments("find_data", Some(&json!({ "id": "1" }))),
+            Ok(())
+        );
+        assert_eq!(
+            check_arguments("find_data", Some(&json!({ "name": "a" }))),
+            Ok(())
+        );
+        assert_eq!(
+            check_arguments("find_data", Some(&json!({ "id": "1", "name": "a" }))),
+            Err(
+                "Tool 'find_data' needs exactly one of the parameters id, name; got id, name"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check_arguments("find_data", Some(&json!({ "id": null }))),
+            Err(
+                "Tool 'find_data' needs exactly one of the parameters id, name; got none"
+                    .to_string()
+            )
+        );
+        assert_eq!(check_arguments("echo", None), Ok(()));
+    }
+
+    #[test]
+    fn adds_one_alternative_per_parameter() {
+        let mut tool = Tool {
+            name: "find_data".to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool);
+        assert_eq!(
+            tool.input_schema["oneOf"],
+            json!([{ "required": ["id"] }, { "required": ["name"] }])
+        );
+    }
+}
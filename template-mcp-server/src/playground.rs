@@ -0,0 +1,240 @@
+//! Ready-to-send example calls of every tool
+//!
+//! [`PLAYGROUND_URI`] serves one entry per tool with a `tools/call` request
+//! that can be copied and sent as it is, to try the tool without reading its
+//! schema first:
+//!
+//! ```json
+//! {
+//!   "tool": "create_data",
+//!   "description": "Create new example data",
+//!   "request": {
+//!     "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+//!     "params": { "name": "create_data", "arguments": { "name": "widget", "value": 42.0 } }
+//!   }
+//! }
+//! ```
+//!
+//! The arguments are built by [`example_arguments`] from the tool's schema as
+//! `tools/list` serves it: each required parameter, and each optional one with
+//! examples or a default, gets its first example (see `param_examples.rs`),
+//! else its default, else its first allowed value, else a placeholder of its
+//! type and format. The entries follow `tools/list`, so a new tool is in the playground
+//! as soon as it is served.
+
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, Tool};
+use serde_json::{json, Map, Value};
+
+/// URI of the playground resource
+pub const PLAYGROUND_URI: &str = "template://playground";
+
+pub fn resource() -> Resource {
+    Resource {
+        uri: PLAYGROUND_URI.to_string(),
+        name: "playground".to_string(),
+        description: Some("An example tools/call request for every tool".to_string()),
+        mime_type: Some("application/json".to_string()),
+        annotations: None,
+        raw: None,
+    }
+}
+
+pub fn matches(uri: &str) -> bool {
+    uri == PLAYGROUND_URI
+}
+
+/// The playground document for `tools`
+pub fn document(tools: &[Tool]) -> Value {
+    let entries: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "tool": tool.name,
+                "description": tool.description.lines().next().unwrap_or_default(),
+                "request": {
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": {
+                        "name": tool.name,
+                        "arguments": example_arguments(&tool.input_schema),
+                    }
+                }
+            })
+        })
+        .collect();
+    json!({ "tools": entries })
+}
+
+pub fn read(tools: &[Tool]) -> anyhow::Result<ReadResourceResult> {
+    crate::schema_resources::contents(PLAYGROUND_URI, &document(tools))
+}
+
+/// Example arguments for an object `schema`
+pub fn example_arguments(schema: &Value) -> Value {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let mut arguments = Map::new();
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, property) in properties {
+            let property = resolve(schema, property);
+            let suggested = suggested_value(property);
+            if suggested.is_some() || required.contains(&name.as_str()) {
+                let value = suggested.unwrap_or_else(|| example_value(schema, property));
+                arguments.insert(name.clone(), value);
+            }
+        }
+    }
+    Value::Object(arguments)
+}
+
+/// A value the schema itself offers: an example, the default or a constant
+fn suggested_value(property: &Value) -> Option<Value> {
+    property
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|examples| examples.first())
+        .or_else(|| property.get("default"))
+        .or_else(|| property.get("const"))
+        .or_else(|| {
+            property
+                .get("enum")
+                .and_then(Value::as_array)
+                .and_then(|values| values.first())
+        })
+        .cloned()
+}
+
+/// A value of the type `property` describes, within its bounds
+fn example_value(root: &Value, property: &Value) -> Value {
+    if let Some(value) = suggested_value(property) {
+        return value;
+    }
+    // `Option<T>` is described as a choice between `T` and null
+    if let Some(variant) = ["anyOf", "oneOf"]
+        .iter()
+        .filter_map(|keyword| property.get(*keyword).and_then(Value::as_array))
+        .flatten()
+        .map(|variant| resolve(root, variant))
+        .find(|variant| variant.get("type").and_then(Value::as_str) != Some("null"))
+    {
+        return example_value(root, variant);
+    }
+    let kind = match property.get("type") {
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        Some(kind) => kind.as_str(),
+        None => None,
+    };
+    let minimum = property.get("minimum").and_then(Value::as_f64);
+    match kind {
+        Some("string") => Value::from(match property.get("format").and_then(Value::as_str) {
+            Some("decimal") => "0",
+            Some("date-time") => "2024-01-01T00:00:00Z",
+            _ => "example",
+        }),
+        Some("integer") => Value::from(minimum.map_or(0, |minimum| minimum.ceil() as i64)),
+        Some("number") => Value::from(minimum.unwrap_or(0.0)),
+        Some("boolean") => Value::Bool(false),
+        Some("array") => Value::Array(Vec::new()),
+        Some("object") => example_arguments(property),
+        _ => Value::Null,
+    }
+}
+
+/// `schema`, or the definition in `root` it refers to with `$ref`
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+        .unwrap_or(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_required_and_suggested_parameters() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "examples": ["widget"] },
+                "count": { "type": "integer", "minimum": 1.5 },
+                "mode": { "enum": ["fast", "slow"] },
+                "label": { "type": "string" },
+                "since": { "type": ["string", "null"], "format": "date-time" },
+                "limit": { "type": "integer", "default": 10 }
+            },
+            "required": ["name", "count", "since"]
+        });
+        assert_eq!(
+            example_arguments(&schema),
+            json!({
+                "name": "widget",
+                "count": 2,
+                "mode": "fast",
+                "since": "2024-01-01T00:00:00Z",
+                "limit": 10
+            })
+        );
+    }
+
+    #[test]
+    fn follows_references_and_options() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "point": { "$ref": "#/$defs/Point" },
+                "scale": { "anyOf": [{ "type": "null" }, { "type": "number", "minimum": 0.5 }] }
+            },
+            "required": ["point", "scale"],
+            "$defs": {
+                "Point": {
+                    "type": "object",
+                    "properties": { "x": { "type": "number" } },
+                    "required": ["x"]
+                }
+            }
+        });
+        assert_eq!(
+            example_arguments(&schema),
+            json!({ "point": { "x": 0.0 }, "scale": 0.5 })
+        );
+    }
+
+    #[test]
+    fn has_a_ready_request_per_tool() {
+        let tool = Tool {
+            name: "echo".to_string(),
+            description: "Echo a message\nBack to the caller".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "message": { "type": "string" } },
+                "required": ["message"]
+            }),
+            output_schema: None,
+        };
+        assert_eq!(
+            document(&[tool])["tools"][0],
+            json!({
+                "tool": "echo",
+                "description": "Echo a message",
+                "request": {
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": { "name": "echo", "arguments": { "message": "example" } }
+                }
+            })
+        );
+        assert!(matches(&resource().uri));
+    }
+}
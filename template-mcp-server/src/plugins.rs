@@ -0,0 +1,220 @@
+//! Loading tools from plugin libraries
+//!
+//! A plugin is a `cdylib` crate that depends on `template-mcp-server` and
+//! exports its tools with [`declare_plugin!`]:
+//!
+//! ```ignore
+//! fn tools() -> Vec<Box<dyn template_mcp_server::registry::Tool>> {
+//!     vec![Box::new(MyTool)]
+//! }
+//!
+//! template_mcp_server::declare_plugin!(tools);
+//! ```
+//!
+//! When `MCP_PLUGIN_DIR` is set, the backend loads every library in that
+//! directory at startup and registers its tools next to the built-in ones.
+//! Plugins whose ABI version or `template-mcp-server` version differs from the
+//! server's are refused, as are tools whose names are already taken; both are
+//! logged and skipped. Plugins are loaded once and never reloaded.
+//!
+//! # The unsafe boundary
+//!
+//! Tools cross the library boundary as `Vec<Box<dyn Tool>>`, which has no
+//! stable ABI. That is sound only if the plugin and the server agree on the
+//! layout of every type involved, so the loader checks what it can before
+//! touching them:
+//!
+//! - [`PLUGIN_ABI_VERSION`], read through an `extern "C"` function, is bumped
+//!   whenever the plugin interface changes.
+//! - The `template-mcp-server` version the plugin was built against must match
+//!   the server's exactly, since `Tool` and the protocol types come from it.
+//!
+//! What the loader can't check is left to the operator: the plugin must be
+//! built with the same `rustc` and compatible dependency versions as the
+//! server. Beyond that:
+//!
+//! - Loading a library runs its initializers, so only load plugins from a
+//!   directory you trust as much as the server binary itself.
+//! - A plugin's tools point into its code, so a loaded library is never
+//!   unloaded.
+//! - A plugin links its own copy of its dependencies. A plugin's tokio can't
+//!   see the server's runtime, so plugin tools must not use tokio timers or IO
+//!   directly, and its `tracing` events don't reach the server's subscriber.
+//!
+//! Libraries are opened with `dlopen`, so loading only works on Unix.
+//! Elsewhere every plugin fails to load with an error saying so, and the
+//! server starts with its built-in tools.
+
+use crate::registry::Tool;
+use std::ffi::{c_char, CStr};
+use std::path::{Path, PathBuf};
+
+/// Version of the plugin interface, bumped on every incompatible change
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Environment variable naming the directory plugins are loaded from
+pub const PLUGIN_DIR_ENV_VAR: &str = "MCP_PLUGIN_DIR";
+
+#[doc(hidden)]
+pub const CRATE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+const ABI_VERSION_SYMBOL: &str = "template_mcp_plugin_abi_version";
+const CRATE_VERSION_SYMBOL: &str = "template_mcp_plugin_crate_version";
+const TOOLS_SYMBOL: &str = "template_mcp_plugin_tools";
+
+/// Export `$tools: fn() -> Vec<Box<dyn Tool>>` as this library's plugin tools
+#[macro_export]
+macro_rules! declare_plugin {
+    ($tools:path) => {
+        #[no_mangle]
+        pub extern "C" fn template_mcp_plugin_abi_version() -> u32 {
+            $crate::plugins::PLUGIN_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn template_mcp_plugin_crate_version() -> *const ::std::ffi::c_char {
+            $crate::plugins::CRATE_VERSION.as_ptr().cast()
+        }
+
+        #[no_mangle]
+        pub fn template_mcp_plugin_tools(
+        ) -> ::std::vec::Vec<::std::boxed::Box<dyn $crate::registry::Tool>> {
+            $tools()
+        }
+    };
+}
+
+/// Tools loaded from one plugin library
+pub struct LoadedPlugin {
+    pub path: PathBuf,
+    pub tools: Vec<Box<dyn Tool>>,
+}
+
+/// Load the tools of the plugin library at `path`
+///
+/// # Safety
+///
+/// Loading runs the library's initializers, and its tools are trusted to match
+/// the server's `Tool` layout; see the [module docs](self). The library must
+/// come from a trusted source and be built with the server's `rustc`.
+pub unsafe fn load_plugin(path: &Path) -> anyhow::Result<LoadedPlugin> {
+    let library = dl::open(path)?;
+
+    // SAFETY: the symbol is exported by `declare_plugin!` with this signature
+    let abi_version: extern "C" fn() -> u32 =
+        unsafe { std::mem::transmute(dl::symbol(library, ABI_VERSION_SYMBOL)?) };
+    let abi_version = abi_version();
+    if abi_version != PLUGIN_ABI_VERSION {
+        anyhow::bail!(
+            "plugin ABI version {abi_version} is incompatible with the server's {PLUGIN_ABI_VERSION}"
+        );
+    }
+
+    // SAFETY: the ABI version matches, so this symbol has this signature and
+    // returns a pointer to a NUL-terminated static string
+    let crate_version: extern "C" fn() -> *const c_char =
+        unsafe { std::mem::transmute(dl::symbol(library, CRATE_VERSION_SYMBOL)?) };
+    let crate_version = unsafe { CStr::from_ptr(crate_version()) }.to_string_lossy();
+    let server_version = env!("CARGO_PKG_VERSION");
+    if crate_version != server_version {
+        anyhow::bail!(
+            "plugin was built against template-mcp-server {crate_version}, the server is {server_version}"
+        );
+    }
+
+    // SAFETY: both versions match, so `Tool` has the same layout on both sides;
+    // the caller guarantees the same compiler
+    let tools: fn() -> Vec<Box<dyn Tool>> =
+        unsafe { std::mem::transmute(dl::symbol(library, TOOLS_SYMBOL)?) };
+    Ok(LoadedPlugin {
+        path: path.to_path_buf(),
+        tools: tools(),
+    })
+}
+
+/// Load every plugin library in `dir`, in file name order
+///
+/// Plugins that fail to load are logged and skipped.
+///
+/// # Safety
+///
+/// Every library in `dir` is loaded with [`load_plugin`] and must meet its
+/// requirements.
+pub unsafe fn load_dir(dir: &Path) -> anyhow::Result<Vec<LoadedPlugin>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some(std::env::consts::DLL_EXTENSION.as_ref()))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut plugins = Vec::new();
+    for path in paths {
+        // SAFETY: forwarded to the caller
+        match unsafe { load_plugin(&path) } {
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => tracing::warn!("Skipping plugin {}: {e}", path.display()),
+        }
+    }
+    Ok(plugins)
+}
+
+#[cfg(unix)]
+mod dl {
+    use std::ffi::{c_void, CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Open a library that stays loaded for the rest of the process
+    ///
+    /// The handle is deliberately never passed to `dlclose`.
+    pub(super) unsafe fn open(path: &Path) -> anyhow::Result<*mut c_void> {
+        let path_c = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: `path_c` is NUL-terminated; running the library's initializers
+        // is the caller's responsibility
+        let handle = unsafe { libc::dlopen(path_c.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            anyhow::bail!("failed to load {}: {}", path.display(), last_error());
+        }
+        Ok(handle)
+    }
+
+    pub(super) fn symbol(library: *mut c_void, name: &str) -> anyhow::Result<*mut c_void> {
+        let name_c = CString::new(name)?;
+        // SAFETY: `library` is a live handle from `open`, never closed
+        let symbol = unsafe { libc::dlsym(library, name_c.as_ptr()) };
+        if symbol.is_null() {
+            anyhow::bail!("missing symbol {name}; is the library a plugin?");
+        }
+        Ok(symbol)
+    }
+
+    fn last_error() -> String {
+        // SAFETY: `dlerror` returns null or a NUL-terminated string valid until the
+        // next `dl*` call on this thread, and it is copied before then
+        let error = unsafe { libc::dlerror() };
+        if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            unsafe { CStr::from_ptr(error) }
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod dl {
+    use std::ffi::c_void;
+    use std::path::Path;
+
+    pub(super) unsafe fn open(path: &Path) -> anyhow::Result<*mut c_void> {
+        anyhow::bail!(
+            "failed to load {}: plugins are only supported on Unix",
+            path.display()
+        )
+    }
+
+    pub(super) fn symbol(_library: *mut c_void, name: &str) -> anyhow::Result<*mut c_void> {
+        anyhow::bail!("missing symbol {name}: plugins are only supported on Unix")
+    }
+}
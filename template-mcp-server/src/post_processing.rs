@@ -0,0 +1,139 @@
+//! Rewriting responses before they leave the server
+//!
+//! Post-processors registered with
+//! [`TemplateBackend::with_post_processor`](crate::TemplateBackend::with_post_processor)
+//! see every tool result and resource read as JSON, after all other result
+//! handling, and may change it in place. This keeps output sanitization, such
+//! as masking email addresses or access tokens, in one place instead of in
+//! every tool. Recordings contain the processed responses.
+//!
+//! [`RegexRedactor`] replaces matches of a pattern in every string of a
+//! response, including text content holding rendered JSON.
+
+use regex::Regex;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Rewrites responses in place
+pub trait ResponsePostProcessor: Send + Sync {
+    /// Process the response to a call of `tool`, or to a read of a resource,
+    /// in which case `tool` is the resource URI
+    fn process(&self, tool: &str, value: &mut Value);
+}
+
+/// Replaces matches of a pattern in every string of a response
+#[derive(Debug, Clone)]
+pub struct RegexRedactor {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl RegexRedactor {
+    /// Replace matches of `pattern` with `replacement`, which may refer to
+    /// capture groups as `$1` or `$name`
+    pub fn new(pattern: &str, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.into(),
+        })
+    }
+
+    /// Mask email addresses as `[redacted email]`
+    pub fn emails() -> Self {
+        Self::new(
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            "[redacted email]",
+        )
+        .expect("email pattern is valid")
+    }
+
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                if let std::borrow::Cow::Owned(redacted) =
+                    self.pattern.replace_all(text, self.replacement.as_str())
+                {
+                    *text = redacted;
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.redact(field)),
+            _ => {}
+        }
+    }
+}
+
+impl ResponsePostProcessor for RegexRedactor {
+    fn process(&self, _tool: &str, value: &mut Value) {
+        self.redact(value);
+    }
+}
+
+/// Run `processors` over `response`, a response for `tool`
+pub fn apply<T: Serialize + DeserializeOwned>(
+    processors: &[Arc<dyn ResponsePostProcessor>],
+    tool: &str,
+    response: T,
+) -> Result<T, String> {
+    if processors.is_empty() {
+        return Ok(response);
+    }
+    let mut value =
+        serde_json::to_value(response).map_err(|e| format!("Failed to serialize result: {e}"))?;
+    for processor in processors {
+        processor.process(tool, &mut value);
+    }
+    serde_json::from_value(value).map_err(|e| format!("Post-processing broke the result: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_emails_in_every_string() {
+        let processors: Vec<Arc<dyn ResponsePostProcessor>> =
+            vec![Arc::new(RegexRedactor::emails())];
+        let response = json!({
+            "content": [{ "type": "text", "text": "{\"owner\":\"ada@example.com\"}" }],
+            "contacts": ["bob@example.org", "no email here"],
+            "count": 2
+        });
+        let processed = apply(&processors, "list_data", response).unwrap();
+        assert_eq!(
+            processed,
+            json!({
+                "content": [{ "type": "text", "text": "{\"owner\":\"[redacted email]\"}" }],
+                "contacts": ["[redacted email]", "no email here"],
+                "count": 2
+            })
+        );
+    }
+
+    #[test]
+    fn replacements_may_refer_to_groups() {
+        let redactor = RegexRedactor::new(r"token=(\w{2})\w+", "token=$1…").unwrap();
+        let mut value = json!("token=abcdef");
+        redactor.process("echo", &mut value);
+        assert_eq!(value, json!("token=ab…"));
+        assert!(RegexRedactor::new("(", "").is_err());
+    }
+
+    #[test]
+    fn reports_results_processing_broke() {
+        struct Nulling;
+        impl ResponsePostProcessor for Nulling {
+            fn process(&self, _tool: &str, value: &mut Value) {
+                *value = Value::Null;
+            }
+        }
+        let processors: Vec<Arc<dyn ResponsePostProcessor>> = vec![Arc::new(Nulling)];
+        let broken = apply(&processors, "echo", vec![1]).unwrap_err();
+        assert!(
+            broken.starts_with("Post-processing broke the result"),
+            "{broken}"
+        );
+    }
+}
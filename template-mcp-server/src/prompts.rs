@@ -0,0 +1,303 @@
+//! Prompts composed with live resource content
+//!
+//! `#[mcp_tools]` doesn't collect `#[mcp_prompt]` methods in 0.11, so prompts
+//! are declared in [`PROMPTS`] and served by the backend. A prompt is a list
+//! of user messages, each either fixed text or [`PromptPart::Resource`], a
+//! resource embedded by URI. The backend reads embedded resources when the
+//! prompt is fetched with `prompts/get`, so the prompt carries their current
+//! content.
+//!
+//! A prompt may take arguments, declared as a struct shared with a tool (see
+//! [`crate::shared_args`]), and embed [`PromptPart::Tool`], the result of
+//! that tool called with the prompt's arguments.
+//!
+//! The 0.11 protocol types have no embedded-resource content for prompt
+//! messages, so an embedded resource is sent as a text message holding its
+//! text. Resources with only a binary `blob` can't be embedded.
+
+use crate::backend::TemplateError;
+use crate::shared_args::PromptArgs;
+use crate::DataFilter;
+use pulseengine_mcp_protocol::{
+    CallToolResult, Content, GetPromptResult, Prompt, PromptMessage, PromptMessageContent,
+    PromptMessageRole, ReadResourceResult,
+};
+use pulseengine_mcp_server::CommonMcpError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One message of a prompt
+#[derive(Debug, Clone, Copy)]
+pub enum PromptPart {
+    Text(&'static str),
+    /// The text of the resource with this URI, read when the prompt is fetched
+    Resource(&'static str),
+    /// The text of the result of this tool, called with the prompt's
+    /// arguments when the prompt is fetched
+    Tool(&'static str),
+}
+
+/// A prompt served next to the generated ones
+#[derive(Debug, Clone, Copy)]
+pub struct PromptDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub messages: &'static [PromptPart],
+    pub arguments: Option<PromptArgs>,
+}
+
+/// Prompts served by the backend
+pub const PROMPTS: &[PromptDef] = &[
+    PromptDef {
+        name: "diagnose_server",
+        description: "Ask for a diagnosis of the server's current status",
+        messages: &[
+            PromptPart::Text("Here is the current status of the server:"),
+            PromptPart::Resource("template://server-status"),
+            PromptPart::Text(
+                "Point out anything that needs attention, such as an open circuit breaker, \
+                 a component that isn't healthy or connections close to the limit.",
+            ),
+        ],
+        arguments: None,
+    },
+    PromptDef {
+        name: "review_data",
+        description: "Ask for a review of the stored example data matching a filter",
+        messages: &[
+            PromptPart::Text("Here are the stored example data entries to review:"),
+            PromptPart::Tool("filter_data"),
+            PromptPart::Text(
+                "Point out entries that look wrong, such as duplicate names, \
+                 outlying values or missing tags.",
+            ),
+        ],
+        arguments: Some(PromptArgs::of::<DataFilter>()),
+    },
+];
+
+pub fn list() -> impl Iterator<Item = Prompt> {
+    PROMPTS.iter().map(|prompt| Prompt {
+        name: prompt.name.to_string(),
+        description: Some(prompt.description.to_string()),
+        arguments: prompt.arguments.map(|arguments| (arguments.list)()),
+    })
+}
+
+pub fn find(name: &str) -> Option<&'static PromptDef> {
+    PROMPTS.iter().find(|prompt| prompt.name == name)
+}
+
+/// Build `prompt` with `arguments`, reading embedded resources with `read`
+/// and calling embedded tools with `call`
+pub async fn resolve<F, Fut, C, CallFut>(
+    prompt: &PromptDef,
+    arguments: Option<&HashMap<String, String>>,
+    mut read: F,
+    mut call: C,
+) -> Result<GetPromptResult, TemplateError>
+where
+    F: FnMut(&'static str) -> Fut,
+    Fut: std::future::Future<Output = Result<ReadResourceResult, TemplateError>>,
+    C: FnMut(&'static str, Value) -> CallFut,
+    CallFut: std::future::Future<Output = Result<CallToolResult, TemplateError>>,
+{
+    let no_arguments = HashMap::new();
+    let arguments = arguments.unwrap_or(&no_arguments);
+    let arguments = match prompt.arguments {
+        Some(declared) => (declared.parse)(arguments).map_err(|reason| {
+            CommonMcpError::InvalidParams(format!(
+                "Invalid arguments for prompt '{}': {reason}",
+                prompt.name
+            ))
+        })?,
+        None if arguments.is_empty() => Value::Object(Default::default()),
+        None => {
+            return Err(CommonMcpError::InvalidParams(format!(
+                "Prompt '{}' takes no arguments",
+                prompt.name
+            ))
+            .into())
+        }
+    };
+    let mut messages = Vec::with_capacity(prompt.messages.len());
+    for part in prompt.messages {
+        let text = match *part {
+            PromptPart::Text(text) => text.to_string(),
+            PromptPart::Resource(uri) => {
+                let result = read(uri).await?;
+                let texts: Vec<_> = result
+                    .contents
+                    .into_iter()
+                    .filter_map(|content| content.text)
+                    .collect();
+                if texts.is_empty() {
+                    return Err(CommonMcpError::Internal(format!(
+                        "Resource {uri} has no text to embed in a prompt"
+                    ))
+                    .into());
+                }
+                texts.join("\n")
+            }
+            PromptPart::Tool(tool) => {
+                let result = call(tool, arguments.clone()).await?;
+                let texts: Vec<_> = result
+                    .content
+                    .into_iter()
+                    .filter_map(|content| match content {
+                        Content::Text { text } => Some(text),
+                        _ => None,
+                    })
+                    .collect();
+                if result.is_error == Some(true) {
+                    return Err(CommonMcpError::Internal(format!(
+                        "Tool {tool} failed while building the prompt: {}",
+                        texts.join("\n")
+                    ))
+                    .into());
+                }
+                texts.join("\n")
+            }
+        };
+        messages.push(PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::Text { text },
+        });
+    }
+    Ok(GetPromptResult {
+        description: Some(prompt.description.to_string()),
+        messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulseengine_mcp_protocol::ResourceContents;
+    use serde_json::json;
+
+    fn read_text(uri: &'static str) -> ReadResourceResult {
+        ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: uri.to_string(),
+                mime_type: None,
+                text: Some(format!("content of {uri}")),
+                blob: None,
+            }],
+        }
+    }
+
+    fn texts(result: &GetPromptResult) -> Vec<&str> {
+        result
+            .messages
+            .iter()
+            .map(|message| match &message.content {
+                PromptMessageContent::Text { text } => text.as_str(),
+                other => panic!("not text: {other:?}"),
+            })
+            .collect()
+    }
+
+    fn arguments(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_every_prompt() {
+        let names: Vec<String> = list().map(|prompt| prompt.name).collect();
+        assert_eq!(names, ["diagnose_server", "review_data"]);
+        assert!(find("review_data").is_some());
+        assert!(find("missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn embeds_current_resource_content() {
+        let prompt = find("diagnose_server").unwrap();
+        let result = resolve(
+            prompt,
+            None,
+            |uri| async move { Ok(read_text(uri)) },
+            |_, _| async { unreachable!("no tool is embedded") },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            texts(&result)[..2],
+            [
+                "Here is the current status of the server:",
+                "content of template://server-status"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn calls_embedded_tools_with_the_arguments() {
+        let prompt = find("review_data").unwrap();
+        let result = resolve(
+            prompt,
+            Some(&arguments(&[("min_value", "2")])),
+            |_| async { unreachable!("no resource is embedded") },
+            |tool, arguments| async move {
+                assert_eq!(tool, "filter_data");
+                assert_eq!(arguments["min_value"], json!(2.0));
+                Ok(CallToolResult::text("2 entries"))
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(texts(&result)[1], "2 entries");
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_arguments() {
+        let unexpected = resolve(
+            find("diagnose_server").unwrap(),
+            Some(&arguments(&[("tag", "a")])),
+            |uri| async move { Ok(read_text(uri)) },
+            |_, _| async { Ok(CallToolResult::text("")) },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            unexpected.to_string(),
+            CommonMcpError::InvalidParams("Prompt 'diagnose_server' takes no arguments".into())
+                .to_string()
+        );
+
+        let mistyped = resolve(
+            find("review_data").unwrap(),
+            Some(&arguments(&[("min_value", "lots")])),
+            |uri| async move { Ok(read_text(uri)) },
+            |_, _| async { Ok(CallToolResult::text("")) },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            mistyped
+                .to_string()
+                .contains("Invalid arguments for prompt 'review_data'"),
+            "{mistyped}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_when_an_embedded_tool_fails() {
+        let error = resolve(
+            find("review_data").unwrap(),
+            None,
+            |uri| async move { Ok(read_text(uri)) },
+            |_, _| async { Ok(CallToolResult::error_text("store offline")) },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Tool filter_data failed while building the prompt: store offline"),
+            "{error}"
+        );
+    }
+}
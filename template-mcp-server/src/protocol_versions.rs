@@ -0,0 +1,223 @@
+//! Protocol version negotiation
+//!
+//! The server speaks the MCP revisions in [`SUPPORTED_PROTOCOL_VERSIONS`]. In
+//! `initialize`, the client names the version it wants; the server answers
+//! with that version if it supports it, else the newest supported version
+//! older than it, else its latest (the client then decides whether to go on).
+//! For the rest of the session, [`adapt_result`] removes from every result the
+//! fields the negotiated revision doesn't define, listed in
+//! [`VERSIONED_FIELDS`], so a client pinned to `2024-11-05` gets tools without
+//! `outputSchema` and tool results as text content only. Those results keep
+//! the data of their structured content as an extra text content item with
+//! its JSON, next to the readable text (see [`crate::structured`]).
+//! Everything else is the same in every supported revision.
+//!
+//! The framework's `initialize` handler always answers with its own latest
+//! version, so negotiation happens in
+//! [`RawMessageHandler`](crate::raw_messages::RawMessageHandler), which serves
+//! STDIO. HTTP clients always get the latest version.
+
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+/// Protocol revisions the server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+pub const LATEST_PROTOCOL_VERSION: &str = SUPPORTED_PROTOCOL_VERSIONS[0];
+
+/// A result field that only exists from protocol version `since` on
+#[derive(Debug, Clone, Copy)]
+pub struct VersionedField {
+    pub since: &'static str,
+    pub method: &'static str,
+    /// Keys from the result down to the field; `*` stands for every item of
+    /// an array
+    pub path: &'static [&'static str],
+}
+
+/// Result fields removed for clients on older protocol versions
+///
+/// Add an entry when the server starts sending a field from a newer revision.
+pub const VERSIONED_FIELDS: &[VersionedField] = &[
+    VersionedField {
+        since: "2025-06-18",
+        method: "initialize",
+        path: &["capabilities", "elicitation"],
+    },
+    VersionedField {
+        since: "2025-06-18",
+        method: "tools/list",
+        path: &["tools", "*", "outputSchema"],
+    },
+    VersionedField {
+        since: STRUCTURED_CONTENT_SINCE,
+        method: "tools/call",
+        path: &["structuredContent"],
+    },
+];
+
+/// The version to answer a client requesting `requested` with
+pub fn negotiate(requested: &str) -> &'static str {
+    // Versions are dates, so they order as strings
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .copied()
+        .find(|version| *version <= requested)
+        .unwrap_or(LATEST_PROTOCOL_VERSION)
+}
+
+/// Remove the fields of a `method` result that `version` doesn't define
+pub fn adapt_result(version: &str, method: &str, result: &mut Value) {
+    if method == "initialize" {
+        if let Some(result) = result.as_object_mut() {
+            result.insert("protocolVersion".to_string(), Value::from(version));
+        }
+    }
+    if method == "tools/call" && version < STRUCTURED_CONTENT_SINCE {
+        keep_structured_content_as_text(result);
+    }
+    for field in VERSIONED_FIELDS {
+        if field.method == method && version < field.since {
+            remove(result, field.path);
+        }
+    }
+}
+
+/// First version with `structuredContent` in tool results
+const STRUCTURED_CONTENT_SINCE: &str = "2025-06-18";
+
+/// Add the JSON of a tool result's structured content as a text content item,
+/// unless the text content already is that JSON
+fn keep_structured_content_as_text(result: &mut Value) {
+    let Some(structured) = result.get("structuredContent").cloned() else {
+        return;
+    };
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let carried = content.iter().any(|item| {
+        item.get("text")
+            .and_then(Value::as_str)
+            .and_then(|text| serde_json::from_str::<Value>(text).ok())
+            .is_some_and(|text| text == structured)
+    });
+    if !carried {
+        let json = structured.to_string();
+        content.push(serde_json::json!({ "type": "text", "text": json }));
+    }
+}
+
+fn remove(value: &mut Value, path: &[&str]) {
+    match (path, value) {
+        ([key], Value::Object(fields)) => {
+            fields.remove(*key);
+        }
+        (["*", rest @ ..], Value::Array(items)) => {
+            for item in items {
+                remove(item, rest);
+            }
+        }
+        ([key, rest @ ..], Value::Object(fields)) => {
+            if let Some(value) = fields.get_mut(*key) {
+                remove(value, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The version negotiated in a session's `initialize`, the latest until then
+#[derive(Debug, Clone)]
+pub struct NegotiatedVersion(Arc<RwLock<&'static str>>);
+
+impl Default for NegotiatedVersion {
+    fn default() -> Self {
+        Self(Arc::new(RwLock::new(LATEST_PROTOCOL_VERSION)))
+    }
+}
+
+impl NegotiatedVersion {
+    pub fn get(&self) -> &'static str {
+        *self
+            .0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Negotiate with a client requesting `requested`
+    pub fn negotiate(&self, requested: &str) -> &'static str {
+        let version = negotiate(requested);
+        *self
+            .0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = version;
+        version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn negotiates_the_newest_version_not_after_the_request() {
+        assert_eq!(negotiate("2025-03-26"), "2025-03-26");
+        assert_eq!(negotiate("2025-05-01"), "2025-03-26");
+        assert_eq!(negotiate("2099-01-01"), LATEST_PROTOCOL_VERSION);
+        assert_eq!(negotiate("2020-01-01"), LATEST_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn the_session_keeps_the_negotiated_version() {
+        let version = NegotiatedVersion::default();
+        assert_eq!(version.get(), LATEST_PROTOCOL_VERSION);
+        assert_eq!(version.clone().negotiate("2024-11-05"), "2024-11-05");
+        assert_eq!(version.get(), "2024-11-05");
+    }
+
+    #[test]
+    fn removes_fields_of_newer_revisions() {
+        let tools = json!({ "tools": [{ "name": "echo", "outputSchema": {} }, { "name": "add" }] });
+        let mut adapted = tools.clone();
+        adapt_result("2025-03-26", "tools/list", &mut adapted);
+        assert_eq!(
+            adapted,
+            json!({ "tools": [{ "name": "echo" }, { "name": "add" }] })
+        );
+        let mut latest = tools.clone();
+        adapt_result(LATEST_PROTOCOL_VERSION, "tools/list", &mut latest);
+        assert_eq!(latest, tools);
+
+        let mut initialize =
+            json!({ "protocolVersion": "x", "capabilities": { "elicitation": {}, "tools": {} } });
+        adapt_result("2024-11-05", "initialize", &mut initialize);
+        assert_eq!(
+            initialize,
+            json!({ "protocolVersion": "2024-11-05", "capabilities": { "tools": {} } })
+        );
+    }
+
+    #[test]
+    fn keeps_structured_content_as_text() {
+        let mut result = json!({
+            "content": [{ "type": "text", "text": "Sum is 5" }],
+            "structuredContent": { "sum": 5 }
+        });
+        adapt_result("2025-03-26", "tools/call", &mut result);
+        assert_eq!(
+            result,
+            json!({ "content": [
+                { "type": "text", "text": "Sum is 5" },
+                { "type": "text", "text": "{\"sum\":5}" }
+            ] })
+        );
+
+        let mut carried = json!({
+            "content": [{ "type": "text", "text": "{ \"sum\": 5 }" }],
+            "structuredContent": { "sum": 5 }
+        });
+        adapt_result("2025-03-26", "tools/call", &mut carried);
+        assert_eq!(carried["content"].as_array().unwrap().len(), 1);
+    }
+}
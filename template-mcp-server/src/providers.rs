@@ -0,0 +1,309 @@
+//! Shared subsystems initialized in dependency order
+//!
+//! Larger servers have subsystems, such as a database pool or a cache, that
+//! tools share and that must start in a particular order. Each is registered
+//! with [`ProvidersBuilder::provide`] under a name, with the names it depends
+//! on and an async function creating it. [`ProvidersBuilder::build`] runs
+//! them one at a time, each after its dependencies, which it can get from the
+//! [`Providers`] it is passed. The first provider that fails to initialize
+//! aborts the build with [`ProviderError::Failed`], as do unknown dependencies
+//! and dependency cycles, before anything runs.
+//!
+//! The macros don't pass a request context to tools, so the built providers
+//! are installed on the server with `TemplateMcpServer::set_providers`, and
+//! tools get them with `self.provider::<T>(name)`.
+//!
+//! State a server holds one of, such as its HTTP client, can be provided by
+//! type instead, with [`ProvidersBuilder::provide_state`], and tools get it
+//! with `self.state::<T>()`. This is the app state pattern of web frameworks,
+//! except that the 0.11 macros turn every method parameter into a tool
+//! argument: a `State<T>` parameter would be in the tool's schema, so tools
+//! take state from `self` and their schemas stay as they are.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type Instance = Arc<dyn Any + Send + Sync>;
+type InitFuture = Pin<Box<dyn Future<Output = anyhow::Result<Instance>> + Send>>;
+
+/// Why providers couldn't be built
+#[derive(Debug)]
+pub enum ProviderError {
+    /// Two providers were registered under the same name
+    Duplicate(String),
+    UnknownDependency {
+        provider: String,
+        dependency: String,
+    },
+    /// The named providers can't be ordered because of a dependency cycle
+    /// among them
+    Cycle(Vec<String>),
+    Failed {
+        provider: String,
+        error: anyhow::Error,
+    },
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Duplicate(name) => write!(f, "provider '{name}' is registered twice"),
+            Self::UnknownDependency {
+                provider,
+                dependency,
+            } => write!(
+                f,
+                "provider '{provider}' depends on '{dependency}', which isn't registered"
+            ),
+            Self::Cycle(names) => write!(
+                f,
+                "providers have a dependency cycle among them: {}",
+                names.join(", ")
+            ),
+            Self::Failed { provider, error } => {
+                write!(f, "failed to initialize provider '{provider}': {error:#}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Initialized providers by name
+#[derive(Clone, Default)]
+pub struct Providers {
+    instances: HashMap<String, Instance>,
+    init_order: Vec<String>,
+}
+
+impl Providers {
+    pub fn builder() -> ProvidersBuilder {
+        ProvidersBuilder::new()
+    }
+
+    /// The provider named `name`, if it exists and is a `T`
+    pub fn get<T: Send + Sync + 'static>(&self, name: &str) -> Option<Arc<T>> {
+        self.instances.get(name)?.clone().downcast().ok()
+    }
+
+    /// The state of type `T`, if provided
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.get(state_name::<T>())
+    }
+
+    /// Provider names in the order they were initialized
+    pub fn init_order(&self) -> &[String] {
+        &self.init_order
+    }
+}
+
+impl std::fmt::Debug for Providers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Providers")
+            .field("init_order", &self.init_order)
+            .finish()
+    }
+}
+
+struct Registration {
+    name: String,
+    depends_on: Vec<String>,
+    init: Box<dyn FnOnce(Providers) -> InitFuture + Send>,
+}
+
+/// Registers providers, then initializes them in dependency order
+#[derive(Default)]
+pub struct ProvidersBuilder {
+    registrations: Vec<Registration>,
+}
+
+impl ProvidersBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `init` as provider `name`, to run after the providers named
+    /// in `depends_on`
+    pub fn provide<T, F, Fut>(mut self, name: &str, depends_on: &[&str], init: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(Providers) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        self.registrations.push(Registration {
+            name: name.to_string(),
+            depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+            init: Box::new(move |providers| {
+                Box::pin(async move { Ok(Arc::new(init(providers).await?) as Instance) })
+            }),
+        });
+        self
+    }
+
+    /// Register `init` as the state of type `T`, to run after the providers
+    /// named in `depends_on`
+    ///
+    /// The state is a provider named [`state_name`] of `T`, so other providers
+    /// can depend on it by that name.
+    pub fn provide_state<T, F, Fut>(self, depends_on: &[&str], init: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(Providers) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        self.provide(state_name::<T>(), depends_on, init)
+    }
+
+    /// Initialize every provider, dependencies first
+    ///
+    /// Providers that don't depend on each other run in registration order.
+    pub async fn build(self) -> Result<Providers, ProviderError> {
+        let mut providers = Providers::default();
+        for registration in order(self.registrations)? {
+            let instance = (registration.init)(providers.clone())
+                .await
+                .map_err(|error| ProviderError::Failed {
+                    provider: registration.name.clone(),
+                    error,
+                })?;
+            tracing::debug!(provider = %registration.name, "Provider initialized");
+            providers
+                .instances
+                .insert(registration.name.clone(), instance);
+            providers.init_order.push(registration.name);
+        }
+        Ok(providers)
+    }
+}
+
+/// Name of the provider holding the state of type `T`
+pub fn state_name<T: 'static>() -> &'static str {
+    std::any::type_name::<T>()
+}
+
+/// `registrations` sorted so every provider comes after its dependencies
+fn order(mut pending: Vec<Registration>) -> Result<Vec<Registration>, ProviderError> {
+    for (index, registration) in pending.iter().enumerate() {
+        if pending[..index]
+            .iter()
+            .any(|earlier| earlier.name == registration.name)
+        {
+            return Err(ProviderError::Duplicate(registration.name.clone()));
+        }
+        if let Some(dependency) = registration
+            .depends_on
+            .iter()
+            .find(|dependency| !pending.iter().any(|other| &other.name == *dependency))
+        {
+            return Err(ProviderError::UnknownDependency {
+                provider: registration.name.clone(),
+                dependency: dependency.clone(),
+            });
+        }
+    }
+
+    let mut ordered: Vec<Registration> = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let Some(index) = pending.iter().position(|registration| {
+            registration
+                .depends_on
+                .iter()
+                .all(|dependency| ordered.iter().any(|done| &done.name == dependency))
+        }) else {
+            let names = pending.iter().map(|r| r.name.clone()).collect();
+            return Err(ProviderError::Cycle(names));
+        };
+        ordered.push(pending.remove(index));
+    }
+    Ok(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pool {
+        size: usize,
+    }
+
+    struct Cache {
+        pool_size: usize,
+    }
+
+    #[tokio::test]
+    async fn builds_dependencies_first() {
+        let providers = Providers::builder()
+            .provide("cache", &["pool"], |providers| async move {
+                let pool = providers
+                    .get::<Pool>("pool")
+                    .expect("the pool is built first");
+                Ok(Cache {
+                    pool_size: pool.size,
+                })
+            })
+            .provide("pool", &[], |_| async { Ok(Pool { size: 4 }) })
+            .provide_state(&[], |_| async { Ok(String::from("state")) })
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            providers.init_order(),
+            ["pool", "cache", state_name::<String>()]
+        );
+        assert_eq!(providers.get::<Cache>("cache").unwrap().pool_size, 4);
+        assert!(providers.get::<Pool>("cache").is_none());
+        assert_eq!(*providers.state::<String>().unwrap(), "state");
+    }
+
+    async fn error_of(builder: ProvidersBuilder) -> String {
+        builder.build().await.unwrap_err().to_string()
+    }
+
+    #[tokio::test]
+    async fn refuses_bad_registrations_before_running_any() {
+        let ran = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = ran.clone();
+        let unknown = Providers::builder()
+            .provide("pool", &[], move |_| async move {
+                flag.store(true, std::sync::atomic::Ordering::Relaxed);
+                Ok(())
+            })
+            .provide("cache", &["db"], |_| async { Ok(()) });
+        assert_eq!(
+            error_of(unknown).await,
+            "provider 'cache' depends on 'db', which isn't registered"
+        );
+        assert!(!ran.load(std::sync::atomic::Ordering::Relaxed));
+
+        let duplicate = Providers::builder()
+            .provide("pool", &[], |_| async { Ok(()) })
+            .provide("pool", &[], |_| async { Ok(()) });
+        assert_eq!(
+            error_of(duplicate).await,
+            "provider 'pool' is registered twice"
+        );
+
+        let cycle = Providers::builder()
+            .provide("a", &["b"], |_| async { Ok(()) })
+            .provide("b", &["a"], |_| async { Ok(()) });
+        assert_eq!(
+            error_of(cycle).await,
+            "providers have a dependency cycle among them: a, b"
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_failure() {
+        let failing = Providers::builder()
+            .provide::<(), _, _>("pool", &[], |_| async { anyhow::bail!("no database") })
+            .provide::<(), _, _>("cache", &["pool"], |_| async { unreachable!() });
+        assert_eq!(
+            error_of(failing).await,
+            "failed to initialize provider 'pool': no database"
+        );
+    }
+}
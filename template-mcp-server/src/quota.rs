@@ -0,0 +1,252 @@
+//! Per-client daily call quotas
+//!
+//! Every `tools/call` that runs its tool counts against the calling client's
+//! quota for the current day. Calls refused before then, for access, a
+//! pending confirmation or bad arguments, don't count. Days start at `LimitsConfig::quota_reset_hour_utc`, and once a
+//! client has used `LimitsConfig::daily_call_quota` calls, further calls fail
+//! with a `quota_exceeded` error until the next reset. Current usage is served
+//! as the `template://quota/{client}` resource, to the client itself and to
+//! admin clients; see [`crate::admin`].
+//!
+//! With authentication, calls count against the identity it established: the
+//! user, else the API key, of the [`AuthContext`] handed to
+//! [`TemplateBackend::with_auth_context`](crate::TemplateBackend::with_auth_context),
+//! and [`ANONYMOUS_CLIENT`] for a context with neither. The client id can't
+//! move calls to another quota then. The 0.11 transports don't pass the
+//! context to backends, so an embedder that authenticates its connections
+//! sets it per connection. Without authentication, the client is the one
+//! named by `MCP_CLIENT_ID`, or
+//! [`TemplateBackend::with_client_id`](crate::TemplateBackend::with_client_id)
+//! when embedding the backend.
+
+use crate::clock::{Clock, SystemClock};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use pulseengine_mcp_server::auth::AuthContext;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Environment variable naming the client whose quota is used
+pub const CLIENT_ID_ENV_VAR: &str = "MCP_CLIENT_ID";
+
+/// Client ID used when none is configured
+pub const ANONYMOUS_CLIENT: &str = "anonymous";
+
+/// The client an authenticated connection's calls count against
+pub fn authenticated_client(context: &AuthContext) -> &str {
+    context
+        .user_id
+        .as_deref()
+        .or(context.api_key_id.as_deref())
+        .unwrap_or(ANONYMOUS_CLIENT)
+}
+
+/// Quota limits, read from the server configuration on every call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaPolicy {
+    /// Calls allowed per client per day; `None` means unlimited
+    pub daily_limit: Option<u64>,
+    /// UTC hour (0-23) at which the daily counters reset
+    pub reset_hour_utc: u32,
+}
+
+/// A client's usage in the current quota day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuotaUsage {
+    pub client: String,
+    pub limit: Option<u64>,
+    pub used: u64,
+    /// `None` when the quota is unlimited
+    pub remaining: Option<u64>,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// A call rejected because the client's quota is used up
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaExceeded {
+    pub usage: QuotaUsage,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "quota_exceeded: client '{}' has used all {} calls for today; quota resets at {}",
+            self.usage.client,
+            self.usage.used,
+            self.usage.resets_at.to_rfc3339()
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Counts calls per client per quota day
+pub struct QuotaTracker {
+    clock: Arc<dyn Clock>,
+    counters: Mutex<HashMap<String, Counter>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Counter {
+    day_start: DateTime<Utc>,
+    used: u64,
+}
+
+impl Default for QuotaTracker {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+impl QuotaTracker {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            counters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count one call by `client`, or reject it if the quota is used up
+    pub fn try_acquire(
+        &self,
+        client: &str,
+        policy: QuotaPolicy,
+    ) -> Result<QuotaUsage, QuotaExceeded> {
+        let day_start = day_start(self.clock.now(), policy.reset_hour_utc);
+        let mut counters = self.lock();
+        let counter = counters
+            .entry(client.to_string())
+            .or_insert(Counter { day_start, used: 0 });
+        if counter.day_start != day_start {
+            *counter = Counter { day_start, used: 0 };
+        }
+
+        if policy
+            .daily_limit
+            .is_some_and(|limit| counter.used >= limit)
+        {
+            return Err(QuotaExceeded {
+                usage: usage(client, policy, *counter),
+            });
+        }
+        counter.used += 1;
+        Ok(usage(client, policy, *counter))
+    }
+
+    /// Current usage of `client`, without counting a call
+    pub fn usage(&self, client: &str, policy: QuotaPolicy) -> QuotaUsage {
+        let day_start = day_start(self.clock.now(), policy.reset_hour_utc);
+        let used = self
+            .lock()
+            .get(client)
+            .filter(|counter| counter.day_start == day_start)
+            .map_or(0, |counter| counter.used);
+        usage(client, policy, Counter { day_start, used })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Counter>> {
+        self.counters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn usage(client: &str, policy: QuotaPolicy, counter: Counter) -> QuotaUsage {
+    QuotaUsage {
+        client: client.to_string(),
+        limit: policy.daily_limit,
+        used: counter.used,
+        remaining: policy
+            .daily_limit
+            .map(|limit| limit.saturating_sub(counter.used)),
+        resets_at: counter.day_start + Duration::days(1),
+    }
+}
+
+/// Start of the quota day containing `now`
+fn day_start(now: DateTime<Utc>, reset_hour_utc: u32) -> DateTime<Utc> {
+    let reset = NaiveTime::from_hms_opt(reset_hour_utc.min(23), 0, 0).unwrap_or(NaiveTime::MIN);
+    let today = now.date_naive().and_time(reset).and_utc();
+    if now < today {
+        today - Duration::days(1)
+    } else {
+        today
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).unwrap()
+    }
+
+    fn policy(daily_limit: Option<u64>, reset_hour_utc: u32) -> QuotaPolicy {
+        QuotaPolicy {
+            daily_limit,
+            reset_hour_utc,
+        }
+    }
+
+    #[test]
+    fn rejects_calls_over_the_limit() {
+        let quota = QuotaTracker::new(Arc::new(ManualClock::new(at(12, 0))));
+        let policy = policy(Some(2), 0);
+
+        assert_eq!(quota.try_acquire("a", policy).unwrap().remaining, Some(1));
+        assert_eq!(quota.try_acquire("a", policy).unwrap().remaining, Some(0));
+        let exceeded = quota.try_acquire("a", policy).unwrap_err();
+        assert_eq!(exceeded.usage.used, 2);
+        assert!(exceeded.to_string().starts_with("quota_exceeded"));
+
+        // Rejected calls aren't counted, and other clients have their own
+        assert_eq!(quota.usage("a", policy).used, 2);
+        assert!(quota.try_acquire("b", policy).is_ok());
+    }
+
+    #[test]
+    fn resets_at_the_reset_hour() {
+        let clock = Arc::new(ManualClock::new(at(5, 30)));
+        let quota = QuotaTracker::new(clock.clone());
+        let policy = policy(Some(1), 6);
+
+        let usage = quota.try_acquire("a", policy).unwrap();
+        assert_eq!(usage.resets_at, at(6, 0));
+        assert!(quota.try_acquire("a", policy).is_err());
+
+        clock.set(at(6, 0));
+        let usage = quota.try_acquire("a", policy).unwrap();
+        assert_eq!(usage.used, 1);
+        assert_eq!(usage.resets_at, at(6, 0) + Duration::days(1));
+    }
+
+    #[test]
+    fn unlimited_quotas_only_count() {
+        let quota = QuotaTracker::new(Arc::new(ManualClock::new(at(0, 0))));
+        let policy = policy(None, 0);
+        for _ in 0..100 {
+            quota.try_acquire("a", policy).unwrap();
+        }
+        let usage = quota.usage("a", policy);
+        assert_eq!((usage.used, usage.remaining), (100, None));
+    }
+
+    #[test]
+    fn authenticated_clients_are_their_user_then_their_key() {
+        let mut context = AuthContext {
+            user_id: Some("alice".to_string()),
+            roles: Vec::new(),
+            api_key_id: Some("key-1".to_string()),
+            permissions: Vec::new(),
+        };
+        assert_eq!(authenticated_client(&context), "alice");
+        context.user_id = None;
+        assert_eq!(authenticated_client(&context), "key-1");
+        context.api_key_id = None;
+        assert_eq!(authenticated_client(&context), ANONYMOUS_CLIENT);
+    }
+}
@@ -0,0 +1,459 @@
+//! Handling raw JSON-RPC messages
+//!
+//! [`RawMessageHandler`] takes one message as the bytes of a line, the way
+//! the STDIO transport reads it, and returns the line to write back: size and
+//! format validation, parsing, single and batch dispatch through the
+//! framework's request handler, and the error responses for each failure are
+//! the transport's. Bytes that aren't UTF-8, which the transport can't read
+//! at all, get a parse error.
+//!
+//! Unlike the transport, the handler may be called for several messages at
+//! once, so it rejects a request whose id is already in flight; see
+//! [`crate::in_flight`]. It also negotiates the protocol version in
+//! `initialize` and shapes later results for it, see
+//! [`crate::protocol_versions`], and answers nothing but `initialize` and
+//! `ping` until the session is initialized, see [`crate::lifecycle`]. It
+//! records the capabilities the client advertises, which some tools need, see
+//! [`crate::client_capabilities`], and writes the notifications tools send,
+//! see [`crate::client_notifications`]. HTML and NDJSON tool results get
+//! their mime type, see [`crate::html`], tool results report their cost, see
+//! [`crate::costs`], every request is handled under a correlation id, see
+//! [`crate::correlation`], and batches stop or go on after a failed request
+//! per their policy, see [`crate::batches`].
+//!
+//! [`RawMessageHandler::serve_stdio`] serves STDIO with it in place of the
+//! transport. It keeps reading while a request is handled, answering `ping`
+//! at once instead of after the requests before it, and sends the keepalive
+//! pings of [`crate::keepalive`]. The whole path from bytes to response can
+//! also be driven without a process or a pipe, by the fuzz targets in `fuzz/`
+//! and by embedders.
+
+use crate::batches;
+use crate::call_order::CallHistory;
+use crate::client_capabilities::ClientCapabilities;
+use crate::client_notifications::{ClientNotifier, Outgoing};
+use crate::correlation;
+use crate::costs;
+use crate::html;
+use crate::in_flight::InFlightIds;
+use crate::keepalive::Keepalive;
+use crate::lifecycle::Lifecycle;
+use crate::protocol_versions::{self, NegotiatedVersion};
+use crate::resource_wait;
+use crate::{TemplateBackend, TemplateMcpServer};
+use pulseengine_mcp_protocol::{Error, Response};
+use pulseengine_mcp_server::auth::{AuthConfig, AuthenticationManager};
+use pulseengine_mcp_server::{GenericServerHandler, MiddlewareStack};
+use pulseengine_mcp_transport::batch::{self, JsonRpcMessage};
+use pulseengine_mcp_transport::stdio::StdioConfig;
+use pulseengine_mcp_transport::validation::{extract_id_from_malformed, validate_message_string};
+use pulseengine_mcp_transport::RequestHandler;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinSet;
+use tokio::time::Interval;
+
+/// Answers raw messages like the STDIO transport
+pub struct RawMessageHandler {
+    handler: RequestHandler,
+    server: TemplateMcpServer,
+    notifier: ClientNotifier,
+    max_message_size: usize,
+    keepalive: Arc<Keepalive>,
+    ping_interval: Option<Duration>,
+    ping_timeout: Duration,
+}
+
+impl RawMessageHandler {
+    /// Handler dispatching to `backend`, with authentication disabled as in
+    /// [`TemplateBackend::serve`]
+    pub async fn new(backend: TemplateBackend) -> anyhow::Result<Self> {
+        // In memory like `serve`, so no key store on disk is opened
+        let mut auth_config = AuthConfig::memory();
+        auth_config.enabled = false;
+        let auth = AuthenticationManager::new(auth_config).await?;
+        let config = backend.server().config();
+        // This client's own, not those of the clients of other transports
+        let capabilities = ClientCapabilities::new();
+        let notifier = ClientNotifier::new();
+        let backend = backend
+            .with_client_capabilities(capabilities.clone())
+            .with_call_history(CallHistory::new())
+            .with_notifier(notifier.clone())
+            .with_max_wait_ms(resource_wait::STDIO_MAX_TIMEOUT_MS);
+        let keepalive = backend.server().keepalive().clone();
+        let server = backend.server().clone();
+        let request_server = server.clone();
+        let handler =
+            GenericServerHandler::new(Arc::new(backend), Arc::new(auth), MiddlewareStack::new());
+        let in_flight = InFlightIds::new();
+        let version = NegotiatedVersion::default();
+        let lifecycle = Lifecycle::new();
+        let handler: RequestHandler = Box::new(move |request| {
+            let handler = handler.clone();
+            let in_flight = in_flight.clone();
+            let version = version.clone();
+            let lifecycle = lifecycle.clone();
+            let capabilities = capabilities.clone();
+            let server = request_server.clone();
+            Box::pin(async move {
+                let _guard = match in_flight.begin(&request.id) {
+                    Ok(guard) => guard,
+                    Err(error) => return batch::create_error_response(error, request.id),
+                };
+                if let Err(error) = lifecycle.check(&request.method, &request.id) {
+                    return batch::create_error_response(error, request.id);
+                }
+                let method = request.method.clone();
+                let initialize_params = (method == "initialize").then(|| request.params.clone());
+                let negotiated = match request.params.get("protocolVersion") {
+                    Some(Value::String(requested)) if method == "initialize" => {
+                        version.negotiate(requested)
+                    }
+                    _ => version.get(),
+                };
+                let request_id = correlation::mint();
+                let handling = costs::reporting(html::tagging(handler.handle_request(request)));
+                let ((response, mime_types), cost) =
+                    correlation::correlated(request_id.clone(), &method, handling).await;
+                if let Some(params) = initialize_params {
+                    let initialized = response.as_ref().is_ok_and(|r| r.error.is_none());
+                    if initialized {
+                        capabilities.record(&params);
+                    }
+                    lifecycle.finish_initialize(initialized);
+                }
+                match response {
+                    Ok(mut response) => {
+                        if let Some(result) = &mut response.result {
+                            protocol_versions::adapt_result(negotiated, &method, result);
+                            html::tag(result, &mime_types);
+                            let config = server.config();
+                            if config.transport.echo_request_id {
+                                correlation::attach(result, &request_id);
+                            }
+                            if let Some(cost) = &cost {
+                                costs::attach(result, cost, config.limits.max_response_bytes);
+                            }
+                        }
+                        response
+                    }
+                    Err(error) => batch::create_error_response(error.into(), Value::Null),
+                }
+            })
+        });
+        Ok(Self {
+            handler,
+            server,
+            notifier,
+            max_message_size: StdioConfig::default().max_message_size,
+            keepalive,
+            ping_interval: config
+                .transport
+                .ping_interval_seconds
+                .map(Duration::from_secs),
+            ping_timeout: Duration::from_secs(config.transport.ping_timeout_seconds),
+        })
+    }
+
+    /// Reject messages longer than `bytes` instead of the transport's 10 MB
+    pub fn with_max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = bytes;
+        self
+    }
+
+    /// Answer the lines read from stdin on stdout until stdin is closed or
+    /// the client misses a ping
+    pub async fn serve_stdio(self) -> std::io::Result<()> {
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+
+    /// Answer the lines read from `input` on `output` until the end of the
+    /// input or a missed ping, then finish the requests already read
+    pub async fn serve<R, W>(self, input: R, output: W) -> std::io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let this = Arc::new(self);
+        let output = Arc::new(Mutex::new(output));
+        let mut outgoing = this.notifier.connect();
+        let forwarder = {
+            let output = output.clone();
+            tokio::spawn(async move {
+                while let Some(outgoing) = outgoing.recv().await {
+                    match outgoing {
+                        Outgoing::Line(line) => {
+                            if let Err(e) = write_line(&output, &line).await {
+                                tracing::warn!("Failed to write a notification: {e}");
+                            }
+                        }
+                        Outgoing::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })
+        };
+        let (queue, mut queued) = mpsc::unbounded_channel::<Vec<u8>>();
+        // Requests are answered one at a time and in order, like the transport
+        // does, so a client's write is seen by the read it sends after it
+        let worker = {
+            let this = this.clone();
+            let output = output.clone();
+            tokio::spawn(async move {
+                while let Some(message) = queued.recv().await {
+                    this.answer(&message, &output).await;
+                }
+            })
+        };
+        let mut notifications = JoinSet::new();
+        let mut pings = this.ping_interval.map(|interval| {
+            let mut pings = tokio::time::interval(interval);
+            // The first tick is immediate; the first ping waits an interval
+            pings.reset();
+            pings
+        });
+        let mut input = BufReader::new(input);
+        let mut line = Vec::new();
+        loop {
+            while notifications.try_join_next().is_some() {}
+            // A read interrupted by a tick keeps what it read in `line`
+            let read = tokio::select! {
+                read = input.read_until(b'\n', &mut line) => read?,
+                () = next_tick(&mut pings) => {
+                    if this.keepalive.missed(this.ping_timeout) {
+                        tracing::warn!("Client didn't answer a ping; closing the connection");
+                        break;
+                    }
+                    write_line(&output, &this.keepalive.ping().to_string()).await?;
+                    continue;
+                }
+            };
+            if read == 0 {
+                break;
+            }
+            let message = std::mem::take(&mut line);
+            let message = message.trim_ascii_end();
+            if message.is_empty() {
+                continue;
+            }
+            match classify(message) {
+                Kind::Pong(id) if this.keepalive.pong(&id) => {}
+                // Answered now, however many requests are queued
+                Kind::Ping => this.answer(message, &output).await,
+                // Handled alongside the requests, so a cancellation reaches
+                // the one it is for
+                Kind::Notification => {
+                    let this = this.clone();
+                    let message = message.to_vec();
+                    notifications.spawn(async move { this.handle(&message).await });
+                }
+                _ => {
+                    // The worker only stops once the queue is dropped
+                    let _ = queue.send(message.to_vec());
+                }
+            }
+        }
+        drop(queue);
+        let _ = worker.await;
+        while notifications.join_next().await.is_some() {}
+        this.notifier.disconnect();
+        let _ = forwarder.await;
+        Ok(())
+    }
+
+    async fn answer<W: AsyncWrite + Unpin>(&self, message: &[u8], output: &Mutex<W>) {
+        if let Some(response) = self.handle(message).await {
+            // Notifications sent during the call come before its response
+            self.notifier.flush().await;
+            if let Err(e) = write_line(output, &response).await {
+                tracing::warn!("Failed to write a response: {e}");
+            }
+        }
+    }
+
+    /// The response line to `message`, or `None` if it only holds
+    /// notifications
+    pub async fn handle(&self, message: &[u8]) -> Option<String> {
+        let Ok(line) = std::str::from_utf8(message) else {
+            return error_line(
+                Error::parse_error("Message is not valid UTF-8"),
+                Value::Null,
+            );
+        };
+        if let Err(e) = validate_message_string(line, Some(self.max_message_size)) {
+            return error_line(
+                Error::invalid_request(format!("Message validation failed: {e}")),
+                extract_id_from_malformed(line),
+            );
+        }
+        let message = match JsonRpcMessage::parse(line) {
+            Ok(message) => message,
+            Err(e) => {
+                return error_line(
+                    Error::parse_error(format!("Invalid JSON: {e}")),
+                    extract_id_from_malformed(line),
+                )
+            }
+        };
+        if let Err(e) = message.validate() {
+            return error_line(
+                Error::invalid_request(format!("Invalid JSON-RPC: {e}")),
+                Value::Null,
+            );
+        }
+
+        let policy = self.server.config().transport.batch_policy;
+        match batches::process(message, &self.handler, policy).await {
+            Ok(Some(response)) => response.to_string().ok(),
+            Ok(None) => None,
+            Err(e) => error_line(
+                Error::internal_error(format!("Processing failed: {e}")),
+                Value::Null,
+            ),
+        }
+    }
+}
+
+/// How [`RawMessageHandler::serve`] handles a message
+enum Kind {
+    Ping,
+    /// Answer to a request with this id
+    Pong(Value),
+    Notification,
+    /// Anything else, including batches and malformed messages
+    Request,
+}
+
+fn classify(message: &[u8]) -> Kind {
+    let Ok(Value::Object(message)) = serde_json::from_slice::<Value>(message) else {
+        return Kind::Request;
+    };
+    match (message.get("method"), message.get("id")) {
+        (Some(Value::String(method)), Some(_)) if method == "ping" => Kind::Ping,
+        (Some(_), None) => Kind::Notification,
+        (None, Some(id)) => Kind::Pong(id.clone()),
+        _ => Kind::Request,
+    }
+}
+
+async fn next_tick(pings: &mut Option<Interval>) {
+    match pings {
+        Some(pings) => {
+            pings.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn write_line<W: AsyncWrite + Unpin>(output: &Mutex<W>, line: &str) -> std::io::Result<()> {
+    let mut output = output.lock().await;
+    output.write_all(line.as_bytes()).await?;
+    output.write_all(b"\n").await?;
+    output.flush().await
+}
+
+fn error_line(error: Error, id: Value) -> Option<String> {
+    let response: Response = batch::create_error_response(error, id);
+    serde_json::to_string(&response).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn handler() -> RawMessageHandler {
+        RawMessageHandler::new(TemplateBackend::new(TemplateMcpServer::default()))
+            .await
+            .unwrap()
+    }
+
+    fn initialize(id: u64) -> Vec<u8> {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    async fn respond(handler: &RawMessageHandler, message: &[u8]) -> Value {
+        let line = handler.handle(message).await.expect("a response");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_malformed_messages_with_errors() {
+        let handler = handler().await;
+        let response = respond(&handler, b"\xff\xfe").await;
+        assert_eq!(response["error"]["message"], "Message is not valid UTF-8");
+
+        let response = respond(&handler, br#"{"jsonrpc":"2.0","id":7,"method":"#).await;
+        assert_eq!(response["error"]["code"], -32700);
+        assert_eq!(response["id"], 7);
+
+        let handler = handler.with_max_message_size(16);
+        let response = respond(&handler, &initialize(1)).await;
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Message validation failed"));
+    }
+
+    #[tokio::test]
+    async fn requires_initialize_first() {
+        let handler = handler().await;
+        let call = br#"{"jsonrpc":"2.0","id":2,"method":"tools/list","params":{}}"#;
+        let response = respond(&handler, call).await;
+        assert_eq!(
+            response["error"]["message"],
+            "Server not initialized; send initialize before tools/list"
+        );
+
+        let response = respond(&handler, &initialize(1)).await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-06-18");
+        assert!(handler
+            .handle(br#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+            .await
+            .is_none());
+        let response = respond(&handler, call).await;
+        assert!(response["result"]["tools"].as_array().unwrap().len() > 1);
+    }
+
+    #[tokio::test]
+    async fn serves_lines_in_order() {
+        let input = [
+            initialize(1),
+            b"\n".to_vec(),
+            br#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"add_numbers","arguments":{"a":1,"b":2}}}"#.to_vec(),
+            br#"{"jsonrpc":"2.0","id":3,"method":"ping"}"#.to_vec(),
+        ]
+        .join(&b'\n');
+        let (output, mut read) = tokio::io::duplex(1 << 16);
+        handler().await.serve(&input[..], output).await.unwrap();
+
+        let mut text = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut read, &mut text)
+            .await
+            .unwrap();
+        let mut ids: Vec<Value> = text
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["id"].clone())
+            .collect();
+        // The ping may overtake the requests queued before it
+        ids.sort_by_key(|id| id.as_u64());
+        assert_eq!(ids, [json!(1), json!(2), json!(3)]);
+        assert!(text.contains(r#""text":"3.0""#), "{text}");
+    }
+}
@@ -0,0 +1,328 @@
+//! Session recording and replay support
+//!
+//! Set `MCP_RECORD=session.jsonl` to append every request the backend handles,
+//! together with its result or error, as one JSON line. Feed the file to
+//! `cargo run --bin replay -- session.jsonl` to run the same requests against a
+//! fresh server and report any responses that differ. Timestamps are
+//! normalized before comparison so recordings stay replayable.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable naming the file to record the session to
+pub const RECORD_ENV_VAR: &str = "MCP_RECORD";
+
+/// One request handled by the backend and its outcome
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecordedExchange {
+    pub method: String,
+    pub params: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub recorded_at_ms: u64,
+}
+
+impl RecordedExchange {
+    pub fn new(method: impl Into<String>, params: Value, outcome: Result<Value, String>) -> Self {
+        let (result, error) = match outcome {
+            Ok(value) => (Some(value), None),
+            Err(message) => (None, Some(message)),
+        };
+        let recorded_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self {
+            method: method.into(),
+            params,
+            result,
+            error,
+            recorded_at_ms,
+        }
+    }
+
+    /// The outcome as a single value (`{"result": ...}` or `{"error": ...}`)
+    pub fn outcome(&self) -> Value {
+        match (&self.result, &self.error) {
+            (_, Some(error)) => serde_json::json!({ "error": error }),
+            (result, None) => serde_json::json!({ "result": result }),
+        }
+    }
+}
+
+/// Appends exchanges to a JSONL file
+#[derive(Debug)]
+pub struct SessionRecorder {
+    file: Mutex<File>,
+}
+
+impl SessionRecorder {
+    /// Open (or create) `path` for appending
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Recorder for the file named by `MCP_RECORD`, if set
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var(RECORD_ENV_VAR).ok()?;
+        match Self::create(&path) {
+            Ok(recorder) => {
+                tracing::info!(path = %path, "Recording MCP session");
+                Some(recorder)
+            }
+            Err(e) => {
+                tracing::warn!(path = %path, error = %e, "Failed to open session recording");
+                None
+            }
+        }
+    }
+
+    /// Append one exchange; failures are logged rather than failing the request
+    pub fn record(&self, exchange: &RecordedExchange) {
+        let line = match serde_json::to_string(exchange) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to serialize recorded exchange");
+                return;
+            }
+        };
+
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(e) = writeln!(file, "{line}") {
+            tracing::warn!(error = %e, "Failed to write recorded exchange");
+        }
+    }
+}
+
+/// Load a recorded session
+pub fn load_session(path: impl AsRef<Path>) -> anyhow::Result<Vec<RecordedExchange>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut exchanges = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let exchange = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("Invalid recording on line {}: {}", index + 1, e))?;
+        exchanges.push(exchange);
+    }
+    Ok(exchanges)
+}
+
+/// Replace timestamps in `value` with a fixed placeholder
+///
+/// Object fields named `timestamp`, `*_at`, `*_at_ms` or `uptime_seconds` are
+/// replaced outright; RFC 3339 timestamps embedded in strings are masked.
+pub fn normalize(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if is_time_key(key) {
+                    *field = Value::String(TIMESTAMP_PLACEHOLDER.to_string());
+                } else {
+                    normalize(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize),
+        Value::String(text) => {
+            if let Some(masked) = mask_timestamps(text) {
+                *text = masked;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Differences between `expected` and `actual`, as `path: expected != actual`
+pub fn diff(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff_at("$", expected, actual, &mut differences);
+    differences
+}
+
+const TIMESTAMP_PLACEHOLDER: &str = "<timestamp>";
+
+/// Whether `key` holds a time, which differs between otherwise equal reads
+pub(crate) fn is_time_key(key: &str) -> bool {
+    key == "timestamp" || key == "uptime_seconds" || key.ends_with("_at") || key.ends_with("_at_ms")
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            for (key, e_value) in e {
+                let child = format!("{path}.{key}");
+                match a.get(key) {
+                    Some(a_value) => diff_at(&child, e_value, a_value, out),
+                    None => out.push(format!("{child}: missing in replay")),
+                }
+            }
+            for key in a.keys().filter(|k| !e.contains_key(*k)) {
+                out.push(format!("{path}.{key}: unexpected in replay"));
+            }
+        }
+        (Value::Array(e), Value::Array(a)) if e.len() == a.len() => {
+            for (index, (e_item, a_item)) in e.iter().zip(a).enumerate() {
+                diff_at(&format!("{path}[{index}]"), e_item, a_item, out);
+            }
+        }
+        _ if expected != actual => out.push(format!("{path}: {expected} != {actual}")),
+        _ => {}
+    }
+}
+
+/// Mask every `YYYY-MM-DD[T ]HH:MM:SS[.frac][Z|±HH:MM]` run in `text`
+fn mask_timestamps(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut masked = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut i = 0;
+    let mut changed = false;
+
+    while i < bytes.len() {
+        match timestamp_len(&bytes[i..]) {
+            Some(len) => {
+                masked.push_str(&text[last..i]);
+                masked.push_str(TIMESTAMP_PLACEHOLDER);
+                i += len;
+                last = i;
+                changed = true;
+            }
+            None => i += 1,
+        }
+    }
+
+    changed.then(|| {
+        masked.push_str(&text[last..]);
+        masked
+    })
+}
+
+fn timestamp_len(bytes: &[u8]) -> Option<usize> {
+    const SHAPE: &[u8] = b"dddd-dd-ddTdd:dd:dd";
+    if bytes.len() < SHAPE.len() {
+        return None;
+    }
+    for (b, s) in bytes.iter().zip(SHAPE) {
+        let ok = match s {
+            b'd' => b.is_ascii_digit(),
+            b'T' => *b == b'T' || *b == b' ',
+            other => b == other,
+        };
+        if !ok {
+            return None;
+        }
+    }
+
+    let mut len = SHAPE.len();
+    if bytes.get(len) == Some(&b'.') {
+        len += 1;
+        while bytes.get(len).is_some_and(u8::is_ascii_digit) {
+            len += 1;
+        }
+    }
+    match bytes.get(len) {
+        Some(b'Z') => len += 1,
+        Some(b'+') | Some(b'-')
+            if bytes.len() >= len + 6
+                && bytes[len + 1..len + 3].iter().all(u8::is_ascii_digit)
+                && bytes[len + 3] == b':' =>
+        {
+            len += 6
+        }
+        _ => {}
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn records_and_loads_a_session() {
+        let path = std::env::temp_dir().join(format!("session-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let recorder = SessionRecorder::create(&path).unwrap();
+        let ok = RecordedExchange::new("tools/list", json!({}), Ok(json!({ "tools": [] })));
+        let failed = RecordedExchange::new(
+            "tools/call",
+            json!({ "name": "missing" }),
+            Err("Unknown tool".to_string()),
+        );
+        recorder.record(&ok);
+        recorder.record(&failed);
+
+        let loaded = load_session(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, [ok, failed]);
+        assert_eq!(loaded[0].outcome(), json!({ "result": { "tools": [] } }));
+        assert_eq!(loaded[1].outcome(), json!({ "error": "Unknown tool" }));
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_recording() {
+        let path = std::env::temp_dir().join(format!("bad-session-{}.jsonl", std::process::id()));
+        std::fs::write(&path, "\n{\"method\": 1}\n").unwrap();
+        let error = load_session(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            error.to_string().starts_with("Invalid recording on line 2"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn normalizes_timestamps() {
+        let mut value = json!({
+            "created_at": "2026-01-01T00:00:00Z",
+            "uptime_seconds": 12,
+            "items": [{ "note": "seen at 2026-01-01 10:20:30.5+02:00 and again" }],
+            "name": "widget"
+        });
+        normalize(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "created_at": "<timestamp>",
+                "uptime_seconds": "<timestamp>",
+                "items": [{ "note": "seen at <timestamp> and again" }],
+                "name": "widget"
+            })
+        );
+    }
+
+    #[test]
+    fn diffs_by_path() {
+        let expected = json!({ "a": 1, "list": [1, 2], "gone": true });
+        let actual = json!({ "a": 2, "list": [1, 3], "new": null });
+        assert_eq!(
+            diff(&expected, &actual),
+            [
+                "$.a: 1 != 2",
+                "$.gone: missing in replay",
+                "$.list[1]: 2 != 3",
+                "$.new: unexpected in replay",
+            ]
+        );
+        assert!(diff(&expected, &expected).is_empty());
+    }
+}
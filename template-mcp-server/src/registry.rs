@@ -0,0 +1,235 @@
+//! Registry of tools that can come from any crate
+//!
+//! `#[mcp_tools]` only covers a single impl block. A `ToolRegistry` collects
+//! boxed [`Tool`] objects instead, so tool sets defined in other crates can be
+//! registered into one server next to the macro-generated tools.
+//! [`provider_tools`] adapts every tool of a `#[mcp_tools]` impl into a
+//! `dyn Tool`.
+
+use async_trait::async_trait;
+use pulseengine_mcp_protocol::{
+    CallToolRequestParam, CallToolResult, Error, Tool as ToolDefinition,
+};
+use pulseengine_mcp_server::McpToolsProvider;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A single MCP tool: its definition plus an async invoke
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Name, description and input schema, as listed by `tools/list`
+    fn definition(&self) -> ToolDefinition;
+
+    /// Run the tool with the client-supplied arguments
+    async fn invoke(&self, arguments: Option<Value>) -> Result<CallToolResult, Error>;
+}
+
+/// `dyn Tool` adapter for one tool of a `#[mcp_tools]` impl block
+pub struct ProviderTool<P> {
+    provider: P,
+    definition: ToolDefinition,
+}
+
+#[async_trait]
+impl<P> Tool for ProviderTool<P>
+where
+    P: McpToolsProvider + Send + Sync,
+{
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    async fn invoke(&self, arguments: Option<Value>) -> Result<CallToolResult, Error> {
+        self.provider
+            .call_tool_impl(CallToolRequestParam {
+                name: self.definition.name.clone(),
+                arguments,
+            })
+            .await
+    }
+}
+
+/// Adapt every macro-generated tool of `provider` into a `dyn Tool`
+pub fn provider_tools<P>(provider: P) -> Vec<Box<dyn Tool>>
+where
+    P: McpToolsProvider + Clone + Send + Sync + 'static,
+{
+    provider
+        .get_available_tools()
+        .into_iter()
+        .map(|definition| {
+            Box::new(ProviderTool {
+                provider: provider.clone(),
+                definition,
+            }) as Box<dyn Tool>
+        })
+        .collect()
+}
+
+/// Tools registered by name, listed in registration order
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: RwLock<RegisteredTools>,
+}
+
+#[derive(Default)]
+struct RegisteredTools {
+    order: Vec<String>,
+    by_name: HashMap<String, Arc<dyn Tool>>,
+    generation: u64,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool, rejecting a name that is already taken
+    pub fn register(&self, tool: Box<dyn Tool>) -> anyhow::Result<()> {
+        let name = tool.definition().name;
+        let mut tools = self.write();
+        if tools.by_name.contains_key(&name) {
+            return Err(anyhow::anyhow!("Tool '{}' is already registered", name));
+        }
+        tools.order.push(name.clone());
+        tools.by_name.insert(name, Arc::from(tool));
+        tools.generation += 1;
+        Ok(())
+    }
+
+    /// Register several tools, stopping at the first duplicate name
+    pub fn register_all(
+        &self,
+        tools: impl IntoIterator<Item = Box<dyn Tool>>,
+    ) -> anyhow::Result<()> {
+        tools.into_iter().try_for_each(|tool| self.register(tool))
+    }
+
+    /// Look up a tool by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.read().by_name.get(name).cloned()
+    }
+
+    /// Definitions of all registered tools
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let tools = self.read();
+        tools
+            .order
+            .iter()
+            .filter_map(|name| tools.by_name.get(name))
+            .map(|tool| tool.definition())
+            .collect()
+    }
+
+    /// Number of registrations so far, which changes with the tool set
+    pub fn generation(&self) -> u64 {
+        self.read().generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Invoke a tool by name
+    pub async fn call(&self, request: CallToolRequestParam) -> Result<CallToolResult, Error> {
+        let tool = self
+            .get(&request.name)
+            .ok_or_else(|| Error::invalid_params(format!("Unknown tool: {}", request.name)))?;
+        tool.invoke(request.arguments).await
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, RegisteredTools> {
+        self.tools
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, RegisteredTools> {
+        self.tools
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TemplateMcpServer;
+    use pulseengine_mcp_protocol::Content;
+    use serde_json::json;
+
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl Tool for Named {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: self.0.to_string(),
+                description: String::new(),
+                input_schema: json!({ "type": "object" }),
+                output_schema: None,
+            }
+        }
+
+        async fn invoke(&self, _arguments: Option<Value>) -> Result<CallToolResult, Error> {
+            Ok(CallToolResult::success(vec![Content::text(self.0)]))
+        }
+    }
+
+    #[tokio::test]
+    async fn registers_tools_in_order() {
+        let registry = ToolRegistry::new();
+        assert!(registry.is_empty());
+        registry
+            .register_all([
+                Box::new(Named("zeta")) as Box<dyn Tool>,
+                Box::new(Named("alpha")),
+            ])
+            .unwrap();
+        let error = registry.register(Box::new(Named("zeta"))).unwrap_err();
+        assert_eq!(error.to_string(), "Tool 'zeta' is already registered");
+
+        let names: Vec<String> = registry.definitions().into_iter().map(|d| d.name).collect();
+        assert_eq!(names, ["zeta", "alpha"]);
+        assert_eq!((registry.len(), registry.generation()), (2, 2));
+
+        let result = registry
+            .call(CallToolRequestParam {
+                name: "alpha".to_string(),
+                arguments: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&result.content).unwrap(),
+            json!([{ "type": "text", "text": "alpha" }])
+        );
+        let error = registry
+            .call(CallToolRequestParam {
+                name: "beta".to_string(),
+                arguments: None,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "Unknown tool: beta");
+    }
+
+    #[tokio::test]
+    async fn adapts_macro_generated_tools() {
+        let tools = provider_tools(TemplateMcpServer::default());
+        let add = tools
+            .iter()
+            .find(|tool| tool.definition().name == "add_numbers")
+            .expect("add_numbers is a macro-generated tool");
+        let result = add.invoke(Some(json!({ "a": 2, "b": 3 }))).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&result.content).unwrap(),
+            json!([{ "type": "text", "text": "5.0" }])
+        );
+    }
+}
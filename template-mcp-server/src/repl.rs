@@ -0,0 +1,349 @@
+//! Interactive shell for trying the server without a client
+//!
+//! `template-mcp-server --repl` reads commands from stdin instead of serving
+//! MCP, runs each through `TemplateBackend::dispatch`, the path every client
+//! request takes after the transport, and prints the result:
+//!
+//! ```text
+//! mcp> call add_numbers {"a": 1, "b": 2}
+//! 3.0
+//! mcp> read template://server-status
+//! mcp> schema add_numbers
+//! ```
+//!
+//! There is no transport, so no authentication either. Calls are made as the
+//! backend's client id, which `client <id>` changes, e.g. to an id in
+//! `AccessConfig::admin_clients` to try the admin tools. `help` lists every
+//! command.
+
+use crate::TemplateBackend;
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Command-line flag starting the REPL instead of the server
+pub const REPL_FLAG: &str = "--repl";
+
+/// Printed before reading each command
+pub const PROMPT: &str = "mcp> ";
+
+const HELP: &str = "\
+Commands:
+  list tools              List the tools with their descriptions
+  list resources          List the resources
+  call <tool> [json]      Call a tool with a JSON object of arguments
+  read <uri>              Read a resource
+  schema <tool>           Show a tool's input schema
+  client [id]             Show or set the client id calls are made as
+  help                    Show this help
+  quit                    Leave the REPL";
+
+/// A command typed at the prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    ListTools,
+    ListResources,
+    Call { tool: String, arguments: Value },
+    Read { uri: String },
+    Schema { tool: String },
+    Client { id: Option<String> },
+    Quit,
+}
+
+impl Command {
+    /// Parse a command line
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let required = |what: &str| {
+            if rest.is_empty() {
+                Err(format!("Usage: {name} <{what}>"))
+            } else {
+                Ok(rest.to_string())
+            }
+        };
+        match name {
+            "help" | "?" => Ok(Self::Help),
+            "list" => match rest {
+                "" | "tools" => Ok(Self::ListTools),
+                "resources" => Ok(Self::ListResources),
+                other => Err(format!(
+                    "Can't list '{other}'; try 'list tools' or 'list resources'"
+                )),
+            },
+            "call" => {
+                let (tool, arguments) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                if tool.is_empty() {
+                    return Err("Usage: call <tool> [json]".to_string());
+                }
+                let arguments = match arguments.trim() {
+                    "" => Value::Object(Default::default()),
+                    json => serde_json::from_str(json)
+                        .map_err(|e| format!("Arguments aren't valid JSON: {e}"))?,
+                };
+                Ok(Self::Call {
+                    tool: tool.to_string(),
+                    arguments,
+                })
+            }
+            "read" => Ok(Self::Read {
+                uri: required("uri")?,
+            }),
+            "schema" => Ok(Self::Schema {
+                tool: required("tool")?,
+            }),
+            "client" => Ok(Self::Client {
+                id: (!rest.is_empty()).then(|| rest.to_string()),
+            }),
+            "quit" | "exit" => Ok(Self::Quit),
+            other => Err(format!(
+                "Unknown command '{other}'; type 'help' for the commands"
+            )),
+        }
+    }
+}
+
+/// Runs commands against a backend
+pub struct Repl {
+    backend: TemplateBackend,
+}
+
+impl Repl {
+    pub fn new(backend: TemplateBackend) -> Self {
+        Self { backend }
+    }
+
+    /// Read commands from `input` and write their output to `output` until
+    /// `quit` or the end of the input
+    pub async fn run<R, W>(&mut self, input: R, mut output: W) -> std::io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = input.lines();
+        loop {
+            output.write_all(PROMPT.as_bytes()).await?;
+            output.flush().await?;
+            let Some(line) = lines.next_line().await? else {
+                output.write_all(b"\n").await?;
+                return output.flush().await;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let text = match Command::parse(&line) {
+                Ok(Command::Quit) => return output.flush().await,
+                Ok(command) => self.execute(command).await,
+                Err(e) => format!("error: {e}"),
+            };
+            output.write_all(text.as_bytes()).await?;
+            output.write_all(b"\n").await?;
+        }
+    }
+
+    /// The output of `command`
+    pub async fn execute(&mut self, command: Command) -> String {
+        let output = match command {
+            Command::Help => Ok(HELP.to_string()),
+            Command::Quit => Ok(String::new()),
+            Command::ListTools => self.list_tools().await,
+            Command::ListResources => self.list_resources().await,
+            Command::Call { tool, arguments } => self.call(tool, arguments).await,
+            Command::Read { uri } => self.read(uri).await,
+            Command::Schema { tool } => self.schema(&tool).await,
+            Command::Client { id: Some(id) } => {
+                self.backend = self.backend.clone().with_client_id(id);
+                Ok(format!("Calling as client '{}'", self.backend.client_id()))
+            }
+            Command::Client { id: None } => {
+                Ok(format!("Calling as client '{}'", self.backend.client_id()))
+            }
+        };
+        output.unwrap_or_else(|e| format!("error: {e}"))
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, String> {
+        self.backend
+            .dispatch(method, params)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn tools(&self) -> Result<Vec<Value>, String> {
+        let listed = self.dispatch("tools/list", Value::Null).await?;
+        Ok(match listed.get("tools") {
+            Some(Value::Array(tools)) => tools.clone(),
+            _ => Vec::new(),
+        })
+    }
+
+    async fn list_tools(&self) -> Result<String, String> {
+        let lines: Vec<String> = self
+            .tools()
+            .await?
+            .iter()
+            .map(|tool| {
+                let name = tool["name"].as_str().unwrap_or_default();
+                let summary = tool["description"]
+                    .as_str()
+                    .and_then(|description| description.lines().next())
+                    .unwrap_or_default();
+                format!("{name:<24}{summary}")
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    async fn list_resources(&self) -> Result<String, String> {
+        let mut lines = Vec::new();
+        let mut cursor = Value::Null;
+        loop {
+            let page = self
+                .dispatch("resources/list", serde_json::json!({ "cursor": cursor }))
+                .await?;
+            for resource in page["resources"].as_array().into_iter().flatten() {
+                let uri = resource["uri"].as_str().unwrap_or_default();
+                let name = resource["name"].as_str().unwrap_or_default();
+                lines.push(format!("{uri:<40}{name}"));
+            }
+            match page.get("nextCursor") {
+                Some(next) if !next.is_null() => cursor = next.clone(),
+                _ => return Ok(lines.join("\n")),
+            }
+        }
+    }
+
+    async fn call(&self, tool: String, arguments: Value) -> Result<String, String> {
+        let result = self
+            .dispatch(
+                "tools/call",
+                serde_json::json!({ "name": tool, "arguments": arguments }),
+            )
+            .await?;
+        let text = render_contents(&result["content"]);
+        if result["isError"] == Value::Bool(true) {
+            Err(text)
+        } else {
+            Ok(text)
+        }
+    }
+
+    async fn read(&self, uri: String) -> Result<String, String> {
+        let result = self
+            .dispatch("resources/read", serde_json::json!({ "uri": uri }))
+            .await?;
+        Ok(render_contents(&result["contents"]))
+    }
+
+    async fn schema(&self, tool: &str) -> Result<String, String> {
+        let tools = self.tools().await?;
+        let definition = tools
+            .iter()
+            .find(|definition| definition["name"] == tool)
+            .ok_or_else(|| format!("Unknown tool '{tool}'"))?;
+        Ok(pretty(&definition["inputSchema"]))
+    }
+}
+
+/// Tool or resource contents as text, JSON texts pretty-printed
+fn render_contents(contents: &Value) -> String {
+    let rendered: Vec<String> = contents
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(
+            |content| match (content["text"].as_str(), content["blob"].as_str()) {
+                (Some(text), _) => match serde_json::from_str::<Value>(text) {
+                    Ok(json) if json.is_object() || json.is_array() => pretty(&json),
+                    _ => text.to_string(),
+                },
+                (None, Some(blob)) => format!(
+                    "<{} base64 characters of {}>",
+                    blob.len(),
+                    content["mime_type"].as_str().unwrap_or("binary data")
+                ),
+                (None, None) => pretty(content),
+            },
+        )
+        .collect();
+    rendered.join("\n")
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TemplateMcpServer;
+    use serde_json::json;
+
+    async fn session(input: &str) -> String {
+        let mut repl = Repl::new(TemplateBackend::new(TemplateMcpServer::default()));
+        let mut output = Vec::new();
+        repl.run(input.as_bytes(), &mut output).await.unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(
+            Command::parse("call add_numbers {\"a\": 1, \"b\": 2}"),
+            Ok(Command::Call {
+                tool: "add_numbers".to_string(),
+                arguments: json!({ "a": 1, "b": 2 }),
+            })
+        );
+        assert_eq!(Command::parse("  list  "), Ok(Command::ListTools));
+        assert_eq!(Command::parse("client"), Ok(Command::Client { id: None }));
+        assert_eq!(Command::parse("read"), Err("Usage: read <uri>".to_string()));
+        assert_eq!(
+            Command::parse("list prompts"),
+            Err("Can't list 'prompts'; try 'list tools' or 'list resources'".to_string())
+        );
+        assert!(Command::parse("call echo {oops")
+            .unwrap_err()
+            .starts_with("Arguments aren't valid JSON"));
+        assert_eq!(
+            Command::parse("launch"),
+            Err("Unknown command 'launch'; type 'help' for the commands".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn runs_commands_until_quit() {
+        let output = session(
+            "call add_numbers {\"a\": 1, \"b\": 2}\n\nclient ops\nfrobnicate\nquit\nhelp\n",
+        )
+        .await;
+        assert_eq!(
+            output,
+            "mcp> 3.0\nmcp> mcp> Calling as client 'ops'\n\
+             mcp> error: Unknown command 'frobnicate'; type 'help' for the commands\nmcp> "
+        );
+    }
+
+    #[tokio::test]
+    async fn shows_tools_and_their_schemas() {
+        let output = session("list tools\nschema add_numbers\nschema nope\n").await;
+        assert!(output.contains("\nadd_numbers "), "{output}");
+        assert!(output.contains("\"required\": ["), "{output}");
+        assert!(output.contains("error: Unknown tool 'nope'"), "{output}");
+        assert!(output.ends_with("mcp> \n"), "{output}");
+    }
+
+    #[test]
+    fn renders_json_and_binary_contents() {
+        let contents = json!([
+            { "text": "{\"a\":1}" },
+            { "text": "plain" },
+            { "blob": "AAAA", "mime_type": "image/png" }
+        ]);
+        assert_eq!(
+            render_contents(&contents),
+            "{\n  \"a\": 1\n}\nplain\n<4 base64 characters of image/png>"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Deadline, cancellation and client of the tool call being handled
+//!
+//! The backend runs every tool call in a [`RequestContext`] whose deadline is
+//! `ServerConfig::timeout_seconds` away. A call still running at its deadline
+//! fails with a timeout error. The context's cancellation token is cancelled
+//! when the call ends, whether it finished, timed out or was dropped because
+//! the client went away.
+//!
+//! Dropping the call stops everything it awaits, but not work it handed to
+//! other tasks, such as a request running on an HTTP client's connection
+//! pool. Tools pass the context on to such work so it stops with the call:
+//!
+//! ```ignore
+//! let ctx = RequestContext::current().unwrap_or_default();
+//! let response = ctx
+//!     .run(client.get(url).timeout(ctx.remaining()).send())
+//!     .await??;
+//! // or, for work on its own task:
+//! let token = ctx.token().clone();
+//! tokio::spawn(async move { tokio::select! { _ = token.cancelled() => {}, _ = work => {} } });
+//! ```
+//!
+//! The context also reaches the client, for warnings that don't fail the
+//! call; see [`crate::client_notifications`]. It names the client too, as
+//! identified for quotas, for tools answering per client such as
+//! [`crate::whoami`].
+
+use crate::client_notifications::{ClientNotifier, LogLevel};
+use serde_json::Value;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Deadline used when there is no context, far enough away to never pass
+const NO_DEADLINE: Duration = Duration::from_secs(100 * 365 * 24 * 3600);
+
+tokio::task_local! {
+    static CURRENT: RequestContext;
+}
+
+/// Why a request stopped before finishing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stopped {
+    Cancelled,
+    DeadlineExceeded,
+}
+
+impl std::fmt::Display for Stopped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "Request cancelled"),
+            Self::DeadlineExceeded => write!(f, "Request deadline exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for Stopped {}
+
+/// Deadline, cancellation token and client of a request
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    deadline: Instant,
+    token: CancellationToken,
+    notifier: ClientNotifier,
+    tool: Option<String>,
+    client: Option<String>,
+}
+
+impl Default for RequestContext {
+    /// A context that is never cancelled and has no deadline
+    fn default() -> Self {
+        Self::new(NO_DEADLINE)
+    }
+}
+
+impl RequestContext {
+    /// Context of a request that must finish within `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+            token: CancellationToken::new(),
+            notifier: ClientNotifier::new(),
+            tool: None,
+            client: None,
+        }
+    }
+
+    /// Send the notifications of the call to `tool` through `notifier`
+    pub fn with_notifier(mut self, notifier: ClientNotifier, tool: impl Into<String>) -> Self {
+        self.notifier = notifier;
+        self.tool = Some(tool.into());
+        self
+    }
+
+    /// Make the call on behalf of `client`
+    pub fn with_client(mut self, client: impl Into<String>) -> Self {
+        self.client = Some(client.into());
+        self
+    }
+
+    /// The client making the call, if known
+    pub fn client(&self) -> Option<&str> {
+        self.client.as_deref()
+    }
+
+    /// Context of the tool call being handled on this task, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Time left until the deadline
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Token cancelled when the request ends, to hand to outbound calls
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Tell the client about a problem that doesn't fail the call, as a
+    /// warning log message
+    pub fn warn(&self, message: impl Into<String>) {
+        self.notifier.log_message(
+            LogLevel::Warning,
+            self.tool.as_deref(),
+            Value::String(message.into()),
+        );
+    }
+
+    /// Run `future` unless the request is cancelled or its deadline passes
+    /// first
+    pub async fn run<F: Future>(&self, future: F) -> Result<F::Output, Stopped> {
+        tokio::select! {
+            output = future => Ok(output),
+            _ = self.token.cancelled() => Err(Stopped::Cancelled),
+            _ = tokio::time::sleep_until(self.deadline) => Err(Stopped::DeadlineExceeded),
+        }
+    }
+
+    /// Run `future` with this as the current context
+    pub async fn enter<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// Run `call` with this as the current context until it finishes, is
+    /// cancelled or the deadline passes, then cancel the token
+    pub async fn scope<F: Future>(self, call: F) -> Result<F::Output, Stopped> {
+        let _cancel_on_end = self.token.clone().drop_guard();
+        let context = self.clone();
+        self.enter(async move { context.run(call).await }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stops_at_the_deadline() {
+        let context = RequestContext::new(Duration::from_millis(20));
+        assert!(context.remaining() <= Duration::from_millis(20));
+        assert_eq!(context.run(async { 1 }).await, Ok(1));
+        assert_eq!(
+            context.run(std::future::pending::<()>()).await,
+            Err(Stopped::DeadlineExceeded)
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_when_cancelled() {
+        let context = RequestContext::default();
+        context.cancel();
+        assert!(context.is_cancelled());
+        assert_eq!(
+            context.run(std::future::pending::<()>()).await,
+            Err(Stopped::Cancelled)
+        );
+    }
+
+    #[tokio::test]
+    async fn scopes_the_current_context() {
+        assert!(RequestContext::current().is_none());
+        let context = RequestContext::default().with_client("agent");
+        let token = context.token().clone();
+        let client = context
+            .scope(async {
+                RequestContext::current()
+                    .unwrap()
+                    .client()
+                    .map(str::to_string)
+            })
+            .await;
+        assert_eq!(client, Ok(Some("agent".to_string())));
+        assert!(
+            token.is_cancelled(),
+            "the token is cancelled when the call ends"
+        );
+    }
+}
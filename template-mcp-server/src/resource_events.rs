@@ -0,0 +1,171 @@
+//! Resource changes as a Server-Sent Events feed
+//!
+//! Browser dashboards can't send `resources/wait` requests, but they can hold
+//! an `EventSource` open. With [`EVENTS_ADDR_ENV_VAR`] set, the server serves
+//! `GET /events?uri=<resource URI>`, a `text/event-stream` with one
+//! [`CHANGED_EVENT`] per version of the resource, the first being its
+//! current content:
+//!
+//! ```text
+//! event: changed
+//! id: 3f9a0c6d2b71e845
+//! data: {"uri":"template://stats/tools","contents":[{"uri":"template://stats/tools","mimeType":"application/json","text":"..."}]}
+//! ```
+//!
+//! Changes are found the way `resources/wait` finds them (see
+//! [`crate::resource_wait`]), so every resource can be followed without
+//! opting in. An event's id is the content's token, so a client reconnecting
+//! with `Last-Event-ID`, as `EventSource` does, gets no event until the
+//! resource differs from the last one it saw, and one right away if it
+//! changed in the meantime. Reads go through the backend, with its access
+//! checks: a resource that can't be read ends the feed with an `error` event
+//! carrying the reason. Idle feeds get a comment line every 15 seconds, so
+//! proxies don't close them.
+//!
+//! The 0.11 HTTP transport has no extension point for new routes, so the
+//! feed has its own listener, like the health endpoint (see
+//! [`crate::health`]). Follow several resources with one feed each.
+
+use crate::resource_wait::{WaitResourceRequest, MAX_TIMEOUT_MS};
+use crate::TemplateBackend;
+use axum::extract::Query;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::time::Duration;
+
+/// Environment variable with the address to serve the feed on
+pub const EVENTS_ADDR_ENV_VAR: &str = "MCP_EVENTS_ADDR";
+
+pub const EVENTS_PATH: &str = "/events";
+
+/// Name of the event sent for each version of a resource
+pub const CHANGED_EVENT: &str = "changed";
+
+/// Name of the event ending a feed whose resource can't be read
+pub const ERROR_EVENT: &str = "error";
+
+/// Delay `EventSource` waits before reconnecting to a feed that dropped
+const RETRY: Duration = Duration::from_secs(3);
+
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Deserialize)]
+struct FeedQuery {
+    uri: String,
+}
+
+/// Serve the feed on `listener`, reading through `backend`
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    backend: TemplateBackend,
+) -> std::io::Result<()> {
+    let router = axum::Router::new().route(
+        EVENTS_PATH,
+        axum::routing::get(move |Query(query): Query<FeedQuery>, headers: HeaderMap| {
+            let backend = backend.clone();
+            async move {
+                let last_event_id = headers
+                    .get("last-event-id")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                Sse::new(feed(backend, query.uri, last_event_id))
+                    .keep_alive(KeepAlive::new().interval(KEEPALIVE_INTERVAL))
+            }
+        }),
+    );
+    axum::serve(listener, router).await
+}
+
+/// What a feed does next
+enum Next {
+    /// Send the version after the one with this token, or the current one if
+    /// none
+    After(Option<String>),
+    Done,
+}
+
+/// The events of `uri`, starting after the version `last_event_id` names
+pub fn feed(
+    backend: TemplateBackend,
+    uri: String,
+    last_event_id: Option<String>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(Next::After(last_event_id), move |next| {
+        let backend = backend.clone();
+        let uri = uri.clone();
+        async move {
+            let Next::After(last) = next else {
+                return None;
+            };
+            loop {
+                let request = WaitResourceRequest {
+                    uri: uri.clone(),
+                    wait: last.is_some(),
+                    if_changed_since: last.clone(),
+                    timeout_ms: Some(MAX_TIMEOUT_MS),
+                };
+                match backend.wait_resource(request).await {
+                    Ok(result) if result.changed => {
+                        let data = json!({ "uri": uri, "contents": result.contents });
+                        let event = Event::default()
+                            .event(CHANGED_EVENT)
+                            .id(result.token.clone())
+                            .retry(RETRY)
+                            .data(data.to_string());
+                        return Some((Ok(event), Next::After(Some(result.token))));
+                    }
+                    // Timed out unchanged; the keepalive covers the silence
+                    Ok(_) => continue,
+                    Err(e) => {
+                        let event = Event::default().event(ERROR_EVENT).data(e.to_string());
+                        return Some((Ok(event), Next::Done));
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TemplateMcpServer;
+    use futures_util::StreamExt;
+
+    fn backend() -> TemplateBackend {
+        TemplateBackend::new(TemplateMcpServer::default())
+    }
+
+    /// The text of `event` as it goes on the wire
+    fn wire(event: Event) -> String {
+        format!("{event:?}")
+    }
+
+    #[tokio::test]
+    async fn starts_with_the_current_content() {
+        let mut events = Box::pin(feed(
+            backend(),
+            "template://server-status".to_string(),
+            None,
+        ));
+        let first = wire(events.next().await.unwrap().unwrap());
+        assert!(first.contains("event: changed"), "{first}");
+        assert!(first.contains("template://server-status"), "{first}");
+        assert!(first.contains("retry:3000"), "{first}");
+    }
+
+    #[tokio::test]
+    async fn ends_with_an_error_it_cant_read() {
+        let events: Vec<String> = feed(backend(), "template://server-config".to_string(), None)
+            .map(|event| wire(event.unwrap()))
+            .collect()
+            .await;
+        assert_eq!(events.len(), 1);
+        assert!(events[0].contains("event: error"), "{}", events[0]);
+        assert!(events[0].contains("requires admin access"), "{}", events[0]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Telling clients when the resource list changes
+//!
+//! Most resources are static, but some come and go while the server runs:
+//! result resources are registered by tool calls such as `data_report` and
+//! disappear when they expire (see [`crate::result_resources`]). Whenever such
+//! a resource is added or removed, [`ResourceListChanges::changed`] sends
+//! `notifications/resources/list_changed`, so clients listing resources
+//! refresh their view:
+//!
+//! ```json
+//! { "jsonrpc": "2.0", "method": "notifications/resources/list_changed", "params": {} }
+//! ```
+//!
+//! Notifications are throttled to one per [`MIN_INTERVAL`]: the first change
+//! is sent at once, and further changes within the interval are sent as one
+//! notification when it ends. Static resources never change, so they never
+//! send one. Like tool warnings, the notifications travel through the
+//! connection's [`ClientNotifier`], so only STDIO clients get them, and the
+//! server advertises `listChanged` only while one is connected.
+
+use crate::client_notifications::ClientNotifier;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Method of the notifications sent when the resource list changes
+pub const LIST_CHANGED_NOTIFICATION: &str = "notifications/resources/list_changed";
+
+/// Shortest time between two notifications
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+struct Throttle {
+    last_sent: Option<Instant>,
+    /// A notification is already scheduled for the end of the interval
+    pending: bool,
+}
+
+/// Sends throttled `list_changed` notifications, shared by the clones of a
+/// backend
+#[derive(Debug, Clone, Default)]
+pub struct ResourceListChanges {
+    notifier: ClientNotifier,
+    throttle: Arc<Mutex<Throttle>>,
+}
+
+impl ResourceListChanges {
+    pub fn new(notifier: ClientNotifier) -> Self {
+        Self {
+            notifier,
+            throttle: Arc::default(),
+        }
+    }
+
+    /// Record that a dynamic resource was added or removed
+    pub fn changed(&self) {
+        let now = Instant::now();
+        let mut throttle = self.lock();
+        if throttle.pending {
+            return;
+        }
+        let due = throttle.last_sent.map(|sent| sent + MIN_INTERVAL);
+        match (due, tokio::runtime::Handle::try_current()) {
+            (Some(due), Ok(runtime)) if due > now => {
+                throttle.pending = true;
+                let changes = self.clone();
+                runtime.spawn(async move {
+                    tokio::time::sleep_until(due).await;
+                    let mut throttle = changes.lock();
+                    throttle.pending = false;
+                    throttle.last_sent = Some(Instant::now());
+                    changes.send();
+                });
+            }
+            _ => {
+                throttle.last_sent = Some(now);
+                self.send();
+            }
+        }
+    }
+
+    fn send(&self) {
+        self.notifier.notify(LIST_CHANGED_NOTIFICATION, json!({}));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Throttle> {
+        self.throttle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_notifications::Outgoing;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    fn sent(outgoing: &mut UnboundedReceiver<Outgoing>) -> usize {
+        let mut count = 0;
+        while let Ok(item) = outgoing.try_recv() {
+            match item {
+                Outgoing::Line(line) => {
+                    assert!(line.contains(LIST_CHANGED_NOTIFICATION), "{line}");
+                    count += 1;
+                }
+                Outgoing::Flush(_) => {}
+            }
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn throttles_bursts_to_one_notification_per_interval() {
+        let notifier = ClientNotifier::new();
+        let mut outgoing = notifier.connect();
+        let changes = ResourceListChanges::new(notifier);
+
+        changes.changed();
+        assert_eq!(sent(&mut outgoing), 1);
+
+        changes.changed();
+        changes.changed();
+        assert_eq!(sent(&mut outgoing), 0);
+
+        tokio::time::sleep(MIN_INTERVAL + Duration::from_millis(100)).await;
+        assert_eq!(sent(&mut outgoing), 1);
+    }
+
+    #[tokio::test]
+    async fn sends_at_once_after_a_quiet_interval() {
+        let notifier = ClientNotifier::new();
+        let mut outgoing = notifier.connect();
+        let changes = ResourceListChanges::new(notifier);
+        changes.changed();
+        tokio::time::sleep(MIN_INTERVAL + Duration::from_millis(100)).await;
+        changes.changed();
+        assert_eq!(sent(&mut outgoing), 2);
+    }
+}
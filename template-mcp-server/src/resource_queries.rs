@@ -0,0 +1,261 @@
+//! Query parameters of templated resources
+//!
+//! One resource can serve variants of its contents without a URI per variant:
+//! `template://example-data/7?format=summary` reads the same entry as
+//! `template://example-data/7`, in its short form. The backend takes the query
+//! off the URI before the resource is routed, checks each parameter against
+//! the resource's entry in [`RESOURCE_QUERIES`] and converts it to the
+//! parameter's [`QueryType`], filling in the defaults of the parameters the
+//! query leaves out. The resource method reads the parsed query with
+//! [`current`]:
+//!
+//! ```ignore
+//! let summary = resource_queries::current().text("format") == Some("summary");
+//! ```
+//!
+//! A value that doesn't convert fails the read. Parameters a resource doesn't
+//! declare are ignored, unless the resource is `strict` or
+//! `ResourcesConfig::strict_query_params` makes every resource strict; then they
+//! fail it too, naming the parameter. The contents keep the URI as requested,
+//! query included.
+//!
+//! `#[mcp_resource]` only passes the template's path parameters to the method
+//! in 0.11, so the backend parses the query itself and hands it to the method
+//! through [`current`]. Values are taken as written, without percent
+//! decoding. Resources served outside the `#[mcp_resource]` methods, such as
+//! [`crate::logs`], parse their own queries.
+
+use crate::ServerConfig;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT: Query;
+}
+
+/// Type a query parameter's value is converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryType {
+    Text,
+    Integer,
+    Number,
+    Boolean,
+    /// Text that must be one of the values
+    OneOf(&'static [&'static str]),
+}
+
+/// An optional query parameter of a resource
+#[derive(Debug, Clone, Copy)]
+pub struct QueryParam {
+    pub name: &'static str,
+    pub kind: QueryType,
+    /// Value used when the query leaves the parameter out, as it would be
+    /// written in the query; none if unset
+    pub default: Option<&'static str>,
+}
+
+/// The query parameters of the resources matching `uri_template`
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceQuery {
+    pub uri_template: &'static str,
+    pub params: &'static [QueryParam],
+    /// Whether parameters not in `params` fail the read instead of being
+    /// ignored
+    pub strict: bool,
+}
+
+/// Query parameters of the `#[mcp_resource]` methods that take any
+pub const RESOURCE_QUERIES: &[ResourceQuery] = &[ResourceQuery {
+    uri_template: "template://example-data/{id}",
+    params: &[QueryParam {
+        name: "format",
+        kind: QueryType::OneOf(&["full", "summary"]),
+        default: Some("full"),
+    }],
+    strict: false,
+}];
+
+/// The parsed query of a resource read, defaults included
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    values: HashMap<String, Value>,
+}
+
+impl Query {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// The value of the text parameter `name`
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(Value::as_str)
+    }
+}
+
+/// Query of the resource read being handled on this task; empty if none
+pub fn current() -> Query {
+    CURRENT.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Read a resource with `future`, [`current`] returning `query`
+pub async fn with_query<F: Future>(query: Query, future: F) -> F::Output {
+    CURRENT.scope(query, future).await
+}
+
+/// `uri` without its query, and its query parsed for the resource it names
+///
+/// A resource without an entry in [`RESOURCE_QUERIES`] takes no parameters.
+pub fn parse<'a>(uri: &'a str, config: &ServerConfig) -> Result<(&'a str, Query), String> {
+    let Some((path, query)) = uri.split_once('?') else {
+        return Ok((uri, defaults(find(uri))));
+    };
+    let entry = find(path);
+    let params = entry.map_or(&[][..], |entry| entry.params);
+    let strict = config.resources.strict_query_params || entry.is_some_and(|entry| entry.strict);
+    let mut parsed = defaults(entry);
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, text) = pair.split_once('=').unwrap_or((pair, ""));
+        match params.iter().find(|param| param.name == name) {
+            Some(param) => {
+                let value = convert(param.kind, text).ok_or_else(|| {
+                    format!("Query parameter '{name}' must be {}", type_name(param.kind))
+                })?;
+                parsed.values.insert(name.to_string(), value);
+            }
+            None if strict => return Err(format!("Unknown query parameter '{name}' for {path}")),
+            None => {}
+        }
+    }
+    Ok((path, parsed))
+}
+
+fn find(path: &str) -> Option<&'static ResourceQuery> {
+    RESOURCE_QUERIES
+        .iter()
+        .find(|entry| matches_template(entry.uri_template, path))
+}
+
+/// Whether `path` fills in `template`, each `{param}` standing for one
+/// non-empty segment
+fn matches_template(template: &str, path: &str) -> bool {
+    let mut segments = path.split('/');
+    template.split('/').all(|part| match segments.next() {
+        Some(segment) if part.starts_with('{') && part.ends_with('}') => !segment.is_empty(),
+        Some(segment) => segment == part,
+        None => false,
+    }) && segments.next().is_none()
+}
+
+fn defaults(entry: Option<&ResourceQuery>) -> Query {
+    let values = entry
+        .into_iter()
+        .flat_map(|entry| entry.params)
+        .filter_map(|param| {
+            let value = convert(param.kind, param.default?)?;
+            Some((param.name.to_string(), value))
+        })
+        .collect();
+    Query { values }
+}
+
+fn convert(kind: QueryType, text: &str) -> Option<Value> {
+    match kind {
+        QueryType::Text => Some(Value::from(text)),
+        QueryType::Integer => text.parse::<i64>().ok().map(Value::from),
+        QueryType::Number => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        QueryType::Boolean => text.parse::<bool>().ok().map(Value::Bool),
+        QueryType::OneOf(values) => values.contains(&text).then(|| Value::from(text)),
+    }
+}
+
+fn type_name(kind: QueryType) -> String {
+    match kind {
+        QueryType::Text => "text".to_string(),
+        QueryType::Integer => "an integer".to_string(),
+        QueryType::Number => "a number".to_string(),
+        QueryType::Boolean => "true or false".to_string(),
+        QueryType::OneOf(values) => format!("one of {}", values.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_defaults() {
+        let config = ServerConfig::default();
+        let (path, query) = parse("template://example-data/7", &config).unwrap();
+        assert_eq!(path, "template://example-data/7");
+        assert_eq!(query.text("format"), Some("full"));
+
+        let (path, query) = parse("template://server-status", &config).unwrap();
+        assert_eq!(path, "template://server-status");
+        assert_eq!(query, Query::default());
+    }
+
+    #[test]
+    fn converts_declared_parameters() {
+        let config = ServerConfig::default();
+        let (path, query) =
+            parse("template://example-data/7?format=summary&extra=1", &config).unwrap();
+        assert_eq!(path, "template://example-data/7");
+        assert_eq!(query.text("format"), Some("summary"));
+        assert_eq!(query.get("extra"), None);
+        assert_eq!(
+            parse("template://example-data/7?format=short", &config).unwrap_err(),
+            "Query parameter 'format' must be one of full, summary"
+        );
+    }
+
+    #[test]
+    fn strict_resources_reject_unknown_parameters() {
+        let mut config = ServerConfig::default();
+        config.resources.strict_query_params = true;
+        assert_eq!(
+            parse("template://example-data/7?extra=1", &config).unwrap_err(),
+            "Unknown query parameter 'extra' for template://example-data/7"
+        );
+    }
+
+    #[test]
+    fn templates_match_one_segment_per_parameter() {
+        let template = "template://example-data/{id}";
+        assert!(matches_template(template, "template://example-data/7"));
+        assert!(!matches_template(template, "template://example-data/"));
+        assert!(!matches_template(
+            template,
+            "template://example-data/7/more"
+        ));
+        assert!(!matches_template(template, "template://other/7"));
+    }
+
+    #[test]
+    fn converts_each_type() {
+        assert_eq!(convert(QueryType::Integer, "3"), Some(Value::from(3)));
+        assert_eq!(convert(QueryType::Number, "1.5"), Some(Value::from(1.5)));
+        assert_eq!(convert(QueryType::Boolean, "true"), Some(Value::Bool(true)));
+        assert_eq!(convert(QueryType::Integer, "x"), None);
+        assert_eq!(type_name(QueryType::Boolean), "true or false");
+    }
+
+    #[tokio::test]
+    async fn hands_the_query_to_the_resource() {
+        let (_, query) = parse(
+            "template://example-data/7?format=summary",
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        let format = with_query(query, async {
+            current().text("format").map(str::to_string)
+        })
+        .await;
+        assert_eq!(format.as_deref(), Some("summary"));
+        assert_eq!(current(), Query::default());
+    }
+}
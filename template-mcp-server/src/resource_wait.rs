@@ -0,0 +1,252 @@
+//! Long-poll reads for resources
+//!
+//! `resources/wait` reads a resource like `resources/read` and also returns a
+//! token derived from a hash of the content. Times in JSON content, the
+//! fields session recordings also leave out of their comparisons such as
+//! `uptime_seconds` and `*_at`, aren't hashed, so a resource that reports
+//! them, like `template://server-status`, only counts as changed when
+//! something else in it changes. Sending that token back as
+//! `if_changed_since` with `wait: true` blocks until the content hashes
+//! differently or `timeout_ms` elapses, then returns the body and a new token.
+//!
+//! Changes are detected by re-reading the resource on a short interval, and
+//! immediately when [`ResourceChanges::notify`] is called, so every resource
+//! supports waiting without opting in.
+//!
+//! STDIO answers one request at a time, so a waiting read holds the session
+//! until it returns: of what the client sends meanwhile, only `ping` is
+//! answered. [`RawMessageHandler`](crate::raw_messages::RawMessageHandler)
+//! caps its waits at [`STDIO_MAX_TIMEOUT_MS`] for that reason, against
+//! [`MAX_TIMEOUT_MS`] elsewhere.
+
+use crate::recording;
+use pulseengine_mcp_protocol::{ReadResourceResult, ResourceContents};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Custom JSON-RPC method for waiting reads
+pub const WAIT_METHOD: &str = "resources/wait";
+
+/// Timeout used when the request doesn't set `timeout_ms`
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// Upper bound on `timeout_ms`
+pub const MAX_TIMEOUT_MS: u64 = 120_000;
+
+/// Upper bound on `timeout_ms` over STDIO, where a wait holds the session
+pub const STDIO_MAX_TIMEOUT_MS: u64 = 10_000;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Parameters of `resources/wait`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaitResourceRequest {
+    pub uri: String,
+    /// Block until the content differs from `if_changed_since`
+    #[serde(default)]
+    pub wait: bool,
+    /// Token from a previous read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_changed_since: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Result of `resources/wait`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaitResourceResult {
+    pub contents: Vec<ResourceContents>,
+    /// Token for the returned content, to pass as `if_changed_since` next time
+    pub token: String,
+    /// False if the wait timed out with the content unchanged
+    pub changed: bool,
+}
+
+/// Token identifying the content of a resource read
+pub fn content_token(result: &ReadResourceResult) -> String {
+    let mut hasher = DefaultHasher::new();
+    for content in &result.contents {
+        content.uri.hash(&mut hasher);
+        content.mime_type.hash(&mut hasher);
+        match content.text.as_deref().map(serde_json::from_str::<Value>) {
+            Some(Ok(mut json)) => {
+                remove_times(&mut json);
+                json.to_string().hash(&mut hasher);
+            }
+            _ => content.text.hash(&mut hasher),
+        }
+        content.blob.hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+fn remove_times(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|key, _| !recording::is_time_key(key));
+            object.values_mut().for_each(remove_times);
+        }
+        Value::Array(items) => items.iter_mut().for_each(remove_times),
+        _ => {}
+    }
+}
+
+/// Wakes waiting reads when server state changes
+///
+/// Waiting reads also re-check on an interval, so calling [`notify`] is an
+/// optimization rather than a requirement.
+///
+/// [`notify`]: ResourceChanges::notify
+#[derive(Debug, Default)]
+pub struct ResourceChanges {
+    notify: Notify,
+}
+
+impl ResourceChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Have every waiting read re-check its resource now
+    pub fn notify(&self) {
+        self.notify.notify_waiters();
+    }
+}
+
+/// Read with `read` until the content no longer matches the request's token,
+/// waiting at most `max_timeout_ms` whatever the request asks for
+pub async fn wait_for_change<F, Fut, E>(
+    request: &WaitResourceRequest,
+    changes: &ResourceChanges,
+    max_timeout_ms: u64,
+    mut read: F,
+) -> Result<WaitResourceResult, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ReadResourceResult, E>>,
+{
+    let timeout_ms = request
+        .timeout_ms
+        .unwrap_or(DEFAULT_TIMEOUT_MS)
+        .min(max_timeout_ms);
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+
+    loop {
+        // Created before reading so a notification during the read isn't missed
+        let notified = changes.notify.notified();
+        let result = read().await?;
+        let token = content_token(&result);
+        let changed = request.if_changed_since.as_deref() != Some(token.as_str());
+
+        if changed || !request.wait || tokio::time::Instant::now() >= deadline {
+            return Ok(WaitResourceResult {
+                contents: result.contents,
+                token,
+                changed,
+            });
+        }
+
+        tokio::select! {
+            _ = notified => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = tokio::time::sleep_until(deadline) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    fn read_of(text: &str) -> ReadResourceResult {
+        ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: "template://test".to_string(),
+                mime_type: Some("application/json".to_string()),
+                text: Some(text.to_string()),
+                blob: None,
+            }],
+        }
+    }
+
+    fn waiting(token: String, timeout_ms: u64) -> WaitResourceRequest {
+        WaitResourceRequest {
+            uri: "template://test".to_string(),
+            wait: true,
+            if_changed_since: Some(token),
+            timeout_ms: Some(timeout_ms),
+        }
+    }
+
+    #[test]
+    fn tokens_leave_out_times() {
+        let status = |uptime: u64, tools: u64| {
+            read_of(
+                &serde_json::json!({
+                    "uptime_seconds": uptime,
+                    "tools_count": tools,
+                    "health": { "checked_at": format!("2026-01-01T00:00:{uptime:02}Z") }
+                })
+                .to_string(),
+            )
+        };
+        assert_eq!(content_token(&status(1, 5)), content_token(&status(9, 5)));
+        assert_ne!(content_token(&status(1, 5)), content_token(&status(1, 6)));
+        assert_ne!(
+            content_token(&read_of("plain")),
+            content_token(&read_of("text"))
+        );
+    }
+
+    #[tokio::test]
+    async fn background_change_ends_the_wait() {
+        let content = Arc::new(Mutex::new("before".to_string()));
+        let changes = Arc::new(ResourceChanges::new());
+        let read = || {
+            let text = content.lock().unwrap().clone();
+            async move { Ok::<_, ()>(read_of(&text)) }
+        };
+        let token = content_token(&read_of("before"));
+
+        let writer = {
+            let content = content.clone();
+            let changes = changes.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                *content.lock().unwrap() = "after".to_string();
+                changes.notify();
+            })
+        };
+        let started = Instant::now();
+        let result = wait_for_change(&waiting(token, 10_000), &changes, MAX_TIMEOUT_MS, read)
+            .await
+            .unwrap();
+        writer.await.unwrap();
+
+        assert!(result.changed);
+        assert_eq!(result.contents[0].text.as_deref(), Some("after"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn unchanged_content_waits_out_the_capped_timeout() {
+        let changes = ResourceChanges::new();
+        let read = || async { Ok::<_, ()>(read_of("same")) };
+        let token = content_token(&read_of("same"));
+
+        let started = Instant::now();
+        let result = wait_for_change(&waiting(token.clone(), 60_000), &changes, 300, read)
+            .await
+            .unwrap();
+        assert!(!result.changed);
+        assert_eq!(result.token, token);
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(300) && waited < Duration::from_secs(5));
+    }
+}
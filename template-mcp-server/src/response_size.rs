@@ -0,0 +1,187 @@
+//! Limiting the size of tool results
+//!
+//! Some clients reject responses over a fixed size. With
+//! `LimitsConfig::max_response_bytes` set, every tool result whose JSON is
+//! longer is handled per the tool's [`OversizePolicy`]: rejected with an
+//! error, or truncated to fit. Tools default to
+//! `LimitsConfig::oversize_responses`; a tool's `oversize` setting (see
+//! [`crate::tool_settings`]) overrides it, e.g. to truncate a listing whose
+//! first entries are still useful.
+//!
+//! A truncated result keeps as much of its content as fits, cutting the last
+//! text that fits partly, and drops its structured content, which can't be
+//! cut and still match its schema. The 0.11 `CallToolResult` has no `_meta`,
+//! so the last content is a [`TruncationNote`] as JSON text, with
+//! `"truncated": true`.
+
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde::{Deserialize, Serialize};
+
+/// How a tool result over the size limit is handled
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizePolicy {
+    /// Fail the call with an error giving both sizes
+    #[default]
+    Reject,
+    /// Cut the content to fit and mark the result as truncated
+    Truncate,
+}
+
+/// The policy for results of `tool`
+pub fn policy_for(tool: &str, default: OversizePolicy) -> OversizePolicy {
+    crate::tool_settings::settings_of(tool)
+        .oversize
+        .unwrap_or(default)
+}
+
+/// Last content of a truncated result
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TruncationNote {
+    pub truncated: bool,
+    pub note: String,
+    /// Size of the whole result in bytes
+    pub original_bytes: usize,
+    pub max_bytes: usize,
+}
+
+/// `result` within `max_bytes` of JSON, per `policy`
+pub fn limit(
+    tool: &str,
+    result: CallToolResult,
+    max_bytes: usize,
+    policy: OversizePolicy,
+) -> Result<CallToolResult, String> {
+    let original_bytes = size_of(&result);
+    if original_bytes <= max_bytes {
+        return Ok(result);
+    }
+    match policy {
+        OversizePolicy::Reject => Err(format!(
+            "Tool '{tool}' returned {original_bytes} bytes, over the {max_bytes} byte response limit"
+        )),
+        OversizePolicy::Truncate => Ok(truncate(result, original_bytes, max_bytes)),
+    }
+}
+
+fn truncate(result: CallToolResult, original_bytes: usize, max_bytes: usize) -> CallToolResult {
+    let note = TruncationNote {
+        truncated: true,
+        note: format!(
+            "Result truncated: it was {original_bytes} bytes, over the {max_bytes} byte limit"
+        ),
+        original_bytes,
+        max_bytes,
+    };
+    let note = Content::text(serde_json::to_string(&note).unwrap_or_default());
+    let mut truncated = CallToolResult {
+        content: vec![note],
+        is_error: result.is_error,
+        structured_content: None,
+    };
+
+    // Contents are kept in order, up to the first that doesn't fit whole
+    let mut kept = 0;
+    for content in result.content {
+        // Each content kept adds itself and a comma
+        let budget = max_bytes.saturating_sub(size_of(&truncated) + 1);
+        if size_of(&content) <= budget {
+            truncated.content.insert(kept, content);
+            kept += 1;
+            continue;
+        }
+        if let Content::Text { text } = &content {
+            if let Some(cut) = longest_fitting_prefix(text, budget) {
+                truncated.content.insert(kept, cut);
+            }
+        }
+        break;
+    }
+    truncated
+}
+
+/// The longest prefix of `text` whose text content serializes within `budget`
+/// bytes, if any is not empty
+fn longest_fitting_prefix(text: &str, budget: usize) -> Option<Content> {
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).skip(1).collect();
+    // Longer prefixes never serialize shorter, so the fitting ones come first
+    let fitting =
+        boundaries.partition_point(|end| size_of(&Content::text(&text[..*end])) <= budget);
+    (fitting > 0).then(|| Content::text(&text[..boundaries[fitting - 1]]))
+}
+
+fn size_of<T: Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(0, |json| json.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_of(result: &CallToolResult) -> TruncationNote {
+        match result.content.last() {
+            Some(Content::Text { text }) => serde_json::from_str(text).unwrap(),
+            other => panic!("not a note: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn passes_results_within_the_limit() {
+        let result = CallToolResult::text("short");
+        let limited = limit("echo", result.clone(), 1000, OversizePolicy::Reject).unwrap();
+        assert_eq!(size_of(&limited), size_of(&result));
+    }
+
+    #[test]
+    fn rejects_oversize_results() {
+        let result = CallToolResult::text("x".repeat(200));
+        let size = size_of(&result);
+        assert_eq!(
+            limit("echo", result, 100, OversizePolicy::Reject).unwrap_err(),
+            format!("Tool 'echo' returned {size} bytes, over the 100 byte response limit")
+        );
+    }
+
+    #[test]
+    fn truncates_to_fit() {
+        let result = CallToolResult {
+            content: vec![Content::text("first"), Content::text("é".repeat(200))],
+            is_error: Some(false),
+            structured_content: Some(serde_json::json!({ "items": [] })),
+        };
+        let original_bytes = size_of(&result);
+        let truncated = limit("list_data", result, 250, OversizePolicy::Truncate).unwrap();
+
+        assert!(size_of(&truncated) <= 250, "{}", size_of(&truncated));
+        assert_eq!(truncated.structured_content, None);
+        assert_eq!(truncated.content.len(), 3);
+        assert_eq!(
+            serde_json::to_value(&truncated.content[0]).unwrap(),
+            serde_json::json!({ "type": "text", "text": "first" })
+        );
+        match &truncated.content[1] {
+            Content::Text { text } => assert!(!text.is_empty() && text.len() < 400),
+            other => panic!("not text: {other:?}"),
+        }
+        let note = note_of(&truncated);
+        assert!(note.truncated);
+        assert_eq!(note.original_bytes, original_bytes);
+        assert_eq!(note.max_bytes, 250);
+    }
+
+    #[test]
+    fn tool_settings_override_the_default_policy() {
+        assert_eq!(
+            policy_for("list_data", OversizePolicy::Reject),
+            OversizePolicy::Truncate
+        );
+        assert_eq!(
+            policy_for("echo", OversizePolicy::Truncate),
+            OversizePolicy::Truncate
+        );
+        assert_eq!(
+            policy_for("echo", OversizePolicy::Reject),
+            OversizePolicy::Reject
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Tool results served as downloadable resources
+//!
+//! A tool that produces more than a client should see inline (a full report,
+//! an export) returns [`ContentWithResource`]: the content to show, plus a
+//! [`ResourceDef`] with the full data. The backend registers the data as a
+//! resource under [`RESULT_URI_PREFIX`] and replaces the result with the
+//! content, a text line naming the resource URI and a [`ResourceLink`] as
+//! structured content. The resource can be read until its TTL
+//! ([`DEFAULT_RESOURCE_TTL`] unless the definition sets one) runs out, after
+//! which reads fail as not found.
+//!
+//! The 0.11 `Content` type has no `resource_link` variant, so the link is
+//! carried as structured content in the shape of one.
+
+use crate::clock::Clock;
+use crate::ids::IdGenerator;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use pulseengine_mcp_protocol::{
+    CallToolResult, Content, ReadResourceResult, Resource, ResourceContents,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// URI prefix of result resources
+pub const RESULT_URI_PREFIX: &str = "template://results/";
+
+/// How long result resources stay readable by default
+pub const DEFAULT_RESOURCE_TTL: chrono::Duration = chrono::Duration::minutes(15);
+
+// Rendered wrappers always start with their tag, which keeps detection cheap
+const CONTENT_WITH_RESOURCE_PREFIX: &str = r#"{"type":"content_with_resource""#;
+
+/// Data to serve as a resource alongside a tool result
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResourceDef {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Base64-encoded binary data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+    /// Overrides [`DEFAULT_RESOURCE_TTL`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_seconds: Option<i64>,
+}
+
+impl ResourceDef {
+    pub fn text(
+        name: impl Into<String>,
+        mime_type: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            mime_type: mime_type.into(),
+            text: Some(text.into()),
+            blob: None,
+            ttl_seconds: None,
+        }
+    }
+
+    pub fn blob(name: impl Into<String>, mime_type: impl Into<String>, bytes: &[u8]) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            mime_type: mime_type.into(),
+            text: None,
+            blob: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+            ttl_seconds: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Keep the resource readable for `ttl` instead of the default
+    pub fn with_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.ttl_seconds = Some(ttl.num_seconds());
+        self
+    }
+}
+
+/// A tool result shown inline, with the full data served as a resource
+#[derive(Clone)]
+pub struct ContentWithResource {
+    pub content: Vec<Content>,
+    pub resource: ResourceDef,
+}
+
+impl ContentWithResource {
+    pub fn new(content: Vec<Content>, resource: ResourceDef) -> Self {
+        Self { content, resource }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "content_with_resource")]
+struct Rendered {
+    content: Vec<Content>,
+    resource: ResourceDef,
+}
+
+impl std::fmt::Debug for ContentWithResource {
+    /// Tool results are rendered with `Debug`, so this writes the shape
+    /// [`unpack`] recognizes
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rendered = Rendered {
+            content: self.content.clone(),
+            resource: self.resource.clone(),
+        };
+        match serde_json::to_string(&rendered) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+/// Link to a registered result resource, in the shape of a `resource_link`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename = "resource_link", rename_all = "camelCase")]
+pub struct ResourceLink {
+    pub uri: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub mime_type: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Result resources registered by tool calls, until they expire
+pub struct ResultResources {
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    entries: Mutex<HashMap<String, Stored>>,
+}
+
+struct Stored {
+    definition: ResourceDef,
+    expires_at: DateTime<Utc>,
+}
+
+impl ResultResources {
+    pub fn new(clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            clock,
+            ids,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Serve `definition` under a new URI until its TTL runs out
+    pub fn register(&self, definition: ResourceDef) -> ResourceLink {
+        let now = self.clock.now();
+        let ttl = definition
+            .ttl_seconds
+            .map_or(DEFAULT_RESOURCE_TTL, chrono::Duration::seconds);
+        let expires_at = now + ttl;
+        let uri = format!("{RESULT_URI_PREFIX}{:016x}", self.ids.next_id());
+        let link = ResourceLink {
+            uri: uri.clone(),
+            name: definition.name.clone(),
+            description: definition.description.clone(),
+            mime_type: definition.mime_type.clone(),
+            expires_at,
+        };
+
+        let mut entries = self.lock();
+        entries.retain(|_, stored| stored.expires_at > now);
+        entries.insert(
+            uri,
+            Stored {
+                definition,
+                expires_at,
+            },
+        );
+        link
+    }
+
+    /// The contents of `uri`, unless it is unknown or has expired
+    pub fn read(&self, uri: &str) -> Option<ReadResourceResult> {
+        let now = self.clock.now();
+        let mut entries = self.lock();
+        entries.retain(|_, stored| stored.expires_at > now);
+        let definition = &entries.get(uri)?.definition;
+        Some(ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(definition.mime_type.clone()),
+                text: definition.text.clone(),
+                blob: definition.blob.clone(),
+            }],
+        })
+    }
+
+    /// How long until the resource behind `link` expires
+    pub fn time_left(&self, link: &ResourceLink) -> std::time::Duration {
+        (link.expires_at - self.clock.now())
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// Drop the resources that have expired, returning how many
+    pub fn remove_expired(&self) -> usize {
+        let now = self.clock.now();
+        let mut entries = self.lock();
+        let before = entries.len();
+        entries.retain(|_, stored| stored.expires_at > now);
+        before - entries.len()
+    }
+
+    /// The resources that haven't expired, ordered by URI
+    pub fn list(&self) -> Vec<Resource> {
+        let now = self.clock.now();
+        let mut resources: Vec<Resource> = self
+            .lock()
+            .iter()
+            .filter(|(_, stored)| stored.expires_at > now)
+            .map(|(uri, stored)| Resource {
+                uri: uri.clone(),
+                name: stored.definition.name.clone(),
+                description: stored.definition.description.clone(),
+                mime_type: Some(stored.definition.mime_type.clone()),
+                annotations: None,
+                raw: None,
+            })
+            .collect();
+        resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+        resources
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Stored>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether `uri` names a result resource
+pub fn matches(uri: &str) -> bool {
+    uri.starts_with(RESULT_URI_PREFIX)
+}
+
+/// Register the resource of a rendered [`ContentWithResource`] and link to
+/// it, returning the link if the result had one
+pub fn unpack(result: &mut CallToolResult, resources: &ResultResources) -> Option<ResourceLink> {
+    let (index, rendered) = result
+        .content
+        .iter()
+        .enumerate()
+        .find_map(|(index, content)| match content {
+            Content::Text { text } if text.starts_with(CONTENT_WITH_RESOURCE_PREFIX) => {
+                serde_json::from_str::<Rendered>(text)
+                    .ok()
+                    .map(|rendered| (index, rendered))
+            }
+            _ => None,
+        })?;
+
+    let link = resources.register(rendered.resource);
+    let link_text = Content::text(format!("Full result: {}", link.uri));
+    result.content.splice(
+        index..=index,
+        rendered
+            .content
+            .into_iter()
+            .chain(std::iter::once(link_text)),
+    );
+    result.structured_content = serde_json::to_value(&link).ok();
+    Some(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::ids::SequentialIds;
+    use serde_json::json;
+
+    fn resources() -> (Arc<ManualClock>, ResultResources) {
+        let clock = Arc::new(ManualClock::new("2026-01-01T00:00:00Z".parse().unwrap()));
+        let resources =
+            ResultResources::new(clock.clone(), Arc::new(SequentialIds::starting_at(1)));
+        (clock, resources)
+    }
+
+    #[test]
+    fn serves_resources_until_they_expire() {
+        let (clock, resources) = resources();
+        let report = resources.register(ResourceDef::text("report", "text/csv", "a,b\n1,2"));
+        let logo = resources.register(
+            ResourceDef::blob("logo", "image/png", &[0, 1, 2]).with_ttl(chrono::Duration::hours(1)),
+        );
+        assert_eq!(report.uri, "template://results/0000000000000001");
+        assert!(matches(&report.uri));
+        assert_eq!(
+            resources.time_left(&report),
+            std::time::Duration::from_secs(15 * 60)
+        );
+
+        let contents = &resources.read(&report.uri).unwrap().contents[0];
+        assert_eq!(contents.text.as_deref(), Some("a,b\n1,2"));
+        assert_eq!(contents.mime_type.as_deref(), Some("text/csv"));
+        assert_eq!(
+            resources.read(&logo.uri).unwrap().contents[0]
+                .blob
+                .as_deref(),
+            Some("AAEC")
+        );
+
+        clock.advance(chrono::Duration::minutes(15));
+        assert!(resources.read(&report.uri).is_none());
+        assert_eq!(resources.time_left(&report), std::time::Duration::ZERO);
+        let listed: Vec<String> = resources.list().into_iter().map(|r| r.uri).collect();
+        assert_eq!(listed, [logo.uri]);
+
+        clock.advance(chrono::Duration::hours(1));
+        assert_eq!(resources.remove_expired(), 1);
+        assert!(resources.list().is_empty());
+    }
+
+    #[test]
+    fn unpacks_rendered_results_into_links() {
+        let (_, resources) = resources();
+        let rendered = ContentWithResource::new(
+            vec![Content::text("3 rows")],
+            ResourceDef::text("rows", "application/json", "[1,2,3]").with_description("All rows"),
+        );
+        let mut result = CallToolResult::success(vec![Content::text(format!("{rendered:?}"))]);
+
+        let link = unpack(&mut result, &resources).unwrap();
+        assert_eq!(link.description.as_deref(), Some("All rows"));
+        assert_eq!(
+            serde_json::to_value(&result.content).unwrap(),
+            json!([
+                { "type": "text", "text": "3 rows" },
+                { "type": "text", "text": format!("Full result: {}", link.uri) }
+            ])
+        );
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["type"], "resource_link");
+        assert_eq!(structured["mimeType"], "application/json");
+        assert!(resources.read(&link.uri).is_some());
+    }
+
+    #[test]
+    fn leaves_plain_results_alone() {
+        let (_, resources) = resources();
+        let mut result = CallToolResult::success(vec![Content::text("{\"type\":\"other\"}")]);
+        assert!(unpack(&mut result, &resources).is_none());
+        assert!(result.structured_content.is_none());
+        assert!(resources.list().is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! The client's filesystem roots
+//!
+//! MCP clients expose the directories a server may work in as "roots", sent
+//! in reply to a `roots/list` request and announced with
+//! `notifications/roots/list_changed` when they change. [`ClientRoots`] caches
+//! them for tools, which read them with `TemplateMcpServer::roots`.
+//!
+//! The 0.11 transports can't send requests to the client, so the roots are
+//! fetched through a [`RootsSource`] attached with
+//! `TemplateBackend::with_roots_source`. The backend fetches them before the
+//! first tool call and again whenever the client sends `list_changed`.
+//! Without a source, the list stays empty. `list_roots` is only served to
+//! clients that advertise the `roots` capability; see
+//! [`crate::client_capabilities`].
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Notification a client sends when its roots change
+pub const LIST_CHANGED_NOTIFICATION: &str = "notifications/roots/list_changed";
+
+/// A directory the client lets the server work in
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Root {
+    /// A `file://` URI
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Answers `roots/list` on behalf of the client
+#[async_trait]
+pub trait RootsSource: Send + Sync {
+    async fn list_roots(&self) -> anyhow::Result<Vec<Root>>;
+}
+
+/// The most recently fetched roots
+#[derive(Default)]
+pub struct ClientRoots {
+    roots: RwLock<Vec<Root>>,
+    source: RwLock<Option<Arc<dyn RootsSource>>>,
+    fetched: AtomicBool,
+}
+
+impl ClientRoots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch roots from `source` from now on
+    pub fn set_source(&self, source: Arc<dyn RootsSource>) {
+        *self
+            .source
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(source);
+        self.fetched.store(false, Ordering::Release);
+    }
+
+    /// The cached roots
+    pub fn roots(&self) -> Vec<Root> {
+        self.roots
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Fetch the roots from the source, keeping the cached ones on failure
+    pub async fn refresh(&self) -> anyhow::Result<()> {
+        let source = self
+            .source
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        let Some(source) = source else {
+            return Ok(());
+        };
+
+        let roots = source.list_roots().await?;
+        *self
+            .roots
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = roots;
+        self.fetched.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Fetch the roots unless they were fetched before
+    pub async fn ensure_fetched(&self) -> anyhow::Result<()> {
+        if self.fetched.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        self.refresh().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Serves `roots`, failing once they run out
+    struct FakeSource {
+        roots: Vec<Vec<Root>>,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl RootsSource for FakeSource {
+        async fn list_roots(&self) -> anyhow::Result<Vec<Root>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            self.roots
+                .get(call)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("client gone"))
+        }
+    }
+
+    fn root(uri: &str) -> Root {
+        Root {
+            uri: uri.to_string(),
+            name: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_once_until_refreshed() {
+        let source = Arc::new(FakeSource {
+            roots: vec![vec![root("file:///a")], vec![root("file:///b")]],
+            calls: AtomicUsize::new(0),
+        });
+        let roots = ClientRoots::new();
+        roots.set_source(source.clone());
+
+        roots.ensure_fetched().await.unwrap();
+        roots.ensure_fetched().await.unwrap();
+        assert_eq!(roots.roots(), [root("file:///a")]);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+
+        roots.refresh().await.unwrap();
+        assert_eq!(roots.roots(), [root("file:///b")]);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_cached_roots_when_a_fetch_fails() {
+        let roots = ClientRoots::new();
+        roots.set_source(Arc::new(FakeSource {
+            roots: vec![vec![root("file:///a")]],
+            calls: AtomicUsize::new(0),
+        }));
+        roots.refresh().await.unwrap();
+        assert!(roots.refresh().await.is_err());
+        assert_eq!(roots.roots(), [root("file:///a")]);
+    }
+
+    #[tokio::test]
+    async fn stays_empty_without_a_source() {
+        let roots = ClientRoots::new();
+        roots.ensure_fetched().await.unwrap();
+        assert!(roots.roots().is_empty());
+    }
+}
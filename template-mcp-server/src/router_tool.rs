@@ -0,0 +1,170 @@
+//! Every tool behind one `invoke` tool, for clients that load few tools
+//!
+//! Some clients load only a handful of tools. With
+//! `ToolsConfig::router_tool` set, e.g. by `MCP_ROUTER_TOOL=1`, `tools/list`
+//! lists [`ROUTER_TOOL`] alone, and a call names the tool it stands for:
+//!
+//! ```json
+//! { "name": "invoke", "arguments": { "tool": "add_numbers", "arguments": { "a": 2, "b": 3 } } }
+//! ```
+//!
+//! The backend turns it into the call of that tool, with every check the
+//! tool's own call gets, and returns that call's result. Its schema
+//! enumerates the tools a client could call, the ones `tools/list` would
+//! otherwise show it, and its description lists each with its parameters.
+//! Tools stay callable by their own name.
+//!
+//! This is a workaround for tool-count limits. Clients lose the tools'
+//! schemas, and with them argument hints and output schemas, so only turn it
+//! on for clients that need it.
+
+use pulseengine_mcp_protocol::{CallToolRequestParam, Error, Tool as ToolDefinition};
+use serde_json::{json, Value};
+
+pub const ROUTER_TOOL: &str = "invoke";
+
+/// The definition of the router tool for a client that may call `tools`
+pub fn definition(tools: &[ToolDefinition]) -> ToolDefinition {
+    let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+    let mut description = String::from(
+        "Call one of this server's tools by name. The server offers its tools through \
+         this one tool: set `tool` to a name below and `arguments` to that tool's \
+         arguments, and the result is the tool's own.\n\nTools:",
+    );
+    for tool in tools {
+        let summary = tool.description.lines().next().unwrap_or_default();
+        description.push_str(&format!("\n- {}({}): {summary}", tool.name, params(tool)));
+    }
+    ToolDefinition {
+        name: ROUTER_TOOL.to_string(),
+        description,
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "tool": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Name of the tool to call"
+                },
+                "arguments": {
+                    "type": "object",
+                    "description": "Arguments of the tool; none if omitted"
+                }
+            },
+            "required": ["tool"]
+        }),
+        output_schema: None,
+    }
+}
+
+/// The parameters of `tool`, optional ones marked with `?`
+fn params(tool: &ToolDefinition) -> String {
+    let required: Vec<&str> = tool
+        .input_schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    tool.input_schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| {
+            properties
+                .keys()
+                .map(|name| {
+                    if required.contains(&name.as_str()) {
+                        name.clone()
+                    } else {
+                        format!("{name}?")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default()
+}
+
+/// The call of the tool an `invoke` call with `arguments` stands for
+pub fn routed_call(arguments: Option<&Value>) -> Result<CallToolRequestParam, Error> {
+    let name = arguments
+        .and_then(|args| args.get("tool"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::invalid_params("Missing required parameter: tool"))?;
+    if name == ROUTER_TOOL {
+        return Err(Error::invalid_params(format!(
+            "'{ROUTER_TOOL}' can't call itself"
+        )));
+    }
+    let arguments = match arguments.and_then(|args| args.get("arguments")) {
+        None | Some(Value::Null) => None,
+        Some(arguments @ Value::Object(_)) => Some(arguments.clone()),
+        Some(_) => return Err(Error::invalid_params("'arguments' must be an object")),
+    };
+    Ok(CallToolRequestParam {
+        name: name.to_string(),
+        arguments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_each_tool_with_its_parameters() {
+        let add = ToolDefinition {
+            name: "add_numbers".to_string(),
+            description: "Add two numbers together\nMore detail".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "a": {}, "b": {}, "label": {} },
+                "required": ["a", "b"]
+            }),
+            output_schema: None,
+        };
+        let router = definition(&[add]);
+        assert_eq!(router.name, ROUTER_TOOL);
+        assert!(
+            router
+                .description
+                .ends_with("\n- add_numbers(a, b, label?): Add two numbers together"),
+            "{}",
+            router.description
+        );
+        assert_eq!(
+            router.input_schema["properties"]["tool"]["enum"],
+            json!(["add_numbers"])
+        );
+    }
+
+    #[test]
+    fn routes_calls_to_the_named_tool() {
+        let call = routed_call(Some(
+            &json!({ "tool": "add_numbers", "arguments": { "a": 2, "b": 3 } }),
+        ))
+        .unwrap();
+        assert_eq!(call.name, "add_numbers");
+        assert_eq!(call.arguments, Some(json!({ "a": 2, "b": 3 })));
+        assert_eq!(
+            routed_call(Some(&json!({ "tool": "get_status" })))
+                .unwrap()
+                .arguments,
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_bad_routed_calls() {
+        for (arguments, message) in [
+            (json!({}), "Missing required parameter: tool"),
+            (json!({ "tool": "invoke" }), "'invoke' can't call itself"),
+            (
+                json!({ "tool": "echo", "arguments": [1] }),
+                "'arguments' must be an object",
+            ),
+        ] {
+            let error = routed_call(Some(&arguments)).unwrap_err();
+            assert_eq!(error.message, message);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Safe mode, which turns off every destructive tool
+//!
+//! Pointing a server at production data shouldn't take a review of every
+//! tool. With `AccessConfig::safe_mode` set, e.g. by `MCP_SAFE_MODE=1`, the
+//! tools that destroy data are left out of `tools/list`, and calls to them
+//! fail before they run with an error saying the tool is disabled in safe
+//! mode. `AccessConfig::safe_mode_effects` widens this to every tool with one
+//! of the listed effects (see [`crate::effects`]), e.g. `network` to keep a
+//! server from reaching out as well. Other tools and resources stay
+//! available.
+//!
+//! A tool is destructive if its `destructive` setting is on (see
+//! [`crate::tool_settings`]) or it needs confirmation before it runs (see
+//! [`crate::confirmation`]). Unlike the `destructiveHint` annotation, which
+//! the 0.11 tool definitions don't have anyway, this is enforced by the
+//! server.
+
+use crate::{confirmation, effects, tool_settings, ServerConfig};
+
+pub fn is_destructive(tool: &str) -> bool {
+    tool_settings::settings_of(tool).destructive || confirmation::requirement(tool).is_some()
+}
+
+/// Whether `config`'s safe mode turns `tool` off
+pub fn is_disabled(tool: &str, config: &ServerConfig) -> bool {
+    config.access.safe_mode
+        && (is_destructive(tool)
+            || effects::effects_of(tool)
+                .iter()
+                .any(|effect| config.access.safe_mode_effects.contains(effect)))
+}
+
+/// Reject a call to a tool `config`'s safe mode turns off
+pub fn check_access(tool: &str, config: &ServerConfig) -> Result<(), String> {
+    if is_disabled(tool, config) {
+        Err(format!("Tool '{tool}' is disabled in safe mode"))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::ToolEffect;
+
+    fn safe_mode(effects: Vec<ToolEffect>) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.access.safe_mode = true;
+        config.access.safe_mode_effects = effects;
+        config
+    }
+
+    #[test]
+    fn blocks_destructive_tools() {
+        let config = safe_mode(Vec::new());
+        for tool in [
+            "clear_data",
+            "delete_data",
+            "import_snapshot",
+            "reset_tool_stats",
+        ] {
+            assert_eq!(
+                check_access(tool, &config),
+                Err(format!("Tool '{tool}' is disabled in safe mode"))
+            );
+        }
+        assert_eq!(check_access("list_data", &config), Ok(()));
+        assert_eq!(check_access("fetch_upstream", &config), Ok(()));
+    }
+
+    #[test]
+    fn blocks_tools_with_the_listed_effects() {
+        let config = safe_mode(vec![ToolEffect::Network]);
+        assert!(is_disabled("fetch_upstream", &config));
+        assert!(!is_disabled("list_data", &config));
+    }
+
+    #[test]
+    fn blocks_nothing_when_off() {
+        let mut config = ServerConfig::default();
+        config.access.safe_mode_effects = vec![ToolEffect::Network];
+        assert!(!is_disabled("clear_data", &config));
+        assert!(!is_disabled("fetch_upstream", &config));
+    }
+}
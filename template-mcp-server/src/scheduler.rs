@@ -0,0 +1,227 @@
+//! Recurring background tasks
+//!
+//! [`Scheduler::every`] runs an async task at a fixed interval on the tokio
+//! runtime, for periodic work such as refreshing a cache or purging expired
+//! sessions. The first run happens one interval after scheduling, and a run
+//! that takes longer than the interval delays the next one rather than
+//! overlapping it. A failing or panicking run is logged and the task keeps
+//! its schedule.
+//!
+//! [`Scheduler::shutdown`] stops every task: runs in progress finish, no new
+//! ones start. Intervals use tokio's timer, so tests can drive them with a
+//! paused clock (`tokio::time::pause` and `advance`) instead of waiting.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// How a scheduled task has fared so far
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskStats {
+    pub name: String,
+    pub interval: Duration,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+/// Runs recurring tasks until shut down
+pub struct Scheduler {
+    shutdown: watch::Sender<bool>,
+    tasks: Mutex<Vec<ScheduledTask>>,
+}
+
+struct ScheduledTask {
+    name: String,
+    interval: Duration,
+    counters: Arc<Counters>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct Counters {
+    runs: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            shutdown: watch::channel(false).0,
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Run `task` every `interval` until the scheduler shuts down
+    ///
+    /// Must be called within a tokio runtime. Tasks scheduled after shutdown
+    /// never run.
+    pub fn every<F, Fut>(&self, name: impl Into<String>, interval: Duration, task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let counters = Arc::new(Counters::default());
+        let handle = tokio::spawn(run_every(
+            name.clone(),
+            interval,
+            task,
+            counters.clone(),
+            self.shutdown.subscribe(),
+        ));
+        self.lock().push(ScheduledTask {
+            name,
+            interval,
+            counters,
+            handle: Some(handle),
+        });
+    }
+
+    /// Stop all tasks, waiting for runs in progress to finish
+    pub async fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+        let handles: Vec<JoinHandle<()>> = self
+            .lock()
+            .iter_mut()
+            .filter_map(|task| task.handle.take())
+            .collect();
+        for handle in handles {
+            let _ = handle.await;
+        }
+    }
+
+    pub fn stats(&self) -> Vec<TaskStats> {
+        self.lock()
+            .iter()
+            .map(|task| TaskStats {
+                name: task.name.clone(),
+                interval: task.interval,
+                runs: task.counters.runs.load(Ordering::Relaxed),
+                failures: task.counters.failures.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ScheduledTask>> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+async fn run_every<F, Fut>(
+    name: String,
+    period: Duration,
+    task: F,
+    counters: Arc<Counters>,
+    mut shutdown: watch::Receiver<bool>,
+) where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        if *shutdown.borrow() {
+            break;
+        }
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+
+        // A separate task, so a panicking run doesn't end the loop
+        let outcome = tokio::spawn(task()).await;
+        counters.runs.fetch_add(1, Ordering::Relaxed);
+        let error = match outcome {
+            Ok(Ok(())) => continue,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
+        counters.failures.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!("Scheduled task {name} failed: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(20);
+
+    /// Wait until every task of `scheduler` ran at least `runs` times
+    async fn ran(scheduler: &Scheduler, runs: u64) {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while scheduler.stats().iter().any(|stats| stats.runs < runs) {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "{:?}",
+                scheduler.stats()
+            );
+            tokio::time::sleep(INTERVAL).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_tasks_until_shutdown() {
+        let scheduler = Scheduler::new();
+        let runs = Arc::new(AtomicU64::new(0));
+        let counted = runs.clone();
+        scheduler.every("count", INTERVAL, move || {
+            let counted = counted.clone();
+            async move {
+                counted.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+        });
+        ran(&scheduler, 2).await;
+        scheduler.shutdown().await;
+
+        let after_shutdown = runs.load(Ordering::Relaxed);
+        assert!(after_shutdown >= 2, "{after_shutdown} runs");
+        tokio::time::sleep(INTERVAL * 3).await;
+        assert_eq!(runs.load(Ordering::Relaxed), after_shutdown);
+        assert_eq!(
+            scheduler.stats(),
+            [TaskStats {
+                name: "count".to_string(),
+                interval: INTERVAL,
+                runs: after_shutdown,
+                failures: 0,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_the_schedule_of_failing_tasks() {
+        let scheduler = Scheduler::new();
+        scheduler.every("fail", INTERVAL, || async { anyhow::bail!("no luck") });
+        scheduler.every("panic", INTERVAL, || async { panic!("in a run") });
+        ran(&scheduler, 2).await;
+        scheduler.shutdown().await;
+
+        for stats in scheduler.stats() {
+            assert!(stats.runs >= 2, "{stats:?}");
+            assert_eq!(stats.failures, stats.runs, "{stats:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn never_runs_tasks_scheduled_after_shutdown() {
+        let scheduler = Scheduler::new();
+        scheduler.shutdown().await;
+        scheduler.every("late", INTERVAL, || async { Ok(()) });
+        tokio::time::sleep(INTERVAL * 3).await;
+        assert_eq!(scheduler.stats()[0].runs, 0);
+    }
+}
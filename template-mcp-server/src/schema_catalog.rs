@@ -0,0 +1,130 @@
+//! Tool schemas built once and served from a cache
+//!
+//! A listed tool's schema is the one its definition declares, decorated by
+//! every per-tool setting: defaults, examples, groups, effects, costs, output
+//! schemas and so on. Doing that on every `tools/list` puts work proportional
+//! to the tool set on the handshake path. The backend builds the decorated
+//! definitions of all its tools into a [`SchemaCatalog`] when it is
+//! constructed, and `tools/list`, the schema resources and the playground
+//! serve them from there, only leaving out the tools this client doesn't get.
+//!
+//! The catalog is immutable: while neither the tools nor the configuration
+//! change, every listing gets the same catalog,
+//! [`TemplateBackend::schema_catalog`](crate::TemplateBackend::schema_catalog)
+//! returning the same `Arc`. Registering a tool, e.g. from a plugin, or
+//! reloading the configuration, which some decorations read, makes the next
+//! listing build a new one.
+
+use pulseengine_mcp_protocol::Tool as ToolDefinition;
+use std::sync::{Arc, RwLock};
+
+/// What a catalog was built from: the tool registry's and the configuration's
+/// generation
+pub type CatalogVersion = (u64, u64);
+
+/// Decorated definitions of every registered tool, in registration order
+#[derive(Debug, Clone)]
+pub struct SchemaCatalog {
+    tools: Vec<ToolDefinition>,
+    version: CatalogVersion,
+}
+
+impl SchemaCatalog {
+    pub fn tools(&self) -> &[ToolDefinition] {
+        &self.tools
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ToolDefinition> {
+        self.tools.iter().find(|tool| tool.name == name)
+    }
+
+    pub fn version(&self) -> CatalogVersion {
+        self.version
+    }
+}
+
+/// The catalog of a backend, rebuilt when what it was built from changes
+#[derive(Debug, Default)]
+pub struct SchemaCatalogCache {
+    current: RwLock<Option<Arc<SchemaCatalog>>>,
+}
+
+impl SchemaCatalogCache {
+    /// The catalog for `version`, built with `build` unless it is cached
+    pub fn get_or_build(
+        &self,
+        version: CatalogVersion,
+        build: impl FnOnce() -> Vec<ToolDefinition>,
+    ) -> Arc<SchemaCatalog> {
+        if let Some(catalog) = self.cached(version) {
+            return catalog;
+        }
+        let mut current = self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Another listing may have built it while this one waited
+        if let Some(catalog) = current.as_ref().filter(|c| c.version == version) {
+            return catalog.clone();
+        }
+        let catalog = Arc::new(SchemaCatalog {
+            tools: build(),
+            version,
+        });
+        *current = Some(catalog.clone());
+        catalog
+    }
+
+    fn cached(&self, version: CatalogVersion) -> Option<Arc<SchemaCatalog>> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .filter(|catalog| catalog.version == version)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn builds_once_per_version() {
+        let cache = SchemaCatalogCache::default();
+        let mut builds = 0;
+        let first = cache.get_or_build((1, 1), || {
+            builds += 1;
+            vec![tool("echo")]
+        });
+        let again = cache.get_or_build((1, 1), || {
+            builds += 1;
+            Vec::new()
+        });
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(builds, 1);
+        assert!(first.get("echo").is_some());
+        assert!(first.get("other").is_none());
+    }
+
+    #[test]
+    fn rebuilds_when_the_tools_or_config_change() {
+        let cache = SchemaCatalogCache::default();
+        let first = cache.get_or_build((1, 1), || vec![tool("echo")]);
+        let new_tool = cache.get_or_build((2, 1), || vec![tool("echo"), tool("plugin")]);
+        assert_eq!(new_tool.tools().len(), 2);
+        let new_config = cache.get_or_build((2, 2), || vec![tool("echo")]);
+        assert_eq!(new_config.version(), (2, 2));
+        assert!(!Arc::ptr_eq(&first, &new_config));
+    }
+}
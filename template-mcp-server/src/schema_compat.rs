@@ -0,0 +1,399 @@
+//! Backward-compatibility checks for tool schemas
+//!
+//! [`schema_diff`] compares two versions of a tool's input schema and
+//! classifies each change from the point of view of existing clients:
+//!
+//! - **Additive**: every request valid under the old schema is still valid,
+//!   e.g. a new optional parameter, a widened type, a parameter that is no
+//!   longer required, or a new enum value.
+//! - **Breaking**: some old request may now be rejected, e.g. a removed
+//!   parameter, a new required parameter, a narrowed type or a removed enum
+//!   value.
+//! - **Cosmetic**: documentation-only changes (`description`, `default`,
+//!   `examples`, `title`).
+//!
+//! [`tools_diff`] applies this to whole `tools/list` results, as written by
+//! `cargo run --bin schema-check -- --dump`.
+
+use pulseengine_mcp_protocol::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/// How a change affects existing clients
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Cosmetic,
+    Additive,
+    Breaking,
+}
+
+/// One classified difference between two schemas
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// Location of the change, e.g. `echo.properties.message`
+    pub path: String,
+    pub kind: ChangeKind,
+    pub description: String,
+}
+
+/// All classified differences between two schemas
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    pub changes: Vec<SchemaChange>,
+}
+
+impl CompatReport {
+    pub fn is_breaking(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| change.kind == ChangeKind::Breaking)
+    }
+
+    /// Whether the schemas are identical apart from cosmetic changes
+    pub fn is_unchanged(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|change| change.kind == ChangeKind::Cosmetic)
+    }
+
+    /// The most severe change, or `None` for identical schemas
+    pub fn severity(&self) -> Option<ChangeKind> {
+        self.changes.iter().map(|change| change.kind).max()
+    }
+
+    fn push(&mut self, path: &str, kind: ChangeKind, description: impl Into<String>) {
+        self.changes.push(SchemaChange {
+            path: path.to_string(),
+            kind,
+            description: description.into(),
+        });
+    }
+}
+
+const COSMETIC_KEYWORDS: &[&str] = &["description", "default", "examples", "title"];
+
+/// Classify the differences between two versions of an input schema
+pub fn schema_diff(old: &Value, new: &Value) -> CompatReport {
+    let mut report = CompatReport::default();
+    diff_schema("$", old, new, &mut report);
+    report
+}
+
+/// Classify the differences between two tool lists
+///
+/// Removing a tool is breaking and adding one is additive; tools present in
+/// both lists are compared with [`schema_diff`], with paths prefixed by the
+/// tool name.
+pub fn tools_diff(old: &[Tool], new: &[Tool]) -> CompatReport {
+    let mut report = CompatReport::default();
+    for old_tool in old {
+        match new.iter().find(|tool| tool.name == old_tool.name) {
+            Some(new_tool) => {
+                diff_schema(
+                    &old_tool.name,
+                    &old_tool.input_schema,
+                    &new_tool.input_schema,
+                    &mut report,
+                );
+            }
+            None => report.push(&old_tool.name, ChangeKind::Breaking, "tool removed"),
+        }
+    }
+    for new_tool in new {
+        if !old.iter().any(|tool| tool.name == new_tool.name) {
+            report.push(&new_tool.name, ChangeKind::Additive, "tool added");
+        }
+    }
+    report
+}
+
+fn diff_schema(path: &str, old: &Value, new: &Value, report: &mut CompatReport) {
+    let empty = Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    diff_types(path, old, new, report);
+    diff_enums(path, old, new, report);
+    diff_properties(path, old, new, report);
+
+    if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+        diff_schema(&format!("{path}.items"), old_items, new_items, report);
+    }
+
+    for keyword in COSMETIC_KEYWORDS {
+        if old.get(*keyword) != new.get(*keyword) {
+            report.push(path, ChangeKind::Cosmetic, format!("`{keyword}` changed"));
+        }
+    }
+}
+
+fn diff_types(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    report: &mut CompatReport,
+) {
+    let (old_types, new_types) = (types(old), types(new));
+    if old_types == new_types {
+        return;
+    }
+
+    let describe = |types: &Option<BTreeSet<String>>| match types {
+        Some(types) => types.iter().cloned().collect::<Vec<_>>().join(" | "),
+        None => "any".to_string(),
+    };
+    let description = format!(
+        "type changed from {} to {}",
+        describe(&old_types),
+        describe(&new_types)
+    );
+
+    let widened = match (&old_types, &new_types) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some(old_types), Some(new_types)) => old_types.iter().all(|old_type| {
+            new_types.contains(old_type) || (old_type == "integer" && new_types.contains("number"))
+        }),
+    };
+    let kind = if widened {
+        ChangeKind::Additive
+    } else {
+        ChangeKind::Breaking
+    };
+    report.push(path, kind, description);
+}
+
+/// The set of allowed `type`s, or `None` when unconstrained
+fn types(schema: &Map<String, Value>) -> Option<BTreeSet<String>> {
+    match schema.get("type")? {
+        Value::String(single) => Some(BTreeSet::from([single.clone()])),
+        Value::Array(many) => Some(
+            many.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn diff_enums(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    report: &mut CompatReport,
+) {
+    match (
+        old.get("enum").and_then(Value::as_array),
+        new.get("enum").and_then(Value::as_array),
+    ) {
+        (None, None) => {}
+        (None, Some(_)) => report.push(path, ChangeKind::Breaking, "values restricted to an enum"),
+        (Some(_), None) => report.push(path, ChangeKind::Additive, "enum restriction removed"),
+        (Some(old_values), Some(new_values)) => {
+            for value in old_values
+                .iter()
+                .filter(|value| !new_values.contains(value))
+            {
+                report.push(
+                    path,
+                    ChangeKind::Breaking,
+                    format!("enum value {value} removed"),
+                );
+            }
+            for value in new_values
+                .iter()
+                .filter(|value| !old_values.contains(value))
+            {
+                report.push(
+                    path,
+                    ChangeKind::Additive,
+                    format!("enum value {value} added"),
+                );
+            }
+        }
+    }
+}
+
+fn diff_properties(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    report: &mut CompatReport,
+) {
+    let empty = Map::new();
+    let old_properties = old
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let new_properties = new
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let (old_required, new_required) = (required(old), required(new));
+
+    for (name, old_property) in old_properties {
+        let child = format!("{path}.properties.{name}");
+        match new_properties.get(name) {
+            Some(new_property) => diff_schema(&child, old_property, new_property, report),
+            None => report.push(&child, ChangeKind::Breaking, "parameter removed"),
+        }
+    }
+
+    for name in new_properties
+        .keys()
+        .filter(|name| !old_properties.contains_key(*name))
+    {
+        let child = format!("{path}.properties.{name}");
+        if new_required.contains(name.as_str()) {
+            report.push(&child, ChangeKind::Breaking, "required parameter added");
+        } else {
+            report.push(&child, ChangeKind::Additive, "optional parameter added");
+        }
+    }
+
+    for name in new_required.difference(&old_required) {
+        if old_properties.contains_key(*name) {
+            report.push(
+                &format!("{path}.properties.{name}"),
+                ChangeKind::Breaking,
+                "parameter became required",
+            );
+        }
+    }
+    for name in old_required.difference(&new_required) {
+        if new_properties.contains_key(*name) {
+            report.push(
+                &format!("{path}.properties.{name}"),
+                ChangeKind::Additive,
+                "parameter is no longer required",
+            );
+        }
+    }
+}
+
+fn required(schema: &Map<String, Value>) -> BTreeSet<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kinds(report: &CompatReport) -> Vec<(&str, ChangeKind)> {
+        report
+            .changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.kind))
+            .collect()
+    }
+
+    fn tool(name: &str, input_schema: Value) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema,
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn classifies_parameter_changes() {
+        let old = json!({
+            "type": "object",
+            "properties": { "a": { "type": "integer" }, "b": {}, "c": {} },
+            "required": ["a", "b"]
+        });
+        let new = json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "number", "description": "First" },
+                "b": {},
+                "d": {},
+                "e": {}
+            },
+            "required": ["a", "e"]
+        });
+        let report = schema_diff(&old, &new);
+        assert_eq!(
+            kinds(&report),
+            [
+                ("$.properties.a", ChangeKind::Additive),
+                ("$.properties.a", ChangeKind::Cosmetic),
+                ("$.properties.c", ChangeKind::Breaking),
+                ("$.properties.d", ChangeKind::Additive),
+                ("$.properties.e", ChangeKind::Breaking),
+                ("$.properties.b", ChangeKind::Additive),
+            ]
+        );
+        assert_eq!(
+            report.changes[0].description,
+            "type changed from integer to number"
+        );
+        assert!(report.is_breaking());
+        assert_eq!(report.severity(), Some(ChangeKind::Breaking));
+    }
+
+    #[test]
+    fn classifies_type_and_enum_changes() {
+        let narrowed = schema_diff(
+            &json!({ "type": ["string", "null"], "enum": ["a", "b"] }),
+            &json!({ "type": "string", "enum": ["a", "c"] }),
+        );
+        assert_eq!(
+            narrowed
+                .changes
+                .iter()
+                .map(|change| change.description.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "type changed from null | string to string",
+                "enum value \"b\" removed",
+                "enum value \"c\" added",
+            ]
+        );
+
+        let items = schema_diff(
+            &json!({ "type": "array", "items": { "type": "string" } }),
+            &json!({ "type": "array", "items": {} }),
+        );
+        assert_eq!(kinds(&items), [("$.items", ChangeKind::Additive)]);
+        assert!(!items.is_breaking());
+    }
+
+    #[test]
+    fn cosmetic_changes_leave_schemas_unchanged() {
+        let report = schema_diff(
+            &json!({ "type": "string", "title": "Old" }),
+            &json!({ "type": "string", "title": "New", "examples": ["x"] }),
+        );
+        assert!(report.is_unchanged());
+        assert_eq!(report.severity(), Some(ChangeKind::Cosmetic));
+        assert_eq!(schema_diff(&json!({}), &json!({})).severity(), None);
+    }
+
+    #[test]
+    fn compares_tool_lists() {
+        let schema = json!({ "type": "object", "properties": { "x": {} } });
+        let old = [tool("keep", schema.clone()), tool("drop", json!({}))];
+        let new = [
+            tool("keep", json!({ "type": "object", "properties": {} })),
+            tool("fresh", json!({})),
+        ];
+        assert_eq!(
+            kinds(&tools_diff(&old, &new)),
+            [
+                ("keep.properties.x", ChangeKind::Breaking),
+                ("drop", ChangeKind::Breaking),
+                ("fresh", ChangeKind::Additive),
+            ]
+        );
+    }
+}
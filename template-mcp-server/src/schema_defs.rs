@@ -0,0 +1,204 @@
+//! Shared types as `$defs` of tool schemas
+//!
+//! schemars puts a type nested in another under `$defs`, but writes out the
+//! type a schema is for in full, so `ExampleData`, the whole output of
+//! `create_data` and `find_data`, was a definition in some schemas and inlined
+//! in others. Every schema now defines the [`SHARED_TYPES`] it uses the same
+//! way: once, under `$defs`, referred to with `$ref`:
+//!
+//! ```json
+//! {
+//!   "type": "object",
+//!   "$ref": "#/$defs/ExampleData",
+//!   "$defs": { "ExampleData": { "type": "object", "properties": { "id": ... } } }
+//! }
+//! ```
+//!
+//! Clients resolve `$ref` within the schema it appears in, so each schema
+//! keeps its own `$defs` rather than pointing into another tool's. The pass
+//! also fills in definitions a schema refers to but lacks: the 0.11 macros
+//! drop the `$defs` of parameter types, which left `import_snapshot`'s
+//! `$ref` to `ExampleData` dangling. Definitions that refer to other shared
+//! types, themselves included, are added once each, so recursive types end
+//! in a `$ref` instead of expanding forever.
+//!
+//! Add a type to [`SHARED_TYPES`] once more than one tool uses it. Its name
+//! must be the one schemars gives it, the title of its schema.
+
+use crate::ExampleData;
+use pulseengine_mcp_protocol::Tool;
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+const DEFS_PREFIX: &str = "#/$defs/";
+
+/// A type defined once in the `$defs` of the schemas using it
+#[derive(Debug, Clone, Copy)]
+pub struct SharedType {
+    pub name: &'static str,
+    pub schema: fn() -> Value,
+}
+
+impl SharedType {
+    pub const fn of<T: JsonSchema>(name: &'static str) -> Self {
+        Self {
+            name,
+            schema: schema_of::<T>,
+        }
+    }
+}
+
+pub const SHARED_TYPES: &[SharedType] = &[SharedType::of::<ExampleData>("ExampleData")];
+
+fn schema_of<T: JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or(Value::Null)
+}
+
+fn shared_type(name: &str) -> Option<&'static SharedType> {
+    SHARED_TYPES.iter().find(|shared| shared.name == name)
+}
+
+/// Define the shared types of a tool's input and output schemas in their
+/// `$defs`
+pub fn apply_to_schema(tool: &mut Tool) {
+    hoist_shared_types(&mut tool.input_schema);
+    if let Some(schema) = &mut tool.output_schema {
+        hoist_shared_types(schema);
+    }
+}
+
+/// Replace the shared types written out in `schema` by a `$ref` to their
+/// definition, adding the definitions it refers to
+pub fn hoist_shared_types(schema: &mut Value) {
+    let Some(root) = schema.as_object_mut() else {
+        return;
+    };
+    let mut defs = match root.remove("$defs") {
+        Some(Value::Object(defs)) => defs,
+        _ => Map::new(),
+    };
+
+    if let Some(shared) = root
+        .get("title")
+        .and_then(Value::as_str)
+        .and_then(shared_type)
+    {
+        // Outputs must stay objects at the root, so the type stays next to
+        // the reference
+        let mut definition = std::mem::take(root);
+        let schema_uri = definition.remove("$schema");
+        definition.remove("title");
+        defs.entry(shared.name.to_string())
+            .or_insert_with(|| Value::Object(definition));
+        root.extend(schema_uri.map(|uri| ("$schema".to_string(), uri)));
+        root.insert("type".to_string(), json!("object"));
+        root.insert(
+            "$ref".to_string(),
+            json!(format!("{DEFS_PREFIX}{}", shared.name)),
+        );
+    }
+
+    // Every definition added can refer to more, until all refs resolve
+    let mut pending = references(&Value::Object(root.clone()));
+    pending.extend(defs.values().flat_map(references));
+    while let Some(name) = pending.pop() {
+        if defs.contains_key(&name) {
+            continue;
+        }
+        let Some(shared) = shared_type(&name) else {
+            continue;
+        };
+        let mut definition = (shared.schema)();
+        let Some(fields) = definition.as_object_mut() else {
+            continue;
+        };
+        fields.remove("$schema");
+        fields.remove("title");
+        if let Some(Value::Object(nested)) = fields.remove("$defs") {
+            for (nested_name, nested) in nested {
+                pending.extend(references(&nested));
+                defs.entry(nested_name).or_insert(nested);
+            }
+        }
+        pending.extend(references(&definition));
+        defs.insert(name, definition);
+    }
+
+    if !defs.is_empty() {
+        root.insert("$defs".to_string(), Value::Object(defs));
+    }
+}
+
+/// Names of the definitions `schema` refers to with `$ref`
+fn references(schema: &Value) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_references(schema, &mut names);
+    names
+}
+
+fn collect_references(value: &Value, names: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            if let Some(name) = fields
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix(DEFS_PREFIX))
+            {
+                names.push(name.to_string());
+            }
+            fields
+                .values()
+                .for_each(|value| collect_references(value, names));
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_references(item, names)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_data() -> Value {
+        let mut schema = schema_of::<ExampleData>();
+        let fields = schema.as_object_mut().unwrap();
+        fields.remove("$schema");
+        fields.remove("title");
+        schema
+    }
+
+    #[test]
+    fn moves_a_shared_root_type_into_defs() {
+        let mut schema = schema_of::<ExampleData>();
+        hoist_shared_types(&mut schema);
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["$ref"], "#/$defs/ExampleData");
+        assert_eq!(schema["$defs"]["ExampleData"], example_data());
+        assert!(schema.get("properties").is_none());
+    }
+
+    #[test]
+    fn adds_missing_definitions() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "entries": { "type": "array", "items": { "$ref": "#/$defs/ExampleData" } }
+            }
+        });
+        hoist_shared_types(&mut schema);
+        assert_eq!(schema["$defs"], json!({ "ExampleData": example_data() }));
+    }
+
+    #[test]
+    fn leaves_other_schemas_alone() {
+        let plain = json!({
+            "type": "object",
+            "properties": { "x": { "$ref": "#/$defs/Unknown" } }
+        });
+        let mut schema = plain.clone();
+        hoist_shared_types(&mut schema);
+        assert_eq!(schema, plain);
+    }
+}
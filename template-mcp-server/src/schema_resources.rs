@@ -0,0 +1,195 @@
+//! The server's API description, served as resources
+//!
+//! When enabled (with `TemplateBackend::with_schema_resources`, or by setting
+//! `MCP_SCHEMA_RESOURCES` for the default backend), two resources describe the
+//! server's tools:
+//!
+//! - [`SCHEMA_URI`]: the schema catalog, the `tools/list` result that
+//!   `schema-check --dump` writes
+//! - [`OPENAPI_URI`]: an OpenAPI 3.1 document with one operation per tool,
+//!   built from the same catalog by [`openapi`]
+//!
+//! Both are generated from the backend's `tools/list` on every read, so they
+//! can't drift from what clients are served.
+
+use pulseengine_mcp_protocol::{ReadResourceResult, Resource, ResourceContents, ServerInfo, Tool};
+use serde_json::{json, Map, Value};
+
+/// Environment variable enabling the schema resources of the default backend
+pub const SCHEMA_RESOURCES_ENV_VAR: &str = "MCP_SCHEMA_RESOURCES";
+
+/// URI of the tool schema catalog
+pub const SCHEMA_URI: &str = "template://schema";
+
+/// URI of the OpenAPI document
+pub const OPENAPI_URI: &str = "template://openapi";
+
+/// Resource listings for both resources
+pub fn list() -> Vec<Resource> {
+    let resource = |uri: &str, name: &str, description: &str| Resource {
+        uri: uri.to_string(),
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        mime_type: Some("application/json".to_string()),
+        annotations: None,
+        raw: None,
+    };
+    vec![
+        resource(SCHEMA_URI, "schema", "JSON schemas of every tool"),
+        resource(OPENAPI_URI, "openapi", "OpenAPI description of every tool"),
+    ]
+}
+
+/// Whether `uri` names one of the schema resources
+pub fn matches(uri: &str) -> bool {
+    uri == SCHEMA_URI || uri == OPENAPI_URI
+}
+
+/// `document` as the JSON text content of the resource at `uri`
+pub fn contents(uri: &str, document: &Value) -> anyhow::Result<ReadResourceResult> {
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text: Some(serde_json::to_string_pretty(document)?),
+            blob: None,
+        }],
+    })
+}
+
+/// OpenAPI 3.1 document describing `tools`
+///
+/// MCP has no REST endpoints, so each tool is described as a `POST` to
+/// `/tools/{name}` taking the tool's arguments and returning its result, which
+/// is what a `tools/call` request does.
+pub fn openapi(info: &ServerInfo, tools: &[Tool]) -> Value {
+    let mut paths = Map::new();
+    for tool in tools {
+        let result_schema = tool
+            .output_schema
+            .clone()
+            .unwrap_or_else(|| json!({ "$ref": "#/components/schemas/CallToolResult" }));
+        let summary = tool.description.lines().next().unwrap_or_default();
+        paths.insert(
+            format!("/tools/{}", tool.name),
+            json!({
+                "post": {
+                    "operationId": tool.name,
+                    "summary": summary,
+                    "description": tool.description,
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": tool.input_schema } },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Tool result",
+                            "content": { "application/json": { "schema": result_schema } },
+                        },
+                    },
+                },
+            }),
+        );
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": info.server_info.name,
+            "version": info.server_info.version,
+            "description": info.instructions,
+        },
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "CallToolResult": {
+                    "type": "object",
+                    "properties": {
+                        "content": { "type": "array", "items": { "type": "object" } },
+                        "isError": { "type": "boolean" },
+                        "structuredContent": {},
+                    },
+                    "required": ["content"],
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulseengine_mcp_protocol::{Implementation, ServerCapabilities};
+
+    fn info() -> ServerInfo {
+        ServerInfo {
+            protocol_version: Default::default(),
+            capabilities: ServerCapabilities::default(),
+            server_info: Implementation {
+                name: "template".to_string(),
+                version: "1.0.0".to_string(),
+            },
+            instructions: None,
+        }
+    }
+
+    fn tool(name: &str, output_schema: Option<Value>) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: format!("Run {name}\nIn detail"),
+            input_schema: json!({ "type": "object", "properties": { "x": {} } }),
+            output_schema,
+        }
+    }
+
+    #[test]
+    fn lists_both_resources() {
+        let uris: Vec<String> = list().into_iter().map(|resource| resource.uri).collect();
+        assert_eq!(uris, [SCHEMA_URI, OPENAPI_URI]);
+        assert!(uris.iter().all(|uri| matches(uri)));
+        assert!(!matches("template://server-status"));
+    }
+
+    #[test]
+    fn describes_each_tool_as_an_operation() {
+        let output = json!({ "type": "object", "properties": { "sum": {} } });
+        let document = openapi(
+            &info(),
+            &[
+                tool("echo", None),
+                tool("add_numbers", Some(output.clone())),
+            ],
+        );
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["info"]["title"], "template");
+
+        let echo = &document["paths"]["/tools/echo"]["post"];
+        assert_eq!(echo["operationId"], "echo");
+        assert_eq!(echo["summary"], "Run echo");
+        assert_eq!(
+            echo["requestBody"]["content"]["application/json"]["schema"]["properties"],
+            json!({ "x": {} })
+        );
+        assert_eq!(
+            echo["responses"]["200"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/CallToolResult" })
+        );
+        assert_eq!(
+            document["paths"]["/tools/add_numbers"]["post"]["responses"]["200"]["content"]
+                ["application/json"]["schema"],
+            output
+        );
+    }
+
+    #[test]
+    fn serves_documents_as_json_text() {
+        let document = json!({ "a": 1 });
+        let result = contents(SCHEMA_URI, &document).unwrap();
+        let text = result.contents[0].text.as_deref().unwrap();
+        assert_eq!(serde_json::from_str::<Value>(text).unwrap(), document);
+        assert_eq!(
+            result.contents[0].mime_type.as_deref(),
+            Some("application/json")
+        );
+    }
+}
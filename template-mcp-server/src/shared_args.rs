@@ -0,0 +1,197 @@
+//! Arguments shared by a tool and a prompt
+//!
+//! A prompt that works on what a tool returns should take the tool's
+//! arguments, or the two drift apart. Declare the arguments once as a struct
+//! deriving `JsonSchema` and `Deserialize`, with a doc comment on each field,
+//! and use it for both:
+//!
+//! - the tool takes the struct as its only parameter, so the 0.11 macros use
+//!   the struct's schema as the tool's input schema and deserialize the whole
+//!   arguments object into it
+//! - the prompt declares [`PromptArgs::of`] the struct in its
+//!   [`PromptDef`](crate::prompts::PromptDef), which lists one prompt argument
+//!   per field, with the field's doc comment as its description, and parses
+//!   the prompt's arguments into the struct
+//!
+//! `#[mcp_prompt]` methods aren't collected in 0.11 (see [`crate::prompts`]),
+//! so the struct is named in the prompt's table entry. `DataFilter` is shared
+//! by the `filter_data` tool and the `review_data` prompt this way.
+//!
+//! Prompt arguments are strings, so each is converted to the type of its
+//! field in the schema first: `"1.5"` for a number field becomes `1.5`.
+
+use pulseengine_mcp_protocol::PromptArgument;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// The arguments of a prompt, taken from a struct shared with a tool
+#[derive(Debug, Clone, Copy)]
+pub struct PromptArgs {
+    /// The prompt arguments, one per field
+    pub list: fn() -> Vec<PromptArgument>,
+    /// The prompt's arguments checked against the struct, as the JSON of the
+    /// struct
+    pub parse: fn(&HashMap<String, String>) -> Result<Value, String>,
+}
+
+impl PromptArgs {
+    /// The arguments of `T`
+    pub const fn of<T: JsonSchema + DeserializeOwned + Serialize>() -> Self {
+        Self {
+            list: prompt_arguments::<T>,
+            parse: parse_arguments::<T>,
+        }
+    }
+}
+
+/// One prompt argument per property of `T`'s schema, required if the property
+/// is
+pub fn prompt_arguments<T: JsonSchema>() -> Vec<PromptArgument> {
+    let schema = schema_of::<T>();
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    properties(&schema)
+        .iter()
+        .map(|(name, property)| PromptArgument {
+            name: name.clone(),
+            description: property["description"].as_str().map(str::to_string),
+            required: Some(required.contains(&name.as_str())),
+        })
+        .collect()
+}
+
+/// `arguments` as a `T`, converted to the types of its fields, back as JSON
+pub fn parse_arguments<T: JsonSchema + DeserializeOwned + Serialize>(
+    arguments: &HashMap<String, String>,
+) -> Result<Value, String> {
+    let schema = schema_of::<T>();
+    let properties = properties(&schema);
+    let mut object = Map::new();
+    for (name, text) in arguments {
+        let Some(property) = properties.get(name) else {
+            return Err(format!("Unknown argument '{name}'"));
+        };
+        let value = typed(property, text)
+            .ok_or_else(|| format!("Argument '{name}' must be {}", type_names(property)))?;
+        object.insert(name.clone(), value);
+    }
+    let parsed: T = serde_json::from_value(Value::Object(object)).map_err(|e| e.to_string())?;
+    serde_json::to_value(parsed).map_err(|e| e.to_string())
+}
+
+fn schema_of<T: JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or(Value::Null)
+}
+
+fn properties(schema: &Value) -> Map<String, Value> {
+    schema["properties"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// The types `property` accepts, e.g. `["number", "null"]` for an `Option`
+fn types(property: &Value) -> Vec<&str> {
+    match &property["type"] {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn type_names(property: &Value) -> String {
+    let names: Vec<&str> = types(property)
+        .into_iter()
+        .filter(|name| *name != "null")
+        .collect();
+    format!("a {}", names.join(" or "))
+}
+
+/// `text` as a value of the first type of `property` it parses as
+fn typed(property: &Value, text: &str) -> Option<Value> {
+    let types = types(property);
+    if types.is_empty() {
+        return Some(Value::String(text.to_string()));
+    }
+    types.into_iter().find_map(|name| match name {
+        "string" => Some(Value::String(text.to_string())),
+        "integer" => text.parse::<i64>().ok().map(Value::from),
+        "number" => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        "boolean" => text.parse::<bool>().ok().map(Value::Bool),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_data::DataFilter;
+    use serde_json::json;
+
+    fn arguments(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_a_prompt_argument_per_field() {
+        let listed: Vec<(String, Option<String>, Option<bool>)> = prompt_arguments::<DataFilter>()
+            .into_iter()
+            .map(|argument| (argument.name, argument.description, argument.required))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (
+                    "min_value".to_string(),
+                    Some("Only entries with at least this value".to_string()),
+                    Some(false)
+                ),
+                (
+                    "name_contains".to_string(),
+                    Some("Only entries whose name contains this text".to_string()),
+                    Some(false)
+                ),
+                (
+                    "tag".to_string(),
+                    Some("Only entries with this tag".to_string()),
+                    Some(false)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_arguments_to_their_field_types() {
+        let parsed =
+            parse_arguments::<DataFilter>(&arguments(&[("min_value", "1.5"), ("tag", "home")]))
+                .unwrap();
+        assert_eq!(
+            parsed,
+            json!({ "tag": "home", "name_contains": null, "min_value": 1.5 })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_mistyped_arguments() {
+        assert_eq!(
+            parse_arguments::<DataFilter>(&arguments(&[("colour", "red")])),
+            Err("Unknown argument 'colour'".to_string())
+        );
+        assert_eq!(
+            parse_arguments::<DataFilter>(&arguments(&[("min_value", "lots")])),
+            Err("Argument 'min_value' must be a number".to_string())
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Snapshots of the data store for backup and migration
+//!
+//! `export_snapshot` returns every stored entry as a [`Snapshot`], and
+//! `import_snapshot` restores one, either merged into the existing data or
+//! replacing it; see [`ImportMode`]. Both are admin tools; see
+//! [`crate::admin`].
+//!
+//! Snapshots carry a format [`version`](Snapshot::version). When the entry
+//! format changes, bump [`SNAPSHOT_VERSION`] and add a step to
+//! [`Snapshot::migrate`] that upgrades snapshots of the previous version, so
+//! older backups stay importable.
+
+use crate::data_store::DataStore;
+use crate::structured::ToText;
+use crate::ExampleData;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Format version of snapshots written by this server
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Every entry of the data store at one point in time
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Snapshot {
+    pub version: u32,
+    #[schemars(with = "String")]
+    pub exported_at: DateTime<Utc>,
+    pub entries: Vec<ExampleData>,
+}
+
+impl ToText for Snapshot {
+    fn to_text(&self) -> String {
+        let plural = if self.entries.len() == 1 { "y" } else { "ies" };
+        format!(
+            "Snapshot of {} entr{plural}, format version {}, exported at {}",
+            self.entries.len(),
+            self.version,
+            self.exported_at.to_rfc3339()
+        )
+    }
+}
+
+/// The snapshot's version is newer than this server understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedVersion {
+    pub version: u32,
+}
+
+impl std::fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "snapshot version {} is not supported; this server reads versions up to {}",
+            self.version, SNAPSHOT_VERSION
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedVersion {}
+
+impl Snapshot {
+    /// Snapshot of the entries in `store`
+    pub fn export(store: &DataStore, exported_at: DateTime<Utc>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            exported_at,
+            entries: store.entries(),
+        }
+    }
+
+    /// The snapshot in the current format
+    pub fn migrate(self) -> Result<Self, UnsupportedVersion> {
+        // Add an arm per older version, upgrading it one version at a time
+        match self.version {
+            SNAPSHOT_VERSION => Ok(self),
+            version => Err(UnsupportedVersion { version }),
+        }
+    }
+}
+
+/// How an imported snapshot combines with the stored data
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Keep existing entries; snapshot entries overwrite those with their id
+    Merge,
+    /// Remove all existing entries first
+    Replace,
+}
+
+/// What an import changed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub mode: ImportMode,
+    /// Entries stored from the snapshot
+    pub imported: usize,
+    /// Existing entries overwritten by a snapshot entry with the same id
+    pub updated: usize,
+    /// Existing entries removed by a replace
+    pub removed: usize,
+}
+
+impl ToText for ImportSummary {}
+
+/// Restore `snapshot` into `store`
+pub fn import(
+    store: &DataStore,
+    snapshot: Snapshot,
+    mode: ImportMode,
+) -> Result<ImportSummary, UnsupportedVersion> {
+    let entries = snapshot.migrate()?.entries;
+    let imported = entries.len();
+    let (updated, removed) = match mode {
+        ImportMode::Merge => (store.upsert_all(entries), 0),
+        ImportMode::Replace => (0, store.replace_all(entries)),
+    };
+    Ok(ImportSummary {
+        mode,
+        imported,
+        updated,
+        removed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(id: u64, name: &str) -> ExampleData {
+        ExampleData::builder().id(id).name(name).build().unwrap()
+    }
+
+    fn store(entries: &[(u64, &str)]) -> DataStore {
+        let store = DataStore::new();
+        for (id, name) in entries {
+            store.insert(data(*id, name)).unwrap();
+        }
+        store
+    }
+
+    fn names(store: &DataStore) -> Vec<String> {
+        store.entries().into_iter().map(|data| data.name).collect()
+    }
+
+    fn snapshot(entries: &[(u64, &str)]) -> Snapshot {
+        Snapshot::export(&store(entries), Utc::now())
+    }
+
+    #[test]
+    fn exports_every_entry() {
+        let snapshot = snapshot(&[(2, "b"), (1, "a")]);
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        let ids: Vec<u64> = snapshot.entries.iter().map(|data| data.id).collect();
+        assert_eq!(ids, [1, 2]);
+        assert!(snapshot
+            .to_text()
+            .starts_with("Snapshot of 2 entries, format version 1"));
+    }
+
+    #[test]
+    fn merges_or_replaces() {
+        let existing = [(1, "old"), (3, "kept")];
+
+        let merged = store(&existing);
+        let summary = import(
+            &merged,
+            snapshot(&[(1, "new"), (2, "added")]),
+            ImportMode::Merge,
+        );
+        assert_eq!(
+            summary,
+            Ok(ImportSummary {
+                mode: ImportMode::Merge,
+                imported: 2,
+                updated: 1,
+                removed: 0,
+            })
+        );
+        assert_eq!(names(&merged), ["new", "added", "kept"]);
+
+        let replaced = store(&existing);
+        let summary = import(&replaced, snapshot(&[(2, "only")]), ImportMode::Replace).unwrap();
+        assert_eq!((summary.imported, summary.removed), (1, 2));
+        assert_eq!(names(&replaced), ["only"]);
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let mut newer = snapshot(&[(1, "a")]);
+        newer.version = SNAPSHOT_VERSION + 1;
+        let target = store(&[(5, "untouched")]);
+        let error = import(&target, newer, ImportMode::Replace).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "snapshot version {} is not supported; this server reads versions up to {SNAPSHOT_VERSION}",
+                SNAPSHOT_VERSION + 1
+            )
+        );
+        assert_eq!(names(&target), ["untouched"]);
+    }
+}
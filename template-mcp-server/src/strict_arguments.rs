@@ -0,0 +1,120 @@
+//! Rejecting unexpected tool arguments
+//!
+//! By default, arguments a tool doesn't declare are ignored. Strict tools set
+//! `additionalProperties: false` in their input schema instead, and calls
+//! carrying undeclared arguments fail with an error naming them. A tool is
+//! strict if its `strict` setting is on (see [`crate::tool_settings`]), or if
+//! `ToolsConfig::strict_arguments` makes every tool strict.
+//!
+//! Strictness catches misspelled optional parameters, which a lenient tool
+//! would silently run without.
+
+use crate::{tool_settings, ServerConfig};
+use pulseengine_mcp_protocol::Tool;
+use serde_json::Value;
+
+/// Whether `tool` rejects undeclared arguments under `config`
+pub fn is_strict(tool: &str, config: &ServerConfig) -> bool {
+    config.tools.strict_arguments || tool_settings::settings_of(tool).strict
+}
+
+/// Disallow undeclared arguments in a strict tool's input schema
+pub fn apply_to_schema(tool: &mut Tool, config: &ServerConfig) {
+    if !is_strict(&tool.name, config) {
+        return;
+    }
+    if let Some(schema) = tool.input_schema.as_object_mut() {
+        schema.insert("additionalProperties".to_string(), Value::Bool(false));
+    }
+}
+
+/// Arguments not declared in `tool`'s input schema
+pub fn unexpected_arguments(tool: &Tool, arguments: Option<&Value>) -> Vec<String> {
+    let Some(arguments) = arguments.and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let properties = tool
+        .input_schema
+        .get("properties")
+        .and_then(Value::as_object);
+    arguments
+        .keys()
+        .filter(|name| properties.is_none_or(|properties| !properties.contains_key(*name)))
+        .cloned()
+        .collect()
+}
+
+/// Reject a call to a strict tool that carries undeclared arguments
+pub fn check_arguments(
+    tool: &Tool,
+    arguments: Option<&Value>,
+    config: &ServerConfig,
+) -> Result<(), String> {
+    if !is_strict(&tool.name, config) {
+        return Ok(());
+    }
+    match unexpected_arguments(tool, arguments).as_slice() {
+        [] => Ok(()),
+        unexpected => Err(format!(
+            "Unexpected arguments for tool '{}': {}",
+            tool.name,
+            unexpected.join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "a": { "type": "number" }, "b": { "type": "number" } }
+            }),
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn strict_tools_reject_undeclared_arguments() {
+        let config = ServerConfig::default();
+        let add = tool("add_numbers");
+        let arguments = json!({ "a": 1, "b": 2, "c": 3, "d": 4 });
+        assert_eq!(
+            check_arguments(&add, Some(&arguments), &config),
+            Err("Unexpected arguments for tool 'add_numbers': c, d".to_string())
+        );
+        assert_eq!(
+            check_arguments(&add, Some(&json!({ "a": 1 })), &config),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn other_tools_ignore_them_unless_configured() {
+        let echo = tool("echo");
+        let arguments = json!({ "typo": true });
+        let mut config = ServerConfig::default();
+        assert_eq!(check_arguments(&echo, Some(&arguments), &config), Ok(()));
+
+        config.tools.strict_arguments = true;
+        assert!(check_arguments(&echo, Some(&arguments), &config).is_err());
+    }
+
+    #[test]
+    fn strict_schemas_disallow_additional_properties() {
+        let config = ServerConfig::default();
+        let mut add = tool("add_numbers");
+        apply_to_schema(&mut add, &config);
+        assert_eq!(add.input_schema["additionalProperties"], json!(false));
+
+        let mut echo = tool("echo");
+        apply_to_schema(&mut echo, &config);
+        assert!(echo.input_schema.get("additionalProperties").is_none());
+    }
+}
@@ -0,0 +1,239 @@
+//! Structured tool output
+//!
+//! Tools with an `output_schema` setting (see [`crate::tool_settings`])
+//! declare that `outputSchema`, usually [`output_schema`] of their return
+//! type, derived with `schemars`, and their results carry the value as
+//! `structuredContent`. Such a tool returns its value wrapped in [`Json`],
+//! and the backend serves both renderings of it: the value as
+//! `structuredContent` for clients that read it, and its [`ToText`] rendering
+//! as the text content for clients that only show text:
+//!
+//! ```json
+//! {
+//!   "content": [{ "type": "text", "text": "widget (id 42): 1.5, tagged a, b" }],
+//!   "structuredContent": { "id": 42, "name": "widget", "value": 1.5, "tags": ["a", "b"] }
+//! }
+//! ```
+//!
+//! The default `ToText` rendering puts each field of the value on a line as
+//! `name: value`; implement `to_text` for a type to render it your own way,
+//! as `ExampleData` does. Tools returning `Json` without a declared schema
+//! carry the data in the text content alone, so it stays the value's JSON.
+//!
+//! Tools building JSON whose shape isn't known up front return
+//! `Json<serde_json::Value>`, e.g. `Ok(Json(json!({ ... })))`, with
+//! [`any_object_schema`] as their schema. A bare `serde_json::Value` would be
+//! rendered as `Debug` text instead, like any other return type.
+
+use pulseengine_mcp_protocol::{CallToolResult, Content, Tool};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Rendered values always start with their tag, which keeps detection cheap
+const JSON_PREFIX: &str = r#"{"type":"json""#;
+
+/// A tool return value rendered as JSON rather than `Debug` text
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Json<T>(pub T);
+
+/// How a value returned in [`Json`] reads as text
+pub trait ToText: Serialize {
+    /// The value for a reader; by default one `name: value` line per field
+    fn to_text(&self) -> String {
+        serde_json::to_value(self)
+            .map(|value| default_text(&value))
+            .unwrap_or_default()
+    }
+}
+
+impl ToText for Value {}
+
+impl<T: Serialize> ToText for Vec<T> {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "json")]
+struct Rendered {
+    text: String,
+    json: String,
+}
+
+impl<T: ToText> std::fmt::Debug for Json<T> {
+    /// Tool results are rendered with `Debug`, so this writes the shape
+    /// [`apply_to_result`] unpacks, with the value's text and JSON
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rendered = Rendered {
+            text: self.0.to_text(),
+            json: serde_json::to_string(&self.0).map_err(|_| std::fmt::Error)?,
+        };
+        match serde_json::to_string(&rendered) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+/// `value` as `name: value` lines, or `- item` lines for an array
+fn default_text(value: &Value) -> String {
+    match value {
+        Value::Object(fields) if !fields.is_empty() => fields
+            .iter()
+            .map(|(name, field)| format!("{name}: {}", inline_text(field)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Array(items) if !items.is_empty() => items
+            .iter()
+            .map(|item| format!("- {}", inline_text(item)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => inline_text(other),
+    }
+}
+
+/// `value` on one line: strings unquoted, lists of them comma-separated and
+/// anything else as JSON
+fn inline_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_string) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+impl<T> std::ops::Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// JSON schema of a tool return type
+pub fn output_schema<T: JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or(Value::Null)
+}
+
+/// Schema accepting any object, for tools returning `Json<serde_json::Value>`
+///
+/// Structured content must be an object, so such tools should return one.
+pub fn any_object_schema() -> Value {
+    serde_json::json!({ "type": "object", "additionalProperties": true })
+}
+
+fn structured_output(tool: &str) -> Option<fn() -> Value> {
+    crate::tool_settings::settings_of(tool).output_schema
+}
+
+/// Declare the output schema in a tool's definition
+pub fn apply_to_schema(tool: &mut Tool) {
+    if let Some(schema) = structured_output(&tool.name) {
+        tool.output_schema = Some(schema());
+    }
+}
+
+/// Unpack a rendered [`Json`] value of a result: into its text and
+/// `structured_content` for a tool with a declared schema, and into its JSON
+/// for other tools
+pub fn apply_to_result(tool: &str, result: &mut CallToolResult) {
+    let structured = structured_output(tool).is_some();
+    for content in &mut result.content {
+        let Content::Text { text } = content else {
+            continue;
+        };
+        if !text.starts_with(JSON_PREFIX) {
+            continue;
+        }
+        let Ok(rendered) = serde_json::from_str::<Rendered>(text) else {
+            continue;
+        };
+        if structured {
+            if result.structured_content.is_none() {
+                result.structured_content = serde_json::from_str(&rendered.json).ok();
+            }
+            *text = rendered.text;
+        } else {
+            *text = rendered.json;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExampleData;
+    use serde_json::json;
+
+    fn widget() -> ExampleData {
+        ExampleData {
+            id: 42,
+            name: "widget".to_string(),
+            value: 1.5,
+            tags: vec!["a".to_string(), "b".to_string()],
+        }
+    }
+
+    /// `value` as a tool returning it renders it
+    fn rendered<T: ToText>(value: T) -> CallToolResult {
+        CallToolResult::text(format!("{:?}", Json(value)))
+    }
+
+    fn text_of(result: &CallToolResult) -> &str {
+        match &result.content[0] {
+            Content::Text { text } => text,
+            other => panic!("not text: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn splits_values_of_tools_with_a_schema() {
+        let mut result = rendered(widget());
+        apply_to_result("create_data", &mut result);
+        assert_eq!(text_of(&result), "widget (id 42): 1.5, tagged a, b");
+        assert_eq!(
+            result.structured_content,
+            Some(json!({ "id": 42, "name": "widget", "value": 1.5, "tags": ["a", "b"] }))
+        );
+    }
+
+    #[test]
+    fn keeps_the_json_of_other_tools() {
+        let mut result = rendered(json!({ "ok": true }));
+        apply_to_result("echo", &mut result);
+        assert_eq!(text_of(&result), r#"{"ok":true}"#);
+        assert_eq!(result.structured_content, None);
+
+        let mut plain = CallToolResult::text("plain");
+        apply_to_result("create_data", &mut plain);
+        assert_eq!(text_of(&plain), "plain");
+    }
+
+    #[test]
+    fn renders_fields_and_items_as_lines() {
+        assert_eq!(
+            json!({ "name": "widget", "tags": ["a", "b"], "size": { "w": 1 } }).to_text(),
+            "name: widget\nsize: {\"w\":1}\ntags: a, b"
+        );
+        assert_eq!(vec!["a", "b"].to_text(), "- a\n- b");
+    }
+
+    #[test]
+    fn declares_schemas_of_structured_tools() {
+        let tool = |name: &str| Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        };
+        let mut create = tool("create_data");
+        apply_to_schema(&mut create);
+        assert_eq!(create.output_schema, Some(output_schema::<ExampleData>()));
+        let mut echo = tool("echo");
+        apply_to_schema(&mut echo);
+        assert_eq!(echo.output_schema, None);
+    }
+}
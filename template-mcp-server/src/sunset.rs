@@ -0,0 +1,214 @@
+//! Deprecated tools removed on a sunset date
+//!
+//! A tool with a `sunset` setting (see [`crate::tool_settings`]) is
+//! deprecated and stops being served on its sunset date. Until then it works
+//! as before, with `x-deprecated` and `x-sunset` in its input schema, and
+//! every call warns the client that the
+//! tool is going away (see [`crate::client_notifications`]); in the last
+//! [`COUNTDOWN_DAYS`] days the server logs the countdown too, so operators
+//! notice clients that still use it. From the sunset date on, the tool is
+//! left out of `tools/list` and calls to it fail with a `gone` error:
+//!
+//! ```json
+//! {
+//!   "code": -32601,
+//!   "message": "Tool 'echo@1' was removed on 2027-06-01; use echo@2 instead",
+//!   "data": { "error": "gone", "sunset": "2027-06-01", "replacement": "echo@2" }
+//! }
+//! ```
+//!
+//! Dates are compared with the server's clock, in UTC, so a
+//! [`crate::clock::ManualClock`] or `ServerConfig::fixed_time` can move a
+//! server past a sunset. A tool served in several versions deprecates one
+//! version at a time, with a sunset on e.g. `echo@1` and `echo@2` as its
+//! replacement.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use pulseengine_mcp_protocol::{Error, ErrorCode, Tool};
+use serde_json::json;
+
+/// When a deprecated tool is removed, and what replaces it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sunset {
+    /// First day the tool isn't served, as `YYYY-MM-DD`
+    pub date: &'static str,
+    /// Tool to call instead, if any
+    pub replacement: Option<&'static str>,
+}
+
+impl Sunset {
+    pub fn date(&self) -> NaiveDate {
+        NaiveDate::parse_from_str(self.date, "%Y-%m-%d").expect("sunset dates are YYYY-MM-DD")
+    }
+
+    /// Whole days from `now` until the sunset date
+    pub fn days_left(&self, now: DateTime<Utc>) -> i64 {
+        (self.date() - now.date_naive()).num_days()
+    }
+
+    fn instead(&self) -> String {
+        self.replacement
+            .map(|replacement| format!("; use {replacement} instead"))
+            .unwrap_or_default()
+    }
+}
+
+/// Days before a sunset from which calls log a countdown on the server
+pub const COUNTDOWN_DAYS: i64 = 30;
+
+pub fn sunset_of(tool: &str) -> Option<&'static Sunset> {
+    crate::tool_settings::settings_of(tool).sunset.as_ref()
+}
+
+/// A call to a deprecated tool before its sunset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deprecation {
+    pub tool: &'static str,
+    pub sunset: &'static Sunset,
+    pub days_left: i64,
+}
+
+impl Deprecation {
+    /// Whether the sunset is close enough to log the countdown
+    pub fn is_imminent(&self) -> bool {
+        self.days_left <= COUNTDOWN_DAYS
+    }
+}
+
+impl std::fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = if self.days_left == 1 { "" } else { "s" };
+        write!(
+            f,
+            "Tool '{}' is deprecated and will be removed on {}, in {} day{plural}{}",
+            self.tool,
+            self.sunset.date,
+            self.days_left,
+            self.sunset.instead()
+        )
+    }
+}
+
+/// A call to a tool after its sunset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoneTool {
+    pub tool: &'static str,
+    pub sunset: &'static Sunset,
+}
+
+impl std::fmt::Display for GoneTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tool '{}' was removed on {}{}",
+            self.tool,
+            self.sunset.date,
+            self.sunset.instead()
+        )
+    }
+}
+
+impl std::error::Error for GoneTool {}
+
+impl From<GoneTool> for Error {
+    fn from(gone: GoneTool) -> Self {
+        Self::with_data(
+            ErrorCode::MethodNotFound,
+            gone.to_string(),
+            json!({
+                "error": "gone",
+                "sunset": gone.sunset.date,
+                "replacement": gone.sunset.replacement,
+            }),
+        )
+    }
+}
+
+/// The deprecation of `tool` at `now`, if it has a sunset, failing once the
+/// sunset has come
+pub fn check(tool: &str, now: DateTime<Utc>) -> Result<Option<Deprecation>, GoneTool> {
+    let settings = crate::tool_settings::settings_of(tool);
+    let Some(sunset) = &settings.sunset else {
+        return Ok(None);
+    };
+    let tool = settings.tool;
+    match sunset.days_left(now) {
+        days_left if days_left > 0 => Ok(Some(Deprecation {
+            tool,
+            sunset,
+            days_left,
+        })),
+        _ => Err(GoneTool { tool, sunset }),
+    }
+}
+
+/// Whether `tool` has been removed by its sunset at `now`
+pub fn is_gone(tool: &str, now: DateTime<Utc>) -> bool {
+    check(tool, now).is_err()
+}
+
+/// Mark a deprecated tool's schema with its sunset date
+pub fn apply_to_schema(tool: &mut Tool) {
+    let Some(sunset) = sunset_of(&tool.name) else {
+        return;
+    };
+    if let Some(schema) = tool.input_schema.as_object_mut() {
+        schema.insert("x-deprecated".to_string(), json!(true));
+        schema.insert("x-sunset".to_string(), json!(sunset.date));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> DateTime<Utc> {
+        format!("{date}T12:00:00Z").parse().unwrap()
+    }
+
+    #[test]
+    fn deprecated_tools_count_down_to_their_sunset() {
+        let far = check("echo@1", at("2027-01-01")).unwrap().unwrap();
+        assert_eq!(far.days_left, 151);
+        assert!(!far.is_imminent());
+
+        let close = check("echo@1", at("2027-05-31")).unwrap().unwrap();
+        assert!(close.is_imminent());
+        assert_eq!(
+            close.to_string(),
+            "Tool 'echo@1' is deprecated and will be removed on 2027-06-01, in 1 day; use echo@2 instead"
+        );
+        assert_eq!(check("echo@2", at("2030-01-01")), Ok(None));
+    }
+
+    #[test]
+    fn tools_are_gone_from_their_sunset_on() {
+        assert!(!is_gone("echo@1", at("2027-05-31")));
+        assert!(is_gone("echo@1", at("2027-06-01")));
+        let gone = check("echo@1", at("2028-01-01")).unwrap_err();
+        let error = Error::from(gone);
+        assert_eq!(
+            error.message,
+            "Tool 'echo@1' was removed on 2027-06-01; use echo@2 instead"
+        );
+        assert_eq!(
+            error.data,
+            Some(json!({ "error": "gone", "sunset": "2027-06-01", "replacement": "echo@2" }))
+        );
+    }
+
+    #[test]
+    fn marks_deprecated_schemas() {
+        let mut tool = Tool {
+            name: "echo@1".to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object" }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool);
+        assert_eq!(
+            tool.input_schema,
+            json!({ "type": "object", "x-deprecated": true, "x-sunset": "2027-06-01" })
+        );
+    }
+}
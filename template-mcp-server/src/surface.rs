@@ -0,0 +1,297 @@
+//! A reviewable snapshot of everything the server exposes
+//!
+//! [`surface_snapshot`] renders the tools, resources and prompts a client
+//! sees as plain text, sorted by name so it only changes when the surface
+//! does. Each tool gets its description and one line per parameter with the
+//! parameter's schema; schema keywords outside the parameters, such as
+//! `x-effects`, get a line of their own:
+//!
+//! ```text
+//! tool echo
+//!   | Echo back a message with optional prefix
+//!   param message (required): {"examples":["hello world"],"type":"string"}
+//!   param prefix: {"type":"string"}
+//!   schema: {"x-version":"2"}
+//! ```
+//!
+//! The snapshot is committed as `surface.txt` and checked in CI with
+//! `cargo run --bin surface-snapshot -- --check surface.txt`, so a change to
+//! the surface only passes once the file is regenerated and the change shows
+//! up in review. [`check_snapshot`] does the comparison, returning a line
+//! diff of what changed.
+
+use crate::TemplateBackend;
+use pulseengine_mcp_protocol::{ListPromptsResult, ListResourcesResult, ListToolsResult};
+use pulseengine_mcp_protocol::{Prompt, Resource, Tool};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Environment variable that makes [`check_snapshot`] rewrite the file
+/// instead of failing
+pub const UPDATE_ENV_VAR: &str = "UPDATE_SURFACE_SNAPSHOT";
+
+/// Lines of unchanged context around each change in a diff
+const CONTEXT_LINES: usize = 2;
+
+/// The surface `backend` serves, as text
+pub async fn surface_snapshot(backend: &TemplateBackend) -> anyhow::Result<String> {
+    let tools: ListToolsResult =
+        serde_json::from_value(backend.dispatch("tools/list", Value::Null).await?)?;
+    let prompts: ListPromptsResult =
+        serde_json::from_value(backend.dispatch("prompts/list", Value::Null).await?)?;
+
+    let mut resources = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let page: ListResourcesResult = serde_json::from_value(
+            backend
+                .dispatch("resources/list", json!({ "cursor": cursor }))
+                .await?,
+        )?;
+        resources.extend(page.resources);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(render(&tools.tools, &resources, &prompts.prompts))
+}
+
+/// The text of a surface with these tools, resources and prompts
+pub fn render(tools: &[Tool], resources: &[Resource], prompts: &[Prompt]) -> String {
+    let mut out = String::new();
+
+    let mut tools: Vec<&Tool> = tools.iter().collect();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    for tool in tools {
+        let _ = writeln!(out, "tool {}", tool.name);
+        write_description(&mut out, &tool.description);
+        write_parameters(&mut out, &tool.input_schema);
+    }
+
+    let mut resources: Vec<&Resource> = resources.iter().collect();
+    resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+    for resource in resources {
+        let _ = write!(out, "resource {} ({})", resource.uri, resource.name);
+        if let Some(mime_type) = &resource.mime_type {
+            let _ = write!(out, " {mime_type}");
+        }
+        out.push('\n');
+        write_description(
+            &mut out,
+            resource.description.as_deref().unwrap_or_default(),
+        );
+    }
+
+    let mut prompts: Vec<&Prompt> = prompts.iter().collect();
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    for prompt in prompts {
+        let arguments: Vec<String> = prompt
+            .arguments
+            .iter()
+            .flatten()
+            .map(|argument| match argument.required {
+                Some(true) => argument.name.clone(),
+                _ => format!("{}?", argument.name),
+            })
+            .collect();
+        let _ = writeln!(out, "prompt {}({})", prompt.name, arguments.join(", "));
+        write_description(&mut out, prompt.description.as_deref().unwrap_or_default());
+    }
+    out
+}
+
+fn write_description(out: &mut String, description: &str) {
+    for line in description.lines() {
+        let _ = writeln!(out, "  | {line}");
+    }
+}
+
+fn write_parameters(out: &mut String, schema: &Value) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, property) in properties {
+            let flag = if required.contains(&name.as_str()) {
+                " (required)"
+            } else {
+                ""
+            };
+            let _ = writeln!(out, "  param {name}{flag}: {property}");
+        }
+    }
+    // Everything else, except what an object schema always has
+    if let Some(schema) = schema.as_object() {
+        let mut rest = schema.clone();
+        rest.remove("properties");
+        rest.remove("required");
+        if rest.get("type") == Some(&json!("object")) {
+            rest.remove("type");
+        }
+        if !rest.is_empty() {
+            let _ = writeln!(out, "  schema: {}", Value::Object(rest));
+        }
+    }
+}
+
+/// Compare `actual` with the snapshot at `path`
+///
+/// With [`UPDATE_ENV_VAR`] set, or no file at `path` yet, the file is
+/// written instead. Otherwise a difference fails with a diff of the lines
+/// that changed, `-` for the snapshot's and `+` for the actual ones.
+pub fn check_snapshot(path: &Path, actual: &str) -> Result<(), String> {
+    let update = std::env::var_os(UPDATE_ENV_VAR).is_some();
+    let expected = match std::fs::read_to_string(path) {
+        // Checkouts on Windows may have turned the line endings into CRLF
+        Ok(expected) if !update => expected.replace("\r\n", "\n"),
+        Err(e) if !update && e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("Failed to read {}: {e}", path.display()));
+        }
+        _ => {
+            return std::fs::write(path, actual)
+                .map_err(|e| format!("Failed to write {}: {e}", path.display()));
+        }
+    };
+    if expected == actual {
+        return Ok(());
+    }
+    Err(format!(
+        "MCP surface differs from {}; review the changes and rerun with {UPDATE_ENV_VAR}=1 to accept them:\n{}",
+        path.display(),
+        line_diff(&expected, actual)
+    ))
+}
+
+/// The changed lines between `expected` and `actual`, with a little context
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequences of every pair of suffixes
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    // Each line as (mark, line number in the snapshot, text)
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', i + 1, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', i + 1, old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', i + 1, new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&index| lines[index].0 != ' ')
+        .collect();
+    let shown = |index: usize| {
+        changed
+            .iter()
+            .any(|&change| index + CONTEXT_LINES >= change && index <= change + CONTEXT_LINES)
+    };
+    let mut out = String::new();
+    let mut skipped = true;
+    for (index, (mark, line_number, text)) in lines.iter().enumerate() {
+        if !shown(index) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            let _ = writeln!(out, "@@ line {line_number} @@");
+            skipped = false;
+        }
+        let _ = writeln!(out, "{mark} {text}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, schema: Value) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: format!("Run {name}"),
+            input_schema: schema,
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn renders_a_sorted_surface() {
+        let tools = [
+            tool(
+                "echo",
+                json!({
+                    "type": "object",
+                    "properties": { "message": { "type": "string" }, "prefix": {} },
+                    "required": ["message"],
+                    "x-version": "2"
+                }),
+            ),
+            tool("add", json!({ "type": "object" })),
+        ];
+        let resources = [Resource {
+            uri: "template://status".to_string(),
+            name: "status".to_string(),
+            description: Some("Server status".to_string()),
+            mime_type: Some("application/json".to_string()),
+            annotations: None,
+            raw: None,
+        }];
+        assert_eq!(
+            render(&tools, &resources, &[]),
+            "tool add\n  | Run add\n\
+             tool echo\n  | Run echo\n  param message (required): {\"type\":\"string\"}\n  param prefix: {}\n  schema: {\"x-version\":\"2\"}\n\
+             resource template://status (status) application/json\n  | Server status\n"
+        );
+    }
+
+    #[test]
+    fn diffs_changed_lines_with_context() {
+        let expected = "a\nb\nc\nd\ne\nf\ng\n";
+        let actual = "a\nb\nc\nd\nE\nf\ng\n";
+        assert_eq!(
+            line_diff(expected, actual),
+            "@@ line 3 @@\n  c\n  d\n- e\n+ E\n  f\n  g\n"
+        );
+        assert_eq!(line_diff(expected, expected), "");
+    }
+
+    #[test]
+    fn checks_against_the_committed_file() {
+        let path = std::env::temp_dir().join(format!("surface-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // A missing snapshot is written
+        assert_eq!(check_snapshot(&path, "tool a\n"), Ok(()));
+        assert_eq!(check_snapshot(&path, "tool a\n"), Ok(()));
+        std::fs::write(&path, "tool a\r\n").unwrap();
+        assert_eq!(check_snapshot(&path, "tool a\n"), Ok(()));
+
+        let error = check_snapshot(&path, "tool b\n").unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.starts_with("MCP surface differs from"), "{error}");
+        assert!(error.ends_with("- tool a\n+ tool b\n"), "{error}");
+    }
+}
@@ -0,0 +1,258 @@
+//! Tabular tool results
+//!
+//! A tool returning [`Table`] produces text content holding the table as JSON
+//! in a fixed shape, `{"type": "table", "columns": [...], "rows": [[...]]}`,
+//! described by [`table_schema`]. The backend recognizes that shape and also
+//! returns it as `structuredContent`, so clients can render a real table
+//! instead of nested JSON.
+//!
+//! Give a table tool [`table_schema`] as its `output_schema` setting (see
+//! [`crate::tool_settings`]) to advertise the shape in `tools/list`.
+//!
+//! Build tables with [`Table::builder`], or collect rows of any [`TableRow`]
+//! type: `let table: Table = items.iter().collect();`
+
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Value of the `type` field that marks a serialized table
+pub const TABLE_TYPE: &str = "table";
+
+// Serialized tables always start with their tag, which keeps detection cheap
+const TABLE_PREFIX: &str = r#"{"type":"table""#;
+
+/// Data type of a column's cells
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// Arbitrary JSON (arrays, objects)
+    Json,
+}
+
+/// A named, typed table column
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+}
+
+impl Column {
+    pub fn new(name: impl Into<String>, column_type: ColumnType) -> Self {
+        Self {
+            name: name.into(),
+            column_type,
+        }
+    }
+}
+
+/// Rows of cells under a list of columns; missing cells are `null`
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "table")]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl std::fmt::Debug for Table {
+    /// Tool results are rendered with `Debug`, so this writes the table JSON
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match serde_json::to_string(self) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+impl Table {
+    pub fn builder() -> TableBuilder {
+        TableBuilder::default()
+    }
+}
+
+/// Builder for [`Table`]
+#[derive(Debug, Default)]
+pub struct TableBuilder {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl TableBuilder {
+    pub fn column(mut self, name: impl Into<String>, column_type: ColumnType) -> Self {
+        self.columns.push(Column::new(name, column_type));
+        self
+    }
+
+    /// Add a row; it is padded with `null` (or truncated) to the column count
+    pub fn row(mut self, cells: impl IntoIterator<Item = Value>) -> Self {
+        self.rows.push(cells.into_iter().collect());
+        self
+    }
+
+    pub fn build(self) -> Table {
+        let width = self.columns.len();
+        let rows = self
+            .rows
+            .into_iter()
+            .map(|mut row| {
+                row.resize(width, Value::Null);
+                row
+            })
+            .collect();
+        Table {
+            columns: self.columns,
+            rows,
+        }
+    }
+}
+
+/// A type that can be a row of a [`Table`]
+pub trait TableRow {
+    fn columns() -> Vec<Column>;
+
+    /// Cells in the order of [`TableRow::columns`]
+    fn cells(&self) -> Vec<Value>;
+}
+
+impl<R: TableRow> FromIterator<R> for Table {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(
+                TableBuilder {
+                    columns: R::columns(),
+                    rows: Vec::new(),
+                },
+                |builder, row| builder.row(row.cells()),
+            )
+            .build()
+    }
+}
+
+impl<R: TableRow> TableRow for &R {
+    fn columns() -> Vec<Column> {
+        R::columns()
+    }
+
+    fn cells(&self) -> Vec<Value> {
+        (*self).cells()
+    }
+}
+
+/// JSON schema of a serialized [`Table`]
+pub fn table_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "type": { "const": TABLE_TYPE },
+            "columns": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "type": {
+                            "enum": ["string", "integer", "number", "boolean", "json"]
+                        }
+                    },
+                    "required": ["name", "type"]
+                }
+            },
+            "rows": {
+                "type": "array",
+                "items": { "type": "array" }
+            }
+        },
+        "required": ["type", "columns", "rows"]
+    })
+}
+
+/// Copy a table in a tool result's text content into `structured_content`
+pub fn detect(result: &mut CallToolResult) {
+    if result.structured_content.is_some() {
+        return;
+    }
+    let table = result.content.iter().find_map(|content| match content {
+        Content::Text { text } if text.starts_with(TABLE_PREFIX) => {
+            serde_json::from_str::<Table>(text).ok()
+        }
+        _ => None,
+    });
+    if let Some(table) = table {
+        result.structured_content = serde_json::to_value(table).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Point {
+        x: i64,
+        label: &'static str,
+    }
+
+    impl TableRow for Point {
+        fn columns() -> Vec<Column> {
+            vec![
+                Column::new("x", ColumnType::Integer),
+                Column::new("label", ColumnType::String),
+            ]
+        }
+
+        fn cells(&self) -> Vec<Value> {
+            vec![json!(self.x), json!(self.label)]
+        }
+    }
+
+    #[test]
+    fn pads_and_truncates_rows_to_the_columns() {
+        let table = Table::builder()
+            .column("a", ColumnType::Integer)
+            .column("b", ColumnType::Boolean)
+            .row([json!(1)])
+            .row([json!(2), json!(true), json!("extra")])
+            .build();
+        assert_eq!(
+            table.rows,
+            [vec![json!(1), Value::Null], vec![json!(2), json!(true)]]
+        );
+    }
+
+    #[test]
+    fn collects_rows() {
+        let points = [Point { x: 1, label: "a" }, Point { x: 2, label: "b" }];
+        let table: Table = points.iter().collect();
+        assert_eq!(
+            serde_json::to_value(&table).unwrap(),
+            json!({
+                "type": "table",
+                "columns": [{ "name": "x", "type": "integer" }, { "name": "label", "type": "string" }],
+                "rows": [[1, "a"], [2, "b"]]
+            })
+        );
+    }
+
+    #[test]
+    fn detects_tables_in_text_content() {
+        let table = Table::builder()
+            .column("n", ColumnType::Number)
+            .row([json!(1.5)])
+            .build();
+        let mut result = CallToolResult::text(format!("{table:?}"));
+        detect(&mut result);
+        assert_eq!(
+            result.structured_content,
+            Some(serde_json::to_value(&table).unwrap())
+        );
+
+        let mut plain = CallToolResult::text("not a table");
+        detect(&mut plain);
+        assert_eq!(plain.structured_content, None);
+    }
+}
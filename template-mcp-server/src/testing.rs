@@ -0,0 +1,489 @@
+//! Test helpers, enabled with the `testing` feature
+//!
+//! [`MockUpstream`] is a local HTTP server that answers with scripted
+//! responses, for testing tools that call external APIs. Point the tool at
+//! [`MockUpstream::url`], queue the responses the test needs (including
+//! failures, delays and dropped connections), then assert on the requests it
+//! received:
+//!
+//! ```ignore
+//! let upstream = MockUpstream::start().await?;
+//! upstream.enqueue(MockResponse::status(503));
+//! upstream.enqueue(MockResponse::json(serde_json::json!({ "id": 1 })));
+//!
+//! // ... call the tool configured with `upstream.url()` ...
+//!
+//! assert_eq!(upstream.request_count(), 2); // failed once, retried once
+//! ```
+//!
+//! [`assert_tool_schema!`](crate::assert_tool_schema) locks the input schema a
+//! tool is served with, so a change to its parameters fails a test instead of
+//! breaking clients.
+
+use crate::TemplateBackend;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Assert that a tool's input schema, as `tools/list` serves it, is the
+/// expected JSON
+///
+/// A mismatch panics listing each differing JSON path, then the whole actual
+/// schema to paste in if the change is intended:
+///
+/// ```ignore
+/// use serde_json::json;
+/// use template_mcp_server::{assert_tool_schema, TemplateBackend, TemplateMcpServer};
+///
+/// let backend = TemplateBackend::new(TemplateMcpServer::default());
+/// assert_tool_schema!(backend, "add_numbers", json!({
+///     "type": "object",
+///     "properties": {
+///         "a": { "type": "number" },
+///         "b": { "type": "number" }
+///     },
+///     "required": ["a", "b"],
+///     "additionalProperties": false
+/// }));
+/// assert_tool_schema!(backend, "echo", json!({
+///     "type": "object",
+///     "properties": {
+///         "message": { "type": "string", "examples": ["hello world"] },
+///         "prefix": { "type": "string" }
+///     },
+///     "required": ["message"],
+///     "x-version": "2"
+/// }));
+/// ```
+#[macro_export]
+macro_rules! assert_tool_schema {
+    ($backend:expr, $tool:expr, $expected:expr $(,)?) => {
+        $crate::testing::check_tool_schema(&$backend, $tool, &$expected)
+            .unwrap_or_else(|message| panic!("{}", message))
+    };
+}
+
+/// The input schema `backend` serves for `tool`
+pub fn tool_schema(backend: &TemplateBackend, tool: &str) -> Option<Value> {
+    backend
+        .tool_definitions()
+        .into_iter()
+        .find(|definition| definition.name == tool)
+        .map(|definition| definition.input_schema)
+}
+
+/// Check `tool`'s input schema against `expected`, as
+/// [`assert_tool_schema!`](crate::assert_tool_schema) does
+pub fn check_tool_schema(
+    backend: &TemplateBackend,
+    tool: &str,
+    expected: &Value,
+) -> Result<(), String> {
+    let Some(actual) = tool_schema(backend, tool) else {
+        let served: Vec<String> = backend
+            .tool_definitions()
+            .into_iter()
+            .map(|definition| definition.name)
+            .collect();
+        return Err(format!(
+            "No tool '{tool}' is served; tools are: {}",
+            served.join(", ")
+        ));
+    };
+    let differences = schema_diff(&actual, expected);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    let pretty = serde_json::to_string_pretty(&actual).unwrap_or_else(|_| actual.to_string());
+    Err(format!(
+        "Schema of tool '{tool}' differs from the expected one:\n  {}\nActual schema:\n{pretty}",
+        differences.join("\n  ")
+    ))
+}
+
+/// Differences between `actual` and `expected`, one per JSON pointer
+pub fn schema_diff(actual: &Value, expected: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff("", actual, expected, &mut differences);
+    differences
+}
+
+fn diff(path: &str, actual: &Value, expected: &Value, differences: &mut Vec<String>) {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => {
+            let mut keys: Vec<&String> = actual.keys().chain(expected.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match (actual.get(key), expected.get(key)) {
+                    (Some(actual), Some(expected)) => diff(&path, actual, expected, differences),
+                    (None, Some(expected)) => {
+                        differences.push(format!("{path}: missing, expected {expected}"))
+                    }
+                    (Some(actual), None) => {
+                        differences.push(format!("{path}: unexpected {actual}"))
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(items), Value::Array(expected_items))
+            if items.len() == expected_items.len() =>
+        {
+            for (index, (actual, expected)) in items.iter().zip(expected_items).enumerate() {
+                diff(&format!("{path}/{index}"), actual, expected, differences);
+            }
+        }
+        _ if actual != expected => {
+            let path = if path.is_empty() { "(root)" } else { path };
+            differences.push(format!("{path}: expected {expected}, got {actual}"));
+        }
+        _ => {}
+    }
+}
+
+/// A scripted HTTP response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: String,
+    /// Wait this long before answering
+    pub delay: Duration,
+    /// Close the connection without answering
+    pub disconnect: bool,
+}
+
+impl MockResponse {
+    /// An empty response with `status`
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "text/plain".to_string(),
+            body: String::new(),
+            delay: Duration::ZERO,
+            disconnect: false,
+        }
+    }
+
+    /// `200 OK` with a text body
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self::status(200).with_body(body)
+    }
+
+    /// `200 OK` with a JSON body
+    pub fn json(body: Value) -> Self {
+        let mut response = Self::ok(body.to_string());
+        response.content_type = "application/json".to_string();
+        response
+    }
+
+    /// Drop the connection instead of answering, like a crashed upstream
+    pub fn disconnect() -> Self {
+        Self {
+            disconnect: true,
+            ..Self::status(0)
+        }
+    }
+
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// A request received by the mock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Value of a header, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A local HTTP server answering with scripted responses
+///
+/// Queued responses are used in order, one per request; once the queue is
+/// empty every request gets the fallback response (`200 OK` by default). Each
+/// connection serves a single request. The server stops when dropped.
+pub struct MockUpstream {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+struct State {
+    script: VecDeque<MockResponse>,
+    fallback: MockResponse,
+    requests: Vec<RecordedRequest>,
+}
+
+impl MockUpstream {
+    /// Start a mock on a free local port
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            script: VecDeque::new(),
+            fallback: MockResponse::ok(""),
+            requests: Vec::new(),
+        }));
+
+        let server_state = state.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, server_state.clone()));
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    /// Base URL of the mock, e.g. `http://127.0.0.1:41234`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Answer the next unanswered request with `response`
+    pub fn enqueue(&self, response: MockResponse) {
+        lock(&self.state).script.push_back(response);
+    }
+
+    /// Answer requests with `response` once the queue is empty
+    pub fn set_fallback(&self, response: MockResponse) {
+        lock(&self.state).fallback = response;
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        lock(&self.state).requests.clone()
+    }
+
+    pub fn request_count(&self) -> usize {
+        lock(&self.state).requests.len()
+    }
+}
+
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn lock(state: &Mutex<State>) -> std::sync::MutexGuard<'_, State> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+
+    let response = {
+        let mut state = lock(&state);
+        state.requests.push(request);
+        let fallback = state.fallback.clone();
+        state.script.pop_front().unwrap_or(fallback)
+    };
+
+    if !response.delay.is_zero() {
+        tokio::time::sleep(response.delay).await;
+    }
+    if response.disconnect {
+        return;
+    }
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(response.body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = find_head_end(&buffer) {
+            break end;
+        }
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let body_start = head_end + 4;
+    while buffer.len() < body_start + content_length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body_end = buffer.len().min(body_start + content_length);
+    let body = String::from_utf8_lossy(&buffer[body_start..body_end]).into_owned();
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn find_head_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TemplateMcpServer;
+    use serde_json::json;
+
+    /// The raw response of `upstream` to `request`
+    async fn send(upstream: &MockUpstream, request: &str) -> String {
+        let mut stream = TcpStream::connect(upstream.addr()).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn lists_differences_by_json_pointer() {
+        let actual = json!({ "a/b": 1, "list": [1, 2], "extra": true });
+        let expected = json!({ "a/b": 2, "list": [1, 3], "missing": null });
+        assert_eq!(
+            schema_diff(&actual, &expected),
+            [
+                "/a~1b: expected 2, got 1",
+                "/extra: unexpected true",
+                "/list/1: expected 3, got 2",
+                "/missing: missing, expected null",
+            ]
+        );
+        assert_eq!(
+            schema_diff(&json!([1]), &json!([1, 2])),
+            ["(root): expected [1,2], got [1]"]
+        );
+    }
+
+    #[test]
+    fn checks_served_schemas() {
+        let backend = TemplateBackend::new(TemplateMcpServer::default());
+        let mut schema = tool_schema(&backend, "add_numbers").unwrap();
+        assert_tool_schema!(backend, "add_numbers", schema.clone());
+
+        schema["required"] = json!(["a"]);
+        let message = check_tool_schema(&backend, "add_numbers", &schema).unwrap_err();
+        assert!(
+            message.starts_with(
+                "Schema of tool 'add_numbers' differs from the expected one:\n  \
+                 /required: expected [\"a\"], got [\"a\",\"b\"]\nActual schema:\n"
+            ),
+            "{message}"
+        );
+        let message = check_tool_schema(&backend, "nope", &schema).unwrap_err();
+        assert!(message.starts_with("No tool 'nope' is served; tools are: "));
+    }
+
+    #[tokio::test]
+    async fn answers_with_the_script_then_the_fallback() {
+        let upstream = MockUpstream::start().await.unwrap();
+        upstream.enqueue(MockResponse::status(503));
+        upstream.enqueue(MockResponse::disconnect());
+        upstream.set_fallback(MockResponse::json(json!({ "id": 1 })));
+
+        let body = "{\"q\":1}";
+        let request = format!(
+            "POST /items HTTP/1.1\r\nHost: mock\r\nX-Trace: abc\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        assert!(send(&upstream, &request)
+            .await
+            .starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert_eq!(send(&upstream, &request).await, "");
+        let response = send(&upstream, "GET /items/1 HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("Content-Type: application/json\r\n"));
+        assert!(response.ends_with("\r\n\r\n{\"id\":1}"), "{response}");
+
+        let requests = upstream.requests();
+        assert_eq!(upstream.request_count(), 3);
+        assert_eq!(
+            (requests[0].method.as_str(), requests[0].path.as_str()),
+            ("POST", "/items")
+        );
+        assert_eq!(requests[0].header("x-trace"), Some("abc"));
+        assert_eq!(requests[0].body, body);
+        assert_eq!(requests[2].path, "/items/1");
+    }
+}
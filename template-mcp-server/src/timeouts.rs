@@ -0,0 +1,109 @@
+//! Tool timeouts computed from the arguments
+//!
+//! Every tool call must finish within `ServerConfig::timeout_seconds`, which
+//! suits most tools but not one whose work grows with its input. A tool with
+//! a `timeout` setting (see [`crate::tool_settings`]) gets its deadline from
+//! that function of its arguments instead, given the configured timeout to
+//! scale from, and at most [`MAX_FACTOR`] times it; other tools keep the
+//! configured timeout. `tools/validate` reports the timeout a call would get
+//! as `timeout_ms`.
+//!
+//! Each function deserializes the arguments it needs; arguments it can't read
+//! get the configured timeout, and the call then fails on them as usual.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Computes a tool's timeout from its arguments and the configured timeout
+pub type TimeoutFn = fn(arguments: &Value, configured: Duration) -> Duration;
+
+/// Extra time `process_list` gets per item
+const PER_ITEM: Duration = Duration::from_millis(1);
+
+/// How many times the configured timeout a computed timeout may be at most
+pub const MAX_FACTOR: u32 = 4;
+
+/// The timeout of a call to `tool` with `arguments`
+pub fn timeout_for(tool: &str, arguments: Option<&Value>, configured: Duration) -> Duration {
+    let Some(timeout_fn) = crate::tool_settings::settings_of(tool).timeout else {
+        return configured;
+    };
+    let arguments = arguments
+        .cloned()
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    timeout_fn(&arguments, configured).min(configured * MAX_FACTOR)
+}
+
+/// `timeout` in whole seconds, or in milliseconds if it has a fraction
+pub fn describe(timeout: Duration) -> String {
+    if timeout.subsec_nanos() == 0 {
+        format!("{}s", timeout.as_secs())
+    } else {
+        format!("{}ms", timeout.as_millis())
+    }
+}
+
+/// The configured timeout plus a millisecond per item
+pub fn process_list_timeout(arguments: &Value, configured: Duration) -> Duration {
+    #[derive(Deserialize)]
+    struct Arguments {
+        #[serde(default)]
+        items: Vec<String>,
+    }
+    match Arguments::deserialize(arguments) {
+        Ok(arguments) => {
+            configured + PER_ITEM * u32::try_from(arguments.items.len()).unwrap_or(u32::MAX)
+        }
+        Err(_) => configured,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONFIGURED: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn grows_with_the_items_of_process_list() {
+        let items: Vec<String> = (0..500).map(|i| i.to_string()).collect();
+        let arguments = json!({ "items": items, "operation": "count" });
+        assert_eq!(
+            timeout_for("process_list", Some(&arguments), CONFIGURED),
+            CONFIGURED + Duration::from_millis(500)
+        );
+        assert_eq!(timeout_for("process_list", None, CONFIGURED), CONFIGURED);
+        let unreadable = json!({ "items": 3 });
+        assert_eq!(
+            timeout_for("process_list", Some(&unreadable), CONFIGURED),
+            CONFIGURED
+        );
+    }
+
+    #[test]
+    fn is_capped_at_a_multiple_of_the_configured_timeout() {
+        let configured = Duration::from_millis(10);
+        let items: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+        let arguments = json!({ "items": items });
+        assert_eq!(
+            timeout_for("process_list", Some(&arguments), configured),
+            configured * MAX_FACTOR
+        );
+    }
+
+    #[test]
+    fn other_tools_keep_the_configured_timeout() {
+        assert_eq!(
+            timeout_for("echo", Some(&json!({})), CONFIGURED),
+            CONFIGURED
+        );
+    }
+
+    #[test]
+    fn describes_whole_seconds_and_fractions() {
+        assert_eq!(describe(Duration::from_secs(30)), "30s");
+        assert_eq!(describe(Duration::from_millis(30_500)), "30500ms");
+    }
+}
@@ -0,0 +1,142 @@
+//! Tool errors with a code and structured details
+//!
+//! A failing tool normally reaches the client as an error message only. A tool
+//! whose failures clients should handle programmatically (insufficient funds,
+//! a conflicting edit) returns [`ToolError`] instead, with a payload type that
+//! implements [`ErrorCode`] and `Serialize`. The backend turns it into a
+//! JSON-RPC error whose `data` holds the payload's code and the payload:
+//!
+//! ```json
+//! {
+//!   "code": -32602,
+//!   "message": "Insufficient funds",
+//!   "data": { "code": 1001, "details": { "balance": "5.00", "requested": "7.50" } }
+//! }
+//! ```
+//!
+//! The 0.11 `ErrorCode` enum only holds the standard JSON-RPC and MCP codes, so
+//! the payload's code is carried in `data` and the JSON-RPC code stays
+//! `InvalidParams`, as for every other tool error.
+
+use pulseengine_mcp_protocol::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Rendered errors always start with their tag, which keeps detection cheap
+const TOOL_ERROR_PREFIX: &str = r#"{"type":"tool_error""#;
+
+/// Application-defined code of a tool error payload
+pub trait ErrorCode {
+    /// The code clients branch on, e.g. `1001`
+    fn code(&self) -> i32;
+}
+
+/// A tool error with a message and a structured payload
+pub struct ToolError<E> {
+    message: String,
+    payload: E,
+}
+
+impl<E: Serialize + ErrorCode> ToolError<E> {
+    pub fn new(message: impl Into<String>, payload: E) -> Self {
+        Self {
+            message: message.into(),
+            payload,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn payload(&self) -> &E {
+        &self.payload
+    }
+}
+
+/// Code, message and serialized payload of a [`ToolError`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename = "tool_error")]
+pub struct ToolErrorData {
+    pub code: i32,
+    pub message: String,
+    pub details: Value,
+}
+
+impl ToolErrorData {
+    /// The `data` of the JSON-RPC error
+    pub fn data(&self) -> Value {
+        json!({ "code": self.code, "details": self.details })
+    }
+}
+
+impl<E: Serialize + ErrorCode> std::fmt::Display for ToolError<E> {
+    /// The macros pass tool errors on as their `Display` text, so this writes
+    /// the shape [`unpack`] recognizes
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let data = ToolErrorData {
+            code: self.payload.code(),
+            message: self.message.clone(),
+            details: serde_json::to_value(&self.payload).map_err(|_| std::fmt::Error)?,
+        };
+        match serde_json::to_string(&data) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+impl<E: Serialize + ErrorCode> std::fmt::Debug for ToolError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl<E: Serialize + ErrorCode> std::error::Error for ToolError<E> {}
+
+/// The [`ToolErrorData`] rendered into the message of a tool call error, if any
+pub fn unpack(error: &Error) -> Option<ToolErrorData> {
+    let start = error.message.find(TOOL_ERROR_PREFIX)?;
+    serde_json::from_str(&error.message[start..]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct InsufficientFunds {
+        balance: String,
+        requested: String,
+    }
+
+    impl ErrorCode for InsufficientFunds {
+        fn code(&self) -> i32 {
+            1001
+        }
+    }
+
+    #[test]
+    fn carries_the_code_and_payload_through_the_message() {
+        let error = ToolError::new(
+            "Insufficient funds",
+            InsufficientFunds {
+                balance: "5.00".to_string(),
+                requested: "7.50".to_string(),
+            },
+        );
+        // As the macros pass it on
+        let wrapped = Error::invalid_params(format!("Tool execution failed: {error}"));
+        let data = unpack(&wrapped).expect("the payload is found");
+        assert_eq!(data.message, "Insufficient funds");
+        assert_eq!(
+            data.data(),
+            json!({ "code": 1001, "details": { "balance": "5.00", "requested": "7.50" } })
+        );
+    }
+
+    #[test]
+    fn finds_nothing_in_plain_errors() {
+        assert_eq!(unpack(&Error::invalid_params("Missing parameter")), None);
+    }
+}
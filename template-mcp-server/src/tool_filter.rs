@@ -0,0 +1,97 @@
+//! Exposing a subset of the tools per deployment
+//!
+//! One binary can serve different tools in different deployments without
+//! recompiling. `AccessConfig::enabled_tools` lists the tools a deployment
+//! serves, all of them if it's empty, and `AccessConfig::disabled_tools` lists
+//! tools it never serves, even if enabled. A tool filtered out is left out of
+//! `tools/list` and everything built from it, and calls to it fail with a
+//! Method Not Found error, as if it didn't exist.
+//!
+//! An entry names a tool or, for a tool served in several versions, all of
+//! them: `echo` covers `echo@1` and `echo@2`, while `echo@1` covers only that
+//! version; see [`crate::tool_versions`].
+
+use crate::ServerConfig;
+use pulseengine_mcp_protocol::{Error, ErrorCode};
+
+/// A call to a tool this deployment doesn't serve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilteredTool(pub String);
+
+impl std::fmt::Display for FilteredTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown tool: {}", self.0)
+    }
+}
+
+impl std::error::Error for FilteredTool {}
+
+impl From<FilteredTool> for Error {
+    fn from(filtered: FilteredTool) -> Self {
+        Self::new(ErrorCode::MethodNotFound, filtered.to_string())
+    }
+}
+
+/// Whether `config` lets this deployment serve `tool`
+pub fn is_enabled(tool: &str, config: &ServerConfig) -> bool {
+    let listed = |entries: &[String]| entries.iter().any(|entry| covers(entry, tool));
+    (config.access.enabled_tools.is_empty() || listed(&config.access.enabled_tools))
+        && !listed(&config.access.disabled_tools)
+}
+
+/// Reject a call to a tool `config` filters out
+pub fn check(tool: &str, config: &ServerConfig) -> Result<(), FilteredTool> {
+    if is_enabled(tool, config) {
+        Ok(())
+    } else {
+        Err(FilteredTool(tool.to_string()))
+    }
+}
+
+/// Whether `entry` names `tool` or the tool `tool` is a version of
+fn covers(entry: &str, tool: &str) -> bool {
+    tool == entry
+        || tool
+            .strip_prefix(entry)
+            .is_some_and(|version| version.starts_with('@'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(enabled: &[&str], disabled: &[&str]) -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.access.enabled_tools = enabled.iter().map(ToString::to_string).collect();
+        config.access.disabled_tools = disabled.iter().map(ToString::to_string).collect();
+        config
+    }
+
+    #[test]
+    fn serves_every_tool_by_default() {
+        let config = ServerConfig::default();
+        assert!(is_enabled("echo", &config));
+        assert!(is_enabled("delete_data", &config));
+    }
+
+    #[test]
+    fn serves_only_enabled_tools_and_never_disabled_ones() {
+        let config = config(&["echo", "delete_data"], &["delete_data"]);
+        assert!(is_enabled("echo", &config));
+        assert!(!is_enabled("delete_data", &config));
+        assert!(!is_enabled("list_data", &config));
+        assert_eq!(
+            check("list_data", &config).unwrap_err().to_string(),
+            "Unknown tool: list_data"
+        );
+    }
+
+    #[test]
+    fn a_name_covers_every_version_of_a_tool() {
+        let config = config(&["echo"], &["echo@1"]);
+        assert!(is_enabled("echo@2", &config));
+        assert!(!is_enabled("echo@1", &config));
+        // Only a version suffix, not any name starting with the entry
+        assert!(!is_enabled("echo_twice", &config));
+    }
+}
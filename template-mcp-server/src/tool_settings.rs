@@ -0,0 +1,438 @@
+//! Per-tool settings, in one table
+//!
+//! Beyond its signature and description, the server knows a lot about each
+//! tool: its side effects, its cost, whether it needs confirmation, which
+//! parameters take units, and so on. The 0.11 macros take no per-tool options,
+//! so all of that is declared in [`TOOL_SETTINGS`], one [`ToolSettings`] per
+//! tool, and each feature module reads its field from there. Tools without an
+//! entry get the defaults of [`ToolSettings::new`].
+//!
+//! Entries name tools as served: a versioned tool has an entry per version,
+//! such as `echo@1`, and one for its alias, `echo` (see
+//! [`crate::tool_versions`]). A setting for a tool the server doesn't serve
+//! would be ignored without a word, so [`check`] fails startup on any entry
+//! naming an unknown tool, parameter or configuration field.
+
+use crate::client_capabilities::ClientCapability;
+use crate::costs::ToolCost;
+use crate::effects::ToolEffect;
+use crate::param_examples::{examples, ExamplesFn};
+use crate::response_size::OversizePolicy;
+use crate::structured::{any_object_schema, output_schema};
+use crate::sunset::Sunset;
+use crate::timeouts::TimeoutFn;
+use crate::units::Dimension;
+use crate::{config_loading, ServerConfig};
+use pulseengine_mcp_protocol::Tool;
+use serde_json::Value;
+
+/// What the server knows about one tool besides its definition
+#[derive(Debug, Clone, Copy)]
+pub struct ToolSettings {
+    /// Name of the tool as served
+    pub tool: &'static str,
+
+    /// Side effects, checked against `AccessConfig::allowed_effects`; see
+    /// [`crate::effects`]
+    pub effects: &'static [ToolEffect],
+    /// Only admin clients may call it; see [`crate::admin`]
+    pub admin: bool,
+    /// Destroys data, so safe mode turns it off; see [`crate::safe_mode`]
+    pub destructive: bool,
+    /// Runs only once confirmed, with a summary of what a call with the given
+    /// arguments would do; see [`crate::confirmation`]
+    pub confirmation: Option<fn(&Value) -> String>,
+    /// Tools a session must call successfully first; see
+    /// [`crate::call_order`]
+    pub requires: &'static [&'static str],
+    /// Client capabilities it needs; see [`crate::client_capabilities`]
+    pub capabilities: &'static [ClientCapability],
+    /// Sunset date of a deprecated tool; see [`crate::sunset`]
+    pub sunset: Option<Sunset>,
+    /// For an alias, each version and the tool method implementing it, oldest
+    /// first; see [`crate::tool_versions`]
+    pub versions: &'static [(&'static str, &'static str)],
+
+    /// Rejects undeclared arguments; see [`crate::strict_arguments`]
+    pub strict: bool,
+    /// Groups of parameters of which a call gives exactly one; see
+    /// [`crate::param_groups`]
+    pub one_of: &'static [&'static [&'static str]],
+    /// List parameters that are `[]` when omitted; see
+    /// [`crate::default_empty`]
+    pub default_empty: &'static [&'static str],
+    /// Parameters and the paths of the `ServerConfig` fields they default
+    /// to, such as `tools.default_region`; see
+    /// [`crate::config_defaults`]
+    pub config_defaults: &'static [(&'static str, &'static str)],
+    /// Parameters taking a quantity in any unit of a dimension; see
+    /// [`crate::units`]
+    pub units: &'static [(&'static str, Dimension)],
+    /// Example values of parameters; see [`crate::param_examples`]
+    pub examples: &'static [(&'static str, ExamplesFn)],
+
+    /// Declared cost class; see [`crate::costs`]
+    pub cost: ToolCost,
+    /// Computes the timeout of a call from its arguments; see
+    /// [`crate::timeouts`]
+    pub timeout: Option<TimeoutFn>,
+    /// A plain `fn` that blocks, run on the blocking thread pool; see
+    /// [`crate::blocking`]
+    pub blocking: bool,
+    /// Calls go through a circuit breaker, configurable by name in
+    /// `ToolsConfig::tool_circuit_breakers`; see [`crate::circuit_breaker`]
+    pub circuit_breaker: bool,
+
+    /// Schema of its structured results; see [`crate::structured`] and
+    /// [`crate::table`]
+    pub output_schema: Option<fn() -> Value>,
+    /// Handling of results over `LimitsConfig::max_response_bytes`, if not
+    /// `LimitsConfig::oversize_responses`; see [`crate::response_size`]
+    pub oversize: Option<OversizePolicy>,
+}
+
+impl ToolSettings {
+    /// Settings of a tool with nothing special about it
+    pub const fn new(tool: &'static str) -> Self {
+        Self {
+            tool,
+            effects: &[],
+            admin: false,
+            destructive: false,
+            confirmation: None,
+            requires: &[],
+            capabilities: &[],
+            sunset: None,
+            versions: &[],
+            strict: false,
+            one_of: &[],
+            default_empty: &[],
+            config_defaults: &[],
+            units: &[],
+            examples: &[],
+            cost: ToolCost::Low,
+            timeout: None,
+            blocking: false,
+            circuit_breaker: false,
+            output_schema: None,
+            oversize: None,
+        }
+    }
+
+    /// Every parameter the settings name, for [`check`]
+    fn params(&self) -> impl Iterator<Item = &'static str> {
+        self.one_of
+            .iter()
+            .flat_map(|group| group.iter().copied())
+            .chain(self.default_empty.iter().copied())
+            .chain(self.config_defaults.iter().map(|(param, _)| *param))
+            .chain(self.units.iter().map(|(param, _)| *param))
+            .chain(self.examples.iter().map(|(param, _)| *param))
+    }
+}
+
+/// Settings of every tool with any, by name
+pub const TOOL_SETTINGS: &[ToolSettings] = &[
+    ToolSettings {
+        strict: true,
+        ..ToolSettings::new("add_numbers")
+    },
+    ToolSettings {
+        units: &[
+            ("distance", Dimension::Length),
+            ("duration", Dimension::Time),
+        ],
+        ..ToolSettings::new("average_speed")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        confirmation: Some(|_| "Delete all example data".to_string()),
+        ..ToolSettings::new("clear_data")
+    },
+    ToolSettings {
+        cost: ToolCost::Medium,
+        ..ToolSettings::new("countdown")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        default_empty: &["tags"],
+        examples: &[
+            ("name", || examples::<String>(["widget".into()])),
+            ("value", || examples::<f64>([42.0])),
+        ],
+        circuit_breaker: true,
+        output_schema: Some(output_schema::<crate::ExampleData>),
+        ..ToolSettings::new("create_data")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        default_empty: &["tags"],
+        cost: ToolCost::Medium,
+        output_schema: Some(output_schema::<crate::bulk::BulkResult<crate::ExampleData>>),
+        ..ToolSettings::new("create_data_bulk")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        cost: ToolCost::Medium,
+        ..ToolSettings::new("data_report")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        confirmation: Some(crate::confirmation::delete_data_summary),
+        ..ToolSettings::new("delete_data")
+    },
+    ToolSettings {
+        versions: &[("1", "echo"), ("2", "echo_v2")],
+        examples: &[("message", || examples::<String>(["hello world".into()]))],
+        ..ToolSettings::new("echo")
+    },
+    ToolSettings {
+        sunset: Some(Sunset {
+            date: "2027-06-01",
+            replacement: Some("echo@2"),
+        }),
+        ..ToolSettings::new("echo@1")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        ..ToolSettings::new("example_with_panic")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        cost: ToolCost::Medium,
+        ..ToolSettings::new("export_ndjson")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        admin: true,
+        cost: ToolCost::Medium,
+        output_schema: Some(output_schema::<crate::snapshot::Snapshot>),
+        ..ToolSettings::new("export_snapshot")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Network],
+        cost: ToolCost::High,
+        ..ToolSettings::new("fetch_upstream")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        ..ToolSettings::new("filter_data")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        one_of: &[&["id", "name"]],
+        output_schema: Some(output_schema::<crate::ExampleData>),
+        ..ToolSettings::new("find_data")
+    },
+    ToolSettings {
+        cost: ToolCost::High,
+        blocking: true,
+        ..ToolSettings::new("hash_rounds")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        admin: true,
+        destructive: true,
+        requires: &["export_snapshot"],
+        cost: ToolCost::Medium,
+        ..ToolSettings::new("import_snapshot")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        output_schema: Some(crate::table::table_schema),
+        oversize: Some(OversizePolicy::Truncate),
+        ..ToolSettings::new("list_data")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Filesystem],
+        capabilities: &[ClientCapability::Roots],
+        cost: ToolCost::Medium,
+        ..ToolSettings::new("list_roots")
+    },
+    ToolSettings {
+        cost: ToolCost::Medium,
+        ..ToolSettings::new("long_task")
+    },
+    ToolSettings {
+        output_schema: Some(output_schema::<crate::input_content::ProcessedDocument>),
+        ..ToolSettings::new("process_document")
+    },
+    ToolSettings {
+        default_empty: &["items"],
+        examples: &[("operation", || {
+            examples::<String>(["count".into(), "join".into(), "reverse".into()])
+        })],
+        cost: ToolCost::Medium,
+        timeout: Some(crate::timeouts::process_list_timeout),
+        ..ToolSettings::new("process_list")
+    },
+    ToolSettings {
+        admin: true,
+        destructive: true,
+        ..ToolSettings::new("reset_tool_stats")
+    },
+    ToolSettings {
+        config_defaults: &[("region", "tools.default_region")],
+        ..ToolSettings::new("service_endpoint")
+    },
+    ToolSettings {
+        effects: &[ToolEffect::Database],
+        output_schema: Some(any_object_schema),
+        ..ToolSettings::new("tag_counts")
+    },
+];
+
+static DEFAULT: ToolSettings = ToolSettings::new("");
+
+/// The settings of `tool`, the defaults if it has no entry
+pub fn settings_of(tool: &str) -> &'static ToolSettings {
+    TOOL_SETTINGS
+        .iter()
+        .find(|settings| settings.tool == tool)
+        .unwrap_or(&DEFAULT)
+}
+
+/// Entries of [`TOOL_SETTINGS`] naming something the server doesn't have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsError {
+    pub problems: Vec<String>,
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid tool settings")?;
+        for problem in &self.problems {
+            write!(f, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// Check every entry of [`TOOL_SETTINGS`] against the served `tools`
+///
+/// Each entry must name a served tool, and the parameters it names must be
+/// in that tool's input schema. Tools it requires first or names as a
+/// replacement must be served too, and config defaults must name a
+/// `ServerConfig` field.
+pub fn check(tools: &[Tool]) -> Result<(), SettingsError> {
+    check_entries(TOOL_SETTINGS, tools)
+}
+
+fn check_entries(entries: &[ToolSettings], tools: &[Tool]) -> Result<(), SettingsError> {
+    let served = |name: &str| tools.iter().find(|tool| tool.name == name);
+    let config = serde_json::to_value(ServerConfig::default()).unwrap_or(Value::Null);
+    let mut problems = Vec::new();
+    for (i, settings) in entries.iter().enumerate() {
+        let name = settings.tool;
+        if entries[..i].iter().any(|earlier| earlier.tool == name) {
+            problems.push(format!("'{name}' has more than one entry"));
+        }
+        let Some(tool) = served(name) else {
+            problems.push(format!("'{name}' is not a tool this server serves"));
+            continue;
+        };
+        let properties = tool
+            .input_schema
+            .get("properties")
+            .and_then(Value::as_object);
+        for param in settings.params() {
+            if !properties.is_some_and(|properties| properties.contains_key(param)) {
+                problems.push(format!("'{name}' has no parameter '{param}'"));
+            }
+        }
+        for (param, field) in settings.config_defaults {
+            if config_loading::field_value(&config, field).is_none() {
+                problems.push(format!(
+                    "'{name}' defaults '{param}' to '{field}', which is not a ServerConfig field"
+                ));
+            }
+        }
+        let others = settings
+            .requires
+            .iter()
+            .copied()
+            .chain(settings.sunset.and_then(|sunset| sunset.replacement));
+        for other in others {
+            if served(other).is_none() {
+                problems.push(format!(
+                    "'{name}' refers to '{other}', which is not a tool this server serves"
+                ));
+            }
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(SettingsError { problems })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TemplateBackend, TemplateMcpServer};
+    use serde_json::json;
+
+    fn tool(name: &str, params: &[&str]) -> Tool {
+        let properties: serde_json::Map<_, _> = params
+            .iter()
+            .map(|param| (param.to_string(), json!({ "type": "string" })))
+            .collect();
+        Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({ "type": "object", "properties": properties }),
+            output_schema: None,
+        }
+    }
+
+    #[test]
+    fn every_entry_names_a_served_tool() {
+        let backend = TemplateBackend::new(TemplateMcpServer::default());
+        assert_eq!(check(&backend.tools().definitions()), Ok(()));
+    }
+
+    #[test]
+    fn tools_without_an_entry_get_the_defaults() {
+        let settings = settings_of("no_such_tool");
+        assert!(settings.effects.is_empty());
+        assert_eq!(settings.cost, ToolCost::Low);
+        assert!(settings.confirmation.is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_tools_and_parameters() {
+        let entries = [
+            ToolSettings {
+                destructive: true,
+                ..ToolSettings::new("delete_dat")
+            },
+            ToolSettings {
+                default_empty: &["tag"],
+                requires: &["prepare"],
+                ..ToolSettings::new("create")
+            },
+            ToolSettings {
+                config_defaults: &[("region", "region_default")],
+                ..ToolSettings::new("endpoint")
+            },
+            ToolSettings::new("endpoint"),
+        ];
+        let tools = [tool("create", &["tags"]), tool("endpoint", &["region"])];
+
+        assert_eq!(
+            check_entries(&entries, &tools).map_err(|e| e.problems),
+            Err(vec![
+                "'delete_dat' is not a tool this server serves".to_string(),
+                "'create' has no parameter 'tag'".to_string(),
+                "'create' refers to 'prepare', which is not a tool this server serves".to_string(),
+                "'endpoint' defaults 'region' to 'region_default', which is not a \
+                 ServerConfig field"
+                    .to_string(),
+                "'endpoint' has more than one entry".to_string(),
+            ])
+        );
+    }
+}
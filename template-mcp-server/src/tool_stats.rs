@@ -0,0 +1,191 @@
+//! Per-tool call statistics
+//!
+//! The backend records every call to a known tool that passes the quota,
+//! admin and confirmation checks in [`ToolStats`]: how long it took and
+//! whether it failed, counting rejected arguments and results with `isError`
+//! as failures. `template://stats/tools` serves the counts, error counts, p50 and
+//! p95 latency and time of the last call of each tool, for a quick view of
+//! which tools are hot without a metrics stack. The admin tool
+//! `reset_tool_stats` clears them.
+//!
+//! Latency percentiles are computed over each tool's most recent
+//! [`LATENCY_SAMPLES`] calls; the counts cover every call since the last
+//! reset.
+
+use crate::clock::Clock;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const TOOL_STATS_URI: &str = "template://stats/tools";
+
+/// Latencies kept per tool for the percentiles
+pub const LATENCY_SAMPLES: usize = 1000;
+
+/// Statistics of one tool
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolStatsEntry {
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Median latency in milliseconds
+    pub p50_ms: f64,
+    /// 95th percentile latency in milliseconds
+    pub p95_ms: f64,
+    pub last_called: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct Counters {
+    calls: u64,
+    errors: u64,
+    latencies_ms: VecDeque<f64>,
+    last_called: DateTime<Utc>,
+}
+
+/// Call statistics of every tool called since the last reset
+pub struct ToolStats {
+    clock: Arc<dyn Clock>,
+    tools: Mutex<BTreeMap<String, Counters>>,
+}
+
+impl std::fmt::Debug for ToolStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolStats")
+            .field("tools", &self.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ToolStats {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            tools: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Counters>> {
+        self.tools
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Record a call to `tool` that took `latency`
+    pub fn record(&self, tool: &str, latency: Duration, failed: bool) {
+        let now = self.clock.now();
+        let mut tools = self.lock();
+        let counters = tools.entry(tool.to_string()).or_insert_with(|| Counters {
+            calls: 0,
+            errors: 0,
+            latencies_ms: VecDeque::with_capacity(LATENCY_SAMPLES),
+            last_called: now,
+        });
+        counters.calls += 1;
+        if failed {
+            counters.errors += 1;
+        }
+        if counters.latencies_ms.len() == LATENCY_SAMPLES {
+            counters.latencies_ms.pop_front();
+        }
+        counters
+            .latencies_ms
+            .push_back(latency.as_secs_f64() * 1000.0);
+        counters.last_called = now;
+    }
+
+    /// Statistics of each tool called since the last reset, by tool name
+    pub fn entries(&self) -> Vec<ToolStatsEntry> {
+        self.lock()
+            .iter()
+            .map(|(tool, counters)| {
+                let mut latencies: Vec<f64> = counters.latencies_ms.iter().copied().collect();
+                latencies.sort_by(f64::total_cmp);
+                ToolStatsEntry {
+                    tool: tool.clone(),
+                    calls: counters.calls,
+                    errors: counters.errors,
+                    p50_ms: percentile(&latencies, 0.50),
+                    p95_ms: percentile(&latencies, 0.95),
+                    last_called: counters.last_called,
+                }
+            })
+            .collect()
+    }
+
+    /// Forget every tool's statistics, returning how many tools had any
+    pub fn reset(&self) -> usize {
+        let mut tools = self.lock();
+        let cleared = tools.len();
+        tools.clear();
+        cleared
+    }
+}
+
+/// Nearest-rank percentile of `sorted`, or 0 if it is empty
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn start() -> DateTime<Utc> {
+        "2026-01-01T00:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn counts_calls_and_errors_per_tool() {
+        let clock = Arc::new(ManualClock::new(start()));
+        let stats = ToolStats::new(clock.clone());
+        stats.record("echo", Duration::from_millis(10), false);
+        clock.advance(chrono::Duration::seconds(60));
+        stats.record("echo", Duration::from_millis(30), true);
+        stats.record("add_numbers", Duration::from_millis(5), false);
+
+        let entries = stats.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tool, "add_numbers");
+        let echo = &entries[1];
+        assert_eq!((echo.calls, echo.errors), (2, 1));
+        assert_eq!((echo.p50_ms, echo.p95_ms), (10.0, 30.0));
+        assert_eq!(echo.last_called, start() + chrono::Duration::seconds(60));
+    }
+
+    #[test]
+    fn keeps_the_latest_latencies() {
+        let stats = ToolStats::new(Arc::new(ManualClock::new(start())));
+        stats.record("echo", Duration::from_secs(100), false);
+        for _ in 0..LATENCY_SAMPLES {
+            stats.record("echo", Duration::from_millis(1), false);
+        }
+        let echo = &stats.entries()[0];
+        assert_eq!(echo.calls, LATENCY_SAMPLES as u64 + 1);
+        assert_eq!(echo.p95_ms, 1.0);
+    }
+
+    #[test]
+    fn resets_every_tool() {
+        let stats = ToolStats::new(Arc::new(ManualClock::new(start())));
+        stats.record("echo", Duration::ZERO, false);
+        stats.record("add_numbers", Duration::ZERO, false);
+        assert_eq!(stats.reset(), 2);
+        assert!(stats.entries().is_empty());
+    }
+
+    #[test]
+    fn takes_nearest_rank_percentiles() {
+        let sorted: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 0.50), 10.0);
+        assert_eq!(percentile(&sorted, 0.95), 19.0);
+        assert_eq!(percentile(&[], 0.95), 0.0);
+    }
+}
@@ -0,0 +1,186 @@
+//! Several versions of a tool served side by side
+//!
+//! Changing what a tool does breaks agents written against the old behavior.
+//! A tool with a `versions` setting (see [`crate::tool_settings`]) is instead
+//! served once per version, as
+//! `name@version`, so such agents can pin the version they know, plus once as
+//! plain `name`, an alias of the latest version for everyone else. Each
+//! version is a separate tool method, e.g. `echo` and `echo_v2`, whose own
+//! names aren't served. Every served definition carries its version as the
+//! `x-version` keyword of its input schema.
+//!
+//! The setting belongs to the alias, and other settings name tools as served
+//! too: an entry for `echo` applies to the alias only, not to `echo@1`, and
+//! none applies to `echo_v2`, which isn't served under that name.
+
+use crate::registry::Tool;
+use crate::tool_settings::{ToolSettings, TOOL_SETTINGS};
+use async_trait::async_trait;
+use pulseengine_mcp_protocol::{CallToolResult, Error, Tool as ToolDefinition};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Name of `version` of the tool `name`, e.g. `echo@2`
+pub fn versioned_name(name: &str, version: &str) -> String {
+    format!("{name}@{version}")
+}
+
+/// The settings of the alias of the version `tool` implements, if any
+fn versions_implemented_by(tool: &str) -> Option<&'static ToolSettings> {
+    TOOL_SETTINGS
+        .iter()
+        .find(|settings| settings.versions.iter().any(|(_, by)| *by == tool))
+}
+
+/// A tool served under another name, with its version in its schema
+struct VersionedTool {
+    inner: Arc<dyn Tool>,
+    name: String,
+    version: &'static str,
+}
+
+#[async_trait]
+impl Tool for VersionedTool {
+    fn definition(&self) -> ToolDefinition {
+        let mut definition = self.inner.definition();
+        definition.name = self.name.clone();
+        if let Some(schema) = definition.input_schema.as_object_mut() {
+            schema.insert("x-version".to_string(), Value::from(self.version));
+        }
+        definition
+    }
+
+    async fn invoke(&self, arguments: Option<Value>) -> Result<CallToolResult, Error> {
+        self.inner.invoke(arguments).await
+    }
+}
+
+/// `tools` with the tools implementing versions replaced by the versions
+/// and aliases they serve, where the first of them was
+pub fn apply(tools: Vec<Box<dyn Tool>>) -> Vec<Box<dyn Tool>> {
+    let mut implementing: HashMap<String, Arc<dyn Tool>> = HashMap::new();
+    let mut slots = Vec::with_capacity(tools.len());
+    for tool in tools {
+        let name = tool.definition().name;
+        match versions_implemented_by(&name) {
+            Some(versions) => {
+                if !slots
+                    .iter()
+                    .any(|slot| matches!(slot, Slot::Versions(v) if v.tool == versions.tool))
+                {
+                    slots.push(Slot::Versions(versions));
+                }
+                implementing.insert(name, Arc::from(tool));
+            }
+            None => slots.push(Slot::Tool(tool)),
+        }
+    }
+
+    let mut served: Vec<Box<dyn Tool>> = Vec::new();
+    for slot in slots {
+        let versions = match slot {
+            Slot::Tool(tool) => {
+                served.push(tool);
+                continue;
+            }
+            Slot::Versions(versions) => versions,
+        };
+        if let Some((version, by)) = versions.versions.last().copied() {
+            if let Some(inner) = implementing.get(by) {
+                served.push(Box::new(VersionedTool {
+                    inner: inner.clone(),
+                    name: versions.tool.to_string(),
+                    version,
+                }));
+            }
+        }
+        for (version, by) in versions.versions {
+            match implementing.get(*by) {
+                Some(inner) => served.push(Box::new(VersionedTool {
+                    inner: inner.clone(),
+                    name: versioned_name(versions.tool, version),
+                    version,
+                })),
+                None => tracing::warn!(
+                    "No tool '{by}' for version {version} of '{}'",
+                    versions.tool
+                ),
+            }
+        }
+    }
+    served
+}
+
+enum Slot {
+    Tool(Box<dyn Tool>),
+    Versions(&'static ToolSettings),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A tool answering with its own name
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl Tool for Named {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: self.0.to_string(),
+                description: String::new(),
+                input_schema: json!({ "type": "object" }),
+                output_schema: None,
+            }
+        }
+
+        async fn invoke(&self, _arguments: Option<Value>) -> Result<CallToolResult, Error> {
+            Ok(CallToolResult::text(self.0))
+        }
+    }
+
+    fn tools(names: &[&'static str]) -> Vec<Box<dyn Tool>> {
+        names
+            .iter()
+            .map(|name| Box::new(Named(name)) as Box<dyn Tool>)
+            .collect()
+    }
+
+    #[test]
+    fn serves_each_version_and_an_alias_in_place() {
+        let served = apply(tools(&["add_numbers", "echo", "get_status", "echo_v2"]));
+        let names: Vec<String> = served.iter().map(|tool| tool.definition().name).collect();
+        assert_eq!(
+            names,
+            ["add_numbers", "echo", "echo@1", "echo@2", "get_status"]
+        );
+        let versions: Vec<Value> = served
+            .iter()
+            .map(|tool| tool.definition().input_schema["x-version"].clone())
+            .collect();
+        assert_eq!(
+            versions,
+            [json!(null), json!("2"), json!("1"), json!("2"), json!(null)]
+        );
+    }
+
+    #[tokio::test]
+    async fn the_alias_calls_the_latest_version() {
+        let served = apply(tools(&["echo", "echo_v2"]));
+        let mut answers = Vec::new();
+        for tool in &served {
+            let content = serde_json::to_value(tool.invoke(None).await.unwrap().content).unwrap();
+            answers.push(content[0]["text"].clone());
+        }
+        assert_eq!(answers, [json!("echo_v2"), json!("echo"), json!("echo_v2")]);
+    }
+
+    #[test]
+    fn skips_versions_without_a_tool() {
+        let served = apply(tools(&["echo"]));
+        let names: Vec<String> = served.iter().map(|tool| tool.definition().name).collect();
+        assert_eq!(names, ["echo@1"]);
+    }
+}
@@ -0,0 +1,382 @@
+//! TypeScript declarations for the tool schemas
+//!
+//! [`emit`] turns the tool definitions served by `tools/list` into a `.d.ts`
+//! file, so clients can type their calls from the same schemas the server
+//! advertises. For every tool it declares:
+//!
+//! - `{Tool}Params`, from the input schema
+//! - `{Tool}Result`, from the output schema; tools without one return text, so
+//!   their result is `string`
+//!
+//! Object schemas with a `title` (such as `ExampleData`) and `$defs` entries
+//! become named interfaces. Resources carry no schema, so they aren't covered.
+//!
+//! Rust types map through their JSON schema:
+//!
+//! | Rust                          | JSON schema                    | TypeScript         |
+//! | ----------------------------- | ------------------------------ | ------------------ |
+//! | `String`                      | `string`                       | `string`           |
+//! | `f64`, `i64`, `u64`, ...      | `number`, `integer`            | `number`           |
+//! | `bool`                        | `boolean`                      | `boolean`          |
+//! | `Vec<T>`                      | `array` with `items`           | `Array<T>`         |
+//! | `Option<T>` parameter / field | not in `required`              | optional (`name?`) |
+//! | `Option<T>` elsewhere         | type including `null`          | `T \| null`        |
+//! | struct                        | `object` with `properties`     | interface          |
+//! | `HashMap<String, T>`, `Value` | `object` without `properties`  | `Record<string, unknown>` |
+//! | enum of unit variants         | `enum`                         | union of literals  |
+//!
+//! Integers beyond 2^53 lose precision as a JS `number`.
+
+use pulseengine_mcp_protocol::Tool;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Header written at the top of the generated file
+pub const HEADER: &str = "// Generated by `cargo run --bin emit-ts-types`. Do not edit.\n";
+
+/// A `.d.ts` declaring the params and result of each tool
+pub fn emit(tools: &[Tool]) -> String {
+    let mut emitter = Emitter::default();
+    let mut out = String::from(HEADER);
+
+    for tool in tools {
+        let name = pascal_case(&tool.name);
+        out.push('\n');
+        write_doc(&mut out, "", Some(&tool.description));
+        out.push_str(&emitter.declaration(&format!("{name}Params"), &tool.input_schema));
+
+        out.push('\n');
+        match &tool.output_schema {
+            Some(schema) => match emitter.named_object(schema) {
+                Some(title) => {
+                    let _ = writeln!(out, "export type {name}Result = {title};");
+                }
+                None => out.push_str(&emitter.declaration(&format!("{name}Result"), schema)),
+            },
+            None => {
+                let _ = writeln!(out, "export type {name}Result = string;");
+            }
+        }
+    }
+
+    // Named types are collected while emitting the tools, so they go last
+    for declaration in emitter.named.values() {
+        out.push('\n');
+        out.push_str(declaration);
+    }
+    out
+}
+
+#[derive(Default)]
+struct Emitter {
+    /// Named interfaces by name, from `title`d objects and `$defs`
+    named: BTreeMap<String, String>,
+}
+
+impl Emitter {
+    /// `export interface` for an object schema, `export type` otherwise
+    fn declaration(&mut self, name: &str, schema: &Value) -> String {
+        if let Some(defs) = schema.get("$defs").and_then(Value::as_object) {
+            for (def_name, def) in defs {
+                // Named as `$ref`s to it are, by `type_of`
+                let def_name = pascal_case(def_name);
+                if !self.named.contains_key(&def_name) {
+                    let declaration = self.declaration(&def_name, def);
+                    self.named.insert(def_name, declaration);
+                }
+            }
+        }
+
+        match object_properties(schema) {
+            Some(properties) => {
+                let mut out = String::new();
+                write_doc(&mut out, "", description(schema));
+                let fields = self.fields(schema, properties, "  ");
+                if fields.is_empty() {
+                    let _ = writeln!(out, "export interface {name} {{}}");
+                } else {
+                    let _ = writeln!(out, "export interface {name} {{\n{fields}}}");
+                }
+                out
+            }
+            None => format!("export type {name} = {};\n", self.type_of(schema, "")),
+        }
+    }
+
+    /// Register a titled object schema as a named interface
+    fn named_object(&mut self, schema: &Value) -> Option<String> {
+        let title = schema.get("title").and_then(Value::as_str)?;
+        object_properties(schema)?;
+        let title = pascal_case(title);
+        if !self.named.contains_key(&title) {
+            let declaration = self.declaration(&title, schema);
+            self.named.insert(title.clone(), declaration);
+        }
+        Some(title)
+    }
+
+    fn fields(&mut self, schema: &Value, properties: &Map<String, Value>, indent: &str) -> String {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+
+        let mut out = String::new();
+        for (name, property) in properties {
+            write_doc(&mut out, indent, description(property));
+            let optional = if required.contains(&name.as_str()) {
+                ""
+            } else {
+                "?"
+            };
+            let nested = format!("{indent}  ");
+            let _ = writeln!(
+                out,
+                "{indent}{}{optional}: {};",
+                property_name(name),
+                self.type_of(property, &nested)
+            );
+        }
+        out
+    }
+
+    fn type_of(&mut self, schema: &Value, indent: &str) -> String {
+        let Some(fields) = schema.as_object() else {
+            // `true` allows anything
+            return "unknown".to_string();
+        };
+
+        if let Some(reference) = fields.get("$ref").and_then(Value::as_str) {
+            return reference
+                .rsplit('/')
+                .next()
+                .map(pascal_case)
+                .unwrap_or_else(|| "unknown".to_string());
+        }
+        if let Some(value) = fields.get("const") {
+            return value.to_string();
+        }
+        if let Some(values) = fields.get("enum").and_then(Value::as_array) {
+            return union(values.iter().map(Value::to_string));
+        }
+        if let Some(variants) = fields
+            .get("anyOf")
+            .or_else(|| fields.get("oneOf"))
+            .and_then(Value::as_array)
+        {
+            let variants: Vec<String> = variants
+                .iter()
+                .map(|variant| self.type_of(variant, indent))
+                .collect();
+            return union(variants);
+        }
+        if let Some(title) = self.named_object(schema) {
+            return title;
+        }
+
+        match fields.get("type") {
+            Some(Value::String(name)) => self.type_named(name, schema, indent),
+            Some(Value::Array(names)) => {
+                let names: Vec<String> = names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|name| self.type_named(name, schema, indent))
+                    .collect();
+                union(names)
+            }
+            _ => "unknown".to_string(),
+        }
+    }
+
+    fn type_named(&mut self, name: &str, schema: &Value, indent: &str) -> String {
+        match name {
+            "string" => "string".to_string(),
+            "number" | "integer" => "number".to_string(),
+            "boolean" => "boolean".to_string(),
+            "null" => "null".to_string(),
+            "array" => match schema.get("items") {
+                Some(items) => format!("Array<{}>", self.type_of(items, indent)),
+                None => "Array<unknown>".to_string(),
+            },
+            "object" => match object_properties(schema) {
+                Some(properties) => {
+                    let fields = self.fields(schema, properties, indent);
+                    let close = indent.strip_suffix("  ").unwrap_or("");
+                    format!("{{\n{fields}{close}}}")
+                }
+                None => "Record<string, unknown>".to_string(),
+            },
+            _ => "unknown".to_string(),
+        }
+    }
+}
+
+fn object_properties(schema: &Value) -> Option<&Map<String, Value>> {
+    let is_object = match schema.get("type") {
+        Some(Value::String(name)) => name == "object",
+        None => true,
+        Some(_) => false,
+    };
+    if !is_object || schema.get("$ref").is_some() {
+        return None;
+    }
+    schema.get("properties").and_then(Value::as_object)
+}
+
+fn description(schema: &Value) -> Option<&str> {
+    schema.get("description").and_then(Value::as_str)
+}
+
+fn union(types: impl IntoIterator<Item = String>) -> String {
+    let mut unique: Vec<String> = Vec::new();
+    for ty in types {
+        if !unique.contains(&ty) {
+            unique.push(ty);
+        }
+    }
+    if unique.is_empty() {
+        "never".to_string()
+    } else {
+        unique.join(" | ")
+    }
+}
+
+fn write_doc(out: &mut String, indent: &str, doc: Option<&str>) {
+    let Some(doc) = doc.map(str::trim).filter(|doc| !doc.is_empty()) else {
+        return;
+    };
+    let _ = writeln!(out, "{indent}/**");
+    for line in doc.lines() {
+        let line = line.replace("*/", "*\\/");
+        if line.trim().is_empty() {
+            let _ = writeln!(out, "{indent} *");
+        } else {
+            let _ = writeln!(out, "{indent} * {line}");
+        }
+    }
+    let _ = writeln!(out, "{indent} */");
+}
+
+/// `add_numbers` → `AddNumbers`
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn property_name(name: &str) -> String {
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        Value::String(name.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, input_schema: Value, output_schema: Option<Value>) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: "Does a thing".to_string(),
+            input_schema,
+            output_schema,
+        }
+    }
+
+    #[test]
+    fn declares_params_and_text_results() {
+        let add = tool(
+            "add_numbers",
+            json!({
+                "type": "object",
+                "properties": {
+                    "a": { "type": "number", "description": "First */ number" },
+                    "mode": { "enum": ["fast", "exact"] },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "label": { "type": ["string", "null"] },
+                    "extra-info": { "type": "object" }
+                },
+                "required": ["a"]
+            }),
+            None,
+        );
+        assert_eq!(
+            emit(&[add]),
+            format!(
+                "{HEADER}\n/**\n * Does a thing\n */\nexport interface AddNumbersParams {{\n  \
+                 /**\n   * First *\\/ number\n   */\n  a: number;\n  \
+                 \"extra-info\"?: Record<string, unknown>;\n  label?: string | null;\n  \
+                 mode?: \"fast\" | \"exact\";\n  tags?: Array<string>;\n}}\n\n\
+                 export type AddNumbersResult = string;\n"
+            )
+        );
+    }
+
+    #[test]
+    fn names_titled_objects_and_defs() {
+        let get = tool(
+            "get_data",
+            json!({
+                "type": "object",
+                "properties": { "filter": { "$ref": "#/$defs/filter" } },
+                "$defs": {
+                    "filter": {
+                        "type": "object",
+                        "properties": { "limit": { "type": "integer" } },
+                        "required": ["limit"]
+                    }
+                }
+            }),
+            Some(json!({
+                "title": "ExampleData",
+                "type": "object",
+                "properties": { "point": { "type": "object", "properties": { "x": {} } } }
+            })),
+        );
+        let declarations = emit(&[get]);
+        assert!(
+            declarations.contains("  filter?: Filter;\n"),
+            "{declarations}"
+        );
+        assert!(
+            declarations.contains("export type GetDataResult = ExampleData;\n"),
+            "{declarations}"
+        );
+        assert!(
+            declarations.ends_with(
+                "\nexport interface ExampleData {\n  point?: {\n    x?: unknown;\n  };\n}\n\
+                 \nexport interface Filter {\n  limit: number;\n}\n"
+            ),
+            "{declarations}"
+        );
+    }
+
+    #[test]
+    fn converts_names() {
+        assert_eq!(pascal_case("add_numbers"), "AddNumbers");
+        assert_eq!(pascal_case("echo-v2"), "EchoV2");
+        assert_eq!(property_name("$ok_1"), "$ok_1");
+        assert_eq!(property_name("1st"), "\"1st\"");
+    }
+}
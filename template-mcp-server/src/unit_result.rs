@@ -0,0 +1,80 @@
+//! Results of tools that return `()`
+//!
+//! Command-style tools are written as `async fn ... -> anyhow::Result<()>`.
+//! The macros render such a result as the text `()`, which means nothing to a
+//! client, so the backend replaces it with the shape chosen by
+//! `ToolsConfig::unit_result`. String results are always rendered quoted, so
+//! the bare `()` text can't come from any other return type.
+
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde::{Deserialize, Serialize};
+
+// `format!("{:?}", ())`
+const UNIT_TEXT: &str = "()";
+
+/// What a client sees when a tool returns `()`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitResult {
+    /// `{"ok": true}`, as text and structured content
+    #[default]
+    Ok,
+    /// A successful result with no content
+    Empty,
+}
+
+/// Replace the rendering of `()` in a successful result
+pub fn apply_to_result(result: &mut CallToolResult, style: UnitResult) {
+    let is_unit = matches!(
+        result.content.as_slice(),
+        [Content::Text { text }] if text == UNIT_TEXT
+    );
+    if !is_unit || result.is_error == Some(true) {
+        return;
+    }
+
+    match style {
+        UnitResult::Ok => {
+            let ok = serde_json::json!({ "ok": true });
+            result.content = vec![Content::text(ok.to_string())];
+            result.structured_content = Some(ok);
+        }
+        UnitResult::Empty => result.content.clear(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_unit_as_ok() {
+        let mut unit = CallToolResult::text(UNIT_TEXT);
+        apply_to_result(&mut unit, UnitResult::Ok);
+        assert_eq!(unit.structured_content, Some(json!({ "ok": true })));
+        assert!(matches!(
+            unit.content.as_slice(),
+            [Content::Text { text }] if text == r#"{"ok":true}"#
+        ));
+    }
+
+    #[test]
+    fn renders_unit_as_nothing() {
+        let mut unit = CallToolResult::text(UNIT_TEXT);
+        apply_to_result(&mut unit, UnitResult::Empty);
+        assert!(unit.content.is_empty());
+        assert_eq!(unit.structured_content, None);
+    }
+
+    #[test]
+    fn leaves_other_results_and_errors_alone() {
+        let mut quoted = CallToolResult::text("\"()\"");
+        apply_to_result(&mut quoted, UnitResult::Empty);
+        assert_eq!(quoted.content.len(), 1);
+
+        let mut error = CallToolResult::error_text(UNIT_TEXT);
+        apply_to_result(&mut error, UnitResult::Ok);
+        assert_eq!(error.structured_content, None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Tool parameters normalized to SI units
+//!
+//! A parameter in a tool's `units` setting (see [`crate::tool_settings`]) is
+//! a quantity of some [`Dimension`].
+//! Clients pass either a plain number, taken to be in the SI unit (metres,
+//! kilograms or seconds), or a value with its unit, such as
+//! `{"value": 5, "unit": "ft"}`. Before the tool runs, the backend converts
+//! the latter to the SI unit, so the tool always gets a plain `f64`.
+//! `tools/list` advertises both forms and the accepted units, and a call with
+//! a unit that isn't in [`UNITS`] fails with the ones that are.
+//!
+//! Add entries to [`UNITS`] for more units, and a [`Dimension`] with its SI
+//! unit for more kinds of quantity.
+
+use pulseengine_mcp_protocol::Tool;
+use serde_json::{json, Value};
+
+/// A kind of quantity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Mass,
+    Time,
+}
+
+impl Dimension {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Length => "length",
+            Self::Mass => "mass",
+            Self::Time => "time",
+        }
+    }
+
+    /// Symbol of the unit values are converted to
+    pub fn si_unit(self) -> &'static str {
+        match self {
+            Self::Length => "m",
+            Self::Mass => "kg",
+            Self::Time => "s",
+        }
+    }
+
+    /// Symbols of the units accepted for this dimension
+    pub fn units(self) -> impl Iterator<Item = &'static str> {
+        UNITS
+            .iter()
+            .filter(move |unit| unit.dimension == self)
+            .map(|unit| unit.symbol)
+    }
+}
+
+/// A unit and how many SI units one of it is
+#[derive(Debug, Clone, Copy)]
+pub struct Unit {
+    pub dimension: Dimension,
+    pub symbol: &'static str,
+    pub to_si: f64,
+}
+
+const fn unit(dimension: Dimension, symbol: &'static str, to_si: f64) -> Unit {
+    Unit {
+        dimension,
+        symbol,
+        to_si,
+    }
+}
+
+/// Accepted units
+pub const UNITS: &[Unit] = &[
+    unit(Dimension::Length, "m", 1.0),
+    unit(Dimension::Length, "km", 1000.0),
+    unit(Dimension::Length, "cm", 0.01),
+    unit(Dimension::Length, "mm", 0.001),
+    unit(Dimension::Length, "in", 0.0254),
+    unit(Dimension::Length, "ft", 0.3048),
+    unit(Dimension::Length, "yd", 0.9144),
+    unit(Dimension::Length, "mi", 1609.344),
+    unit(Dimension::Mass, "kg", 1.0),
+    unit(Dimension::Mass, "g", 0.001),
+    unit(Dimension::Mass, "mg", 0.000_001),
+    unit(Dimension::Mass, "t", 1000.0),
+    unit(Dimension::Mass, "lb", 0.453_592_37),
+    unit(Dimension::Mass, "oz", 0.028_349_523_125),
+    unit(Dimension::Time, "s", 1.0),
+    unit(Dimension::Time, "ms", 0.001),
+    unit(Dimension::Time, "min", 60.0),
+    unit(Dimension::Time, "h", 3600.0),
+    unit(Dimension::Time, "d", 86400.0),
+];
+
+/// Parameters of `tool` taking a quantity, and the dimension of each
+fn params_for(tool: &str) -> &'static [(&'static str, Dimension)] {
+    crate::tool_settings::settings_of(tool).units
+}
+
+/// `value` in `unit`, converted to the SI unit of `dimension`
+pub fn to_si(value: f64, unit: &str, dimension: Dimension) -> Result<f64, String> {
+    UNITS
+        .iter()
+        .find(|known| known.dimension == dimension && known.symbol == unit)
+        .map(|known| value * known.to_si)
+        .ok_or_else(|| {
+            format!(
+                "Unknown {} unit '{unit}'; use one of: {}",
+                dimension.name(),
+                dimension.units().collect::<Vec<_>>().join(", ")
+            )
+        })
+}
+
+/// Replace each unit argument of `tool` given with a unit by its SI value
+pub fn apply_to_arguments(tool: &str, arguments: &mut Option<Value>) -> Result<(), String> {
+    let Some(args) = arguments.as_mut().and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+
+    for &(param, dimension) in params_for(tool) {
+        let Some(Value::Object(quantity)) = args.get(param) else {
+            continue;
+        };
+        let value = quantity.get("value").and_then(Value::as_f64);
+        let unit = quantity.get("unit").and_then(Value::as_str);
+        let (Some(value), Some(unit)) = (value, unit) else {
+            return Err(format!(
+                "Parameter '{}' needs a number \"value\" and a string \"unit\"",
+                param
+            ));
+        };
+        let converted =
+            to_si(value, unit, dimension).map_err(|e| format!("Parameter '{}': {e}", param))?;
+        args.insert(param.to_string(), json!(converted));
+    }
+    Ok(())
+}
+
+/// Accept a value with a unit for each unit parameter in a tool's schema
+pub fn apply_to_schema(tool: &mut Tool) {
+    for &(param, dimension) in params_for(&tool.name) {
+        let Some(property) = tool
+            .input_schema
+            .get_mut("properties")
+            .and_then(|p| p.get_mut(param))
+        else {
+            continue;
+        };
+
+        let si_unit = dimension.si_unit();
+        let units: Vec<_> = dimension.units().collect();
+        let description = property.get("description").cloned().unwrap_or_else(|| {
+            json!(format!(
+                "A {} in {si_unit}, or a value with a unit",
+                dimension.name()
+            ))
+        });
+        *property = json!({
+            "description": description,
+            "oneOf": [
+                {
+                    "type": "number",
+                    "description": format!("In {si_unit}"),
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "value": { "type": "number" },
+                        "unit": { "type": "string", "enum": units },
+                    },
+                    "required": ["value", "unit"],
+                    "additionalProperties": false,
+                },
+            ],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_si_units() {
+        assert_eq!(to_si(2.0, "km", Dimension::Length), Ok(2000.0));
+        assert_eq!(to_si(1.5, "h", Dimension::Time), Ok(5400.0));
+        assert_eq!(
+            to_si(1.0, "kg", Dimension::Length),
+            Err("Unknown length unit 'kg'; use one of: m, km, cm, mm, in, ft, yd, mi".to_string())
+        );
+    }
+
+    #[test]
+    fn normalizes_unit_arguments() {
+        let mut arguments = Some(json!({
+            "distance": { "value": 10, "unit": "ft" },
+            "duration": 4.0
+        }));
+        apply_to_arguments("average_speed", &mut arguments).unwrap();
+        let distance = arguments.as_ref().unwrap()["distance"].as_f64().unwrap();
+        assert!((distance - 3.048).abs() < 1e-9, "{distance}");
+        assert_eq!(arguments.unwrap()["duration"], 4.0);
+
+        let mut other_tool = Some(json!({ "distance": { "value": 1, "unit": "ft" } }));
+        apply_to_arguments("echo", &mut other_tool).unwrap();
+        assert_eq!(other_tool.unwrap()["distance"]["unit"], "ft");
+    }
+
+    #[test]
+    fn rejects_quantities_without_a_known_unit() {
+        let mut missing = Some(json!({ "distance": { "value": 1 } }));
+        assert_eq!(
+            apply_to_arguments("average_speed", &mut missing),
+            Err("Parameter 'distance' needs a number \"value\" and a string \"unit\"".to_string())
+        );
+        let mut unknown = Some(json!({ "duration": { "value": 1, "unit": "week" } }));
+        assert_eq!(
+            apply_to_arguments("average_speed", &mut unknown),
+            Err(
+                "Parameter 'duration': Unknown time unit 'week'; use one of: s, ms, min, h, d"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn advertises_both_forms() {
+        let mut tool = Tool {
+            name: "average_speed".to_string(),
+            description: String::new(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "distance": { "type": "number" }, "duration": { "type": "number" } }
+            }),
+            output_schema: None,
+        };
+        apply_to_schema(&mut tool);
+        let distance = &tool.input_schema["properties"]["distance"];
+        assert_eq!(
+            distance["description"],
+            "A length in m, or a value with a unit"
+        );
+        assert_eq!(distance["oneOf"][0]["type"], "number");
+        assert_eq!(
+            distance["oneOf"][1]["properties"]["unit"]["enum"],
+            json!(["m", "km", "cm", "mm", "in", "ft", "yd", "mi"])
+        );
+    }
+}
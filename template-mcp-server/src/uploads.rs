@@ -0,0 +1,532 @@
+//! Large tool arguments uploaded in chunks
+//!
+//! A client whose messages are too small for an argument sends it in pieces
+//! instead:
+//!
+//! 1. `begin_upload` names the target `tool`, the string `param` to fill and
+//!    any other `arguments`, and returns an `upload_id`
+//! 2. `append_chunk` adds each piece, in order, as `data`; an `index` counting
+//!    from 0 makes a repeated or skipped chunk fail instead of corrupting the
+//!    value
+//! 3. `finalize` runs the target tool with the pieces joined as `param`, as a
+//!    regular call of that tool, and returns its result
+//!
+//! An upload holds at most `LimitsConfig::max_upload_bytes` and is forgotten
+//! `LimitsConfig::upload_ttl_seconds` after its last chunk, so an abandoned
+//! upload doesn't keep its buffer. At most [`MAX_PENDING_UPLOADS`] are open at
+//! once. `finalize` is handled by the backend, which turns it into the call of
+//! the target tool.
+
+use crate::clock::Clock;
+use crate::ids::IdGenerator;
+use crate::registry::Tool;
+use crate::{ServerConfig, TemplateMcpServer};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use pulseengine_mcp_protocol::{
+    CallToolRequestParam, CallToolResult, Content, Error, Tool as ToolDefinition,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub const BEGIN_TOOL: &str = "begin_upload";
+pub const APPEND_TOOL: &str = "append_chunk";
+pub const FINALIZE_TOOL: &str = "finalize";
+
+/// Uploads open at once
+pub const MAX_PENDING_UPLOADS: usize = 32;
+
+/// Size and lifetime of an upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadLimits {
+    pub max_bytes: usize,
+    /// Time after the last chunk before the upload is forgotten
+    pub ttl: chrono::Duration,
+}
+
+impl UploadLimits {
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            max_bytes: config.limits.max_upload_bytes,
+            ttl: chrono::Duration::seconds(config.limits.upload_ttl_seconds as i64),
+        }
+    }
+}
+
+/// An upload in progress, as returned by `begin_upload` and `append_chunk`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UploadStatus {
+    pub upload_id: String,
+    pub tool: String,
+    pub param: String,
+    /// Chunks received so far, and the `index` of the next one
+    pub chunks: usize,
+    pub received_bytes: usize,
+    pub max_bytes: usize,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Why an upload call failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadError {
+    /// Unknown, finalized or expired
+    UnknownUpload(String),
+    /// The target is one of the upload tools
+    InvalidTarget(String),
+    TooLarge {
+        upload_id: String,
+        max_bytes: usize,
+    },
+    OutOfOrder {
+        upload_id: String,
+        expected: usize,
+        got: usize,
+    },
+    TooManyUploads,
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownUpload(id) => write!(f, "Unknown or expired upload: {id}"),
+            Self::InvalidTarget(tool) => write!(f, "Tool '{tool}' can't be the target of an upload"),
+            Self::TooLarge {
+                upload_id,
+                max_bytes,
+            } => write!(
+                f,
+                "Upload {upload_id} would exceed the {max_bytes} byte limit; it was discarded"
+            ),
+            Self::OutOfOrder {
+                upload_id,
+                expected,
+                got,
+            } => write!(f, "Upload {upload_id} expects chunk {expected}, got {got}"),
+            Self::TooManyUploads => write!(
+                f,
+                "Too many uploads in progress (at most {MAX_PENDING_UPLOADS}); finalize or wait for one to expire"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+struct Upload {
+    tool: String,
+    param: String,
+    arguments: Map<String, Value>,
+    data: String,
+    chunks: usize,
+    limits: UploadLimits,
+    expires_at: DateTime<Utc>,
+}
+
+/// Uploads in progress, until finalized or expired
+pub struct Uploads {
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    uploads: Mutex<HashMap<String, Upload>>,
+}
+
+impl Uploads {
+    pub fn new(clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            clock,
+            ids,
+            uploads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Upload>> {
+        let now = self.clock.now();
+        let mut uploads = self
+            .uploads
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        uploads.retain(|_, upload| upload.expires_at > now);
+        uploads
+    }
+
+    /// Start an upload of `param` for a call of `tool` with `arguments`
+    pub fn begin(
+        &self,
+        tool: &str,
+        param: &str,
+        arguments: Map<String, Value>,
+        limits: UploadLimits,
+    ) -> Result<UploadStatus, UploadError> {
+        if [BEGIN_TOOL, APPEND_TOOL, FINALIZE_TOOL].contains(&tool) {
+            return Err(UploadError::InvalidTarget(tool.to_string()));
+        }
+        let mut uploads = self.lock();
+        if uploads.len() >= MAX_PENDING_UPLOADS {
+            return Err(UploadError::TooManyUploads);
+        }
+        let upload_id = format!("upload-{:016x}", self.ids.next_id());
+        let upload = Upload {
+            tool: tool.to_string(),
+            param: param.to_string(),
+            arguments,
+            data: String::new(),
+            chunks: 0,
+            limits,
+            expires_at: self.clock.now() + limits.ttl,
+        };
+        let status = status(&upload_id, &upload);
+        uploads.insert(upload_id, upload);
+        Ok(status)
+    }
+
+    /// Add `data` to an upload, checked to be chunk `index` if given
+    ///
+    /// A chunk taking the upload over its limit discards the whole upload.
+    pub fn append(
+        &self,
+        upload_id: &str,
+        data: &str,
+        index: Option<usize>,
+    ) -> Result<UploadStatus, UploadError> {
+        let mut uploads = self.lock();
+        let upload = uploads
+            .get_mut(upload_id)
+            .ok_or_else(|| UploadError::UnknownUpload(upload_id.to_string()))?;
+        if let Some(index) = index.filter(|index| *index != upload.chunks) {
+            return Err(UploadError::OutOfOrder {
+                upload_id: upload_id.to_string(),
+                expected: upload.chunks,
+                got: index,
+            });
+        }
+        if upload.data.len() + data.len() > upload.limits.max_bytes {
+            let max_bytes = upload.limits.max_bytes;
+            uploads.remove(upload_id);
+            return Err(UploadError::TooLarge {
+                upload_id: upload_id.to_string(),
+                max_bytes,
+            });
+        }
+        upload.data.push_str(data);
+        upload.chunks += 1;
+        upload.expires_at = self.clock.now() + upload.limits.ttl;
+        Ok(status(upload_id, upload))
+    }
+
+    /// End an upload, returning the call of its target tool
+    pub fn finalize(&self, upload_id: &str) -> Result<CallToolRequestParam, UploadError> {
+        let upload = self
+            .lock()
+            .remove(upload_id)
+            .ok_or_else(|| UploadError::UnknownUpload(upload_id.to_string()))?;
+        let mut arguments = upload.arguments;
+        arguments.insert(upload.param, Value::String(upload.data));
+        Ok(CallToolRequestParam {
+            name: upload.tool,
+            arguments: Some(Value::Object(arguments)),
+        })
+    }
+}
+
+fn status(upload_id: &str, upload: &Upload) -> UploadStatus {
+    UploadStatus {
+        upload_id: upload_id.to_string(),
+        tool: upload.tool.clone(),
+        param: upload.param.clone(),
+        chunks: upload.chunks,
+        received_bytes: upload.data.len(),
+        max_bytes: upload.limits.max_bytes,
+        expires_at: upload.expires_at,
+    }
+}
+
+/// The call of the target tool a `finalize` call with `arguments` stands for
+pub fn finalize_call(
+    uploads: &Uploads,
+    arguments: Option<&Value>,
+) -> Result<CallToolRequestParam, Error> {
+    let upload_id = string_argument(arguments, "upload_id")?;
+    uploads
+        .finalize(upload_id)
+        .map_err(|e| Error::invalid_params(e.to_string()))
+}
+
+fn string_argument<'a>(arguments: Option<&'a Value>, name: &str) -> Result<&'a str, Error> {
+    arguments
+        .and_then(|args| args.get(name))
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::invalid_params(format!("Missing required parameter: {name}")))
+}
+
+/// The upload tools, registered by the backend for every server
+pub fn tools(server: TemplateMcpServer) -> Vec<Box<dyn Tool>> {
+    [
+        UploadToolKind::Begin,
+        UploadToolKind::Append,
+        UploadToolKind::Finalize,
+    ]
+    .into_iter()
+    .map(|kind| {
+        Box::new(UploadTool {
+            server: server.clone(),
+            kind,
+        }) as Box<dyn Tool>
+    })
+    .collect()
+}
+
+#[derive(Clone, Copy)]
+enum UploadToolKind {
+    Begin,
+    Append,
+    Finalize,
+}
+
+struct UploadTool {
+    server: TemplateMcpServer,
+    kind: UploadToolKind,
+}
+
+#[async_trait]
+impl Tool for UploadTool {
+    fn definition(&self) -> ToolDefinition {
+        let (name, description, input_schema) = match self.kind {
+            UploadToolKind::Begin => (
+                BEGIN_TOOL,
+                "Start uploading a large string argument of a tool in chunks",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "tool": { "type": "string" },
+                        "param": { "type": "string" },
+                        "arguments": { "type": "object" }
+                    },
+                    "required": ["tool", "param"]
+                }),
+            ),
+            UploadToolKind::Append => (
+                APPEND_TOOL,
+                "Append the next chunk to an upload",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "upload_id": { "type": "string" },
+                        "data": { "type": "string" },
+                        "index": { "type": "integer", "minimum": 0 }
+                    },
+                    "required": ["upload_id", "data"]
+                }),
+            ),
+            UploadToolKind::Finalize => (
+                FINALIZE_TOOL,
+                "Run the target tool of an upload with the uploaded argument",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "upload_id": { "type": "string" }
+                    },
+                    "required": ["upload_id"]
+                }),
+            ),
+        };
+        ToolDefinition {
+            name: name.to_string(),
+            description: description.to_string(),
+            input_schema,
+            output_schema: None,
+        }
+    }
+
+    async fn invoke(&self, arguments: Option<Value>) -> Result<CallToolResult, Error> {
+        let arguments = arguments.as_ref();
+        let uploads = self.server.uploads();
+        let status = match self.kind {
+            UploadToolKind::Begin => {
+                let tool = string_argument(arguments, "tool")?;
+                let param = string_argument(arguments, "param")?;
+                let target_arguments = match arguments.and_then(|args| args.get("arguments")) {
+                    None | Some(Value::Null) => Map::new(),
+                    Some(Value::Object(target_arguments)) => target_arguments.clone(),
+                    Some(_) => return Err(Error::invalid_params("arguments must be an object")),
+                };
+                let limits = UploadLimits::from_config(&self.server.config());
+                uploads.begin(tool, param, target_arguments, limits)
+            }
+            UploadToolKind::Append => {
+                let upload_id = string_argument(arguments, "upload_id")?;
+                let data = string_argument(arguments, "data")?;
+                let index = match arguments.and_then(|args| args.get("index")) {
+                    None | Some(Value::Null) => None,
+                    Some(index) => Some(index.as_u64().ok_or_else(|| {
+                        Error::invalid_params("index must be a non-negative integer")
+                    })? as usize),
+                };
+                uploads.append(upload_id, data, index)
+            }
+            UploadToolKind::Finalize => {
+                return Err(Error::invalid_request(
+                    "finalize runs through TemplateBackend, which calls the target tool",
+                ))
+            }
+        }
+        .map_err(|e| Error::invalid_params(e.to_string()))?;
+
+        let status = serde_json::to_value(status)
+            .map_err(|e| Error::internal_error(format!("Failed to serialize status: {e}")))?;
+        Ok(CallToolResult {
+            content: vec![Content::text(status.to_string())],
+            is_error: Some(false),
+            structured_content: Some(status),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::TemplateBackend;
+    use crate::clock::ManualClock;
+    use crate::ids::SequentialIds;
+    use pulseengine_mcp_server::McpBackend;
+
+    const LIMITS: UploadLimits = UploadLimits {
+        max_bytes: 8,
+        ttl: chrono::Duration::seconds(60),
+    };
+
+    fn uploads() -> (Arc<ManualClock>, Uploads) {
+        let clock = Arc::new(ManualClock::new("2026-01-01T00:00:00Z".parse().unwrap()));
+        let uploads = Uploads::new(clock.clone(), Arc::new(SequentialIds::starting_at(1)));
+        (clock, uploads)
+    }
+
+    fn arguments(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn joins_chunks_into_the_target_call() {
+        let (_, uploads) = uploads();
+        let begun = uploads
+            .begin(
+                "echo",
+                "message",
+                arguments(json!({ "prefix": "P" })),
+                LIMITS,
+            )
+            .unwrap();
+        assert_eq!(begun.upload_id, "upload-0000000000000001");
+        uploads.append(&begun.upload_id, "abc", Some(0)).unwrap();
+        let status = uploads.append(&begun.upload_id, "de", None).unwrap();
+        assert_eq!((status.chunks, status.received_bytes), (2, 5));
+
+        let call = uploads.finalize(&begun.upload_id).unwrap();
+        assert_eq!(call.name, "echo");
+        assert_eq!(
+            call.arguments,
+            Some(json!({ "prefix": "P", "message": "abcde" }))
+        );
+        assert_eq!(
+            uploads.finalize(&begun.upload_id).unwrap_err(),
+            UploadError::UnknownUpload(begun.upload_id)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_chunks() {
+        let (_, uploads) = uploads();
+        let id = uploads
+            .begin("echo", "message", Map::new(), LIMITS)
+            .unwrap()
+            .upload_id;
+        assert_eq!(
+            uploads.append(&id, "a", Some(1)).unwrap_err(),
+            UploadError::OutOfOrder {
+                upload_id: id.clone(),
+                expected: 0,
+                got: 1,
+            }
+        );
+        assert_eq!(
+            uploads.append(&id, "too many bytes", None).unwrap_err(),
+            UploadError::TooLarge {
+                upload_id: id.clone(),
+                max_bytes: 8,
+            }
+        );
+        // Going over the limit discards the upload
+        assert_eq!(
+            uploads.append(&id, "a", None).unwrap_err(),
+            UploadError::UnknownUpload(id)
+        );
+        assert_eq!(
+            uploads
+                .begin(FINALIZE_TOOL, "upload_id", Map::new(), LIMITS)
+                .unwrap_err(),
+            UploadError::InvalidTarget(FINALIZE_TOOL.to_string())
+        );
+    }
+
+    #[test]
+    fn forgets_idle_uploads_and_caps_open_ones() {
+        let (clock, uploads) = uploads();
+        let id = uploads
+            .begin("echo", "message", Map::new(), LIMITS)
+            .unwrap()
+            .upload_id;
+        clock.advance(chrono::Duration::seconds(50));
+        // Each chunk restarts the TTL
+        uploads.append(&id, "a", None).unwrap();
+        clock.advance(chrono::Duration::seconds(50));
+        uploads.append(&id, "b", None).unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        assert!(uploads.finalize(&id).is_err());
+
+        for _ in 0..MAX_PENDING_UPLOADS {
+            uploads
+                .begin("echo", "message", Map::new(), LIMITS)
+                .unwrap();
+        }
+        assert_eq!(
+            uploads
+                .begin("echo", "message", Map::new(), LIMITS)
+                .unwrap_err(),
+            UploadError::TooManyUploads
+        );
+    }
+
+    #[tokio::test]
+    async fn uploads_through_the_backend() {
+        let backend = TemplateBackend::new(TemplateMcpServer::default());
+        let call = |name: &str, arguments: Value| CallToolRequestParam {
+            name: name.to_string(),
+            arguments: Some(arguments),
+        };
+        let begun = backend
+            .call_tool(call(
+                BEGIN_TOOL,
+                json!({ "tool": "echo", "param": "message" }),
+            ))
+            .await
+            .unwrap();
+        let upload_id = begun.structured_content.unwrap()["upload_id"].clone();
+        for (index, data) in ["hel", "lo"].into_iter().enumerate() {
+            backend
+                .call_tool(call(
+                    APPEND_TOOL,
+                    json!({ "upload_id": upload_id, "data": data, "index": index }),
+                ))
+                .await
+                .unwrap();
+        }
+        let result = backend
+            .call_tool(call(FINALIZE_TOOL, json!({ "upload_id": upload_id })))
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&result.content).unwrap(),
+            json!([{ "type": "text", "text": "\"hello\"" }])
+        );
+    }
+}
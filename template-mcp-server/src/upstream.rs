@@ -0,0 +1,82 @@
+//! A client for an upstream API, shared by every tool call
+//!
+//! HTTP clients such as `reqwest::Client` keep a pool of connections, which
+//! only pays off if tools share one client instead of building their own per
+//! call. Construct the client once at startup as shared state (see
+//! [`crate::providers`]), and have tools take it from the server:
+//!
+//! ```ignore
+//! let client = self.state::<UpstreamClient>();
+//! ```
+//!
+//! `main` provides an [`UpstreamClient`] this way, and `create_data` sends
+//! its request for the `create_data` upstream call through it. The crate has
+//! no HTTP client (see [`crate::http_proxy`]), so this one is a stand-in that
+//! only counts the requests sent through it; replace it with your own, e.g. a
+//! struct holding a `reqwest::Client`. Its [`UpstreamStats`] are part of the
+//! server status, so one instance id with a growing request count shows the
+//! calls share the client.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Client for the upstream API the example tools would call
+#[derive(Debug)]
+pub struct UpstreamClient {
+    instance_id: String,
+    requests: AtomicU64,
+}
+
+/// Which client instance is shared, and how many requests it sent
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct UpstreamStats {
+    pub instance_id: String,
+    pub requests: u64,
+}
+
+impl Default for UpstreamClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpstreamClient {
+    /// A client with its own connection pool, and an instance id telling it
+    /// apart from any other
+    pub fn new() -> Self {
+        Self {
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            requests: AtomicU64::new(0),
+        }
+    }
+
+    /// Send the request for `operation` upstream
+    pub async fn send(&self, operation: &str) -> anyhow::Result<()> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        tracing::debug!(operation, instance_id = %self.instance_id, "Upstream request sent");
+        Ok(())
+    }
+
+    pub fn stats(&self) -> UpstreamStats {
+        UpstreamStats {
+            instance_id: self.instance_id.clone(),
+            requests: self.requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn counts_the_requests_sent_through_one_instance() {
+        let client = UpstreamClient::new();
+        client.send("create_data").await.unwrap();
+        client.send("create_data").await.unwrap();
+        let stats = client.stats();
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.instance_id, client.stats().instance_id);
+        assert_ne!(stats.instance_id, UpstreamClient::new().stats().instance_id);
+    }
+}
@@ -0,0 +1,269 @@
+//! Validating tool arguments without running the tool
+//!
+//! `tools/validate` takes the same params as `tools/call` and runs only the
+//! argument handling a call would: filling config defaults, the non-finite
+//! number check and validation against the tool's input schema. It returns
+//! whether the arguments are valid, every problem found, and the arguments as
+//! the tool would receive them, with the timeout the call would get. The tool
+//! body never runs. A call `tools/call` would refuse for access, such as an
+//! admin tool from another client or a tool safe mode turns off, fails
+//! validation with the same error.
+//!
+//! Schema validation covers the keywords the tool schemas use: `type`,
+//! `required`, `properties`, `additionalProperties: false`, `items` and
+//! `enum`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// Custom JSON-RPC method for validate-only calls
+pub const VALIDATE_METHOD: &str = "tools/validate";
+
+/// One problem with the arguments
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Location of the problem, e.g. `a` or `items[2]`
+    pub path: String,
+    pub message: String,
+}
+
+/// Result of `tools/validate`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ValidationResult {
+    pub valid: bool,
+    pub errors: Vec<ValidationError>,
+    /// Arguments after defaults were filled in
+    pub arguments: Value,
+    /// Milliseconds the call would have to finish; see [`crate::timeouts`]
+    pub timeout_ms: u64,
+}
+
+impl ValidationResult {
+    pub fn new(arguments: Value, errors: Vec<ValidationError>, timeout: Duration) -> Self {
+        Self {
+            valid: errors.is_empty(),
+            errors,
+            arguments,
+            timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// Check `arguments` against a tool's input schema
+pub fn validate_arguments(schema: &Value, arguments: &Value) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    validate("", schema, arguments, &mut errors);
+    errors
+}
+
+fn validate(path: &str, schema: &Value, value: &Value, errors: &mut Vec<ValidationError>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        if !matches_type(expected, value) {
+            errors.push(ValidationError {
+                path: display_path(path),
+                message: format!("expected {}, got {}", describe_type(expected), kind(value)),
+            });
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(ValidationError {
+                path: display_path(path),
+                message: format!("{value} is not one of {}", Value::Array(allowed.clone())),
+            });
+        }
+    }
+
+    match value {
+        Value::Object(fields) => validate_object(path, schema, fields, errors),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(&format!("{path}[{index}]"), item_schema, item, errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn validate_object(
+    path: &str,
+    schema: &Map<String, Value>,
+    fields: &Map<String, Value>,
+    errors: &mut Vec<ValidationError>,
+) {
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    for name in required {
+        if fields.get(name).is_none_or(Value::is_null) {
+            errors.push(ValidationError {
+                path: child_path(path, name),
+                message: "missing required parameter".to_string(),
+            });
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    // Unknown parameters are ignored by calls unless the schema forbids them.
+    // An explicit null for an optional parameter means "not provided".
+    let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+    for (name, value) in fields {
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(property) if !value.is_null() => {
+                validate(&child_path(path, name), property, value, errors)
+            }
+            None if closed => errors.push(ValidationError {
+                path: child_path(path, name),
+                message: "unexpected parameter".to_string(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn matches_type(expected: &Value, value: &Value) -> bool {
+    match expected {
+        Value::String(name) => matches_type_name(name, value),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|name| matches_type_name(name, value)),
+        _ => true,
+    }
+}
+
+fn matches_type_name(name: &str, value: &Value) -> bool {
+    match name {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        Value::String(name) => name.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn child_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        "arguments".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "number" },
+                "mode": { "type": "string", "enum": ["fast", "slow"] },
+                "items": { "type": "array", "items": { "type": "integer" } },
+                "nested": {
+                    "type": "object",
+                    "properties": { "flag": { "type": "boolean" } },
+                    "additionalProperties": false
+                }
+            },
+            "required": ["a"]
+        })
+    }
+
+    fn error(path: &str, message: &str) -> ValidationError {
+        ValidationError {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_arguments() {
+        let arguments =
+            json!({ "a": 1.5, "mode": "fast", "items": [1, 2], "extra": true, "nested": null });
+        assert!(validate_arguments(&schema(), &arguments).is_empty());
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_path() {
+        let arguments = json!({
+            "mode": "medium",
+            "items": [1, 2.5],
+            "nested": { "flag": "yes", "other": 1 }
+        });
+        assert_eq!(
+            validate_arguments(&schema(), &arguments),
+            [
+                error("a", "missing required parameter"),
+                error("items[1]", "expected integer, got number"),
+                error("mode", r#""medium" is not one of ["fast","slow"]"#),
+                error("nested.flag", "expected boolean, got string"),
+                error("nested.other", "unexpected parameter"),
+            ]
+        );
+        assert_eq!(
+            validate_arguments(&schema(), &json!([])),
+            [error("arguments", "expected object, got array")]
+        );
+    }
+
+    #[test]
+    fn results_report_the_timeout() {
+        let result = ValidationResult::new(
+            json!({}),
+            vec![error("a", "missing required parameter")],
+            Duration::from_secs(30),
+        );
+        assert!(!result.valid);
+        assert_eq!(result.timeout_ms, 30_000);
+    }
+}
@@ -0,0 +1,143 @@
+//! Partial results with warnings
+//!
+//! A tool that can finish but with caveats (say, some inputs were skipped)
+//! returns [`WithWarnings`] instead of failing the whole call. Its text content
+//! holds the value and the warnings as JSON in a fixed shape,
+//! `{"type": "with_warnings", "text": "...", "warnings": [...]}`, which the
+//! backend unpacks: the result's first content is the value, rendered as it
+//! would be without the wrapper, followed by one text content per warning,
+//! each starting with [`WARNING_PREFIX`]. The call is not an error.
+//!
+//! The 0.11 `CallToolResult` has no `_meta`, so warnings are only surfaced as
+//! content.
+
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde::{Deserialize, Serialize};
+
+/// Start of the text content carrying each warning
+pub const WARNING_PREFIX: &str = "Warning: ";
+
+// Rendered wrappers always start with their tag, which keeps detection cheap
+const WITH_WARNINGS_PREFIX: &str = r#"{"type":"with_warnings""#;
+
+/// A tool result plus warnings about it
+#[derive(Clone, PartialEq)]
+pub struct WithWarnings<T> {
+    pub value: T,
+    pub warnings: Vec<String>,
+}
+
+impl<T> WithWarnings<T> {
+    /// `value` without warnings so far
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn warn(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into());
+    }
+
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warn(warning);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename = "with_warnings")]
+struct Rendered {
+    text: String,
+    warnings: Vec<String>,
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for WithWarnings<T> {
+    /// Tool results are rendered with `Debug`, so this writes the shape
+    /// [`unpack`] recognizes, keeping the value's own rendering as `text`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rendered = Rendered {
+            text: format!("{:?}", self.value),
+            warnings: self.warnings.clone(),
+        };
+        match serde_json::to_string(&rendered) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+/// Split a rendered [`WithWarnings`] into the value and warning contents
+pub fn unpack(result: &mut CallToolResult) {
+    let Some((index, rendered)) = result
+        .content
+        .iter()
+        .enumerate()
+        .find_map(|(index, content)| match content {
+            Content::Text { text } if text.starts_with(WITH_WARNINGS_PREFIX) => {
+                serde_json::from_str::<Rendered>(text)
+                    .ok()
+                    .map(|rendered| (index, rendered))
+            }
+            _ => None,
+        })
+    else {
+        return;
+    };
+
+    let warnings = rendered
+        .warnings
+        .into_iter()
+        .map(|warning| Content::text(format!("{WARNING_PREFIX}{warning}")));
+    result.content.splice(
+        index..=index,
+        std::iter::once(Content::text(rendered.text)).chain(warnings),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(result: &CallToolResult) -> Vec<&str> {
+        result
+            .content
+            .iter()
+            .filter_map(|content| match content {
+                Content::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_the_value_from_its_warnings() {
+        let value = WithWarnings::new(3)
+            .with_warning("2 items skipped")
+            .with_warning("input truncated");
+        let mut result = CallToolResult::text(format!("{value:?}"));
+        unpack(&mut result);
+        assert_eq!(
+            texts(&result),
+            ["3", "Warning: 2 items skipped", "Warning: input truncated"]
+        );
+        assert_ne!(result.is_error, Some(true));
+    }
+
+    #[test]
+    fn keeps_the_rendering_of_the_value() {
+        let mut value = WithWarnings::new("text");
+        value.warn("careful");
+        let mut result = CallToolResult::text(format!("{value:?}"));
+        unpack(&mut result);
+        assert_eq!(texts(&result), ["\"text\"", "Warning: careful"]);
+    }
+
+    #[test]
+    fn leaves_other_results_alone() {
+        let mut result = CallToolResult::text("plain");
+        unpack(&mut result);
+        assert_eq!(texts(&result), ["plain"]);
+    }
+}
@@ -0,0 +1,157 @@
+//! The `whoami` tool, reflecting who the server thinks the client is
+//!
+//! Permission problems are easier to diagnose once the caller can see the
+//! identity its calls run under. With `AccessConfig::whoami_tool` set, the
+//! server serves [`WHOAMI_TOOL`], which returns the client's identity, how it
+//! was established and the scopes it holds:
+//!
+//! ```json
+//! { "identity": "ops-console", "authenticated": true, "method": "client_id", "scopes": ["admin"] }
+//! ```
+//!
+//! The 0.11 framework doesn't pass the authenticated identity to backends, so
+//! the identity is the connection's client id, as for quotas (see
+//! [`crate::quota`]): `"anonymous"` with method `"none"` unless one is set.
+//! The only scope is `admin`, held by the clients in
+//! `AccessConfig::admin_clients`. The result never carries a credential; an
+//! authentication method that adds one must keep it out of [`WhoAmI`].
+//!
+//! The tool is registered when the backend is built, so the setting is read
+//! at startup.
+
+use crate::quota::ANONYMOUS_CLIENT;
+use crate::registry::Tool;
+use crate::request_context::RequestContext;
+use crate::TemplateMcpServer;
+use async_trait::async_trait;
+use pulseengine_mcp_protocol::{CallToolResult, Content, Error, Tool as ToolDefinition};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const WHOAMI_TOOL: &str = "whoami";
+
+/// Scope held by admin clients
+pub const ADMIN_SCOPE: &str = "admin";
+
+/// Who the server thinks the client is
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct WhoAmI {
+    /// The client's id, or `anonymous`
+    pub identity: String,
+    pub authenticated: bool,
+    /// How the identity was established: `client_id` or `none`
+    pub method: String,
+    pub scopes: Vec<String>,
+}
+
+impl WhoAmI {
+    /// The identity of `client`, with the scopes `server` grants it
+    pub fn of(client: &str, server: &TemplateMcpServer) -> Self {
+        let authenticated = client != ANONYMOUS_CLIENT;
+        let admin = authenticated
+            && server
+                .config()
+                .access
+                .admin_clients
+                .iter()
+                .any(|c| c == client);
+        Self {
+            identity: client.to_string(),
+            authenticated,
+            method: if authenticated { "client_id" } else { "none" }.to_string(),
+            scopes: admin.then(|| ADMIN_SCOPE.to_string()).into_iter().collect(),
+        }
+    }
+}
+
+/// The `whoami` tool of `server`
+pub fn tool(server: TemplateMcpServer) -> Box<dyn Tool> {
+    Box::new(WhoAmITool { server })
+}
+
+struct WhoAmITool {
+    server: TemplateMcpServer,
+}
+
+#[async_trait]
+impl Tool for WhoAmITool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: WHOAMI_TOOL.to_string(),
+            description: "Show the identity, authentication method and scopes the server \
+                          sees for this client"
+                .to_string(),
+            input_schema: json!({ "type": "object", "properties": {} }),
+            output_schema: Some(crate::structured::output_schema::<WhoAmI>()),
+        }
+    }
+
+    async fn invoke(&self, _arguments: Option<Value>) -> Result<CallToolResult, Error> {
+        let context = RequestContext::current().unwrap_or_default();
+        let whoami = WhoAmI::of(context.client().unwrap_or(ANONYMOUS_CLIENT), &self.server);
+        let scopes = if whoami.scopes.is_empty() {
+            "no scopes".to_string()
+        } else {
+            format!("scopes {}", whoami.scopes.join(", "))
+        };
+        let text = format!(
+            "{} (authenticated: {}, method: {}, {scopes})",
+            whoami.identity, whoami.authenticated, whoami.method
+        );
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            structured_content: serde_json::to_value(&whoami).ok(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerConfig;
+
+    fn server(admins: &[&str]) -> TemplateMcpServer {
+        let mut config = ServerConfig::default();
+        config.access.admin_clients = admins.iter().map(ToString::to_string).collect();
+        TemplateMcpServer::with_config(config)
+    }
+
+    #[test]
+    fn admin_clients_hold_the_admin_scope() {
+        let server = server(&["ops"]);
+        assert_eq!(
+            WhoAmI::of("ops", &server),
+            WhoAmI {
+                identity: "ops".to_string(),
+                authenticated: true,
+                method: "client_id".to_string(),
+                scopes: vec![ADMIN_SCOPE.to_string()],
+            }
+        );
+        assert!(WhoAmI::of("dev", &server).scopes.is_empty());
+    }
+
+    #[test]
+    fn anonymous_clients_are_never_admins() {
+        let whoami = WhoAmI::of(ANONYMOUS_CLIENT, &server(&[ANONYMOUS_CLIENT]));
+        assert!(!whoami.authenticated);
+        assert_eq!(whoami.method, "none");
+        assert!(whoami.scopes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_tool_describes_the_caller() {
+        let result = tool(server(&[])).invoke(None).await.unwrap();
+        assert_eq!(
+            result.structured_content,
+            Some(json!({
+                "identity": ANONYMOUS_CLIENT,
+                "authenticated": false,
+                "method": "none",
+                "scopes": []
+            }))
+        );
+    }
+}
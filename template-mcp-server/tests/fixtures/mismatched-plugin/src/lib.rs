@@ -0,0 +1,10 @@
+//! A plugin built for a plugin ABI the server doesn't speak
+//!
+//! It exports the version symbol of `declare_plugin!` by hand, and nothing
+//! else, so a loader that ignored the version would fail on the missing
+//! tools instead of refusing the library.
+
+#[no_mangle]
+pub extern "C" fn template_mcp_plugin_abi_version() -> u32 {
+    u32::MAX
+}
@@ -0,0 +1,43 @@
+//! A plugin with one tool, `fixture_echo`, that returns its `text` argument
+
+use async_trait::async_trait;
+use pulseengine_mcp_protocol::{CallToolResult, Content, Error, Tool as ToolDefinition};
+use serde_json::{json, Value};
+use template_mcp_server::registry::Tool;
+
+struct EchoTool;
+
+#[async_trait]
+impl Tool for EchoTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fixture_echo".to_string(),
+            description: "Return the given text".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"]
+            }),
+            output_schema: None,
+        }
+    }
+
+    async fn invoke(&self, arguments: Option<Value>) -> Result<CallToolResult, Error> {
+        let text = arguments
+            .as_ref()
+            .and_then(|args| args.get("text"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::invalid_params("Missing required parameter: text"))?;
+        Ok(CallToolResult {
+            content: vec![Content::text(format!("plugin: {text}"))],
+            is_error: Some(false),
+            structured_content: None,
+        })
+    }
+}
+
+fn tools() -> Vec<Box<dyn Tool>> {
+    vec![Box::new(EchoTool)]
+}
+
+template_mcp_server::declare_plugin!(tools);
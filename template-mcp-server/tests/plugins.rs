@@ -0,0 +1,127 @@
+//! Loading the plugin libraries in `tests/fixtures`
+//!
+//! The fixtures are workspace members built by cargo on first use, so they
+//! come from the same compiler as the server, as plugins have to.
+
+#![cfg(unix)]
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use template_mcp_server::plugins;
+
+const PLUGIN: &str = "template-mcp-plugin-fixture";
+const MISMATCHED_PLUGIN: &str = "template-mcp-mismatched-plugin-fixture";
+
+/// The library of the fixture `package`, built if needed
+fn fixture(package: &str) -> &'static Path {
+    static LIBRARIES: OnceLock<Vec<(String, PathBuf)>> = OnceLock::new();
+    let libraries = LIBRARIES.get_or_init(|| {
+        let mut cargo = Command::new(env!("CARGO"));
+        cargo
+            .args(["build", "--message-format=json", "--package", PLUGIN])
+            .args(["--package", MISMATCHED_PLUGIN])
+            .current_dir(env!("CARGO_MANIFEST_DIR"));
+        // Build scripts such as ring's rerun when these change, so passing on
+        // the ones cargo sets for this test would rebuild the server on every
+        // run, here and in the next `cargo test`
+        for (name, _) in std::env::vars_os() {
+            let name = name.to_string_lossy();
+            if name.starts_with("CARGO_") && !matches!(&*name, "CARGO_HOME" | "CARGO_TARGET_DIR")
+                || name == "OUT_DIR"
+            {
+                cargo.env_remove(&*name);
+            }
+        }
+        let output = cargo.output().expect("cargo runs");
+        assert!(
+            output.status.success(),
+            "the fixtures build: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter(|message| message["reason"] == "compiler-artifact")
+            .filter(|message| message["target"]["kind"] == json!(["cdylib"]))
+            .filter_map(|message| {
+                let name = message["package_id"].as_str()?;
+                let file = message["filenames"].as_array()?.first()?.as_str()?;
+                Some((name.to_string(), PathBuf::from(file)))
+            })
+            .collect()
+    });
+    libraries
+        .iter()
+        .find(|(id, path)| id.contains(package) && path.exists())
+        .map(|(_, path)| path.as_path())
+        .unwrap_or_else(|| panic!("{package} was built"))
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime starts")
+}
+
+#[test]
+fn loads_and_calls_plugin_tools() {
+    // SAFETY: the fixture is built from this workspace by the same cargo
+    let plugin = unsafe { plugins::load_plugin(fixture(PLUGIN)) }.expect("the plugin loads");
+
+    assert_eq!(plugin.tools.len(), 1);
+    let tool = &plugin.tools[0];
+    assert_eq!(tool.definition().name, "fixture_echo");
+
+    let result = runtime()
+        .block_on(tool.invoke(Some(json!({ "text": "hello" }))))
+        .expect("the tool succeeds");
+    assert_eq!(
+        serde_json::to_value(&result.content).unwrap(),
+        json!([{ "type": "text", "text": "plugin: hello" }])
+    );
+
+    let error = runtime()
+        .block_on(tool.invoke(None))
+        .expect_err("text is required");
+    assert!(error.message.contains("text"), "{}", error.message);
+}
+
+#[test]
+fn refuses_plugins_of_another_abi_version() {
+    // SAFETY: as above; the fixture only exports a version function
+    let error = unsafe { plugins::load_plugin(fixture(MISMATCHED_PLUGIN)) }
+        .err()
+        .expect("the plugin is refused");
+
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "plugin ABI version {} is incompatible with the server's {}",
+            u32::MAX,
+            plugins::PLUGIN_ABI_VERSION
+        )
+    );
+}
+
+#[test]
+fn loads_a_directory_skipping_refused_plugins() {
+    let dir = std::env::temp_dir().join(format!("template-mcp-plugins-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for package in [PLUGIN, MISMATCHED_PLUGIN] {
+        let library = fixture(package);
+        std::fs::copy(library, dir.join(library.file_name().unwrap())).unwrap();
+    }
+    std::fs::write(dir.join("notes.txt"), "not a library").unwrap();
+
+    // SAFETY: as above
+    let loaded = unsafe { plugins::load_dir(&dir) }.expect("the directory is readable");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<String> = loaded
+        .iter()
+        .flat_map(|plugin| plugin.tools.iter().map(|tool| tool.definition().name))
+        .collect();
+    assert_eq!(names, ["fixture_echo"]);
+}
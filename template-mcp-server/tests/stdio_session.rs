@@ -0,0 +1,254 @@
+//! A whole STDIO session, from the client's first line to end of input
+//!
+//! The session runs through [`RawMessageHandler::serve`] over in-memory
+//! pipes, the same path as `serve_stdio`, so each line is parsed, dispatched
+//! and answered as a client of the binary would see it.
+
+use serde_json::{json, Value};
+use template_mcp_server::raw_messages::RawMessageHandler;
+use template_mcp_server::{ServerConfig, TemplateBackend, TemplateMcpServer};
+use tokio::io::AsyncReadExt;
+
+/// The responses to `messages`, sent one per line
+///
+/// Only a lone `ping` may be answered ahead of the lines before it, so
+/// without one the responses come in the order of the requests.
+async fn session(messages: &[Value]) -> Vec<Value> {
+    session_of(TemplateBackend::new(TemplateMcpServer::default()), messages).await
+}
+
+/// The responses to `messages` in a session of its own on `backend`
+async fn session_of(backend: TemplateBackend, messages: &[Value]) -> Vec<Value> {
+    let handler = RawMessageHandler::new(backend)
+        .await
+        .expect("handler starts");
+    let mut input = Vec::new();
+    for message in messages {
+        input.extend(message.to_string().into_bytes());
+        input.push(b'\n');
+    }
+    let (output, mut read) = tokio::io::duplex(1 << 20);
+    handler
+        .serve(&input[..], output)
+        .await
+        .expect("the session runs to the end of input");
+
+    let mut text = String::new();
+    read.read_to_string(&mut text).await.unwrap();
+    text.lines()
+        .map(|line| serde_json::from_str(line).expect("each line is JSON"))
+        .collect()
+}
+
+fn initialize() -> [Value; 2] {
+    [
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "stdio-session", "version": "1.0" }
+            }
+        }),
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    ]
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+#[tokio::test]
+async fn lists_and_calls_tools() {
+    let [initialize, initialized] = initialize();
+    let responses = session(&[
+        initialize,
+        initialized,
+        request(2, "tools/list", json!({})),
+        request(
+            3,
+            "tools/call",
+            json!({ "name": "add_numbers", "arguments": { "a": 2, "b": 3 } }),
+        ),
+        request(
+            4,
+            "tools/call",
+            json!({ "name": "add_numbers", "arguments": { "a": 2 } }),
+        ),
+        request(5, "tools/call", json!({ "name": "no_such_tool" })),
+    ])
+    .await;
+    assert_eq!(responses.len(), 5);
+
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+    let tools: Vec<&str> = responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .collect();
+    assert!(tools.contains(&"add_numbers"), "{tools:?}");
+    assert!(tools.contains(&"echo"), "{tools:?}");
+
+    assert_eq!(
+        responses[2]["result"]["content"],
+        json!([{ "type": "text", "text": "5.0" }])
+    );
+    assert_eq!(
+        responses[3]["error"]["message"],
+        "Missing required parameter 'b'"
+    );
+    assert_eq!(
+        responses[4]["error"]["message"],
+        "Unknown tool: no_such_tool"
+    );
+}
+
+#[tokio::test]
+async fn reads_resources_and_answers_batches() {
+    let [initialize, initialized] = initialize();
+    let responses = session(&[
+        initialize,
+        initialized,
+        request(
+            2,
+            "resources/read",
+            json!({ "uri": "template://server-status" }),
+        ),
+        json!([
+            request(3, "ping", json!({})),
+            request(
+                4,
+                "tools/call",
+                json!({ "name": "add_numbers", "arguments": { "a": 1, "b": 1 } }),
+            ),
+        ]),
+    ])
+    .await;
+
+    let contents = &responses[1]["result"]["contents"][0];
+    assert_eq!(contents["uri"], "template://server-status");
+    let status: Value = serde_json::from_str(contents["text"].as_str().unwrap()).unwrap();
+    assert!(status.is_object(), "{status}");
+
+    // The batch is answered on one line, as an array
+    let batch = responses
+        .last()
+        .unwrap()
+        .as_array()
+        .expect("a batch response");
+    let ids: Vec<&Value> = batch.iter().map(|response| &response["id"]).collect();
+    assert_eq!(ids, [&json!(3), &json!(4)]);
+    assert_eq!(
+        batch[1]["result"]["content"],
+        json!([{ "type": "text", "text": "2.0" }])
+    );
+}
+
+#[tokio::test]
+async fn refuses_requests_before_initialize() {
+    let responses = session(&[request(1, "tools/list", json!({}))]).await;
+    assert_eq!(
+        responses[0]["error"]["message"],
+        "Server not initialized; send initialize before tools/list"
+    );
+}
+
+#[tokio::test]
+async fn call_order_holds_within_a_session() {
+    let mut config = ServerConfig::default();
+    config.access.admin_clients = vec!["ops".to_string()];
+    let server = TemplateMcpServer::with_config(config);
+    let backend = || TemplateBackend::new(server.clone()).with_client_id("ops");
+    let [initialize, initialized] = initialize();
+    let export = request(2, "tools/call", json!({ "name": "export_snapshot" }));
+    let import = request(3, "tools/call", json!({ "name": "import_snapshot" }));
+
+    let exported = session_of(
+        backend(),
+        &[initialize.clone(), initialized.clone(), export],
+    )
+    .await;
+    assert!(exported[1]["result"].is_object(), "{}", exported[1]);
+
+    // The export of the first session doesn't count for the second
+    let refused = session_of(backend(), &[initialize, initialized, import]).await;
+    assert_eq!(
+        refused[1]["error"]["message"],
+        "Tool 'import_snapshot' needs a successful call of 'export_snapshot' earlier in the session"
+    );
+    assert_eq!(refused[1]["error"]["data"]["requires"], "export_snapshot");
+}
+
+/// The names of the tools a session of a client advertising `capabilities`
+/// lists
+async fn listed_tools(server: &TemplateMcpServer, capabilities: Value) -> Vec<String> {
+    let [mut initialize, initialized] = initialize();
+    initialize["params"]["capabilities"] = capabilities;
+    let responses = session_of(
+        TemplateBackend::new(server.clone()),
+        &[initialize, initialized, request(2, "tools/list", json!({}))],
+    )
+    .await;
+    responses[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn each_session_lists_the_tools_of_its_client_capabilities() {
+    let server = TemplateMcpServer::default();
+    let (with_roots, without) = tokio::join!(
+        listed_tools(&server, json!({ "roots": {} })),
+        listed_tools(&server, json!({})),
+    );
+    assert!(
+        with_roots.contains(&"list_roots".to_string()),
+        "{with_roots:?}"
+    );
+    assert!(!without.contains(&"list_roots".to_string()), "{without:?}");
+
+    // The client without roots didn't change what the first one's sessions list
+    assert_eq!(
+        listed_tools(&server, json!({ "roots": {} })).await,
+        with_roots
+    );
+}
+
+#[tokio::test]
+async fn responses_carry_the_request_id() {
+    let [initialize, initialized] = initialize();
+    let ping = |id: Value| json!({ "jsonrpc": "2.0", "id": id, "method": "ping" });
+    let responses = session(&[
+        initialize,
+        initialized,
+        ping(json!("2")),
+        ping(json!(2)),
+        json!({ "jsonrpc": "2.0", "method": "notifications/cancelled", "params": { "requestId": 9 } }),
+        ping(Value::Null),
+    ])
+    .await;
+
+    // The notification gets no response
+    assert_eq!(responses.len(), 4);
+    assert_eq!(
+        responses[1..],
+        [
+            json!({ "jsonrpc": "2.0", "id": "2", "result": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "result": {} }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {
+                    "code": -32600,
+                    "message": "Invalid JSON-RPC: Protocol error: Request ID cannot be null"
+                }
+            }),
+        ]
+    );
+}